@@ -95,11 +95,15 @@ mod test {
     use assert_cmd::prelude::*;
     use std::process::Command;
     /// Runs `diff` to determine whether f1 & f2 
-    /// are strictly identical or not. Whitespaces are omitted
-    fn diff_is_strictly_identical (f1: &str, f2: &str) -> Result<bool, std::string::FromUtf8Error> {
-        let output = Command::new("diff")
-            .arg("-q")
-            .arg("-Z")
+    /// are strictly identical or not.
+    /// Trailing whitespaces are omitted, unless `byte_per_byte` is set
+    fn diff_is_strictly_identical (f1: &str, f2: &str, byte_per_byte: bool) -> Result<bool, std::string::FromUtf8Error> {
+        let mut cmd = Command::new("diff");
+        cmd.arg("-q");
+        if !byte_per_byte {
+            cmd.arg("-Z");
+        }
+        let output = cmd
             .arg(f1)
             .arg(f2)
             .output()
@@ -156,7 +160,8 @@ mod test {
                 // compare produced OBS and mirror OBS using `diff`
                 let identical = diff_is_strictly_identical(
                     "testv1.rnx", 
-                    obs_counterpart.to_str().unwrap()) // mirror OBS file
+                    obs_counterpart.to_str().unwrap(), // mirror OBS file
+                    false)
                         .unwrap(); 
                 assert_eq!(identical,true)
             }
@@ -170,7 +175,8 @@ mod test {
     /// we do have an /OBS/Vy counterpart.
     /// We uncompress the file and perform a `file diff` which
     /// must returns 0. The hidden trick: /CRNX/Vx and its counterpart
-    /// comprise the same epoch content
+    /// comprise the same epoch content.
+    /// CRINEX3 decompression must match official CRX2RNX outputs byte per byte
     #[test]
     fn test_decompression_v3()  -> Result<(), Box<dyn std::error::Error>> {
         let test_resources = env!("CARGO_MANIFEST_DIR").to_owned() 
@@ -214,7 +220,8 @@ mod test {
                 // compare produced OBS and mirror OBS using `diff`
                 let identical = diff_is_strictly_identical(
                     "testv3.rnx", 
-                    obs_counterpart.to_str().unwrap()) // mirror OBS file
+                    obs_counterpart.to_str().unwrap(), // mirror OBS file
+                    true)
                         .unwrap(); 
                 assert_eq!(identical,true)
             }
@@ -282,7 +282,7 @@ for fp in &filepaths {
             .observable_filter_mut(filter.to_vec())
     }
     if let Some(lli) = lli {
-        let mask = rinex::observation::record::LliFlags::from_bits(lli)
+        let mask = observation::LliFlags::from_bits(lli)
             .unwrap();
         rinex
             .lli_filter_mut(mask)
@@ -354,7 +354,7 @@ for fp in &filepaths {
                         ///////////////////////////////////
                         // determine remaining OBS codes
                         ///////////////////////////////////
-                        let mut codes : Vec<String> = Vec::new();
+                        let mut codes : Vec<rinex::observation::Observable> = Vec::new();
                         r.iter()
                             .for_each(|(_, (_,data))| {
                                 data.iter()
@@ -362,7 +362,7 @@ for fp in &filepaths {
                                         data.iter()
                                             .for_each(|(code, _)| {
                                                 if !codes.contains(code) {
-                                                    codes.push(code.to_string())
+                                                    codes.push(*code)
                                                 }
                                             })
                                     })
@@ -34,7 +34,7 @@ fn main() {
         let (clk_offset, obs_data) = data;
         println!("Found epoch: `{:#?}`", epoch); 
         println!("Clock offset: `{:#?}`", clk_offset);
-        //   HashMap<Sv, HashMap<String, ObservationData>> 
+        //   HashMap<Sv, HashMap<Observable, ObservationData>> 
         //   : list of observation data, indexed by Observation Code
         //     and sorted by Satellite Vehicule
         for vehicule in obs_data.iter() { // over all sat. vehicules
@@ -92,7 +92,7 @@ fn main() {
     println!("\n------------- Epochs ----------\n{:#?}", epochs); 
     
     // Build OBS record that contains only Pseudo Range measurements 
-    // --> use provided macro to test each obscode and retain only matching data
+    // --> use Observable methods to test each obscode and retain only matching data
     let epochs : Vec<_> = record
         .iter()
         .map(|(_epoch, (_clock_offset, sv))| { // record: {key: epochs, values: (array of clock offsets, array of sv data) }
@@ -100,7 +100,7 @@ fn main() {
                 .map(|(_sv, obs)| { // array of sv data: {key: sv, values: array of data)
                     obs.iter()
                         .find(|(code, _)| { // array of data: {key: OBS code, values: ObsData}
-                            rinex::is_pseudo_range_obs_code!(code)
+                            code.is_pseudo_range()
                         })
               })
         })
@@ -118,7 +118,7 @@ fn main() {
                 .map(|(_, obs)| { // from filtered content, apply previous filter
                     obs.iter()
                         .find(|(code, _)| { // obs code kind filter
-                            rinex::is_pseudo_range_obs_code!(code)
+                            code.is_pseudo_range()
                         })
                         .map(|(code, data)| (epoch, code, data)) // build returned struct
                 })
@@ -137,7 +137,7 @@ fn main() {
                 .map(|(_, obs)| { // from filtered content, apply previous filter
                     obs.iter()
                         .find(|(code, _)| { // obs code kind filter
-                            code.as_str() == "C1C" // unique code 
+                            code.to_string() == "C1C" // unique code 
                         })
                         .map(|(code, data)| (epoch, code, data)) // build returned struct
                 })
@@ -158,7 +158,7 @@ fn main() {
                 .map(|(_, obs)| { // from filtered content, apply previous filter
                     obs.iter()
                         .find(|(obs_code, obs_data)| { // obs code kind filter
-                            obs_code.as_str() == "C1C" && obs_data.is_ok() // unique code 
+                            obs_code.to_string() == "C1C" && obs_data.is_ok() // unique code 
                         })
                         .map(|(code, data)| (epoch, code, data)) // build returned struct
                 })
@@ -180,7 +180,7 @@ fn main() {
                 .map(|(_, obs)| { // from filtered content, apply previous filter
                     obs.iter()
                         .find(|(obs_code, obs_data)| { // obs code kind filter
-                            rinex::is_doppler_obs_code!(obs_code) && obs_data.is_ok()
+                            obs_code.is_doppler() && obs_data.is_ok()
                         })
                         .map(|(code, data)| (epoch, code, data)) // build returned struct
                 })
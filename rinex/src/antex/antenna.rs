@@ -4,6 +4,7 @@ use strum_macros::EnumString;
 #[derive(Clone, Debug)]
 #[derive(PartialEq, PartialOrd)]
 #[derive(EnumString)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum Method {
     #[strum(serialize = "CHAMBER")]
     Chamber,
@@ -28,13 +29,17 @@ impl Default for Method {
 /// Calibration information
 #[derive(Clone, Debug)]
 #[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Calibration {
     /// Calibration method
     pub method: Method,
     /// Agency who performed the calibration
     pub agency: String,
+    /// Number of individual antennas calibrated:
+    /// type mean calibrations usually average several antennas
+    pub number: u16,
     /// Date of calibration
-    pub date: String,
+    pub date: Option<chrono::NaiveDate>,
 }
 
 impl Default for Calibration {
@@ -42,14 +47,23 @@ impl Default for Calibration {
         Self {
             method: Method::default(),
             agency: String::from("Unknown"),
-            date: String::from("Unknown"),
+            number: 0,
+            date: None,
         }
     }
 }
 
+impl Calibration {
+    /// Parses calibration date, formatted like "23-SEP-20"
+    pub fn parse_date (content: &str) -> Option<chrono::NaiveDate> {
+        chrono::NaiveDate::parse_from_str(content.trim(), "%d-%b-%y").ok()
+    }
+}
+
 /// Describes an Antenna section inside the ATX record
 #[derive(Clone, Debug)]
 #[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Antenna {
     pub ant_type: String,
     pub sn: String,
@@ -126,4 +140,25 @@ impl Antenna {
         a.sinex_code = Some(code.to_string());
         a
     }
+    /// Returns true if this is an individual calibration,
+    /// that applies to the antenna of this serial number only.
+    /// Type mean calibrations do not specify a serial number
+    pub fn is_individual (&self) -> bool {
+        !self.sn.is_empty() && self.sn != "?"
+    }
+    /// Returns true if this calibration is valid at given date.
+    /// A calibration without validity period is always valid
+    pub fn is_valid_at (&self, date: chrono::NaiveDateTime) -> bool {
+        if let Some(from) = self.valid_from {
+            if date < from {
+                return false
+            }
+        }
+        if let Some(until) = self.valid_until {
+            if date > until {
+                return false
+            }
+        }
+        true
+    }
 }
@@ -1,9 +1,14 @@
 //! Antex (ATX) - special RINEX, for antenna caracteristics
+use thiserror::Error;
+
 pub mod pcv;
 pub mod record;
 pub mod antenna;
 pub mod frequency;
 
+use antenna::Antenna;
+use frequency::{Frequency, Pattern};
+
 /// ANTEX special RINEX fields
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
@@ -26,3 +31,132 @@ impl Default for HeaderFields {
         }
     }
 }
+
+#[derive(Error, Debug)]
+/// PCV grid resampling errors
+pub enum Error {
+    #[error("angular increment must be strictly positive")]
+    InvalidIncrement,
+    #[error("pattern does not match antenna zenith grid")]
+    GridMismatch,
+}
+
+/// Linear interpolation of `values`, sampled every `step` from `start`,
+/// at given `x` coordinate
+fn interpolate (values: &[f64], start: f64, step: f64, x: f64) -> f64 {
+    let pos = (x - start) / step;
+    let i = (pos.floor().max(0.0) as usize).min(values.len() -1);
+    if i == values.len() -1 {
+        return values[i]
+    }
+    let frac = pos - i as f64;
+    values[i] + (values[i+1] - values[i]) * frac
+}
+
+/// Resamples phase patterns along the zenith axis
+fn resample_zenith (values: &[f64], zen: (f64, f64), dzen: f64, new_dzen: f64) -> Vec<f64> {
+    let n = ((zen.1 - zen.0) / new_dzen + 1.0E-9).floor() as usize + 1;
+    (0..n)
+        .map(|i| interpolate(values, zen.0, dzen, zen.0 + i as f64 * new_dzen))
+        .collect()
+}
+
+/// Resamples the PCV grid of given `frequency`, attached to given `antenna`,
+/// to a different angular resolution: `new_dzen` zenith increment
+/// and `new_dazi` azimuth increment, both in degrees.
+/// Values are bilinearly interpolated. Returns the antenna description
+/// updated with the new grid definition, and the resampled frequency.
+/// `new_dazi` is ignored when the calibration is not azimuth dependent
+/// (antenna.dazi = 0).
+/// This is typically used to harmonize calibrations coming from different
+/// sources, prior comparison or application.
+pub fn resample (antenna: &Antenna, frequency: &Frequency, new_dzen: f64, new_dazi: f64) -> Result<(Antenna, Frequency), Error> {
+    if antenna.dzen <= 0.0 || new_dzen <= 0.0 {
+        return Err(Error::InvalidIncrement)
+    }
+    let (zen1, zen2) = antenna.zen;
+    let n_zen = ((zen2 - zen1) / antenna.dzen).round() as usize + 1;
+    let mut noazi : Vec<Vec<f64>> = Vec::new();
+    let mut rows : Vec<(f64, Vec<f64>)> = Vec::new();
+    for pattern in frequency.patterns.iter() {
+        if pattern.pattern().len() != n_zen {
+            return Err(Error::GridMismatch)
+        }
+        let values = resample_zenith(&pattern.pattern(), antenna.zen, antenna.dzen, new_dzen);
+        match pattern.azimuth_pattern() {
+            Some((azi, _)) => rows.push((azi, values)),
+            None => noazi.push(values),
+        }
+    }
+    let mut new_frequency = frequency.clone();
+    new_frequency.patterns = noazi
+        .into_iter()
+        .map(Pattern::NonAzimuthDependent)
+        .collect();
+    let mut new_antenna = antenna.with_zenith(zen1, zen2, new_dzen);
+    if antenna.dazi > 0.0 && rows.len() > 0 {
+        if new_dazi <= 0.0 {
+            return Err(Error::InvalidIncrement)
+        }
+        rows.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        // azimuth is periodic
+        let (first_azi, first_values) = rows[0].clone();
+        rows.push((first_azi + 360.0, first_values));
+        let n_azi = (360.0 / new_dazi + 1.0E-9).floor() as usize + 1;
+        for i in 0..n_azi {
+            let azi = i as f64 * new_dazi;
+            let azi_mod = if azi < first_azi { azi + 360.0 } else { azi };
+            let k = rows
+                .iter()
+                .rposition(|(a, _)| *a <= azi_mod)
+                .unwrap_or(0)
+                .min(rows.len() -2);
+            let (a0, v0) = &rows[k];
+            let (a1, v1) = &rows[k+1];
+            let frac = if a1 > a0 { (azi_mod - a0) / (a1 - a0) } else { 0.0 };
+            let values = v0
+                .iter()
+                .zip(v1.iter())
+                .map(|(p0, p1)| p0 + (p1 - p0) * frac)
+                .collect();
+            new_frequency.patterns.push(Pattern::AzimuthDependent((azi, values)));
+        }
+        new_antenna = new_antenna.with_dazi(new_dazi);
+    }
+    Ok((new_antenna, new_frequency))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_resample() {
+        let antenna = Antenna::default()
+            .with_zenith(0.0, 90.0, 10.0)
+            .with_dazi(90.0);
+        let noazi : Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let mut frequency = Frequency::default()
+            .add_pattern(Pattern::NonAzimuthDependent(noazi));
+        for azi in vec![0.0, 90.0, 180.0, 270.0, 360.0] {
+            let values : Vec<f64> = (0..10).map(|i| i as f64 + azi / 90.0).collect();
+            frequency = frequency.add_pattern(Pattern::AzimuthDependent((azi, values)));
+        }
+        let resampled = resample(&antenna, &frequency, 5.0, 45.0);
+        assert_eq!(resampled.is_ok(), true);
+        let (antenna, frequency) = resampled.unwrap();
+        assert_eq!(antenna.dzen, 5.0);
+        assert_eq!(antenna.dazi, 45.0);
+        // 1 NOAZI + 0:45:360 azimuths
+        assert_eq!(frequency.patterns.len(), 1 + 9);
+        let noazi = frequency.patterns[0].pattern();
+        assert_eq!(noazi.len(), 19);
+        assert_eq!(noazi[1], 0.5);
+        assert_eq!(noazi[18], 9.0);
+        let (azi, values) = frequency.patterns[2].azimuth_pattern().unwrap();
+        assert_eq!(azi, 45.0);
+        assert_eq!(values[0], 0.5);
+        assert_eq!(values[2], 1.5);
+        // invalid increment
+        assert_eq!(resample(&antenna, &frequency, 0.0, 45.0).is_err(), true);
+    }
+}
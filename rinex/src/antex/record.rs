@@ -5,6 +5,26 @@ use crate::channel;
 use crate::antex::frequency::{Frequency, Pattern};
 use crate::antex::antenna::{Antenna, Calibration, Method};
 
+/// Returns the calibration that applies to the antenna of given type
+/// and serial number, at given date. Only calibrations valid at this date
+/// are considered. An individual calibration of this very antenna (matching
+/// serial number) is preferred over the type mean calibration.
+/// When several calibrations apply, the most recent one is returned
+pub fn calibration<'a> (record: &'a Record, ant_type: &str, sn: &str, date: chrono::NaiveDateTime) -> Option<&'a (Antenna, Vec<Frequency>)> {
+    let candidates = record
+        .iter()
+        .filter(|(antenna, _)| antenna.ant_type == ant_type && antenna.is_valid_at(date));
+    candidates
+        .clone()
+        .filter(|(antenna, _)| antenna.is_individual() && antenna.sn == sn)
+        .max_by_key(|(antenna, _)| antenna.valid_from)
+        .or_else(|| {
+            candidates
+                .filter(|(antenna, _)| !antenna.is_individual())
+                .max_by_key(|(antenna, _)| antenna.valid_from)
+        })
+}
+
 /// Returns true if this line matches 
 /// the beginning of a `epoch` for ATX file (special files),
 /// this is not really an epoch but rather a group of dataset
@@ -61,12 +81,16 @@ pub fn build_record_entry (content: &str) -> Result<(Antenna, Vec<Frequency>), E
         } else if marker.contains("METH / BY / # / DATE") {
             let (method, rem) = content.split_at(20);
             let (agency, rem) = rem.split_at(20);
-            let (_, rem) = rem.split_at(10); // N#
+            let (number, rem) = rem.split_at(10);
             let (date, _) = rem.split_at(10);
             let cal = Calibration {
-                method: Method::from_str(method.trim()).unwrap(),
+                // blank: converted from igs_01.pcv
+                method: Method::from_str(method.trim())
+                    .unwrap_or(Method::Converted),
                 agency: agency.trim().to_string(),
-                date: date.trim().to_string(),
+                number: u16::from_str(number.trim())
+                    .unwrap_or(0),
+                date: Calibration::parse_date(date),
             };
             antenna = antenna.with_calibration(cal)
         
@@ -165,4 +189,58 @@ mod test {
          let content = "   G01                                                      START OF FREQUENCY";
          assert_eq!(is_new_epoch(content), false);
     }
+    #[test]
+    fn test_calibration_lookup() {
+        let block = |sn: &str, method: &str, valid: &[&str]| -> String {
+            let mut lines = vec![
+                format!("{:<60}START OF ANTENNA", ""),
+                format!("{:<17}{:<20}{:<23}TYPE / SERIAL NO", "TRM59800.00", sn, ""),
+                format!("{:<20}{:<20}{:>6}{:<4}{:<10}{}", method, "IGS", 10, "", "01-JAN-20", "METH / BY / # / DATE"),
+            ];
+            if let Some(from) = valid.get(0) {
+                lines.push(format!("{:<60}VALID FROM", from));
+            }
+            if let Some(until) = valid.get(1) {
+                lines.push(format!("{:<60}VALID UNTIL", until));
+            }
+            lines.push(format!("{:<60}END OF ANTENNA", ""));
+            lines.join("\n")
+        };
+        let (type_mean, _) = build_record_entry(&block("", "ROBOT", &[])).unwrap();
+        assert_eq!(type_mean.ant_type, "TRM59800.00");
+        assert_eq!(type_mean.is_individual(), false);
+        assert_eq!(type_mean.calibration.method, Method::Robot);
+        assert_eq!(type_mean.calibration.number, 10);
+        assert_eq!(type_mean.calibration.date, Some(chrono::NaiveDate::from_ymd(2020, 1, 1)));
+        let (old, _) = build_record_entry(&block("1234", "ROBOT", &[
+            "  2010     1     1     0     0    0.0000000",
+            "  2019    12    31    23    59   59.9999999",
+        ])).unwrap();
+        assert_eq!(old.is_individual(), true);
+        assert_eq!(old.valid_from, Some(epoch::str2date("2010 01 01 00 00 00").unwrap()));
+        assert_eq!(old.valid_until.is_some(), true);
+        let (new, _) = build_record_entry(&block("1234", "", &[
+            "  2020     1     1     0     0    0.0000000",
+        ])).unwrap();
+        assert_eq!(new.calibration.method, Method::Converted);
+        let mut record = Record::new();
+        for antenna in vec![type_mean, old, new] {
+            record.push((antenna, Vec::new()))
+        }
+        let t2015 = epoch::str2date("2015 01 01 00 00 00").unwrap();
+        let t2021 = epoch::str2date("2021 01 01 00 00 00").unwrap();
+        let t2000 = epoch::str2date("2000 01 01 00 00 00").unwrap();
+        // individual calibration is preferred
+        let (antenna, _) = calibration(&record, "TRM59800.00", "1234", t2015).unwrap();
+        assert_eq!(antenna.valid_until.is_some(), true);
+        let (antenna, _) = calibration(&record, "TRM59800.00", "1234", t2021).unwrap();
+        assert_eq!(antenna.calibration.method, Method::Converted);
+        // no individual calibration valid at this date
+        let (antenna, _) = calibration(&record, "TRM59800.00", "1234", t2000).unwrap();
+        assert_eq!(antenna.is_individual(), false);
+        // other serial number
+        let (antenna, _) = calibration(&record, "TRM59800.00", "5678", t2015).unwrap();
+        assert_eq!(antenna.is_individual(), false);
+        assert_eq!(calibration(&record, "LEIAR25.R4", "1234", t2015).is_none(), true);
+    }
 }
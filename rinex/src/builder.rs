@@ -0,0 +1,364 @@
+//! `RinexBuilder` to produce RINEX files from scratch
+use thiserror::Error;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::Rinex;
+use crate::sv;
+use crate::meteo;
+use crate::epoch;
+use crate::header;
+use crate::version;
+use crate::hardware;
+use crate::record;
+use crate::navigation;
+use crate::observation;
+use crate::types::Type;
+use crate::constellation::Constellation;
+
+/// Builder related errors
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("{0:?} data does not apply to this builder")]
+    TypeMismatch(Type),
+    #[error("RINEX type \"{0:?}\" cannot be built yet")]
+    TypeNotSupported(Type),
+}
+
+/// `RinexBuilder` constructs Observation, Navigation and Meteo
+/// `RINEX` programmatically: describe the production context,
+/// then push data, epoch per epoch. Observables that were
+/// not declared are added to the header when first encountered.
+/// `TIME OF FIRST / LAST OBS` and the header constellation
+/// are deduced from the record when not specified.
+///
+/// Example:
+/// ```
+/// use rinex::prelude::*;
+/// use rinex::builder::RinexBuilder;
+/// use std::str::FromStr;
+/// let e0 = Epoch::new(
+///     rinex::epoch::str2date("2022 06 08 10 00 00").unwrap(),
+///     EpochFlag::Ok);
+/// let g01 = Sv::from_str("G01").unwrap();
+/// let c1c = ObsObservable::from_str("C1C").unwrap();
+/// let rinex = RinexBuilder::new(RinexType::ObservationData)
+///     .with_general_infos("my-program", "me", "my-agency")
+///     .with_station("ABCD", "12345M001")
+///     .with_observation(e0, g01, c1c, ObservationData::new(20243517.560, None, None))
+///     .with_clock_offset(e0, 1.0E-6)
+///     .build()
+///     .unwrap();
+/// assert_eq!(rinex.epochs(), vec![e0]);
+/// assert_eq!(rinex.header.constellation, Some(Constellation::GPS));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RinexBuilder {
+    /// header being constructed
+    header: header::Header,
+    /// record being constructed
+    record: record::Record,
+    /// first error encountered, reported by [RinexBuilder::build]
+    error: Option<Error>,
+}
+
+impl RinexBuilder {
+    /// Creates a new builder for given `RINEX` type.
+    /// Only Observation, Navigation and Meteo data are supported,
+    /// other types are reported by [RinexBuilder::build]
+    pub fn new (rinex_type: Type) -> Self {
+        let mut header = header::Header::default()
+            .with_type(rinex_type);
+        header.constellation = None;
+        let mut error: Option<Error> = None;
+        let record = match rinex_type {
+            Type::ObservationData => {
+                header.obs = Some(observation::HeaderFields {
+                    crinex: None,
+                    codes: HashMap::new(),
+                    clock_offset_applied: false,
+                    time_of_first_obs: None,
+                    time_of_last_obs: None,
+                    time_system: None,
+                    glo_channels: HashMap::new(),
+                    phase_shifts: Vec::new(),
+                    nb_satellites: None,
+                    nb_observations: BTreeMap::new(),
+                });
+                record::Record::ObsRecord(observation::record::Record::new())
+            },
+            Type::MeteoData => {
+                header.meteo = Some(meteo::HeaderFields {
+                    codes: Vec::new(),
+                    sensors: Vec::new(),
+                });
+                record::Record::MeteoRecord(meteo::record::Record::new())
+            },
+            Type::NavigationData => record::Record::NavRecord(navigation::record::Record::new()),
+            _ => {
+                error = Some(Error::TypeNotSupported(rinex_type));
+                record::Record::default()
+            },
+        };
+        Self {
+            header,
+            record,
+            error,
+        }
+    }
+
+    /// Sets `RINEX` revision to produce
+    pub fn with_version (mut self, version: version::Version) -> Self {
+        self.header.version = version;
+        self
+    }
+
+    /// Sets producer program, operator and agency
+    pub fn with_general_infos (mut self, program: &str, run_by: &str, agency: &str) -> Self {
+        self.header = self.header.with_general_infos(program, run_by, agency);
+        self
+    }
+
+    /// Sets name of observer
+    pub fn with_observer (mut self, observer: &str) -> Self {
+        self.header.observer = observer.to_string();
+        self
+    }
+
+    /// Sets station (marker) name and number
+    pub fn with_station (mut self, name: &str, id: &str) -> Self {
+        self.header.station = name.to_string();
+        self.header.station_id = id.to_string();
+        self
+    }
+
+    /// Sets station approximate position (ECEF [m])
+    pub fn with_position (mut self, position: (f64,f64,f64)) -> Self {
+        self.header.coords = Some(rust_3d::Point3D::new(position.0, position.1, position.2));
+        self
+    }
+
+    /// Sets receiver information
+    pub fn with_rcvr (mut self, rcvr: hardware::Rcvr) -> Self {
+        self.header = self.header.with_receiver(rcvr);
+        self
+    }
+
+    /// Sets antenna information
+    pub fn with_antenna (mut self, antenna: hardware::Antenna) -> Self {
+        self.header = self.header.with_antenna(antenna);
+        self
+    }
+
+    /// Sets sampling interval [s]
+    pub fn with_sampling_interval (mut self, interval: f32) -> Self {
+        self.header.sampling_interval = Some(interval);
+        self
+    }
+
+    /// Sets header constellation, deduced from the record otherwise
+    pub fn with_constellation (mut self, constellation: Constellation) -> Self {
+        self.header.constellation = Some(constellation);
+        self
+    }
+
+    /// Adds a header comment
+    pub fn with_comment (mut self, comment: &str) -> Self {
+        self.header.comments.push(comment.to_string());
+        self
+    }
+
+    /// Declares Observables for given constellation, in this order.
+    /// Only applies to Observation data
+    pub fn with_observables (mut self, constellation: Constellation, observables: &[observation::Observable]) -> Self {
+        match self.header.obs.as_mut() {
+            Some(obs) => {
+                let codes = obs.codes
+                    .entry(constellation)
+                    .or_insert_with(Vec::new);
+                for observable in observables {
+                    if !codes.contains(observable) {
+                        codes.push(*observable)
+                    }
+                }
+            },
+            None => self.fail(Error::TypeMismatch(Type::ObservationData)),
+        }
+        self
+    }
+
+    /// Declares Meteo observables, in this order.
+    /// Only applies to Meteo data
+    pub fn with_meteo_observables (mut self, observables: &[meteo::Observable]) -> Self {
+        match self.header.meteo.as_mut() {
+            Some(meteo) => {
+                for observable in observables {
+                    if !meteo.codes.contains(observable) {
+                        meteo.codes.push(observable.clone())
+                    }
+                }
+            },
+            None => self.fail(Error::TypeMismatch(Type::MeteoData)),
+        }
+        self
+    }
+
+    /// Adds a meteo sensor description
+    pub fn with_sensor (mut self, sensor: meteo::Sensor) -> Self {
+        match self.header.meteo.as_mut() {
+            Some(meteo) => meteo.sensors.push(sensor),
+            None => self.fail(Error::TypeMismatch(Type::MeteoData)),
+        }
+        self
+    }
+
+    /// Adds an observation made on given vehicule at given epoch
+    pub fn with_observation (mut self, epoch: epoch::Epoch, sv: sv::Sv, observable: observation::Observable, data: observation::record::ObservationData) -> Self {
+        self = self.with_observables(sv.constellation, &[observable]);
+        if let Some(record) = self.record.as_mut_obs() {
+            record
+                .entry(epoch)
+                .or_insert_with(|| (None, BTreeMap::new()))
+                .1
+                .entry(sv)
+                .or_insert_with(HashMap::new)
+                .insert(observable, data);
+        }
+        self
+    }
+
+    /// Sets receiver clock offset [s] at given epoch
+    pub fn with_clock_offset (mut self, epoch: epoch::Epoch, offset: f64) -> Self {
+        match self.record.as_mut_obs() {
+            Some(record) => {
+                record
+                    .entry(epoch)
+                    .or_insert_with(|| (None, BTreeMap::new()))
+                    .0 = Some(offset)
+            },
+            None => self.fail(Error::TypeMismatch(Type::ObservationData)),
+        }
+        self
+    }
+
+    /// Adds a meteo observation at given epoch
+    pub fn with_meteo_observation (mut self, epoch: epoch::Epoch, observable: meteo::Observable, value: f32) -> Self {
+        self = self.with_meteo_observables(&[observable.clone()]);
+        if let Some(record) = self.record.as_mut_meteo() {
+            record
+                .entry(epoch)
+                .or_insert_with(HashMap::new)
+                .insert(observable, value);
+        }
+        self
+    }
+
+    /// Adds a navigation frame at given epoch
+    pub fn with_frame (mut self, epoch: epoch::Epoch, frame: navigation::Frame) -> Self {
+        match self.record.as_mut_nav() {
+            Some(record) => {
+                record
+                    .entry(epoch)
+                    .or_insert_with(BTreeMap::new)
+                    .entry(frame.class())
+                    .or_insert_with(Vec::new)
+                    .push(frame)
+            },
+            None => self.fail(Error::TypeMismatch(Type::NavigationData)),
+        }
+        self
+    }
+
+    /// Builds `RINEX`, ready to be produced with [Rinex::to_file].
+    /// Returns the first error encountered while building
+    pub fn build (self) -> Result<Rinex, Error> {
+        if let Some(error) = self.error {
+            return Err(error)
+        }
+        let mut header = self.header;
+        let record = self.record;
+        if header.date.is_empty() {
+            header.date = epoch::now()
+                .format("%Y%m%d %H%M%S UTC")
+                .to_string();
+        }
+        if header.constellation.is_none() {
+            let mut constellations: Vec<Constellation> = Vec::new();
+            if let Some(obs) = &header.obs {
+                constellations.extend(obs.codes.keys());
+            }
+            if let Some(r) = record.as_nav() {
+                for (_, classes) in r.iter() {
+                    for (_, frames) in classes.iter() {
+                        for frame in frames.iter() {
+                            constellations.push(frame.sv().constellation)
+                        }
+                    }
+                }
+            }
+            constellations.sort();
+            constellations.dedup();
+            header.constellation = match constellations.len() {
+                0 => None,
+                1 => Some(constellations[0]),
+                _ => Some(Constellation::Mixed),
+            };
+        }
+        if let Some(obs) = header.obs.as_mut() {
+            let epochs = record.epochs_iter();
+            if obs.time_of_first_obs.is_none() {
+                obs.time_of_first_obs = epochs.clone().next().map(|(e, _)| *e);
+            }
+            if obs.time_of_last_obs.is_none() {
+                obs.time_of_last_obs = epochs.clone().next_back().map(|(e, _)| *e);
+            }
+        }
+        Ok(Rinex::new(header, record))
+    }
+
+    /// Retains first error encountered
+    fn fail (&mut self, error: Error) {
+        if self.error.is_none() {
+            self.error = Some(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_obs_builder() {
+        let e0 = epoch::Epoch::new(
+            epoch::str2date("2022 01 01 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e1 = epoch::Epoch::new(
+            epoch::str2date("2022 01 01 00 00 30").unwrap(),
+            epoch::EpochFlag::Ok);
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let r01 = sv::Sv::from_str("R01").unwrap();
+        let c1c = observation::Observable::from_str("C1C").unwrap();
+        let l1c = observation::Observable::from_str("L1C").unwrap();
+        let data = observation::record::ObservationData::new(1.0, None, None);
+        let rinex = RinexBuilder::new(Type::ObservationData)
+            .with_observables(Constellation::GPS, &[l1c, c1c])
+            .with_observation(e0, g01, c1c, data)
+            .with_observation(e1, g01, l1c, data)
+            .with_observation(e1, r01, c1c, data)
+            .build()
+            .unwrap();
+        assert_eq!(rinex.epochs(), vec![e0, e1]);
+        assert_eq!(rinex.header.constellation, Some(Constellation::Mixed));
+        let obs = rinex.header.obs.as_ref().unwrap();
+        assert_eq!(obs.codes[&Constellation::GPS], vec![l1c, c1c]);
+        assert_eq!(obs.codes[&Constellation::Glonass], vec![c1c]);
+        assert_eq!(obs.time_of_first_obs, Some(e0));
+        assert_eq!(obs.time_of_last_obs, Some(e1));
+        // type mismatch
+        let builder = RinexBuilder::new(Type::ObservationData)
+            .with_meteo_observation(e0, meteo::Observable::Pressure, 1013.0);
+        assert_eq!(builder.build().err(), Some(Error::TypeMismatch(Type::MeteoData)));
+        let builder = RinexBuilder::new(Type::ClockData);
+        assert_eq!(builder.build().err(), Some(Error::TypeNotSupported(Type::ClockData)));
+    }
+}
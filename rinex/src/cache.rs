@@ -0,0 +1,764 @@
+//! Binary cache, to reload previously parsed files
+//! without parsing their record again.
+//!
+//! A cache file is tied to its source file: it is invalidated
+//! as soon as the source file size or modification time changes.
+//! Header is always parsed from the source file, only the record,
+//! comments, site events, warnings and raw entries are cached.
+//! Epochs are delta encoded (whole seconds and nanoseconds separately), integers are encoded as variable length integers
+//! and observables are described once, in a table.
+//! Observation, Meteo, Clock and IONEX records are supported.
+//! Navigation records are not: caching them fails with [Error::TypeNotSupported].
+use thiserror::Error;
+use std::str::FromStr;
+use std::convert::TryFrom;
+use std::collections::{BTreeMap, HashMap};
+use crate::sv;
+use crate::epoch;
+use crate::meteo;
+use crate::clocks;
+use crate::ionosphere;
+use crate::parser;
+use crate::observation;
+use crate::record::{Record, Comments};
+use crate::types::Type;
+
+/// Cache file identifier
+const MAGIC: &[u8; 8] = b"RNXCACHE";
+
+/// Cache format revision, cache files
+/// of other revisions are considered invalid
+pub const VERSION: u16 = 3;
+
+/// Cache related errors
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("file i/o error")]
+    IoError(#[from] std::io::Error),
+    #[error("not a cache file")]
+    NotACacheFile,
+    #[error("cache revision {0} is not supported")]
+    VersionMismatch(u16),
+    #[error("source file has changed since cache was produced")]
+    Outdated,
+    #[error("cache does not match source file type")]
+    TypeMismatch,
+    #[error("{0:?} records cannot be cached")]
+    TypeNotSupported(Type),
+    #[error("corrupt cache content")]
+    Corrupt,
+    #[error("failed to parse source file")]
+    ParsingError(#[from] crate::Error),
+}
+
+/// Source file fingerprint: (size [bytes], modification time [s], [ns])
+fn fingerprint (source: &str) -> Result<(u64, u64, u32), Error> {
+    let metadata = std::fs::metadata(source)?;
+    let modified = metadata.modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok((metadata.len(), modified.as_secs(), modified.subsec_nanos()))
+}
+
+/// Cache content encoder
+struct Encoder {
+    buf: Vec<u8>,
+    /// previous epoch ([s], [ns]), for delta encoding
+    previous: (i64, i64),
+}
+
+impl Encoder {
+    fn new () -> Self {
+        Self {
+            buf: Vec::new(),
+            previous: (0, 0),
+        }
+    }
+    fn u8 (&mut self, value: u8) {
+        self.buf.push(value)
+    }
+    fn varint (&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.buf.push(byte);
+                break
+            }
+            self.buf.push(byte | 0x80)
+        }
+    }
+    fn signed (&mut self, value: i64) {
+        // zigzag encoding
+        self.varint(((value << 1) ^ (value >> 63)) as u64)
+    }
+    fn f64 (&mut self, value: f64) {
+        self.buf.extend_from_slice(&value.to_le_bytes())
+    }
+    fn f32 (&mut self, value: f32) {
+        self.buf.extend_from_slice(&value.to_le_bytes())
+    }
+    fn opt_f64 (&mut self, value: Option<f64>) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                self.f64(value)
+            },
+            None => self.u8(0),
+        }
+    }
+    fn string (&mut self, value: &str) {
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes())
+    }
+    fn epoch (&mut self, epoch: &epoch::Epoch) {
+        let secs = epoch.date.timestamp();
+        let nanos = epoch.date.timestamp_subsec_nanos() as i64;
+        self.signed(secs - self.previous.0);
+        self.signed(nanos - self.previous.1);
+        self.previous = (secs, nanos);
+        self.u8(flag2u8(epoch.flag))
+    }
+}
+
+/// Cache content decoder
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    /// previous epoch ([s], [ns]), for delta decoding
+    previous: (i64, i64),
+}
+
+impl<'a> Decoder<'a> {
+    fn new (buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            pos: 0,
+            previous: (0, 0),
+        }
+    }
+    fn bytes (&mut self, n: usize) -> Result<&'a [u8], Error> {
+        let end = self.pos.checked_add(n)
+            .ok_or(Error::Corrupt)?;
+        let bytes = self.buf.get(self.pos..end)
+            .ok_or(Error::Corrupt)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+    fn u8 (&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn varint (&mut self) -> Result<u64, Error> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value)
+            }
+        }
+        Err(Error::Corrupt)
+    }
+    fn usize (&mut self) -> Result<usize, Error> {
+        Ok(self.varint()? as usize)
+    }
+    fn signed (&mut self) -> Result<i64, Error> {
+        let value = self.varint()?;
+        Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+    }
+    fn f64 (&mut self) -> Result<f64, Error> {
+        let mut bytes = [0_u8; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+        Ok(f64::from_le_bytes(bytes))
+    }
+    fn f32 (&mut self) -> Result<f32, Error> {
+        let mut bytes = [0_u8; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+        Ok(f32::from_le_bytes(bytes))
+    }
+    fn opt_f64 (&mut self) -> Result<Option<f64>, Error> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.f64()?)),
+            _ => Err(Error::Corrupt),
+        }
+    }
+    fn string (&mut self) -> Result<String, Error> {
+        let n = self.usize()?;
+        String::from_utf8(self.bytes(n)?.to_vec())
+            .map_err(|_| Error::Corrupt)
+    }
+    fn epoch (&mut self) -> Result<epoch::Epoch, Error> {
+        let secs = self.previous.0
+            .checked_add(self.signed()?)
+            .ok_or(Error::Corrupt)?;
+        let nanos = self.previous.1
+            .checked_add(self.signed()?)
+            .ok_or(Error::Corrupt)?;
+        self.previous = (secs, nanos);
+        let nanos = u32::try_from(nanos)
+            .map_err(|_| Error::Corrupt)?;
+        let date = chrono::NaiveDateTime::from_timestamp_opt(secs, nanos)
+            .ok_or(Error::Corrupt)?;
+        let flag = u82flag(self.u8()?)?;
+        Ok(epoch::Epoch::new(date, flag))
+    }
+}
+
+/// Epoch flag compact representation
+fn flag2u8 (flag: epoch::EpochFlag) -> u8 {
+    match flag {
+        epoch::EpochFlag::Ok => 0,
+        epoch::EpochFlag::PowerFailure => 1,
+        epoch::EpochFlag::AntennaBeingMoved => 2,
+        epoch::EpochFlag::NewSiteOccupation => 3,
+        epoch::EpochFlag::HeaderInformationFollows => 4,
+        epoch::EpochFlag::ExternalEvent => 5,
+        epoch::EpochFlag::CycleSlip => 6,
+    }
+}
+
+/// Epoch flag from its compact representation
+fn u82flag (value: u8) -> Result<epoch::EpochFlag, Error> {
+    match value {
+        0 => Ok(epoch::EpochFlag::Ok),
+        1 => Ok(epoch::EpochFlag::PowerFailure),
+        2 => Ok(epoch::EpochFlag::AntennaBeingMoved),
+        3 => Ok(epoch::EpochFlag::NewSiteOccupation),
+        4 => Ok(epoch::EpochFlag::HeaderInformationFollows),
+        5 => Ok(epoch::EpochFlag::ExternalEvent),
+        6 => Ok(epoch::EpochFlag::CycleSlip),
+        _ => Err(Error::Corrupt),
+    }
+}
+
+/// Encodes given observables table
+fn encode_table (enc: &mut Encoder, table: &[observation::Observable]) {
+    enc.varint(table.len() as u64);
+    for code in table.iter() {
+        enc.string(&code.to_string())
+    }
+}
+
+/// Decodes an observables table
+fn decode_table (dec: &mut Decoder) -> Result<Vec<observation::Observable>, Error> {
+    // not pre allocated: size is read from a possibly corrupt file
+    let mut table: Vec<observation::Observable> = Vec::new();
+    for _ in 0..dec.usize()? {
+        let code = observation::Observable::from_str(&dec.string()?)
+            .map_err(|_| Error::Corrupt)?;
+        table.push(code)
+    }
+    Ok(table)
+}
+
+/// Returns index of given code in observables table
+fn table_index (table: &[observation::Observable], code: &observation::Observable) -> u64 {
+    table.iter()
+        .position(|c| c == code)
+        .unwrap_or(0) as u64
+}
+
+fn encode_obs (enc: &mut Encoder, record: &observation::record::Record) {
+    let mut table: Vec<observation::Observable> = Vec::new();
+    for (_, (_, vehicules)) in record.iter() {
+        for (_, observations) in vehicules.iter() {
+            for code in observations.keys() {
+                if !table.contains(code) {
+                    table.push(*code)
+                }
+            }
+        }
+    }
+    encode_table(enc, &table);
+    enc.varint(record.len() as u64);
+    for (e, (clk, vehicules)) in record.iter() {
+        enc.epoch(e);
+        match clk {
+            Some(clk) => {
+                enc.u8(1);
+                enc.f64(*clk)
+            },
+            None => enc.u8(0),
+        }
+        enc.varint(vehicules.len() as u64);
+        for (sv, observations) in vehicules.iter() {
+            enc.string(&sv.to_string());
+            enc.varint(observations.len() as u64);
+            for (code, data) in observations.iter() {
+                enc.varint(table_index(&table, code));
+                enc.f64(data.obs);
+                enc.u8(data.lli.map(|lli| lli.bits()).unwrap_or(0xff));
+                enc.u8(data.ssi.map(|ssi| ssi as u8).unwrap_or(0xff));
+            }
+        }
+    }
+}
+
+fn decode_obs (dec: &mut Decoder) -> Result<observation::record::Record, Error> {
+    let table = decode_table(dec)?;
+    let mut record = observation::record::Record::new();
+    for _ in 0..dec.usize()? {
+        let e = dec.epoch()?;
+        let clk = match dec.u8()? {
+            0 => None,
+            _ => Some(dec.f64()?),
+        };
+        let mut vehicules: BTreeMap<sv::Sv, HashMap<observation::Observable, observation::record::ObservationData>> = BTreeMap::new();
+        for _ in 0..dec.usize()? {
+            let sv = sv::Sv::from_str(&dec.string()?)
+                .map_err(|_| Error::Corrupt)?;
+            let mut observations: HashMap<observation::Observable, observation::record::ObservationData> = HashMap::new();
+            for _ in 0..dec.usize()? {
+                let code = table.get(dec.usize()?)
+                    .ok_or(Error::Corrupt)?;
+                let obs = dec.f64()?;
+                let lli = match dec.u8()? {
+                    0xff => None,
+                    bits => Some(observation::record::LliFlags::from_bits(bits)
+                        .ok_or(Error::Corrupt)?),
+                };
+                let ssi = match dec.u8()? {
+                    0xff => None,
+                    ssi => Some(observation::record::Ssi::from_str(&ssi.to_string())
+                        .map_err(|_| Error::Corrupt)?),
+                };
+                observations.insert(*code, observation::record::ObservationData::new(obs, lli, ssi));
+            }
+            vehicules.insert(sv, observations);
+        }
+        record.insert(e, (clk, vehicules));
+    }
+    Ok(record)
+}
+
+fn encode_meteo (enc: &mut Encoder, record: &meteo::record::Record) {
+    enc.varint(record.len() as u64);
+    for (e, observations) in record.iter() {
+        enc.epoch(e);
+        enc.varint(observations.len() as u64);
+        for (observable, value) in observations.iter() {
+            enc.string(&observable.to_string());
+            enc.f32(*value);
+        }
+    }
+}
+
+fn decode_meteo (dec: &mut Decoder) -> Result<meteo::record::Record, Error> {
+    let mut record = meteo::record::Record::new();
+    for _ in 0..dec.usize()? {
+        let e = dec.epoch()?;
+        let mut observations: HashMap<meteo::Observable, f32> = HashMap::new();
+        for _ in 0..dec.usize()? {
+            let observable = meteo::Observable::from_str(&dec.string()?)
+                .map_err(|_| Error::Corrupt)?;
+            observations.insert(observable, dec.f32()?);
+        }
+        record.insert(e, observations);
+    }
+    Ok(record)
+}
+
+fn encode_clock_data (enc: &mut Encoder, dtype: &clocks::record::DataType, data: &clocks::record::Data) {
+    enc.string(&dtype.to_string());
+    enc.f64(data.bias);
+    enc.opt_f64(data.bias_sigma);
+    enc.opt_f64(data.rate);
+    enc.opt_f64(data.rate_sigma);
+    enc.opt_f64(data.accel);
+    enc.opt_f64(data.accel_sigma);
+}
+
+fn decode_clock_data (dec: &mut Decoder) -> Result<(clocks::record::DataType, clocks::record::Data), Error> {
+    let dtype = clocks::record::DataType::from_str(&dec.string()?)
+        .map_err(|_| Error::Corrupt)?;
+    let data = clocks::record::Data {
+        bias: dec.f64()?,
+        bias_sigma: dec.opt_f64()?,
+        rate: dec.opt_f64()?,
+        rate_sigma: dec.opt_f64()?,
+        accel: dec.opt_f64()?,
+        accel_sigma: dec.opt_f64()?,
+    };
+    Ok((dtype, data))
+}
+
+fn encode_clock (enc: &mut Encoder, record: &clocks::record::Record) {
+    enc.varint(record.len() as u64);
+    for (e, entries) in record.iter() {
+        enc.epoch(e);
+        enc.varint(entries.stations().len() as u64);
+        for (station, data) in entries.stations().iter() {
+            enc.string(station);
+            enc.varint(data.len() as u64);
+            for (dtype, data) in data.iter() {
+                encode_clock_data(enc, dtype, data)
+            }
+        }
+        enc.varint(entries.satellites().len() as u64);
+        for (sv, data) in entries.satellites().iter() {
+            enc.string(&sv.to_string());
+            enc.varint(data.len() as u64);
+            for (dtype, data) in data.iter() {
+                encode_clock_data(enc, dtype, data)
+            }
+        }
+    }
+}
+
+fn decode_clock (dec: &mut Decoder) -> Result<clocks::record::Record, Error> {
+    let mut record = clocks::record::Record::new();
+    for _ in 0..dec.usize()? {
+        let e = dec.epoch()?;
+        let mut entries = clocks::record::ClockEntries::default();
+        for _ in 0..dec.usize()? {
+            let station = dec.string()?;
+            for _ in 0..dec.usize()? {
+                let (dtype, data) = decode_clock_data(dec)?;
+                entries.insert(clocks::record::System::Station(station.clone()), dtype, data)
+            }
+        }
+        for _ in 0..dec.usize()? {
+            let sv = sv::Sv::from_str(&dec.string()?)
+                .map_err(|_| Error::Corrupt)?;
+            for _ in 0..dec.usize()? {
+                let (dtype, data) = decode_clock_data(dec)?;
+                entries.insert(clocks::record::System::Sv(sv), dtype, data)
+            }
+        }
+        record.insert(e, entries);
+    }
+    Ok(record)
+}
+
+fn encode_map (enc: &mut Encoder, map: &ionosphere::record::Map) {
+    enc.varint(map.len() as u64);
+    for (coords, values) in map.iter() {
+        enc.f32(coords.lat);
+        enc.f32(coords.lon1);
+        enc.f32(coords.lon2);
+        enc.f32(coords.dlon);
+        enc.f32(coords.h);
+        enc.varint(values.len() as u64);
+        for value in values.iter() {
+            enc.f32(*value)
+        }
+    }
+}
+
+fn decode_map (dec: &mut Decoder) -> Result<ionosphere::record::Map, Error> {
+    let mut map = ionosphere::record::Map::new();
+    for _ in 0..dec.usize()? {
+        let coords = ionosphere::record::Coordinates {
+            lat: dec.f32()?,
+            lon1: dec.f32()?,
+            lon2: dec.f32()?,
+            dlon: dec.f32()?,
+            h: dec.f32()?,
+        };
+        let mut values: Vec<f32> = Vec::new();
+        for _ in 0..dec.usize()? {
+            values.push(dec.f32()?)
+        }
+        map.push((coords, values));
+    }
+    Ok(map)
+}
+
+fn decode_optional_map (dec: &mut Decoder) -> Result<Option<ionosphere::record::Map>, Error> {
+    match dec.u8()? {
+        0 => Ok(None),
+        1 => Ok(Some(decode_map(dec)?)),
+        _ => Err(Error::Corrupt),
+    }
+}
+
+fn encode_ionex (enc: &mut Encoder, record: &ionosphere::record::Record) {
+    enc.varint(record.len() as u64);
+    for (e, (tec, rms, height)) in record.iter() {
+        enc.epoch(e);
+        encode_map(enc, tec);
+        for map in [rms, height] {
+            match map {
+                Some(map) => {
+                    enc.u8(1);
+                    encode_map(enc, map)
+                },
+                None => enc.u8(0),
+            }
+        }
+    }
+}
+
+fn decode_ionex (dec: &mut Decoder) -> Result<ionosphere::record::Record, Error> {
+    let mut record = ionosphere::record::Record::new();
+    for _ in 0..dec.usize()? {
+        let e = dec.epoch()?;
+        let tec = decode_map(dec)?;
+        let rms = decode_optional_map(dec)?;
+        let height = decode_optional_map(dec)?;
+        record.insert(e, (tec, rms, height));
+    }
+    Ok(record)
+}
+
+/// Encodes given record and related content,
+/// tied to given `source` file
+pub fn encode (source: &str, rinex_type: Type, record: &Record, comments: &Comments, events: &observation::SiteEvents, warnings: &[parser::Diagnostic], raw: &parser::RawRecord) -> Result<Vec<u8>, Error> {
+    let (size, secs, nanos) = fingerprint(source)?;
+    let mut enc = Encoder::new();
+    enc.buf.extend_from_slice(MAGIC);
+    enc.buf.extend_from_slice(&VERSION.to_le_bytes());
+    enc.varint(size);
+    enc.varint(secs);
+    enc.varint(nanos as u64);
+    enc.string(&format!("{:?}", rinex_type));
+    // comments
+    enc.varint(comments.len() as u64);
+    for (e, content) in comments.iter() {
+        enc.epoch(e);
+        enc.varint(content.len() as u64);
+        for comment in content.iter() {
+            enc.string(comment)
+        }
+    }
+    // site events, as their special records
+    enc.previous = (0, 0);
+    enc.varint(events.len() as u64);
+    for (e, event) in events.iter() {
+        enc.epoch(e);
+        let records = event.to_records();
+        enc.varint(records.len() as u64);
+        for record in records.iter() {
+            enc.string(record)
+        }
+    }
+    // warnings: line number is shifted by one, 0 standing for unknown line
+    enc.previous = (0, 0);
+    enc.varint(warnings.len() as u64);
+    for warning in warnings.iter() {
+        enc.varint(warning.line.map(|l| l as u64 + 1).unwrap_or(0));
+        match &warning.epoch {
+            Some(e) => {
+                enc.u8(1);
+                enc.epoch(e)
+            },
+            None => enc.u8(0),
+        }
+        enc.string(&warning.message)
+    }
+    // raw entries
+    enc.previous = (0, 0);
+    enc.varint(raw.len() as u64);
+    for (e, entries) in raw.iter() {
+        enc.epoch(e);
+        enc.varint(entries.len() as u64);
+        for entry in entries.iter() {
+            enc.string(&entry.sv);
+            enc.varint(entry.content.len() as u64);
+            for line in entry.content.iter() {
+                enc.string(line)
+            }
+        }
+    }
+    // record
+    enc.previous = (0, 0);
+    match record {
+        Record::ObsRecord(record) => encode_obs(&mut enc, record),
+        Record::MeteoRecord(record) => encode_meteo(&mut enc, record),
+        Record::ClockRecord(record) => encode_clock(&mut enc, record),
+        Record::IonexRecord(record) => encode_ionex(&mut enc, record),
+        _ => return Err(Error::TypeNotSupported(rinex_type)),
+    }
+    Ok(enc.buf)
+}
+
+/// Decodes cache content, produced for given `source` file.
+/// Returns (record, comments, site events, warnings, raw entries)
+pub fn decode (source: &str, rinex_type: Type, buf: &[u8]) -> Result<(Record, Comments, observation::SiteEvents, parser::Diagnostics, parser::RawRecord), Error> {
+    let mut dec = Decoder::new(buf);
+    if dec.bytes(MAGIC.len()).map_err(|_| Error::NotACacheFile)? != MAGIC {
+        return Err(Error::NotACacheFile)
+    }
+    let mut version = [0_u8; 2];
+    version.copy_from_slice(dec.bytes(2)?);
+    let version = u16::from_le_bytes(version);
+    if version != VERSION {
+        return Err(Error::VersionMismatch(version))
+    }
+    let (size, secs, nanos) = fingerprint(source)?;
+    if dec.varint()? != size || dec.varint()? != secs || dec.varint()? != nanos as u64 {
+        return Err(Error::Outdated)
+    }
+    if dec.string()? != format!("{:?}", rinex_type) {
+        return Err(Error::TypeMismatch)
+    }
+    // comments
+    let mut comments = Comments::new();
+    for _ in 0..dec.usize()? {
+        let e = dec.epoch()?;
+        let mut content: Vec<String> = Vec::new();
+        for _ in 0..dec.usize()? {
+            content.push(dec.string()?)
+        }
+        comments.insert(e, content);
+    }
+    // site events
+    dec.previous = (0, 0);
+    let mut events = observation::SiteEvents::new();
+    for _ in 0..dec.usize()? {
+        let e = dec.epoch()?;
+        let mut records: Vec<String> = Vec::new();
+        for _ in 0..dec.usize()? {
+            records.push(dec.string()?)
+        }
+        let records: Vec<&str> = records.iter()
+            .map(|r| r.as_str())
+            .collect();
+        events.insert(e, observation::SiteEvent::from_records(&records));
+    }
+    // warnings
+    dec.previous = (0, 0);
+    let mut warnings = parser::Diagnostics::new();
+    for _ in 0..dec.usize()? {
+        let line = match dec.usize()? {
+            0 => None,
+            line => Some(line - 1),
+        };
+        let epoch = match dec.u8()? {
+            0 => None,
+            1 => Some(dec.epoch()?),
+            _ => return Err(Error::Corrupt),
+        };
+        warnings.push(parser::Diagnostic {
+            line,
+            epoch,
+            message: dec.string()?,
+        })
+    }
+    // raw entries
+    dec.previous = (0, 0);
+    let mut raw = parser::RawRecord::new();
+    for _ in 0..dec.usize()? {
+        let e = dec.epoch()?;
+        let mut entries: Vec<parser::RawEntry> = Vec::new();
+        for _ in 0..dec.usize()? {
+            let sv = dec.string()?;
+            let mut content: Vec<String> = Vec::new();
+            for _ in 0..dec.usize()? {
+                content.push(dec.string()?)
+            }
+            entries.push(parser::RawEntry {
+                sv,
+                content,
+            });
+        }
+        raw.insert(e, entries);
+    }
+    // record
+    dec.previous = (0, 0);
+    let record = match rinex_type {
+        Type::ObservationData => Record::ObsRecord(decode_obs(&mut dec)?),
+        Type::MeteoData => Record::MeteoRecord(decode_meteo(&mut dec)?),
+        Type::ClockData => Record::ClockRecord(decode_clock(&mut dec)?),
+        Type::IonosphereMaps => Record::IonexRecord(decode_ionex(&mut dec)?),
+        _ => return Err(Error::TypeNotSupported(rinex_type)),
+    };
+    if dec.pos != buf.len() {
+        return Err(Error::Corrupt)
+    }
+    Ok((record, comments, events, warnings, raw))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_varint() {
+        let mut enc = Encoder::new();
+        let values: Vec<i64> = vec![0, 1, -1, 63, -64, 30_000_000_000, i64::MAX, i64::MIN];
+        for v in values.iter() {
+            enc.signed(*v)
+        }
+        enc.varint(u64::MAX);
+        let mut dec = Decoder::new(&enc.buf);
+        for v in values.iter() {
+            assert_eq!(dec.signed().unwrap(), *v);
+        }
+        assert_eq!(dec.varint().unwrap(), u64::MAX);
+        assert!(dec.u8().is_err());
+    }
+    #[test]
+    fn test_epochs() {
+        let mut enc = Encoder::new();
+        let t0 = epoch::str2date("2022 06 08 10 00 00").unwrap()
+            + chrono::Duration::nanoseconds(123_456_700);
+        let epochs: Vec<epoch::Epoch> = (0..10)
+            .map(|i| epoch::Epoch::new(
+                t0 + chrono::Duration::seconds(30 * i),
+                epoch::EpochFlag::Ok))
+            .collect();
+        for e in epochs.iter() {
+            enc.epoch(e)
+        }
+        // delta encoding: 30s steps are compact,
+        // 3 bytes per epoch once the first one is known
+        assert!(enc.buf.len() < 10 + 10 * 3);
+        let mut dec = Decoder::new(&enc.buf);
+        for e in epochs.iter() {
+            assert_eq!(dec.epoch().unwrap(), *e);
+        }
+    }
+    #[test]
+    fn test_corrupt_table() {
+        // huge table size, but no content
+        let mut enc = Encoder::new();
+        enc.varint(u64::MAX >> 1);
+        let mut dec = Decoder::new(&enc.buf);
+        assert!(matches!(decode_table(&mut dec), Err(Error::Corrupt)));
+        // table is valid
+        let table: Vec<observation::Observable> = vec!["C1C", "L1C"]
+            .iter()
+            .map(|c| observation::Observable::from_str(c).unwrap())
+            .collect();
+        let mut enc = Encoder::new();
+        encode_table(&mut enc, &table);
+        let mut dec = Decoder::new(&enc.buf);
+        assert_eq!(decode_table(&mut dec).unwrap(), table);
+    }
+    #[test]
+    fn test_ionex() {
+        let coords = |lat: f32| ionosphere::record::Coordinates {
+            lat,
+            lon1: -180.0,
+            lon2: 180.0,
+            dlon: 5.0,
+            h: 450.0,
+        };
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let t1 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 02 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let mut record = ionosphere::record::Record::new();
+        record.insert(t0, (
+            vec![(coords(87.5), vec![9.2, 9.3, 9.4]), (coords(85.0), vec![10.1])],
+            Some(vec![(coords(87.5), vec![0.5, 0.6, 0.7])]),
+            None,
+        ));
+        record.insert(t1, (
+            vec![(coords(87.5), vec![8.0])],
+            None,
+            Some(vec![(coords(87.5), vec![450.0])]),
+        ));
+        let mut enc = Encoder::new();
+        encode_ionex(&mut enc, &record);
+        let mut dec = Decoder::new(&enc.buf);
+        assert_eq!(decode_ionex(&mut dec).unwrap(), record);
+        assert_eq!(dec.pos, enc.buf.len());
+        // truncated content
+        let mut dec = Decoder::new(&enc.buf[..enc.buf.len() - 2]);
+        assert!(matches!(decode_ionex(&mut dec), Err(Error::Corrupt)));
+    }
+}
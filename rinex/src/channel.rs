@@ -1,8 +1,16 @@
 //! Carrier channels and associated methods 
 use thiserror::Error;
 use std::str::FromStr;
+use std::collections::HashMap;
 use crate::sv;
 use crate::constellation::Constellation;
+use crate::observation::Observable;
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+/// Speed of light in vacuum, in m/s
+pub const SPEED_OF_LIGHT_M_S: f64 = 299_792_458.0_f64;
 
 /*
 /// Carrier code
@@ -83,27 +91,50 @@ impl Default for Code {
 
 #[derive(Debug, Clone, Copy)]
 #[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum Channel {
     /// L1 (GPS, SBAS, QZSS)
     L1,
     /// L2 (GPS, QZSS)
     L2,
-    /// L5 (GPS, SBAS), QZSS 
+    /// L5 (GPS, SBAS, QZSS, NavIC)
     L5,
-    /// LEX (QZSS)
+    /// LEX: QZSS L6
     LEX, 
-    /// Glonass channel 1 with possible channel offset
-    G1(Option<u8>),
-    /// Glonass channel 2 with possible channel offset
-    G2(Option<u8>),
+    /// S: NavIC S band
+    S,
+    /// Glonass channel 1 with possible FDMA channel number
+    G1(Option<i8>),
+    /// Glonass channel 2 with possible FDMA channel number
+    G2(Option<i8>),
+    /// G3: Glonass CDMA
+    G3,
     /// E1: GAL
     E1,
     /// E2: GAL
     E2,
-    /// E5: GAL E5a + E5b
+    /// E5: GAL E5a (BeiDou B2a)
     E5, 
+    /// E7: GAL E5b
+    E7,
+    /// E8: GAL E5 AltBOC (E5a + E5b)
+    E8,
     /// E6: GAL military
-    E6
+    E6,
+    /// B1I: BeiDou
+    B1I,
+    /// B1C: BeiDou
+    B1C,
+    /// B2I: BeiDou
+    B2I,
+    /// B2a: BeiDou
+    B2A,
+    /// B2b: BeiDou
+    B2B,
+    /// B2: BeiDou B2 AltBOC (B2a + B2b)
+    B2,
+    /// B3I: BeiDou
+    B3I,
 }
 
 impl Default for Channel {
@@ -135,6 +166,25 @@ impl std::str::FromStr for Channel {
         } else if s.contains("L5") {
             Ok(Channel::L5)
         
+        } else if s.eq("B1I") {
+            Ok(Channel::B1I)
+        } else if s.eq("B2I") {
+            Ok(Channel::B2I)
+        } else if s.eq("B3I") {
+            Ok(Channel::B3I)
+        } else if s.eq("B1C") {
+            Ok(Channel::B1C)
+        } else if s.eq("B2A") || s.eq("B2a") {
+            Ok(Channel::B2A)
+        } else if s.eq("B2B") || s.eq("B2b") {
+            Ok(Channel::B2B)
+        } else if s.eq("B2") {
+            Ok(Channel::B2)
+        } else if s.eq("L6") || s.eq("LEX") {
+            Ok(Channel::LEX)
+        } else if s.eq("G3") {
+            Ok(Channel::G3)
+
         } else if s.contains("G1") {
             if s.eq("G1") {
                 Ok(Channel::G1(None))
@@ -142,7 +192,7 @@ impl std::str::FromStr for Channel {
                 let items : Vec<&str> = s.split("(").collect();
                 let item = items[1].replace(")","");
                 Ok(Channel::G1(
-                    Some(u8::from_str_radix(&item, 10)?)))
+                    Some(i8::from_str_radix(&item, 10)?)))
             } else {
                 Err(Error::ParseError(s.to_string()))
             }
@@ -154,7 +204,7 @@ impl std::str::FromStr for Channel {
                 let items : Vec<&str> = s.split("(").collect();
                 let item = items[1].replace(")","");
                 Ok(Channel::G2(
-                    Some(u8::from_str_radix(&item, 10)?)))
+                    Some(i8::from_str_radix(&item, 10)?)))
             } else {
                 Err(Error::ParseError(s.to_string()))
             }
@@ -166,19 +216,50 @@ impl std::str::FromStr for Channel {
 }
 
 impl Channel {
-    /// Returns frequency associated to this channel in MHz 
+    /// Returns frequency associated to this channel in MHz.
+    /// Glonass channels without FDMA channel number
+    /// return the center frequency of the band
     pub fn carrier_frequency_mhz (&self) -> f64 {
         match self {
-            Channel::L1 | Channel::E1 => 1575.42_f64,
+            Channel::L1 | Channel::E1 | Channel::B1C => 1575.42_f64,
             Channel::L2 | Channel::E2 => 1227.60_f64,
-            Channel::L5 | Channel::E5 => 1176.45_f64,
+            Channel::L5 | Channel::E5 | Channel::B2A => 1176.45_f64,
+            Channel::S => 2492.028_f64,
             Channel::G1(Some(c)) => 1602.0_f64 + (*c as f64 *9.0/16.0), 
             Channel::G1(_) => 1602.0_f64,
-            Channel::G2(Some(c)) => 1246.06_f64 + (*c as f64 * 7.0/16.0),
-            Channel::G2(_) => 1246.06_f64,
-            _ => 0.0, //TODO
+            Channel::G2(Some(c)) => 1246.0_f64 + (*c as f64 * 7.0/16.0),
+            Channel::G2(_) => 1246.0_f64,
+            Channel::G3 => 1202.025_f64,
+            Channel::B1I => 1561.098_f64,
+            Channel::B2I | Channel::B2B | Channel::E7 => 1207.14_f64,
+            Channel::E8 | Channel::B2 => 1191.795_f64,
+            Channel::B3I => 1268.52_f64,
+            Channel::E6 | Channel::LEX => 1278.75_f64,
         }
     }
+
+    /// Returns carrier wavelength, in meters
+    pub fn wavelength_m (&self) -> f64 {
+        SPEED_OF_LIGHT_M_S / (self.carrier_frequency_mhz() * 1.0E6)
+    }
+
+    /// Converts a carrier phase expressed in cycles,
+    /// as found in Observation records, to meters
+    pub fn cycles_to_m (&self, cycles: f64) -> f64 {
+        cycles * self.wavelength_m()
+    }
+
+    /// Converts a carrier phase expressed in meters, to cycles
+    pub fn m_to_cycles (&self, m: f64) -> f64 {
+        m / self.wavelength_m()
+    }
+
+    /// Converts a Doppler shift [Hz], as found in Observation records,
+    /// to a range rate [m.s⁻¹]. A positive Doppler shift
+    /// means the vehicule is approaching: range rate is then negative
+    pub fn doppler_to_range_rate (&self, doppler: f64) -> f64 {
+        -doppler * self.wavelength_m()
+    }
     
     /// Returns channel bandwidth in MHz
     pub fn bandwidth_mhz (&self) -> f64 {
@@ -186,80 +267,119 @@ impl Channel {
             Channel::L1 | Channel::G1(_) | Channel::E1 => 15.345_f64,
             Channel::L2 | Channel::G2(_) | Channel::E2 => 11.0_f64,
             Channel::L5 | Channel::E5 => 12.5_f64,
+            Channel::B1I => 4.092_f64,
+            Channel::B1C => 32.736_f64,
+            Channel::B2I | Channel::B2A | Channel::B2B | Channel::B3I | Channel::E7 => 20.46_f64,
+            Channel::E8 | Channel::B2 => 51.15_f64,
+            Channel::G3 => 0.0, //TODO
+            Channel::S => 0.0, //TODO
             Channel::E6 => 0.0, //TODO
             Channel::LEX => 0.0, //TODO
         }
     }
 
     /// Identifies Frequency channel, from given observable, related
-    /// to given Constellation
-    pub fn from_observable (constellation: Constellation, observable: &str) -> Result<Self, Error> {
+    /// to given Constellation.
+    /// Supports modern observables as well as V2 legacy codes
+    /// (C1, P1, P2, CA, LA..)
+    pub fn from_observable (constellation: Constellation, observable: &Observable) -> Result<Self, Error> {
+        let code = observable.code();
+        // RINEX 2.12 L1C legacy codes: CA, LA, DA, SA (CB, LB..)
+        let is_legacy_l1c = code.attribute.is_none()
+            && (code.band == 'A' || code.band == 'B');
+        let invalid = || Error::InvalidObservable(observable.to_string());
         match constellation {
             Constellation::GPS => {
-                if observable.contains("1") {
-                    Ok(Self::L1)
-                } else if observable.contains("2") {
-                    Ok(Self::L2)
-                } else if observable.contains("5") {
-                    Ok(Self::L5)
-                } else {
-                    Err(Error::InvalidObservable(observable.to_string()))
+                match code.band {
+                    '1' => Ok(Self::L1),
+                    '2' => Ok(Self::L2),
+                    '5' => Ok(Self::L5),
+                    _ if is_legacy_l1c => Ok(Self::L1),
+                    _ => Err(invalid()),
                 }
             },
             Constellation::Glonass => {
-                if observable.contains("1") {
-                    Ok(Self::G1(None))
-                } else if observable.contains("2") {
-                    Ok(Self::G2(None))
-                } else {
-                    Err(Error::InvalidObservable(observable.to_string()))
+                match code.band {
+                    '1' => Ok(Self::G1(None)),
+                    '2' => Ok(Self::G2(None)),
+                    '3' => Ok(Self::G3),
+                    _ => Err(invalid()),
                 }
             },
             Constellation::Galileo => {
-                if observable.contains("1") {
-                    Ok(Self::E1)
-                } else if observable.contains("2") {
-                    Ok(Self::E2)
-                } else if observable.contains("5") {
-                    Ok(Self::E5)
-                } else if observable.contains("6") {
-                    Ok(Self::E6)
-                } else {
-                    Err(Error::InvalidObservable(observable.to_string()))
+                match code.band {
+                    '1' => Ok(Self::E1),
+                    '2' => Ok(Self::E2),
+                    '5' => Ok(Self::E5),
+                    '6' => Ok(Self::E6),
+                    '7' => Ok(Self::E7),
+                    '8' => Ok(Self::E8),
+                    _ => Err(invalid()),
                 }
             },
             Constellation::SBAS(_) => {
-                if observable.contains("1") {
-                    Ok(Self::L1)
-                } else if observable.contains("5") {
-                    Ok(Self::L5)
-                } else {
-                    Err(Error::InvalidObservable(observable.to_string()))
+                match code.band {
+                    '1' => Ok(Self::L1),
+                    '5' => Ok(Self::L5),
+                    _ => Err(invalid()),
                 }
             },
             Constellation::QZSS => {
-                if observable.contains("1") {
-                    Ok(Self::L1)
-                } else if observable.contains("2") {
-                    Ok(Self::L2)
-                } else if observable.contains("5") {
-                    Ok(Self::L5)
-                } else if observable.contains("7") {
-                    Ok(Self::LEX) // TODO confirm !
-                } else {
-                    Err(Error::InvalidObservable(observable.to_string()))
+                match code.band {
+                    '1' => Ok(Self::L1),
+                    '2' => Ok(Self::L2),
+                    '5' => Ok(Self::L5),
+                    '6' => Ok(Self::LEX),
+                    _ if is_legacy_l1c => Ok(Self::L1),
+                    _ => Err(invalid()),
                 }
             },
             Constellation::IRNSS => {
-                if observable.contains("1") {
-                    Ok(Self::L1)
-                } else if observable.contains("5") {
-                    Ok(Self::L5)
-                } else {
-                    Err(Error::InvalidObservable(observable.to_string()))
+                match code.band {
+                    '1' => Ok(Self::L1),
+                    '5' => Ok(Self::L5),
+                    '9' => Ok(Self::S),
+                    _ => Err(invalid()),
+                }
+            },
+            Constellation::BeiDou => {
+                match code.band {
+                    '2' => Ok(Self::B1I),
+                    '1' => {
+                        // RINEX 3.01 B1I (C1I, L1Q..) or modern B1C (B1A)
+                        match code.attribute {
+                            Some('I') | Some('Q') => Ok(Self::B1I),
+                            _ => Ok(Self::B1C),
+                        }
+                    },
+                    '5' => Ok(Self::B2A),
+                    '7' => {
+                        // B2I (C7I, L7Q..) or B2b (C7D, L7P..)
+                        match code.attribute {
+                            Some('D') | Some('P') | Some('Z') => Ok(Self::B2B),
+                            _ => Ok(Self::B2I),
+                        }
+                    },
+                    '8' => Ok(Self::B2),
+                    '6' => Ok(Self::B3I), // B3I, B3A
+                    _ => Err(invalid()),
                 }
             },
-            _ => todo!("not implemented for constellation \"{}\" yet..", constellation.to_3_letter_code()),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Identifies Frequency channel, from given observable, made
+    /// by given vehicule. Glonass channels are tied to the vehicule
+    /// FDMA channel number, when described in `glo_channels`
+    /// (GLONASS SLOT / FRQ # header field), so [Channel::carrier_frequency_mhz]
+    /// returns the true carrier frequency
+    pub fn from_sv_observable (sv: &sv::Sv, observable: &Observable, glo_channels: &HashMap<sv::Sv, i8>) -> Result<Self, Error> {
+        let channel = Self::from_observable(sv.constellation, observable)?;
+        match (channel, glo_channels.get(sv)) {
+            (Self::G1(_), Some(c)) => Ok(Self::G1(Some(*c))),
+            (Self::G2(_), Some(c)) => Ok(Self::G2(Some(*c))),
+            _ => Ok(channel),
         }
     }
     
@@ -280,6 +400,7 @@ impl Channel {
                 match sv.prn {
                     1 => Ok(Self::G1(None)),
                     2 => Ok(Self::G2(None)),
+                    3 => Ok(Self::G3),
                     _ => Ok(Self::G1(None)),
                 }
             },
@@ -288,6 +409,9 @@ impl Channel {
                     1 => Ok(Self::E1),
                     2 => Ok(Self::E2),
                     5 => Ok(Self::E5),
+                    6 => Ok(Self::E6),
+                    7 => Ok(Self::E7),
+                    8 => Ok(Self::E8),
                     _ => Ok(Self::E1),
                 }
             },
@@ -300,11 +424,13 @@ impl Channel {
             },
             Constellation::BeiDou => {
                 match sv.prn {
-                    1 => Ok(Self::E1),
-                    2 => Ok(Self::E2),
-                    5 => Ok(Self::E5),
-                    6 => Ok(Self::E6),
-                    _ => Ok(Self::E1),
+                    1 => Ok(Self::B1C),
+                    2 => Ok(Self::B1I),
+                    5 => Ok(Self::B2A),
+                    6 => Ok(Self::B3I),
+                    7 => Ok(Self::B2I),
+                    8 => Ok(Self::B2),
+                    _ => Ok(Self::B1C),
                 }
             },
             Constellation::QZSS => {
@@ -317,9 +443,10 @@ impl Channel {
                 }
             },
             Constellation::IRNSS => {
-                match sv.prn { // TODO: confirm!
+                match sv.prn {
                     1 => Ok(Self::L1),
                     5 => Ok(Self::L5),
+                    9 => Ok(Self::S),
                     _ => Ok(Self::L1),
                 }
             },
@@ -343,5 +470,71 @@ mod test {
         assert_eq!(Channel::from_str("L1").is_ok(), true);
         assert_eq!(Channel::from_str("C1").is_err(), true);
         assert_eq!(Channel::from_str("L5").is_ok(), true);
+        assert_eq!(Channel::from_str("B1C").unwrap(), Channel::B1C);
+        assert_eq!(Channel::from_str("B2a").unwrap(), Channel::B2A);
+        assert_eq!(Channel::from_str("L6").unwrap(), Channel::LEX);
+        assert_eq!(Channel::from_sv_code("C05").unwrap(), Channel::B2A);
+        assert_eq!(Channel::from_sv_code("C02").unwrap(), Channel::B1I);
+        assert_eq!(Channel::from_sv_code("I09").unwrap(), Channel::S);
+    }
+    #[test]
+    fn test_phase_units() {
+        let l1 = Channel::L1;
+        assert!((l1.wavelength_m() - 0.190293672798).abs() < 1.0E-9);
+        let m = l1.cycles_to_m(100.0);
+        assert!((m - 19.0293672798).abs() < 1.0E-7);
+        assert!((l1.m_to_cycles(m) - 100.0).abs() < 1.0E-9);
+        assert!((l1.doppler_to_range_rate(-1000.0) - 190.293672798).abs() < 1.0E-6);
+    }
+    #[test]
+    fn test_from_observable() {
+        // V2 legacy codes
+        for code in vec!["C1", "P1", "L1", "CA", "LA", "SA"] {
+            assert_eq!(Channel::from_observable(Constellation::GPS, &Observable::from_str(code).unwrap()).unwrap(), Channel::L1);
+        }
+        for code in vec!["C2", "P2", "L2", "S2"] {
+            assert_eq!(Channel::from_observable(Constellation::GPS, &Observable::from_str(code).unwrap()).unwrap(), Channel::L2);
+        }
+        assert_eq!(Channel::from_observable(Constellation::Glonass, &Observable::from_str("P1").unwrap()).unwrap(), Channel::G1(None));
+        assert_eq!(Channel::from_observable(Constellation::Glonass, &Observable::from_str("P2").unwrap()).unwrap(), Channel::G2(None));
+        // BeiDou
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("C2I").unwrap()).unwrap(), Channel::B1I);
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("C1I").unwrap()).unwrap(), Channel::B1I);
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("L7I").unwrap()).unwrap(), Channel::B2I);
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("C6I").unwrap()).unwrap(), Channel::B3I);
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("C1P").unwrap()).unwrap(), Channel::B1C);
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("C5P").unwrap()).unwrap(), Channel::B2A);
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("L7D").unwrap()).unwrap(), Channel::B2B);
+        assert_eq!(Channel::from_observable(Constellation::BeiDou, &Observable::from_str("C8X").unwrap()).unwrap(), Channel::B2);
+        assert_eq!(Channel::B1C.carrier_frequency_mhz(), 1575.42);
+        assert_eq!(Channel::B2A.carrier_frequency_mhz(), 1176.45);
+        assert_eq!(Channel::B2B.carrier_frequency_mhz(), 1207.14);
+        // QZSS L6, NavIC S band, Glonass CDMA
+        assert_eq!(Channel::from_observable(Constellation::QZSS, &Observable::from_str("L6L").unwrap()).unwrap(), Channel::LEX);
+        assert_eq!(Channel::from_observable(Constellation::QZSS, &Observable::from_str("L7X").unwrap()).is_err(), true);
+        assert_eq!(Channel::from_observable(Constellation::IRNSS, &Observable::from_str("C9A").unwrap()).unwrap(), Channel::S);
+        assert_eq!(Channel::from_observable(Constellation::IRNSS, &Observable::from_str("C5A").unwrap()).unwrap(), Channel::L5);
+        assert_eq!(Channel::from_observable(Constellation::Glonass, &Observable::from_str("C3Q").unwrap()).unwrap(), Channel::G3);
+        assert_eq!(Channel::S.carrier_frequency_mhz(), 2492.028);
+        // Galileo E5a, E5b, E5 AltBOC
+        assert_eq!(Channel::from_observable(Constellation::Galileo, &Observable::from_str("C5Q").unwrap()).unwrap(), Channel::E5);
+        assert_eq!(Channel::from_observable(Constellation::Galileo, &Observable::from_str("L7Q").unwrap()).unwrap(), Channel::E7);
+        assert_eq!(Channel::from_observable(Constellation::Galileo, &Observable::from_str("C8Q").unwrap()).unwrap(), Channel::E8);
+        assert_eq!(Channel::E8.carrier_frequency_mhz(), 1191.795);
+        // Glonass FDMA
+        let r01 = sv::Sv::from_str("R01").unwrap();
+        let r10 = sv::Sv::from_str("R10").unwrap();
+        let mut glo_channels = HashMap::new();
+        glo_channels.insert(r01, 1);
+        let c1c = Observable::from_str("C1C").unwrap();
+        let l2c = Observable::from_str("L2C").unwrap();
+        assert_eq!(Channel::from_sv_observable(&r01, &c1c, &glo_channels).unwrap(), Channel::G1(Some(1)));
+        assert_eq!(Channel::from_sv_observable(&r01, &l2c, &glo_channels).unwrap(), Channel::G2(Some(1)));
+        assert_eq!(Channel::from_sv_observable(&r10, &c1c, &glo_channels).unwrap(), Channel::G1(None));
+        assert_eq!(Channel::G1(Some(-7)).carrier_frequency_mhz(), 1598.0625);
+        assert_eq!(Channel::G2(Some(1)).carrier_frequency_mhz(), 1246.4375);
+        assert_eq!(Channel::G1(None).carrier_frequency_mhz(), 1602.0);
+        // not supported: no panic
+        assert_eq!(Channel::from_observable(Constellation::Mixed, &Observable::from_str("C1").unwrap()).is_err(), true);
     }
 }
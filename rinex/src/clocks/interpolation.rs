@@ -0,0 +1,155 @@
+//! Clock bias interpolation, to evaluate clock products
+//! at arbitrary epochs (typically observation epochs)
+use crate::epoch;
+use super::record::{Record, System, DataType, Data};
+use std::collections::HashMap;
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+/// Clock bias interpolation method
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum Interpolation {
+    /// Linear interpolation between the two surrounding samples
+    Linear,
+    /// Lagrange interpolation over given number of samples,
+    /// centered on the interpolated epoch when possible
+    Lagrange(usize),
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+/// Data types we interpolate, by order of preference.
+/// Discontinuity measurements (DR) are not interpolated
+const PREFERRED_TYPES: [DataType; 4] = [DataType::As, DataType::Ar, DataType::Cr, DataType::Ms];
+
+/// Returns preferred clock data of a given system
+fn preferred_data (data: &HashMap<DataType, Data>) -> Option<&Data> {
+    PREFERRED_TYPES.iter()
+        .find_map(|dtype| data.get(dtype))
+}
+
+/// Interpolates the clock bias [s] of given satellite or station
+/// at given epoch, with given method.
+/// Satellite and receiver analysis results (AS, AR) are preferred,
+/// then calibration (CR) and monitor (MS) measurements.
+/// Returns None if given epoch is not within the time frame described
+/// for this system, as we do not extrapolate, or if there is not enough
+/// data for the requested method.
+/// Example:
+/// ```
+/// use rinex::*;
+/// use rinex::clocks::{self, System, Interpolation};
+/// use std::str::FromStr;
+/// let rinex = Rinex::from_file("../test_resources/CLK/V3/example2.txt")
+///     .unwrap();
+/// let record = rinex.record.as_clock()
+///     .unwrap();
+/// let g01 = System::Sv(sv::Sv::from_str("G01").unwrap());
+/// let e = epoch::Epoch::new(
+///     epoch::str2date("2017 03 11 00 00 00").unwrap(),
+///     epoch::EpochFlag::Ok);
+/// // samples are returned as is
+/// assert_eq!(clocks::interpolate(record, &g01, e, Interpolation::Linear), Some(0.175309377613E-08));
+/// ```
+pub fn interpolate (record: &Record, system: &System, epoch: epoch::Epoch, method: Interpolation) -> Option<f64> {
+    // (offset to interpolated epoch [s], bias)
+    let samples: Vec<(f64, f64)> = record.iter()
+        .filter_map(|(e, entries)| {
+            let data = match system {
+                System::Sv(sv) => entries.satellites().get(sv),
+                System::Station(station) => entries.stations().get(station),
+            }?;
+            let dt = (e.date - epoch.date).num_nanoseconds()? as f64 / 1.0E9;
+            preferred_data(data)
+                .map(|data| (dt, data.bias))
+        })
+        .collect();
+    // index of the first sample at or after interpolated epoch
+    let index = samples.iter()
+        .position(|(dt, _)| *dt >= 0.0)?;
+    if samples[index].0 == 0.0 {
+        return Some(samples[index].1)
+    }
+    if index == 0 {
+        return None // we do not extrapolate
+    }
+    let order = match method {
+        Interpolation::Linear => 2,
+        Interpolation::Lagrange(order) => order,
+    };
+    if order < 2 || samples.len() < order {
+        return None
+    }
+    // window of `order` samples, centered on the interpolated epoch
+    let start = index
+        .saturating_sub(order / 2)
+        .min(samples.len() - order);
+    let window = &samples[start..start + order];
+    let mut bias = 0.0_f64;
+    for (i, (ti, yi)) in window.iter().enumerate() {
+        let mut li = 1.0_f64;
+        for (j, (tj, _)) in window.iter().enumerate() {
+            if i != j {
+                li *= -tj / (ti - tj);
+            }
+        }
+        bias += li * yi;
+    }
+    Some(bias)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sv::Sv;
+    use crate::clocks::record::ClockEntries;
+    use std::str::FromStr;
+    #[test]
+    fn test_interpolation() {
+        let g01 = Sv::from_str("G01").unwrap();
+        let t0 = epoch::str2date("2017 03 11 00 00 00").unwrap();
+        // quadratic clock model, sampled every 30s
+        let model = |t: f64| 1.0E-4 + 1.0E-9 * t + 1.0E-12 * t * t;
+        let mut record = Record::new();
+        for i in 0..10 {
+            let t = i as f64 * 30.0;
+            let mut entries = ClockEntries::default();
+            entries.insert(System::Sv(g01), DataType::As, Data {
+                bias: model(t),
+                bias_sigma: None,
+                rate: None,
+                rate_sigma: None,
+                accel: None,
+                accel_sigma: None,
+            });
+            let e = epoch::Epoch::new(t0 + chrono::Duration::seconds(i * 30), epoch::EpochFlag::Ok);
+            record.insert(e, entries);
+        }
+        let system = System::Sv(g01);
+        let at = |seconds: i64| epoch::Epoch::new(t0 + chrono::Duration::seconds(seconds), epoch::EpochFlag::Ok);
+        // samples
+        assert_eq!(interpolate(&record, &system, at(60), Interpolation::Linear), Some(model(60.0)));
+        // linear
+        let bias = interpolate(&record, &system, at(75), Interpolation::Linear).unwrap();
+        assert!((bias - (model(60.0) + model(90.0)) / 2.0).abs() < 1.0E-15);
+        // lagrange: quadratic model is reproduced, including at boundaries
+        for seconds in [5, 75, 131, 265] {
+            let bias = interpolate(&record, &system, at(seconds), Interpolation::Lagrange(3)).unwrap();
+            assert!((bias - model(seconds as f64)).abs() < 1.0E-15);
+        }
+        // no extrapolation
+        assert_eq!(interpolate(&record, &system, at(-10), Interpolation::Linear), None);
+        assert_eq!(interpolate(&record, &system, at(300), Interpolation::Linear), None);
+        // not enough samples
+        assert_eq!(interpolate(&record, &system, at(75), Interpolation::Lagrange(11)), None);
+        // unknown system
+        let g02 = System::Sv(Sv::from_str("G02").unwrap());
+        assert_eq!(interpolate(&record, &g02, at(75), Interpolation::Linear), None);
+    }
+}
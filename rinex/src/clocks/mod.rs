@@ -1,5 +1,11 @@
 //! RINEX Clock files parser & analysis 
 pub mod record;
+pub mod interpolation;
+
+pub use record::{System, DataType, Data};
+pub use interpolation::{Interpolation, interpolate};
+
+use crate::epoch;
 
 /// Clocks `RINEX` specific header fields
 #[derive(Clone, Debug)]
@@ -13,6 +19,82 @@ pub struct HeaderFields {
     pub station: Option<Station>,
     /// Reference clock descriptor
     pub clock_ref: Option<String>,
+    /// Reference clocks used in the analysis,
+    /// possibly on a per period basis
+    pub reference_clocks: Vec<ReferenceClocks>,
+}
+
+impl HeaderFields {
+    /// Returns analysis center, if specified
+    pub fn analysis_center (&self) -> Option<&Agency> {
+        self.agency.as_ref()
+    }
+    /// Returns reference clocks applicable at given epoch.
+    /// Lists that do not specify a validity period always apply
+    pub fn reference_clocks_at (&self, epoch: epoch::Epoch) -> Vec<&ReferenceClock> {
+        self.reference_clocks
+            .iter()
+            .filter(|refs| refs.contains(epoch))
+            .flat_map(|refs| refs.clocks.iter())
+            .collect()
+    }
+    /// Returns true if this file describes analysis results
+    /// (AR or AS data), for which the analysis center
+    /// and reference clocks must be specified
+    pub fn is_analysis (&self) -> bool {
+        self.codes.contains(&DataType::Ar) || self.codes.contains(&DataType::As)
+    }
+    /// Verifies fields required to produce a Clock RINEX are specified
+    pub(crate) fn check_production (&self) -> std::io::Result<()> {
+        if self.is_analysis() {
+            if self.agency.is_none() {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                    "ANALYSIS CENTER must be specified for AR/AS data"))
+            }
+            if self.reference_clocks.is_empty() {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                    "ANALYSIS CLK REF must be specified for AR/AS data"))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reference clock, as described by `ANALYSIS CLK REF`
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct ReferenceClock {
+    /// Station or satellite name
+    pub name: String,
+    /// Station identifier (DOMES number)
+    pub id: String,
+    /// Optional a priori clock constraint [s]
+    pub constraint: Option<f64>,
+}
+
+/// List of reference clocks, as described by `# OF CLK REF`
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct ReferenceClocks {
+    /// Start of validity period, if specified
+    pub start: Option<epoch::Epoch>,
+    /// End of validity period, if specified
+    pub end: Option<epoch::Epoch>,
+    /// Reference clocks
+    pub clocks: Vec<ReferenceClock>,
+}
+
+impl ReferenceClocks {
+    /// Returns true if this list applies to given epoch
+    pub fn contains (&self, epoch: epoch::Epoch) -> bool {
+        let after_start = self.start
+            .map(|start| epoch.date >= start.date)
+            .unwrap_or(true);
+        let before_end = self.end
+            .map(|end| epoch.date <= end.date)
+            .unwrap_or(true);
+        after_start && before_end
+    }
 }
 
 /// Describes a clock station 
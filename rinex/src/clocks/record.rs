@@ -1,11 +1,17 @@
 use std::str::FromStr;
+use std::io::Write;
+use chrono::{Datelike, Timelike};
 use crate::sv::Sv;
 use crate::epoch;
+use crate::header::Header;
+use crate::version::Version;
+use crate::navigation::record::format_float;
 use thiserror::Error;
 use strum_macros::EnumString;
 use std::collections::{BTreeMap, HashMap};
 
 #[derive(Error, PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum System {
     /// Sv system for AS data
     Sv(Sv),
@@ -54,10 +60,12 @@ pub enum Error {
     ParseFloatError(#[from] std::num::ParseFloatError),
     #[error("failed to identify observable")]
     ParseObservableError(#[from] strum::ParseError),
+    #[error("missing data fields")]
+    MissingData,
 }
 
 /// Clocks file payload
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Data {
     /// Clock bias
@@ -105,91 +113,147 @@ impl std::fmt::Display for DataType {
     }
 }
 
+/// Clock entries of a given epoch.
+/// Station (receiver) clocks and satellite clocks
+/// are stored in distinct maps, then sorted by data type
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct ClockEntries {
+    /// Station (receiver) clocks, by station name
+    stations: HashMap<String, HashMap<DataType, Data>>,
+    /// Satellite clocks
+    satellites: HashMap<Sv, HashMap<DataType, Data>>,
+}
+
+impl ClockEntries {
+    /// Returns station (receiver) clocks, by station name
+    pub fn stations (&self) -> &HashMap<String, HashMap<DataType, Data>> {
+        &self.stations
+    }
+    /// Returns mutable station (receiver) clocks
+    pub fn stations_mut (&mut self) -> &mut HashMap<String, HashMap<DataType, Data>> {
+        &mut self.stations
+    }
+    /// Returns satellite clocks
+    pub fn satellites (&self) -> &HashMap<Sv, HashMap<DataType, Data>> {
+        &self.satellites
+    }
+    /// Returns mutable satellite clocks
+    pub fn satellites_mut (&mut self) -> &mut HashMap<Sv, HashMap<DataType, Data>> {
+        &mut self.satellites
+    }
+    /// Inserts (or replaces) given data, for given system and data type
+    pub fn insert (&mut self, system: System, dtype: DataType, data: Data) {
+        match system {
+            System::Sv(sv) => {
+                self.satellites
+                    .entry(sv)
+                    .or_insert_with(HashMap::new)
+                    .insert(dtype, data);
+            },
+            System::Station(station) => {
+                self.stations
+                    .entry(station)
+                    .or_insert_with(HashMap::new)
+                    .insert(dtype, data);
+            },
+        }
+    }
+    /// Merges given entries into self,
+    /// data types are merged on a per system basis
+    pub fn merge_mut (&mut self, rhs: &Self) {
+        for (station, data) in rhs.stations.iter() {
+            self.stations
+                .entry(station.clone())
+                .or_insert_with(HashMap::new)
+                .extend(data.clone());
+        }
+        for (sv, data) in rhs.satellites.iter() {
+            self.satellites
+                .entry(*sv)
+                .or_insert_with(HashMap::new)
+                .extend(data.clone());
+        }
+    }
+    /// Returns true if self does not contain any data
+    pub fn is_empty (&self) -> bool {
+        self.stations.is_empty() && self.satellites.is_empty()
+    }
+}
+
 /// RINEX record for CLOCKS files,
-/// record is sorted by Epoch then by data type and finaly by `system`
-pub type Record = BTreeMap<epoch::Epoch, HashMap<System, HashMap<DataType, Data>>>;
+/// record is sorted by Epoch, then station and satellite
+/// clocks are stored separately, see [ClockEntries]
+pub type Record = BTreeMap<epoch::Epoch, ClockEntries>;
 
 pub fn is_new_epoch (line: &str) -> bool {
-    // first 3 bytes match a DataType code
-    let content = line.split_at(2).0;
-    DataType::from_str(content).is_ok()
+    // first 2 bytes match a DataType code
+    match line.get(0..2) {
+        Some(content) => DataType::from_str(content).is_ok(),
+        None => false,
+    }
+}
+
+/// Returns width of the satellite or station name field:
+/// RINEX 3.04 extended it to 9 characters, to fit 9 character station names
+fn name_width (version: Version) -> usize {
+    if version >= (Version { major: 3, minor: 4 }) {
+        9
+    } else {
+        4
+    }
 }
 
 /// Builds `RINEX` record entry for `Clocks` data files.   
 /// Returns identified `epoch` to sort data efficiently.  
 /// Returns 2D data as described in `record` definition
-pub fn build_record_entry (content: &str) -> 
+pub fn build_record_entry (version: Version, content: &str) -> 
         Result<(epoch::Epoch, System, DataType, Data), Error> 
 {
     let mut lines = content.lines();
     let line = lines.next()
-        .unwrap();
-    // Data type code
-    let (dtype, rem) = line.split_at(3);
-    let data_type = DataType::from_str(dtype.trim())?;
-    let (system_str, rem) = rem.split_at(4);
-    let system = match Sv::from_str(system_str) {
+        .ok_or(Error::MissingData)?;
+    // A2,1X: data type code
+    let dtype = line.get(0..2)
+        .ok_or(Error::MissingData)?;
+    let data_type = DataType::from_str(dtype)?;
+    // A4,1X (A9,1X): satellite or station name
+    let width = name_width(version);
+    let system_str = line.get(3..3+width)
+        .ok_or(Error::MissingData)?;
+    let system = match Sv::from_str(system_str.trim_end()) {
         Ok(sv) => System::Sv(sv),
         _ => System::Station(system_str.trim_end().to_string()),
     };
-    // Epoch
-    let offset = 
-        4+1 // Y always a 4 digit number, even on RINEX2
-       +2+1 // m
-       +2+1  // d
-       +2+1  // h
-       +2+1  // m
-        +11; // s
-    let (epoch, rem) = rem.split_at(offset);
+    // I4,4(1X,I2),F10.6: epoch
+    let offset = 4 + width;
+    let epoch = line.get(offset..offset+26)
+        .ok_or(Error::MissingData)?;
     let date = epoch::str2date(epoch)?; 
-    // n
-    let (n, rem) = rem.split_at(5);
+    // I3: number of data values
+    let n = line.get(offset+26..offset+29)
+        .ok_or(Error::MissingData)?;
     let m = u8::from_str_radix(n.trim(), 10)?;
-
-    let (content, rem) = rem.split_at(20);
-    let bias = f64::from_str(content.trim())?;
-    let bias_sigma :Option<f64> = match m > 1 {
-        true => {
-            let (content, _) = rem.split_at(20);
-            Some(f64::from_str(content.trim())?)
-        },
-        _ => None,
-    };
-    let rate: Option<f64> = match m > 2 {
-        true => {
-            let (content, _) = rem.split_at(20);
-            Some(f64::from_str(content.trim())?)
-        },
-        _ => None,
-    };
-    let rate_sigma :Option<f64> = match m > 3 {
-        true => {
-            let (content, _) = rem.split_at(20);
-            Some(f64::from_str(content.trim())?)
-        },
-        _ => None,
-    };
-    let accel: Option<f64> = match m > 4 {
-        true => {
-            let (content, _) = rem.split_at(20);
-            Some(f64::from_str(content.trim())?)
-        },
-        _ => None,
-    };
-    let accel_sigma :Option<f64> = match m > 5 {
-        true => {
-            let (content, _) = rem.split_at(20);
-            Some(f64::from_str(content.trim())?)
-        },
-        _ => None,
-    };
+    // 2X,2(E19.12,1X): bias and bias sigma,
+    // remaining values are found on the continuation line
+    let mut fields = line.get(offset+29..)
+        .unwrap_or("")
+        .split_ascii_whitespace()
+        .chain(lines.flat_map(|l| l.split_ascii_whitespace()));
+    let mut values: Vec<f64> = Vec::with_capacity(m as usize);
+    for _ in 0..m {
+        let field = fields.next()
+            .ok_or(Error::MissingData)?;
+        values.push(f64::from_str(field)?)
+    }
     let data = Data {
-        bias,
-        bias_sigma,
-        rate,
-        rate_sigma,
-        accel,
-        accel_sigma,
+        bias: *values.first()
+            .ok_or(Error::MissingData)?,
+        bias_sigma: values.get(1).copied(),
+        rate: values.get(2).copied(),
+        rate_sigma: values.get(3).copied(),
+        accel: values.get(4).copied(),
+        accel_sigma: values.get(5).copied(),
     };
     let epoch = epoch::Epoch {
         flag: epoch::EpochFlag::Ok,
@@ -199,6 +263,59 @@ pub fn build_record_entry (content: &str) ->
 }
     
 
+/// Writes a single clock data line, and possible continuation line
+fn write_data (writer: &mut impl Write, width: usize, epoch: &epoch::Epoch, dtype: &DataType, system: &str, data: &Data) -> std::io::Result<()> {
+    let date = epoch.date;
+    let seconds = date.time().second() as f64
+        + date.time().nanosecond() as f64 / 1.0E9;
+    let values: Vec<Option<f64>> = vec![
+        Some(data.bias), data.bias_sigma,
+        data.rate, data.rate_sigma,
+        data.accel, data.accel_sigma,
+    ];
+    let m = values.iter()
+        .rposition(|v| v.is_some())
+        .unwrap_or(0) + 1;
+    write!(writer, "{:<2} {:<width$} {:4} {:02} {:02} {:02} {:02}{:10.6}{:3}   ",
+        dtype.to_string(), system,
+        date.date().year(), date.date().month(), date.date().day(),
+        date.time().hour(), date.time().minute(), seconds, m, width = width)?;
+    for (i, value) in values[..m].iter().enumerate() {
+        if i == 2 {
+            write!(writer, "\n")?
+        }
+        write!(writer, "{} ", format_float(value.unwrap_or(0.0), false))?
+    }
+    write!(writer, "\n")
+}
+
+/// Writes given Clock record, epoch by epoch.
+/// Station clocks come first, sorted by name, then satellite clocks
+pub fn to_writer (header: &Header, record: &Record, writer: &mut impl Write) -> std::io::Result<()> {
+    let width = name_width(header.version);
+    for (epoch, entries) in record.iter() {
+        let mut stations: Vec<&String> = entries.stations.keys().collect();
+        stations.sort();
+        for station in stations {
+            let mut data: Vec<(&DataType, &Data)> = entries.stations[station].iter().collect();
+            data.sort_by_key(|(dtype, _)| dtype.to_string());
+            for (dtype, data) in data {
+                write_data(writer, width, epoch, dtype, station, data)?
+            }
+        }
+        let mut satellites: Vec<&Sv> = entries.satellites.keys().collect();
+        satellites.sort();
+        for sv in satellites {
+            let mut data: Vec<(&DataType, &Data)> = entries.satellites[sv].iter().collect();
+            data.sort_by_key(|(dtype, _)| dtype.to_string());
+            for (dtype, data) in data {
+                write_data(writer, width, epoch, dtype, &sv.to_string(), data)?
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -215,4 +332,55 @@ mod test {
         let l = "AS AREQ 1994 07 14 20 59  0.000000  6   -0.123456789012E+00 -0.123456789012E+01"; 
         assert_eq!(is_new_epoch(l), true);
     }
+    #[test]
+    fn test_clock_entries() {
+        let l = "AR AREQ 1994 07 14 20 59  0.000000  6   -0.123456789012E+00 -0.123456789012E+01 
+-0.123456789012E+02 -0.123456789012E+03 -0.123456789012E+04 -0.123456789012E+05 ";
+        let (_, system, dtype, data) = build_record_entry(Version::new(3, 0), l)
+            .unwrap();
+        assert_eq!(system, System::Station(String::from("AREQ")));
+        assert_eq!(data.rate, Some(-0.123456789012E+02));
+        assert_eq!(data.accel_sigma, Some(-0.123456789012E+05));
+        let mut entries = ClockEntries::default();
+        assert_eq!(entries.is_empty(), true);
+        entries.insert(system, dtype, data);
+        let l = "AS G16  1994 07 14 20 59  0.000000  1   -0.123456789012E+00";
+        let (_, system, dtype, data) = build_record_entry(Version::new(3, 0), l)
+            .unwrap();
+        let g16 = Sv::from_str("G16").unwrap();
+        assert_eq!(system, System::Sv(g16));
+        assert_eq!(data.bias_sigma, None);
+        entries.insert(system, dtype, data);
+        assert_eq!(entries.stations().len(), 1);
+        assert_eq!(entries.satellites().len(), 1);
+        assert_eq!(entries.stations()["AREQ"][&DataType::Ar].bias_sigma, Some(-0.123456789012E+01));
+        assert_eq!(entries.satellites()[&g16][&DataType::As].bias, -0.123456789012E+00);
+        entries.satellites_mut().clear();
+        assert_eq!(entries.satellites().is_empty(), true);
+        assert_eq!(entries.is_empty(), false);
+    }
+    #[test]
+    fn test_truncated_entries() {
+        // truncated epoch
+        assert!(build_record_entry(Version::new(3, 0), "AR AREQ 1994 07 14").is_err());
+        // announced values are missing
+        let l = "AR AREQ 1994 07 14 20 59  0.000000  6   -0.123456789012E+00 -0.123456789012E+01";
+        assert!(build_record_entry(Version::new(3, 0), l).is_err());
+        assert!(build_record_entry(Version::new(3, 0), "").is_err());
+    }
+    #[test]
+    fn test_v3_04_entries() {
+        let l = "AR DGAR00GBR 2017 03 11 00 00  0.000000  2    0.371678253222E-07  0.179791429122E-10";
+        let (e, system, dtype, data) = build_record_entry(Version::new(3, 4), l)
+            .unwrap();
+        assert_eq!(system, System::Station(String::from("DGAR00GBR")));
+        assert_eq!(dtype, DataType::Ar);
+        assert_eq!(e.date, epoch::str2date("2017 03 11 00 00 00").unwrap());
+        assert_eq!(data.bias, 0.371678253222E-07);
+        assert_eq!(data.bias_sigma, Some(0.179791429122E-10));
+        let l = "AS G01       2017 03 11 00 00  0.000000  1    0.175309377613E-08";
+        let (_, system, _, _) = build_record_entry(Version::new(3, 4), l)
+            .unwrap();
+        assert_eq!(system, System::Sv(Sv::from_str("G01").unwrap()));
+    }
 }
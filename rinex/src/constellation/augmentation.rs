@@ -37,6 +37,28 @@ impl Default for Augmentation {
     }
 }
 
+impl Augmentation {
+    /// Identifies the augmentation system operating given SBAS
+    /// vehicule, from its full PRN # (120 to 158).
+    /// Returns None if this PRN is not assigned (or reserved).
+    /// Assignments evolve as GEO vehicules are replaced,
+    /// this table reflects the current assignments
+    pub fn from_prn (prn: u8) -> Option<Augmentation> {
+        match prn {
+            120 | 121 | 123 | 124 | 126 | 136 => Some(Self::EGNOS),
+            131 | 133 | 135 | 138 => Some(Self::WAAS),
+            129 | 137 => Some(Self::MSAS),
+            127 | 128 | 132 => Some(Self::GAGAN),
+            130 | 143 | 144 => Some(Self::BDSBAS),
+            134 => Some(Self::KASS),
+            125 | 140 | 141 => Some(Self::SDCM),
+            148 => Some(Self::ASBAS),
+            122 => Some(Self::SPAN),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(feature = "with-geo")]
 use std::str::FromStr;
 #[cfg(feature = "with-geo")]
@@ -4,7 +4,7 @@ pub mod augmentation;
 use augmentation::Augmentation;
 
 #[cfg(feature = "with-serde")]
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
 #[derive(Error, Debug)]
 /// Constellation parsing & identification related errors
@@ -16,11 +16,11 @@ pub enum Error {
 }
 
 /// Describes all known `GNSS` constellations
-/// when manipulating `RINEX`
+/// when manipulating `RINEX`.
+/// SBAS vehicules form a single system: SBAS constellations
+/// compare (and hash) equal whatever their augmentation system,
+/// which is an attribute of the vehicule, see [crate::sv::Sv::augmentation]
 #[derive(Clone, Copy, Debug)]
-#[derive(PartialEq, Eq)]
-#[derive(PartialOrd, Ord, Hash)]
-#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum Constellation {
     /// `GPS` american constellation,
     GPS,
@@ -41,6 +41,62 @@ pub enum Constellation {
     Mixed,
 }
 
+#[cfg(feature = "with-serde")]
+impl Serialize for Constellation {
+    /// Constellations are serialized as plain strings, so they can be used as map keys:
+    /// SBAS vehicules are described by their augmentation system ("EGNOS")
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::SBAS(sbas) => serializer.serialize_str(&format!("{:?}", sbas)),
+            _ => serializer.serialize_str(&format!("{:?}", self)),
+        }
+    }
+}
+
+#[cfg(feature = "with-serde")]
+impl<'de> Deserialize<'de> for Constellation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        if let Ok(sbas) = <Augmentation as std::str::FromStr>::from_str(&s) {
+            return Ok(Self::SBAS(sbas))
+        }
+        <Self as std::str::FromStr>::from_str(&s)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl PartialEq for Constellation {
+    fn eq (&self, rhs: &Self) -> bool {
+        self.rank() == rhs.rank()
+    }
+}
+
+impl Eq for Constellation {}
+
+impl std::hash::Hash for Constellation {
+    fn hash<H: std::hash::Hasher> (&self, state: &mut H) {
+        std::hash::Hash::hash(&self.rank(), state)
+    }
+}
+
+impl PartialOrd for Constellation {
+    fn partial_cmp (&self, rhs: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl Ord for Constellation {
+    fn cmp (&self, rhs: &Self) -> std::cmp::Ordering {
+        self.rank().cmp(&rhs.rank())
+    }
+}
+
 impl Default for Constellation {
     /// Builds a default `GNSS::GPS` constellation
     fn default() -> Constellation {
@@ -49,6 +105,13 @@ impl Default for Constellation {
 }
 
 impl Constellation {
+    /// Returns augmentation system, for SBAS constellations
+    pub fn augmentation (&self) -> Option<Augmentation> {
+        match self {
+            Self::SBAS(sbas) => Some(*sbas),
+            _ => None,
+        }
+    }
     /// Identifies `gnss` constellation from given 1 letter code.    
     /// Given code should match official RINEX codes.    
     /// This method is case insensitive though
@@ -150,6 +213,19 @@ impl Constellation {
             Err(Error::UnknownCode(code.to_string()))
         }
     }
+    /// Rank of this system, augmentation systems are not differentiated
+    fn rank (&self) -> u8 {
+        match self {
+            Self::GPS => 0,
+            Self::Glonass => 1,
+            Self::BeiDou => 2,
+            Self::QZSS => 3,
+            Self::Galileo => 4,
+            Self::SBAS(_) => 5,
+            Self::IRNSS => 6,
+            Self::Mixed => 7,
+        }
+    }
 }
 
 impl std::str::FromStr for Constellation {
@@ -215,4 +291,23 @@ mod tests {
         let c = Augmentation::from_str("WASS");
         assert_eq!(c.is_err(), true);
     }
+    #[test]
+    fn test_sbas() {
+        assert_eq!(Augmentation::from_prn(123), Some(Augmentation::EGNOS));
+        assert_eq!(Augmentation::from_prn(138), Some(Augmentation::WAAS));
+        assert_eq!(Augmentation::from_prn(137), Some(Augmentation::MSAS));
+        assert_eq!(Augmentation::from_prn(127), Some(Augmentation::GAGAN));
+        assert_eq!(Augmentation::from_prn(158), None);
+        assert_eq!(Augmentation::from_prn(12), None);
+        let egnos = Constellation::SBAS(Augmentation::EGNOS);
+        assert_eq!(egnos.augmentation(), Some(Augmentation::EGNOS));
+        assert_eq!(Constellation::GPS.augmentation(), None);
+        // SBAS form a single system
+        assert_eq!(egnos, Constellation::from_1_letter_code("S").unwrap());
+        assert_ne!(egnos, Constellation::GPS);
+        let mut set = std::collections::HashSet::new();
+        set.insert(egnos);
+        assert_eq!(set.contains(&Constellation::SBAS(Augmentation::WAAS)), true);
+        assert_eq!(Constellation::GPS < egnos, true);
+    }
 }
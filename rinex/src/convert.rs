@@ -0,0 +1,85 @@
+//! File conversion utilities
+use thiserror::Error;
+use crate::header;
+use crate::hatanaka;
+use crate::reader::BufferedReader;
+use std::io::{BufRead, Write};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("io error")]
+    IoError(#[from] std::io::Error),
+    #[error("failed to parse RINEX header")]
+    HeaderError(#[from] header::Error),
+    #[error("CRINEX decompression failed")]
+    HatanakaError(#[from] hatanaka::Error),
+}
+
+/// Decompresses `src` into a readable RINEX file `dst`.
+/// Both .gz (`with-gzip` feature) and .Z layers are supported,
+/// and CRINEX content is decompressed.
+/// Header fields are preserved as is: CRINEX specific fields are
+/// replaced by COMMENT lines describing the compression program and date.
+/// Content is streamed: the record is never loaded into memory.
+/// Example:
+/// ```
+/// use rinex::convert;
+/// let output = std::env::temp_dir().join("KMS300DNK_R_20221591000_01H_30S_MO.rnx");
+/// let output = output.to_str().unwrap();
+/// convert::decompress_file(
+///     "../test_resources/CRNX/V3/KMS300DNK_R_20221591000_01H_30S_MO.crx",
+///     output)
+///     .unwrap();
+/// let rinex = rinex::Rinex::from_file(output)
+///     .unwrap();
+/// assert_eq!(rinex.header.is_crinex(), false);
+/// let _ = std::fs::remove_file(output);
+/// ```
+pub fn decompress_file (src: &str, dst: &str) -> Result<(), Error> {
+    let mut reader = BufferedReader::new(src)?;
+    let header = header::Header::new(&mut reader)?;
+    let crinex = header.obs
+        .as_ref()
+        .and_then(|obs| obs.crinex.as_ref());
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(dst)?);
+    // header section: copied as is, except CRINEX fields.
+    // Header fields are never compressed
+    for line in BufferedReader::new(src)?.lines() {
+        let line = line?;
+        if line.contains("CRINEX VERS   / TYPE") || line.contains("CRINEX PROG / DATE") {
+            continue
+        }
+        writeln!(writer, "{}", line)?;
+        if line.contains("PGM / RUN BY / DATE") {
+            if let Some(crinex) = crinex {
+                let comment = format!("CRINEX {}.{} decompressed, compressed by {}",
+                    crinex.version.major, crinex.version.minor, crinex.prog.trim());
+                writeln!(writer, "{:<60}COMMENT", comment)?;
+                let comment = format!("CRINEX compression date {}",
+                    crinex.date.format("%Y-%m-%d %H:%M"));
+                writeln!(writer, "{:<60}COMMENT", comment)?;
+            }
+        }
+        if line.contains("END OF HEADER") {
+            break
+        }
+    }
+    // record section
+    let mut decompressor = hatanaka::Decompressor::new(8); // M = 8 is more than enough
+    for line in reader.lines() {
+        let line = line?;
+        match crinex {
+            Some(_) => {
+                // decompressor works on several lines at once,
+                // we iterate one line at a time: add the \n terminator
+                let mut content = line;
+                content.push('\n');
+                let recovered = decompressor.decompress(&header, &content)?;
+                write!(writer, "{}", recovered)?
+            },
+            None => writeln!(writer, "{}", line)?,
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
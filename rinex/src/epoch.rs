@@ -1,8 +1,9 @@
 //! `Epoch` is an observation timestamp with
 //! a `flag` associated to it
 use thiserror::Error;
-use std::str::FromStr;
-use chrono::{Datelike,Timelike};
+use chrono::Timelike;
+use crate::leap;
+use crate::gnss_time;
 
 #[cfg(feature = "with-serde")]
 use serde::{Serialize, Deserialize};
@@ -69,11 +70,14 @@ impl std::fmt::Display for EpochFlag {
 }
 
 /// An `Epoch` is an observation timestamp associated
-/// to an `EpochFlag`
+/// to an `EpochFlag`. Epochs are sorted chronologically,
+/// then by flag value: at a given date, the sane epoch (0)
+/// comes first, followed by events (1 to 6)
 #[derive(Copy, Clone, Debug)]
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct Epoch {
-    /// `date`: sampling time stamp
+    /// `date`: sampling time stamp, with nanosecond precision:
+    /// fractional seconds of epoch descriptors are preserved
     pub date: chrono::NaiveDateTime,
     /// `flag` validates or not this particular `epoch`
     pub flag: EpochFlag,
@@ -87,31 +91,108 @@ impl Serialize for Epoch {
         S: serde::Serializer,
     {
         let s = format!("{} {}", 
-            self.date.format("%Y-%m-%d %H:%M:%S"),
+            self.date.format("%Y-%m-%d %H:%M:%S%.f"),
             self.flag.to_string());
         serializer.serialize_str(&s)
     }
 }
 
-/*impl std::fmt::Display for Epoch {
+#[cfg(feature = "with-serde")]
+impl<'de> Deserialize<'de> for Epoch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let (date, flag) = s.trim().rsplit_once(' ')
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid epoch \"{}\"", s)))?;
+        let date = chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S%.f")
+            .map_err(serde::de::Error::custom)?;
+        let flag = flag.parse::<EpochFlag>()
+            .map_err(serde::de::Error::custom)?;
+        Ok(Self { date, flag })
+    }
+}
+
+impl std::cmp::Ord for Epoch {
+    fn cmp (&self, other: &Self) -> std::cmp::Ordering {
+        self.date.cmp(&other.date)
+            .then_with(|| self.flag.cmp(&other.flag))
+    }
+}
+
+impl std::cmp::PartialOrd for Epoch {
+    fn partial_cmp (&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Epoch {
+    /// Formats self as in V3 epoch descriptors: "2022 06 08 10 00 30.5000000  0",
+    /// or as in V2 epoch descriptors with the alternate flag (`{:#}`):
+    /// "22 06 08 10 00 30.5000000  0"
     fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        f.write_str("salut")
+        let seconds = self.date.time().second() as f64
+            + self.date.time().nanosecond() as f64 / 1.0E9;
+        if f.alternate() {
+            write!(f, "{}", self.date.format("%y %m %d %H %M"))?;
+        } else {
+            write!(f, "{}", self.date.format("%Y %m %d %H %M"))?;
+        }
+        write!(f, "{:11.7}  {}", seconds, self.flag)
+    }
+}
+
+impl std::ops::Add<chrono::Duration> for Epoch {
+    type Output = Epoch;
+    fn add (self, rhs: chrono::Duration) -> Epoch {
+        Epoch {
+            date: self.date + rhs,
+            flag: self.flag,
+        }
+    }
+}
+
+impl std::ops::AddAssign<chrono::Duration> for Epoch {
+    fn add_assign (&mut self, rhs: chrono::Duration) {
+        self.date = self.date + rhs
+    }
+}
+
+impl std::ops::Sub<chrono::Duration> for Epoch {
+    type Output = Epoch;
+    fn sub (self, rhs: chrono::Duration) -> Epoch {
+        Epoch {
+            date: self.date - rhs,
+            flag: self.flag,
+        }
+    }
+}
+
+impl std::ops::SubAssign<chrono::Duration> for Epoch {
+    fn sub_assign (&mut self, rhs: chrono::Duration) {
+        self.date = self.date - rhs
+    }
+}
+
+impl std::ops::Sub<Epoch> for Epoch {
+    type Output = chrono::Duration;
+    /// Returns the duration between two epochs, flags are ignored
+    fn sub (self, rhs: Epoch) -> chrono::Duration {
+        self.date - rhs.date
     }
-}*/
+}
 
 impl Default for Epoch {
     fn default() -> Epoch {
-        let now = chrono::Utc::now();
+        let now = now();
         Epoch {
             flag: EpochFlag::default(),
-            date: chrono::NaiveDate::from_ymd(
-                now.naive_utc().date().year(),
-                now.naive_utc().date().month(),
-                now.naive_utc().date().day())
-                    .and_hms(
-                        now.time().hour(),
-                        now.time().minute(),
-                        now.time().second())
+            date: now.date()
+                .and_hms(
+                    now.time().hour(),
+                    now.time().minute(),
+                    now.time().second())
         }
     }
 }
@@ -125,7 +206,123 @@ impl Epoch {
             flag,
         }
     }
-    pub fn to_string (&self) -> &str { "hello" }
+    /// Rounds self to the nearest multiple of `interval`,
+    /// counted from 1970-01-01 00:00:00. Flag is preserved.
+    /// Self is returned as is, for null or negative intervals.
+    /// ```
+    /// use rinex::epoch::{Epoch, EpochFlag};
+    /// let date = chrono::NaiveDate::from_ymd(2022, 6, 8).and_hms_milli(10, 0, 44, 900);
+    /// let epoch = Epoch::new(date, EpochFlag::Ok);
+    /// let rounded = epoch.round_to(chrono::Duration::seconds(30));
+    /// assert_eq!(rounded.date, chrono::NaiveDate::from_ymd(2022, 6, 8).and_hms(10, 0, 30));
+    /// let rounded = epoch.round_to(chrono::Duration::seconds(1));
+    /// assert_eq!(rounded.date, chrono::NaiveDate::from_ymd(2022, 6, 8).and_hms(10, 0, 45));
+    /// ```
+    pub fn round_to (&self, interval: chrono::Duration) -> Epoch {
+        match self.remainder(interval) {
+            Some((rem, interval)) => {
+                if 2 * rem >= interval {
+                    *self + chrono::Duration::nanoseconds(interval - rem)
+                } else {
+                    *self - chrono::Duration::nanoseconds(rem)
+                }
+            },
+            None => *self,
+        }
+    }
+    /// Rounds self down to a multiple of `interval`,
+    /// counted from 1970-01-01 00:00:00. Flag is preserved.
+    /// Self is returned as is, for null or negative intervals.
+    pub fn floor_to (&self, interval: chrono::Duration) -> Epoch {
+        match self.remainder(interval) {
+            Some((rem, _)) => *self - chrono::Duration::nanoseconds(rem),
+            None => *self,
+        }
+    }
+    /// Returns (self % interval, interval) in nanoseconds
+    fn remainder (&self, interval: chrono::Duration) -> Option<(i64, i64)> {
+        let interval = interval.num_nanoseconds()
+            .filter(|ns| *ns > 0)?;
+        let elapsed = (self.date - chrono::NaiveDateTime::from_timestamp(0, 0))
+            .num_nanoseconds()?;
+        Some((elapsed.rem_euclid(interval), interval))
+    }
+    /// Converts this `Epoch`, expressed in GPST, to UTC.
+    /// `leap` is the LEAP SECONDS header field: when it is not provided,
+    /// the built-in leap second history is used.
+    /// Epochs prior GPS time origin are left untouched.
+    /// ```
+    /// use rinex::epoch::{Epoch, EpochFlag};
+    /// let gpst = chrono::NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+    /// let epoch = Epoch::new(gpst, EpochFlag::Ok);
+    /// let utc = epoch.to_utc(None);
+    /// assert_eq!(utc.date, chrono::NaiveDate::from_ymd(2020, 12, 31).and_hms(23, 59, 42));
+    /// ```
+    pub fn to_utc (&self, leap: Option<&leap::Leap>) -> Epoch {
+        let leap = match leap {
+            Some(leap) => Some(gnss_time::gpst_utc_leap(leap)),
+            None => leap::gpst_utc_from_gpst(self.date),
+        };
+        Epoch {
+            date: self.date - chrono::Duration::seconds(leap.unwrap_or(0) as i64),
+            flag: self.flag,
+        }
+    }
+    /// Converts this `Epoch`, expressed in UTC, to GPST.
+    /// `leap` is the LEAP SECONDS header field: when it is not provided,
+    /// the built-in leap second history is used.
+    /// Epochs prior GPS time origin are left untouched.
+    /// ```
+    /// use rinex::epoch::{Epoch, EpochFlag};
+    /// let utc = chrono::NaiveDate::from_ymd(2020, 12, 31).and_hms(23, 59, 42);
+    /// let epoch = Epoch::new(utc, EpochFlag::Ok);
+    /// let gpst = epoch.to_gpst(None);
+    /// assert_eq!(gpst.date, chrono::NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0));
+    /// ```
+    pub fn to_gpst (&self, leap: Option<&leap::Leap>) -> Epoch {
+        let leap = match leap {
+            Some(leap) => Some(gnss_time::gpst_utc_leap(leap)),
+            None => leap::gpst_utc(self.date),
+        };
+        Epoch {
+            date: self.date + chrono::Duration::seconds(leap.unwrap_or(0) as i64),
+            flag: self.flag,
+        }
+    }
+}
+
+/// Returns current date and time, expressed in UTC.   
+/// This is the only sanctioned way to obtain "now" in this library:
+/// header dates (PGM / RUN BY / DATE), CRINEX compression dates
+/// and processing markers are always expressed in UTC.
+/// `chrono` local time conversions are prohibited, see clippy.toml
+/// On `wasm32-unknown-unknown`, there is no system clock available
+/// and this returns 1970-01-01 00:00:00 instead of panicking
+#[allow(clippy::disallowed_methods)]
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn now() -> chrono::NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub fn now() -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::from_timestamp(0, 0)
+}
+
+/// Returns the offset, in minutes, between given `epoch` and `reference`,
+/// if it looks like a local time conversion: non null, multiple of 15'
+/// (some timezones are offset by 30' or 45') and within ±14h.
+/// Used in debug builds, to verify record epochs are expressed
+/// in the time system declared in the header
+#[cfg(debug_assertions)]
+pub(crate) fn local_time_offset (epoch: &chrono::NaiveDateTime, reference: &chrono::NaiveDateTime) -> Option<i64> {
+    let dt = (*epoch - *reference).num_minutes();
+    if *epoch - *reference == chrono::Duration::minutes(dt) 
+        && dt != 0 && dt % 15 == 0 && dt.abs() <= 14 * 60 {
+        Some(dt)
+    } else {
+        None
+    }
 }
 
 #[derive(Error, Debug)]
@@ -137,28 +334,48 @@ pub enum ParseDateError {
     ParseFloatError(#[from] std::num::ParseFloatError),
     #[error("failed to parse y/m/d h:m fields")]
     ParseIntError(#[from] std::num::ParseIntError),
+    #[error("invalid date")]
+    InvalidDate,
+}
+
+/// Parses "ss.sssssssss" seconds field into (seconds, nanoseconds),
+/// without floating point conversion, so the fractional part is preserved.
+/// Digits beyond nanosecond precision are dropped
+fn parse_seconds (s: &str) -> Result<(u32, u32), ParseDateError> {
+    match s.split_once('.') {
+        Some((integer, fraction)) => {
+            let secs = match integer.is_empty() {
+                true => 0,
+                false => u32::from_str_radix(integer, 10)?,
+            };
+            let digits: String = fraction.chars()
+                .take(9)
+                .collect();
+            let nanos = match digits.is_empty() {
+                true => 0,
+                false => u32::from_str_radix(&format!("{:0<9}", digits), 10)?,
+            };
+            Ok((secs, nanos))
+        },
+        None => Ok((u32::from_str_radix(s, 10)?, 0)),
+    }
 }
 
 /// Builds an `epoch.date` field from "yyyy mm dd hh mm ss.sssss"
-/// content, as generally found in `RINEX` epoch descriptors
+/// content, as generally found in `RINEX` epoch descriptors.
+/// Fractional seconds are preserved, down to the nanosecond
 pub fn str2date (s: &str) -> Result<chrono::NaiveDateTime, ParseDateError> {
     let items : Vec<&str> = s.split_ascii_whitespace().collect();
     if items.len() != 6 {
         return Err(ParseDateError::FormatMismatch)
     }
-    let mut secs: u32 = 0;
     let (mut y,m,d,h,min) : (i32,u32,u32,u32,u32) =
         (i32::from_str_radix(items[0],10)?,
          u32::from_str_radix(items[1],10)?,
          u32::from_str_radix(items[2],10)?,
          u32::from_str_radix(items[3],10)?,
          u32::from_str_radix(items[4],10)?);
-    if let Ok(s) = f64::from_str(items[5].trim()) {
-        secs = s as u32
-    }
-    else if let Ok(s) = u32::from_str_radix(items[5].trim(), 10) {
-        secs = s 
-    }
+    let (secs, nanos) = parse_seconds(items[5].trim())?;
 	if y < 100 { // 2 digit nb case
     	if y > 90 { // old rinex
         	y += 1900
@@ -166,13 +383,58 @@ pub fn str2date (s: &str) -> Result<chrono::NaiveDateTime, ParseDateError> {
 			y += 2000
 		}
 	}
-    Ok(chrono::NaiveDate::from_ymd(y,m,d)
-        .and_hms(h,min,secs))
+    chrono::NaiveDate::from_ymd_opt(y,m,d)
+        .and_then(|date| date.and_hms_nano_opt(h,min,secs,nanos))
+        .ok_or(ParseDateError::InvalidDate)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use chrono::Datelike;
+    #[test]
+    fn test_now() {
+        #[allow(clippy::disallowed_methods)]
+        let reference = chrono::Utc::now().naive_utc();
+        let dt = now() - reference;
+        assert_eq!(dt.num_seconds().abs() < 2, true);
+    }
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_local_time_offset() {
+        let reference = str2date("2022 01 01 00 00 00").unwrap();
+        let epoch = str2date("2022 01 01 02 00 00").unwrap();
+        assert_eq!(local_time_offset(&epoch, &reference), Some(120));
+        let epoch = str2date("2021 12 31 18 30 00").unwrap();
+        assert_eq!(local_time_offset(&epoch, &reference), Some(-330));
+        assert_eq!(local_time_offset(&reference, &reference), None);
+        let epoch = str2date("2022 01 01 00 00 30").unwrap();
+        assert_eq!(local_time_offset(&epoch, &reference), None);
+        let epoch = str2date("2022 01 02 00 00 00").unwrap();
+        assert_eq!(local_time_offset(&epoch, &reference), None);
+    }
+    #[test]
+    fn test_epoch() {
+        let e0 = Epoch::new(str2date("2022 06 08 10 00 30.5").unwrap(), EpochFlag::PowerFailure);
+        assert_eq!(e0.to_string(), "2022 06 08 10 00 30.5000000  1");
+        assert_eq!(format!("{:#}", e0), "22 06 08 10 00 30.5000000  1");
+        let e1 = e0 + chrono::Duration::seconds(30);
+        assert_eq!(e1.date, str2date("2022 06 08 10 01 00.5").unwrap());
+        assert_eq!(e1.flag, EpochFlag::PowerFailure);
+        assert_eq!(e1 - e0, chrono::Duration::seconds(30));
+        assert_eq!(e1 - chrono::Duration::seconds(30), e0);
+        let mut e2 = e0;
+        e2 += chrono::Duration::milliseconds(500);
+        assert_eq!(e2.floor_to(chrono::Duration::minutes(1)).date, str2date("2022 06 08 10 00 00").unwrap());
+        assert_eq!(e2.round_to(chrono::Duration::minutes(1)).date, str2date("2022 06 08 10 01 00").unwrap());
+        assert_eq!(e2.round_to(chrono::Duration::zero()), e2);
+        // chronological order first, then flags
+        let ok = Epoch::new(e0.date, EpochFlag::Ok);
+        assert!(ok < e0);
+        let event = Epoch::new(e0.date - chrono::Duration::seconds(1), EpochFlag::ExternalEvent);
+        assert!(event < ok);
+        assert!(e0 < e1);
+    }
     #[test]
     fn test_str2date() {
         assert_eq!(str2date("22 01 01 00 00 00").is_ok(), true);
@@ -196,5 +458,15 @@ mod test {
         assert_eq!(date.time().hour(), 13);
         assert_eq!(date.time().minute(), 0);
         assert_eq!(date.time().second(), 0);
+
+        // fractional seconds are preserved
+        let date = str2date("2022 03 04 00 00 30.5000000").unwrap();
+        assert_eq!(date.time().second(), 30);
+        assert_eq!(date.time().nanosecond(), 500_000_000);
+        let date = str2date(" 17  1  1  0  0  0.1234567891").unwrap();
+        assert_eq!(date.time().nanosecond(), 123_456_789);
+        let date = str2date("2022 03 04 00 00 .25").unwrap();
+        assert_eq!(date.time().nanosecond(), 250_000_000);
+        assert_eq!(str2date("2022 03 04 00 00 3a.0").is_err(), true);
     }
 }
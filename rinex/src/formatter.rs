@@ -4,22 +4,45 @@ pub mod point3d {
     where
         S: serde::Serializer,
     {
-        let p = point3d.as_ref().unwrap_or(
-            &rust_3d::Point3D {
-                x: 0.0_f64,
-                y: 0.0_f64,
-                z: 0.0_f64,
-            }
-        );
-        let s = format!("{},{},{}",p.x,p.y,p.z); 
-        serializer.serialize_str(&s)
+        match point3d {
+            Some(p) => {
+                let s = format!("{},{},{}",p.x,p.y,p.z); 
+                serializer.serialize_some(&s)
+            },
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<rust_3d::Point3D>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::Deserialize;
+        let s: Option<String> = Option::deserialize(deserializer)?;
+        match s {
+            Some(s) => {
+                let xyz: Vec<f64> = s.split(',')
+                    .map(|c| c.trim().parse::<f64>())
+                    .collect::<Result<_, _>>()
+                    .map_err(serde::de::Error::custom)?;
+                if xyz.len() != 3 {
+                    return Err(serde::de::Error::custom(format!("invalid 3D point \"{}\"", s)))
+                }
+                Ok(Some(rust_3d::Point3D {
+                    x: xyz[0],
+                    y: xyz[1],
+                    z: xyz[2],
+                }))
+            },
+            None => Ok(None),
+        }
     }
 }
 
 
 #[cfg(feature = "with-serde")]
 pub mod datetime {
-    use serde::{Serializer};
+    use serde::{Serializer, Deserializer, Deserialize};
     pub fn serialize<S>(datetime: &chrono::NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -28,11 +51,12 @@ pub mod datetime {
         serializer.serialize_str(&s)
     }
 
-    /*pub fn deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<chrono::NaiveDateTime, D::Error>
     where
         D: Deserializer<'de>, 
     {
         let s = String::deserialize(deserializer)?;
-        chrono::NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")?
-    }*/
+        chrono::NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+            .map_err(serde::de::Error::custom)
+    }
 }
@@ -1,269 +1,289 @@
-//! This module provides the `GnssTime` structure that attaches
-//! a `GNSS` constellation to the `chrono::naiveDateTime` structure.    
-//! This constellation is the time system that produced the given datetime
+//! GNSS time scales, and conversions between them.
+//! Time scales are related to one another by a nominal (integer) offset,
+//! refined by the `TIME SYSTEM CORR` parameters broadcasted in NAV files
 use thiserror::Error;
-use crate::constellation;
-use chrono::{Timelike, Datelike};
+use crate::leap::{self, Leap};
+use crate::constellation::Constellation;
+use crate::navigation::TimeSystemCorrection;
+use crate::observation::TimeSystem;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum Error {
-    #[error("invalid time system \"{0}\"")]
-    InvalidTimeSystem(String),
-    #[error("unknown augmentation system \"{0}\"")]
-    UnknownAugmentationSystem(String),
-    #[error("time system mismatch")]
-    CorrectionTimeSystemError,
-    #[error("reference time system is not the expected")]
-    CorrectionTimeReferenceError,
+    #[error("unknown time scale \"{0}\"")]
+    UnknownTimeScale(String),
+    #[error("leap seconds must be known to convert {0} to {1}")]
+    MissingLeapSeconds(TimeScale, TimeScale),
 }
 
-/// GnssTime struct is a time realization,
-/// tied to the related `GNSS` constellation producing
-/// that realization
-#[derive(Debug)]
-#[allow(dead_code)]
-pub struct GnssTime {
-    time: chrono::NaiveDateTime,
-    gnss: constellation::Constellation,
+/// Time scales of GNSS constellations, and UTC
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum TimeScale {
+    /// GPS time
+    GPST,
+    /// Galileo system time
+    GST,
+    /// BeiDou time
+    BDT,
+    /// Glonass time: UTC(SU) + 3h
+    GLONASST,
+    /// QZSS time
+    QZSST,
+    /// IRNSS time
+    IRNSST,
+    /// Universal Coordinated Time
+    UTC,
 }
 
-impl Default for GnssTime {
-    /// Builds default `GnssTime` structure
-    fn default() -> GnssTime {
-        let now = chrono::Utc::now();
-        GnssTime {
-            time: chrono::NaiveDate::from_ymd(
-                now.date().year(),
-                now.date().month(),
-                now.date().day(),
-            ).and_hms(
-                now.time().hour(),
-                now.time().minute(),
-                now.time().second()
-            ),
-            gnss: constellation::Constellation::default(),
+impl std::fmt::Display for TimeScale {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::GPST => f.write_str("GPST"),
+            Self::GST => f.write_str("GST"),
+            Self::BDT => f.write_str("BDT"),
+            Self::GLONASST => f.write_str("GLONASST"),
+            Self::QZSST => f.write_str("QZSST"),
+            Self::IRNSST => f.write_str("IRNSST"),
+            Self::UTC => f.write_str("UTC"),
         }
     }
 }
 
-impl GnssTime {
-    /// Builds a new `GnssTime` realization
-    pub fn new(time: chrono::NaiveDateTime, gnss: constellation::Constellation) -> GnssTime {
-        GnssTime {
-            time, 
-            gnss
+impl std::str::FromStr for TimeScale {
+    type Err = Error;
+    fn from_str (s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "GPS" | "GPST" => Ok(Self::GPST),
+            "GAL" | "GST" => Ok(Self::GST),
+            "BDS" | "BDT" => Ok(Self::BDT),
+            "GLO" | "GLONASST" => Ok(Self::GLONASST),
+            "QZS" | "QZSST" => Ok(Self::QZSST),
+            "IRN" | "IRNSST" => Ok(Self::IRNSST),
+            "UTC" => Ok(Self::UTC),
+            _ => Err(Error::UnknownTimeScale(s.to_string())),
         }
     }
+}
 
-    /// Corrects self to given reference using given correction parameters    
-    /// correction: correction to be applied   
-    /// reference: reference time (must match expected reference)   
-    /// TODO: refer to p39
-    pub fn correct (&mut self, correction: &GnssTimeCorrection, reference: &GnssTime) -> Result<(), Error> {
-        // check this is the expected reference time
-        match correction.corr_type {
-            TimeCorrectionType::GPUT => {
-                // check time system matches the expected one
-                match self.gnss {
-                    constellation::Constellation::GPS => {},
-                    _ => return Err(Error::CorrectionTimeSystemError),
-                }
-            },
-            TimeCorrectionType::GAUT => {
-                // check time system matches the expected one
-                match self.gnss {
-                    constellation::Constellation::Galileo => {},
-                    _ => return Err(Error::CorrectionTimeSystemError),
-                }
-            },
-            TimeCorrectionType::SBUT => {
-                // check time system matches the expected one
-                match self.gnss {
-                    constellation::Constellation::Sbas => {},
-                    _ => return Err(Error::CorrectionTimeSystemError),
-                }
-            },
-            TimeCorrectionType::GLUT => {
-                // check time system matches the expected one
-                match self.gnss {
-                    constellation::Constellation::Glonass => {},
-                    _ => return Err(Error::CorrectionTimeSystemError),
-                }
-            },
-            TimeCorrectionType::GPGA => {
-                if reference.gnss != constellation::Constellation::Galileo {
-                    return Err(Error::CorrectionTimeReferenceError)
-                }
-                // check time system matches the expected one
-                match self.gnss {
-                    constellation::Constellation::GPS => {},
-                    _ => return Err(Error::CorrectionTimeSystemError),
-                }
-            },
-            TimeCorrectionType::GLGP => {
-                if reference.gnss != constellation::Constellation::GPS {
-                    return Err(Error::CorrectionTimeReferenceError)
-                }
-                // check time system matches the expected one
-                match self.gnss {
-                    constellation::Constellation::Glonass => {},
-                    _ => return Err(Error::CorrectionTimeSystemError),
-                }
-            },
-            TimeCorrectionType::GZUT => {
-                // check time system matches the expected one
-                match self.gnss {
-                    constellation::Constellation::QZSS => {},
-                    _ => return Err(Error::CorrectionTimeSystemError),
-                }
-            },
+impl From<TimeSystem> for TimeScale {
+    fn from (system: TimeSystem) -> Self {
+        match system {
+            TimeSystem::GPS => Self::GPST,
+            TimeSystem::GLO => Self::GLONASST,
+            TimeSystem::GAL => Self::GST,
+            TimeSystem::QZS => Self::QZSST,
+            TimeSystem::BDT => Self::BDT,
+            TimeSystem::IRN => Self::IRNSST,
+            TimeSystem::UTC => Self::UTC,
         }
-        Ok(())
     }
 }
 
-/// List of known correction types:     
-/// GPUT: GPS->UTC  (a0,a1)     
-/// GAUT: GAL->UTC  (a0,a1)     
-/// SBUT: SBAS->UTC (a0,a1)     
-/// GLUT: GLO->UTC  a0=τ(c) a1=0    
-/// GPGA: GPS->GAL  a0=a0g  a1=a1g   
-/// GLGP: GLO->GPS  a0=τ(gps) a1=zero   
-/// GZUT: QZS->UTC  a0,a1
-pub enum TimeCorrectionType {
-    GPUT,
-    GAUT,
-    SBUT,
-    GLUT,
-    GPGA,
-    GLGP,
-    GZUT,
-}
-
-impl std::str::FromStr for TimeCorrectionType {
-    type Err = Error;
-    fn from_str (s: &str) -> Result<Self, Self::Err> {
-        if s.eq("GPUT") {
-            Ok(TimeCorrectionType::GPUT)
-        } else if s.eq("GAUT") {
-            Ok(TimeCorrectionType::GAUT)
-        } else if s.eq("SBUT") {
-            Ok(TimeCorrectionType::SBUT)
-        } else if s.eq("GLUT") {
-            Ok(TimeCorrectionType::GLUT)
-        } else if s.eq("GPGA") {
-            Ok(TimeCorrectionType::GPGA)
-        } else if s.eq("GLGP") {
-            Ok(TimeCorrectionType::GLGP)
-        } else if s.eq("GZUT") {
-            Ok(TimeCorrectionType::GZUT)
-        } else {
-            Err(Error::InvalidTimeSystem(s.to_string()))
+impl TimeScale {
+    /// Returns the time scale of given constellation,
+    /// SBAS is aligned to GPST. Returns None for `Mixed`
+    pub fn from_constellation (constellation: Constellation) -> Option<Self> {
+        match constellation {
+            Constellation::GPS => Some(Self::GPST),
+            Constellation::Galileo => Some(Self::GST),
+            Constellation::BeiDou => Some(Self::BDT),
+            Constellation::Glonass => Some(Self::GLONASST),
+            Constellation::QZSS => Some(Self::QZSST),
+            Constellation::IRNSS => Some(Self::IRNSST),
+            Constellation::SBAS(_) => Some(Self::GPST),
+            _ => None,
         }
     }
-}
-
-/// Describes known UTC providers 
-/// (laboratories)
-pub enum UtcProvider {
-    Unknown,
-    NIST,
-    USNO,
-    SU,
-    BIPM,
-    EuropeLab,
-    CRL,
-}
-
-impl std::str::FromStr for UtcProvider {
-    type Err = Error;
-    fn from_str (s: &str) -> Result<Self, Self::Err> {
-        if s.eq("1") {
-            Ok(UtcProvider::NIST)
-        } else if s.eq("2") {
-            Ok(UtcProvider::USNO)
-        } else if s.eq("3") {
-            Ok(UtcProvider::SU)
-        } else if s.eq("4") {
-            Ok(UtcProvider::BIPM)
-        } else if s.eq("5") {
-            Ok(UtcProvider::EuropeLab)
-        } else if s.eq("6") {
-            Ok(UtcProvider::CRL)
+    /// Identifies time scale from its `TIME SYSTEM CORR` 2 letter code
+    fn from_correction_code (code: &str) -> Option<Self> {
+        match code {
+            "GP" => Some(Self::GPST),
+            "GA" => Some(Self::GST),
+            "BD" => Some(Self::BDT),
+            "GL" => Some(Self::GLONASST),
+            "QZ" => Some(Self::QZSST),
+            "IR" => Some(Self::IRNSST),
+            "UT" => Some(Self::UTC),
+            _ => None,
+        }
+    }
+    /// Returns true if this time scale is affected by leap seconds
+    fn is_utc_based (&self) -> bool {
+        matches!(self, Self::UTC | Self::GLONASST)
+    }
+    /// Nominal offset (self - GPST) in seconds,
+    /// `leap` being the GPST - UTC offset
+    fn nominal_offset (&self, leap: u32) -> f64 {
+        match self {
+            Self::GPST | Self::GST | Self::QZSST | Self::IRNSST => 0.0,
+            Self::BDT => -14.0,
+            Self::UTC => -(leap as f64),
+            Self::GLONASST => 10800.0 - leap as f64,
+        }
+    }
+    /// Returns GPST - UTC offset from the built-in leap second history,
+    /// at given date expressed in this time scale
+    pub(crate) fn builtin_leap (&self, date: chrono::NaiveDateTime) -> Option<u32> {
+        let offset = chrono::Duration::seconds(self.nominal_offset(0) as i64);
+        if self.is_utc_based() {
+            // history is indexed by UTC dates
+            leap::gpst_utc(date - offset)
         } else {
-            Ok(UtcProvider::Unknown)
+            leap::gpst_utc_from_gpst(date - offset)
+        }
+    }
+    /// Origin of the week counter of this time scale
+    fn week_origin (&self) -> chrono::NaiveDateTime {
+        match self {
+            Self::BDT => chrono::NaiveDate::from_ymd(2006, 1, 1).and_hms(0, 0, 0),
+            _ => chrono::NaiveDate::from_ymd(1980, 1, 6).and_hms(0, 0, 0),
         }
     }
 }
 
-/// Not documented ?
-pub enum AugmentationSystem {
-    EGNOS,
-    WAAS,
-    MSAS,
+/// Returns the GPST - UTC offset described by given `LEAP SECONDS` field,
+/// which is expressed with respect to BDT in BeiDou files
+pub(crate) fn gpst_utc_leap (leap: &Leap) -> u32 {
+    match leap.system {
+        Some(Constellation::BeiDou) => leap.leap + 14,
+        _ => leap.leap,
+    }
 }
 
-impl std::str::FromStr for AugmentationSystem {
-    type Err = Error;
-    /// Builds `AugmentationSystem` from string
-    fn from_str (s: &str) -> Result<Self, Self::Err> {
-        if s.eq("EGNOS") {
-            Ok(AugmentationSystem::EGNOS)
-        } else if s.eq("WAAS") {
-            Ok(AugmentationSystem::WAAS)
-        } else if s.eq("MSAS") {
-            Ok(AugmentationSystem::MSAS)
+/// Evaluates (from - to) fine correction at given date, expressed in `from`,
+/// if it is described in given list of corrections
+fn fine_correction (date: chrono::NaiveDateTime, from: TimeScale, to: TimeScale, corrections: &[TimeSystemCorrection]) -> Option<f64> {
+    for corr in corrections.iter() {
+        let (xx, yy) = match (corr.system.get(0..2), corr.system.get(2..4)) {
+            (Some(xx), Some(yy)) => (xx, yy),
+            _ => continue,
+        };
+        let (xx, yy) = match (TimeScale::from_correction_code(xx), TimeScale::from_correction_code(yy)) {
+            (Some(xx), Some(yy)) => (xx, yy),
+            _ => continue,
+        };
+        let sign = if xx == from && yy == to {
+            1.0
+        } else if xx == to && yy == from {
+            -1.0
         } else {
-            Err(Error::UnknownAugmentationSystem(s.to_string()))
-        }
+            continue
+        };
+        let reference = match (corr.epoch, corr.reference) {
+            (Some(epoch), _) => Some(epoch.date),
+            (None, Some((week, seconds))) => Some(xx.week_origin()
+                + chrono::Duration::weeks(week as i64)
+                + chrono::Duration::seconds(seconds as i64)),
+            _ => None,
+        };
+        let dt = match reference {
+            Some(reference) => (date - reference).num_nanoseconds()
+                .map(|ns| ns as f64 * 1.0E-9)
+                .unwrap_or(0.0),
+            None => 0.0,
+        };
+        let (a0, a1, a2) = corr.a;
+        return Some(sign * (a0 + a1 * dt + a2 * dt * dt))
     }
+    None
 }
 
-/// `GnssTimeCorrection` describes
-/// GNSS Time System corrections.   
-/// `system` : XXYY: XX corrected to YY   
-/// (a0, a1): correction params ((s), (s.s⁻¹))    
-/// delta_t: correction param   
-/// week: week number counter   
-/// `augmentation system`: (EGNOS,WAAS,MSAS)   
-/// utc_provider: provider identifier
-#[allow(dead_code)]
-pub struct GnssTimeCorrection {
-    corr_type: TimeCorrectionType,
-    params: (f64,f64),
-    delta_t: u32,
-    week: u32,
-    augmentation: Option<AugmentationSystem>,
-    utc_provider: Option<UtcProvider>,
+/// Returns (from - to) offset in seconds, at given date expressed in `from`.
+/// `leap` is the GPST - UTC offset, required when UTC based scales are involved.
+/// Fine corrections are applied when described in `corrections`,
+/// either directly or through GPST, otherwise nominal offsets are used
+pub fn offset (date: chrono::NaiveDateTime, from: TimeScale, to: TimeScale, leap: Option<u32>, corrections: &[TimeSystemCorrection]) -> Result<f64, Error> {
+    if from == to {
+        return Ok(0.0)
+    }
+    let leap = match leap {
+        Some(leap) => leap,
+        None => {
+            if from.is_utc_based() || to.is_utc_based() {
+                return Err(Error::MissingLeapSeconds(from, to))
+            }
+            0
+        },
+    };
+    let nominal = from.nominal_offset(leap) - to.nominal_offset(leap);
+    let fine = match fine_correction(date, from, to, corrections) {
+        Some(fine) => fine,
+        None => {
+            fine_correction(date, from, TimeScale::GPST, corrections).unwrap_or(0.0)
+            + fine_correction(date, TimeScale::GPST, to, corrections).unwrap_or(0.0)
+        },
+    };
+    Ok(nominal + fine)
 }
 
-impl Default for GnssTimeCorrection {
-    fn default() -> GnssTimeCorrection {
-        GnssTimeCorrection {
-            corr_type: TimeCorrectionType::GPUT,
-            params: (0.0_f64, 0.0_f64),
-            delta_t: 0,
-            week: 0,
-            augmentation: None,
-            utc_provider: None,
-        }
-    }
+/// Converts given date, expressed in `from` time scale, to `to` time scale.
+/// See [offset] for the `leap` and `corrections` parameters.
+/// Example:
+/// ```
+/// use rinex::gnss_time::{self, TimeScale};
+/// let date = chrono::NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+/// let gpst = gnss_time::convert(date, TimeScale::BDT, TimeScale::GPST, None, &[])
+///     .unwrap();
+/// assert_eq!(gpst, chrono::NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 14));
+/// let utc = gnss_time::convert(gpst, TimeScale::GPST, TimeScale::UTC, Some(18), &[])
+///     .unwrap();
+/// assert_eq!(utc, chrono::NaiveDate::from_ymd(2020, 12, 31).and_hms(23, 59, 56));
+/// ```
+pub fn convert (date: chrono::NaiveDateTime, from: TimeScale, to: TimeScale, leap: Option<u32>, corrections: &[TimeSystemCorrection]) -> Result<chrono::NaiveDateTime, Error> {
+    let offset = offset(date, from, to, leap, corrections)?;
+    Ok(date - chrono::Duration::nanoseconds((offset * 1.0E9).round() as i64))
 }
 
-impl std::str::FromStr for GnssTimeCorrection {
-    type Err = Error;
-    fn from_str (s: &str) -> Result<Self, Self::Err> {
-        Ok(GnssTimeCorrection::default())
-        /*
-SBUT  0.1331791282D-06 0.107469589D-12 552960 1025 EGNOS  5 TIME SYSTEM CORR    
-        let systype = TimeCorrectionType::from_str()?;
-        let params ...
-        let delta_t..
-        let week..
-        if not null:
-            AugmentationSystem::from_str()?
-        if not null:
-            UtcProvider::from_str()?
-        */
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_timescale() {
+        assert_eq!(TimeScale::from_str("GPS").unwrap(), TimeScale::GPST);
+        assert_eq!(TimeScale::from_str("bdt").unwrap(), TimeScale::BDT);
+        assert_eq!(TimeScale::from_str("XYZ").is_err(), true);
+        assert_eq!(TimeScale::from_constellation(Constellation::Glonass), Some(TimeScale::GLONASST));
+        assert_eq!(TimeScale::from_constellation(Constellation::Mixed), None);
+        assert_eq!(TimeScale::from(TimeSystem::GAL), TimeScale::GST);
+    }
+    #[test]
+    fn test_builtin_leap() {
+        // 2017-01-01T00:00:10 GPST is still 2016 in UTC
+        let date = chrono::NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 10);
+        assert_eq!(TimeScale::GPST.builtin_leap(date), Some(17));
+        assert_eq!(TimeScale::UTC.builtin_leap(date), Some(18));
+        assert_eq!(TimeScale::GLONASST.builtin_leap(date), Some(17));
+        let date = chrono::NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 18);
+        assert_eq!(TimeScale::GPST.builtin_leap(date), Some(18));
+        // BDT is 14s behind GPST
+        let date = chrono::NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 3);
+        assert_eq!(TimeScale::BDT.builtin_leap(date), Some(17));
+        let date = chrono::NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 4);
+        assert_eq!(TimeScale::BDT.builtin_leap(date), Some(18));
+    }
+    #[test]
+    fn test_offset() {
+        let date = chrono::NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        assert_eq!(offset(date, TimeScale::GPST, TimeScale::UTC, None, &[]),
+            Err(Error::MissingLeapSeconds(TimeScale::GPST, TimeScale::UTC)));
+        assert_eq!(offset(date, TimeScale::GPST, TimeScale::BDT, None, &[]), Ok(14.0));
+        assert_eq!(offset(date, TimeScale::GLONASST, TimeScale::UTC, Some(18), &[]), Ok(10800.0));
+        assert_eq!(offset(date, TimeScale::GLONASST, TimeScale::GPST, Some(18), &[]), Ok(10782.0));
+        let corrections = vec![
+            TimeSystemCorrection {
+                system: String::from("GAGP"),
+                utc: String::new(),
+                epoch: None,
+                reference: Some((2138, 432000)),
+                a: (1.0E-9, 1.0E-12, 0.0),
+            },
+        ];
+        // reference time of the correction: week 2138 + 432000s
+        let reference = chrono::NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let gst_gpst = offset(reference, TimeScale::GST, TimeScale::GPST, None, &corrections).unwrap();
+        assert!((gst_gpst - 1.0E-9).abs() < 1.0E-15);
+        let gpst_gst = offset(reference + chrono::Duration::seconds(100), TimeScale::GPST, TimeScale::GST, None, &corrections).unwrap();
+        assert!((gpst_gst + 1.0E-9 + 1.0E-10).abs() < 1.0E-15);
     }
 }
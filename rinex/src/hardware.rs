@@ -1,4 +1,7 @@
 //! Hardware and receiver related structures
+use crate::constellation::Constellation;
+use crate::observation::Observable;
+
 #[cfg(feature = "with-serde")]
 use crate::formatter::point3d;
 
@@ -45,7 +48,7 @@ impl std::str::FromStr for Rcvr {
 
 /// Antenna description 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Antenna {
     /// Hardware model / make descriptor
     pub model: String,
@@ -60,6 +63,18 @@ pub struct Antenna {
     pub eastern_ecc: Option<f32>,
     /// northern eccentricity compare to ref. point
     pub northern_ecc: Option<f32>,
+    /// antenna boresight (vertical axis pointing toward vehicules),
+    /// in vehicle body frame, on moving platforms
+    #[cfg_attr(feature = "with-serde", serde(with = "point3d"))]
+    pub bsight: Option<rust_3d::Point3D>,
+    /// antenna zero direction (azimuth reference),
+    /// in vehicle body frame, on moving platforms
+    #[cfg_attr(feature = "with-serde", serde(with = "point3d"))]
+    pub zero_dir: Option<rust_3d::Point3D>,
+    /// antenna zero direction azimuth [°], from north, on fixed stations
+    pub zero_dir_azimuth: Option<f64>,
+    /// average phase center positions, per observable
+    pub phase_centers: Vec<PhaseCenter>,
 }
 
 impl Default for Antenna {
@@ -72,6 +87,75 @@ impl Default for Antenna {
             height: None,
             eastern_ecc: None,
             northern_ecc: None,
+            bsight: None,
+            zero_dir: None,
+            zero_dir_azimuth: None,
+            phase_centers: Vec::new(),
         }
     }
 }
+
+/// Average antenna phase center position, for a given observable
+#[derive(Debug, Clone)]
+#[derive(PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct PhaseCenter {
+    /// Constellation
+    pub constellation: Constellation,
+    /// Observable
+    pub observable: Observable,
+    /// Position relative to the antenna reference point [m]:
+    /// (north, east, up) on fixed stations,
+    /// (x, y, z) in vehicle body frame on moving platforms
+    pub offset: (f64, f64, f64),
+}
+
+impl std::str::FromStr for PhaseCenter {
+    type Err = std::io::Error;
+    /// Parses "ANTENNA: PHASECENTER" header content
+    fn from_str (content: &str) -> Result<Self, Self::Err> {
+        let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid phase center");
+        let field = |start: usize, end: usize| -> Result<f64, std::io::Error> {
+            let field = content.get(start..end)
+                .ok_or_else(invalid)?;
+            f64::from_str(field.trim())
+                .map_err(|_| invalid())
+        };
+        let constellation = Constellation::from_1_letter_code(content.get(0..1).unwrap_or(""))
+            .map_err(|_| invalid())?;
+        let observable = Observable::from_str(content.get(2..5).unwrap_or("").trim())
+            .map_err(|_| invalid())?;
+        Ok(Self {
+            constellation,
+            observable,
+            offset: (field(5, 14)?, field(14, 28)?, field(28, 42)?),
+        })
+    }
+}
+
+impl std::fmt::Display for PhaseCenter {
+    /// Formats self as "ANTENNA: PHASECENTER" header content
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (n, e, u) = self.offset;
+        write!(f, "{} {:<3}{:9.4}{:14.4}{:14.4}",
+            self.constellation.to_1_letter_code(),
+            self.observable.to_string(),
+            n, e, u)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_phase_center() {
+        let content = "G L1C   0.0912        0.0012       -0.0009";
+        let center = PhaseCenter::from_str(content).unwrap();
+        assert_eq!(center.constellation, Constellation::GPS);
+        assert_eq!(center.observable, Observable::from_str("L1C").unwrap());
+        assert_eq!(center.offset, (0.0912, 0.0012, -0.0009));
+        assert_eq!(center.to_string(), content);
+        assert_eq!(PhaseCenter::from_str("G L1C").is_err(), true);
+    }
+}
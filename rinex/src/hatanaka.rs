@@ -1,12 +1,14 @@
 //! Structures and macros for
 //! RINEX OBS file compression and decompression.   
 use crate::sv;
+use crate::epoch;
 use crate::header;
 use crate::is_comment;
 use crate::types::Type;
+use crate::observation;
 use thiserror::Error;
 use std::str::FromStr;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Error, Debug)]
 /// Hatanaka Kernel, compression
@@ -160,12 +162,25 @@ impl Kernel {
         Dtype::Numerical(self.state[0])
     }
     
-    /// Compresses numerical data using Hatanaka method
-    fn numerical_data_compression (&self, _data: i64) -> Dtype {
-        Dtype::Numerical(0)
+    /// Compresses numerical data using Hatanaka method,
+    /// this is the exact inverse of `numerical_data_recovery`
+    fn numerical_data_compression (&mut self, data: i64) -> Dtype {
+        self.n += 1;
+        self.n = std::cmp::min(self.n, self.order);
+        self.state.iter_mut().map(|x| *x = 0).count();
+        self.state[0] = data;
+        for index in 0..self.n {
+            self.state[index+1] =
+                self.state[index]
+                    - self.p_state[index]
+        }
+        self.p_state = self.state.clone();
+        Dtype::Numerical(self.state[self.n])
     }
 
-    /// Performs TextDiff operation as defined in Hatanaka compression method 
+    /// Performs TextDiff operation as defined in Hatanaka compression method:
+    /// whitespaces mark unchanged characters, '&' marks a character
+    /// that turned into a whitespace, any other character is the new value
     fn text_data_recovery (&mut self, data: String) -> Dtype {
         let mut init = self.init
             .as_text()
@@ -179,10 +194,10 @@ impl Kernel {
             if let Some(c) = data.next() {
                 if c == '&' { // special whitespace insertion
                     recovered.push_str(" ")
-                } else if c.is_ascii_alphanumeric() {
-                    recovered.push_str(&c.to_string())
-                } else {
+                } else if c == ' ' { // unchanged
                     recovered.push_str(&next_c.to_string())
+                } else {
+                    recovered.push_str(&c.to_string())
                 }
             } else {
                 recovered.push_str(&next_c.to_string())
@@ -192,9 +207,9 @@ impl Kernel {
         // in case we need to extend current value
         loop {
             if let Some(c) = data.next() {
-                if c == '&' { // special whitespace insertion
+                if c == '&' || c == ' ' { // blank, or whitespace insertion
                     recovered.push_str(" ")
-                } else if c.is_ascii_alphanumeric() {
+                } else {
                     recovered.push_str(&c.to_string())
                 }
             } else {
@@ -205,36 +220,46 @@ impl Kernel {
         Dtype::Text(String::from(&recovered))
     }
     
-    /// Compresses text data using Hatanaka method
-    fn text_data_compression (&mut self, _data: String) -> String {
-        /*let current = self.init
+    /// Compresses text data using Hatanaka method:
+    /// unchanged characters are replaced by a whitespace,
+    /// characters that turned into whitespaces are marked with '&'
+    fn text_data_compression (&mut self, data: String) -> String {
+        let init = self.init
             .as_text()
             .unwrap();
-        let ptr_data = data
-            .as_str()
-            .chars();     
-        let ptr_current = current
-            .as_str()
-            .chars();
-        let mut result = String::new();
-        for i in 0..data.len() {
-            let next = ptr_data
-                .next()
-                .unwrap();
-            if let Some(c) = ptr_current.next() {
-                if c == next {
-                    content.push_str(" ");
-                } else {
-                    content.push_str(next);
-                    hidden.push_str(next)
-                }
-             } else {
-                
-             }
+        let mut p = init.chars();
+        let mut result = String::with_capacity(data.len());
+        for c in data.chars() {
+            match p.next() {
+                Some(prev) => {
+                    if c == prev {
+                        result.push(' ')
+                    } else if c == ' ' { // special whitespace insertion
+                        result.push('&')
+                    } else {
+                        result.push(c)
+                    }
+                },
+                None => { // extending current value
+                    if c == ' ' {
+                        result.push('&')
+                    } else {
+                        result.push(c)
+                    }
+                },
+            }
         }
-        self.init = Dtype(content.clone());
-        result*/
-        String::from("Hello World")
+        // new value might be shorter than previous one:
+        // erase remaining characters
+        for prev in p {
+            if prev == ' ' {
+                result.push(' ')
+            } else {
+                result.push('&')
+            }
+        }
+        self.init = Dtype::Text(data); // for next time
+        result.trim_end().to_string()
     }
 }
 
@@ -253,6 +278,8 @@ pub enum Error {
     FaultyCrinex1Format,
     #[error("CRINEX3 standard mismatch")]
     FaultyCrinex3Format,
+    #[error("RINEX3 standard mismatch")]
+    FaultyRinex3Format,
     #[error("failed to identify sat. vehicule")]
     SvError(#[from] sv::Error),
     #[error("failed to parse integer number")]
@@ -263,6 +290,121 @@ pub enum Error {
     DataRecoveryError,
 }
 
+/// Compression statistics of a single epoch
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EpochStatistics {
+    /// Number of kernel (re)initializations,
+    /// a high count is a sign of data that compresses poorly
+    pub resets: usize,
+    /// RINEX (uncompressed) content size, in bytes
+    pub rinex_bytes: usize,
+    /// CRINEX (compressed) content size, in bytes
+    pub crinex_bytes: usize,
+}
+
+impl EpochStatistics {
+    /// Returns compression ratio (compressed size / uncompressed size)
+    pub fn ratio (&self) -> f64 {
+        if self.rinex_bytes == 0 {
+            1.0
+        } else {
+            self.crinex_bytes as f64 / self.rinex_bytes as f64
+        }
+    }
+}
+
+/// Compression effectiveness report,
+/// gathered while compressing or decompressing a record
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statistics {
+    /// Maximal compression order supported by the tool
+    pub max_order: usize,
+    /// Highest compression order actually used
+    pub order: usize,
+    /// Per epoch statistics
+    pub epochs: BTreeMap<epoch::Epoch, EpochStatistics>,
+}
+
+impl Statistics {
+    /// Builds an empty report
+    pub fn new (max_order: usize) -> Self {
+        Self {
+            max_order,
+            order: 0,
+            epochs: BTreeMap::new(),
+        }
+    }
+    /// Returns total number of kernel (re)initializations
+    pub fn resets (&self) -> usize {
+        self.epochs.values().map(|e| e.resets).sum()
+    }
+    /// Returns total RINEX (uncompressed) size, in bytes
+    pub fn rinex_bytes (&self) -> usize {
+        self.epochs.values().map(|e| e.rinex_bytes).sum()
+    }
+    /// Returns total CRINEX (compressed) size, in bytes
+    pub fn crinex_bytes (&self) -> usize {
+        self.epochs.values().map(|e| e.crinex_bytes).sum()
+    }
+    /// Returns overall compression ratio (compressed size / uncompressed size)
+    pub fn ratio (&self) -> f64 {
+        let rinex = self.rinex_bytes();
+        if rinex == 0 {
+            1.0
+        } else {
+            self.crinex_bytes() as f64 / rinex as f64
+        }
+    }
+    /// Returns epochs that compressed worse than given ratio
+    pub fn poorly_compressed (&self, ratio: f64) -> Vec<epoch::Epoch> {
+        self.epochs
+            .iter()
+            .filter_map(|(e, stats)| {
+                if stats.ratio() > ratio {
+                    Some(*e)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Identifies epoch from a (recovered) epoch descriptor,
+/// either in RINEX2 or RINEX3 format
+fn parse_epoch (descriptor: &str) -> Option<epoch::Epoch> {
+    let (date, flag) = match descriptor.starts_with(">") {
+        true => (descriptor.get(1..29)?, descriptor.get(31..32)?),
+        false => (descriptor.get(0..26)?, descriptor.get(28..29)?),
+    };
+    let date = epoch::str2date(date).ok()?;
+    let flag = epoch::EpochFlag::from_str(flag).ok()?;
+    Some(epoch::Epoch::new(date, flag))
+}
+
+/// Returns number of special records announced by given
+/// uncompressed epoch descriptor, if it describes an epoch event
+/// (flag 2 to 5). Returns None for other epochs and compressed descriptors
+fn event_records (descriptor: &str) -> Option<u16> {
+    let (flag, n) = match descriptor.chars().nth(0)? {
+        '>' => (descriptor.get(31..32)?, descriptor.get(32..35)),
+        '&' => (descriptor.get(28..29)?, descriptor.get(29..32)),
+        _ => return None,
+    };
+    let flag = epoch::EpochFlag::from_str(flag).ok()?;
+    if !observation::event::is_event_flag(flag) {
+        return None
+    }
+    let n = n.and_then(|n| u16::from_str(n.trim()).ok());
+    Some(n.unwrap_or(0))
+}
+
+/// Removes blank padding at the end of a recovered line
+fn trim_line_end (result: &mut String) {
+    let len = result.trim_end_matches(' ').len();
+    result.truncate(len)
+}
+
 /// Structure to decompress a CRINEX file
 pub struct Decompressor {
     /// to identify very first few bytes passed
@@ -273,12 +415,20 @@ pub struct Decompressor {
     clock_offset : bool,
     /// to determine where we are in the record
     pointer : u16,
+    /// special records remaining, following an epoch event
+    special_records : u16,
     /// Epoch decompressor
     epo_krn : Kernel,
     /// Clock offset decompressor
     clk_krn : Kernel,
     /// decompressors
     sv_krn  : HashMap<sv::Sv, Vec<(Kernel, Kernel, Kernel)>>,
+    /// epoch being recovered
+    epoch : Option<epoch::Epoch>,
+    /// statistics of epoch being recovered
+    epoch_stats : EpochStatistics,
+    /// compression report
+    stats : Statistics,
 }
 
 impl Decompressor {
@@ -289,11 +439,19 @@ impl Decompressor {
             header : true,
             clock_offset : false,
             pointer : 0,
+            special_records : 0,
             epo_krn : Kernel::new(0),
             clk_krn : Kernel::new(max_order),
             sv_krn  : HashMap::new(),
+            epoch : None,
+            epoch_stats : EpochStatistics::default(),
+            stats : Statistics::new(max_order),
         }
     }
+    /// Returns compression report of the content decompressed so far
+    pub fn statistics (&self) -> &Statistics {
+        &self.stats
+    }
     /// Decompresses (recovers) RINEX from given CRINEX record block.   
     /// This method will decompress and manage CRINEX comments or weird events properly.    
     /// This method will crash on header data: header section should be previously / separately parsed.    
@@ -339,7 +497,15 @@ impl Decompressor {
                 Some(l) => l,
                 None => break,
             };
-            // [0] : COMMENTS
+            // [0] : special records following an epoch event,
+            //       comments included: passed as is
+            if self.special_records > 0 {
+                self.special_records -= 1;
+                result.push_str(line);
+                result.push_str("\n");
+                continue
+            }
+            // [0*] : COMMENTS
             if is_comment!(line) {
                 if line.contains("RINEX FILE SPLICE") {
                     // [0*] SPLICE special comments
@@ -352,15 +518,25 @@ impl Decompressor {
                 result.push_str("\n");
                 continue
             }
-            // [0*] : special epoch events
-            //        with uncompressed descriptor
-            if line.starts_with("> ") && !self.first_epo {
-                result.push_str(line); // feed as is..
-                result.push_str("\n");
-                continue
-            }
+            // compressed & recovered sizes, for statistics
+            let start = result.len();
+            self.epoch_stats.crinex_bytes += line.len() +1;
             // [1] recover epoch descriptor 
             if self.header {
+                // [1*] epoch events: uncompressed descriptor
+                //      followed by uncompressed special records
+                if let Some(n) = event_records(line) {
+                    match line.strip_prefix("&") {
+                        Some(descriptor) => {
+                            result.push_str(" ");
+                            result.push_str(descriptor)
+                        },
+                        None => result.push_str(line),
+                    }
+                    result.push_str("\n");
+                    self.special_records = n;
+                    continue
+                }
                 self.recover_epoch_descriptor(crx_version.major, &line)?; 
                 self.header = false;
                 self.clock_offset = true;
@@ -371,7 +547,9 @@ impl Decompressor {
                 clock_offset = match line.contains("&") {
                     false => {
                         if let Ok(num) = i64::from_str_radix(line.trim(),10) {
-                            Some(num)
+                            // differential value: recover through clock kernel
+                            self.clk_krn.recover(Dtype::Numerical(num))?
+                                .as_numerical()
                         } else {
                             None // parsing fails on empty line
                         }
@@ -386,6 +564,8 @@ impl Decompressor {
                             n.into(), 
                             Dtype::Numerical(num))
                             .unwrap();
+                        self.epoch_stats.resets += 1;
+                        self.stats.order = std::cmp::max(self.stats.order, n.into());
                         Some(num)
                     },
                 };
@@ -395,6 +575,7 @@ impl Decompressor {
                     .as_text()
                     .unwrap();
                 let recovered_epoch = recovered_epoch.as_str().trim_end();
+                self.epoch = parse_epoch(recovered_epoch);
                 match rnx_version.major {
                     1|2 => { // old RINEX
                         // system # id is appended
@@ -405,9 +586,11 @@ impl Decompressor {
                         // terminate first line with required content
                         let end = std::cmp::min(begin+12*3, systems.len());
                         result.push_str(&systems[begin..end]);
-                        // squeeze clock offset here, if any
+                        // squeeze clock offset here, if any: F12.9 [s]
                         if let Some(offset) = clock_offset {
-                            result.push_str(&format!("  {:3.9}", (offset as f64)/1000.0_f64))
+                            let end = std::cmp::min(12*3, systems.len());
+                            result.push_str(&" ".repeat(12*3 - end));
+                            result.push_str(&format!("{:12.9}", (offset as f64)/1.0E9))
                         }
                         loop { // missing lines to fit remaining systems 
                             begin += 12*3; // `systems` pointer
@@ -420,13 +603,16 @@ impl Decompressor {
                         }
                     },
                     _ => { // modern RINEX
-                        result.push_str(recovered_epoch.split_at(35).0);
+                        // epoch, flag and number of vehicules, exactly
+                        result.push_str(recovered_epoch.get(0..35).unwrap_or(recovered_epoch));
+                        // 6X reserved + F15.12 clock offset [s], if any
                         if let Some(offset) = clock_offset {
-                            result.push_str(&format!("         {:3.12}", (offset as f64)/1000.0_f64))
+                            result.push_str(&format!("      {:15.12}", (offset as f64)/1.0E12))
                         }
                     }
                 };
                 result.push_str("\n");
+                self.epoch_stats.rinex_bytes += result.len() - start;
                 self.clock_offset = false;
                 continue
             }
@@ -571,6 +757,9 @@ impl Decompressor {
                             }
                         }
                     }
+                    if rnx_version.major > 2 {
+                        trim_line_end(&mut result)
+                    }
                     result.push_str("\n");
                     break
                 }
@@ -595,6 +784,8 @@ impl Decompressor {
                                     order.into(),
                                     Dtype::Numerical(data))
                                     .unwrap();
+                            self.epoch_stats.resets += 1;
+                            self.stats.order = std::cmp::max(self.stats.order, order.into());
                             obs_data.push(Some(data));
                             obs_count += 1
                         } else {
@@ -652,6 +843,9 @@ impl Decompressor {
                                 }
                             }
                         }
+                        if rnx_version.major > 2 {
+                            trim_line_end(&mut result)
+                        }
                         result.push_str("\n");
                         break // EOL
                     },
@@ -684,6 +878,8 @@ impl Decompressor {
                             order.into(),
                             Dtype::Numerical(data))
                             .unwrap();
+                    self.epoch_stats.resets += 1;
+                    self.stats.order = std::cmp::max(self.stats.order, order.into());
                     obs_data.push(Some(data))
                 } else {
                     let obs = self.sv_krn.get_mut(&sv)
@@ -699,10 +895,16 @@ impl Decompressor {
                 }
                 obs_count +=1
             } // for all OBS
+            self.epoch_stats.rinex_bytes += result.len() - start;
             self.pointer += 1;
             if self.pointer == nb_sv { // nothing else to parse
                 self.pointer = 0; // reset
-                self.header = true // reset FSM
+                self.header = true; // reset FSM
+                // epoch is now fully recovered
+                let stats = std::mem::take(&mut self.epoch_stats);
+                if let Some(epoch) = self.epoch.take() {
+                    self.stats.epochs.insert(epoch, stats);
+                }
             }
         }
 
@@ -726,8 +928,10 @@ impl Decompressor {
                 _ => return Err(Error::NonSupportedCrinexRevision),
             }
         }
-        if self.first_epo {
-            self.epo_krn.init( // init this kernel
+        if self.first_epo || line.starts_with("&") || line.starts_with(">") {
+            // first epoch or uncompressed descriptor:
+            // (re)init this kernel
+            self.epo_krn.init(
                 0, // is always a textdiff
                 Dtype::Text(line.to_string()))?;
             self.first_epo = false
@@ -738,6 +942,261 @@ impl Decompressor {
     }
 }
 
+/// Compression order used when (re)initializing
+/// an observation kernel, as used by `RNX2CRX`
+pub const DEFAULT_ORDER: usize = 3;
+
+/// Structure to compress a RINEX record into CRINEX
+pub struct Compressor {
+    /// to identify very first epoch
+    first_epo: bool,
+    /// maximal compression order
+    max_order: usize,
+    /// pending epoch descriptor
+    epoch: Option<String>,
+    /// pending epoch content
+    content: Vec<String>,
+    /// special records remaining, following an epoch event
+    special_records: u16,
+    /// Epoch compressor
+    epo_krn: Kernel,
+    /// Clock offset compressor,
+    /// None when it needs to be (re)initialized
+    clk_krn: Option<Kernel>,
+    /// observation compressors,
+    /// None when a kernel needs to be (re)initialized
+    sv_krn: HashMap<sv::Sv, Vec<Option<Kernel>>>,
+    /// flags compressors
+    flags_krn: HashMap<sv::Sv, Kernel>,
+    /// compression report
+    stats: Statistics,
+}
+
+impl Compressor {
+    /// Creates a new `RINEX` compressor tool
+    pub fn new (max_order: usize) -> Compressor {
+        Compressor {
+            first_epo: true,
+            max_order,
+            epoch: None,
+            content: Vec::new(),
+            special_records: 0,
+            epo_krn: Kernel::new(0),
+            clk_krn: None,
+            sv_krn: HashMap::new(),
+            flags_krn: HashMap::new(),
+            stats: Statistics::new(max_order),
+        }
+    }
+    /// Returns compression report of the content compressed so far
+    pub fn statistics (&self) -> &Statistics {
+        &self.stats
+    }
+    /// Compresses given RINEX record block into CRINEX.   
+    /// Only RINEX3 / CRINEX3 compression is supported to this day.
+    /// `header` : previously identified RINEX `header` section,
+    ///           that must describe a `CRINEX` revision
+    /// `content`: string reference extracted from a RINEX record.    
+    ///           Like `Decompressor::decompress`, `content` can be single lines,
+    ///           one or several epochs at once, or an entire record.
+    ///           Epochs are only emitted once all their lines were provided.
+    /// `result`: returns compressed block from provided block content
+    pub fn compress (&mut self, header: &header::Header, content: &str) -> Result<String, Error> {
+        // Context sanity checks
+        if !header.is_crinex() {
+            return Err(Error::NotACrinexError)
+        }
+        if header.rinex_type != Type::ObservationData {
+            return Err(Error::NotObsRinexData)
+        }
+        let obs = header.obs
+            .as_ref()
+            .unwrap();
+        let crinex = obs.crinex
+            .as_ref()
+            .unwrap();
+        if crinex.version.major != 3 || header.version.major < 3 {
+            return Err(Error::NonSupportedCrinexRevision)
+        }
+        let mut result = String::new();
+        for line in content.lines() {
+            // special records following an epoch event,
+            // comments included: passed as is
+            if self.special_records > 0 {
+                self.special_records -= 1;
+                result.push_str(line);
+                result.push_str("\n");
+                continue
+            }
+            if is_comment!(line) {
+                result.push_str(line); // feed as is..
+                result.push_str("\n");
+                continue
+            }
+            // epoch events are not compressed
+            if let Some(n) = event_records(line) {
+                result.push_str(line);
+                result.push_str("\n");
+                self.special_records = n;
+                continue
+            }
+            if line.starts_with(">") {
+                self.epoch = Some(line.to_string());
+                self.content.clear()
+            } else if self.epoch.is_some() {
+                self.content.push(line.to_string())
+            } else {
+                return Err(Error::FaultyRinex3Format)
+            }
+            let epoch = self.epoch
+                .as_ref()
+                .unwrap();
+            let nb_sv = match epoch.get(32..35) {
+                Some(n) => u16::from_str_radix(n.trim(), 10)?,
+                None => return Err(Error::FaultyRinex3Format),
+            };
+            if self.content.len() == nb_sv as usize {
+                result.push_str(&self.compress_epoch(header)?);
+                self.epoch = None;
+                self.content.clear()
+            }
+        }
+        Ok(result)
+    }
+    /// Compresses pending epoch
+    fn compress_epoch (&mut self, header: &header::Header) -> Result<String, Error> {
+        let obs_codes = &header.obs
+            .as_ref()
+            .unwrap()
+            .codes;
+        let epoch = self.epoch
+            .as_ref()
+            .unwrap();
+        let mut result = String::new();
+        let mut stats = EpochStatistics::default();
+        stats.rinex_bytes = epoch.len() +1;
+        for line in self.content.iter() {
+            stats.rinex_bytes += line.len() +1
+        }
+        // [1] epoch descriptor
+        let mut descriptor = format!("{:<35}      ", epoch.get(0..35).unwrap_or(epoch));
+        for line in self.content.iter() {
+            descriptor.push_str(line.get(0..3).unwrap_or(""))
+        }
+        if self.first_epo {
+            self.epo_krn.init( // init this kernel
+                0, // is always a textdiff
+                Dtype::Text(descriptor.clone()))?;
+            result.push_str(&descriptor);
+            self.first_epo = false
+        } else {
+            let compressed = self.epo_krn.compress(Dtype::Text(descriptor))?
+                .as_text()
+                .unwrap();
+            result.push_str(&compressed)
+        }
+        result.push_str("\n");
+        // [2] clock offset, if any
+        let clock_offset = match epoch.get(41..) {
+            Some(offset) => offset.trim().parse::<f64>().ok(),
+            None => None,
+        };
+        if let Some(offset) = clock_offset {
+            let offset = (offset * 1.0E12).round() as i64;
+            if let Some(krn) = self.clk_krn.as_mut() {
+                let compressed = krn.compress(Dtype::Numerical(offset))?
+                    .as_numerical()
+                    .unwrap();
+                result.push_str(&compressed.to_string())
+            } else {
+                let order = std::cmp::min(DEFAULT_ORDER, self.max_order);
+                let mut krn = Kernel::new(self.max_order);
+                krn.init(order, Dtype::Numerical(offset))?;
+                self.clk_krn = Some(krn);
+                stats.resets += 1;
+                result.push_str(&format!("{}&{}", order, offset))
+            }
+        } else {
+            self.clk_krn = None
+        }
+        result.push_str("\n");
+        // [3] epoch content
+        let mut vehicules : Vec<sv::Sv> = Vec::with_capacity(self.content.len());
+        for line in self.content.iter() {
+            let sv = sv::Sv::from_str(line.get(0..3).unwrap_or("").trim())?;
+            vehicules.push(sv);
+            let codes = &obs_codes[&sv.constellation];
+            let order = std::cmp::min(DEFAULT_ORDER, self.max_order);
+            let kernels = self.sv_krn
+                .entry(sv)
+                .or_insert(vec![None; codes.len()]);
+            let mut fields : Vec<String> = Vec::with_capacity(codes.len());
+            let mut flags = String::with_capacity(codes.len() *2);
+            for i in 0..codes.len() {
+                let offset = 3 + i*16; // A3 + (F14.3 + LLI + SSI) per OBS
+                let data = line.get(offset..std::cmp::min(offset+14, line.len()))
+                    .unwrap_or("")
+                    .trim()
+                    .parse::<f64>();
+                if let Ok(data) = data {
+                    let data = (data * 1000.0).round() as i64;
+                    if let Some(krn) = kernels[i].as_mut() {
+                        let compressed = krn.compress(Dtype::Numerical(data))?
+                            .as_numerical()
+                            .unwrap();
+                        fields.push(compressed.to_string())
+                    } else {
+                        let mut krn = Kernel::new(self.max_order);
+                        krn.init(order, Dtype::Numerical(data))?;
+                        kernels[i] = Some(krn);
+                        stats.resets += 1;
+                        fields.push(format!("{}&{}", order, data))
+                    }
+                    flags.push_str(line.get(offset+14..offset+15).unwrap_or(" ")); // lli
+                    flags.push_str(line.get(offset+15..offset+16).unwrap_or(" ")); // ssi
+                } else {
+                    // BLANK field: kernel will need to be reinitialized
+                    kernels[i] = None;
+                    fields.push(String::new());
+                    flags.push_str("  ")
+                }
+            }
+            let flags_krn = self.flags_krn
+                .entry(sv)
+                .or_insert_with(|| {
+                    let mut krn = Kernel::new(0);
+                    krn.init(0, Dtype::Text(" ".repeat(codes.len() *2)))
+                        .unwrap();
+                    krn
+                });
+            let flags = flags_krn.compress(Dtype::Text(flags))?
+                .as_text()
+                .unwrap();
+            let fields = fields.join(" ");
+            if flags.len() > 0 {
+                result.push_str(&fields);
+                result.push_str(" ");
+                result.push_str(&flags)
+            } else {
+                result.push_str(fields.trim_end())
+            }
+            result.push_str("\n")
+        }
+        // vehicules that disappeared will need to be reinitialized
+        self.sv_krn.retain(|sv, _| vehicules.contains(sv));
+        // statistics
+        stats.crinex_bytes = result.len();
+        if stats.resets > 0 {
+            let order = std::cmp::min(DEFAULT_ORDER, self.max_order);
+            self.stats.order = std::cmp::max(self.stats.order, order);
+        }
+        if let Some(epoch) = parse_epoch(epoch) {
+            self.stats.epochs.insert(epoch, stats);
+        }
+        Ok(result)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{Kernel,Dtype};
@@ -905,4 +1364,36 @@ mod test {
             println!("RESULT -   \"{}\"", result);
         }
     }
+    #[test]
+    fn test_text_round_trip() {
+        let init = "ABCDEFG 12 000 33 XXACQmpLf";
+        let mut compressor = Kernel::new(5);
+        compressor.init(0, Dtype::Text(init.to_string()))
+            .unwrap();
+        let mut decompressor = Kernel::new(5);
+        decompressor.init(0, Dtype::Text(init.to_string()))
+            .unwrap();
+        let values : Vec<&str> = vec![
+            "ABCDEFG 1 1 1 33  XXABCQMPLF",
+            "Hello 1 1 1 33  blop",
+            "---> rien a voir  11--33!!<",
+            "---> Hello World", 
+            "-1.5 +2.5 ./Hello W0rld", 
+            "+1.5 -2.5 ./Hell0 W0rld", 
+            "+1.5 -2.5 ./Hell0 W0rld", 
+            "--> H3ll0 W0rLd", 
+            "-->.H3ll0_W0rLd", 
+        ];
+        for value in values {
+            let mask = compressor.compress(Dtype::Text(value.to_string()))
+                .unwrap()
+                    .as_text()
+                    .unwrap();
+            let result = decompressor.recover(Dtype::Text(mask.clone()))
+                .unwrap()
+                    .as_text()
+                    .unwrap();
+            assert_eq!(result.trim_end(), value, "mask: \"{}\"", mask);
+        }
+    }
 }
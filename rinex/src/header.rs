@@ -1,16 +1,20 @@
 //! Describes a `RINEX` header, includes
 //! rinex header parser and associated methods
 use crate::leap;
+use crate::epoch;
 use crate::antex;
 use crate::clocks;
 use crate::version;
+use crate::validation;
+use crate::sv::{self, Sv};
 //use crate::gnss_time;
 use crate::hardware;
 use crate::reader::BufferedReader;
 use crate::types::{Type, TypeError};
-use crate::merge::MergeError;
+use crate::merge::{self, MergeError, MergePolicy, FieldPolicy};
 use crate::meteo;
 use crate::observation;
+use crate::navigation;
 use crate::ionosphere;
 use crate::constellation;
 use crate::constellation::{Constellation, augmentation::Augmentation};
@@ -18,8 +22,9 @@ use crate::constellation::{Constellation, augmentation::Augmentation};
 use thiserror::Error;
 use std::str::FromStr;
 use strum_macros::EnumString;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{prelude::*};
+use chrono::{Datelike, Timelike};
 
 #[cfg(feature = "with-serde")]
 use serde::{Serialize, Deserialize};
@@ -27,7 +32,8 @@ use serde::{Serialize, Deserialize};
 #[cfg(feature = "with-serde")]
 use crate::formatter::point3d;
 
-#[derive(Clone, Debug)]
+#[derive(Copy, Clone, Debug)]
+#[derive(PartialEq, Eq)]
 #[derive(EnumString)]
 #[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum MarkerType {
@@ -35,31 +41,31 @@ pub enum MarkerType {
     #[strum(serialize = "GEODETIC", serialize = "Geodetic")]
     Geodetic,
     /// Earth fixed & low precision
-    #[strum(serialize = "NON GEODETIC", serialize = "NonGeodetic")]
+    #[strum(serialize = "NON_GEODETIC", serialize = "NON GEODETIC", serialize = "NonGeodetic")]
     NonGeodetic,
     /// Generated from network
-    #[strum(serialize = "NON PHYSICAL", serialize = "NonPhysical")]
+    #[strum(serialize = "NON_PHYSICAL", serialize = "NON PHYSICAL", serialize = "NonPhysical")]
     NonPhysical,
     /// Orbiting space vehicule
-    #[strum(serialize = "SPACE BORNE", serialize = "Spaceborne")]
+    #[strum(serialize = "SPACEBORNE", serialize = "SPACE BORNE", serialize = "Spaceborne")]
     Spaceborne,
     /// Aircraft, balloon..
-    #[strum(serialize = "AIR BORNE", serialize = "Airborne")]
+    #[strum(serialize = "AIRBORNE", serialize = "AIR BORNE", serialize = "Airborne")]
     Airborne,
     /// Mobile water craft
-    #[strum(serialize = "WATER CRAFT", serialize = "Watercraft")]
+    #[strum(serialize = "WATER_CRAFT", serialize = "WATER CRAFT", serialize = "Watercraft")]
     Watercraft,
     /// Mobile terrestrial vehicule
-    #[strum(serialize = "GROUND CRAFT", serialize = "Groundcraft")]
+    #[strum(serialize = "GROUND_CRAFT", serialize = "GROUND CRAFT", serialize = "Groundcraft")]
     Groundcraft,
     /// Fixed on water surface
-    #[strum(serialize = "FXED BUOY", serialize = "FixedBuoy")]
+    #[strum(serialize = "FIXED_BUOY", serialize = "FXED BUOY", serialize = "FixedBuoy")]
     FixedBuoy,
     /// Floating on water surface
-    #[strum(serialize = "FLOATING BUOY", serialize = "FloatingBuoy")]
+    #[strum(serialize = "FLOATING_BUOY", serialize = "FLOATING BUOY", serialize = "FloatingBuoy")]
     FloatingBuoy,
     /// Floating on ice
-    #[strum(serialize = "FLOATING ICE", serialize = "FloatingIce")]
+    #[strum(serialize = "FLOATING_ICE", serialize = "FLOATING ICE", serialize = "FloatingIce")]
     FloatingIce, 
     /// Fixed on glacier
     #[strum(serialize = "GLACIER", serialize = "Glacier")]
@@ -81,9 +87,44 @@ impl Default for MarkerType {
     }
 }
 
+impl MarkerType {
+    /// Returns true if this marker is fixed with respect
+    /// to the Earth crust (or very slowly moving, like glaciers)
+    pub fn is_static (&self) -> bool {
+        match self {
+            Self::Geodetic | Self::NonGeodetic | Self::NonPhysical
+            | Self::FixedBuoy | Self::Glacier => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for MarkerType {
+    /// Formats self as standard MARKER TYPE label
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let label = match self {
+            Self::Geodetic => "GEODETIC",
+            Self::NonGeodetic => "NON_GEODETIC",
+            Self::NonPhysical => "NON_PHYSICAL",
+            Self::Spaceborne => "SPACEBORNE",
+            Self::Airborne => "AIRBORNE",
+            Self::Watercraft => "WATER_CRAFT",
+            Self::Groundcraft => "GROUND_CRAFT",
+            Self::FixedBuoy => "FIXED_BUOY",
+            Self::FloatingBuoy => "FLOATING_BUOY",
+            Self::FloatingIce => "FLOATING_ICE",
+            Self::Glacier => "GLACIER",
+            Self::Ballistic => "BALLISTIC",
+            Self::Animal => "ANIMAL",
+            Self::Human => "HUMAN",
+        };
+        f.write_str(label)
+    }
+}
+
 /// Describes `RINEX` file header
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Header {
     /// revision for this `RINEX`
     pub version: version::Version, 
@@ -117,6 +158,10 @@ pub struct Header {
     /// station approxiamte coordinates
     #[cfg_attr(feature = "with-serde", serde(with = "point3d"))]
     pub coords: Option<rust_3d::Point3D>, 
+    /// vehicle center of mass, in vehicle body frame,
+    /// on moving platforms
+    #[cfg_attr(feature = "with-serde", serde(with = "point3d"))]
+    pub center_of_mass: Option<rust_3d::Point3D>,
     /// optionnal observation wavelengths
     pub wavelengths: Option<(u32,u32)>, 
     /// optionnal sampling interval (s)
@@ -130,10 +175,12 @@ pub struct Header {
     /// processing:   
     /// optionnal data scaling
     pub data_scaling: Option<f64>,
-    // optionnal ionospheric compensation param(s)
-    //ionospheric_corr: Option<Vec<IonoCorr>>,
-    // possible time system correction(s)
-    //gnsstime_corr: Option<Vec<gnss_time::GnssTimeCorr>>,
+    /// optionnal ionospheric compensation param(s),
+    /// as described in NAV V2/V3 headers
+    pub ionospheric_corr: Vec<navigation::IonosphericCorrection>,
+    /// possible time system correction(s),
+    /// as described in NAV V2/V3 headers
+    pub time_system_corr: Vec<navigation::TimeSystemCorrection>,
     ////////////////////////////////////////
     // Hardware
     ////////////////////////////////////////
@@ -196,6 +243,18 @@ pub enum Error {
     ParsePcvError(#[from] antex::pcv::Error),
     #[error("faulty ionex format")]
     FaultyIonexDescription,
+    #[error("failed to parse epoch")]
+    EpochParsingError(#[from] epoch::ParseDateError),
+    #[error("time system error")]
+    TimeSystemError(#[from] observation::TimeSystemError),
+    #[error("time system must be specified in mixed OBS files")]
+    MissingTimeSystem,
+    #[error("failed to parse observable")]
+    ObservableError(#[from] observation::observable::Error),
+    #[error("failed to parse vehicule")]
+    SvError(#[from] sv::Error),
+    #[error("line {0}: {1}")]
+    Line(usize, Box<Error>),
 }
 
 impl Default for Header {
@@ -222,11 +281,12 @@ impl Default for Header {
             rcvr: None,
             ant: None,
             coords: None, 
+            center_of_mass: None,
             wavelengths: None,
             // processing
             data_scaling: None,
-            //ionospheric_corr: None,
-            //gnsstime_corr: None,
+            ionospheric_corr: Vec::new(),
+            time_system_corr: Vec::new(),
             sampling_interval: None,
             /////////////////////////
             // OBSERVATION
@@ -253,8 +313,30 @@ impl Default for Header {
 }
 
 impl Header {
-    /// Builds a `Header` from local file and previously grabbed 1st line
+    /// Builds a `Header` from local file and previously grabbed 1st line.
+    /// Errors are located with [Error::Line], lines being
+    /// counted from current reader position
     pub fn new (reader: &mut BufferedReader) -> Result<Header, Error> { 
+        let mut line : usize = 0;
+        Self::new_at(reader, &mut line)
+    }
+
+    /// Builds a `Header` from any readable source (in-memory buffer,
+    /// network socket, archive entry..), see [BufferedReader::from_reader]
+    pub fn from_reader<R: std::io::Read> (reader: R) -> Result<Header, Error> {
+        let mut reader = BufferedReader::from_reader(reader)?;
+        Self::new(&mut reader)
+    }
+
+    /// Builds a `Header`, `line` being the number of lines
+    /// consumed so far: it is updated with the number of header lines
+    pub(crate) fn new_at (reader: &mut BufferedReader, line: &mut usize) -> Result<Header, Error> {
+        Self::parse(reader, line)
+            .map_err(|e| Error::Line(*line, Box::new(e)))
+    }
+
+    /// Parses header fields, counting consumed lines
+    fn parse (reader: &mut BufferedReader, line_number: &mut usize) -> Result<Header, Error> { 
         let mut crinex : Option<observation::Crinex> = None;
         let mut crnx_version = version::Version::default(); 
         let mut rinex_type = Type::default();
@@ -277,6 +359,11 @@ impl Header {
         let mut ant_sn = String::new();
         let mut ant_coords : Option<rust_3d::Point3D> = None;
         let mut ant_hen    : Option<(f32,f32,f32)> = None;
+        let mut ant_bsight : Option<rust_3d::Point3D> = None;
+        let mut ant_zero_dir : Option<rust_3d::Point3D> = None;
+        let mut ant_zero_dir_azimuth : Option<f64> = None;
+        let mut ant_phase_centers : Vec<hardware::PhaseCenter> = Vec::new();
+        let mut center_of_mass : Option<rust_3d::Point3D> = None;
         let mut rcvr       : Option<hardware::Rcvr> = None;
         // other
         let mut leap       : Option<leap::Leap> = None;
@@ -286,7 +373,14 @@ impl Header {
         let mut obs_clock_offset_applied = false;
         let mut obs_code_lines : u8 = 0; 
         let mut current_code_syst = Constellation::default(); // to keep track in multi line scenario + Mixed constell 
-        let mut obs_codes  : HashMap<Constellation, Vec<String>> = HashMap::with_capacity(10);
+        let mut obs_codes  : HashMap<Constellation, Vec<observation::Observable>> = HashMap::with_capacity(10);
+        let mut time_of_first_obs : Option<epoch::Epoch> = None;
+        let mut time_of_last_obs : Option<epoch::Epoch> = None;
+        let mut time_system : Option<observation::TimeSystem> = None;
+        let mut glo_channels : HashMap<Sv, i8> = HashMap::new();
+        let mut phase_shifts : Vec<observation::PhaseShift> = Vec::new();
+        let mut nb_satellites : Option<usize> = None;
+        let mut prn_nb_obs : Vec<(Sv, Vec<Option<u32>>)> = Vec::new();
         // (OBS/METEO)
 		let mut met_codes  : Vec<meteo::observable::Observable> = Vec::new();
 		let mut met_sensors: Vec<meteo::sensor::Sensor> = Vec::with_capacity(3);
@@ -297,17 +391,22 @@ impl Header {
         let mut clk_agency_name = String::new();
         let mut clk_station_name = String::new();
         let mut clk_station_id = String::new();
+        let mut clk_refs: Vec<clocks::ReferenceClocks> = Vec::new();
         // ANTEX
         let mut pcv : Option<antex::pcv::Pcv> = None;
         let mut ant_relative_values = String::from("AOAD/M_T");
         let mut ref_ant_sn : Option<String> = None;
         // IONEX
         let mut ionex = ionosphere::HeaderFields::default();
+        // NAV
+        let mut ionospheric_corr: Vec<navigation::IonosphericCorrection> = Vec::new();
+        let mut time_system_corr: Vec<navigation::TimeSystemCorrection> = Vec::new();
         // iterate on a line basis
         let lines = reader.lines();
         for l in lines { 
-            let line = l.unwrap();
-            if line.len() < 60 {
+            *line_number += 1;
+            let line = l?;
+            if line.len() < 60 || !line.is_char_boundary(60) {
                 continue // --> invalid header content
             }
             let (content, marker) = line.split_at(60);
@@ -393,6 +492,9 @@ impl Header {
                     // special case, sometimes GLONASS NAV
                     // drops the constellation field cause it's implied
                     constellation = Some(Constellation::Glonass)
+                } else if type_str.contains("GEO NAV MSG") {
+                    // V2 SBAS NAV special case, constellation is implied
+                    constellation = Some(Constellation::SBAS(Augmentation::default()))
                 } else if type_str.contains("METEOROLOGICAL DATA") {
                     // these files are not tied to a constellation system,
                     // therefore, do not have this field
@@ -468,8 +570,8 @@ impl Header {
                 }
             
             } else if marker.contains("ANT # / TYPE") {
-                let (model, rem) = content.split_at(20);
-                let (sn, _) = rem.split_at(20);
+                let (sn, rem) = content.split_at(20);
+                let (model, _) = rem.split_at(20);
                 ant_model = model.trim().to_string();
                 ant_sn = sn.trim().to_string();
             
@@ -510,16 +612,8 @@ impl Header {
                 }
 
             } else if marker.contains("ANTENNA: DELTA H/E/N") {
-                let (h, rem) = content.split_at(15);
-                let (e, rem) = rem.split_at(15);
-                let (n, _) = rem.split_at(15);
-                if let Ok(h) = f32::from_str(h.trim()) {
-                    if let Ok(e) = f32::from_str(e.trim()) {
-                        if let Ok(n) = f32::from_str(n.trim()) {
-                            ant_hen = Some((h, e, n))
-                        }
-                    }
-                }
+                ant_hen = parse_xyz(content)
+                    .map(|(h, e, n)| (h as f32, e as f32, n as f32));
 
             } else if marker.contains("ANTENNA: DELTA X/Y/Z") {
                 let items: Vec<&str> = content.split_ascii_whitespace()
@@ -533,14 +627,44 @@ impl Header {
                 }
 
             } else if marker.contains("ANTENNA: B.SIGHT XYZ") {
-                //TODO
+                ant_bsight = parse_xyz(content)
+                    .map(|(x, y, z)| rust_3d::Point3D::new(x, y, z));
             } else if marker.contains("ANTENNA: ZERODIR XYZ") {
-                //TODO
+                ant_zero_dir = parse_xyz(content)
+                    .map(|(x, y, z)| rust_3d::Point3D::new(x, y, z));
             } else if marker.contains("CENTER OF MASS: XYZ") {
-                //TODO
+                center_of_mass = parse_xyz(content)
+                    .map(|(x, y, z)| rust_3d::Point3D::new(x, y, z));
+            } else if marker.contains("ANTENNA: ZERODIR AZI") {
+                ant_zero_dir_azimuth = f64::from_str(content.split_at(14).0.trim())
+                    .ok();
             } else if marker.contains("ANTENNA: PHASECENTER") {
-                //TODO
+                // malformed phase centers are not fatal
+                if let Ok(center) = hardware::PhaseCenter::from_str(content) {
+                    ant_phase_centers.push(center)
+                }
             
+            } else if marker.contains("TIME OF FIRST OBS") || marker.contains("TIME OF LAST OBS") {
+                let (date, system) = content.split_at(43);
+                let epoch = epoch::Epoch::new(
+                    epoch::str2date(date)?,
+                    epoch::EpochFlag::Ok);
+                let system = system.trim();
+                if system.len() > 0 {
+                    time_system = Some(observation::TimeSystem::from_str(system)?)
+                } else if time_system.is_none() {
+                    // not specified: deduced from pure constellation.
+                    // It is mandatory in mixed files: left undefined,
+                    // see [observation::HeaderFields::time_system]
+                    time_system = constellation
+                        .and_then(observation::TimeSystem::from_constellation);
+                }
+                if marker.contains("FIRST") {
+                    time_of_first_obs = Some(epoch)
+                } else {
+                    time_of_last_obs = Some(epoch)
+                }
+
             } else if marker.contains("RCV CLOCK OFFS APPL") {
                 let value = content.split_at(20).0.trim();
                 if let Ok(n) = i32::from_str_radix(value, 10) {
@@ -548,15 +672,52 @@ impl Header {
                 }
 
             } else if marker.contains("# OF SATELLITES") {
-                // ---> we don't need this info,
-                //     user can determine it by analyzing the record
+                if let Ok(n) = usize::from_str(content.split_at(6).0.trim()) {
+                    nb_satellites = Some(n);
+                    ionex = ionex
+                        .with_satellites(n as u32)
+                }
 
             } else if marker.contains("PRN / # OF OBS") {
-                // ---> we don't need this info,
-                //     user can determine it by analyzing the record
-                 
+                // up to 9 counts per line, blank when not observed.
+                // continuation lines describe the same vehicule.
+                // malformed lines are not fatal
+                let (prn, counts) = content.split_at(6);
+                let counts = counts
+                    .as_bytes()
+                    .chunks(6)
+                    .map(|c| u32::from_str(String::from_utf8_lossy(c).trim()).ok());
+                if prn.trim().is_empty() {
+                    if let Some((_, values)) = prn_nb_obs.last_mut() {
+                        values.extend(counts)
+                    }
+                } else {
+                    let prn = prn.trim();
+                    // V2: blank system identifier means header constellation
+                    let sv = Sv::from_str(prn)
+                        .ok()
+                        .or(constellation.filter(|c| *c != Constellation::Mixed).and_then(|c| {
+                            u8::from_str(prn).ok()
+                                .map(|prn| Sv::new(c, prn))
+                        }));
+                    if let Some(sv) = sv {
+                        prn_nb_obs.push((sv, counts.collect()))
+                    }
+                }
+
             } else if marker.contains("SYS / PHASE SHIFT") {
-                //TODO
+                // continuation lines describe more vehicules.
+                // malformed corrections are not fatal
+                let continuation = content.chars().next()
+                    .map(|c| c.is_whitespace())
+                    .unwrap_or(true);
+                if continuation {
+                    if let Some(shift) = phase_shifts.last_mut() {
+                        let _ = shift.parse_vehicules(content);
+                    }
+                } else if let Ok(shift) = observation::PhaseShift::from_str(content) {
+                    phase_shifts.push(shift)
+                }
 
             } else if marker.contains("SYS / PVCS APPLIED") {
                 // RINEX::ClockData specific 
@@ -580,11 +741,14 @@ impl Header {
                             //     record builder will not produce much
                     }
                     
-                    let codes : Vec<String> = rem
+                    let codes : Vec<&str> = rem
                         .split_ascii_whitespace()
-                        .map(|r| r.trim().to_string())
                         .collect();
                     if rinex_type == Type::ObservationData {
+                        let codes = codes
+                            .iter()
+                            .map(|c| observation::Observable::from_str(c))
+                            .collect::<Result<Vec<_>, _>>()?;
                         match constellation {
                             Some(Constellation::Mixed) => {
                                 // Old RINEX + Mixed Constellation:
@@ -611,7 +775,7 @@ impl Header {
                         }
                     } else if rinex_type == Type::MeteoData {
                         for c in codes {
-                            if let Ok(o) = meteo::observable::Observable::from_str(&c) {
+                            if let Ok(o) = meteo::observable::Observable::from_str(c) {
                                 met_codes.push(o);
                             }
                         }
@@ -619,11 +783,14 @@ impl Header {
                     obs_code_lines -= 1
                 } else {
                     // Observables, 2nd, 3rd.. lines 
-                    let codes : Vec<String> = content 
+                    let codes : Vec<&str> = content 
                         .split_ascii_whitespace()
-                        .map(|r| r.trim().to_string())
                         .collect(); 
                     if rinex_type == Type::ObservationData {
+                        let codes = codes
+                            .iter()
+                            .map(|c| observation::Observable::from_str(c))
+                            .collect::<Result<Vec<_>, _>>()?;
                         // retrieve correspond system and append codes with new values 
                         let to_retrieve : Vec<Constellation> = match constellation {
                             Some(Constellation::Mixed) => {
@@ -644,7 +811,7 @@ impl Header {
                             if let Some(mut prev) = obs_codes.remove(&r) {
                                 // increment obs code map
                                 for code in &codes {
-                                    prev.push(code.to_string());
+                                    prev.push(*code);
                                 }
                                 obs_codes.insert(r, prev); // (re)insert
                             } 
@@ -652,7 +819,7 @@ impl Header {
                     } else if rinex_type == Type::MeteoData {
                         // simple append, list is simpler
                         for c in codes {
-                            if let Ok(o) = meteo::observable::Observable::from_str(&c) {
+                            if let Ok(o) = meteo::observable::Observable::from_str(c) {
                                 met_codes.push(o);
                             }
                         }
@@ -673,10 +840,10 @@ impl Header {
                             // we'll continue parsing header section,
                             // record builder won't produce much
                     }
-                    let codes : Vec<String> = rem
+                    let codes = rem
                         .split_ascii_whitespace()
-                        .map(|r| r.trim().to_string())
-                        .collect();
+                        .map(|c| observation::Observable::from_str(c))
+                        .collect::<Result<Vec<_>, _>>()?;
                     if let Ok(constell) = Constellation::from_1_letter_code(identifier) {
                         current_code_syst = constell.clone(); // to keep track,
                             // on 2nd and 3rd line, system will not be reminded
@@ -684,10 +851,10 @@ impl Header {
                     }
                 } else {
                     // 2nd, 3rd.. line of observables 
-                    let codes : Vec<String> = content
+                    let codes = content
                         .split_ascii_whitespace()
-                        .map(|r| r.trim().to_string())
-                        .collect();
+                        .map(|c| observation::Observable::from_str(c))
+                        .collect::<Result<Vec<_>, _>>()?;
                     // increment list with new codes
                     if let Some(list) = obs_codes.get_mut(&current_code_syst) {
                         // increment obs code map
@@ -723,6 +890,51 @@ impl Header {
 
             } else if marker.contains("STATION CLK REF") {
                 clk_ref = content.trim().to_string()
+
+            } else if marker.contains("# OF CLK REF") {
+                // modern revisions may exceed 60 columns
+                let body = line.split("# OF CLK REF")
+                    .next()
+                    .unwrap_or("");
+                let mut refs = clocks::ReferenceClocks::default();
+                for (period, (start, end)) in [(&mut refs.start, (6, 33)), (&mut refs.end, (33, 60))] {
+                    let date = body.get(start..end.min(body.len()))
+                        .unwrap_or("")
+                        .trim();
+                    if !date.is_empty() {
+                        *period = Some(epoch::Epoch {
+                            date: epoch::str2date(date)?,
+                            flag: epoch::EpochFlag::Ok,
+                        })
+                    }
+                }
+                clk_refs.push(refs)
+
+            } else if marker.contains("ANALYSIS CLK REF") {
+                // name, optional identifier, optional constraint
+                let body = line.split("ANALYSIS CLK REF")
+                    .next()
+                    .unwrap_or("");
+                let mut items: Vec<&str> = body.split_ascii_whitespace().collect();
+                let mut constraint: Option<f64> = None;
+                if items.len() > 1 {
+                    if let Ok(value) = f64::from_str(&items[items.len()-1].replace("D", "E")) {
+                        constraint = Some(value);
+                        items.pop();
+                    }
+                }
+                if let Some(name) = items.first() {
+                    if clk_refs.is_empty() {
+                        clk_refs.push(clocks::ReferenceClocks::default())
+                    }
+                    if let Some(refs) = clk_refs.last_mut() {
+                        refs.clocks.push(clocks::ReferenceClock {
+                            name: name.to_string(),
+                            id: items.get(1).unwrap_or(&"").to_string(),
+                            constraint,
+                        })
+                    }
+                }
          
             } else if marker.contains("SIGNAL STRENGHT UNIT") {
                 //TODO
@@ -731,29 +943,47 @@ impl Header {
                 sampling_interval = Some(f32::from_str(intv)?)
 
             } else if marker.contains("GLONASS SLOT / FRQ #") {
-                //TODO
+                // first line starts with the number of vehicules,
+                // then up to 8 (slot, channel #) pairs per line
+                let items : Vec<&str> = content.get(4..)
+                    .unwrap_or("")
+                    .split_ascii_whitespace()
+                    .collect();
+                for pair in items.chunks(2) {
+                    if pair.len() == 2 {
+                        let sv = Sv::from_str(pair[0])?;
+                        let channel = i8::from_str(pair[1])?;
+                        glo_channels.insert(sv, channel);
+                    }
+                }
             } else if marker.contains("GLONASS COD/PHS/BIS") {
                 //TODO
 
             } else if marker.contains("ION ALPHA") { 
-                //TODO
                 //0.7451D-08 -0.1490D-07 -0.5960D-07  0.1192D-06          ION ALPHA           
+                // malformed or unknown corrections are not fatal
+                let _ = navigation::corrections::parse_v2_ion(&mut ionospheric_corr, content, true);
 
             } else if marker.contains("ION BETA") {
-                //TODO
                 //0.9011D+05 -0.6554D+05 -0.1311D+06  0.4588D+06          ION BETA            
+                let _ = navigation::corrections::parse_v2_ion(&mut ionospheric_corr, content, false);
+
             } else if marker.contains("IONOSPHERIC CORR") {
-                // TODO
                 // GPSA 0.1025E-07 0.7451E-08 -0.5960E-07 -0.5960E-07
                 // GPSB 0.1025E-07 0.7451E-08 -0.5960E-07 -0.5960E-07
+                let _ = navigation::corrections::parse_v3_ionospheric_corr(&mut ionospheric_corr, content);
 
             } else if marker.contains("TIME SYSTEM CORR") {
-                // TODO
                 // GPUT 0.2793967723E-08 0.000000000E+00 147456 1395
+                if let Ok(corr) = navigation::corrections::parse_v3_time_system_corr(content) {
+                    time_system_corr.push(corr)
+                }
             
             } else if marker.contains("DELTA-UTC") {
-                //TODO
                 //0.931322574615D-09 0.355271367880D-14   233472     1930 DELTA-UTC: A0,A1,T,W
+                if let Ok(corr) = navigation::corrections::parse_v2_delta_utc(content) {
+                    time_system_corr.push(corr)
+                }
             
             } else if marker.contains("DESCRIPTION") { // IONEX description
                 ionex = ionex
@@ -766,10 +996,22 @@ impl Header {
                     ionex = ionex
                         .with_stations(u)
                 }
-            } else if marker.contains("# OF SATELLITES") { // IONEX
-                if let Ok(u) = u32::from_str_radix(content.trim(), 10) {
+            } else if marker.contains("MAP DIMENSION") { // IONEX
+                if let Ok(d) = u8::from_str(content.trim()) {
                     ionex = ionex
-                        .with_satellites(u)
+                        .with_map_dimension(d)
+                }
+            } else if marker.contains("HGT1 / HGT2 / DHGT") { // IONEX
+                if let Ok(grid) = ionosphere::Grid3dElement::from_str(content) {
+                    ionex.grid.height = grid
+                }
+            } else if marker.contains("LAT1 / LAT2 / DLAT") { // IONEX
+                if let Ok(grid) = ionosphere::Grid3dElement::from_str(content) {
+                    ionex.grid.latitude = grid
+                }
+            } else if marker.contains("LON1 / LON2 / DLON") { // IONEX
+                if let Ok(grid) = ionosphere::Grid3dElement::from_str(content) {
+                    ionex.grid.longitude = grid
                 }
             }
         }
@@ -793,12 +1035,13 @@ impl Header {
             rcvr, 
             leap,
             coords: coords,
+            center_of_mass,
             wavelengths: None,
             gps_utc_delta: None,
             sampling_interval: sampling_interval,
             data_scaling: None,
-            //ionospheric_corr: None,
-            //gnsstime_corr: None,
+            ionospheric_corr,
+            time_system_corr,
             ///////////////////////
             // Hardware
             ///////////////////////
@@ -808,15 +1051,13 @@ impl Header {
                         model: ant_model.clone(),
                         sn: ant_sn.clone(),
                         coords: ant_coords.clone(),
-                        height: {
-                            if let Some((h,_,_)) = ant_hen {
-                                Some(h)
-                            } else {
-                                None
-                            }
-                        },
-                        eastern_ecc: None, 
-                        northern_ecc: None, //TODO ant_northern_ecc.clone(),
+                        height: ant_hen.map(|(h, _, _)| h),
+                        eastern_ecc: ant_hen.map(|(_, e, _)| e),
+                        northern_ecc: ant_hen.map(|(_, _, n)| n),
+                        bsight: ant_bsight,
+                        zero_dir: ant_zero_dir,
+                        zero_dir_azimuth: ant_zero_dir_azimuth,
+                        phase_centers: ant_phase_centers.clone(),
                     })
                 } else {
                     None
@@ -831,6 +1072,24 @@ impl Header {
                         crinex: crinex.clone(),
                         codes: obs_codes.clone(),
                         clock_offset_applied: obs_clock_offset_applied,
+                        time_of_first_obs,
+                        time_of_last_obs,
+                        time_system,
+                        glo_channels: glo_channels.clone(),
+                        phase_shifts: phase_shifts.clone(),
+                        nb_satellites,
+                        nb_observations: prn_nb_obs
+                            .iter()
+                            .filter_map(|(sv, counts)| {
+                                let codes = obs_codes.get(&sv.constellation)?;
+                                let counts: HashMap<observation::Observable, u32> = codes
+                                    .iter()
+                                    .zip(counts.iter())
+                                    .filter_map(|(code, n)| n.map(|n| (*code, n)))
+                                    .collect();
+                                Some((*sv, counts))
+                            })
+                            .collect(),
                     })
                 } else {
                     None
@@ -883,6 +1142,7 @@ impl Header {
                                 None
                             }
                         },
+                        reference_clocks: clk_refs.clone(),
                     })
                 } else {
                     None
@@ -930,9 +1190,54 @@ impl Header {
     /// apply/modify accordingly
     ///TODO: data scaling special case: apply/modify accordingly
     pub fn merge_mut (&mut self, header: &Self) -> Result<(), MergeError> {
+        self.merge_with_policy_mut(header, &MergePolicy::default())
+    }
+
+    /// `Merges` self and given header, like [Header::merge_mut],
+    /// but receiver, antenna, position and observables are merged
+    /// following given [MergePolicy]. Useful when combining files
+    /// from different sessions of the same station.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::merge::{MergePolicy, FieldPolicy};
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let mut header = rinex.header.clone();
+    /// let mut other = rinex.header.clone();
+    /// other.rcvr = None;
+    /// let policy = MergePolicy {
+    ///     receiver: FieldPolicy::Intersect,
+    ///     ..MergePolicy::default()
+    /// };
+    /// header.merge_with_policy_mut(&other, &policy)
+    ///     .unwrap();
+    /// assert_eq!(header.rcvr, None);
+    /// ```
+    pub fn merge_with_policy_mut (&mut self, header: &Self, policy: &MergePolicy) -> Result<(), MergeError> {
         if self.rinex_type != header.rinex_type {
             return Err(MergeError::FileTypeMismatch)
         }
+        // type specific compatibility
+        if let (Some(a), Some(b)) = (&mut self.ionex, &header.ionex) {
+            if a.grid.latitude != b.grid.latitude || a.grid.longitude != b.grid.longitude {
+                return Err(MergeError::IonexGridMismatch)
+            }
+            // layers may complete each other
+            let height = a.grid.height.union(&b.grid.height)
+                .ok_or(MergeError::IonexGridMismatch)?;
+            if height.values().len() > 1 {
+                a.map_dimension = 3
+            } else if a.map_dimension != b.map_dimension {
+                return Err(MergeError::IonexGridMismatch)
+            }
+            a.grid.height = height
+        }
+        if let (Some(a), Some(b)) = (&self.antex, &header.antex) {
+            if a.pcv != b.pcv {
+                return Err(MergeError::AntexPcvMismatch)
+            }
+        }
 
         let (a_rev, b_rev) = (self.version, header.version);
         let (a_cst, b_cst) = (self.constellation, header.constellation);
@@ -956,31 +1261,13 @@ impl Header {
                 self.gps_utc_delta = Some(delta)
             }
         }
-        if let Some(rcvr) = &header.rcvr {
-            if self.rcvr.is_none() {
-                self.rcvr = Some(
-                    hardware::Rcvr {
-                        model: rcvr.model.clone(),
-                        sn: rcvr.sn.clone(),
-                        firmware: rcvr.firmware.clone(),
-                    }
-                )
-            }
-        }
-        if let Some(ant) = &header.ant {
-            if self.ant.is_none() {
-                self.ant = Some(
-                    hardware::Antenna {
-                        model: ant.model.clone(),
-                        sn: ant.sn.clone(),
-                        coords: ant.coords.clone(),
-                        height: ant.height,
-                        eastern_ecc: ant.eastern_ecc,
-                        northern_ecc: ant.northern_ecc,
-                    }
-                )
-            }
-        }
+        merge::merge_field(&mut self.rcvr, &header.rcvr, policy.receiver, "receiver", |a, b| a == b)?;
+        merge::merge_field(&mut self.ant, &header.ant, policy.antenna, "antenna", |a, b| {
+            a.model == b.model && a.sn == b.sn
+                && a.height == b.height
+                && a.eastern_ecc == b.eastern_ecc
+                && a.northern_ecc == b.northern_ecc
+        })?;
         //TODO append new array
         /*if let Some(a) = &header.sensors {
             if let Some(b) = &self.sensors {
@@ -993,20 +1280,101 @@ impl Header {
                 self.sensors = Some(a.to_vec())
             }
         }*/
-        if let Some(coords) = &header.coords {
-            if self.coords.is_none() {
-                self.coords = Some(rust_3d::Point3D {
-                    x: coords.x,
-                    y: coords.y,
-                    z: coords.z,
-                })
-            }
+        merge::merge_field(&mut self.coords, &header.coords, policy.position, "position", |a, b| {
+            (a.x, a.y, a.z) == (b.x, b.y, b.z)
+        })?;
+        if self.center_of_mass.is_none() {
+            self.center_of_mass = header.center_of_mass.clone()
         }
         if let Some(wavelengths) = header.wavelengths {
             if self.wavelengths.is_none() {
                 self.wavelengths = Some(wavelengths)
             }
         }
+        // NAV: corrections
+        for corr in header.ionospheric_corr.iter() {
+            if !self.ionospheric_corr.contains(corr) {
+                self.ionospheric_corr.push(corr.clone())
+            }
+        }
+        for corr in header.time_system_corr.iter() {
+            if !self.time_system_corr.contains(corr) {
+                self.time_system_corr.push(corr.clone())
+            }
+        }
+        // OBS: observables & time frame
+        if let Some(b) = &header.obs {
+            if let Some(a) = &mut self.obs {
+                merge_observables(&mut a.codes, &b.codes, policy.observables)?;
+                if let Some(b_first) = b.time_of_first_obs {
+                    a.time_of_first_obs = Some(a.time_of_first_obs.map_or(b_first, |a_first| std::cmp::min(a_first, b_first)))
+                }
+                if let Some(b_last) = b.time_of_last_obs {
+                    a.time_of_last_obs = Some(a.time_of_last_obs.map_or(b_last, |a_last| std::cmp::max(a_last, b_last)))
+                }
+                if a.time_system.is_none() {
+                    a.time_system = b.time_system
+                }
+            } else {
+                self.obs = Some(b.clone())
+            }
+        }
+        // CLOCKS: data types & descriptors
+        if let Some(b) = &header.clocks {
+            if let Some(a) = &mut self.clocks {
+                for code in b.codes.iter() {
+                    if !a.codes.contains(code) {
+                        a.codes.push(code.clone())
+                    }
+                }
+                if a.agency.is_none() {
+                    a.agency = b.agency.clone()
+                }
+                if a.station.is_none() {
+                    a.station = b.station.clone()
+                }
+                if a.clock_ref.is_none() {
+                    a.clock_ref = b.clock_ref.clone()
+                }
+                for refs in b.reference_clocks.iter() {
+                    if !a.reference_clocks.contains(refs) {
+                        a.reference_clocks.push(refs.clone())
+                    }
+                }
+            } else {
+                self.clocks = Some(b.clone())
+            }
+        }
+        // ANTEX
+        if let Some(b) = &header.antex {
+            if let Some(a) = &mut self.antex {
+                if a.reference_sn.is_none() {
+                    a.reference_sn = b.reference_sn.clone()
+                }
+            } else {
+                self.antex = Some(b.clone())
+            }
+        }
+        // IONEX: grids are identical at this point
+        if let Some(b) = &header.ionex {
+            if let Some(a) = &mut self.ionex {
+                if a.description.is_none() {
+                    a.description = b.description.clone()
+                }
+                if a.observables.is_none() {
+                    a.observables = b.observables.clone()
+                }
+                // contributors are possibly shared
+                if let Some(n) = b.n_stations {
+                    a.n_stations = Some(std::cmp::max(a.n_stations.unwrap_or(0), n))
+                }
+                if let Some(n) = b.n_satellites {
+                    a.n_satellites = Some(std::cmp::max(a.n_satellites.unwrap_or(0), n))
+                }
+            } else {
+                self.ionex = Some(b.clone())
+            }
+        }
         //TODO as mut ref
         /*if let Some(a) = &header.obs_codes {
             if let Some(&mut b) = self.obs_codes.as_ref() {
@@ -1082,12 +1450,183 @@ impl Header {
         s
     }
 
-    /// Adds receiver information to self
+    /// Adds receiver information to self, see [Header::with_receiver]
     pub fn with_rcvr (&self, r: hardware::Rcvr) -> Self {
+        self.with_receiver(r)
+    }
+
+    /// Returns a copy of self with given receiver information.
+    /// Like other `with_*` methods, this can be used to correct
+    /// metadata, prior rewriting a file:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::hardware::{Rcvr, Antenna};
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// let header = rinex.header
+    ///     .with_station("DUTH")
+    ///     .with_observer_agency("me", "my-agency")
+    ///     .with_receiver(Rcvr {
+    ///         model: String::from("SEPT POLARX5"),
+    ///         sn: String::from("3001376"),
+    ///         firmware: String::from("5.3.2"),
+    ///     })
+    ///     .with_position((4696989.6880, 723994.2070, 4239678.3040));
+    /// let rinex = rinex.with_header(header);
+    /// assert_eq!(rinex.header.observer, "me");
+    /// assert_eq!(rinex.header.rcvr.unwrap().model, "SEPT POLARX5");
+    /// ```
+    pub fn with_receiver (&self, r: hardware::Rcvr) -> Self {
         let mut s = self.clone();
         s.rcvr = Some(r);
         s
     }
+
+    /// Returns a copy of self with given revision
+    pub fn with_version (&self, version: version::Version) -> Self {
+        let mut s = self.clone();
+        s.version = version;
+        s
+    }
+
+    /// Returns a copy of self with given station (marker) name
+    pub fn with_station (&self, name: &str) -> Self {
+        let mut s = self.clone();
+        s.station = name.to_string();
+        s
+    }
+
+    /// Returns a copy of self with given station (marker) number
+    pub fn with_station_id (&self, id: &str) -> Self {
+        let mut s = self.clone();
+        s.station_id = id.to_string();
+        s
+    }
+
+    /// Returns a copy of self with given marker type
+    pub fn with_marker_type (&self, marker_type: MarkerType) -> Self {
+        let mut s = self.clone();
+        s.marker_type = Some(marker_type);
+        s
+    }
+
+    /// Returns true if the marker is fixed with respect to the Earth crust,
+    /// see [MarkerType::is_static]. Marker type is only mandatory
+    /// for moving markers: markers that do not describe it are static.
+    /// Example, keep static stations of a batch:
+    /// ```
+    /// use rinex::*;
+    /// let batch = vec![
+    ///     "../test_resources/OBS/V3/DUTH0630.22O",
+    ///     "../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx",
+    /// ];
+    /// let stations: Vec<Rinex> = batch
+    ///     .iter()
+    ///     .filter_map(|fp| Rinex::from_file(fp).ok())
+    ///     .filter(|rinex| rinex.header.is_static())
+    ///     .collect();
+    /// assert_eq!(stations.len(), 2);
+    /// ```
+    pub fn is_static (&self) -> bool {
+        self.marker_type
+            .map(|marker| marker.is_static())
+            .unwrap_or(true)
+    }
+
+    /// Returns true if the receiver is embedded in an orbiting
+    /// vehicule (LEO satellite..): its position is not fixed,
+    /// and it operates above the atmosphere
+    pub fn is_spaceborne (&self) -> bool {
+        self.marker_type == Some(MarkerType::Spaceborne)
+    }
+
+    /// Returns a copy of self with given observer and agency
+    pub fn with_observer_agency (&self, observer: &str, agency: &str) -> Self {
+        let mut s = self.clone();
+        s.observer = observer.to_string();
+        s.agency = agency.to_string();
+        s
+    }
+
+    /// Returns a copy of self with given station approximate position (ECEF [m])
+    pub fn with_position (&self, position: (f64,f64,f64)) -> Self {
+        let mut s = self.clone();
+        s.coords = Some(rust_3d::Point3D::new(position.0, position.1, position.2));
+        s
+    }
+
+    /// Returns a copy of self with given sampling interval [s]
+    pub fn with_sampling_interval (&self, interval: f32) -> Self {
+        let mut s = self.clone();
+        s.sampling_interval = Some(interval);
+        s
+    }
+
+    /// Returns a copy of self with given leap second information
+    pub fn with_leap (&self, leap: leap::Leap) -> Self {
+        let mut s = self.clone();
+        s.leap = Some(leap);
+        s
+    }
+
+    /// Regenerates the # OF SATELLITES and PRN / # OF OBS fields
+    /// from given record, so they describe the actual content.
+    /// Should be called prior production, once the record was
+    /// filtered or resampled. Has no effect on non Observation headers.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V2/zegv0010.21o")
+    ///     .unwrap();
+    /// rinex.constellation_filter_mut(vec![constellation::Constellation::GPS]);
+    /// let record = rinex.record.as_obs().unwrap();
+    /// rinex.header.rework_sat_counts(record);
+    /// let obs = rinex.header.obs.as_ref().unwrap();
+    /// assert_eq!(obs.nb_observations.keys().all(|sv| sv.constellation == constellation::Constellation::GPS), true);
+    /// ```
+    pub fn rework_sat_counts (&mut self, record: &observation::record::Record) {
+        if let Some(obs) = &mut self.obs {
+            let mut counts: BTreeMap<Sv, HashMap<observation::Observable, u32>> = BTreeMap::new();
+            for (_, (_, vehicules)) in record.iter() {
+                for (sv, observations) in vehicules.iter() {
+                    let counts = counts
+                        .entry(*sv)
+                        .or_insert_with(HashMap::new);
+                    for code in observations.keys() {
+                        *counts.entry(*code).or_insert(0) += 1
+                    }
+                }
+            }
+            obs.nb_satellites = Some(counts.len());
+            obs.nb_observations = counts;
+        }
+    }
+
+    /// Lints self against the specifications of its own revision,
+    /// see [validation::validate_header]: missing mandatory fields,
+    /// undeclared observables.. Record consistency (TIME OF FIRST OBS,
+    /// observables that are never observed) requires the whole file,
+    /// see [crate::Rinex::validate].
+    /// Returns an empty list when self complies.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// let rules: Vec<&str> = rinex.header.validate()
+    ///     .iter()
+    ///     .map(|v| v.rule)
+    ///     .collect();
+    /// assert_eq!(rules, vec!["HDR-005"]); // missing MARKER TYPE
+    /// ```
+    pub fn validate (&self) -> Vec<validation::Violation> {
+        let profile = validation::Profile::from_version(self.version)
+            .unwrap_or(validation::Profile::V3_04);
+        validation::validate_header(self, profile)
+            .into_iter()
+            .filter(|v| v.rule != validation::VERSION.id)
+            .collect()
+    }
     
     /// Adds antenna information to self
     pub fn with_antenna (&self, a: hardware::Antenna) -> Self {
@@ -1115,8 +1654,17 @@ impl std::fmt::Display for Header {
     /// `header` formatter, mainly for 
     /// `RINEX` file production purposes
     fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if self.is_crinex() {
-            // two special header lines
+        if let Some(obs) = &self.obs {
+            if let Some(crinex) = &obs.crinex {
+                // two special header lines
+                let version = format!("{}.{}", crinex.version.major, crinex.version.minor);
+                write!(f, "{:<20}", version)?;
+                write!(f, "{:<40}", "COMPACT RINEX FORMAT")?;
+                write!(f, "{}", "CRINEX VERS   / TYPE\n")?;
+                write!(f, "{:<40}", crinex.prog)?;
+                write!(f, "{:<20}", crinex.date.format("%d-%b-%y %H:%M").to_string())?;
+                write!(f, "{}", "CRINEX PROG / DATE\n")?
+            }
         }
         // RINEX VERSION / TYPE 
         write!(f, "{:6}.{:02}           ", self.version.major, self.version.minor)?;
@@ -1129,6 +1677,12 @@ impl std::fmt::Display for Header {
                         write!(f,"{:<20}", "")?;
                         write!(f,"{}", "RINEX VERSION / TYPE\n")?
                     },
+                    Some(Constellation::SBAS(_)) if self.version.major < 3 => {
+                        // V2 SBAS special case
+                        write!(f,"{:<20}", "H: GEO NAV MSG DATA")?;
+                        write!(f,"{:<20}", "")?;
+                        write!(f,"{}", "RINEX VERSION / TYPE\n")?
+                    },
                     Some(c) => {
                         write!(f,"{:<20}", "NAVIGATION DATA")?;
                         write!(f,"{:<20}", c.to_1_letter_code())?;
@@ -1152,7 +1706,14 @@ impl std::fmt::Display for Header {
                 write!(f,"{:<20}", "")?;
                 write!(f,"{:<20}", "RINEX VERSION / TYPE\n")?
             },
-            Type::ClockData => todo!(),
+            Type::ClockData => {
+                write!(f,"{:<20}", "CLOCK DATA")?;
+                match self.constellation {
+                    Some(c) => write!(f,"{:<20}", c.to_1_letter_code())?,
+                    None => write!(f,"{:<20}", "")?,
+                }
+                write!(f,"{}", "RINEX VERSION / TYPE\n")?
+            },
             Type::AntennaData => todo!(),
             Type::IonosphereMaps => todo!(),
         }
@@ -1178,23 +1739,52 @@ impl std::fmt::Display for Header {
         write!(f, "{:<20}", self.station_id)?;
         write!(f, "{:<40}", " ")?;
         write!(f, "{}", "MARKER NUMBER\n")?;
+        // MARKER TYPE: modern revisions only
+        if self.version.major > 2 {
+            if let Some(marker_type) = &self.marker_type {
+                write!(f, "{:<20}{:<40}MARKER TYPE\n", marker_type.to_string(), "")?
+            }
+        }
+        // APPROX POSITION
+        if let Some(coords) = &self.coords {
+            write!(f, "{:14.4}{:14.4}{:14.4}{:<18}", coords.x, coords.y, coords.z, "")?;
+            write!(f, "{}", "APPROX POSITION XYZ\n")?
+        }
         // ANT
         if let Some(ant) = &self.ant {
             write!(f, "{:<20}", ant.sn)?;
             write!(f, "{:<40}", ant.model)?;
             write!(f, "{}", "ANT # / TYPE\n")?;
             if let Some(coords) = &ant.coords {
-                write!(f, "{:<20}", coords.x)?;
-                write!(f, "{:<20}", coords.y)?;
-                write!(f, "{:<20}", coords.z)?;
-                write!(f, "{}", "APPROX POSITION XYZ\n")?
+                write!(f, "{:14.4}{:14.4}{:14.4}{:<18}", coords.x, coords.y, coords.z, "")?;
+                write!(f, "{}", "ANTENNA: DELTA X/Y/Z\n")?
             }
             if let Some(h) = &ant.height {
-                write!(f, "{:<20}", h)?;
-                write!(f, "{:<20}", ant.eastern_ecc.unwrap_or(0.0_f32))?;
-                write!(f, "{:<20}", ant.northern_ecc.unwrap_or(0.0_f32))?;
+                write!(f, "{:14.4}{:14.4}{:14.4}{:<18}",
+                    h,
+                    ant.eastern_ecc.unwrap_or(0.0_f32),
+                    ant.northern_ecc.unwrap_or(0.0_f32),
+                    "")?;
                 write!(f, "{}", "ANTENNA: DELTA H/E/N\n")?
             }
+            for center in ant.phase_centers.iter() {
+                write!(f, "{:<60}ANTENNA: PHASECENTER\n", center.to_string())?
+            }
+            if let Some(bsight) = &ant.bsight {
+                write!(f, "{:14.4}{:14.4}{:14.4}{:<18}", bsight.x, bsight.y, bsight.z, "")?;
+                write!(f, "{}", "ANTENNA: B.SIGHT XYZ\n")?
+            }
+            if let Some(zero_dir) = &ant.zero_dir {
+                write!(f, "{:14.4}{:14.4}{:14.4}{:<18}", zero_dir.x, zero_dir.y, zero_dir.z, "")?;
+                write!(f, "{}", "ANTENNA: ZERODIR XYZ\n")?
+            }
+            if let Some(azimuth) = &ant.zero_dir_azimuth {
+                write!(f, "{:14.4}{:<46}ANTENNA: ZERODIR AZI\n", azimuth, "")?
+            }
+        }
+        if let Some(com) = &self.center_of_mass {
+            write!(f, "{:14.4}{:14.4}{:14.4}{:<18}", com.x, com.y, com.z, "")?;
+            write!(f, "{}", "CENTER OF MASS: XYZ\n")?
         }
         // RCVR
         if let Some(rcvr) = &self.rcvr {
@@ -1213,9 +1803,17 @@ impl std::fmt::Display for Header {
         match self.rinex_type {
             Type::ObservationData => {
                 if let Some(obs) = &self.obs {
+                    // constellations are sorted like vehicules,
+                    // for deterministic output
+                    let mut constellations : Vec<&Constellation> = obs.codes.keys().collect();
+                    constellations.sort_by(|c1, c2| {
+                        c1.to_1_letter_code().cmp(c2.to_1_letter_code())
+                            .then(c1.cmp(c2))
+                    });
                     match self.version.major {
                         1|2 => { // old revisions
-                            for (_constell, codes) in obs.codes.iter() {
+                            for constell in constellations {
+                                let codes = &obs.codes[constell];
                                 let mut line = format!("{:6}", codes.len()); 
                                 for i in 0..codes.len() {
                                     if (i+1)%10 == 0 {
@@ -1233,7 +1831,8 @@ impl std::fmt::Display for Header {
                             }
                         },
                         _ => { // modern revisions
-                            for (constell, codes) in obs.codes.iter() {
+                            for constell in constellations {
+                                let codes = &obs.codes[constell];
                                 let mut line = format!("{:<4}", constell.to_1_letter_code());
                                 line.push_str(&format!("{:2}", codes.len())); 
                                 for i in 0..codes.len() {
@@ -1276,8 +1875,62 @@ impl std::fmt::Display for Header {
                     panic!("Meteo RINEX with no `obs codes` specified")
                 }
             },
+            Type::ClockData => {
+                if let Some(clocks) = &self.clocks {
+                    write_clocks_fields(f, clocks)?
+                } else {
+                    panic!("Clock RINEX with no `data types` specified")
+                }
+            },
             _ => {},
         }
+        // TIME OF FIRST / LAST OBS
+        if let Some(obs) = &self.obs {
+            // time system is mandatory in mixed files:
+            // left blank when it was never specified
+            let system = obs.time_system
+                .or(self.constellation.and_then(|c| observation::TimeSystem::from_constellation(c)));
+            if let Some(epoch) = &obs.time_of_first_obs {
+                write_time_of_obs(f, epoch, system)?;
+                write!(f, "TIME OF FIRST OBS\n")?
+            }
+            if let Some(epoch) = &obs.time_of_last_obs {
+                write_time_of_obs(f, epoch, system)?;
+                write!(f, "TIME OF LAST OBS\n")?
+            }
+            // SYS / PHASE SHIFT: modern revisions only
+            if self.version.major > 2 {
+                for shift in obs.phase_shifts.iter() {
+                    for line in shift.to_lines() {
+                        write!(f, "{:<60}SYS / PHASE SHIFT\n", line)?
+                    }
+                }
+            }
+            // GLONASS SLOT / FRQ #: modern revisions only
+            if self.version.major > 2 && obs.glo_channels.len() > 0 {
+                let mut channels : Vec<(&Sv, &i8)> = obs.glo_channels.iter().collect();
+                channels.sort();
+                for (i, chunk) in channels.chunks(8).enumerate() {
+                    let mut line = match i {
+                        0 => format!("{:3} ", channels.len()),
+                        _ => format!("{:4}", ""),
+                    };
+                    for (sv, channel) in chunk {
+                        line.push_str(&format!("{} {:2} ", sv, channel))
+                    }
+                    write!(f, "{:<60}GLONASS SLOT / FRQ #\n", line)?
+                }
+            }
+        }
+        // ION ALPHA / ION BETA: V2 NAV
+        if self.rinex_type == Type::NavigationData && self.version.major < 3 {
+            for corr in self.ionospheric_corr.iter() {
+                if let Some((alpha, beta)) = corr.to_v2_ion() {
+                    write!(f, "{:<60}ION ALPHA\n", alpha)?;
+                    write!(f, "{:<60}ION BETA\n", beta)?
+                }
+            }
+        }
         // LEAP
         if let Some(leap) = &self.leap {
             write!(f, "{:6}", leap.leap)?;
@@ -1295,6 +1948,31 @@ impl std::fmt::Display for Header {
             }
             write!(f, "LEAP SECONDS\n")?
         }
+        // # OF SATELLITES / PRN / # OF OBS
+        if let Some(obs) = &self.obs {
+            if let Some(n) = obs.nb_satellites {
+                write!(f, "{:<60}# OF SATELLITES\n", format!("{:6}", n))?
+            }
+            for (sv, counts) in obs.nb_observations.iter() {
+                let codes = match obs.codes.get(&sv.constellation) {
+                    Some(codes) => codes,
+                    None => continue,
+                };
+                for (i, chunk) in codes.chunks(9).enumerate() {
+                    let mut line = match i {
+                        0 => format!("   {}", sv),
+                        _ => format!("{:6}", ""),
+                    };
+                    for code in chunk {
+                        match counts.get(code) {
+                            Some(n) => line.push_str(&format!("{:6}", n)),
+                            None => line.push_str(&format!("{:6}", "")),
+                        }
+                    }
+                    write!(f, "{:<60}PRN / # OF OBS\n", line)?
+                }
+            }
+        }
         // SENSOR(s)
         if let Some(meteo) = &self.meteo {
             let sensors = &meteo.sensors;
@@ -1306,3 +1984,123 @@ impl std::fmt::Display for Header {
         write!(f, "{:>74}", "END OF HEADER\n")
     }
 }
+
+/// Parses 3 floating point values, whitespace separated,
+/// as found in XYZ header records
+pub(crate) fn parse_xyz (content: &str) -> Option<(f64,f64,f64)> {
+    let mut items = content.split_ascii_whitespace()
+        .map(|item| f64::from_str(item));
+    match (items.next(), items.next(), items.next()) {
+        (Some(Ok(x)), Some(Ok(y)), Some(Ok(z))) => Some((x, y, z)),
+        _ => None,
+    }
+}
+
+/// Merges observables `b` into `a`, per constellation,
+/// following given policy
+fn merge_observables (a: &mut HashMap<Constellation, Vec<observation::Observable>>, b: &HashMap<Constellation, Vec<observation::Observable>>, policy: FieldPolicy) -> Result<(), MergeError> {
+    match policy {
+        FieldPolicy::KeepSelf => {
+            for (constell, codes) in b.iter() {
+                let a_codes = a
+                    .entry(*constell)
+                    .or_insert_with(Vec::new);
+                for code in codes {
+                    if !a_codes.contains(code) {
+                        a_codes.push(*code)
+                    }
+                }
+            }
+        },
+        FieldPolicy::KeepOther => {
+            let mut codes = b.clone();
+            merge_observables(&mut codes, a, FieldPolicy::KeepSelf)?;
+            *a = codes
+        },
+        FieldPolicy::Intersect => {
+            for (constell, codes) in a.iter_mut() {
+                match b.get(constell) {
+                    Some(b_codes) => codes.retain(|code| b_codes.contains(code)),
+                    None => codes.clear(),
+                }
+            }
+            a.retain(|_, codes| !codes.is_empty())
+        },
+        FieldPolicy::Fail => {
+            for (constell, codes) in b.iter() {
+                match a.get(constell) {
+                    Some(a_codes) => {
+                        let identical = a_codes.len() == codes.len()
+                            && codes.iter().all(|code| a_codes.contains(code));
+                        if !identical {
+                            return Err(MergeError::FieldMismatch("observables"))
+                        }
+                    },
+                    None => {
+                        a.insert(*constell, codes.clone());
+                    },
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Writes TIME OF FIRST / LAST OBS content
+/// Formats a `# OF CLK REF` validity period boundary
+fn write_clk_ref_epoch (f: &mut std::fmt::Formatter, epoch: &Option<epoch::Epoch>) -> std::fmt::Result {
+    match epoch {
+        Some(epoch) => {
+            let date = epoch.date;
+            let seconds = date.time().second() as f64
+                + date.time().nanosecond() as f64 / 1.0E9;
+            write!(f, " {:4} {:02} {:02} {:2} {:2}{:10.6}",
+                date.date().year(), date.date().month(), date.date().day(),
+                date.time().hour(), date.time().minute(), seconds)
+        },
+        None => write!(f, "{:27}", ""),
+    }
+}
+
+/// Formats Clock RINEX specific fields: data types, station,
+/// analysis center and reference clocks
+fn write_clocks_fields (f: &mut std::fmt::Formatter, clocks: &clocks::HeaderFields) -> std::fmt::Result {
+    let mut line = format!("{:6}", clocks.codes.len());
+    for code in clocks.codes.iter() {
+        line.push_str(&format!("{:>6}", code.to_string()));
+    }
+    write!(f, "{:<60}# / TYPES OF DATA\n", line)?;
+    if let Some(station) = &clocks.station {
+        write!(f, "{:<4} {:<55}STATION NAME / NUM\n", station.name, station.id)?
+    }
+    if let Some(clock_ref) = &clocks.clock_ref {
+        write!(f, "{:<60}STATION CLK REF\n", clock_ref)?
+    }
+    if let Some(agency) = &clocks.agency {
+        write!(f, "{:<3}  {:<55}ANALYSIS CENTER\n", agency.code, agency.name)?
+    }
+    for refs in clocks.reference_clocks.iter() {
+        write!(f, "{:6}", refs.clocks.len())?;
+        write_clk_ref_epoch(f, &refs.start)?;
+        write_clk_ref_epoch(f, &refs.end)?;
+        write!(f, "# OF CLK REF\n")?;
+        for clock in refs.clocks.iter() {
+            let constraint = match clock.constraint {
+                Some(value) => navigation::record::format_float(value, false),
+                None => String::new(),
+            };
+            write!(f, "{:<4} {:<20}{:<15}{:>20}ANALYSIS CLK REF\n", clock.name, clock.id, "", constraint)?
+        }
+    }
+    Ok(())
+}
+
+fn write_time_of_obs (f: &mut std::fmt::Formatter, epoch: &epoch::Epoch, system: Option<observation::TimeSystem>) -> std::fmt::Result {
+    let date = epoch.date;
+    let seconds = date.time().second() as f64
+        + date.time().nanosecond() as f64 / 1.0E9;
+    write!(f, "{:6}{:6}{:6}{:6}{:6}{:13.7}{:>8}{:9}",
+        date.date().year(), date.date().month(), date.date().day(),
+        date.time().hour(), date.time().minute(), seconds,
+        system.map(|s| s.to_string()).unwrap_or_default(), "")
+}
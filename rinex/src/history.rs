@@ -0,0 +1,54 @@
+//! Processing history, described by header comments.
+//!
+//! Each operation is described by a single `COMMENT`:
+//! "rustrnx: " + description, date of operation in columns 41-60,
+//! for example "rustrnx: decimated to 30s                20221024 102531 UTC"
+
+/// History comments prefix
+pub const PREFIX: &str = "rustrnx: ";
+
+/// Maximal operation description length, to fit a `COMMENT`
+const MAX_DESCRIPTION_LEN: usize = 40 - PREFIX.len();
+
+/// Formats given operation description as a history `COMMENT` content.
+/// Descriptions exceeding 31 characters are truncated.
+pub fn comment (operation: &str, date: chrono::NaiveDateTime) -> String {
+    let description: String = operation
+        .chars()
+        .take(MAX_DESCRIPTION_LEN)
+        .collect();
+    format!("{:<41}{} UTC",
+        format!("{}{}", PREFIX, description),
+        date.format("%Y%m%d %H%M%S"))
+}
+
+/// Parses a history `COMMENT`, returns
+/// (date of operation, operation description)
+pub fn parse_comment (comment: &str) -> Option<(chrono::NaiveDateTime, String)> {
+    let content = comment.strip_prefix(PREFIX)?;
+    let offset = 41 - PREFIX.len();
+    let description = content.get(..offset)?.trim();
+    let date = content.get(offset..)?.trim();
+    let date = date.strip_suffix("UTC").unwrap_or(date).trim();
+    let date = chrono::NaiveDateTime::parse_from_str(date, "%Y%m%d %H%M%S").ok()?;
+    Some((date, description.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_history_comment() {
+        let date = chrono::NaiveDate::from_ymd(2022, 10, 24)
+            .and_hms(10, 25, 31);
+        let c = comment("decimated to 30s", date);
+        assert_eq!(c, "rustrnx: decimated to 30s                20221024 102531 UTC");
+        assert_eq!(c.len(), 60);
+        assert_eq!(parse_comment(&c), Some((date, String::from("decimated to 30s"))));
+        let c = comment("constellations: GPS, Glonass, Galileo, BeiDou", date);
+        assert_eq!(c.len(), 60);
+        let (_, description) = parse_comment(&c).unwrap();
+        assert_eq!(description, "constellations: GPS, Glonass, G");
+        assert!(parse_comment("rustrnx-0.6.0 FILE MERGE").is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! TEC interpolation at arbitrary coordinates and epochs,
+//! following the IONEX specifications: spatial bilinear interpolation
+//! and temporal interpolation between consecutive rotated maps.
+//! 3D maps are linearly interpolated in height, between surrounding layers
+use crate::epoch;
+use super::record::{self, Record, Map, Coordinates};
+
+/// Earth rotation, in degrees per second, used to rotate
+/// maps with the Sun when interpolating in time
+const ROTATION_DEG_S: f64 = 360.0 / 86400.0;
+
+/// Linear interpolation of a latitude row, at given longitude [°].
+/// Longitude is wrapped into the row longitude range
+fn interpolate_row (coords: &Coordinates, values: &[f32], lon: f64) -> Option<f64> {
+    let (lon1, lon2, dlon) = (coords.lon1 as f64, coords.lon2 as f64, coords.dlon as f64);
+    if dlon == 0.0 || values.is_empty() {
+        return None
+    }
+    let (min, max) = (lon1.min(lon2), lon1.max(lon2));
+    let mut lon = lon;
+    while lon < min {
+        lon += 360.0
+    }
+    while lon > max {
+        lon -= 360.0
+    }
+    if lon < min {
+        return None // not covered by this grid
+    }
+    if values.len() == 1 {
+        return Some(values[0] as f64)
+    }
+    let x = (lon - lon1) / dlon;
+    let i = (x.floor().max(0.0) as usize).min(values.len() - 2);
+    let p = x - i as f64;
+    Some(values[i] as f64 * (1.0 - p) + values[i + 1] as f64 * p)
+}
+
+/// Bilinear interpolation of a single layer, at given latitude and longitude [°]
+fn interpolate_layer (map: &Map, height: f32, lat: f64, lon: f64) -> Option<f64> {
+    let mut rows: Vec<&(Coordinates, Vec<f32>)> = map.iter()
+        .filter(|(coords, _)| coords.h == height)
+        .collect();
+    rows.sort_by(|(c1, _), (c2, _)| c1.lat.partial_cmp(&c2.lat).unwrap_or(std::cmp::Ordering::Equal));
+    if rows.is_empty() {
+        return None
+    }
+    if rows.len() == 1 {
+        let (coords, values) = rows[0];
+        if coords.lat as f64 != lat {
+            return None
+        }
+        return interpolate_row(coords, values, lon)
+    }
+    let (row0, row1) = rows.windows(2)
+        .find_map(|w| {
+            let (lat0, lat1) = (w[0].0.lat as f64, w[1].0.lat as f64);
+            if lat0.min(lat1) <= lat && lat <= lat0.max(lat1) {
+                Some((w[0], w[1]))
+            } else {
+                None
+            }
+        })?;
+    let (lat0, lat1) = (row0.0.lat as f64, row1.0.lat as f64);
+    let q = if lat1 == lat0 {
+        0.0
+    } else {
+        (lat - lat0) / (lat1 - lat0)
+    };
+    let v0 = interpolate_row(&row0.0, &row0.1, lon)?;
+    let v1 = interpolate_row(&row1.0, &row1.1, lon)?;
+    Some(v0 * (1.0 - q) + v1 * q)
+}
+
+/// Interpolates given map at given latitude and longitude [°],
+/// and height [km] if specified. 2D maps are interpolated whatever the height.
+/// 3D maps are interpolated on their lowest layer if height is not specified,
+/// otherwise linearly between the two surrounding layers
+fn interpolate_map (map: &Map, lat: f64, lon: f64, height: Option<f64>) -> Option<f64> {
+    let heights = record::heights(map);
+    let lowest = *heights.first()?;
+    let height = match height {
+        Some(h) if heights.len() > 1 => h,
+        _ => return interpolate_layer(map, lowest, lat, lon),
+    };
+    let (h0, h1) = heights.windows(2)
+        .find_map(|w| {
+            if w[0] as f64 <= height && height <= w[1] as f64 {
+                Some((w[0], w[1]))
+            } else {
+                None
+            }
+        })?;
+    let v0 = interpolate_layer(map, h0, lat, lon)?;
+    let v1 = interpolate_layer(map, h1, lat, lon)?;
+    let r = (height - h0 as f64) / (h1 as f64 - h0 as f64);
+    Some(v0 * (1.0 - r) + v1 * r)
+}
+
+/// Interpolates given map kind between two epochs, with rotated maps
+fn interpolate_maps<F> (record: &Record, map: F, lat: f64, lon: f64, height: Option<f64>, epoch: epoch::Epoch) -> Option<f64>
+    where F: Fn(&(Map, Option<Map>, Option<Map>)) -> Option<&Map>
+{
+    let (e0, entry0) = record.range(..=epoch).next_back()?;
+    if e0.date == epoch.date {
+        return interpolate_map(map(entry0)?, lat, lon, height)
+    }
+    let (e1, entry1) = record.range(epoch..).next()?;
+    let dt0 = (epoch.date - e0.date).num_nanoseconds()? as f64 / 1.0E9;
+    let dt1 = (e1.date - epoch.date).num_nanoseconds()? as f64 / 1.0E9;
+    let v0 = interpolate_map(map(entry0)?, lat, lon + dt0 * ROTATION_DEG_S, height)?;
+    let v1 = interpolate_map(map(entry1)?, lat, lon - dt1 * ROTATION_DEG_S, height)?;
+    Some((dt1 * v0 + dt0 * v1) / (dt0 + dt1))
+}
+
+/// Interpolates TEC [TECu] at given latitude [°], longitude [°] and epoch,
+/// from given IONEX record. Maps are bilinearly interpolated in space,
+/// then interpolated in time between the two surrounding maps,
+/// rotated to account for the Sun motion (IONEX specifications, method 3).
+/// Returns the TEC and its RMS [TECu], if RMS maps are described.
+/// Returns None if given coordinates or epoch are not covered by the record:
+/// we do not extrapolate.
+/// 3D maps are interpolated on their lowest layer, refer to [interpolate_3d].
+pub fn interpolate (record: &Record, lat: f64, lon: f64, epoch: epoch::Epoch) -> Option<(f64, Option<f64>)> {
+    let tec = interpolate_maps(record, |(tec, _, _)| Some(tec), lat, lon, None, epoch)?;
+    let rms = interpolate_maps(record, |(_, rms, _)| rms.as_ref(), lat, lon, None, epoch);
+    Some((tec, rms))
+}
+
+/// Same as [interpolate], at given height [km] as well.
+/// 3D maps (`HGT1 / HGT2 / DHGT` spanning several layers) are linearly
+/// interpolated between the two layers surrounding given height,
+/// 2D maps are interpolated whatever the height.
+/// Returns None if given height is not within the described layers.
+pub fn interpolate_3d (record: &Record, lat: f64, lon: f64, height: f64, epoch: epoch::Epoch) -> Option<(f64, Option<f64>)> {
+    let tec = interpolate_maps(record, |(tec, _, _)| Some(tec), lat, lon, Some(height), epoch)?;
+    let rms = interpolate_maps(record, |(_, rms, _)| rms.as_ref(), lat, lon, Some(height), epoch);
+    Some((tec, rms))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ionosphere::record::{build_record_entry, insert_map};
+    /// Builds a map block, values being given per latitude row
+    fn map_block (kind: &str, date: &str, rows: &[(f32, Vec<i32>)]) -> String {
+        map_block_3d(kind, date, &[(450.0, rows.to_vec())])
+    }
+    /// Builds a map block, values being given per layer and latitude row
+    fn map_block_3d (kind: &str, date: &str, layers: &[(f32, Vec<(f32, Vec<i32>)>)]) -> String {
+        let mut content = format!("{:<60}START OF {} MAP\n", "     1", kind);
+        content.push_str(&format!("{:<60}EPOCH OF CURRENT MAP\n", date));
+        for (h, rows) in layers {
+            for (lat, values) in rows {
+                content.push_str(&format!("  {:6.1}{:6.1}{:6.1}{:6.1}{:6.1}{:<28}LAT/LON1/LON2/DLON/H\n",
+                    lat, -180.0, 180.0, 90.0, h, ""));
+                let values: Vec<String> = values.iter()
+                    .map(|v| format!("{:5}", v))
+                    .collect();
+                content.push_str(&values.concat());
+                content.push('\n');
+            }
+        }
+        content.push_str(&format!("{:<60}END OF {} MAP\n", "     1", kind));
+        content
+    }
+    #[test]
+    fn test_interpolation() {
+        let mut record = Record::new();
+        let blocks = vec![
+            map_block("TEC", "  2022     1     2     0     0     0",
+                &[(10.0, vec![10, 20, 30, 40, 10]), (0.0, vec![30, 40, 50, 60, 30])]),
+            map_block("RMS", "  2022     1     2     0     0     0",
+                &[(10.0, vec![1, 1, 1, 1, 1]), (0.0, vec![3, 3, 3, 3, 3])]),
+            map_block("TEC", "  2022     1     2     2     0     0",
+                &[(10.0, vec![20, 30, 40, 50, 20]), (0.0, vec![40, 50, 60, 70, 40])]),
+        ];
+        for block in blocks.iter() {
+            let (e, map) = build_record_entry(block, -1).unwrap();
+            insert_map(&mut record, block, e, map);
+        }
+        assert_eq!(record.len(), 2);
+        let (tec, rms, _) = record.values().next().unwrap();
+        assert_eq!(tec.len(), 2);
+        assert_eq!(tec[0].0.lat, 10.0);
+        assert_eq!(tec[0].0.dlon, 90.0);
+        assert_eq!(tec[0].0.h, 450.0);
+        assert_eq!(tec[0].1, vec![1.0, 2.0, 3.0, 4.0, 1.0]);
+        assert_eq!(rms.is_some(), true);
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2022 01 02 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        // grid points
+        for (lat, lon, expected_tec, expected_rms) in [
+            (10.0, -180.0, 1.0, 0.1),
+            (0.0, 90.0, 6.0, 0.3),
+        ] {
+            let (tec, rms) = interpolate(&record, lat, lon, t0).unwrap();
+            assert!((tec - expected_tec).abs() < 1.0E-6);
+            assert!((rms.unwrap() - expected_rms).abs() < 1.0E-6);
+        }
+        // bilinear
+        let (tec, rms) = interpolate(&record, 5.0, -135.0, t0).unwrap();
+        assert!((tec - 2.5).abs() < 1.0E-6);
+        assert!((rms.unwrap() - 0.2).abs() < 1.0E-6);
+        // longitude wrapping
+        let (tec, _) = interpolate(&record, 10.0, 270.0, t0).unwrap();
+        assert!((tec - 2.0).abs() < 1.0E-6);
+        // rotated maps: one hour later, in between both maps
+        let t = t0 + chrono::Duration::hours(1);
+        let (tec, rms) = interpolate(&record, 10.0, -45.0, t).unwrap();
+        // first map is evaluated 15° eastwards, second map 15° westwards
+        let expected = (interpolate_map(&record[&t0].0, 10.0, -30.0, None).unwrap()
+            + interpolate_map(&record[&(t0 + chrono::Duration::hours(2))].0, 10.0, -60.0, None).unwrap()) / 2.0;
+        assert!((tec - expected).abs() < 1.0E-6);
+        assert_eq!(rms, None); // second epoch has no RMS map
+        // not covered
+        assert_eq!(interpolate(&record, 20.0, 0.0, t0), None);
+        assert_eq!(interpolate(&record, 5.0, 0.0, t0 - chrono::Duration::hours(1)), None);
+        assert_eq!(interpolate(&record, 5.0, 0.0, t0 + chrono::Duration::hours(3)), None);
+        // 2D maps: height is ignored
+        assert_eq!(interpolate_3d(&record, 5.0, -135.0, 1000.0, t0), interpolate(&record, 5.0, -135.0, t0));
+    }
+    #[test]
+    fn test_3d_interpolation() {
+        let mut record = Record::new();
+        let block = map_block_3d("TEC", "  2022     1     2     0     0     0", &[
+            (350.0, vec![(10.0, vec![10, 10, 10, 10, 10]), (0.0, vec![30, 30, 30, 30, 30])]),
+            (450.0, vec![(10.0, vec![20, 20, 20, 20, 20]), (0.0, vec![40, 40, 40, 40, 40])]),
+        ]);
+        let (e, map) = build_record_entry(&block, -1).unwrap();
+        insert_map(&mut record, &block, e, map);
+        let (tec, _, _) = record.values().next().unwrap();
+        assert_eq!(tec.len(), 4);
+        assert_eq!(record::heights(tec), vec![350.0, 450.0]);
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2022 01 02 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        // layers
+        let (v, rms) = interpolate_3d(&record, 10.0, 0.0, 350.0, t0).unwrap();
+        assert!((v - 1.0).abs() < 1.0E-6);
+        assert_eq!(rms, None);
+        let (v, _) = interpolate_3d(&record, 0.0, 0.0, 450.0, t0).unwrap();
+        assert!((v - 4.0).abs() < 1.0E-6);
+        // in between layers
+        let (v, _) = interpolate_3d(&record, 5.0, 0.0, 400.0, t0).unwrap();
+        assert!((v - 2.5).abs() < 1.0E-6);
+        // 2D interpolation uses the lowest layer
+        let (v, _) = interpolate(&record, 5.0, 0.0, t0).unwrap();
+        assert!((v - 2.0).abs() < 1.0E-6);
+        // not covered
+        assert_eq!(interpolate_3d(&record, 5.0, 0.0, 300.0, t0), None);
+        assert_eq!(interpolate_3d(&record, 5.0, 0.0, 500.0, t0), None);
+    }
+}
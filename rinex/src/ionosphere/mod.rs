@@ -1,9 +1,13 @@
 use std::str::FromStr;
 use strum_macros::EnumString;
+use crate::constellation::Constellation;
 pub mod record;
+pub mod interpolation;
+
+pub use interpolation::{interpolate, interpolate_3d};
 
 #[cfg(feature = "with-serde")]
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone)]
 #[derive(PartialEq, PartialOrd)]
@@ -54,6 +58,35 @@ impl Default for System {
     }
 }
 
+impl System {
+    /// Returns true if this system describes GNSS
+    /// constellation(s), and not a theoretical model
+    /// or another kind of satellite system
+    pub fn is_gnss (&self) -> bool {
+        *self == Self::GPS || *self == Self::GNS || *self == Self::MIX
+    }
+    /// Builds the GNSS system that describes
+    /// given list of constellations
+    pub fn from_constellations (constellations: &[Constellation]) -> Option<Self> {
+        let has_gps = constellations.contains(&Constellation::GPS);
+        let has_glo = constellations.contains(&Constellation::Glonass);
+        let others = constellations
+            .iter()
+            .any(|c| *c != Constellation::GPS && *c != Constellation::Glonass);
+        if others {
+            Some(Self::MIX)
+        } else if has_gps && has_glo {
+            Some(Self::GNS)
+        } else if has_gps {
+            Some(Self::GPS)
+        } else if has_glo {
+            Some(Self::GNS)
+        } else {
+            None
+        }
+    }
+}
+
 /// Grid definition element,
 /// start - end values with increment
 #[derive(Debug, Clone, Default)]
@@ -78,6 +111,68 @@ impl From<(f32,f32,f32)> for Grid3dElement {
     }
 }
 
+impl std::str::FromStr for Grid3dElement {
+    type Err = std::num::ParseFloatError;
+    /// Parses grid definition from header content (2X,3F6.1),
+    /// values are not necessarily whitespace separated
+    fn from_str (content: &str) -> Result<Self, Self::Err> {
+        let field = |i: usize| content.get(2 + i * 6..8 + i * 6)
+            .unwrap_or("")
+            .trim();
+        Ok(Self {
+            start: f32::from_str(field(0))?,
+            end: f32::from_str(field(1))?,
+            increment: f32::from_str(field(2))?,
+        })
+    }
+}
+
+impl Grid3dElement {
+    /// Returns grid values, from start to end
+    pub fn values (&self) -> Vec<f32> {
+        if self.increment == 0.0 {
+            return vec![self.start]
+        }
+        let n = ((self.end - self.start) / self.increment).round() as i32;
+        (0..=n.max(0))
+            .map(|i| self.start + i as f32 * self.increment)
+            .collect()
+    }
+    /// Returns the grid describing both grids, if such grid exists,
+    /// ie., both grids share the same increment and complete each other.
+    /// Resulting grid follows self's direction
+    pub fn union (&self, rhs: &Self) -> Option<Self> {
+        if self == rhs {
+            return Some(self.clone())
+        }
+        let mut values = self.values();
+        values.extend(rhs.values());
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        values.dedup_by(|a, b| (*a - *b).abs() < 1.0E-3);
+        let (first, last) = (values[0], values[values.len() - 1]);
+        if values.len() == 1 {
+            return Some(Self::from((first, first, 0.0)))
+        }
+        let increment = values[1] - values[0];
+        if values.windows(2).any(|w| ((w[1] - w[0]) - increment).abs() > 1.0E-3) {
+            return None
+        }
+        // both grids must be preserved, not refined
+        let refined = |grid: &Self| grid.increment != 0.0
+            && (grid.increment.abs() - increment).abs() > 1.0E-3;
+        if refined(self) || refined(rhs) {
+            return None
+        }
+        let descending = self.increment < 0.0
+            || (self.increment == 0.0 && rhs.increment < 0.0);
+        if descending {
+            Some(Self::from((last, first, -increment)))
+        } else {
+            Some(Self::from((first, last, increment)))
+        }
+    }
+}
+
 /// Grid definition in terms of
 /// latitude, longitude and altitude
 #[derive(Debug, Clone, Default)]
@@ -91,7 +186,7 @@ pub struct Grid3d {
 
 /// `IONEX` specific header fields
 #[derive(Debug, Clone, Default)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct HeaderFields {
     /// System used or theoretical model used
     pub system: System,
@@ -349,4 +444,26 @@ mod test {
         let func = MappingFunction::from_str(content);
         assert_eq!(func.is_err(), true);
     }
+    #[test]
+    fn test_grid() {
+        let grid = Grid3dElement::from_str("    87.5 -87.5  -2.5").unwrap();
+        assert_eq!(grid, Grid3dElement::from((87.5, -87.5, -2.5)));
+        assert_eq!(grid.values().len(), 71);
+        let grid = Grid3dElement::from_str("   450.0 650.0 100.0").unwrap();
+        assert_eq!(grid.values(), vec![450.0, 550.0, 650.0]);
+        let grid = Grid3dElement::from_str("   450.0 450.0   0.0").unwrap();
+        assert_eq!(grid.values(), vec![450.0]);
+        // union
+        let a = Grid3dElement::from((450.0, 550.0, 100.0));
+        let b = Grid3dElement::from((650.0, 750.0, 100.0));
+        assert_eq!(a.union(&b), Some(Grid3dElement::from((450.0, 750.0, 100.0))));
+        assert_eq!(a.union(&a), Some(a.clone()));
+        let c = Grid3dElement::from((350.0, 350.0, 0.0));
+        assert_eq!(a.union(&c), Some(Grid3dElement::from((350.0, 550.0, 100.0))));
+        let d = Grid3dElement::from((500.0, 500.0, 0.0));
+        assert_eq!(a.union(&d), None);
+        let e = Grid3dElement::from((87.5, 0.0, -2.5));
+        let f = Grid3dElement::from((0.0, -87.5, -2.5));
+        assert_eq!(e.union(&f), Some(Grid3dElement::from((87.5, -87.5, -2.5))));
+    }
 }
@@ -28,6 +28,7 @@ pub type Record = BTreeMap<epoch::Epoch, (Map, Option<Map>, Option<Map>)>;
 
 #[derive(Debug, Clone, Default)]
 #[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Coordinates {
     pub lat: f32,
     pub lon1: f32,
@@ -39,6 +40,61 @@ pub struct Coordinates {
 /// A map is a list of data indexed by Coordinates
 pub type Map = Vec<(Coordinates, Vec<f32>)>;
 
+/// Inserts given map into record, as the TEC, RMS or height map
+/// of given epoch, depending on the map block (`content`) it was parsed from
+pub fn insert_map (record: &mut Record, content: &str, epoch: epoch::Epoch, map: Map) {
+    let entry = record
+        .entry(epoch)
+        .or_insert_with(|| (Map::new(), None, None));
+    let first = content.lines()
+        .next()
+        .unwrap_or("");
+    if is_new_rms_map(first) {
+        entry.1 = Some(map)
+    } else if is_new_height_map(first) {
+        entry.2 = Some(map)
+    } else {
+        entry.0 = map
+    }
+}
+
+/// Returns heights [km] described by given map, in ascending order.
+/// 2D maps describe a single height
+pub fn heights (map: &Map) -> Vec<f32> {
+    let mut heights: Vec<f32> = Vec::new();
+    for (coords, _) in map.iter() {
+        if !heights.contains(&coords.h) {
+            heights.push(coords.h)
+        }
+    }
+    heights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    heights
+}
+
+/// Returns the layer of given map, at given height [km]
+pub fn layer (map: &Map, height: f32) -> Map {
+    map.iter()
+        .filter(|(coords, _)| coords.h == height)
+        .cloned()
+        .collect()
+}
+
+/// Merges `b` into `a`, layer per layer:
+/// rows of `b` replace the rows of `a` describing the same latitude and height,
+/// other rows are appended. Rows remain grouped by height
+pub fn merge_maps (a: &mut Map, b: &Map) {
+    for (coords, values) in b.iter() {
+        if let Some(row) = a.iter_mut()
+            .find(|(c, _)| c.lat == coords.lat && c.h == coords.h)
+        {
+            *row = (coords.clone(), values.clone())
+        } else {
+            a.push((coords.clone(), values.clone()))
+        }
+    }
+    a.sort_by(|(c1, _), (c2, _)| c1.h.partial_cmp(&c2.h).unwrap_or(std::cmp::Ordering::Equal));
+}
+
 /*
 impl Maps {
     /// Returns (properly scaled) TEC maps
@@ -93,21 +149,29 @@ pub fn build_record_entry (content: &str, exponent: i8) -> Result<(epoch::Epoch,
     let mut map = Map::new();
     let mut data :Vec<f32> = Vec::new();
     for line in lines {
-        let (content, marker) = line.split_at(60);
-        if marker.contains("LAT/LON1/LON2/DLON/H") {
+        let (content, marker) = if line.len() > 60 {
+            line.split_at(60)
+        } else {
+            (line, "")
+        };
+        if marker.contains("START OF") && marker.contains("MAP") {
+            continue
+
+        } else if marker.contains("LAT/LON1/LON2/DLON/H") {
             if data.len() > 0 {
                 // got some data buffered
                 // --> append to map being built 
                 map.push((coords.clone(), data.clone()));
             }
-            let items : Vec<&str> = content
-                .split_ascii_whitespace()
+            // 2X,5F6.1: fields are not necessarily whitespace separated
+            let items : Vec<&str> = (0..5)
+                .map(|i| content.get(2 + i * 6..8 + i * 6).unwrap_or(""))
                 .collect();
             if let Ok(lat) = f32::from_str(items[0].trim()) {
                 if let Ok(lon1) = f32::from_str(items[1].trim()) {
                     if let Ok(lon2) = f32::from_str(items[2].trim()) {
                         if let Ok(dlon) = f32::from_str(items[3].trim()) {
-                            if let Ok(h) = f32::from_str(items[3].trim()) {
+                            if let Ok(h) = f32::from_str(items[4].trim()) {
                                 coords = Coordinates {
                                     lat,
                                     lon1,
@@ -183,6 +247,36 @@ mod test {
         assert_eq!(is_new_tec_map("1                                                      START OF RMS MAP   "), false); 
     }
 
+    fn row (lat: f32, h: f32, value: f32) -> (Coordinates, Vec<f32>) {
+        (Coordinates {
+            lat,
+            lon1: -180.0,
+            lon2: 180.0,
+            dlon: 180.0,
+            h,
+        }, vec![value; 3])
+    }
+    #[test]
+    fn test_layers() {
+        let mut a: Map = vec![
+            row(10.0, 450.0, 1.0),
+            row(0.0, 450.0, 2.0),
+        ];
+        let b: Map = vec![
+            row(10.0, 350.0, 3.0),
+            row(0.0, 350.0, 4.0),
+            row(0.0, 450.0, 5.0),
+        ];
+        assert_eq!(heights(&a), vec![450.0]);
+        merge_maps(&mut a, &b);
+        assert_eq!(a.len(), 4);
+        assert_eq!(heights(&a), vec![350.0, 450.0]);
+        let bottom = layer(&a, 350.0);
+        assert_eq!(bottom, vec![row(10.0, 350.0, 3.0), row(0.0, 350.0, 4.0)]);
+        let top = layer(&a, 450.0);
+        assert_eq!(top, vec![row(10.0, 450.0, 1.0), row(0.0, 450.0, 5.0)]);
+        assert_eq!(layer(&a, 550.0).len(), 0);
+    }
     #[test]
     fn test_ionex_v1_example1() {
         let _content =
@@ -1,4 +1,6 @@
-//! Describes `leap` second information, contained in `header` 
+//! Describes `leap` second information, contained in `header`,
+//! and ships the leap second history, used when the header
+//! does not describe it
 use thiserror::Error;
 use crate::constellation;
 use crate::constellation::Constellation;
@@ -93,6 +95,73 @@ impl std::str::FromStr for Leap {
     }
 }
 
+/// Leap second history: (year, month, day) from which
+/// TAI - UTC applies, in seconds. Must be updated each time
+/// the IERS announces a new leap second (Bulletin C)
+const LEAP_SECONDS: &[(i32, u32, u32, u32)] = &[
+    (1972, 1, 1, 10),
+    (1972, 7, 1, 11),
+    (1973, 1, 1, 12),
+    (1974, 1, 1, 13),
+    (1975, 1, 1, 14),
+    (1976, 1, 1, 15),
+    (1977, 1, 1, 16),
+    (1978, 1, 1, 17),
+    (1979, 1, 1, 18),
+    (1980, 1, 1, 19),
+    (1981, 7, 1, 20),
+    (1982, 7, 1, 21),
+    (1983, 7, 1, 22),
+    (1985, 7, 1, 23),
+    (1988, 1, 1, 24),
+    (1990, 1, 1, 25),
+    (1991, 1, 1, 26),
+    (1992, 7, 1, 27),
+    (1993, 7, 1, 28),
+    (1994, 7, 1, 29),
+    (1996, 1, 1, 30),
+    (1997, 7, 1, 31),
+    (1999, 1, 1, 32),
+    (2006, 1, 1, 33),
+    (2009, 1, 1, 34),
+    (2012, 7, 1, 35),
+    (2015, 7, 1, 36),
+    (2017, 1, 1, 37),
+];
+
+/// TAI - UTC offset at the GPS time origin
+const GPST_TAI_UTC: u32 = 19;
+
+/// Returns TAI - UTC offset in seconds, at given UTC date,
+/// from the built-in leap second history.
+/// Returns None prior 1972, when UTC was not aligned to integer seconds
+pub fn tai_utc (date: chrono::NaiveDateTime) -> Option<u32> {
+    LEAP_SECONDS.iter()
+        .rev()
+        .find(|(y, m, d, _)| date >= chrono::NaiveDate::from_ymd(*y, *m, *d).and_hms(0, 0, 0))
+        .map(|(_, _, _, leap)| *leap)
+}
+
+/// Returns GPST - UTC offset in seconds, at given UTC date,
+/// from the built-in leap second history.
+/// Returns None prior GPS time origin (1980-01-06)
+pub fn gpst_utc (date: chrono::NaiveDateTime) -> Option<u32> {
+    if date < chrono::NaiveDate::from_ymd(1980, 1, 6).and_hms(0, 0, 0) {
+        return None
+    }
+    tai_utc(date)
+        .map(|leap| leap - GPST_TAI_UTC)
+}
+
+/// Returns GPST - UTC offset in seconds, at given GPST date,
+/// from the built-in leap second history
+pub(crate) fn gpst_utc_from_gpst (date: chrono::NaiveDateTime) -> Option<u32> {
+    let leap = gpst_utc(date)?;
+    // date is ahead of UTC: evaluate at the UTC date,
+    // to get transitions right
+    gpst_utc(date - chrono::Duration::seconds(leap as i64))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -110,4 +179,22 @@ mod test {
         let leap = Leap::from_str(content); 
         assert_eq!(leap.is_ok(), true);
     }
+    #[test]
+    fn test_history() {
+        let date = chrono::NaiveDate::from_ymd(1971, 12, 31).and_hms(0, 0, 0);
+        assert_eq!(tai_utc(date), None);
+        assert_eq!(gpst_utc(date), None);
+        let date = chrono::NaiveDate::from_ymd(1980, 1, 6).and_hms(0, 0, 0);
+        assert_eq!(gpst_utc(date), Some(0));
+        let date = chrono::NaiveDate::from_ymd(2016, 12, 31).and_hms(23, 59, 59);
+        assert_eq!(gpst_utc(date), Some(17));
+        let date = chrono::NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 0);
+        assert_eq!(tai_utc(date), Some(37));
+        assert_eq!(gpst_utc(date), Some(18));
+        // 2017-01-01T00:00:10 GPST is still 2016 in UTC
+        let date = chrono::NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 10);
+        assert_eq!(gpst_utc_from_gpst(date), Some(17));
+        let date = chrono::NaiveDate::from_ymd(2017, 1, 1).and_hms(0, 0, 18);
+        assert_eq!(gpst_utc_from_gpst(date), Some(18));
+    }
 }
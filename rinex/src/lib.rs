@@ -3,33 +3,51 @@
 //! Refer to README and official documentation, extensive examples of use
 //! are provided.  
 //! Homepage: <https://github.com/gwbres/rinex>
-mod leap;
-mod merge;
+mod lzw;
 mod formatter;
-//mod gnss_time;
 
 pub mod antex;
+pub mod builder;
+pub mod cache;
 pub mod channel;
 pub mod clocks;
 pub mod constellation;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod convert;
 pub mod epoch;
+pub mod gnss_time;
 pub mod hardware;
 pub mod hatanaka;
 pub mod header;
+pub mod history;
 pub mod ionosphere;
+pub mod leap;
+pub mod merge;
+pub mod naming;
 pub mod meteo;
 pub mod navigation;
 pub mod observation;
+pub mod parser;
+pub mod prelude;
+pub mod qc;
 pub mod record;
+pub mod sp3;
 pub mod sv;
 pub mod types;
+pub mod validation;
 pub mod version;
 pub mod reader;
+pub mod writer;
+pub mod recover;
+pub mod report;
 
 use reader::BufferedReader;
-use std::io::{Read, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use writer::BufferedWriter;
+use std::io::{Read, Write, BufRead};
 
 use thiserror::Error;
+use std::str::FromStr;
 use chrono::{Datelike, Timelike};
 use std::collections::{BTreeMap, HashMap};
 
@@ -94,6 +112,7 @@ fn hourly_session_str (time: chrono::NaiveTime) -> String {
 
 /// `Rinex` describes a `RINEX` file
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Rinex {
     /// `header` field contains general information
     pub header: header::Header,
@@ -101,6 +120,19 @@ pub struct Rinex {
     /// found in `record` section exclusively.    
     /// Comments extracted from `header` sections are exposed in `header.comments`
     pub comments: record::Comments, 
+    /// Site events (new occupation, antenna moved..) described
+    /// by special records in Observation records, see [Rinex::site_events]
+    pub events: observation::SiteEvents,
+    /// Warnings emitted while parsing the record,
+    /// located by line number and epoch when possible
+    pub warnings: parser::Diagnostics,
+    /// Record entries this library could not interpret,
+    /// stored as is when parsing with [parser::UnknownConstellation::KeepRaw]
+    pub raw: parser::RawRecord,
+    /// When set, operations that modify `self` (decimation, filters..)
+    /// append a [history] `COMMENT` describing them to the header,
+    /// see [Rinex::with_history]
+    pub history: bool,
     /// `record` contains `RINEX` file body
     /// and is type and constellation dependent 
     pub record: record::Record,
@@ -111,12 +143,25 @@ impl Default for Rinex {
     fn default() -> Rinex {
         Rinex {
             header: header::Header::default(),
-            comments: record::Comments::new(), 
+            comments: record::Comments::new(),
+            events: observation::SiteEvents::new(), 
+            warnings: Vec::new(),
+            raw: parser::RawRecord::new(),
+            history: false,
             record: record::Record::default(), 
         }
     }
 }
 
+impl<'a> IntoIterator for &'a Rinex {
+    type Item = (&'a epoch::Epoch, record::RecordEntry<'a>);
+    type IntoIter = record::EpochIter<'a>;
+    /// Iterates over all epochs, see [Rinex::epochs_iter]
+    fn into_iter (self) -> Self::IntoIter {
+        self.epochs_iter()
+    }
+}
+
 #[derive(Error, Debug)]
 /// `RINEX` Parsing related errors
 pub enum Error {
@@ -126,6 +171,14 @@ pub enum Error {
     RecordError(#[from] record::Error),
     #[error("file i/o error")]
     IoError(#[from] std::io::Error),
+    #[error("header is truncated: first line is too short")]
+    TruncatedHeader,
+    #[error("header is not valid utf-8")]
+    InvalidEncoding,
+    #[error("no file to load")]
+    NoFiles,
+    #[error("failed to merge files")]
+    MergeError(#[from] merge::MergeError),
 }
 
 #[derive(Error, Debug)]
@@ -144,27 +197,106 @@ impl Rinex {
             header,
             record,
             comments: record::Comments::new(),
+            events: observation::SiteEvents::new(),
+            warnings: Vec::new(),
+            raw: parser::RawRecord::new(),
+            history: false,
         }
     }
 
-    /// Returns a copy of self but with given header attributes
+    /// Returns a copy of self but with given header attributes.
+    /// This is logged as a "header edit" operation, see [Rinex::with_history]
     pub fn with_header (&self, header: header::Header) -> Self {
-        Rinex {
+        let mut s = Rinex {
             header,
             record: self.record.clone(),
             comments: self.comments.clone(),
+            events: self.events.clone(),
+            warnings: self.warnings.clone(),
+            raw: self.raw.clone(),
+            history: self.history,
+        };
+        s.log_operation("header edit");
+        s
+    }
+
+    /// Returns a copy of self that records its processing history:
+    /// operations that modify it (decimation, filters..) will
+    /// append a timestamped `COMMENT` describing them to the header,
+    /// so produced files carry an auditable processing trail.
+    /// Merge operations are always marked, see [merge::native_marker].
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap()
+    ///     .with_history(true);
+    /// rinex.decimate_by_ratio_mut(2);
+    /// let history = rinex.processing_history();
+    /// assert_eq!(history.len(), 1);
+    /// assert_eq!(history[0].1, "decimated by ratio 2");
+    /// ```
+    pub fn with_history (&self, history: bool) -> Self {
+        let mut s = self.clone();
+        s.history = history;
+        s
+    }
+
+    /// Returns processing history of this file, as described
+    /// by [history] header comments: (date of operation, description),
+    /// in chronological order.
+    pub fn processing_history (&self) -> Vec<(chrono::NaiveDateTime, String)> {
+        self.header.comments
+            .iter()
+            .filter_map(|c| history::parse_comment(c))
+            .collect()
+    }
+
+    /// Appends given operation to the processing history,
+    /// if history is enabled
+    fn log_operation (&mut self, operation: &str) {
+        if self.history {
+            let now = epoch::now();
+            self.header.comments.push(history::comment(operation, now));
         }
     }
 
     /// Converts self to CRINEX compatible format.
-    /// This is useful in case we parsed some compressed
-    /// data that we want to uncompress.
-    /// This has no effect if self is not an Observation RINEX,
+    /// Record will then be compressed when produced with `to_file`.
+    /// This has no effect if self is not a modern (V3+) Observation RINEX,
     /// because it is not clear to this day, if CRINEX compression
-    /// is feasible on other types of RINEX.
+    /// is feasible on other types of RINEX, and only CRINEX3 is
+    /// supported by our compressor.
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// rinex.rnx2crx();
+    /// assert_eq!(rinex.header.is_crinex(), true);
+    /// ```
+    pub fn rnx2crx (&mut self) {
+        if self.is_observation_rinex() && self.header.version.major > 2 {
+            let now = epoch::now();
+            self.header = self.header
+                .with_crinex(
+                    observation::Crinex {
+                        version: version::Version {
+                            major: 3, // latest CRINEX
+                            minor: 0, // latest CRINEX
+                        },
+                        prog: "rustcrx".to_string(),
+                        date: now.date().and_time(now.time()),
+                    })
+        }
+    }
+
+    /// Converts self to CRINEX compatible format.
+    /// This has no effect if self is not an Observation RINEX.
+    #[deprecated(note = "use rnx2crx")]
     pub fn crx2rnx (&mut self) {
         if self.is_observation_rinex() {
-            let now = chrono::Utc::now().naive_utc();
+            let now = epoch::now();
             self.header = self.header
                 .with_crinex(
                     observation::Crinex {
@@ -178,26 +310,112 @@ impl Rinex {
         }
     }
 
+    /// Converts self to uncompressed RINEX.
+    /// This is useful in case we parsed some compressed
+    /// data that we want to produce uncompressed.
+    /// This has no effect if self is not a CRINEX.
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/CRNX/V3/ACOR00ESP_R_20213550000_01D_30S_MO.crx")
+    ///     .unwrap();
+    /// assert_eq!(rinex.header.is_crinex(), true);
+    /// rinex.to_uncompressed_mut();
+    /// assert_eq!(rinex.header.is_crinex(), false);
+    /// ```
+    pub fn to_uncompressed_mut (&mut self) {
+        if let Some(obs) = self.header.obs.as_mut() {
+            obs.crinex = None
+        }
+    }
+
     /// Returns filename that would respect naming conventions,
-    /// based on self attributes
+    /// based on self attributes. See [Rinex::filename_with_options]
     pub fn filename (&self) -> String {
+        self.filename_with_options(&naming::Options::default())
+    }
+
+    /// Returns filename that would respect naming conventions,
+    /// based on self attributes and given `opts`, that describe
+    /// long file name attributes that cannot be deduced from the content.
+    /// V2 (and IONEX, ANTEX) files follow the short naming convention,
+    /// V3+ files follow the long naming convention.
+    /// Day of year, file period and sampling frequency are deduced from the record.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::naming::Options;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let opts = Options::default()
+    ///     .with_country("ESP");
+    /// assert_eq!(rinex.filename_with_options(&opts), "ACOR00ESP_R_20213550000_13M_30S_MO.rnx");
+    /// ```
+    pub fn filename_with_options (&self, opts: &naming::Options) -> String {
         let header = &self.header;
         let rtype = header.rinex_type;
-        let nnnn = header.station.as_str()[0..4].to_lowercase(); 
-        //TODO:
-        //self.header.date should be a datetime object
-        //but it is complex to parse..
-        let ddd = String::from("DDD"); 
-        let epoch : epoch::Epoch = match rtype {
-              types::Type::ObservationData 
-            | types::Type::NavigationData 
-            | types::Type::MeteoData 
-            | types::Type::ClockData => self.epochs()[0],
-            _ => todo!(), // other files require a dedicated procedure
+        let first = self.first_epoch()
+            .map(|e| e.date)
+            .unwrap_or_else(epoch::now);
+        let ddd = format!("{:03}", first.ordinal());
+        let yy = format!("{:02}", first.year() % 100);
+        // sampling interval: header or smallest epoch interval
+        let interval: Option<f64> = match header.sampling_interval {
+            Some(interval) => Some(interval as f64),
+            None => {
+                self.epochs()
+                    .windows(2)
+                    .map(|w| w[1].date - w[0].date)
+                    .filter(|dt| *dt > chrono::Duration::zero())
+                    .min()
+                    .and_then(|dt| dt.num_nanoseconds())
+                    .map(|ns| ns as f64 * 1.0E-9)
+            },
+        };
+        // file period: covered time span, last epoch included
+        let period: Option<chrono::Duration> = match (self.first_epoch(), self.last_epoch()) {
+            (Some(first), Some(last)) => {
+                let dt = interval
+                    .map(|dt| chrono::Duration::nanoseconds((dt * 1.0E9) as i64))
+                    .unwrap_or(chrono::Duration::zero());
+                Some(last.date - first.date + dt)
+            },
+            _ => None,
         };
+        let daily = period
+            .map(|period| period > chrono::Duration::hours(1))
+            .unwrap_or(true);
+        // 4 character site or agency identifier
+        let site = |name: &str| -> String {
+            let name: String = name.trim()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .take(4)
+                .collect();
+            format!("{:X<4}", name)
+        };
+        match rtype {
+            types::Type::AntennaData => {
+                return format!("{}.atx", site(&header.agency).to_lowercase())
+            },
+            types::Type::IonosphereMaps => {
+                // CCCGDDDH.YYI: analysis center, global map, session
+                let ccc: String = site(&header.agency).chars().take(3).collect();
+                let s = match daily {
+                    true => String::from("0"),
+                    false => hourly_session_str(first.time()),
+                };
+                return format!("{}g{}{}.{}i", ccc.to_lowercase(), ddd, s, yy)
+            },
+            _ => {},
+        }
         if header.version.major < 3 {
-            let s = hourly_session_str(epoch.date.time());
-            let yy = format!("{:02}", epoch.date.year());
+            let nnnn = site(&header.station).to_lowercase();
+            let s = match daily {
+                true => String::from("0"),
+                false => hourly_session_str(first.time()),
+            };
             let t : String = match rtype {
                 types::Type::ObservationData => {
                     if header.is_crinex() {
@@ -207,83 +425,126 @@ impl Rinex {
                     }
                 },
                 types::Type::NavigationData => {
-                    if let Some(c) = header.constellation {
-                        if c == constellation::Constellation::Glonass {
-                            String::from("g")
-                        } else { 
-                            String::from("n")
-                        }
-                    } else {
-                        String::from("x")
+                    match header.constellation {
+                        Some(constellation::Constellation::GPS) => String::from("n"),
+                        Some(constellation::Constellation::Glonass) => String::from("g"),
+                        Some(constellation::Constellation::Galileo) => String::from("l"),
+                        Some(constellation::Constellation::SBAS(_)) => String::from("h"),
+                        Some(constellation::Constellation::Mixed) => String::from("p"),
+                        _ => String::from("n"),
                     }
                 },
                 types::Type::MeteoData => String::from("m"),
-                _ => todo!(),
+                _ => {
+                    // IGS clock products: CCCWWWWD.clk
+                    let ccc: String = site(&header.agency).chars().take(3).collect();
+                    let gpst = chrono::NaiveDate::from_ymd(1980, 1, 6).and_hms(0, 0, 0);
+                    let days = (first - gpst).num_days();
+                    return format!("{}{:04}{}.clk", ccc.to_lowercase(), days / 7, days % 7)
+                },
             };
             format!("{}{}{}.{}{}", nnnn, ddd, s, yy, t)
         } else {
-            let m = String::from("0");
-            let r = String::from("0");
-            //TODO: 3 letter contry code, example: "GBR"
-            let ccc = String::from("CCC");
-            //TODO: data source
-            // R: Receiver (hw)
-            // S: Stream
-            // U: Unknown
-            let s = String::from("R");
-            let yyyy = format!("{:04}", epoch.date.year());
-            let hh = format!("{:02}", epoch.date.hour());
-            let mm = format!("{:02}", epoch.date.minute());
-            let pp = String::from("00"); //TODO 02d file period, interval ?
-            let up = String::from("H"); //TODO: file period unit
-            let ff = String::from("00"); //TODO: 02d observation frequency 02d
-            //TODO
-            //Units of frequency FF. “C” = 100Hz; “Z” = Hz; “S” = sec; “M” = min;
-            //“H” = hour; “D” = day; “U” = unspecified
-            //NB - _FFU is omitted for files containing navigation data
-            let uf = String::from("Z");
+            let nnnn = site(&header.station).to_uppercase();
+            let m = opts.monument.min(9);
+            let r = opts.receiver.min(9);
+            let ccc = opts.country.to_uppercase();
+            let yyyy = format!("{:04}", first.year());
+            let hh = format!("{:02}", first.hour());
+            let mm = format!("{:02}", first.minute());
+            let ppu = naming::period_code(period);
+            let ffu = naming::frequency_code(interval);
             let c : String = match header.constellation {
                 Some(c) => c.to_1_letter_code().to_uppercase(),
-                _ => String::from("X"),
-            };
-            let t : String = match rtype {
-                types::Type::ObservationData => String::from("O"),
-                types::Type::NavigationData => String::from("N"),
-                types::Type::MeteoData => String::from("M"),
-                types::Type::ClockData => todo!(),
-                types::Type::AntennaData => todo!(),
-                types::Type::IonosphereMaps => todo!(),
+                _ => String::from("M"),
             };
             let fmt = match header.is_crinex() {
                 true => String::from("crx"),
                 false => String::from("rnx"),
             };
-            format!("{}{}{}{}_{}_{}{}{}{}_{}{}_{}{}_{}{}.{}",
-                nnnn, m, r, ccc, s, yyyy, ddd, hh, mm, pp, up, ff, uf, c, t, fmt)
+            match rtype {
+                types::Type::NavigationData => {
+                    // _FFU is omitted for navigation data
+                    format!("{}{}{}{}_{}_{}{}{}{}_{}_{}N.{}",
+                        nnnn, m, r, ccc, opts.source, yyyy, ddd, hh, mm, ppu, c, fmt)
+                },
+                types::Type::ClockData => {
+                    format!("{}{}{}{}_{}_{}{}{}{}_{}_{}_CLK.clk",
+                        nnnn, m, r, ccc, opts.source, yyyy, ddd, hh, mm, ppu, ffu)
+                },
+                _ => {
+                    let t = match rtype {
+                        types::Type::MeteoData => "MM".to_string(),
+                        _ => format!("{}O", c),
+                    };
+                    format!("{}{}{}{}_{}_{}{}{}{}_{}_{}_{}.{}",
+                        nnnn, m, r, ccc, opts.source, yyyy, ddd, hh, mm, ppu, ffu, t, fmt)
+                },
+            }
         }
     }
 
-    /// Builds a `RINEX` from given file.
-    /// Header section must respect labelization standards, 
-    /// some are mandatory.   
-    /// Parses record (file body) for supported `RINEX` types.
-    pub fn from_file (path: &str) -> Result<Rinex, Error> {
+    /// Builds one `RINEX` per given file, see [Rinex::from_file].
+    /// Results are returned in the order files were given.
+    /// Files are parsed in parallel when compiled with the `with-rayon` feature,
+    /// sequentially otherwise.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let results = Rinex::from_files(&[
+    ///     "../test_resources/OBS/V2/kinematic.obs",
+    ///     "../test_resources/OBS/V2/does-not-exist.obs",
+    /// ]);
+    /// assert_eq!(results.len(), 2);
+    /// assert_eq!(results[0].is_ok(), true);
+    /// assert_eq!(results[1].is_err(), true);
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_files (paths: &[&str]) -> Vec<Result<Rinex, Error>> {
+        #[cfg(feature = "with-rayon")] {
+            use rayon::prelude::*;
+            paths
+                .par_iter()
+                .map(|path| Self::from_file(path))
+                .collect()
+        }
+        #[cfg(not(feature = "with-rayon"))] {
+            paths
+                .iter()
+                .map(|path| Self::from_file(path))
+                .collect()
+        }
+    }
+
+    /// Builds a single `RINEX` from given files, typically consecutive
+    /// hourly files of a station, see [Rinex::from_files] and [Rinex::merge_mut].
+    /// Files are merged in the order they were given, headers are combined.
+    /// Fails on the first file that could not be parsed or merged.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_files_merged (paths: &[&str]) -> Result<Rinex, Error> {
+        let mut results = Self::from_files(paths)
+            .into_iter();
+        let mut rinex = match results.next() {
+            Some(result) => result?,
+            None => return Err(Error::NoFiles),
+        };
+        for result in results {
+            rinex.merge_mut(&result?)?;
+        }
+        Ok(rinex)
+    }
+
+    /// Opens given file for record browsing,
+    /// with possible .gz and CRINEX decompression
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_reader (path: &str) -> Result<BufferedReader, Error> {
         // Grab first 80 bytes to fully determine the BufferedReader attributes.
         // We use the `BufferedReader` wrapper for efficient file browsing (.lines())
         // and at the same time, integrated (hidden in .lines() iteration) decompression.
         let mut reader = BufferedReader::new(path)?;
         let mut buffer = [0; 80]; // 1st line mandatory size
-        let mut line = String::new(); // first line
-        if let Ok(n) = reader.read(&mut buffer[..]) {
-            if n < 80 {
-                panic!("corrupt header 1st line")
-            }
-            if let Ok(s) = String::from_utf8(buffer.to_vec()) {
-                line = s.clone()
-            } else {
-                panic!("header 1st line is not valid Utf8 encoding")
-            }
-        }
+        let n = reader.read(&mut buffer[..])?;
+        let crinex = Self::is_crinex(&buffer[..n])?;
 
 /*
  *      deflate (.gzip) fd pointer does not work / is not fully supported
@@ -297,27 +558,310 @@ impl Rinex {
         let mut reader = BufferedReader::new(path)?;
 
         // create buffered reader
-        if line.contains("CRINEX") {
+        if crinex {
             // --> enhance buffered reader
             //     with hatanaka M capacity
             reader = reader.with_hatanaka(8)?; // M = 8 is more than enough
         }
+        Ok(reader)
+    }
+
+    /// Identifies CRINEX content from its first line,
+    /// rejects content that cannot be a `RINEX`
+    fn is_crinex (content: &[u8]) -> Result<bool, Error> {
+        if content.len() < 80 { // 1st line mandatory size
+            return Err(Error::TruncatedHeader)
+        }
+        let line = std::str::from_utf8(&content[..80])
+            .map_err(|_| Error::InvalidEncoding)?;
+        Ok(line.contains("CRINEX"))
+    }
+
+    /// Builds a `RINEX` from any readable source: in-memory buffer,
+    /// network socket, archive entry.. Content is read to completion.
+    /// Like [Rinex::from_file], CRINEX, .Z and gzip (`with-gzip` feature)
+    /// compressed content is supported, and parsing warnings are exposed in `warnings`.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let content = std::fs::read("../test_resources/OBS/V2/kinematic.obs")
+    ///     .unwrap();
+    /// let rinex = Rinex::from_reader(&content[..])
+    ///     .unwrap();
+    /// assert_eq!(rinex.is_observation_rinex(), true);
+    /// ```
+    pub fn from_reader<R: Read> (reader: R) -> Result<Rinex, Error> {
+        let mut reader = BufferedReader::from_reader(reader)?;
+        if Self::is_crinex(reader.fill_buf()?)? {
+            reader = reader.with_hatanaka(8)?; // M = 8 is more than enough
+        }
+        let mut line = 0;
+        let header = header::Header::new_at(&mut reader, &mut line)?;
+        let (record, comments, events, warnings, raw) = record::build_record_at(&mut reader, &header, &parser::ParserOptions::default(), &mut hatanaka::Decompressor::new(8), line)?;
+        Ok(Rinex {
+            header,
+            record,
+            comments,
+            events,
+            warnings,
+            raw,
+            history: false,
+        })
+    }
 
+    /// Builds a `RINEX` from given byte slice, like the content
+    /// of a file uploaded in a browser. This is the entry point
+    /// for `wasm32-unknown-unknown` builds, where file based
+    /// methods like [Rinex::from_file] are not available.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let content = std::fs::read("../test_resources/NAV/V2/amel0010.21g")
+    ///     .unwrap();
+    /// let rinex = Rinex::from_bytes(&content)
+    ///     .unwrap();
+    /// assert_eq!(rinex.is_navigation_rinex(), true);
+    /// ```
+    pub fn from_bytes (content: &[u8]) -> Result<Rinex, Error> {
+        Self::from_reader(content)
+    }
+
+    /// Builds a `RINEX` from given asynchronous source, like an uploaded file
+    /// in a web service, without blocking a thread while content is being received.
+    /// Content is read to completion, then parsed like [Rinex::from_reader].
+    /// Requires the `async` build feature.
+    /// Example:
+    /// ```
+    /// # #[cfg(feature = "async")]
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// use rinex::*;
+    /// let content = std::fs::read("../test_resources/OBS/V2/kinematic.obs")
+    ///     .unwrap();
+    /// let rinex = Rinex::from_async_reader(&content[..])
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(rinex.is_observation_rinex(), true);
+    /// # });
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn from_async_reader<R: tokio::io::AsyncBufRead + Unpin> (mut reader: R) -> Result<Rinex, Error> {
+        use tokio::io::AsyncReadExt;
+        let mut content : Vec<u8> = Vec::new();
+        reader.read_to_end(&mut content).await?;
+        Self::from_reader(&content[..])
+    }
+
+    /// Builds a `RINEX` from given file.
+    /// Header section must respect labelization standards, 
+    /// some are mandatory.   
+    /// Parses record (file body) for supported `RINEX` types.
+    /// Corrupt files (truncated header, invalid encoding, faulty
+    /// header fields..) are reported as [Error].
+    /// Malformed record entries are skipped, see [Rinex::from_file_with_options]
+    /// to have them reported.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file (path: &str) -> Result<Rinex, Error> {
+        let mut reader = Self::open_reader(path)?;
         // --> parse header fields 
-        let header = header::Header::new(&mut reader)
-            .unwrap();
+        let mut line = 0;
+        let header = header::Header::new_at(&mut reader, &mut line)?;
         // --> parse record (file body)
         //     we also grab encountered comments,
         //     they might serve some fileops like `splice` / `merge` 
-        let (record, comments) = record::build_record(&mut reader, &header)
-            .unwrap();
+        let (record, comments, events, _, _) = record::build_record_at(&mut reader, &header, &parser::ParserOptions::default(), &mut hatanaka::Decompressor::new(8), line)?;
+        Ok(Rinex {
+            header,
+            record,
+            comments,
+            events,
+            warnings: Vec::new(),
+            raw: parser::RawRecord::new(),
+            history: false,
+        })
+    }
+
+    /// Builds a `RINEX` from given file, with given parsing options.
+    /// Unlike [Rinex::from_file], parsing warnings are exposed in `warnings`:
+    /// by default, malformed record entries (truncated epochs..) are skipped
+    /// and reported there, unless [parser::ParserOptions::strict] is set.
+    /// Strict parsing also rejects mixed Observation files that do not
+    /// specify the time system of TIME OF FIRST/LAST OBS.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::parser::{ParserOptions, UnknownConstellation};
+    /// let opts = ParserOptions {
+    ///     unknown_constellation: UnknownConstellation::KeepRaw,
+    ///     strict: false,
+    /// };
+    /// let rinex = Rinex::from_file_with_options("../test_resources/OBS/V3/DUTH0630.22O", &opts)
+    ///     .unwrap();
+    /// for warning in rinex.warnings.iter() {
+    ///     println!("{}", warning);
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file_with_options (path: &str, opts: &parser::ParserOptions) -> Result<Rinex, Error> {
+        let (rinex, _) = Self::from_file_with_statistics(path, opts)?;
+        Ok(rinex)
+    }
+
+    /// Same as [Rinex::from_file_with_options], also returns the
+    /// [hatanaka::Statistics] gathered while decompressing a CRINEX file.
+    /// Statistics are `None` if the file is not Hatanaka compressed.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::parser::ParserOptions;
+    /// let (rinex, stats) = Rinex::from_file_with_statistics(
+    ///     "../test_resources/CRNX/V3/ACOR00ESP_R_20213550000_01D_30S_MO.crx",
+    ///     &ParserOptions::default())
+    ///     .unwrap();
+    /// assert_eq!(rinex.header.is_crinex(), true);
+    /// let stats = stats.unwrap();
+    /// assert!(stats.ratio() < 1.0);
+    /// for epoch in stats.poorly_compressed(0.5) {
+    ///     println!("{} compressed poorly", epoch);
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file_with_statistics (path: &str, opts: &parser::ParserOptions) -> Result<(Rinex, Option<hatanaka::Statistics>), Error> {
+        let mut reader = Self::open_reader(path)?;
+        let mut line = 0;
+        let header = header::Header::new_at(&mut reader, &mut line)?;
+        let mut decompressor = hatanaka::Decompressor::new(8);
+        let (record, comments, events, mut warnings, raw) = record::build_record_at(&mut reader, &header, opts, &mut decompressor, line)?;
+        if let Some(obs) = &header.obs {
+            // time system is mandatory in mixed files
+            let dated = obs.time_of_first_obs.is_some() || obs.time_of_last_obs.is_some();
+            if dated && obs.time_system.is_none() {
+                if opts.strict {
+                    return Err(Error::HeaderError(header::Error::MissingTimeSystem))
+                }
+                warnings.insert(0, parser::Diagnostic {
+                    line: None,
+                    epoch: None,
+                    message: String::from("TIME OF FIRST/LAST OBS: time system not specified in mixed file"),
+                });
+            }
+        }
+        let statistics = match header.is_crinex() {
+            true => Some(decompressor.statistics().clone()),
+            false => None,
+        };
+        let rinex = Rinex {
+            header,
+            record,
+            comments,
+            events,
+            warnings,
+            raw,
+            history: false,
+        };
+        Ok((rinex, statistics))
+    }
+
+    /// Stores record of `self`, parsed from given `source` file,
+    /// into given binary `cache` file, for faster reloading with [Rinex::from_cache].
+    /// Observation, Meteo, Clock and IONEX records can be cached,
+    /// Navigation records are not supported ([cache::Error::TypeNotSupported]).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_cache (&self, source: &str, cache: &str) -> Result<(), cache::Error> {
+        let content = cache::encode(source, self.header.rinex_type, &self.record, &self.comments, &self.events, &self.warnings, &self.raw)?;
+        std::fs::write(cache, content)?;
+        Ok(())
+    }
+
+    /// Rebuilds a `RINEX` from given `source` file, using
+    /// its record previously stored in given `cache` file.
+    /// Header section is always parsed from `source`.
+    /// Fails with [cache::Error::Outdated] if `source` changed since
+    /// the cache was produced.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let source = "../test_resources/OBS/V3/DUTH0630.22O";
+    /// let cache = std::env::temp_dir().join("duth0630.rnxcache");
+    /// let cache = cache.to_str().unwrap();
+    /// let rinex = Rinex::from_file(source)
+    ///     .unwrap();
+    /// rinex.to_cache(source, cache)
+    ///     .unwrap();
+    /// let cached = Rinex::from_cache(source, cache)
+    ///     .unwrap();
+    /// assert_eq!(cached.record.as_obs(), rinex.record.as_obs());
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_cache (source: &str, cache: &str) -> Result<Rinex, cache::Error> {
+        let content = std::fs::read(cache)?;
+        let mut reader = Self::open_reader(source)?;
+        let header = header::Header::new(&mut reader)
+            .map_err(|e| cache::Error::ParsingError(Error::HeaderError(e)))?;
+        let (record, comments, events, warnings, raw) = cache::decode(source, header.rinex_type, &content)?;
         Ok(Rinex {
             header,
             record,
             comments,
+            events,
+            warnings,
+            raw,
+            history: false,
         })
     }
 
+    /// Builds a `RINEX` from given `source` file, reloading its record
+    /// from given `cache` file when it is up to date. Otherwise, `source`
+    /// is parsed and `cache` is (re)generated, if this record type can be cached.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file_cached (source: &str, cache: &str) -> Result<Rinex, cache::Error> {
+        if let Ok(rinex) = Self::from_cache(source, cache) {
+            return Ok(rinex)
+        }
+        let rinex = Self::from_file_with_options(source, &parser::ParserOptions::default())?;
+        match rinex.to_cache(source, cache) {
+            Ok(_) | Err(cache::Error::TypeNotSupported(_)) => Ok(rinex),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parses header section of given file, and returns
+    /// a [record::Streamer] to browse its record one epoch at a time,
+    /// without loading the entire record in memory.
+    /// This is the prefered method to process very large files.
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::record::StreamEntry;
+    /// let stream = Rinex::epochs_iter_from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// assert_eq!(stream.header().is_crinex(), false);
+    /// for entry in stream {
+    ///     // I/O and decompression errors are reported
+    ///     let entry = entry.unwrap();
+    ///     if let StreamEntry::Obs(_epoch, _clk_offset, vehicules) = entry {
+    ///         // process this epoch, then drop it
+    ///         assert_eq!(vehicules.len() > 0, true);
+    ///     }
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn epochs_iter_from_file (path: &str) -> Result<record::Streamer, Error> {
+        let mut reader = Self::open_reader(path)?;
+        let header = header::Header::new(&mut reader)?;
+        Ok(record::Streamer::new(reader, &header))
+    }
+
+    /// Returns the calibration of the antenna of given type and serial number,
+    /// that applies at given date, see [antex::record::calibration].
+    /// Individual calibrations are preferred over type mean calibrations.
+    /// Returns None if self is not an ATX RINEX
+    pub fn antenna_calibration (&self, ant_type: &str, sn: &str, date: chrono::NaiveDateTime) -> Option<(&antex::antenna::Antenna, &Vec<antex::frequency::Frequency>)> {
+        let record = self.record.as_antex()?;
+        antex::record::calibration(record, ant_type, sn, date)
+            .map(|(antenna, frequencies)| (antenna, frequencies))
+    }
+
     /// Returns true if this is an ATX RINEX 
     pub fn is_antex_rinex (&self) -> bool { self.header.rinex_type == types::Type::AntennaData }
     
@@ -336,47 +880,98 @@ impl Rinex {
     /// Retruns true if this is an OBS RINX
     pub fn is_observation_rinex (&self) -> bool { self.header.rinex_type == types::Type::ObservationData }
 
+    /// Converts given date, expressed in `from` time scale, to `to` time scale,
+    /// using the leap seconds and TIME SYSTEM CORR parameters of this header.
+    /// When the header does not describe leap seconds, the built-in
+    /// leap second history is used. See [gnss_time::convert]
+    pub fn timescale_convert (&self, date: chrono::NaiveDateTime, from: gnss_time::TimeScale, to: gnss_time::TimeScale) -> Result<chrono::NaiveDateTime, gnss_time::Error> {
+        let leap = match &self.header.leap {
+            Some(leap) => Some(gnss_time::gpst_utc_leap(leap)),
+            None => from.builtin_leap(date),
+        };
+        gnss_time::convert(date, from, to, leap, &self.header.time_system_corr)
+    }
+
+    /// Expresses Ephemeris epochs of this NAV record in given time scale.
+    /// Ephemeris epochs are expressed in the time scale of each constellation,
+    /// and in UTC for Glonass, which makes mixed NAV files hard to compare.
+    /// Returns vehicules, sorted by converted epoch.
+    /// This does not produce anything if self is not a NAV RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::gnss_time::TimeScale;
+    /// let rinex = Rinex::from_file("../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// let epochs = rinex.sv_epochs_to_timescale(TimeScale::GPST)
+    ///     .unwrap();
+    /// for (epoch, vehicules) in epochs.iter() {
+    ///     // all epochs are expressed in GPST
+    /// }
+    /// ```
+    pub fn sv_epochs_to_timescale (&self, scale: gnss_time::TimeScale) -> Result<BTreeMap<epoch::Epoch, Vec<sv::Sv>>, gnss_time::Error> {
+        let mut results: BTreeMap<epoch::Epoch, Vec<sv::Sv>> = BTreeMap::new();
+        let record = match self.record.as_nav() {
+            Some(record) => record,
+            None => return Ok(results),
+        };
+        for (e, classes) in record.iter() {
+            if let Some(frames) = classes.get(&navigation::record::FrameClass::Ephemeris) {
+                for frame in frames.iter() {
+                    let (_, sv, _, _, _, _) = frame.as_eph().unwrap();
+                    let from = match sv.constellation {
+                        constellation::Constellation::Glonass => gnss_time::TimeScale::UTC,
+                        c => gnss_time::TimeScale::from_constellation(c)
+                            .unwrap_or(gnss_time::TimeScale::GPST),
+                    };
+                    let date = self.timescale_convert(e.date, from, scale)?;
+                    results.entry(epoch::Epoch::new(date, e.flag))
+                        .or_insert_with(Vec::new)
+                        .push(sv);
+                }
+            }
+        }
+        Ok(results)
+    }
+
     /// Returns `epoch` of first observation
     pub fn first_epoch (&self) -> Option<epoch::Epoch> {
-        let epochs = self.epochs();
-        if epochs.len() == 0 {
-            None
-        } else {
-            Some(epochs[0])
-        }
+        self.epochs_iter()
+            .next()
+            .map(|(e, _)| *e)
     }
 
     /// Returns `epoch` of last observation
     pub fn last_epoch (&self) -> Option<epoch::Epoch> {
-        let epochs = self.epochs();
-        if epochs.len() == 0 {
-            None
-        } else {
-            Some(epochs[epochs.len()-1])
-        }
+        self.epochs_iter()
+            .next_back()
+            .map(|(e, _)| *e)
     }
 
     /// Returns a list of epochs that present a data gap.
     /// Data gap is determined by comparing |e(k)-e(k-1)|: successive epoch intervals,
     /// to the INTERVAL field found in the header.
-    /// Granularity is currently limited to 1 second. 
+    /// Sub second intervals are supported.
     /// This method will not produce anything if header does not an INTERVAL field.
     pub fn data_gap (&self) -> Vec<epoch::Epoch> {
         if let Some(interval) = self.header.sampling_interval {
-            let interval = interval as u64;
-            let mut epochs = self.epochs();
-            let mut prev = epochs[0].date;
-            epochs
-                .retain(|e| {
-                    let delta = (e.date - prev).num_seconds() as u64; 
+            let interval = chrono::Duration::nanoseconds((interval as f64 * 1.0E9).round() as i64);
+            let mut prev = match self.first_epoch() {
+                Some(e) => e.date,
+                None => return Vec::new(),
+            };
+            self.epochs_iter()
+                .map(|(e, _)| *e)
+                .filter(|e| {
+                    let delta = e.date - prev;
                     if delta <= interval {
                         prev = e.date;
                         true
                     } else {
                         false
                     }
-            });
-            epochs
+                })
+                .collect()
         } else {
             Vec::new()
         }
@@ -391,9 +986,8 @@ impl Rinex {
     ///  - receiver physically moved (new site occupation)
     ///  - other external events 
     pub fn epoch_anomalies (&self, mask: Option<epoch::EpochFlag>) -> Vec<epoch::Epoch> { 
-        let epochs = self.epochs();
-        epochs
-            .into_iter()
+        self.epochs_iter()
+            .map(|(e, _)| *e)
             .filter(|e| {
                 let mut nok = !e.flag.is_ok(); // abnormal epoch
                 if let Some(mask) = mask {
@@ -404,33 +998,138 @@ impl Rinex {
             .collect()
     }
 
-    /// Returns (if possible) event explanation / description by searching through identified comments,
-    /// and returning closest comment (inside record) in time.    
-    /// Usually, comments are associated to epoch events (anomalies) to describe what happened.   
-    /// This method tries to locate a list of comments that were associated to the given timestamp 
-    pub fn event_description (&self, event: epoch::Epoch) -> Option<&str> {
-        let comments : Vec<_> = self.comments
+    /// Returns site occupation changes, during a kinematic session:
+    /// epochs where the antenna started being moved (flag 2) or a new site
+    /// was occupied (flag 3), along with the new site description,
+    /// as described by the special records that followed.
+    /// Other events (flags 4, 5) are exposed in `self.events`.
+    /// ```
+    /// use rinex::*;
+    /// use rinex::epoch::EpochFlag;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V2/kinematic.obs")
+    ///     .unwrap();
+    /// // special records are not observations
+    /// assert_eq!(rinex.epochs().len(), 3);
+    /// let events = rinex.site_events();
+    /// assert_eq!(events.len(), 2);
+    /// let (e, event) = events[1];
+    /// assert_eq!(e.flag, EpochFlag::NewSiteOccupation);
+    /// assert_eq!(event.station, Some(String::from("ROVER")));
+    /// ```
+    pub fn site_events (&self) -> Vec<(epoch::Epoch, &observation::SiteEvent)> {
+        self.events
             .iter()
-            .filter(|(k,_)| *k == &event)
-            .map(|(_,v)| v)
-            .flatten()
-            .collect();
-        if comments.len() > 0 {
-            Some(comments[0]) // TODO grab all content! by serializing into a single string
-        } else {
+            .filter(|(e, _)| {
+                e.flag == epoch::EpochFlag::AntennaBeingMoved
+                    || e.flag == epoch::EpochFlag::NewSiteOccupation
+            })
+            .map(|(e, event)| (*e, event))
+            .collect()
+    }
+
+    /// Returns receiver positions (ECEF [m]) for every epoch contained in self.
+    /// In static sessions, this is the header approximate position.
+    /// In kinematic sessions, positions follow the trajectory described
+    /// by epoch events carrying new coordinates: each new position
+    /// applies until the next one is described.
+    /// Epochs prior to the first known position are not reported.
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V2/kinematic.obs")
+    ///     .unwrap();
+    /// let positions = rinex.positions();
+    /// assert_eq!(positions.len(), 3);
+    /// let (_, first) = positions.iter().next().unwrap();
+    /// assert_eq!(*first, (2390232.69, -5564587.61, 1995022.14));
+    /// let (_, last) = positions.iter().next_back().unwrap();
+    /// assert_eq!(*last, (2390332.69, -5564587.61, 1995022.14));
+    /// ```
+    pub fn positions (&self) -> BTreeMap<epoch::Epoch, (f64,f64,f64)> {
+        let mut results: BTreeMap<epoch::Epoch, (f64,f64,f64)> = BTreeMap::new();
+        let mut position = self.header.coords
+            .as_ref()
+            .map(|p| (p.x, p.y, p.z));
+        let mut events = self.events
+            .iter()
+            .filter_map(|(e, event)| {
+                event.coords
+                    .as_ref()
+                    .map(|p| (e.date, (p.x, p.y, p.z)))
+            })
+            .peekable();
+        for (e, _) in self.epochs_iter() {
+            while let Some((date, _)) = events.peek() {
+                if *date > e.date {
+                    break
+                }
+                position = events.next().map(|(_, p)| p);
+            }
+            if let Some(position) = position {
+                results.insert(*e, position);
+            }
+        }
+        results
+    }
+
+    /// Returns (if possible) event explanation / description by searching through identified comments.
+    /// Usually, comments are associated to epoch events (anomalies) to describe what happened.   
+    /// This method returns all comments that were associated to the given timestamp,
+    /// one per line, or None if no comments were found
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::epoch::{Epoch, EpochFlag};
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V2/kinematic.obs")
+    ///     .unwrap();
+    /// let event = Epoch::new(
+    ///     epoch::str2date("2017 01 01 00 02 00.0000000").unwrap(),
+    ///     EpochFlag::NewSiteOccupation);
+    /// assert_eq!(rinex.event_description(event), Some(String::from("NEW SITE OCCUPATION")));
+    /// ```
+    pub fn event_description (&self, event: epoch::Epoch) -> Option<String> {
+        let comments = self.comments.get(&event)?;
+        if comments.is_empty() {
             None
+        } else {
+            Some(comments.join("\n"))
         }
     } 
 
+    /// Returns all epochs that were not flagged as `Ok`,
+    /// (events and anomalies, like power failures or new site occupations),
+    /// each paired with the comments that were associated to it.
+    /// Epochs without comments are paired to an empty list
+    pub fn event_log (&self) -> BTreeMap<epoch::Epoch, Vec<&str>> {
+        let comments = |e: &epoch::Epoch| -> Vec<&str> {
+            self.comments
+                .get(e)
+                .map(|c| c.iter().map(|s| s.as_str()).collect())
+                .unwrap_or_default()
+        };
+        let mut results: BTreeMap<epoch::Epoch, Vec<&str>> = BTreeMap::new();
+        for (e, _) in self.epochs_iter() {
+            if !e.flag.is_ok() {
+                results.insert(*e, comments(e));
+            }
+        }
+        for (e, _) in self.events.iter() {
+            results.insert(*e, comments(e));
+        }
+        results
+    }
+
     /// Returns `true` if self is a `merged` RINEX file,   
     /// meaning, this file is the combination of two RINEX files merged together.  
-    /// This is determined by the presence of a custom yet somewhat standardized `FILE MERGE` comments
+    /// This is determined by the presence of a custom yet somewhat standardized `FILE MERGE` comments,
+    /// or the `teqc` compatible markers we produce (see [merge::MarkerCompat])
     pub fn is_merged (&self) -> bool {
+        let is_marker = |c: &String| c.contains("FILE MERGE") || merge::marker_date(c).is_some();
+        if self.header.comments.iter().any(is_marker) {
+            return true
+        }
         for (_, content) in self.comments.iter() {
-            for c in content {
-                if c.contains("FILE MERGE") {
-                    return true
-                }
+            if content.iter().any(is_marker) {
+                return true
             }
         }
         false
@@ -439,22 +1138,14 @@ impl Rinex {
     /// Returns list of epochs where RINEX merging operation(s) occurred.    
     /// Epochs are determined either by the pseudo standard `FILE MERGE` comment description.
     pub fn merge_boundaries (&self) -> Vec<chrono::NaiveDateTime> {
-        self.header
+        let mut boundaries: Vec<chrono::NaiveDateTime> = self.header
             .comments
             .iter()
-            .flat_map(|s| {
-                if s.contains("FILE MERGE") {
-                    let content = s.split_at(40).1.trim();
-                    if let Ok(date) = chrono::NaiveDateTime::parse_from_str(content, "%Y%m%d %h%m%s UTC") {
-                        Some(date)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect()
+            .flat_map(|s| merge::marker_date(s))
+            .collect();
+        // a merge operation may be described by several markers
+        boundaries.dedup();
+        boundaries
     }
 
     /// Splits self into several RINEXes if self is a Merged Rinex. 
@@ -467,6 +1158,10 @@ impl Rinex {
                 header: self.header.clone(),
                 record: r.clone(),
                 comments: self.comments.clone(),
+                events: self.events.clone(),
+                warnings: self.warnings.clone(),
+                raw: self.raw.clone(),
+                history: self.history,
             })
         }
         result
@@ -477,8 +1172,7 @@ impl Rinex {
     pub fn split_merged_records (&self) -> Vec<record::Record> {
         let boundaries = self.merge_boundaries();
         let mut result : Vec<record::Record> = Vec::with_capacity(boundaries.len());
-        let epochs = self.epochs();
-        let mut e0 = epochs[0].date;
+        let mut e0 = self.first_epoch().unwrap().date;
         for boundary in boundaries {
             let rec : record::Record = match self.header.rinex_type {
                 types::Type::NavigationData => {
@@ -529,11 +1223,19 @@ impl Rinex {
             Self {
                 header: self.header.clone(),
                 comments: self.comments.clone(),
+                events: self.events.clone(),
+                warnings: self.warnings.clone(),
+                raw: self.raw.clone(),
+                history: self.history,
                 record: r0,
             },
             Self {
                 header: self.header.clone(),
                 comments: self.comments.clone(),
+                events: self.events.clone(),
+                warnings: self.warnings.clone(),
+                raw: self.raw.clone(),
+                history: self.history,
                 record: r1,
             },
         ))
@@ -543,11 +1245,10 @@ impl Rinex {
     /// Splits record into two at desired `epoch`.
     /// Self does not have to be a `Merged` file.
     pub fn split_record_at_epoch (&self, epoch: epoch::Epoch) -> Result<(record::Record,record::Record), SplitError> {
-        let epochs = self.epochs();
-        if epoch.date < epochs[0].date {
+        if epoch.date < self.first_epoch().unwrap().date {
             return Err(SplitError::EpochTooEarly)
         }
-        if epoch.date > epochs[epochs.len()-1].date {
+        if epoch.date > self.last_epoch().unwrap().date {
             return Err(SplitError::EpochTooLate)
         }
         let rec0 : record::Record = match self.header.rinex_type {
@@ -649,44 +1350,132 @@ impl Rinex {
         Ok((rec0,rec1))
     }
 
-    /// Returns list of epochs contained in self.
-    /// Faillible! if this RINEX is not indexed by `epochs`
-    pub fn epochs (&self) -> Vec<epoch::Epoch> {
-        match self.header.rinex_type {
-            types::Type::ObservationData => {
-                self.record
-                    .as_obs()
-                    .unwrap()
-                    .into_iter()
-                    .map(|(k, _)| *k)
-                    .collect()
-            },
-            types::Type::NavigationData => {
-                self.record
-                    .as_nav()
-                    .unwrap()
-                    .into_iter()
-                    .map(|(k, _)| *k)
-                    .collect()
-            },
-            types::Type::MeteoData => {
-                self.record
-                    .as_meteo()
-                    .unwrap()
-                    .into_iter()
-                    .map(|(k, _)| *k)
-                    .collect()
-            },
-            types::Type::IonosphereMaps => {
-                self.record
-                    .as_ionex()
-                    .unwrap()
-                    .into_iter()
-                    .map(|(k, _)| *k)
-                    .collect()
-            },
-            _ => panic!("Cannot get an epoch iterator for \"{:?}\"", self.header.rinex_type),
+    /// Splits self into fixed duration batches (hourly, 15', daily..),
+    /// like teqc "tbin" does. Batches are aligned to midnight of the first epoch,
+    /// a batch contains epochs within [start, start + duration[.
+    /// Empty batches are not produced. Header sections are copied,
+    /// TIME OF FIRST / LAST OBS are rewritten to match each batch.
+    /// Returns a copy of self if `duration` is not strictly positive,
+    /// or if self is not epoch iterable (ATX).
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// // this file spans 00:00:00 - 00:12:00
+    /// let batches = rinex.split_by_duration(chrono::Duration::minutes(5));
+    /// assert_eq!(batches.len(), 3);
+    /// assert_eq!(batches[2].epochs().len(), 5);
+    /// ```
+    pub fn split_by_duration (&self, duration: chrono::Duration) -> Vec<Self> {
+        let step = duration.num_milliseconds();
+        if step <= 0 || self.is_antex_rinex() {
+            return vec![self.clone()]
+        }
+        let epochs = self.epochs();
+        if epochs.len() == 0 {
+            return vec![self.clone()]
+        }
+        let origin = epochs[0].date.date().and_hms(0, 0, 0);
+        let mut bins : Vec<i64> = epochs
+            .iter()
+            .map(|e| (e.date - origin).num_milliseconds().div_euclid(step))
+            .collect();
+        bins.dedup();
+        let mut result : Vec<Self> = Vec::with_capacity(bins.len());
+        for bin in bins {
+            let start = origin + chrono::Duration::milliseconds(bin * step);
+            let end = start + duration;
+            let mut rinex = self.clone();
+            match &mut rinex.record {
+                record::Record::ClockRecord(r) => r.retain(|e, _| e.date >= start && e.date < end),
+                record::Record::IonexRecord(r) => r.retain(|e, _| e.date >= start && e.date < end),
+                record::Record::MeteoRecord(r) => r.retain(|e, _| e.date >= start && e.date < end),
+                record::Record::NavRecord(r) => r.retain(|e, _| e.date >= start && e.date < end),
+                record::Record::ObsRecord(r) => r.retain(|e, _| e.date >= start && e.date < end),
+                record::Record::AntexRecord(_) => unreachable!("epochs::iter()"),
+            }
+            rinex.comments.retain(|e, _| e.date >= start && e.date < end);
+            rinex.events.retain(|e, _| e.date >= start && e.date < end);
+            let first = rinex.first_epoch();
+            let last = rinex.last_epoch();
+            if let Some(obs) = &mut rinex.header.obs {
+                obs.time_of_first_obs = first;
+                obs.time_of_last_obs = last;
+            }
+            result.push(rinex)
         }
+        result
+    }
+
+    /// Returns a zero-copy, borrowed view over given time window.
+    /// Prefer this to [split_at_epoch] when only read access is needed.
+    /// Panics if `range.start` > `range.end`.
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let epochs = rinex.epochs();
+    /// let view = rinex.window(epochs[0]..epochs[10]);
+    /// assert_eq!(view.len(), 10);
+    /// assert_eq!(view.first_epoch(), Some(epochs[0]));
+    /// assert_eq!(view.last_epoch(), Some(epochs[9]));
+    /// for (epoch, (clk_offset, vehicules)) in view.as_obs().unwrap() {
+    ///     // read only access
+    /// }
+    /// ```
+    pub fn window (&self, range: std::ops::Range<epoch::Epoch>) -> record::RecordView {
+        self.record.window(range)
+    }
+
+    /// Validates self against given profile (revision of the standard,
+    /// data center submission rules), see [validation::validate].
+    /// Returns the list of violations, empty when self complies.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::validation::Profile;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// for violation in rinex.validate(Profile::V3_04) {
+    ///     println!("{}", violation);
+    /// }
+    /// ```
+    pub fn validate (&self, profile: validation::Profile) -> Vec<validation::Violation> {
+        validation::validate(self, profile)
+    }
+
+    /// Returns list of epochs contained in self.
+    /// This allocates, prefer [Rinex::epochs_iter] when possible.
+    /// Empty on ATX records, which are not epoch iterable
+    pub fn epochs (&self) -> Vec<epoch::Epoch> {
+        self.epochs_iter()
+            .map(|(e, _)| *e)
+            .collect()
+    }
+
+    /// Returns a borrowed, chronological iterator over
+    /// all epochs and their content, without allocation.
+    /// Empty on ATX records, which are not epoch iterable.
+    /// `&Rinex` also implements `IntoIterator`, which is equivalent.
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// assert_eq!(rinex.epochs_iter().len(), rinex.epochs().len());
+    /// for (epoch, entry) in &rinex {
+    ///     let (clk_offset, vehicules) = entry.as_obs().unwrap();
+    /// }
+    /// let (last, _) = rinex.epochs_iter().next_back().unwrap();
+    /// assert_eq!(Some(*last), rinex.last_epoch());
+    /// ```
+    pub fn epochs_iter (&self) -> record::EpochIter {
+        self.record.epochs_iter()
     }
 
     /// Merges given RINEX into self, in teqc similar fashion.   
@@ -695,21 +1484,76 @@ impl Rinex {
     /// Resulting self.record (modified in place) remains sorted by 
     /// sampling timestamps.
     pub fn merge_mut (&mut self, other: &Self) -> Result<(), merge::MergeError> {
-        self.header.merge_mut(&other.header)?;
-        // grab Self:: + Other:: `epochs`
-        let (epochs, other_epochs) = (self.epochs(), other.epochs());
-        if epochs.len() == 0 { // self is empty
+        self.merge_with_markers_mut(other, &[])
+    }
+
+    /// Merges given RINEX into self, like [Rinex::merge_mut],
+    /// but also emits merge markers in the format of the given third party tools,
+    /// in addition to our own marker.
+    /// ```
+    /// use rinex::Rinex;
+    /// use rinex::merge::MarkerCompat;
+    /// let mut rnx = Rinex::from_file("../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// let other = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// rnx.merge_with_markers_mut(&other, &[MarkerCompat::Gfzrnx, MarkerCompat::Teqc])
+    ///     .unwrap();
+    /// assert!(rnx.is_merged());
+    /// ```
+    pub fn merge_with_markers_mut (&mut self, other: &Self, compat: &[merge::MarkerCompat]) -> Result<(), merge::MergeError> {
+        self.merge_with_policy_and_markers_mut(other, &merge::MergePolicy::default(), compat)
+    }
+
+    /// Merges given RINEX into self, like [Rinex::merge_mut],
+    /// but header sections are combined following given [merge::MergePolicy],
+    /// see [header::Header::merge_with_policy_mut]
+    pub fn merge_with_policy_mut (&mut self, other: &Self, policy: &merge::MergePolicy) -> Result<(), merge::MergeError> {
+        self.merge_with_policy_and_markers_mut(other, policy, &[])
+    }
+
+    /// Describes a merge operation in the header section:
+    /// our own marker, followed by given third party markers
+    fn push_merge_markers (&mut self, compat: &[merge::MarkerCompat]) {
+        let now = epoch::now();
+        self.header.comments.push(merge::native_marker(now));
+        for tool in compat {
+            self.header.comments.push(tool.marker(now));
+        }
+    }
+
+    fn merge_with_policy_and_markers_mut (&mut self, other: &Self, policy: &merge::MergePolicy, compat: &[merge::MarkerCompat]) -> Result<(), merge::MergeError> {
+        self.header.merge_with_policy_mut(&other.header, policy)?;
+        if self.is_antex_rinex() {
+            // ATX records are not indexed by epochs:
+            // append antennas that were not already described
+            let a_rec = self.record
+                .as_mut_antex()
+                .unwrap();
+            let b_rec = other.record
+                .as_antex()
+                .unwrap();
+            for (antenna, frequencies) in b_rec {
+                if !a_rec.iter().any(|(a, _)| a == antenna) {
+                    a_rec.push((antenna.clone(), frequencies.clone()));
+                }
+            }
+            self.push_merge_markers(compat);
+            return Ok(())
+        }
+        for (e, event) in other.events.iter() {
+            self.events
+                .entry(*e)
+                .or_insert_with(|| event.clone());
+        }
+        if self.first_epoch().is_none() { // self is empty
             self.record = other.record.clone();
             Ok(()) // --> self is overwritten
-        } else if other_epochs.len() == 0 { // nothing to merge
+        } else if other.first_epoch().is_none() { // nothing to merge
             Ok(()) // --> self is untouched
         } else {
             // add Merge op descriptor
-            let now = chrono::offset::Utc::now();
-            self.header.comments.push(format!(
-                "rustrnx-{:<20} FILE MERGE          {} UTC", 
-                env!("CARGO_PKG_VERSION"),
-                now.format("%Y%m%d %H%M%S")));
+            self.push_merge_markers(compat);
             // merge op
             match self.header.rinex_type {
                 types::Type::NavigationData => {
@@ -719,8 +1563,18 @@ impl Rinex {
                     let b_rec = other.record
                         .as_nav()
                         .unwrap();
-                    for (k, v) in b_rec {
-                        a_rec.insert(*k, v.clone());
+                    for (k, classes) in b_rec {
+                        let a_classes = a_rec
+                            .entry(*k)
+                            .or_insert_with(BTreeMap::new);
+                        for (class, frames) in classes {
+                            let a_frames = a_classes
+                                .entry(*class)
+                                .or_insert_with(Vec::new);
+                            a_frames.extend(frames.iter().cloned());
+                            // frames we already had are preferred
+                            navigation::record::dedup_frames(a_frames);
+                        }
                     }
                 },
                 types::Type::ObservationData => {
@@ -752,8 +1606,38 @@ impl Rinex {
                     let b_rec = other.record
                         .as_ionex()
                         .unwrap();
-                    for (k, v) in b_rec {
-                        a_rec.insert(*k, v.clone());
+                    for (k, (tec, rms, height)) in b_rec {
+                        if let Some((a_tec, a_rms, a_height)) = a_rec.get_mut(k) {
+                            // merge layer per layer
+                            ionosphere::record::merge_maps(a_tec, tec);
+                            for (a_map, b_map) in [(a_rms, rms), (a_height, height)] {
+                                if let Some(b_map) = b_map {
+                                    if let Some(a_map) = a_map.as_mut() {
+                                        ionosphere::record::merge_maps(a_map, b_map)
+                                    } else {
+                                        *a_map = Some(b_map.clone())
+                                    }
+                                }
+                            }
+                        } else {
+                            a_rec.insert(*k, (tec.clone(), rms.clone(), height.clone()));
+                        }
+                    }
+                },
+                types::Type::ClockData => {
+                    let a_rec = self.record
+                        .as_mut_clock()
+                        .unwrap();
+                    let b_rec = other.record
+                        .as_clock()
+                        .unwrap();
+                    // epochs are shared between systems:
+                    // merge on a system and data type basis
+                    for (k, entries) in b_rec {
+                        a_rec
+                            .entry(*k)
+                            .or_insert_with(clocks::record::ClockEntries::default)
+                            .merge_mut(entries);
                     }
                 },
                 _ => unreachable!("epochs::iter()"),
@@ -761,12 +1645,162 @@ impl Rinex {
             Ok(())
         }
     }
+
+    /// Splices given RINEX into self, in teqc similar fashion.
+    /// Unlike [Rinex::merge_mut], epochs described by both files
+    /// are resolved with given [merge::DuplicatePolicy].
+    /// Header sections are combined like [Rinex::merge_mut].
+    /// Only Observation and Meteo records can be spliced.
+    /// Returns the duplicate epochs and the data gap at the seam, if any.
+    /// Self is not modified when an error is returned.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::merge::DuplicatePolicy;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V2/kinematic.obs")
+    ///     .unwrap();
+    /// let other = rinex.clone();
+    /// // files overlap entirely
+    /// assert_eq!(rinex.splice_mut(&other, DuplicatePolicy::Error).is_err(), true);
+    /// let report = rinex.splice_mut(&other, DuplicatePolicy::KeepFirst)
+    ///     .unwrap();
+    /// assert_eq!(report.duplicates, rinex.epochs());
+    /// assert_eq!(report.gap, None);
+    /// ```
+    pub fn splice_mut (&mut self, other: &Self, policy: merge::DuplicatePolicy) -> Result<merge::SpliceReport, merge::MergeError> {
+        if self.header.rinex_type != other.header.rinex_type {
+            return Err(merge::MergeError::FileTypeMismatch)
+        }
+        if !self.is_observation_rinex() && !self.is_meteo_rinex() {
+            return Err(merge::MergeError::SpliceNotSupported)
+        }
+        // header is merged aside, self is only modified once every check passed
+        let mut header = self.header.clone();
+        header.merge_mut(&other.header)?;
+        let epochs = self.epochs();
+        let duplicates: Vec<epoch::Epoch> = other.epochs_iter()
+            .map(|(e, _)| *e)
+            .filter(|e| epochs.binary_search(e).is_ok())
+            .collect();
+        if policy == merge::DuplicatePolicy::Error {
+            if let Some(e) = duplicates.first() {
+                return Err(merge::MergeError::DuplicateEpoch(*e))
+            }
+        }
+        // seam: expected sampling interval is exceeded
+        let interval = self.header.sampling_interval
+            .or(other.header.sampling_interval)
+            .map(|dt| chrono::Duration::nanoseconds((dt as f64 * 1.0E9).round() as i64))
+            .or_else(|| {
+                epochs.windows(2)
+                    .map(|w| w[1].date - w[0].date)
+                    .min()
+            });
+        let seam = match (self.first_epoch(), self.last_epoch(), other.first_epoch(), other.last_epoch()) {
+            (Some(a0), Some(a1), Some(b0), Some(b1)) => {
+                if a1 < b0 {
+                    Some((a1, b0))
+                } else if b1 < a0 {
+                    Some((b1, a0))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        };
+        let gap = seam.filter(|(end, start)| {
+            match interval {
+                Some(interval) => start.date - end.date > interval,
+                None => true,
+            }
+        });
+        self.header = header;
+        self.log_operation("splice");
+        self.header.comments.push(merge::native_marker(epoch::now()));
+        for (e, event) in other.events.iter() {
+            self.events
+                .entry(*e)
+                .or_insert_with(|| event.clone());
+        }
+        if let Some(a_rec) = self.record.as_mut_obs() {
+            let b_rec = other.record
+                .as_obs()
+                .unwrap();
+            for (e, (clk, vehicules)) in b_rec {
+                let (a_clk, a_vehicules) = match a_rec.get_mut(e) {
+                    Some(entry) => entry,
+                    None => {
+                        a_rec.insert(*e, (*clk, vehicules.clone()));
+                        continue
+                    },
+                };
+                match policy {
+                    merge::DuplicatePolicy::KeepFirst | merge::DuplicatePolicy::Error => {},
+                    merge::DuplicatePolicy::KeepSecond => {
+                        *a_clk = *clk;
+                        *a_vehicules = vehicules.clone();
+                    },
+                    merge::DuplicatePolicy::Average => {
+                        *a_clk = match (*a_clk, *clk) {
+                            (Some(a), Some(b)) => Some((a + b) / 2.0),
+                            (a, b) => a.or(b),
+                        };
+                        for (sv, observations) in vehicules {
+                            let a_observations = a_vehicules
+                                .entry(*sv)
+                                .or_insert_with(HashMap::new);
+                            for (observable, data) in observations {
+                                match a_observations.get_mut(observable) {
+                                    Some(a) => a.obs = (a.obs + data.obs) / 2.0,
+                                    None => {
+                                        a_observations.insert(*observable, *data);
+                                    },
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+        } else if let Some(a_rec) = self.record.as_mut_meteo() {
+            let b_rec = other.record
+                .as_meteo()
+                .unwrap();
+            for (e, observations) in b_rec {
+                let a_observations = match a_rec.get_mut(e) {
+                    Some(entry) => entry,
+                    None => {
+                        a_rec.insert(*e, observations.clone());
+                        continue
+                    },
+                };
+                match policy {
+                    merge::DuplicatePolicy::KeepFirst | merge::DuplicatePolicy::Error => {},
+                    merge::DuplicatePolicy::KeepSecond => *a_observations = observations.clone(),
+                    merge::DuplicatePolicy::Average => {
+                        for (observable, data) in observations {
+                            match a_observations.get_mut(observable) {
+                                Some(a) => *a = (*a + data) / 2.0,
+                                None => {
+                                    a_observations.insert(observable.clone(), *data);
+                                },
+                            }
+                        }
+                    },
+                }
+            }
+        }
+        Ok(merge::SpliceReport {
+            duplicates,
+            gap,
+        })
+    }
     
     /// Retains only data that have an Ok flag associated to them. 
     pub fn epoch_ok_filter_mut (&mut self) {
         if !self.is_observation_rinex() {
             return ; // nothing to browse
         }
+        self.log_operation("epoch ok filter");
         let record = self.record
             .as_mut_obs()
             .unwrap();
@@ -780,44 +1814,63 @@ impl Rinex {
         if !self.is_observation_rinex() {
             return ; // nothing to browse
         }
+        self.log_operation("epoch nok filter");
         let record = self.record
             .as_mut_obs()
             .unwrap();
         record.retain(|e, _| !e.flag.is_ok())
     }
+
+    /// Retains only epochs for which given predicate returns true,
+    /// for any record type: one-off selections do not require
+    /// cloning the record. ATX records are left untouched.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use chrono::Timelike;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// // odd minutes only
+    /// rinex.retain_epochs_mut(|e| e.date.minute() % 2 == 1);
+    /// for e in rinex.epochs() {
+    ///     assert_eq!(e.date.minute() % 2, 1);
+    /// }
+    /// ```
+    pub fn retain_epochs_mut<F: FnMut(&epoch::Epoch) -> bool> (&mut self, f: F) {
+        self.log_operation("epochs selection");
+        self.record.retain_epochs(f)
+    }
+
+    /// Retains only epochs for which given predicate, evaluated on each epoch
+    /// and its content, returns true. See [Rinex::retain_epochs_mut].
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// // epochs with at least 10 vehicules in sight
+    /// rinex.retain_mut(|_, entry| {
+    ///     let (_, vehicules) = entry.as_obs().unwrap();
+    ///     vehicules.len() >= 10
+    /// });
+    /// ```
+    pub fn retain_mut<F: FnMut(&epoch::Epoch, record::RecordEntry) -> bool> (&mut self, f: F) {
+        self.log_operation("epochs selection");
+        self.record.retain(f)
+    }
     
     /// see [epoch_ok_filter_mut]
     pub fn epoch_ok_filter (&self) -> Self {
-        if !self.is_observation_rinex() {
-            return self.clone() // nothing to browse
-        }
-        let header = self.header.clone();
-        let mut record = self.record.as_obs()
-            .unwrap()
-            .clone();
-        record.retain(|e,_| e.flag.is_ok());
-        Self {
-            header,
-            comments: self.comments.clone(),
-            record: record::Record::ObsRecord(record.clone()),
-        }
+        let mut s = self.clone();
+        s.epoch_ok_filter_mut();
+        s
     }
     
     /// see [epoch_nok_filter_mut]
     pub fn epoch_nok_filter (&self) -> Self {
-        if !self.is_observation_rinex() {
-            return self.clone() // nothing to browse
-        }
-        let header = self.header.clone();
-        let mut record = self.record.as_obs()
-            .unwrap()
-            .clone();
-        record.retain(|e,_| !e.flag.is_ok());
-        Self {
-            header,
-            comments: self.comments.clone(),
-            record: record::Record::ObsRecord(record.clone()),
-        }
+        let mut s = self.clone();
+        s.epoch_nok_filter_mut();
+        s
     }
     
     /// Returns epochs where a loss of lock event happened.
@@ -828,6 +1881,57 @@ impl Rinex {
             .epochs()
     }
 
+    /// Returns observations of given observable, made by given vehicule,
+    /// over time, in chronological order. Only epochs where this signal
+    /// was observed are exposed. Observations are expressed as found in the file,
+    /// use [Rinex::lli_series] to retrieve the attached LLI flags.
+    /// Does not produce anything if self is not an Observation RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use std::str::FromStr;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let g18 = sv::Sv::from_str("G18").unwrap();
+    /// let c1c = observation::Observable::from_str("C1C").unwrap();
+    /// for (epoch, pseudo_range) in rinex.sv_time_series(&g18, &c1c) {
+    ///     // pseudo range [m]
+    /// }
+    /// ```
+    pub fn sv_time_series (&self, sv: &sv::Sv, observable: &observation::Observable) -> Vec<(epoch::Epoch, f64)> {
+        let mut results: Vec<(epoch::Epoch, f64)> = Vec::new();
+        if let Some(record) = self.record.as_obs() {
+            for (e, (_, vehicules)) in record.iter() {
+                if let Some(observations) = vehicules.get(sv) {
+                    if let Some(data) = observations.get(observable) {
+                        results.push((*e, data.obs));
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Returns LLI flags attached to given observable, for given vehicule,
+    /// over time. Only epochs where this signal was observed are exposed,
+    /// None meaning no LLI was attached to the observation.
+    /// Use this to identify which signal suffered from a loss of lock,
+    /// [lock_loss_events] only exposes epochs.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn lli_series (&self, sv: &sv::Sv, observable: &observation::Observable) -> BTreeMap<epoch::Epoch, Option<observation::record::LliFlags>> {
+        let mut results: BTreeMap<epoch::Epoch, Option<observation::record::LliFlags>> = BTreeMap::new();
+        if let Some(record) = self.record.as_obs() {
+            for (e, (_, vehicules)) in record.iter() {
+                if let Some(observations) = vehicules.get(sv) {
+                    if let Some(data) = observations.get(observable) {
+                        results.insert(*e, data.lli);
+                    }
+                }
+            }
+        }
+        results
+    }
+
     /// Removes in place, observables where Lock was declared as lost.
     pub fn lock_loss_filter (&mut self) {
         self
@@ -835,9 +1939,16 @@ impl Rinex {
     }
 
     /// Retains data that was recorded along given constellation(s).
-    /// This has no effect on ATX, CLK, MET and IONEX records and NAV 
-    /// record frames other than Ephemeris.
+    /// On CLK records, satellite (AS) entries are filtered, station entries are preserved.
+    /// On IONEX, the header satellite system description is updated accordingly.
+    /// This has no effect on ATX and MET records and NAV
+    /// record frames other than Ephemeris and Almanacs.
     pub fn constellation_filter_mut (&mut self, filter: Vec<constellation::Constellation>) {
+        let description: Vec<&str> = filter
+            .iter()
+            .map(|c| c.to_3_letter_code())
+            .collect();
+        self.log_operation(&format!("constellations: {}", description.join(",")));
         if self.is_observation_rinex() {
             let record = self.record
                 .as_mut_obs()
@@ -851,11 +1962,26 @@ impl Rinex {
                 .unwrap();
             for (_e, classes) in record.iter_mut() {
                 for (class, frames) in classes.iter_mut() {
-                    if *class == navigation::record::FrameClass::Ephemeris {
-                        frames.retain(|fr| {
-                            let (_, sv, _, _, _, _) = fr.as_eph().unwrap();
-                            filter.contains(&sv.constellation)
-                        })
+                    if *class == navigation::record::FrameClass::Ephemeris
+                        || *class == navigation::record::FrameClass::Almanac {
+                        frames.retain(|fr| filter.contains(&fr.sv().constellation))
+                    }
+                }
+            }
+        } else if self.is_clocks_rinex() {
+            let record = self.record
+                .as_mut_clock()
+                .unwrap();
+            for (_e, entries) in record.iter_mut() {
+                entries
+                    .satellites_mut()
+                    .retain(|sv, _| filter.contains(&sv.constellation))
+            }
+        } else if self.is_ionex() {
+            if let Some(ionex) = self.header.ionex.as_mut() {
+                if ionex.system.is_gnss() {
+                    if let Some(system) = ionosphere::System::from_constellations(&filter) {
+                        ionex.system = system
                     }
                 }
             }
@@ -863,9 +1989,17 @@ impl Rinex {
     }
 
     /// Retains data that was generated / recorded against given list of 
-    /// space vehicules. This has no effect on ATX, CLK, MET, IONEX records,
-    /// and NAV record frames other than Ephemeris.
+    /// space vehicules. On CLK records, satellite (AS) entries are filtered,
+    /// station entries are preserved: use [clock_station_filter_mut]
+    /// to filter them out as well.
+    /// This has no effect on ATX, MET, IONEX records,
+    /// and NAV record frames other than Ephemeris and Almanacs.
     pub fn space_vehicule_filter_mut (&mut self, filter: Vec<sv::Sv>) {
+        let description: Vec<String> = filter
+            .iter()
+            .map(|sv| sv.to_string())
+            .collect();
+        self.log_operation(&format!("vehicules: {}", description.join(",")));
         if self.is_observation_rinex() {
             let record = self.record
                 .as_mut_obs()
@@ -879,15 +2013,85 @@ impl Rinex {
                 .unwrap();
             for (_e, classes) in record.iter_mut() {
                 for (class, frames) in classes.iter_mut() {
-                    if *class == navigation::record::FrameClass::Ephemeris {
-                        frames.retain(|fr| {
-                                let (_, sv, _, _, _, _) = fr.as_eph().unwrap();
-                                filter.contains(&sv)
-                            })
+                    if *class == navigation::record::FrameClass::Ephemeris
+                        || *class == navigation::record::FrameClass::Almanac {
+                        frames.retain(|fr| filter.contains(&fr.sv()))
                     }
                 }
             }
-        } 
+        } else if self.is_clocks_rinex() {
+            let record = self.record
+                .as_mut_clock()
+                .unwrap();
+            for (_e, entries) in record.iter_mut() {
+                entries
+                    .satellites_mut()
+                    .retain(|sv, _| filter.contains(sv))
+            }
+        }
+    }
+
+    /// Drops observations of vehicules declared unhealthy by their
+    /// Ephemeris, in given `nav` record. Health status at a given epoch
+    /// is described by the latest Ephemeris published at or before that epoch,
+    /// the earliest Ephemeris otherwise. Vehicules that do not describe
+    /// their health, or that are not described by `nav`, are preserved.
+    /// This has no effect on other types of RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let nav = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// rinex.retain_healthy_mut(&nav);
+    /// ```
+    pub fn retain_healthy_mut (&mut self, nav: &Self) {
+        self.log_operation("healthy vehicules");
+        if !self.is_observation_rinex() {
+            return
+        }
+        let history = match nav.record.as_nav() {
+            Some(record) => navigation::health::health_history(record),
+            None => return,
+        };
+        let record = self.record
+            .as_mut_obs()
+            .unwrap();
+        for (e, (_clk, vehicules)) in record.iter_mut() {
+            vehicules.retain(|sv, _| {
+                history.get(sv)
+                    .and_then(|history| navigation::health::health_at(history, *e))
+                    .unwrap_or(true)
+            })
+        }
+    }
+
+    /// Returns a copy of self, without observations of vehicules
+    /// declared unhealthy by given `nav` record, see [Rinex::retain_healthy_mut]
+    pub fn retain_healthy (&self, nav: &Self) -> Self {
+        let mut s = self.clone();
+        s.retain_healthy_mut(nav);
+        s
+    }
+
+    /// Retains Clock data that was generated by given list of 
+    /// stations / receivers, identified by their name.
+    /// Satellite (AS) entries are preserved.
+    /// An empty list drops all station entries.
+    /// This has no effect on other types of RINEX.
+    pub fn clock_station_filter_mut (&mut self, filter: Vec<&str>) {
+        self.log_operation(&format!("stations: {}", filter.join(",")));
+        if self.is_clocks_rinex() {
+            let record = self.record
+                .as_mut_clock()
+                .unwrap();
+            for (_e, entries) in record.iter_mut() {
+                entries
+                    .stations_mut()
+                    .retain(|station, _| filter.contains(&station.as_str()))
+            }
+        }
     }
     
     /// Extracts distant clock offsets 
@@ -1024,14 +2228,97 @@ impl Rinex {
         results
     }
 
+    /// Extracts broadcast group delays [s] (TGD, BGD, TGD1/TGD2),
+    /// scaled to single frequency observations on given channel,
+    /// on an epoch basis and per space vehicule, see [navigation::Ephemeris::group_delay].
+    /// Vehicules whose Ephemeris do not describe a group delay for this channel
+    /// are not reported. This does not produce anything if self is not a NAV RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::channel::Channel;
+    /// let rinex = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// // GPS & QZSS TGD, scaled to L2
+    /// let delays = rinex.space_vehicule_group_delays(&Channel::L2);
+    /// for (e, vehicules) in delays.iter() {
+    ///     for (sv, tgd) in vehicules.iter() {
+    ///         // [s]
+    ///     }
+    /// }
+    /// ```
+    pub fn space_vehicule_group_delays (&self, channel: &channel::Channel) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> = BTreeMap::new();
+        let record = match self.record.as_nav() {
+            Some(record) => record,
+            None => return results, // nothing to extract
+        };
+        for (e, classes) in record.iter() {
+            if let Some(frames) = classes.get(&navigation::record::FrameClass::Ephemeris) {
+                let mut map: BTreeMap<sv::Sv, f64> = BTreeMap::new();
+                for frame in frames.iter() {
+                    let (_, sv, _, _, _, eph) = frame.as_eph().unwrap();
+                    if let Some(delay) = eph.group_delay(channel) {
+                        map.insert(sv, delay);
+                    }
+                }
+                if map.len() > 0 {
+                    results.insert(*e, map);
+                }
+            }
+        }
+        results
+    }
+
     /// Computes average epoch duration of this record
     pub fn average_epoch_duration (&self) -> std::time::Duration {
-        let mut sum = 0;
-        let epochs = self.epochs();
-        for i in 1..epochs.len() {
-            sum += (epochs[i].date - epochs[i-1].date).num_seconds() as u64
+        let mut sum = chrono::Duration::zero();
+        let mut prev: Option<epoch::Epoch> = None;
+        for (e, _) in self.epochs_iter() {
+            if let Some(prev) = prev {
+                sum = sum + (e.date - prev.date)
+            }
+            prev = Some(*e);
+        }
+        sum.to_std()
+            .unwrap_or_default()
+            / self.epochs_iter().len() as u32
+    }
+
+    /// Defines the order in which observables of given constellation are
+    /// described in the header and data lines of produced files, for example
+    /// to match the historical ordering of a station, since some downstream
+    /// tools are order sensitive. See [observation::HeaderFields::sort_codes].
+    /// Prior RINEX3, observables are shared by all constellations:
+    /// the order then applies to all of them.
+    /// This has no effect on other than Observation RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::constellation::Constellation;
+    /// use rinex::observation::Observable;
+    /// use std::str::FromStr;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// let s1c = Observable::from_str("S1C").unwrap();
+    /// rinex.sort_observables_mut(Constellation::GPS, &[s1c]);
+    /// let obs = rinex.header.obs.as_ref().unwrap();
+    /// assert_eq!(obs.codes[&Constellation::GPS][0], s1c);
+    /// ```
+    pub fn sort_observables_mut (&mut self, constellation: constellation::Constellation, order: &[observation::Observable]) {
+        let version = self.header.version;
+        if let Some(obs) = self.header.obs.as_mut() {
+            if version.major < 3 {
+                let constellations : Vec<constellation::Constellation> = obs.codes.keys()
+                    .copied()
+                    .collect();
+                for constellation in constellations {
+                    obs.sort_codes(constellation, order)
+                }
+            } else {
+                obs.sort_codes(constellation, order)
+            }
         }
-        std::time::Duration::from_secs(sum / epochs.len() as u64)
     }
 
     /// Returns list of observables, in the form 
@@ -1082,6 +2369,67 @@ impl Rinex {
         result
     }
 
+    /// Returns the observable presence matrix: number of epochs each observable
+    /// was actually reported for, per vehicule. This is the quickest way to determine
+    /// which signals a receiver tracked, on which vehicules.
+    /// Does not produce anything if self is not an Observation RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::sv::Sv;
+    /// use rinex::observation::Observable;
+    /// use std::str::FromStr;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let matrix = rinex.observable_matrix();
+    /// let l5q = Observable::from_str("L5Q").unwrap();
+    /// for sv in matrix.vehicules_with(&l5q) {
+    ///     println!("{} reported L5Q {} times", sv, matrix.count(&sv, &l5q));
+    /// }
+    /// ```
+    pub fn observable_matrix (&self) -> observation::presence::Matrix {
+        let mut counts : BTreeMap<sv::Sv, BTreeMap<observation::Observable, usize>> = BTreeMap::new();
+        if let Some(record) = self.record.as_obs() {
+            for (_, (_, vehicules)) in record.iter() {
+                for (sv, observations) in vehicules.iter() {
+                    let counts = counts
+                        .entry(*sv)
+                        .or_insert_with(BTreeMap::new);
+                    for code in observations.keys() {
+                        *counts.entry(*code).or_insert(0) += 1
+                    }
+                }
+            }
+        }
+        observation::presence::Matrix::from_counts(&counts)
+    }
+
+    /// Flattens this Observation record: one row per measurement,
+    /// made of plain fields only. Use this to convert observations
+    /// to other languages (FFI, pyo3..) or to columnar formats (Arrow..)
+    /// without walking nested maps. Rows are sorted by epoch, then by vehicule,
+    /// then by observable.
+    /// Does not produce anything if self is not an Observation RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// let rows = rinex.flatten();
+    /// // columnar export
+    /// let values: Vec<f64> = rows.iter()
+    ///     .filter(|row| row.code == "C1C")
+    ///     .map(|row| row.value)
+    ///     .collect();
+    /// assert_eq!(values.len() > 0, true);
+    /// ```
+    pub fn flatten (&self) -> Vec<observation::flat::FlatObservation> {
+        match self.record.as_obs() {
+            Some(record) => observation::flat::flatten(record),
+            None => Vec::new(),
+        }
+    }
+
     /// Filters out data records that do not contained in the given Observable list. 
     /// For Observation record: "C1C", "L1C", ..., any valid 3 letter observable.
     /// For Meteo record: "PR", "HI", ..., any valid 2 letter sensor physics.
@@ -1091,6 +2439,7 @@ impl Rinex {
     ///   - System Time offset: "GPUT", "GAGP", ..., any valid system time
     /// This has no effect if on ATX and IONEX records.
     pub fn observable_filter_mut (&mut self, filter: Vec<&str>) {
+        self.log_operation(&format!("observables: {}", filter.join(",")));
         if self.is_navigation_rinex() {
             let record = self.record
                 .as_mut_nav()
@@ -1114,15 +2463,13 @@ impl Rinex {
             let record = self.record
                 .as_mut_obs()
                 .unwrap();
+            let filter: Vec<observation::Observable> = filter
+                .iter()
+                .filter_map(|f| observation::Observable::from_str(f).ok())
+                .collect();
             for (_e, (_clk, sv)) in record.iter_mut() {
                 for (_sv, data) in sv.iter_mut() {
-                    data.retain(|code, _| {
-                        let mut found = false;
-                        for f in filter.iter() {
-                            found |= code.eq(f)
-                        }
-                        found
-                    })
+                    data.retain(|code, _| filter.contains(code))
                 }
             }
         } else if self.is_meteo_rinex() {
@@ -1142,15 +2489,17 @@ impl Rinex {
             let record = self.record
                 .as_mut_clock()
                 .unwrap();
-            for (_e, data) in record.iter_mut() {
-                for (_system, data) in data.iter_mut() {
-                    data.retain(|dtype, _| {
-                        let mut found = false;
-                        for f in filter.iter() {
-                            found |= dtype.to_string().eq(f)
-                        }
-                        found
-                    })
+            let retain = |dtype: &clocks::record::DataType| {
+                filter
+                    .iter()
+                    .any(|f| dtype.to_string().eq(f))
+            };
+            for (_e, entries) in record.iter_mut() {
+                for (_station, data) in entries.stations_mut().iter_mut() {
+                    data.retain(|dtype, _| retain(dtype))
+                }
+                for (_sv, data) in entries.satellites_mut().iter_mut() {
+                    data.retain(|dtype, _| retain(dtype))
                 }
             }
         }
@@ -1159,37 +2508,16 @@ impl Rinex {
     /// Executes in place given LLI AND mask filter.
     /// This method is very useful to determine where
     /// loss of lock or external events happened and their nature.
-    /// This has no effect on non observation records.
-    /// Data that do not have an LLI attached to them get also dropped out.
-    pub fn lli_filter_mut (&mut self, mask: observation::record::LliFlags) {
-        if !self.is_observation_rinex() {
-            return ; // nothing to browse
-        }
-        let record = self.record
-            .as_mut_obs()
-            .unwrap();
-        for (_e, (_clk, sv)) in record.iter_mut() {
-            for (_sv, obs) in sv.iter_mut() {
-                obs.retain(|_, data| {
-                    if let Some(lli) = data.lli {
-                        lli.intersects(mask)
-                    } else {
-                        false // drops data with no LLI attached
-                    }
-                })
-            }
-        }
-    }
-
-    /// See [lli_filter_mut]
-    pub fn lli_filter (&self, mask: observation::record::LliFlags) -> Self {
+    /// This has no effect on non observation records.
+    /// Data that do not have an LLI attached to them get also dropped out.
+    pub fn lli_filter_mut (&mut self, mask: observation::record::LliFlags) {
         if !self.is_observation_rinex() {
-            return self.clone(); // nothing to browse
+            return ; // nothing to browse
         }
-        let mut record = self.record
-            .as_obs()
-            .unwrap()
-            .clone();
+        self.log_operation(&format!("lli mask: 0x{:02X}", mask.bits()));
+        let record = self.record
+            .as_mut_obs()
+            .unwrap();
         for (_e, (_clk, sv)) in record.iter_mut() {
             for (_sv, obs) in sv.iter_mut() {
                 obs.retain(|_, data| {
@@ -1201,11 +2529,13 @@ impl Rinex {
                 })
             }
         }
-        Self {
-            record: record::Record::ObsRecord(record),
-            comments: self.comments.clone(),
-            header: self.header.clone(),
-        }
+    }
+
+    /// See [lli_filter_mut]
+    pub fn lli_filter (&self, mask: observation::record::LliFlags) -> Self {
+        let mut s = self.clone();
+        s.lli_filter_mut(mask);
+        s
     }
 
     /// Retains data with a minimum SSI Signal Strength requirement.
@@ -1218,6 +2548,7 @@ impl Rinex {
         if !self.is_observation_rinex() {
             return ; // nothing to browse
         }
+        self.log_operation(&format!("minimum ssi: {}", minimum as u8));
         let record = self.record
             .as_mut_obs()
             .unwrap();
@@ -1237,11 +2568,11 @@ impl Rinex {
     /// Extracts all Ephemeris from this Navigation record,
     /// drops out possible STO / EOP / ION modern NAV frames.
     /// This does not produce anything if self is not a Navigation RINEX.
-    pub fn ephemeris (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64, HashMap<String, navigation::record::ComplexEnum>)>> {
+    pub fn ephemeris (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64, navigation::Ephemeris)>> {
         if !self.is_navigation_rinex() {
             return BTreeMap::new() ; // nothing to browse
         }
-        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64, HashMap<String, navigation::record::ComplexEnum>)>>
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64, navigation::Ephemeris)>>
             = BTreeMap::new();
         let record = self.record
             .as_nav()
@@ -1249,10 +2580,10 @@ impl Rinex {
         for (e, classes) in record.iter() {
             for (class, frames) in classes.iter() {
                 if *class == navigation::record::FrameClass::Ephemeris {
-                    let mut inner: BTreeMap<sv::Sv,  (f64,f64,f64, HashMap<String, navigation::record::ComplexEnum>)> = BTreeMap::new();
+                    let mut inner: BTreeMap<sv::Sv,  (f64,f64,f64, navigation::Ephemeris)> = BTreeMap::new();
                     for frame in frames.iter() {
-                        let (_, sv, clk, clk_dr, clk_drr, map) = frame.as_eph().unwrap();
-                        inner.insert(sv, (clk, clk_dr, clk_drr, map.clone()));
+                        let (_, sv, clk, clk_dr, clk_drr, eph) = frame.as_eph().unwrap();
+                        inner.insert(sv, (clk, clk_dr, clk_drr, *eph));
                     }
                     if inner.len() > 0 {
                         results.insert(*e, inner);
@@ -1263,6 +2594,435 @@ impl Rinex {
         results
     }
 
+    /// Returns the most appropriate Ephemeris frame for given vehicule
+    /// at given epoch, and the epoch of that frame, using Toe / fit interval
+    /// validity windows per constellation, see [navigation::selection::ephemeris_at].
+    /// Returns None if self is not a Navigation RINEX, or if no Ephemeris
+    /// is valid at given epoch.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use std::str::FromStr;
+    /// let rinex = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// let g01 = sv::Sv::from_str("G01").unwrap();
+    /// let e = epoch::Epoch::new(
+    ///     epoch::str2date("2021 01 01 11 00 00").unwrap(),
+    ///     epoch::EpochFlag::Ok);
+    /// if let Some((toc, frame)) = rinex.ephemeris_at(&g01, &e) {
+    ///     let (_, sv, _, _, _, _) = frame.as_eph().unwrap();
+    ///     assert_eq!(sv, g01);
+    ///     assert!((e.date - toc.date).num_seconds().abs() <= 4 * 3600);
+    /// }
+    /// ```
+    pub fn ephemeris_at (&self, sv: &sv::Sv, epoch: &epoch::Epoch) -> Option<(epoch::Epoch, &navigation::Frame)> {
+        let record = self.record.as_nav()?;
+        navigation::selection::ephemeris_at(record, sv, epoch)
+    }
+
+    /// Extracts Ephemeris that describe an orbit (Keplerian elements,
+    /// GLONASS or SBAS state vector) from this Navigation record, per vehicule and per epoch
+    fn orbital_ephemeris (&self) -> BTreeMap<sv::Sv, BTreeMap<epoch::Epoch, navigation::Ephemeris>> {
+        let mut results: BTreeMap<sv::Sv, BTreeMap<epoch::Epoch, navigation::Ephemeris>> = BTreeMap::new();
+        if let Some(record) = self.record.as_nav() {
+            for (e, classes) in record.iter() {
+                for (class, frames) in classes.iter() {
+                    if *class == navigation::record::FrameClass::Ephemeris {
+                        for frame in frames.iter() {
+                            let (_, sv, _, _, _, eph) = frame.as_eph().unwrap();
+                            let orbital = match eph {
+                                navigation::Ephemeris::Glonass(glo) => glo.position.is_some() && glo.velocity.is_some(),
+                                navigation::Ephemeris::Sbas(sbas) => sbas.position.is_some() && sbas.velocity.is_some(),
+                                _ => eph.kepler().is_some(),
+                            };
+                            if orbital {
+                                results
+                                    .entry(sv)
+                                    .or_insert_with(BTreeMap::new)
+                                    .insert(*e, *eph);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Resolves ECEF positions [m] of all GPS, QZSS, Galileo and BeiDou vehicules
+    /// from their broadcast Keplerian elements, GLONASS vehicules from their
+    /// broadcast state vector (PZ-90.11) and SBAS vehicules from their
+    /// broadcast state vector (WGS84), at every Ephemeris epoch.
+    /// This does not produce anything if self is not a Navigation RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// let positions = rinex.space_vehicule_positions();
+    /// for (epoch, vehicules) in positions.iter() {
+    ///     for (sv, (x, y, z)) in vehicules.iter() {
+    ///         // ECEF coordinates, in meters
+    ///     }
+    /// }
+    /// ```
+    pub fn space_vehicule_positions (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64)>> {
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64)>> = BTreeMap::new();
+        for (sv, ephemeris) in self.orbital_ephemeris().iter() {
+            for (e, eph) in ephemeris.iter() {
+                if let Some(position) = eph.position(sv, e, e) {
+                    results
+                        .entry(*e)
+                        .or_insert_with(BTreeMap::new)
+                        .insert(*sv, position);
+                }
+            }
+        }
+        results
+    }
+
+    /// Resolves ECEF positions [m] of all GPS, QZSS, Galileo, BeiDou, GLONASS and SBAS vehicules
+    /// at given epochs, using the closest Ephemeris of each vehicule.
+    /// GLONASS state vectors are propagated to given epochs,
+    /// see [navigation::orbits::glonass_propagate], SBAS state vectors
+    /// are extrapolated, see [navigation::ephemeris::SbasEphemeris::state_at].
+    /// Ephemeris further than [navigation::orbits::MAX_EPHEMERIS_AGE] are not used.
+    /// This does not produce anything if self is not a Navigation RINEX.
+    pub fn space_vehicule_positions_at (&self, epochs: &[epoch::Epoch]) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64)>> {
+        self.resolve_orbits_at(epochs, |eph, sv, toc, e| eph.position(sv, toc, e))
+    }
+
+    /// Resolves ECEF velocities [m.s⁻¹] of all GPS, QZSS, Galileo, BeiDou, GLONASS and SBAS vehicules
+    /// at given epochs, using the closest Ephemeris of each vehicule.
+    /// This does not produce anything if self is not a Navigation RINEX.
+    pub fn space_vehicule_velocities_at (&self, epochs: &[epoch::Epoch]) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64)>> {
+        self.resolve_orbits_at(epochs, |eph, sv, toc, e| eph.velocity(sv, toc, e))
+    }
+
+    /// Returns (elevation, azimuth) angles [°] of all GPS, QZSS, Galileo, BeiDou, GLONASS and SBAS vehicules,
+    /// for every epoch of this record, seen from `ref_position` (ECEF [m]).
+    /// Vehicules positions are resolved from `nav` broadcast Ephemeris.
+    /// When `ref_position` is not provided, header `APPROX POSITION XYZ` is used,
+    /// first from this record, then from the `nav` record, except for
+    /// spaceborne receivers (see [header::Header::is_spaceborne]) that are not static.
+    /// Angles of spaceborne receivers are expressed with respect to the local
+    /// horizon below them: negative elevations are then valid.
+    /// This does not produce anything if no reference position could be determined.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let nav = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// // from Brussels (BE)
+    /// let angles = nav.space_vehicule_elevation_azimuth(&nav, Some((4027881.0, 306998.0, 4919499.0)));
+    /// for (epoch, vehicules) in angles.iter() {
+    ///     for (sv, (elevation, azimuth)) in vehicules.iter() {
+    ///         // masking example
+    ///         if *elevation < 10.0 {
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn space_vehicule_elevation_azimuth (&self, nav: &Self, ref_position: Option<(f64,f64,f64)>) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64)>> {
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64)>> = BTreeMap::new();
+        let ref_position = match self.reference_position(nav, ref_position) {
+            Some(position) => position,
+            None => return results,
+        };
+        let positions = nav.space_vehicule_positions_at(&self.epochs());
+        for (e, vehicules) in positions.iter() {
+            let mut angles: BTreeMap<sv::Sv, (f64,f64)> = BTreeMap::new();
+            for (sv, position) in vehicules.iter() {
+                angles.insert(*sv, navigation::orbits::elevation_azimuth(*position, ref_position));
+            }
+            results.insert(*e, angles);
+        }
+        results
+    }
+
+    /// Estimates the receiver clock offset [s] at every epoch of this Observation record,
+    /// from Ionosphere free pseudo ranges and `nav` broadcast Ephemeris (vehicules
+    /// positions and clocks), seen from `ref_position` (ECEF [m]).
+    /// Troposphere is modeled with a standard atmosphere
+    /// and vehicules below 10° elevation are not used, except for spaceborne
+    /// receivers that operate above the atmosphere.
+    /// When `ref_position` is not provided, header `APPROX POSITION XYZ` is used,
+    /// first from this record, then from the `nav` record, except for
+    /// spaceborne receivers that are not static.
+    /// This does not produce anything if no reference position could be determined,
+    /// or for epochs where pseudo ranges were not evaluated on two separate carriers.
+    /// Use this to identify receiver clock jumps.
+    pub fn receiver_clock_offsets (&self, nav: &Self, ref_position: Option<(f64,f64,f64)>) -> BTreeMap<epoch::Epoch, f64> {
+        let mut results: BTreeMap<epoch::Epoch, f64> = BTreeMap::new();
+        let ref_position = match self.reference_position(nav, ref_position) {
+            Some(position) => position,
+            None => return results,
+        };
+        let residuals = self.pseudo_range_residuals(nav, None, ref_position, 10.0);
+        for (e, vehicules) in residuals.iter() {
+            if vehicules.len() > 0 {
+                let mean = vehicules.values().sum::<f64>() / vehicules.len() as f64;
+                results.insert(*e, mean / channel::SPEED_OF_LIGHT_M_S);
+            }
+        }
+        results
+    }
+
+    /// Estimates the receiver oscillator drift [ppm] of this Observation record,
+    /// by comparing the epoch spacing to the rate of change of vehicules clock
+    /// corrected pseudo ranges, see [Rinex::receiver_clock_offsets] for
+    /// `nav` and `ref_position` usage.
+    /// Only vehicules observed on two successive epochs contribute,
+    /// so changes in the set of tracked vehicules do not bias the estimate.
+    /// Drift is reported at the latter epoch. Large values indicate
+    /// receiver clock steering (jumps) or a poor oscillator.
+    pub fn receiver_clock_drifts (&self, nav: &Self, ref_position: Option<(f64,f64,f64)>) -> BTreeMap<epoch::Epoch, f64> {
+        let mut results: BTreeMap<epoch::Epoch, f64> = BTreeMap::new();
+        let ref_position = match self.reference_position(nav, ref_position) {
+            Some(position) => position,
+            None => return results,
+        };
+        let residuals = self.pseudo_range_residuals(nav, None, ref_position, 10.0);
+        let mut prev: Option<(&epoch::Epoch, &BTreeMap<sv::Sv, f64>)> = None;
+        for (e, vehicules) in residuals.iter() {
+            if let Some((prev_e, prev_vehicules)) = prev {
+                let dt = (e.date - prev_e.date).num_milliseconds() as f64 / 1.0E3;
+                let rates: Vec<f64> = vehicules
+                    .iter()
+                    .filter_map(|(sv, r)| {
+                        prev_vehicules.get(sv)
+                            .map(|prev_r| (r - prev_r) / dt)
+                    })
+                    .collect();
+                if dt > 0.0 && rates.len() > 0 {
+                    let rate = rates.iter().sum::<f64>() / rates.len() as f64;
+                    results.insert(*e, rate / channel::SPEED_OF_LIGHT_M_S * 1.0E6);
+                }
+            }
+            prev = Some((e, vehicules));
+        }
+        results
+    }
+
+    /// Estimates inter system range biases [m] of this static receiver,
+    /// per constellation pair, at every epoch of this Observation record.
+    /// See [Rinex::receiver_clock_offsets] for `nav` and `ref_position` usage.
+    /// Pseudo range residuals are averaged per constellation, the bias of
+    /// pair (A, B) is then the mean residual of B minus the mean residual of A,
+    /// with A < B. A pair is only reported at epochs where both constellations contributed.
+    /// Only GPS, QZSS, Galileo and BeiDou vehicules can be resolved.
+    /// Biases combine receiver hardware delays and offsets between GNSS time scales:
+    /// they should remain stable, jumps betray receiver resets or firmware changes.
+    pub fn inter_system_biases (&self, nav: &Self, ref_position: Option<(f64,f64,f64)>) -> BTreeMap<(constellation::Constellation, constellation::Constellation), BTreeMap<epoch::Epoch, f64>> {
+        let mut results: BTreeMap<(constellation::Constellation, constellation::Constellation), BTreeMap<epoch::Epoch, f64>> = BTreeMap::new();
+        let ref_position = match self.reference_position(nav, ref_position) {
+            Some(position) => position,
+            None => return results,
+        };
+        let residuals = self.pseudo_range_residuals(nav, None, ref_position, 10.0);
+        for (e, vehicules) in residuals.iter() {
+            // mean residual, per constellation
+            let mut sums: BTreeMap<constellation::Constellation, (f64, usize)> = BTreeMap::new();
+            for (sv, residual) in vehicules.iter() {
+                let (sum, n) = sums
+                    .entry(sv.constellation)
+                    .or_insert((0.0, 0));
+                *sum += residual;
+                *n += 1;
+            }
+            let means: Vec<(constellation::Constellation, f64)> = sums
+                .into_iter()
+                .map(|(c, (sum, n))| (c, sum / n as f64))
+                .collect();
+            for (i, (a, mean_a)) in means.iter().enumerate() {
+                for (b, mean_b) in means.iter().skip(i+1) {
+                    results
+                        .entry((*a, *b))
+                        .or_insert_with(BTreeMap::new)
+                        .insert(*e, mean_b - mean_a);
+                }
+            }
+        }
+        results
+    }
+
+    /// Returns given reference position (ECEF [m]), or header
+    /// `APPROX POSITION XYZ`, first from self, then from `nav`.
+    /// Spaceborne receivers move: header position is not relevant
+    fn reference_position (&self, nav: &Self, ref_position: Option<(f64,f64,f64)>) -> Option<(f64,f64,f64)> {
+        match ref_position {
+            Some(position) => Some(position),
+            None if self.header.is_spaceborne() => None,
+            None => {
+                self.header.coords
+                    .as_ref()
+                    .or(nav.header.coords.as_ref())
+                    .map(|p| (p.x, p.y, p.z))
+            },
+        }
+    }
+
+    /// Returns zenith (hydrostatic, wet) tropospheric delays [m] per epoch,
+    /// Saastamoinen model, from Meteo observations (pressure, temperature, humidity),
+    /// at given station latitude [rad] and altitude [m].
+    /// Missing observations are replaced by standard atmosphere.
+    /// Use [meteo::troposphere::slant_delay] to project them onto a line of sight.
+    /// Returns an empty map if this is not a Meteo RINEX.
+    /// ```
+    /// use rinex::*;
+    /// use rinex::meteo::troposphere::{self, MappingFunction};
+    /// let rinex = Rinex::from_file("../test_resources/MET/V2/abvi0010.15m")
+    ///     .unwrap();
+    /// let delays = rinex.zenith_tropospheric_delays(0.3, 0.0);
+    /// let (_, (zhd, zwd)) = delays.iter().next().unwrap();
+    /// assert!((zhd - 2.324).abs() < 1.0E-3);
+    /// assert!((zwd - 0.252).abs() < 1.0E-3);
+    /// let slant = troposphere::slant_delay((*zhd, *zwd), 30.0, MappingFunction::default());
+    /// assert!(slant > 2.0 * (zhd + zwd) * 0.99);
+    /// ```
+    pub fn zenith_tropospheric_delays (&self, latitude: f64, altitude: f64) -> BTreeMap<epoch::Epoch, (f64, f64)> {
+        if let Some(record) = self.record.as_meteo() {
+            meteo::troposphere::zenith_delays_per_epoch(record, latitude, altitude)
+        } else {
+            BTreeMap::new()
+        }
+    }
+
+    /// Returns Ionosphere free pseudo ranges [m], from which modeled geometry,
+    /// vehicules clocks and troposphere were removed. What remains is mostly
+    /// the receiver clock offset, common to all vehicules, and noise.
+    /// Troposphere is modeled from the closest `meteo` observations,
+    /// standard atmosphere otherwise. Spaceborne receivers operate above
+    /// the atmosphere: troposphere and elevation mask do not apply.
+    fn pseudo_range_residuals (&self, nav: &Self, meteo: Option<&Self>, ref_position: (f64,f64,f64), elevation_mask: f64) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> = BTreeMap::new();
+        let spaceborne = self.header.is_spaceborne();
+        let (latitude, _, altitude) = navigation::orbits::ecef2geodetic(ref_position.0, ref_position.1, ref_position.2);
+        let epochs = self.epochs();
+        let positions = nav.space_vehicule_positions_at(&epochs);
+        let velocities = nav.space_vehicule_velocities_at(&epochs);
+        // vehicules clocks, per vehicule
+        let mut clocks: BTreeMap<sv::Sv, BTreeMap<epoch::Epoch, (f64,f64,f64)>> = BTreeMap::new();
+        for (toc, vehicules) in nav.space_vehicule_clocks_drift() {
+            for (sv, clock) in vehicules {
+                clocks
+                    .entry(sv)
+                    .or_insert_with(BTreeMap::new)
+                    .insert(toc, clock);
+            }
+        }
+        let meteo = meteo.and_then(|meteo| meteo.record.as_meteo());
+        let c = channel::SPEED_OF_LIGHT_M_S;
+        let pseudo_ranges = self.pseudo_range_combinations(observation::combination::Combination::IonosphereFree);
+        for (e, vehicules) in pseudo_ranges.iter() {
+            let (pressure, temperature, humidity) = meteo::troposphere::conditions(meteo, e, altitude);
+            let mut map: BTreeMap<sv::Sv, f64> = BTreeMap::new();
+            for (sv, pr) in vehicules.iter() {
+                if sv.constellation == constellation::Constellation::Glonass {
+                    // GLONASS clock model and time scale (UTC) differ
+                    continue
+                }
+                let sv_position = match positions.get(e).and_then(|p| p.get(sv)) {
+                    Some(p) => *p,
+                    None => continue,
+                };
+                let sv_velocity = match velocities.get(e).and_then(|v| v.get(sv)) {
+                    Some(v) => *v,
+                    None => continue,
+                };
+                let clock = match clocks.get(sv) {
+                    Some(clocks) => {
+                        let before = clocks.range(..=*e).next_back();
+                        let after = clocks.range(*e..).next();
+                        match (before, after) {
+                            (Some(b), Some(a)) => {
+                                if (e.date - b.0.date) <= (a.0.date - e.date) {
+                                    b
+                                } else {
+                                    a
+                                }
+                            },
+                            (Some(b), None) => b,
+                            (None, Some(a)) => a,
+                            (None, None) => continue,
+                        }
+                    },
+                    None => continue,
+                };
+                let (elevation, _) = navigation::orbits::elevation_azimuth(sv_position, ref_position);
+                if !spaceborne && elevation < elevation_mask {
+                    continue
+                }
+                // light time and earth rotation during signal propagation
+                let tau = pr / c;
+                let (x, y, z) = (
+                    sv_position.0 - sv_velocity.0 * tau,
+                    sv_position.1 - sv_velocity.1 * tau,
+                    sv_position.2 - sv_velocity.2 * tau);
+                let theta = navigation::orbits::GPS_OMEGA_E * tau;
+                let (x, y) = (
+                    x * theta.cos() + y * theta.sin(),
+                    -x * theta.sin() + y * theta.cos());
+                let range = ((x - ref_position.0).powi(2)
+                    + (y - ref_position.1).powi(2)
+                    + (z - ref_position.2).powi(2)).sqrt();
+                // vehicule clock, with relativistic correction
+                let dt = (e.date - clock.0.date).num_milliseconds() as f64 / 1.0E3;
+                let (a0, a1, a2) = *clock.1;
+                let relativistic = -2.0 * (sv_position.0 * sv_velocity.0
+                    + sv_position.1 * sv_velocity.1
+                    + sv_position.2 * sv_velocity.2) / c.powi(2);
+                let sv_clock = a0 + a1 * dt + a2 * dt.powi(2) + relativistic;
+                let troposphere = match spaceborne {
+                    true => 0.0,
+                    false => meteo::troposphere::saastamoinen(latitude, altitude, elevation, pressure, temperature, humidity),
+                };
+                map.insert(*sv, pr - range + c * sv_clock - troposphere);
+            }
+            if map.len() > 0 {
+                results.insert(*e, map);
+            }
+        }
+        results
+    }
+
+    /// Evaluates `f` at given epochs, using the closest Ephemeris of each vehicule
+    fn resolve_orbits_at<F> (&self, epochs: &[epoch::Epoch], f: F) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64)>>
+        where F: Fn(&navigation::Ephemeris, &sv::Sv, &epoch::Epoch, &epoch::Epoch) -> Option<(f64,f64,f64)>
+    {
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64)>> = BTreeMap::new();
+        let elements = self.orbital_ephemeris();
+        for e in epochs.iter() {
+            for (sv, ephemeris) in elements.iter() {
+                let before = ephemeris.range(..=*e).next_back();
+                let after = ephemeris.range(*e..).next();
+                let closest = match (before, after) {
+                    (Some(b), Some(a)) => {
+                        if (e.date - b.0.date) <= (a.0.date - e.date) {
+                            b
+                        } else {
+                            a
+                        }
+                    },
+                    (Some(b), None) => b,
+                    (None, Some(a)) => a,
+                    (None, None) => continue,
+                };
+                let age = (e.date - closest.0.date).num_seconds().abs();
+                if age > navigation::orbits::MAX_EPHEMERIS_AGE {
+                    continue
+                }
+                if let Some(value) = f(closest.1, sv, closest.0, e) {
+                    results
+                        .entry(*e)
+                        .or_insert_with(BTreeMap::new)
+                        .insert(*sv, value);
+                }
+            }
+        }
+        results
+    }
+
     /// Filters out all Legacy Ephemeris freames from this Navigation record.
     /// This is intended to be used only on modern (V>3) Navigation record,
     /// which are the only records expected to contain other frame types.
@@ -1271,6 +3031,7 @@ impl Rinex {
         if !self.is_navigation_rinex() {
             return ; // nothing to do
         }
+        self.log_operation("legacy nav filter");
         let record = self.record
             .as_mut_nav()
             .unwrap();
@@ -1295,6 +3056,7 @@ impl Rinex {
         if !self.is_navigation_rinex() {
             return ; // nothing to do
         }
+        self.log_operation("modern nav filter");
         let record = self.record
             .as_mut_nav()
             .unwrap();
@@ -1310,6 +3072,51 @@ impl Rinex {
         }
     }
 
+    /// Retains only given Navigation frame classes,
+    /// for example only Ephemeris, or only ION / STO / EOP frames.
+    /// Epochs that no longer contain any frame are dropped.
+    /// This has no effect if self is not a Navigation record.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::navigation::record::FrameClass;
+    /// let mut rinex = Rinex::from_file("../test_resources/NAV/V4/FRMS00XXX_R_20221591000_01H_MN.rnx")
+    ///     .unwrap();
+    /// rinex.retain_frame_classes_mut(&[FrameClass::IonosphericModel, FrameClass::SystemTimeOffset]);
+    /// let histogram = rinex.frame_class_histogram();
+    /// assert_eq!(histogram.get(&FrameClass::Ephemeris), None);
+    /// assert_eq!(histogram.get(&FrameClass::SystemTimeOffset), Some(&2));
+    /// ```
+    pub fn retain_frame_classes_mut (&mut self, classes: &[navigation::record::FrameClass]) {
+        if !self.is_navigation_rinex() {
+            return ; // nothing to do
+        }
+        self.log_operation("frame classes filter");
+        let record = self.record
+            .as_mut_nav()
+            .unwrap();
+        record.retain(|_, frames| {
+            frames.retain(|class, frames| classes.contains(class) && frames.len() > 0);
+            frames.len() > 0
+        })
+    }
+
+    /// Returns number of Navigation frames, per frame class.
+    /// This does not produce anything if self is not a Navigation record
+    pub fn frame_class_histogram (&self) -> BTreeMap<navigation::record::FrameClass, usize> {
+        let mut histogram: BTreeMap<navigation::record::FrameClass, usize> = BTreeMap::new();
+        if let Some(record) = self.record.as_nav() {
+            for (_, classes) in record.iter() {
+                for (class, frames) in classes.iter() {
+                    if frames.len() > 0 {
+                        *histogram.entry(*class).or_insert(0) += frames.len();
+                    }
+                }
+            }
+        }
+        histogram
+    }
+
     /// Extracts all System Time Offset data
     /// on a epoch basis, from this Navigation record.
     /// This does not produce anything if self is not a modern Navigation record
@@ -1339,6 +3146,29 @@ impl Rinex {
         results
     }
 
+    /// Extracts from this Navigation record all Almanacs, on an epoch basis
+    /// and per vehicule described. This does not produce anything if
+    /// self is not a modern Navigation record that contains such frames.
+    pub fn almanacs (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, navigation::almmessage::Message>> {
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, navigation::almmessage::Message>> = BTreeMap::new();
+        if let Some(record) = self.record.as_nav() {
+            for (e, classes) in record.iter() {
+                if let Some(frames) = classes.get(&navigation::record::FrameClass::Almanac) {
+                    let mut inner: BTreeMap<sv::Sv, navigation::almmessage::Message> = BTreeMap::new();
+                    for frame in frames.iter() {
+                        if let Some(alm) = frame.as_alm() {
+                            inner.insert(frame.sv(), alm.clone());
+                        }
+                    }
+                    if inner.len() > 0 {
+                        results.insert(*e, inner);
+                    }
+                }
+            }
+        }
+        results
+    }
+
     /// Extracts from this Navigation record all Ionospheric Models, on a epoch basis,
     /// regardless of their kind. This does not produce anything if 
     /// self is not a modern Navigation record that contains such models.
@@ -1457,27 +3287,207 @@ impl Rinex {
         results
     }
 
-    /// Extracts Pseudo Range data from this
-    /// Observation record, on an epoch basis an per space vehicule. 
+    /// Returns all Ionospheric corrections contained in this Navigation RINEX,
+    /// whether they were described in the header (V2/V3) or in ION frames (V4).
+    /// Header corrections come first, then record frames in chronological order.
+    /// This does not produce anything if self is not a Navigation RINEX.
+    pub fn ionosphere_corrections (&self) -> Vec<navigation::IonosphericCorrection> {
+        if !self.is_navigation_rinex() {
+            return Vec::new(); // nothing to browse
+        }
+        let mut results = self.header.ionospheric_corr.clone();
+        let record = self.record
+            .as_nav()
+            .unwrap();
+        for (e, classes) in record.iter() {
+            if let Some(frames) = classes.get(&navigation::record::FrameClass::IonosphericModel) {
+                for frame in frames.iter() {
+                    if let navigation::record::Frame::Ion(_, sv, model) = frame {
+                        results.push(navigation::IonosphericCorrection {
+                            constellation: sv.constellation,
+                            epoch: Some(*e),
+                            model: model.clone(),
+                        })
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Returns all Time System corrections contained in this Navigation RINEX,
+    /// whether they were described in the header (V2/V3) or in STO frames (V4).
+    /// Header corrections come first, then record frames in chronological order.
+    /// This does not produce anything if self is not a Navigation RINEX.
+    pub fn time_system_corrections (&self) -> Vec<navigation::TimeSystemCorrection> {
+        if !self.is_navigation_rinex() {
+            return Vec::new(); // nothing to browse
+        }
+        let mut results = self.header.time_system_corr.clone();
+        let record = self.record
+            .as_nav()
+            .unwrap();
+        for (e, classes) in record.iter() {
+            if let Some(frames) = classes.get(&navigation::record::FrameClass::SystemTimeOffset) {
+                for frame in frames.iter() {
+                    if let Some(sto) = frame.as_sto() {
+                        results.push(navigation::TimeSystemCorrection {
+                            system: sto.system.clone(),
+                            utc: sto.utc.clone(),
+                            epoch: Some(*e),
+                            reference: None,
+                            a: sto.a,
+                        })
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Extracts Pseudo Range data from this
+    /// Observation record, on an epoch basis an per space vehicule. 
+    /// Pseudo ranges are expressed in meters.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn pseudo_ranges (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
+        if !self.is_observation_rinex() {
+            return BTreeMap::new() ; // nothing to browse
+        }
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> = BTreeMap::new();
+        let record = self.record
+            .as_obs()
+            .unwrap();
+        for (e, (_, sv)) in record.iter() {
+            let mut map: BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>> = BTreeMap::new();
+            for (sv, obs) in sv.iter() {
+                let mut v : Vec<(observation::Observable, f64)> = Vec::new();
+                for (code, data) in obs.iter() {
+                    if code.is_pseudo_range() {
+                        v.push((*code, data.obs));
+                    }
+                }
+                if v.len() > 0 { // did come with at least 1 PR
+                    map.insert(*sv, v);
+                }
+            }
+            if map.len() > 0 { // did produce something
+                results.insert(*e, map);
+            }
+        }
+        results
+    }
+    
+    /// Extracts Pseudo Ranges without Ionospheric path delay contributions,
+    /// by extracting [pseudo_ranges] and using the differential (dual frequency) compensation.
+    /// We can only compute such information if pseudo range was evaluted
+    /// on at least two seperate carrier frequencies, for a given space vehicule at a certain epoch.
+    /// Results are expressed in meters.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn iono_free_pseudo_ranges (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+        self.pseudo_range_combinations(observation::combination::Combination::IonosphereFree)
+    }
+    
+    /// Extracts Raw Carrier Phase observations, expressed in carrier cycles.
+    /// This name does not tell the unit: use [Rinex::carrier_phases_cycles]
+    /// or [Rinex::carrier_phases_m] instead.
+    #[deprecated(note = "use carrier_phases_cycles() or carrier_phases_m()")]
+    pub fn carrier_phases (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
+        self.carrier_phases_cycles()
+    }
+
+    /// Extracts Raw Carrier Phase observations,
+    /// from this Observation record, on an epoch basis an per space vehicule. 
+    /// Carrier phases are expressed in carrier cycles, as found in the file,
+    /// use [Rinex::carrier_phases_m] to obtain them in meters.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn carrier_phases_cycles (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
+        if !self.is_observation_rinex() {
+            return BTreeMap::new() ; // nothing to browse
+        }
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> = BTreeMap::new();
+        let record = self.record
+            .as_obs()
+            .unwrap();
+        for (e, (_, sv)) in record.iter() {
+            let mut map: BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>> = BTreeMap::new();
+            for (sv, obs) in sv.iter() {
+                let mut v : Vec<(observation::Observable, f64)> = Vec::new();
+                for (code, data) in obs.iter() {
+                    if code.is_phase() {
+                        v.push((*code, data.obs));
+                    }
+                }
+                if v.len() > 0 { // did come with at least 1 Phase obs
+                    map.insert(*sv, v);
+                }
+            }
+            if map.len() > 0 { // did produce something
+                results.insert(*e, map);
+            }
+        }
+        results
+    }
+    
+    /// Extracts Carrier Phase observations converted to meters,
+    /// from this Observation record, on an epoch basis an per space vehicule. 
+    /// Observables for which the carrier frequency could not be identified
+    /// are not exposed. Glonass wavelengths take the FDMA channel number
+    /// into account, when described in the header.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn carrier_phases_m (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
+        let glo_channels = self.glo_channels();
+        let mut results = self.carrier_phases_cycles();
+        for (_, vehicules) in results.iter_mut() {
+            for (sv, phases) in vehicules.iter_mut() {
+                *phases = phases
+                    .iter()
+                    .filter_map(|(code, cycles)| {
+                        channel::Channel::from_sv_observable(sv, code, &glo_channels)
+                            .ok()
+                            .map(|channel| (*code, channel.cycles_to_m(*cycles)))
+                    })
+                    .collect();
+            }
+            vehicules.retain(|_, phases| phases.len() > 0);
+        }
+        results.retain(|_, vehicules| vehicules.len() > 0);
+        results
+    }
+
+    /// Returns Glonass FDMA channel numbers, per vehicule,
+    /// as described in the header (GLONASS SLOT / FRQ #).
+    /// Returns an empty map if self is not an Observation RINEX
+    /// or this field was not provided.
+    pub fn glo_channels (&self) -> HashMap<sv::Sv, i8> {
+        self.header.obs
+            .as_ref()
+            .map(|obs| obs.glo_channels.clone())
+            .unwrap_or(HashMap::new())
+    }
+
+    /// Extracts Raw Doppler observations,
+    /// from this Observation record, on an epoch basis an per space vehicule.
+    /// Doppler shifts are expressed in Hz, as found in the file,
+    /// use [Rinex::range_rates] to obtain them in m.s⁻¹.
     /// Does not produce anything if self is not an Observation RINEX.
-    pub fn pseudo_ranges (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(String, f64)>>> {
+    pub fn doppler_shifts (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
         if !self.is_observation_rinex() {
             return BTreeMap::new() ; // nothing to browse
         }
-        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(String, f64)>>> = BTreeMap::new();
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> = BTreeMap::new();
         let record = self.record
             .as_obs()
             .unwrap();
         for (e, (_, sv)) in record.iter() {
-            let mut map: BTreeMap<sv::Sv, Vec<(String, f64)>> = BTreeMap::new();
+            let mut map: BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>> = BTreeMap::new();
             for (sv, obs) in sv.iter() {
-                let mut v : Vec<(String, f64)> = Vec::new();
+                let mut v : Vec<(observation::Observable, f64)> = Vec::new();
                 for (code, data) in obs.iter() {
-                    if is_pseudo_range_obs_code!(code) {
-                        v.push((code.clone(), data.obs));
+                    if code.is_doppler() {
+                        v.push((*code, data.obs));
                     }
                 }
-                if v.len() > 0 { // did come with at least 1 PR
+                if v.len() > 0 { // did come with at least 1 Doppler obs
                     map.insert(*sv, v);
                 }
             }
@@ -1487,142 +3497,191 @@ impl Rinex {
         }
         results
     }
-    
-    /// Extracts Pseudo Ranges without Ionospheric path delay contributions,
-    /// by extracting [pseudo_ranges] and using the differential (dual frequency) compensation.
-    /// We can only compute such information if pseudo range was evaluted
-    /// on at least two seperate carrier frequencies, for a given space vehicule at a certain epoch.
+
+    /// Extracts Doppler observations converted to range rates [m.s⁻¹],
+    /// from this Observation record, on an epoch basis an per space vehicule.
+    /// Range rates are negative when vehicules are approaching.
+    /// Observables for which the carrier frequency could not be identified
+    /// are not exposed. Glonass wavelengths take the FDMA channel number
+    /// into account, when described in the header.
     /// Does not produce anything if self is not an Observation RINEX.
-    pub fn iono_free_pseudo_ranges (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
-        let pr = self.pseudo_ranges();
-        let mut results : BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> = BTreeMap::new();
-        for (e, sv) in pr.iter() {
-            let mut map :BTreeMap<sv::Sv, f64> = BTreeMap::new();
-            for (sv, obs) in sv.iter() {
-                let mut result :Option<f64> = None; 
-                let mut retained : Vec<(String, f64)> = Vec::new();
-                for (code, value) in obs.iter() {
-                    if is_pseudo_range_obs_code!(code) {
-                        retained.push((code.clone(), *value));
-                    }
-                }
-                if retained.len() > 1 { // got a dual frequency scenario
-                    // we only care about 2 carriers
-                    let retained = &retained[0..2]; 
-                    // only left with two observables at this point
-                    // (obscode, data) mapping 
-                    let codes :Vec<String> = retained.iter().map(|r| r.0.clone()).collect();
-                    let data :Vec<f64> = retained.iter().map(|r| r.1).collect();
-                    // need to determine frequencies involved
-                    let mut channels :Vec<channel::Channel> = Vec::with_capacity(2);
-                    for i in 0..codes.len() {
-                        if let Ok(channel) = channel::Channel::from_observable(sv.constellation, &codes[i]) {
-                            channels.push(channel)
-                        }
-                    }
-                    if channels.len() == 2 { // frequency identification passed, twice
-                        // --> compute 
-                        let f0 = (channels[0].carrier_frequency_mhz() *1.0E6).powf(2.0_f64);
-                        let f1 = (channels[1].carrier_frequency_mhz() *1.0E6).powf(2.0_f64);
-                        let diff = (f0 * data[0] - f1 * data[1] ) / (f0 - f1) ;
-                        result = Some(diff)
-                    }
-                }
-                if let Some(result) = result {
-                    // conditions were met for this vehicule
-                    // at this epoch
-                    map.insert(*sv, result);
-                }
-            }
-            if map.len() > 0 { // did produce something
-                results.insert(*e, map);
+    pub fn range_rates (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
+        let glo_channels = self.glo_channels();
+        let mut results = self.doppler_shifts();
+        for (_, vehicules) in results.iter_mut() {
+            for (sv, shifts) in vehicules.iter_mut() {
+                *shifts = shifts
+                    .iter()
+                    .filter_map(|(code, doppler)| {
+                        channel::Channel::from_sv_observable(sv, code, &glo_channels)
+                            .ok()
+                            .map(|channel| (*code, channel.doppler_to_range_rate(*doppler)))
+                    })
+                    .collect();
             }
+            vehicules.retain(|_, shifts| shifts.len() > 0);
         }
+        results.retain(|_, vehicules| vehicules.len() > 0);
         results
     }
-    
-    /// Extracts Raw Carrier Phase observations,
-    /// from this Observation record, on an epoch basis an per space vehicule. 
-    /// Does not produce anything if self is not an Observation RINEX.
-    pub fn carrier_phases (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(String, f64)>>> {
+
+    /// Synthesizes Galileo E5 AltBOC pseudo range, carrier phase and Doppler
+    /// observations, for vehicules that were tracked on E5a and E5b but not on E5 AltBOC,
+    /// see [observation::combination::e5_altboc]. Synthesized observables are
+    /// labeled "C8X", "L8X" and "D8X", and are declared in the header.
+    /// Lock loss indicators of both signals are combined, the weakest SSI is retained.
+    /// This has no effect if self is not an Observation RINEX.
+    pub fn e5_altboc_synthesis_mut (&mut self) {
         if !self.is_observation_rinex() {
-            return BTreeMap::new() ; // nothing to browse
+            return ; // nothing to browse
         }
-        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(String, f64)>>> = BTreeMap::new();
+        self.log_operation("E5 AltBOC synthesis");
+        let (e5a, e5b, e8) = (channel::Channel::E5, channel::Channel::E7, channel::Channel::E8);
+        let mut synthesized: Vec<observation::Observable> = Vec::new();
         let record = self.record
-            .as_obs()
+            .as_mut_obs()
             .unwrap();
-        for (e, (_, sv)) in record.iter() {
-            let mut map: BTreeMap<sv::Sv, Vec<(String, f64)>> = BTreeMap::new();
-            for (sv, obs) in sv.iter() {
-                let mut v : Vec<(String, f64)> = Vec::new();
-                for (code, data) in obs.iter() {
-                    if is_phase_carrier_obs_code!(code) {
-                        v.push((code.clone(), data.obs));
-                    }
+        let kinds: [fn(observation::Code) -> observation::Observable; 3] = [
+            observation::Observable::PseudoRange,
+            observation::Observable::Phase,
+            observation::Observable::Doppler,
+        ];
+        for (_, (_, vehicules)) in record.iter_mut() {
+            for (sv, observations) in vehicules.iter_mut() {
+                if sv.constellation != constellation::Constellation::Galileo {
+                    continue
                 }
-                if v.len() > 0 { // did come with at least 1 Phase obs
-                    map.insert(*sv, v);
+                for kind in kinds.iter() {
+                    let code = kind(observation::Code::new('8', Some('X')));
+                    // first observable (alphabetical order) of this kind, on given band
+                    let first = |band: char| -> Option<observation::record::ObservationData> {
+                        observations
+                            .keys()
+                            .filter(|c| c.band() == band && kind(c.code()) == **c)
+                            .min()
+                            .map(|c| observations[c])
+                    };
+                    if first('8').is_some() {
+                        continue // AltBOC was tracked
+                    }
+                    let (a, b) = match (first('5'), first('7')) {
+                        (Some(a), Some(b)) => (a, b),
+                        _ => continue,
+                    };
+                    let obs = match code {
+                        observation::Observable::PseudoRange(_) => observation::combination::e5_altboc(a.obs, b.obs),
+                        observation::Observable::Phase(_) => {
+                            let m = observation::combination::e5_altboc(e5a.cycles_to_m(a.obs), e5b.cycles_to_m(b.obs));
+                            e8.m_to_cycles(m)
+                        },
+                        _ => {
+                            let rate = observation::combination::e5_altboc(
+                                e5a.doppler_to_range_rate(a.obs),
+                                e5b.doppler_to_range_rate(b.obs));
+                            -rate / e8.wavelength_m()
+                        },
+                    };
+                    let lli = match (a.lli, b.lli) {
+                        (None, None) => None,
+                        (a, b) => Some(a.unwrap_or(observation::record::LliFlags::empty())
+                            | b.unwrap_or(observation::record::LliFlags::empty())),
+                    };
+                    let ssi = match (a.ssi, b.ssi) {
+                        (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+                        _ => None,
+                    };
+                    if !synthesized.contains(&code) {
+                        synthesized.push(code);
+                    }
+                    observations.insert(code, observation::record::ObservationData::new(obs, lli, ssi));
                 }
             }
-            if map.len() > 0 { // did produce something
-                results.insert(*e, map);
+        }
+        if let Some(obs) = self.header.obs.as_mut() {
+            if synthesized.len() > 0 {
+                let codes = obs.codes
+                    .entry(constellation::Constellation::Galileo)
+                    .or_insert_with(Vec::new);
+                for code in synthesized {
+                    if !codes.contains(&code) {
+                        codes.push(code);
+                    }
+                }
             }
         }
-        results
     }
-    
+
     /// Extracts Carrier phases without Ionospheric path delay contributions,
-    /// by extracting [carrier_phases] and using the differential (dual frequency) compensation.
+    /// by extracting [Rinex::carrier_phases_m] and using the differential (dual frequency) compensation.
     /// We can only compute such information if carrier phase was evaluted
     /// on at least two seperate carrier frequencies, for a given space vehicule at a certain epoch.
+    /// Results are expressed in meters.
     /// Does not produce anything if self is not an Observation RINEX.
     pub fn iono_free_carrier_phases (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
-        let pr = self.pseudo_ranges();
-        let mut results : BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> = BTreeMap::new();
-        for (e, sv) in pr.iter() {
-            let mut map :BTreeMap<sv::Sv, f64> = BTreeMap::new();
-            for (sv, obs) in sv.iter() {
-                let mut result :Option<f64> = None; 
-                let mut retained : Vec<(String, f64)> = Vec::new();
-                for (code, value) in obs.iter() {
-                    if is_phase_carrier_obs_code!(code) {
-                        retained.push((code.clone(), *value));
-                    }
-                }
-                if retained.len() > 1 { // got a dual frequency scenario
-                    // we only care about 2 carriers
-                    let retained = &retained[0..2]; 
-                    // only left with two observables at this point
-                    // (obscode, data) mapping 
-                    let codes :Vec<String> = retained.iter().map(|r| r.0.clone()).collect();
-                    let data :Vec<f64> = retained.iter().map(|r| r.1).collect();
-                    // need to determine frequencies involved
-                    let mut channels :Vec<channel::Channel> = Vec::with_capacity(2);
-                    for i in 0..codes.len() {
-                        if let Ok(channel) = channel::Channel::from_observable(sv.constellation, &codes[i]) {
-                            channels.push(channel)
-                        }
-                    }
-                    if channels.len() == 2 { // frequency identification passed, twice
-                        // --> compute 
-                        let f0 = (channels[0].carrier_frequency_mhz() *1.0E6).powf(2.0_f64);
-                        let f1 = (channels[1].carrier_frequency_mhz() *1.0E6).powf(2.0_f64);
-                        let diff = (f0 * data[0] - f1 * data[1] ) / (f0 - f1) ;
-                        result = Some(diff)
+        self.carrier_phase_combinations(observation::combination::Combination::IonosphereFree)
+    }
+
+    /// Evaluates given linear combination on [Rinex::pseudo_ranges],
+    /// on an epoch basis and per space vehicule.
+    /// We can only compute such information if pseudo range was evaluated
+    /// on at least two seperate carrier frequencies,
+    /// refer to [observation::combination::dual_frequency] for carrier selection.
+    /// Results are expressed in meters.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn pseudo_range_combinations (&self, combination: observation::combination::Combination) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+        observation::combination::combine(&self.pseudo_ranges(), combination)
+    }
+
+    /// Evaluates given linear combination on [Rinex::carrier_phases_m],
+    /// on an epoch basis and per space vehicule.
+    /// We can only compute such information if carrier phase was evaluated
+    /// on at least two seperate carrier frequencies,
+    /// refer to [observation::combination::dual_frequency] for carrier selection.
+    /// Results are expressed in meters.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn carrier_phase_combinations (&self, combination: observation::combination::Combination) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+        observation::combination::combine(&self.carrier_phases_m(), combination)
+    }
+
+    /// Evaluates the Melbourne-Wübbena combination,
+    /// on an epoch basis and per space vehicule,
+    /// from [Rinex::pseudo_ranges] and [Rinex::carrier_phases_m].
+    /// Results are expressed in meters.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn melbourne_wubbena (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+        observation::combination::melbourne_wubbena(&self.pseudo_ranges(), &self.carrier_phases_m())
+    }
+
+    /// Evaluates the temporal cross-correlation of signal strengths (S codes),
+    /// between all pairs of vehicules and frequencies, over consecutive time windows.
+    /// A high correlation across the whole matrix, see
+    /// [observation::correlation::Matrix::mean_correlation], indicates an event
+    /// affecting the whole band, like an interference.
+    /// Use [observation::correlation::cross_correlation] directly
+    /// to correlate other time series, like residuals.
+    /// Does not produce anything if self is not an Observation RINEX.
+    pub fn snr_cross_correlation (&self, window: chrono::Duration) -> BTreeMap<epoch::Epoch, observation::correlation::Matrix> {
+        if !self.is_observation_rinex() {
+            return BTreeMap::new()
+        }
+        let record = self.record
+            .as_obs()
+            .unwrap();
+        let mut series : BTreeMap<epoch::Epoch, BTreeMap<observation::correlation::Signal, f64>> = BTreeMap::new();
+        for (e, (_, vehicules)) in record.iter() {
+            let mut signals : BTreeMap<observation::correlation::Signal, f64> = BTreeMap::new();
+            for (sv, observations) in vehicules.iter() {
+                for (code, data) in observations.iter() {
+                    if code.is_ssi() {
+                        signals.insert((*sv, *code), data.obs);
                     }
                 }
-                if let Some(result) = result {
-                    // conditions were met for this vehicule
-                    // at this epoch
-                    map.insert(*sv, result);
-                }
             }
-            if map.len() > 0 { // did produce something
-                results.insert(*e, map);
+            if signals.len() > 0 {
+                series.insert(*e, signals);
             }
         }
-        results
+        observation::correlation::cross_correlation(&series, window)
     }
 
     /// Returns all Pseudo Range observations
@@ -1677,26 +3736,26 @@ impl Rinex {
     ///     }
     /// }
     /// ```
-    pub fn pseudo_range_to_distance (&self, sv_clk_offsets: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>>) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(String, f64)>>> {
+    pub fn pseudo_range_to_distance (&self, sv_clk_offsets: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>>) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
         if !self.is_observation_rinex() {
             return BTreeMap::new()
         }
-        let mut results :BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(String, f64)>>> = BTreeMap::new();
+        let mut results :BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> = BTreeMap::new();
         let record = self.record
             .as_obs()
             .unwrap();
         for (e, (clk, sv)) in record.iter() {
             if let Some(distant_e) = sv_clk_offsets.get(e) { // got related distant epoch
                 if let Some(clk) = clk { // got local clock offset 
-                    let mut map : BTreeMap<sv::Sv, Vec<(String, f64)>> = BTreeMap::new();
+                    let mut map : BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>> = BTreeMap::new();
                     for (sv, obs) in sv.iter() {
                         if let Some(sv_offset) = distant_e.get(sv) { // got related distant offset
-                            let mut v : Vec<(String, f64)> = Vec::new();
+                            let mut v : Vec<(observation::Observable, f64)> = Vec::new();
                             for (code, data) in obs.iter() {
-                                if is_pseudo_range_obs_code!(code) {
+                                if code.is_pseudo_range() {
                                     // We currently do not support the compensation for biases
                                     // than clock induced ones. ie., Ionospheric delays ??
-                                    v.push((code.clone(), data.pr_real_distance(*clk, *sv_offset, 0.0)));
+                                    v.push((*code, data.pr_real_distance(*clk, *sv_offset, 0.0)));
                                 }
                             }
                             if v.len() > 0 { // did come with at least 1 PR
@@ -1713,23 +3772,80 @@ impl Rinex {
         results
     }
 
+    /// Applies broadcast group delays (TGD, BGD, TGD1/TGD2) to single frequency
+    /// Pseudo Range observations of this record, using the most appropriate
+    /// Ephemeris of `nav` at every epoch (see [Rinex::ephemeris_at]),
+    /// refer to [navigation::Ephemeris::group_delay] for supported signals.
+    /// Corrected pseudo ranges are expressed in meters, on an epoch basis and
+    /// per space vehicule. Observations for which no group delay is known are dropped.
+    /// This does not produce anything if self is not an Observation RINEX,
+    /// or `nav` is not a Navigation RINEX.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let nav = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+    ///     .unwrap();
+    /// let obs = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let corrected = obs.group_delay_corrected_pseudo_ranges(&nav);
+    /// for (e, vehicules) in corrected.iter() {
+    ///     for (sv, observations) in vehicules.iter() {
+    ///         for (observable, pr) in observations.iter() {
+    ///             // [m]
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn group_delay_corrected_pseudo_ranges (&self, nav: &Self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> {
+        let mut results: BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>>> = BTreeMap::new();
+        let record = match nav.record.as_nav() {
+            Some(record) => record,
+            None => return results,
+        };
+        for (e, vehicules) in self.pseudo_ranges().iter() {
+            let mut map: BTreeMap<sv::Sv, Vec<(observation::Observable, f64)>> = BTreeMap::new();
+            for (sv, observations) in vehicules.iter() {
+                let eph = match navigation::selection::ephemeris_at(record, sv, e)
+                    .and_then(|(_, frame)| frame.as_eph())
+                {
+                    Some((_, _, _, _, _, eph)) => eph,
+                    None => continue,
+                };
+                let corrected: Vec<(observation::Observable, f64)> = observations
+                    .iter()
+                    .filter_map(|(observable, pr)| {
+                        let channel = channel::Channel::from_observable(sv.constellation, observable).ok()?;
+                        Some((*observable, eph.group_delay_corrected_pseudo_range(*pr, &channel)?))
+                    })
+                    .collect();
+                if corrected.len() > 0 {
+                    map.insert(*sv, corrected);
+                }
+            }
+            if map.len() > 0 {
+                results.insert(*e, map);
+            }
+        }
+        results
+    }
+
     /// Decimates record to fit minimum required epoch interval.
     /// All epochs that do not match the requirement
     /// |e(k).date - e(k-1).date| < interval, get thrown away.
     /// Also note we adjust the INTERVAL field,
     /// meaning, further file production will be correct.
     pub fn decimate_by_interval_mut (&mut self, interval: std::time::Duration) {
+        self.log_operation(&format!("decimated to {}s", interval.as_secs_f64()));
         let min_requirement = chrono::Duration::from_std(interval)
-            .unwrap()
-            .num_seconds();
-        let mut last_preserved = self.epochs()[0].date;
+            .unwrap();
+        let mut last_preserved = self.first_epoch().unwrap().date;
         match self.header.rinex_type {
             types::Type::NavigationData => {
                 let record = self.record
                     .as_mut_nav()
                     .unwrap();
                 record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
+                    let delta = e.date - last_preserved;
                     if e.date != last_preserved { // trick to avoid 1st entry..
                         if delta >= min_requirement {
                             last_preserved = e.date;
@@ -1748,7 +3864,7 @@ impl Rinex {
                     .as_mut_obs()
                     .unwrap();
                 record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
+                    let delta = e.date - last_preserved;
                     if e.date != last_preserved { // trick to avoid 1st entry..
                         if delta >= min_requirement {
                             last_preserved = e.date;
@@ -1767,7 +3883,7 @@ impl Rinex {
                     .as_mut_meteo()
                     .unwrap();
                 record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
+                    let delta = e.date - last_preserved;
                     if e.date != last_preserved { // trick to avoid 1st entry..
                         if delta >= min_requirement {
                             last_preserved = e.date;
@@ -1786,7 +3902,7 @@ impl Rinex {
                     .as_mut_ionex()
                     .unwrap();
                 record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
+                    let delta = e.date - last_preserved;
                     if e.date != last_preserved { // trick to avoid 1st entry..
                         if delta >= min_requirement {
                             last_preserved = e.date;
@@ -1806,103 +3922,135 @@ impl Rinex {
 
     /// Refer to [decimate_by_interval], non mutable implementation
     pub fn decimate_by_interval (&self, interval: std::time::Duration) -> Self {
-        let min_requirement = chrono::Duration::from_std(interval)
-            .unwrap()
-            .num_seconds();
-        let mut last_preserved = self.epochs()[0].date;
-        let record: record::Record = match self.header.rinex_type {
-            types::Type::NavigationData => {
-                let mut record = self.record
-                    .as_nav()
-                    .unwrap()
-                    .clone();
-                record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
-                    if e.date != last_preserved { // trick to avoid 1st entry..
-                        if delta >= min_requirement {
-                            last_preserved = e.date;
-                            true
-                        } else {
-                            false
-                        }
-                    } else {
-                        last_preserved = e.date;
-                        true
-                    }
-                });
-                record::Record::NavRecord(record)
-            },
-            types::Type::ObservationData => {
-                let mut record = self.record
-                    .as_obs()
-                    .unwrap()
-                    .clone();
-                record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
-                    if e.date != last_preserved { // trick to avoid 1st entry..
-                        if delta >= min_requirement {
-                            last_preserved = e.date;
-                            true
-                        } else {
-                            false
-                        }
-                    } else {
-                        last_preserved = e.date;
-                        true
-                    }
-                });
-                record::Record::ObsRecord(record)
-            },
-            types::Type::MeteoData => {
-                let mut record = self.record
-                    .as_meteo()
-                    .unwrap()
-                    .clone();
-                record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
-                    if e.date != last_preserved { // trick to avoid 1st entry..
-                        if delta >= min_requirement {
-                            last_preserved = e.date;
-                            true
-                        } else {
-                            false
+        let mut s = self.clone();
+        s.decimate_by_interval_mut(interval);
+        s
+    }
+
+    /// Resamples Observation record onto a regular grid of given interval,
+    /// aligned on midnight, so files from different receivers can be compared
+    /// epoch to epoch. Epochs that are not synchronized with the grid are dropped.
+    /// When `interpolate` is true, missing grid epochs are linearly interpolated
+    /// from surrounding Ok epochs, no further than `interval` away:
+    /// only phase and pseudo range observations, and receiver clock offsets,
+    /// are interpolated, phase observations are not interpolated across a lock loss.
+    /// The INTERVAL field is adjusted.
+    /// This has no effect on other types of record.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// rinex.resample_mut(std::time::Duration::from_secs(60), false);
+    /// assert_eq!(rinex.header.sampling_interval, Some(60.0));
+    /// use chrono::Timelike;
+    /// for epoch in rinex.epochs() {
+    ///     assert_eq!(epoch.date.second(), 0);
+    /// }
+    /// ```
+    pub fn resample_mut (&mut self, interval: std::time::Duration, interpolate: bool) {
+        if !self.is_observation_rinex() {
+            return
+        }
+        let step = match chrono::Duration::from_std(interval) {
+            Ok(step) if step > chrono::Duration::zero() => step,
+            _ => return,
+        };
+        let step_ns = match step.num_nanoseconds() {
+            Some(ns) => ns,
+            None => return,
+        };
+        self.log_operation(&format!("resampled to {}s", interval.as_secs_f64()));
+        self.header.sampling_interval = Some(interval.as_secs_f32());
+        let record = self.record
+            .as_mut_obs()
+            .unwrap();
+        let midnight = |date: &chrono::NaiveDateTime| date.date().and_hms(0, 0, 0);
+        let source = if interpolate {
+            Some(record.clone())
+        } else {
+            None
+        };
+        record.retain(|e, _| {
+            (e.date - midnight(&e.date))
+                .num_nanoseconds()
+                .map(|ns| ns % step_ns == 0)
+                .unwrap_or(false)
+        });
+        let source = match source {
+            Some(source) => source,
+            None => return,
+        };
+        let (first, last) = match (source.keys().next(), source.keys().next_back()) {
+            (Some(first), Some(last)) => (first.date, last.date),
+            _ => return,
+        };
+        // first grid point, at or after first epoch
+        let offset = (first - midnight(&first)).num_nanoseconds().unwrap_or(0);
+        let mut t = midnight(&first) + chrono::Duration::nanoseconds((offset + step_ns - 1) / step_ns * step_ns);
+        while t <= last {
+            let e = epoch::Epoch::new(t, epoch::EpochFlag::Ok);
+            let synchronized = record.range(e..)
+                .next()
+                .map(|(e, _)| e.date == t)
+                .unwrap_or(false);
+            if !synchronized {
+                let before = source.range(..e)
+                    .rev()
+                    .find(|(e, _)| e.flag.is_ok());
+                let after = source.range(e..)
+                    .find(|(e, _)| e.flag.is_ok());
+                if let (Some((e0, (clk0, vehicules0))), Some((e1, (clk1, vehicules1)))) = (before, after) {
+                    if t - e0.date <= step && e1.date - t <= step {
+                        let dt = (e1.date - e0.date).num_nanoseconds().unwrap_or(0) as f64;
+                        let x = (t - e0.date).num_nanoseconds().unwrap_or(0) as f64 / dt;
+                        let lerp = |a: f64, b: f64| a + (b - a) * x;
+                        let clk = match (clk0, clk1) {
+                            (Some(a), Some(b)) => Some(lerp(*a, *b)),
+                            _ => None,
+                        };
+                        let mut vehicules = BTreeMap::new();
+                        for (sv, observations0) in vehicules0 {
+                            let observations1 = match vehicules1.get(sv) {
+                                Some(observations) => observations,
+                                None => continue,
+                            };
+                            let mut observations = HashMap::new();
+                            for (observable, data0) in observations0 {
+                                if !observable.is_phase() && !observable.is_pseudo_range() {
+                                    continue
+                                }
+                                if let Some(data1) = observations1.get(observable) {
+                                    let lock_loss = data1.lli
+                                        .map(|lli| lli.intersects(observation::LliFlags::LOCK_LOSS))
+                                        .unwrap_or(false);
+                                    if observable.is_phase() && lock_loss {
+                                        continue
+                                    }
+                                    observations.insert(*observable,
+                                        observation::ObservationData::new(lerp(data0.obs, data1.obs), None, None));
+                                }
+                            }
+                            if !observations.is_empty() {
+                                vehicules.insert(*sv, observations);
+                            }
                         }
-                    } else {
-                        last_preserved = e.date;
-                        true
-                    }
-                });
-                record::Record::MeteoRecord(record)
-            },
-            types::Type::IonosphereMaps => {
-                let mut record = self.record
-                    .as_ionex()
-                    .unwrap()
-                    .clone();
-                record.retain(|e, _| {
-                    let delta = (e.date - last_preserved).num_seconds();
-                    if e.date != last_preserved { // trick to avoid 1st entry..
-                        if delta >= min_requirement {
-                            last_preserved = e.date;
-                            true
-                        } else {
-                            false
+                        if !vehicules.is_empty() {
+                            record.insert(e, (clk, vehicules));
                         }
-                    } else {
-                        last_preserved = e.date;
-                        true
                     }
-                });
-                record::Record::IonexRecord(record)
-            },
-            _ => todo!("implement other record types"),
-        };
-        Self {
-            header: self.header.clone(),
-            comments: self.comments.clone(),
-            record,
+                }
+            }
+            t += step;
         }
     }
+
+    /// See [Rinex::resample_mut]
+    pub fn resample (&self, interval: std::time::Duration, interpolate: bool) -> Self {
+        let mut s = self.clone();
+        s.resample_mut(interval, interpolate);
+        s
+    }
     
     /// Decimates (reduce record quantity) by given ratio.
     /// For example, ratio = 2, we keep one out of two entry,
@@ -1910,6 +4058,7 @@ impl Rinex {
     /// This works on any time of record, since we do not care,
     /// about the internal information, just the number of entries in the record. 
     pub fn decimate_by_ratio_mut (&mut self, ratio: u32) {
+        self.log_operation(&format!("decimated by ratio {}", ratio));
         let mut counter = 0;
         match self.header.rinex_type {
             types::Type::NavigationData => {
@@ -1977,84 +4126,100 @@ impl Rinex {
 
     /// See [decimate_by_ratio_mut]
     pub fn decimate_by_ratio (&self, ratio: u32) -> Self {
-        let mut counter = 0;
-        let record :record::Record = match self.header.rinex_type {
-            types::Type::NavigationData => {
-                let mut record = self.record
-                    .as_nav()
-                    .unwrap()
-                    .clone();
-                record.retain(|_, _| {
-                    let retain = (counter % ratio) == 0;
-                    counter += 1;
-                    retain
-                });
-                record::Record::NavRecord(record)
-            },
-            types::Type::ObservationData => {
-                let mut record = self.record
-                    .as_obs()
-                    .unwrap()
-                    .clone();
-                record.retain(|_, _| {
-                    let retain = (counter % ratio) == 0;
-                    counter += 1;
-                    retain
-                });
-                record::Record::ObsRecord(record)
-            },
-            types::Type::MeteoData => {
-                let mut record = self.record
-                    .as_meteo()
-                    .unwrap()
-                    .clone();
-                record.retain(|_, _| {
-                    let retain = (counter % ratio) == 0;
-                    counter += 1;
-                    retain
-                });
-                record::Record::MeteoRecord(record)
-            },
-            types::Type::IonosphereMaps => {
-                let mut record = self.record
-                    .as_ionex()
-                    .unwrap()
-                    .clone();
-                record.retain(|_, _| {
-                    let retain = (counter % ratio) == 0;
-                    counter += 1;
-                    retain
-                });
-                record::Record::IonexRecord(record)
-            },
-            types::Type::AntennaData => {
-                let mut record = self.record
-                    .as_antex()
-                    .unwrap()
-                    .clone();
-                record.retain(|_| {
-                    let retain = (counter % ratio) == 0;
-                    counter += 1;
-                    retain
-                });
-                record::Record::AntexRecord(record)
-            },
-            _ => todo!("implement other record types"),
-        };
-        Self {
-            header: self.header.clone(),
-            comments: self.comments.clone(),
-            record,
-        }
+        let mut s = self.clone();
+        s.decimate_by_ratio_mut(ratio);
+        s
     }
 
     /// Writes self into given file.   
     /// Both header + record will strictly follow RINEX standards.   
-    /// Record: refer to supported RINEX types
+    /// Record: refer to supported RINEX types.   
+    /// File is gzip compressed if `path` terminates with .gz,
+    /// this requires the `with-gzip` feature.
+    /// CRINEX production fails if self holds `raw` entries,
+    /// see [observation::record::check_production]
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn to_file (&self, path: &str) -> std::io::Result<()> {
-        let mut writer = std::fs::File::create(path)?;
-        write!(writer, "{}", self.header.to_string())?;
-        self.record.to_file(&self.header, writer)
+        self.to_file_with_statistics(path)?;
+        Ok(())
+    }
+
+    /// Same as [Rinex::to_file], also returns the [hatanaka::Statistics]
+    /// gathered while compressing the record, when self is a CRINEX
+    /// (see [Rinex::rnx2crx]). Statistics are `None` otherwise.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_file_with_statistics (&self, path: &str) -> std::io::Result<Option<hatanaka::Statistics>> {
+        let mut writer = BufferedWriter::new(path)?;
+        let statistics = self.to_writer_with_statistics(&mut writer)?;
+        writer.finish()?;
+        Ok(statistics)
+    }
+
+    /// Streams self into given writer, epoch by epoch:
+    /// memory usage does not grow with the record size.
+    /// Writer should be buffered, like [std::io::BufWriter].
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let rinex = Rinex::from_file("../test_resources/MET/V2/abvi0010.15m")
+    ///     .unwrap();
+    /// let mut content: Vec<u8> = Vec::new();
+    /// rinex.to_writer(&mut content)
+    ///     .unwrap();
+    /// let content = String::from_utf8(content).unwrap();
+    /// assert_eq!(content.lines().next().unwrap().contains("RINEX VERSION / TYPE"), true);
+    /// ```
+    pub fn to_writer<W: Write> (&self, writer: &mut W) -> std::io::Result<()> {
+        self.to_writer_with_statistics(writer)?;
+        Ok(())
+    }
+
+    /// Same as [Rinex::to_writer], also returns the [hatanaka::Statistics]
+    /// gathered while compressing the record, when self is a CRINEX.
+    ///
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+    ///     .unwrap();
+    /// let mut content: Vec<u8> = Vec::new();
+    /// let stats = rinex.to_writer_with_statistics(&mut content)
+    ///     .unwrap();
+    /// assert_eq!(stats, None);
+    /// rinex.rnx2crx();
+    /// let mut content: Vec<u8> = Vec::new();
+    /// let stats = rinex.to_writer_with_statistics(&mut content)
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(stats.epochs.is_empty(), false);
+    /// assert!(stats.ratio() < 1.0);
+    /// ```
+    pub fn to_writer_with_statistics<W: Write> (&self, writer: &mut W) -> std::io::Result<Option<hatanaka::Statistics>> {
+        if let Some(clocks) = &self.header.clocks {
+            clocks.check_production()?
+        }
+        if self.is_observation_rinex() {
+            observation::record::check_production(&self.header, &self.raw)?
+        }
+        // # OF SATELLITES, PRN / # OF OBS: when described,
+        // regenerate them so they match the record being produced
+        let described = self.header.obs
+            .as_ref()
+            .map(|obs| obs.nb_satellites.is_some() || !obs.nb_observations.is_empty())
+            .unwrap_or(false);
+        match self.record.as_obs() {
+            Some(record) if described => {
+                let mut header = self.header.clone();
+                header.rework_sat_counts(record);
+                write!(writer, "{}", header)?;
+                self.record.to_writer_with_statistics(&header, &self.comments, &self.events, &self.raw, writer)
+            },
+            _ => {
+                write!(writer, "{}", self.header)?;
+                self.record.to_writer_with_statistics(&self.header, &self.comments, &self.events, &self.raw, writer)
+            },
+        }
     }
 }
 
@@ -0,0 +1,151 @@
+//! Unix `compress` (.Z) LZW decompression,
+//! compatible with `ncompress` / `gzip -d`
+use thiserror::Error;
+
+/// .Z files magic bytes
+const MAGIC: [u8; 2] = [0x1f, 0x9d];
+/// Clear code, in block mode
+const CLEAR: usize = 256;
+/// Initial code width
+const INIT_BITS: usize = 9;
+
+#[derive(Error, Debug)]
+/// LZW decompression related errors
+pub enum Error {
+    #[error("not a .Z compressed file")]
+    NotCompressed,
+    #[error("non supported maximal code width {0}")]
+    InvalidMaxBits(usize),
+    #[error("corrupt input")]
+    CorruptInput,
+}
+
+/// Decompresses given .Z (LZW) compressed content
+pub fn decompress (input: &[u8]) -> Result<Vec<u8>, Error> {
+    if input.len() < 3 || input[0..2] != MAGIC {
+        return Err(Error::NotCompressed)
+    }
+    let max_bits = (input[2] & 0x1f) as usize;
+    let block_mode = (input[2] & 0x80) > 0;
+    if max_bits < INIT_BITS || max_bits > 16 {
+        return Err(Error::InvalidMaxBits(max_bits))
+    }
+    let data = &input[3..];
+    let total_bits = data.len() * 8;
+    let max_max_code = 1 << max_bits;
+
+    let mut prefix : Vec<usize> = vec![0; max_max_code];
+    let mut suffix : Vec<u8> = vec![0; max_max_code];
+    for i in 0..256 {
+        suffix[i] = i as u8
+    }
+    let mut stack : Vec<u8> = Vec::with_capacity(max_max_code);
+    let mut output : Vec<u8> = Vec::with_capacity(data.len() * 3);
+
+    let mut n_bits = INIT_BITS;
+    let mut max_code = (1 << n_bits) - 1;
+    let mut free_ent = if block_mode { CLEAR + 1 } else { CLEAR };
+    let mut old_code : Option<usize> = None;
+    let mut fin_char : u8 = 0;
+    // codes are packed in groups of n_bits bytes:
+    // group boundaries are relative to the last code width change
+    let mut base = 0;
+    let mut pos = 0;
+    while pos + n_bits <= total_bits {
+        if free_ent > max_code {
+            pos = align(base, pos, n_bits);
+            base = pos;
+            n_bits += 1;
+            max_code = if n_bits == max_bits {
+                max_max_code
+            } else {
+                (1 << n_bits) - 1
+            };
+            continue
+        }
+        let code = read_code(data, pos, n_bits);
+        pos += n_bits;
+        let prev = match old_code {
+            Some(prev) => prev,
+            None => {
+                // very first code
+                if code >= 256 {
+                    return Err(Error::CorruptInput)
+                }
+                fin_char = code as u8;
+                old_code = Some(code);
+                output.push(fin_char);
+                continue
+            },
+        };
+        if code == CLEAR && block_mode {
+            // table reset
+            free_ent = CLEAR;
+            pos = align(base, pos, n_bits);
+            base = pos;
+            n_bits = INIT_BITS;
+            max_code = (1 << n_bits) - 1;
+            continue
+        }
+        let in_code = code;
+        let mut code = code;
+        stack.clear();
+        if code >= free_ent { // special KwKwK case
+            if code > free_ent {
+                return Err(Error::CorruptInput)
+            }
+            stack.push(fin_char);
+            code = prev
+        }
+        while code >= 256 {
+            stack.push(suffix[code]);
+            code = prefix[code]
+        }
+        fin_char = suffix[code];
+        stack.push(fin_char);
+        output.extend(stack.iter().rev());
+        if free_ent < max_max_code { // new entry
+            prefix[free_ent] = prev;
+            suffix[free_ent] = fin_char;
+            free_ent += 1
+        }
+        old_code = Some(in_code)
+    }
+    Ok(output)
+}
+
+/// Moves given bit position to the next group boundary
+fn align (base: usize, pos: usize, n_bits: usize) -> usize {
+    let group = n_bits * 8;
+    base + (pos - base + group - 1) / group * group
+}
+
+/// Reads one code of given width, at given bit position
+fn read_code (data: &[u8], pos: usize, n_bits: usize) -> usize {
+    let offset = pos / 8;
+    let mut word : usize = 0;
+    for i in 0..3 {
+        if let Some(byte) = data.get(offset + i) {
+            word |= (*byte as usize) << (8 * i)
+        }
+    }
+    (word >> (pos % 8)) & ((1 << n_bits) - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_lzw_decompression() {
+        let resources = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/NAV/V2/";
+        let compressed = std::fs::read(resources.to_owned() + "amel0010.21g.Z")
+            .unwrap();
+        let expected = std::fs::read(resources.to_owned() + "amel0010.21g")
+            .unwrap();
+        let decompressed = decompress(&compressed);
+        assert_eq!(decompressed.is_ok(), true);
+        assert_eq!(decompressed.unwrap(), expected);
+        assert_eq!(decompress(&expected).is_err(), true);
+    }
+}
@@ -1,5 +1,6 @@
 //! `merging` operations related definitions 
 use thiserror::Error;
+use crate::epoch;
 
 #[derive(Error, Debug)]
 /// `RINEX` merge ops related errors
@@ -8,6 +9,211 @@ pub enum MergeError {
     /// merged different kinds of RINEX toghether
     #[error("file types mismatch: cannot merge different `rinex`")]
     FileTypeMismatch,
+    /// IONEX maps can only be merged if described on the same grid
+    #[error("ionex grids mismatch: cannot merge maps described on different grids")]
+    IonexGridMismatch,
+    /// ANTEX calibrations can only be merged if of the same PCV type
+    #[error("antex pcv mismatch: cannot merge absolute and relative calibrations")]
+    AntexPcvMismatch,
+    /// Epoch described by both files, when splicing
+    /// with [DuplicatePolicy::Error]
+    #[error("duplicate epoch {0}")]
+    DuplicateEpoch(epoch::Epoch),
+    /// Splice is only supported on Observation and Meteo records
+    #[error("splice is not supported for this type of rinex")]
+    SpliceNotSupported,
+    /// Header field differs, when merging with [FieldPolicy::Fail]
+    #[error("{0} mismatch: cannot merge different descriptions")]
+    FieldMismatch(&'static str),
+}
+
+/// Describes how a header field described by both files is merged
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldPolicy {
+    /// Description of self is preferred,
+    /// the other description only completes it
+    KeepSelf,
+    /// Other description is preferred,
+    /// description of self only completes it
+    KeepOther,
+    /// Only what both files describe identically is retained
+    Intersect,
+    /// Both descriptions must be identical,
+    /// when described by both files
+    Fail,
+}
+
+impl Default for FieldPolicy {
+    fn default() -> Self {
+        Self::KeepSelf
+    }
+}
+
+/// Header merge policy, per field.
+/// Default policy prefers the description of self
+/// and is the one used by [crate::header::Header::merge_mut]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergePolicy {
+    /// Receiver (REC # / TYPE / VERS)
+    pub receiver: FieldPolicy,
+    /// Antenna (ANT # / TYPE and related fields)
+    pub antenna: FieldPolicy,
+    /// Station position (APPROX POSITION XYZ)
+    pub position: FieldPolicy,
+    /// Observables (OBS: SYS / # / OBS TYPES), compared per constellation.
+    /// Record content is not modified: observations
+    /// whose observable is no longer described are not produced
+    pub observables: FieldPolicy,
+}
+
+impl MergePolicy {
+    /// Builds a policy that applies the same
+    /// strategy to all fields
+    pub fn all (policy: FieldPolicy) -> Self {
+        Self {
+            receiver: policy,
+            antenna: policy,
+            position: policy,
+            observables: policy,
+        }
+    }
+}
+
+/// Merges optional field `b` into `a`, following given policy.
+/// `same` determines whether both descriptions are identical
+pub(crate) fn merge_field<T: Clone> (a: &mut Option<T>, b: &Option<T>, policy: FieldPolicy, field: &'static str, same: impl Fn(&T, &T) -> bool) -> Result<(), MergeError> {
+    match policy {
+        FieldPolicy::KeepSelf => {
+            if a.is_none() {
+                *a = b.clone()
+            }
+        },
+        FieldPolicy::KeepOther => {
+            if b.is_some() {
+                *a = b.clone()
+            }
+        },
+        FieldPolicy::Intersect => {
+            let identical = match (a.as_ref(), b) {
+                (Some(a), Some(b)) => same(a, b),
+                _ => false,
+            };
+            if !identical {
+                *a = None
+            }
+        },
+        FieldPolicy::Fail => {
+            if let (Some(x), Some(y)) = (a.as_ref(), b) {
+                if !same(x, y) {
+                    return Err(MergeError::FieldMismatch(field))
+                }
+            } else if a.is_none() {
+                *a = b.clone()
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Describes how epochs described by both files
+/// are resolved, when splicing two files that overlap in time
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Epochs of the first file are preferred
+    KeepFirst,
+    /// Epochs of the second file are preferred
+    KeepSecond,
+    /// Duplicate epochs are considered an error
+    Error,
+    /// Observations described by both files are averaged,
+    /// others are combined
+    Average,
+}
+
+impl Default for DuplicatePolicy {
+    fn default() -> Self {
+        Self::KeepFirst
+    }
+}
+
+/// Splice operation report
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpliceReport {
+    /// Epochs that were described by both files
+    pub duplicates: Vec<epoch::Epoch>,
+    /// Data gap at the seam, when files do not overlap:
+    /// last epoch of the earliest file
+    /// and first epoch of the latest file
+    pub gap: Option<(epoch::Epoch, epoch::Epoch)>,
+}
+
+/// Third party tools whose merge markers we can mimic,
+/// so downstream tools that grep for these markers
+/// recognize files produced by this crate.
+/// Markers follow the column layout of the reference tool output.
+/// Their program field names the emulated tool: this crate is identified
+/// by its own marker ([native_marker]), always emitted along them
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MarkerCompat {
+    /// `gfzrnx` marker: program (20), "FILE MERGE" (20)
+    /// and date "YYYYMMDD HHMMSS UTC" (20), as found in
+    /// test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx:
+    /// `gfzrnx-1.13-7761    FILE MERGE          20210102 001052 UTC COMMENT`
+    Gfzrnx,
+    /// `teqc` marker: program (20), run by (20)
+    /// and date "YYYYMMDD HH:MM:SSUTC" (20), as found in
+    /// test_resources/CRNX/V1/eijs0010.21d:
+    /// `teqc  2019Feb25                         20210101 01:06:28UTCCOMMENT`.
+    /// teqc does not describe the operation: the run by field
+    /// identifies this crate, which is how [marker_date] recognizes
+    /// this marker among regular teqc program lines
+    Teqc,
+}
+
+impl MarkerCompat {
+    /// Returns the marker emulating this tool,
+    /// formatted as a header `COMMENT` content
+    pub fn marker (&self, date: chrono::NaiveDateTime) -> String {
+        match self {
+            Self::Gfzrnx => format!("{:<20}{:<20}{:<20}",
+                "gfzrnx",
+                "FILE MERGE",
+                date.format("%Y%m%d %H%M%S UTC").to_string()),
+            Self::Teqc => format!("{:<20}{:<20}{:<20}",
+                "teqc",
+                format!("rustrnx-{}", env!("CARGO_PKG_VERSION")),
+                date.format("%Y%m%d %H:%M:%SUTC").to_string()),
+        }
+    }
+}
+
+/// Returns this crate's own merge marker,
+/// formatted as a header `COMMENT` content:
+/// program (20), "FILE MERGE" (20) and date "YYYYMMDD HHMMSS UTC" (20)
+pub fn native_marker (date: chrono::NaiveDateTime) -> String {
+    format!("{:<20}{:<20}{:<20}",
+        format!("rustrnx-{}", env!("CARGO_PKG_VERSION")),
+        "FILE MERGE",
+        date.format("%Y%m%d %H%M%S UTC").to_string())
+}
+
+/// Parses the date of a merge marker: a `FILE MERGE` marker,
+/// whether it is one of ours or a `gfzrnx` one,
+/// or a `teqc` compatible marker produced by this crate
+pub fn marker_date (comment: &str) -> Option<chrono::NaiveDateTime> {
+    if comment.starts_with("teqc") {
+        let run_by = comment.get(20..40)?;
+        if !run_by.starts_with("rustrnx-") {
+            return None // regular teqc program line
+        }
+        let content = comment.get(40..)?.trim();
+        let content = content.strip_suffix("UTC").unwrap_or(content);
+        return chrono::NaiveDateTime::parse_from_str(content, "%Y%m%d %H:%M:%S").ok()
+    }
+    let offset = comment.find("FILE MERGE")?;
+    let content = comment.split_at(offset + "FILE MERGE".len()).1.trim();
+    let content = content.strip_suffix("UTC").unwrap_or(content).trim();
+    chrono::NaiveDateTime::parse_from_str(content, "%Y%m%d %H%M%S").ok()
 }
 
 #[derive(Clone, Debug)]
@@ -35,3 +241,62 @@ pub struct MergeOpts {
         }
     }
 }*/
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_markers() {
+        let date = chrono::NaiveDate::from_ymd(2022, 10, 24)
+            .and_hms(10, 25, 31);
+        let program = format!("rustrnx-{}", env!("CARGO_PKG_VERSION"));
+        let marker = native_marker(date);
+        assert_eq!(marker.len(), 60);
+        assert_eq!(marker.split_at(20).0.trim_end(), program);
+        assert_eq!(marker.split_at(20).1, "FILE MERGE          20221024 102531 UTC ");
+        assert_eq!(marker_date(&marker), Some(date));
+        // gfzrnx reference: NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx
+        let reference = "gfzrnx-1.13-7761    FILE MERGE          20210102 001052 UTC ";
+        let date = chrono::NaiveDate::from_ymd(2021, 1, 2)
+            .and_hms(0, 10, 52);
+        let marker = MarkerCompat::Gfzrnx.marker(date);
+        assert_eq!(marker.len(), reference.len());
+        assert_eq!(marker.split_at(6).0, reference.split_at(6).0);
+        assert_eq!(marker.split_at(20).1, reference.split_at(20).1);
+        assert_eq!(marker_date(&marker), Some(date));
+        assert_eq!(marker_date(reference), Some(date));
+        // teqc reference: CRNX/V1/eijs0010.21d
+        let reference = "teqc  2019Feb25                         20210101 01:06:28UTC";
+        let date = chrono::NaiveDate::from_ymd(2021, 1, 1)
+            .and_hms(1, 6, 28);
+        let marker = MarkerCompat::Teqc.marker(date);
+        assert_eq!(marker.len(), reference.len());
+        assert_eq!(marker.split_at(4).0, reference.split_at(4).0);
+        assert_eq!(marker.split_at(20).1.split_at(20).0.trim_end(), program);
+        assert_eq!(marker.split_at(40).1, reference.split_at(40).1);
+        assert_eq!(marker_date(&marker), Some(date));
+        // regular teqc program line
+        assert_eq!(marker_date(reference), None);
+    }
+    #[test]
+    fn test_merge_field() {
+        let same = |a: &u8, b: &u8| a == b;
+        for (policy, a, b, expected) in [
+            (FieldPolicy::KeepSelf, Some(1), Some(2), Some(1)),
+            (FieldPolicy::KeepSelf, None, Some(2), Some(2)),
+            (FieldPolicy::KeepOther, Some(1), Some(2), Some(2)),
+            (FieldPolicy::KeepOther, Some(1), None, Some(1)),
+            (FieldPolicy::Intersect, Some(1), Some(2), None),
+            (FieldPolicy::Intersect, Some(1), Some(1), Some(1)),
+            (FieldPolicy::Intersect, Some(1), None, None),
+            (FieldPolicy::Fail, Some(1), Some(1), Some(1)),
+            (FieldPolicy::Fail, None, Some(2), Some(2)),
+        ] {
+            let mut a = a;
+            merge_field(&mut a, &b, policy, "test", same).unwrap();
+            assert_eq!(a, expected);
+        }
+        let mut a = Some(1);
+        assert!(merge_field(&mut a, &Some(2), FieldPolicy::Fail, "test", same).is_err());
+    }
+}
@@ -2,6 +2,10 @@
 pub mod sensor;
 pub mod record;
 pub mod observable;
+pub mod troposphere;
+
+pub use observable::Observable;
+pub use sensor::Sensor;
 
 /// Meteo specific header fields
 #[derive(Debug, Clone)]
@@ -42,6 +42,17 @@ pub enum Error {
     ParseIntError(#[from] std::num::ParseIntError),
     #[error("failed to float number")]
     ParseFloatError(#[from] std::num::ParseFloatError),
+    #[error("missing data fields")]
+    MissingData,
+    #[error("header does not describe meteo observables")]
+    MissingObservables,
+}
+
+/// Returns given (trimmed) epoch field
+fn field (line: &str, start: usize, end: usize) -> Result<&str, Error> {
+    line.get(start..end)
+        .map(|s| s.trim())
+        .ok_or(Error::MissingData)
 }
 
 /// Builds `Record` entry for `MeteoData`
@@ -50,7 +61,7 @@ pub fn build_record_entry (header: &Header, content: &str)
 {
     let mut lines = content.lines();
     let mut line = lines.next()
-        .unwrap();
+        .ok_or(Error::MissingData)?;
 
 	let mut map : HashMap<Observable, f32> = HashMap::with_capacity(3);
 
@@ -60,21 +71,21 @@ pub fn build_record_entry (header: &Header, content: &str)
 	let (mut y, m, d, h, min, sec, mut offset) : (i32, u32, u32, u32, u32, u32, usize) 
 		= match header.version.major > 2 {
 		true => {
-			(i32::from_str_radix(line[0..5].trim(),10)?, // Y: 4 digit
-			u32::from_str_radix(line[5..8].trim(),10)?, // m
-			u32::from_str_radix(line[8..11].trim(),10)?, // d
-			u32::from_str_radix(line[11..14].trim(),10)?, // h
-			u32::from_str_radix(line[14..17].trim(),10)?, // m
-			u32::from_str_radix(line[17..20].trim(),10)?, // s
+			(i32::from_str_radix(field(line, 0, 5)?,10)?, // Y: 4 digit
+			u32::from_str_radix(field(line, 5, 8)?,10)?, // m
+			u32::from_str_radix(field(line, 8, 11)?,10)?, // d
+			u32::from_str_radix(field(line, 11, 14)?,10)?, // h
+			u32::from_str_radix(field(line, 14, 17)?,10)?, // m
+			u32::from_str_radix(field(line, 17, 20)?,10)?, // s
 			20)
 		},
 		false => {
-			(i32::from_str_radix(line[0..3].trim(),10)?, // Y: 2 digit
-			u32::from_str_radix(line[3..6].trim(),10)?, // m
-			u32::from_str_radix(line[6..9].trim(),10)?,// d
-			u32::from_str_radix(line[9..12].trim(),10)?,// h
-			u32::from_str_radix(line[12..15].trim(),10)?,// m
-			u32::from_str_radix(line[15..18].trim(),10)?,// s
+			(i32::from_str_radix(field(line, 0, 3)?,10)?, // Y: 2 digit
+			u32::from_str_radix(field(line, 3, 6)?,10)?, // m
+			u32::from_str_radix(field(line, 6, 9)?,10)?,// d
+			u32::from_str_radix(field(line, 9, 12)?,10)?,// h
+			u32::from_str_radix(field(line, 12, 15)?,10)?,// m
+			u32::from_str_radix(field(line, 15, 18)?,10)?,// s
 			18)
 		},
 	};
@@ -85,14 +96,15 @@ pub fn build_record_entry (header: &Header, content: &str)
 			y += 2000
 		}
 	}
-	let date = chrono::NaiveDate::from_ymd(y,m,d)
-		.and_hms(h,min,sec);
+	let date = chrono::NaiveDate::from_ymd_opt(y,m,d)
+		.and_then(|date| date.and_hms_opt(h,min,sec))
+		.ok_or(epoch::ParseDateError::InvalidDate)?;
 	let flag = epoch::EpochFlag::default();
 	let epoch = epoch::Epoch::new(date, flag);
 
 	let codes = &header.meteo
         .as_ref()
-        .unwrap()
+        .ok_or(Error::MissingObservables)?
         .codes;
 	let n_codes = codes.len();
 	let nb_lines : usize = num_integer::div_ceil(n_codes, 8).into(); 
@@ -101,7 +113,8 @@ pub fn build_record_entry (header: &Header, content: &str)
 	for i in 0..nb_lines {
 		for _ in 0..8 {
 			let code = &codes[code_index];
-			let obs : Option<f32> = match f32::from_str(&line[offset..offset+7].trim()) {
+			let end = (offset + 7).min(line.len());
+			let obs : Option<f32> = match f32::from_str(line.get(offset..end).unwrap_or("").trim()) {
 				Ok(f) => Some(f),
 				Err(_) => None,
 			};
@@ -131,24 +144,24 @@ pub fn build_record_entry (header: &Header, content: &str)
 	Ok((epoch, map))
 }
 
-/// Pushes meteo record into given file writer
-pub fn to_file (header: &header::Header, record: &Record, mut writer: std::fs::File) -> std::io::Result<()> {
+/// Streams meteo record into given writer, epoch by epoch
+pub fn to_writer (header: &header::Header, record: &Record, writer: &mut impl Write) -> std::io::Result<()> {
     let obscodes = &header.meteo.as_ref().unwrap().codes;
     for (epoch, obs) in record.iter() {
         if header.version.major > 3 {
-            let _ = write!(writer, " {}", epoch.date.format("%Y %_m %_d %_H %_M %_S").to_string());
+            write!(writer, " {}", epoch.date.format("%Y %_m %_d %_H %_M %_S"))?;
         } else {
-            let _ = write!(writer, " {}", epoch.date.format("%y %_m %_d %_H %_M %_S").to_string());
+            write!(writer, " {}", epoch.date.format("%y %_m %_d %_H %_M %_S"))?;
         }
         let mut index = 0;
         for code in obscodes.iter() { 
             if let Some(data) = obs.get(code) {
-                let _ = write!(writer, "{:7.1}", data);
+                write!(writer, "{:7.1}", data)?;
             } else {
-                let _ = write!(writer, "       ");
+                write!(writer, "       ")?;
             }
             if (index+1) %8 == 0 {
-                let _ = write!(writer, "\n");
+                write!(writer, "\n")?;
             }
             index += 1;
         }
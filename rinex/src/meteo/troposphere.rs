@@ -0,0 +1,192 @@
+//! Tropospheric delay modeling, from Meteo observations
+use crate::epoch;
+use super::record::Record;
+use super::observable::Observable;
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+/// Mapping function, projecting zenith delays
+/// onto the line of sight
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum MappingFunction {
+    /// 1 / sin(elevation), diverges at low elevations
+    Cosecant,
+    /// Black & Eisner (1984) mapping function,
+    /// remains valid down to a few degrees of elevation
+    BlackEisner,
+}
+
+impl Default for MappingFunction {
+    fn default() -> Self {
+        Self::BlackEisner
+    }
+}
+
+impl MappingFunction {
+    /// Evaluates mapping function at given elevation [°]
+    pub fn eval (&self, elevation: f64) -> f64 {
+        let sin = elevation.to_radians().sin();
+        match self {
+            Self::Cosecant => 1.0 / sin,
+            Self::BlackEisner => 1.001 / (0.002001 + sin.powi(2)).sqrt(),
+        }
+    }
+}
+
+/// Zenith (hydrostatic, wet) delays [m], Saastamoinen model,
+/// at given station latitude [rad] and altitude [m],
+/// from pressure [hPa], temperature [°C] and relative humidity [%]
+pub fn zenith_delays (latitude: f64, altitude: f64, pressure: f64, temperature: f64, humidity: f64) -> (f64, f64) {
+    let t = temperature + 273.15;
+    let e = 6.108 * humidity / 100.0 * ((17.15 * t - 4684.0) / (t - 38.45)).exp();
+    let zhd = 0.0022768 * pressure / (1.0 - 0.00266 * (2.0 * latitude).cos() - 0.00028 * altitude / 1.0E3);
+    let zwd = 0.002277 * (1255.0 / t + 0.05) * e;
+    (zhd, zwd)
+}
+
+/// Slant delay [m] at given elevation [°], from given
+/// zenith (hydrostatic, wet) delays [m] and mapping function
+pub fn slant_delay (zenith: (f64, f64), elevation: f64, mapping: MappingFunction) -> f64 {
+    (zenith.0 + zenith.1) * mapping.eval(elevation)
+}
+
+/// Slant tropospheric delay [m], Saastamoinen model, at given
+/// station latitude [rad], altitude [m] and vehicule elevation [°],
+/// from pressure [hPa], temperature [°C] and relative humidity [%].
+/// Zenith delays are mapped with 1 / sin(elevation)
+pub fn saastamoinen (latitude: f64, altitude: f64, elevation: f64, pressure: f64, temperature: f64, humidity: f64) -> f64 {
+    let zenith = zenith_delays(latitude, altitude, pressure, temperature, humidity);
+    slant_delay(zenith, elevation, MappingFunction::Cosecant)
+}
+
+/// Returns standard atmosphere (pressure [hPa], temperature [°C],
+/// relative humidity [%]) at given altitude [m]
+pub fn standard_atmosphere (altitude: f64) -> (f64, f64, f64) {
+    (
+        1013.25 * (1.0 - 2.2557E-5 * altitude).powf(5.2568),
+        15.0 - 6.5E-3 * altitude,
+        70.0,
+    )
+}
+
+/// Fills (pressure, temperature, humidity) with given observations
+fn apply_observations (conditions: &mut (f64, f64, f64), observations: &HashMap<Observable, f32>) {
+    if let Some(p) = observations.get(&Observable::Pressure) {
+        conditions.0 = *p as f64
+    }
+    if let Some(t) = observations.get(&Observable::Temperature) {
+        conditions.1 = *t as f64
+    }
+    if let Some(h) = observations.get(&Observable::HumidityRate) {
+        conditions.2 = *h as f64
+    }
+}
+
+/// Returns (pressure [hPa], temperature [°C], relative humidity [%])
+/// from the Meteo observations closest to given epoch,
+/// standard atmosphere at given altitude [m] for missing observations
+pub fn conditions (record: Option<&Record>, e: &epoch::Epoch, altitude: f64) -> (f64, f64, f64) {
+    let mut conditions = standard_atmosphere(altitude);
+    if let Some(record) = record {
+        let before = record.range(..=*e).next_back();
+        let after = record.range(*e..).next();
+        let closest = match (before, after) {
+            (Some(b), Some(a)) => {
+                if (e.date - b.0.date) <= (a.0.date - e.date) {
+                    Some(b)
+                } else {
+                    Some(a)
+                }
+            },
+            (b, a) => b.or(a),
+        };
+        if let Some((_, observations)) = closest {
+            apply_observations(&mut conditions, observations)
+        }
+    }
+    conditions
+}
+
+/// Returns zenith (hydrostatic, wet) delays [m], Saastamoinen model,
+/// for every epoch of given Meteo record that describes
+/// pressure, temperature or humidity, at given station latitude [rad]
+/// and altitude [m]. Missing observations are replaced by standard atmosphere
+pub fn zenith_delays_per_epoch (record: &Record, latitude: f64, altitude: f64) -> BTreeMap<epoch::Epoch, (f64, f64)> {
+    let mut results: BTreeMap<epoch::Epoch, (f64, f64)> = BTreeMap::new();
+    for (e, observations) in record.iter() {
+        let described = observations.contains_key(&Observable::Pressure)
+            || observations.contains_key(&Observable::Temperature)
+            || observations.contains_key(&Observable::HumidityRate);
+        if !described {
+            continue
+        }
+        let mut conditions = standard_atmosphere(altitude);
+        apply_observations(&mut conditions, observations);
+        let (pressure, temperature, humidity) = conditions;
+        results.insert(*e, zenith_delays(latitude, altitude, pressure, temperature, humidity));
+    }
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_saastamoinen() {
+        // dry zenith delay
+        assert!((saastamoinen(0.8, 0.0, 90.0, 1013.25, 15.0, 0.0) - 2.31).abs() < 0.01);
+        // mapped to lower elevations
+        let zenith = saastamoinen(0.8, 0.0, 90.0, 1013.25, 15.0, 70.0);
+        let slant = saastamoinen(0.8, 0.0, 30.0, 1013.25, 15.0, 70.0);
+        assert!((slant - 2.0 * zenith).abs() < 1.0E-9);
+        // standard atmosphere
+        let e = epoch::Epoch::new(
+            epoch::str2date("2022 01 01 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let (p, t, h) = conditions(None, &e, 0.0);
+        assert_eq!((p, t, h), (1013.25, 15.0, 70.0));
+    }
+    #[test]
+    fn test_zenith_delays() {
+        let (zhd, zwd) = zenith_delays(0.3, 0.0, 1018.6, 25.6, 78.9);
+        assert!((zhd - 2.3243).abs() < 1.0E-4);
+        assert!((zwd - 0.2525).abs() < 1.0E-4);
+        // mapping functions
+        assert!((MappingFunction::Cosecant.eval(30.0) - 2.0).abs() < 1.0E-9);
+        assert!((MappingFunction::BlackEisner.eval(30.0) - 1.9940).abs() < 1.0E-4);
+        assert!((MappingFunction::BlackEisner.eval(90.0) - 1.0).abs() < 1.0E-9);
+        assert!((slant_delay((zhd, zwd), 90.0, MappingFunction::BlackEisner) - (zhd + zwd)).abs() < 1.0E-9);
+        // per epoch
+        let mut record = Record::new();
+        let e0 = epoch::Epoch::new(
+            epoch::str2date("2015 01 01 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e1 = epoch::Epoch::new(
+            epoch::str2date("2015 01 01 00 01 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e2 = epoch::Epoch::new(
+            epoch::str2date("2015 01 01 00 02 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let mut observations: HashMap<Observable, f32> = HashMap::new();
+        observations.insert(Observable::Pressure, 1018.6);
+        observations.insert(Observable::Temperature, 25.6);
+        observations.insert(Observable::HumidityRate, 78.9);
+        record.insert(e0, observations);
+        let mut observations: HashMap<Observable, f32> = HashMap::new();
+        observations.insert(Observable::Pressure, 1013.25);
+        record.insert(e1, observations);
+        let mut observations: HashMap<Observable, f32> = HashMap::new();
+        observations.insert(Observable::WindSpeed, 3.1);
+        record.insert(e2, observations);
+        let delays = zenith_delays_per_epoch(&record, 0.3, 0.0);
+        assert_eq!(delays.len(), 2);
+        let (zhd, zwd) = delays[&e0];
+        assert!((zhd - 2.3243).abs() < 1.0E-4);
+        assert!((zwd - 0.2525).abs() < 1.0E-4);
+        // standard temperature and humidity
+        assert_eq!(delays[&e1], zenith_delays(0.3, 0.0, 1013.25, 15.0, 70.0));
+    }
+}
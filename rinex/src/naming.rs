@@ -0,0 +1,529 @@
+//! File naming conventions: short (V2) and long (V3+) file names.
+//! See [crate::Rinex::filename_with_options] to generate a file name,
+//! and [Metadata] to identify a file from its name
+use thiserror::Error;
+use chrono::Datelike;
+use crate::types::Type;
+use crate::constellation::Constellation;
+
+/// Data source, described in long file names
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DataSource {
+    /// Data collected from a receiver
+    Receiver,
+    /// Data collected from a real time stream
+    Stream,
+    /// Unknown source
+    Unknown,
+}
+
+impl Default for DataSource {
+    fn default() -> Self {
+        Self::Receiver
+    }
+}
+
+impl std::str::FromStr for DataSource {
+    type Err = Error;
+    fn from_str (s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "R" => Ok(Self::Receiver),
+            "S" => Ok(Self::Stream),
+            "U" => Ok(Self::Unknown),
+            _ => Err(Error::InvalidField("data source", s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for DataSource {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Receiver => f.write_str("R"),
+            Self::Stream => f.write_str("S"),
+            Self::Unknown => f.write_str("U"),
+        }
+    }
+}
+
+/// Long file name (V3+) attributes, that cannot be deduced from the file content
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    /// ISO 3166-1 alpha-3 country code, like "FRA"
+    pub country: String,
+    /// Monument or marker number, in [0, 9]
+    pub monument: u8,
+    /// Receiver number, in [0, 9]
+    pub receiver: u8,
+    /// Data source
+    pub source: DataSource,
+}
+
+impl Default for Options {
+    /// Unknown country (XXX), first monument and receiver,
+    /// data collected from a receiver
+    fn default() -> Self {
+        Self {
+            country: String::from("XXX"),
+            monument: 0,
+            receiver: 0,
+            source: DataSource::default(),
+        }
+    }
+}
+
+impl Options {
+    /// Returns a copy of self with given country code
+    pub fn with_country (&self, country: &str) -> Self {
+        let mut s = self.clone();
+        s.country = country.to_string();
+        s
+    }
+}
+
+/// Returns the file period field (PPU), like "01D" or "15M",
+/// from the duration covered by the file. "00U" when unknown
+pub fn period_code (duration: Option<chrono::Duration>) -> String {
+    let seconds = match duration {
+        Some(duration) if duration > chrono::Duration::zero() => duration.num_seconds(),
+        _ => return String::from("00U"),
+    };
+    // rounds up to the given unit
+    let ceil = |unit: i64| (seconds + unit - 1) / unit;
+    if seconds < 3600 {
+        format!("{:02}M", ceil(60))
+    } else if seconds < 86400 {
+        format!("{:02}H", ceil(3600))
+    } else if seconds < 365 * 86400 {
+        format!("{:02}D", ceil(86400))
+    } else {
+        format!("{:02}Y", ceil(365 * 86400))
+    }
+}
+
+/// Returns the sampling frequency field (FFU), like "30S" or "50Z",
+/// from the sampling interval in seconds. "00U" when unknown
+pub fn frequency_code (interval: Option<f64>) -> String {
+    let interval = match interval {
+        Some(interval) if interval > 0.0 => interval,
+        _ => return String::from("00U"),
+    };
+    if interval < 1.0 {
+        let hz = (1.0 / interval).round() as u64;
+        if hz >= 100 {
+            format!("{:02}C", hz / 100)
+        } else {
+            format!("{:02}Z", hz)
+        }
+    } else if interval < 60.0 {
+        format!("{:02}S", interval.round() as u64)
+    } else if interval < 3600.0 {
+        format!("{:02}M", (interval / 60.0).round() as u64)
+    } else if interval < 86400.0 {
+        format!("{:02}H", (interval / 3600.0).round() as u64)
+    } else {
+        format!("{:02}D", (interval / 86400.0).round() as u64)
+    }
+}
+
+/// Parses a period or frequency field, like "15M" or "01C",
+/// into a duration. "00U" (unspecified) yields None
+fn parse_duration_code (code: &str) -> Result<Option<chrono::Duration>, Error> {
+    let invalid = || Error::InvalidField("period / frequency", code.to_string());
+    if code.len() != 3 {
+        return Err(invalid())
+    }
+    let (value, unit) = code.split_at(2);
+    let value = value.parse::<i64>()
+        .map_err(|_| invalid())?;
+    match unit {
+        "U" => Ok(None),
+        "C" => Ok(Some(chrono::Duration::nanoseconds(10_000_000 / value.max(1)))),
+        "Z" => Ok(Some(chrono::Duration::nanoseconds(1_000_000_000 / value.max(1)))),
+        "S" => Ok(Some(chrono::Duration::seconds(value))),
+        "M" => Ok(Some(chrono::Duration::minutes(value))),
+        "H" => Ok(Some(chrono::Duration::hours(value))),
+        "D" => Ok(Some(chrono::Duration::days(value))),
+        "Y" => Ok(Some(chrono::Duration::days(365 * value))),
+        _ => Err(invalid()),
+    }
+}
+
+/// File name parsing related errors
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("\"{0}\" does not follow any known naming convention")]
+    UnknownConvention(String),
+    #[error("invalid {0} field \"{1}\"")]
+    InvalidField(&'static str, String),
+}
+
+/// File attributes, described by a file name that follows
+/// the short (V2) or long (V3+) naming convention.
+/// Example:
+/// ```
+/// use rinex::naming::Metadata;
+/// use rinex::types::Type;
+/// use rinex::constellation::Constellation;
+/// let metadata: Metadata = "ACOR00ESP_R_20213550000_01D_30S_MO.crx.gz".parse()
+///     .unwrap();
+/// assert_eq!(metadata.station, "ACOR");
+/// assert_eq!(metadata.country, Some(String::from("ESP")));
+/// assert_eq!(metadata.year, 2021);
+/// assert_eq!(metadata.doy, 355);
+/// assert_eq!(metadata.period, Some(chrono::Duration::days(1)));
+/// assert_eq!(metadata.sampling, Some(chrono::Duration::seconds(30)));
+/// assert_eq!(metadata.constellation, Some(Constellation::Mixed));
+/// assert_eq!(metadata.rinex_type, Type::ObservationData);
+/// assert_eq!(metadata.crinex, true);
+/// assert_eq!(metadata.compression, Some(String::from("gz")));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    /// Site (or agency) 4 character identifier
+    pub station: String,
+    /// Monument or marker number (long names only)
+    pub monument: Option<u8>,
+    /// Receiver number (long names only)
+    pub receiver: Option<u8>,
+    /// ISO 3166-1 alpha-3 country code (long names only)
+    pub country: Option<String>,
+    /// Data source (long names only)
+    pub source: Option<DataSource>,
+    /// Year
+    pub year: i32,
+    /// Day of year
+    pub doy: u32,
+    /// Hour of the first epoch: hourly session for short names.
+    /// None for daily short names
+    pub hour: Option<u32>,
+    /// Minute of the first epoch (long names only)
+    pub minute: Option<u32>,
+    /// File period: 1 day for daily short names,
+    /// 1 hour for hourly short names
+    pub period: Option<chrono::Duration>,
+    /// Sampling interval (long names only, except NAV)
+    pub sampling: Option<chrono::Duration>,
+    /// Constellation, when it can be identified
+    pub constellation: Option<Constellation>,
+    /// File type
+    pub rinex_type: Type,
+    /// True for CRINEX files
+    pub crinex: bool,
+    /// Compression layer extension, like "gz" or "Z"
+    pub compression: Option<String>,
+}
+
+impl Metadata {
+    /// Returns true if this file name follows the long naming convention
+    pub fn is_long_name (&self) -> bool {
+        self.country.is_some()
+    }
+    /// Parses a short (V2) file name: ssssdddf.yyt
+    fn from_short_name (name: &str, extension: &str) -> Result<Self, Error> {
+        let invalid = |field: &'static str, value: &str| Error::InvalidField(field, value.to_string());
+        match extension.to_lowercase().as_str() {
+            "clk" => return Self::from_clock_name(name),
+            "atx" => {
+                return Ok(Self {
+                    station: name.to_string(),
+                    monument: None,
+                    receiver: None,
+                    country: None,
+                    source: None,
+                    year: 0,
+                    doy: 0,
+                    hour: None,
+                    minute: None,
+                    period: None,
+                    sampling: None,
+                    constellation: None,
+                    rinex_type: Type::AntennaData,
+                    crinex: false,
+                    compression: None,
+                })
+            },
+            _ => {},
+        }
+        let station = name.get(0..4)
+            .ok_or_else(|| Error::UnknownConvention(name.to_string()))?;
+        let doy = name.get(4..7)
+            .ok_or_else(|| Error::UnknownConvention(name.to_string()))?;
+        let doy = doy.parse::<u32>()
+            .map_err(|_| invalid("day of year", doy))?;
+        let session = name.get(7..8)
+            .ok_or_else(|| Error::UnknownConvention(name.to_string()))?;
+        let hour = match session {
+            "0" => None,
+            s => {
+                let c = s.chars().next().unwrap_or('0');
+                if !c.is_ascii_lowercase() || c > 'x' {
+                    return Err(invalid("session", s))
+                }
+                Some(c as u32 - 'a' as u32)
+            },
+        };
+        let period = match hour {
+            Some(_) => chrono::Duration::hours(1),
+            None => chrono::Duration::days(1),
+        };
+        let mut metadata = Self {
+            station: station.to_string(),
+            monument: None,
+            receiver: None,
+            country: None,
+            source: None,
+            year: 0,
+            doy,
+            hour,
+            minute: None,
+            period: Some(period),
+            sampling: None,
+            constellation: None,
+            rinex_type: Type::ObservationData,
+            crinex: false,
+            compression: None,
+        };
+        let yy = extension.get(0..2)
+            .ok_or_else(|| Error::UnknownConvention(extension.to_string()))?;
+        let yy = yy.parse::<i32>()
+            .map_err(|_| invalid("year", yy))?;
+        metadata.year = if yy >= 80 { 1900 + yy } else { 2000 + yy };
+        let t = extension.get(2..)
+            .ok_or_else(|| Error::UnknownConvention(extension.to_string()))?;
+        match t.to_lowercase().as_str() {
+            "o" => {},
+            "d" => metadata.crinex = true,
+            "m" => metadata.rinex_type = Type::MeteoData,
+            "i" => metadata.rinex_type = Type::IonosphereMaps,
+            "n" => {
+                metadata.rinex_type = Type::NavigationData;
+                metadata.constellation = Some(Constellation::GPS);
+            },
+            "g" => {
+                metadata.rinex_type = Type::NavigationData;
+                metadata.constellation = Some(Constellation::Glonass);
+            },
+            "l" => {
+                metadata.rinex_type = Type::NavigationData;
+                metadata.constellation = Some(Constellation::Galileo);
+            },
+            "h" => {
+                metadata.rinex_type = Type::NavigationData;
+                metadata.constellation = Constellation::from_1_letter_code("s").ok();
+            },
+            "p" => {
+                metadata.rinex_type = Type::NavigationData;
+                metadata.constellation = Some(Constellation::Mixed);
+            },
+            _ => return Err(invalid("file type", t)),
+        }
+        Ok(metadata)
+    }
+    /// Parses an IGS clock product short file name: cccwwwwd.clk,
+    /// where wwww is the GPS week and d the day of week
+    fn from_clock_name (name: &str) -> Result<Self, Error> {
+        let (ccc, week, day) = match (name.get(0..3), name.get(3..7), name.get(7..8)) {
+            (Some(ccc), Some(week), Some(day)) => (ccc, week, day),
+            _ => return Err(Error::UnknownConvention(name.to_string())),
+        };
+        let week = week.parse::<i64>()
+            .map_err(|_| Error::InvalidField("GPS week", week.to_string()))?;
+        let day = day.parse::<i64>()
+            .map_err(|_| Error::InvalidField("day of week", day.to_string()))?;
+        let date = chrono::NaiveDate::from_ymd(1980, 1, 6)
+            + chrono::Duration::days(7 * week + day);
+        Ok(Self {
+            station: ccc.to_string(),
+            monument: None,
+            receiver: None,
+            country: None,
+            source: None,
+            year: date.year(),
+            doy: date.ordinal(),
+            hour: None,
+            minute: None,
+            period: Some(chrono::Duration::days(1)),
+            sampling: None,
+            constellation: None,
+            rinex_type: Type::ClockData,
+            crinex: false,
+            compression: None,
+        })
+    }
+    /// Parses a long (V3+) file name: SSSSMRCCC_S_YYYYDDDHHMM_PPU[_FFU]_DT.FMT
+    fn from_long_name (name: &str, extension: &str) -> Result<Self, Error> {
+        let invalid = |field: &'static str, value: &str| Error::InvalidField(field, value.to_string());
+        let fields: Vec<&str> = name.split('_').collect();
+        if fields.len() < 4 || fields[0].len() != 9 || fields[2].len() != 11 {
+            return Err(Error::UnknownConvention(name.to_string()))
+        }
+        let site = fields[0];
+        let station = &site[0..4];
+        let monument = site[4..5].parse::<u8>()
+            .map_err(|_| invalid("monument", &site[4..5]))?;
+        let receiver = site[5..6].parse::<u8>()
+            .map_err(|_| invalid("receiver", &site[5..6]))?;
+        let country = &site[6..9];
+        let source = fields[1].parse::<DataSource>()?;
+        let date = fields[2];
+        let year = date[0..4].parse::<i32>()
+            .map_err(|_| invalid("year", &date[0..4]))?;
+        let doy = date[4..7].parse::<u32>()
+            .map_err(|_| invalid("day of year", &date[4..7]))?;
+        let hour = date[7..9].parse::<u32>()
+            .map_err(|_| invalid("hour", &date[7..9]))?;
+        let minute = date[9..11].parse::<u32>()
+            .map_err(|_| invalid("minute", &date[9..11]))?;
+        let period = parse_duration_code(fields[3])?;
+        // _FFU is omitted for NAV data
+        let (sampling, dt) = match fields.len() {
+            4 => (None, ""),
+            5 => (None, fields[4]),
+            _ => (parse_duration_code(fields[4])?, fields[5]),
+        };
+        let mut metadata = Self {
+            station: station.to_string(),
+            monument: Some(monument),
+            receiver: Some(receiver),
+            country: Some(country.to_string()),
+            source: Some(source),
+            year,
+            doy,
+            hour: Some(hour),
+            minute: Some(minute),
+            period,
+            sampling,
+            constellation: None,
+            rinex_type: Type::ObservationData,
+            crinex: false,
+            compression: None,
+        };
+        match extension.to_lowercase().as_str() {
+            "rnx" => {},
+            "crx" => metadata.crinex = true,
+            "clk" => {
+                metadata.rinex_type = Type::ClockData;
+                return Ok(metadata)
+            },
+            _ => return Err(invalid("format", extension)),
+        }
+        if dt.len() != 2 {
+            return Err(invalid("data type", dt))
+        }
+        let (c, t) = dt.split_at(1);
+        metadata.rinex_type = match t {
+            "O" => Type::ObservationData,
+            "N" => Type::NavigationData,
+            "M" => Type::MeteoData,
+            _ => return Err(invalid("data type", dt)),
+        };
+        if metadata.rinex_type != Type::MeteoData || c != "M" {
+            metadata.constellation = Some(Constellation::from_1_letter_code(c)
+                .map_err(|_| invalid("constellation", c))?);
+        }
+        Ok(metadata)
+    }
+}
+
+impl std::str::FromStr for Metadata {
+    type Err = Error;
+    /// Parses file name, possibly preceded by its directory
+    fn from_str (s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim()
+            .rsplit(|c| c == '/' || c == '\\')
+            .next()
+            .unwrap_or("");
+        let mut name = name.to_string();
+        // compression layer
+        let mut compression: Option<String> = None;
+        for layer in [".gz", ".Z"] {
+            if let Some(stripped) = name.strip_suffix(layer) {
+                compression = Some(layer[1..].to_string());
+                name = stripped.to_string();
+                break
+            }
+        }
+        let (stem, extension) = name.rsplit_once('.')
+            .ok_or_else(|| Error::UnknownConvention(s.to_string()))?;
+        let mut metadata = match stem.contains('_') {
+            true => Self::from_long_name(stem, extension)?,
+            false => Self::from_short_name(stem, extension)?,
+        };
+        metadata.compression = compression;
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_period_code() {
+        assert_eq!(period_code(None), "00U");
+        assert_eq!(period_code(Some(chrono::Duration::minutes(15))), "15M");
+        assert_eq!(period_code(Some(chrono::Duration::hours(1))), "01H");
+        assert_eq!(period_code(Some(chrono::Duration::days(1))), "01D");
+        assert_eq!(period_code(Some(chrono::Duration::days(365))), "01Y");
+    }
+    #[test]
+    fn test_frequency_code() {
+        assert_eq!(frequency_code(None), "00U");
+        assert_eq!(frequency_code(Some(0.01)), "01C");
+        assert_eq!(frequency_code(Some(0.02)), "50Z");
+        assert_eq!(frequency_code(Some(1.0)), "01S");
+        assert_eq!(frequency_code(Some(30.0)), "30S");
+        assert_eq!(frequency_code(Some(900.0)), "15M");
+        assert_eq!(frequency_code(Some(86400.0)), "01D");
+    }
+    #[test]
+    fn test_short_names() {
+        let metadata: Metadata = "../test_resources/OBS/V2/aopr0010.17o".parse().unwrap();
+        assert_eq!(metadata.station, "aopr");
+        assert_eq!(metadata.is_long_name(), false);
+        assert_eq!(metadata.year, 2017);
+        assert_eq!(metadata.doy, 1);
+        let metadata: Metadata = "aopr0010.80o".parse().unwrap();
+        assert_eq!(metadata.year, 1980);
+        let metadata: Metadata = "aopr0010.79o".parse().unwrap();
+        assert_eq!(metadata.year, 2079);
+        assert_eq!(metadata.hour, None);
+        assert_eq!(metadata.period, Some(chrono::Duration::days(1)));
+        assert_eq!(metadata.rinex_type, Type::ObservationData);
+        let metadata: Metadata = "delf001b.21d.Z".parse().unwrap();
+        assert_eq!(metadata.hour, Some(1));
+        assert_eq!(metadata.period, Some(chrono::Duration::hours(1)));
+        assert_eq!(metadata.crinex, true);
+        assert_eq!(metadata.compression, Some(String::from("Z")));
+        let metadata: Metadata = "ajac3550.21g".parse().unwrap();
+        assert_eq!(metadata.rinex_type, Type::NavigationData);
+        assert_eq!(metadata.constellation, Some(Constellation::Glonass));
+        assert_eq!(metadata.year, 2021);
+        let metadata: Metadata = "igs21380.clk".parse().unwrap();
+        assert_eq!(metadata.rinex_type, Type::ClockData);
+        assert_eq!((metadata.year, metadata.doy), (2020, 362));
+        assert_eq!("ajac3550.21z".parse::<Metadata>().is_err(), true);
+        assert_eq!("ajac".parse::<Metadata>().is_err(), true);
+    }
+    #[test]
+    fn test_long_names() {
+        let metadata: Metadata = "AMEL00NLD_R_20210010000_01D_MN.rnx".parse().unwrap();
+        assert_eq!(metadata.station, "AMEL");
+        assert_eq!(metadata.monument, Some(0));
+        assert_eq!(metadata.source, Some(DataSource::Receiver));
+        assert_eq!(metadata.hour, Some(0));
+        assert_eq!(metadata.sampling, None);
+        assert_eq!(metadata.rinex_type, Type::NavigationData);
+        assert_eq!(metadata.constellation, Some(Constellation::Mixed));
+        let metadata: Metadata = "KMS300DNK_S_20221591000_01H_01C_GO.crx".parse().unwrap();
+        assert_eq!(metadata.source, Some(DataSource::Stream));
+        assert_eq!(metadata.hour, Some(10));
+        assert_eq!(metadata.period, Some(chrono::Duration::hours(1)));
+        assert_eq!(metadata.sampling, Some(chrono::Duration::milliseconds(10)));
+        assert_eq!(metadata.constellation, Some(Constellation::GPS));
+        let metadata: Metadata = "TEST00FRA_U_20221591000_15M_50Z_MM.rnx".parse().unwrap();
+        assert_eq!(metadata.rinex_type, Type::MeteoData);
+        assert_eq!(metadata.constellation, None);
+        assert_eq!(metadata.sampling, Some(chrono::Duration::milliseconds(20)));
+        assert_eq!("KMS300DNK_X_20221591000_01H_01C_GO.crx".parse::<Metadata>().is_err(), true);
+    }
+}
@@ -0,0 +1,145 @@
+//! `Navigation` ALM Almanac messages
+use crate::epoch;
+use thiserror::Error;
+use std::str::FromStr;
+
+/// Message Parsing error
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to parse date field")]
+    ParseDateError(#[from] epoch::ParseDateError),
+    #[error("failed to parse almanac parameter")]
+    ParseFloatError(#[from] std::num::ParseFloatError),
+    #[error("almanac message missing 1st line")]
+    AlmMissing1stLine,
+    #[error("almanac message missing 2nd line")]
+    AlmMissing2ndLine,
+    #[error("almanac message missing 3rd line")]
+    AlmMissing3rdLine,
+    #[error("almanac message missing 4th line")]
+    AlmMissing4thLine,
+}
+
+/// Almanac Message: reduced precision orbit and clock parameters,
+/// valid for longer periods than ephemeris, broadcasted for the entire constellation
+#[derive(Debug, Clone)]
+#[derive(Default)]
+#[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct Message {
+    /// Eccentricity
+    pub e: f64,
+    /// Time of applicability [s] of GNSS week
+    pub toa: u32,
+    /// Inclination offset from reference inclination [rad]
+    pub delta_i: f64,
+    /// Rate of right ascension [rad.s⁻¹]
+    pub omega_dot: f64,
+    /// Square root of semi major axis [m^1/2]
+    pub sqrt_a: f64,
+    /// Longitude of ascending node at weekly epoch [rad]
+    pub omega_0: f64,
+    /// Argument of perigee [rad]
+    pub omega: f64,
+    /// Mean anomaly at reference time [rad]
+    pub m_0: f64,
+    /// Clock bias [s]
+    pub af0: f64,
+    /// Clock drift [s.s⁻¹]
+    pub af1: f64,
+    /// Vehicule health
+    pub health: u32,
+    /// GNSS week of applicability
+    pub week: u32,
+}
+
+/// Parses a 19 digit field, blank fields are null
+fn parse_field (content: &str) -> Result<f64, Error> {
+    let content = content.trim();
+    if content.is_empty() {
+        Ok(0.0_f64)
+    } else {
+        Ok(f64::from_str(&content.replace("D", "E"))?)
+    }
+}
+
+/// Splits given line into 19 digit fields,
+/// after given offset
+fn fields (line: &str, offset: usize) -> Vec<&str> {
+    let mut fields: Vec<&str> = Vec::with_capacity(4);
+    let mut rem = line.get(offset..).unwrap_or("");
+    while !rem.is_empty() {
+        let split = rem.len().min(19);
+        let (field, r) = rem.split_at(split);
+        fields.push(field);
+        rem = r;
+    }
+    fields
+}
+
+impl Message {
+    pub fn parse (mut lines: std::str::Lines<'_>) -> Result<(epoch::Epoch, Self), Error> {
+        let line = match lines.next() {
+            Some(l) => l,
+            _ => return Err(Error::AlmMissing1stLine)
+        };
+        let date = epoch::str2date(line.get(0..23).unwrap_or(line).trim())?;
+        let mut values: Vec<f64> = Vec::with_capacity(12);
+        for field in fields(line, 23) {
+            values.push(parse_field(field)?)
+        }
+        for error in [Error::AlmMissing2ndLine, Error::AlmMissing3rdLine, Error::AlmMissing4thLine] {
+            let line = match lines.next() {
+                Some(l) => l,
+                _ => return Err(error)
+            };
+            for field in fields(line, 4) {
+                values.push(parse_field(field)?)
+            }
+        }
+        values.resize(12, 0.0_f64);
+        Ok((epoch::Epoch {
+            date,
+            flag: epoch::EpochFlag::Ok,
+        },
+        Self {
+            e: values[0],
+            toa: values[1] as u32,
+            delta_i: values[2],
+            omega_dot: values[3],
+            sqrt_a: values[4],
+            omega_0: values[5],
+            omega: values[6],
+            m_0: values[7],
+            af0: values[8],
+            af1: values[9],
+            health: values[10] as u32,
+            week: values[11] as u32,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_almanac() {
+        let content =
+"    2022 06 08 00 00 00 9.853839874268E-03 5.898240000000E+05 8.544921875000E-03
+    -7.863184958883E-09 5.153650390625E+03-1.286897115738E+00 5.386352539063E-01
+    -2.914714813232E+00 1.144409179688E-04 3.637978807092E-12 0.000000000000E+00
+     2.213000000000E+03";
+        let (epoch, msg) = Message::parse(content.lines()).unwrap();
+        assert_eq!(epoch.date, epoch::str2date("2022 06 08 00 00 00").unwrap());
+        assert_eq!(msg.e, 9.853839874268E-03);
+        assert_eq!(msg.toa, 589824);
+        assert_eq!(msg.sqrt_a, 5.153650390625E+03);
+        assert_eq!(msg.omega_0, -1.286897115738E+00);
+        assert_eq!(msg.af1, 3.637978807092E-12);
+        assert_eq!(msg.health, 0);
+        assert_eq!(msg.week, 2213);
+        // missing lines
+        let content = "    2022 06 08 00 00 00 9.853839874268E-03 5.898240000000E+05 8.544921875000E-03";
+        assert_eq!(Message::parse(content.lines()).is_err(), true);
+    }
+}
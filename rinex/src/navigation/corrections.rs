@@ -0,0 +1,274 @@
+//! Ionospheric and time system corrections,
+//! whether they were described in V2/V3 headers or V4 record frames
+use thiserror::Error;
+use std::str::FromStr;
+use crate::epoch;
+use crate::constellation::Constellation;
+use crate::navigation::ionmessage;
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+/// Corrections parsing error
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unknown correction type \"{0}\"")]
+    UnknownCorrectionType(String),
+    #[error("missing data fields")]
+    MissingData,
+    #[error("failed to parse float data")]
+    ParseFloatError(#[from] std::num::ParseFloatError),
+    #[error("failed to parse integer data")]
+    ParseIntError(#[from] std::num::ParseIntError),
+}
+
+/// Ionospheric model parameters
+#[derive(Debug, Clone)]
+#[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct IonosphericCorrection {
+    /// Constellation broadcasting this model
+    pub constellation: Constellation,
+    /// Epoch of transmission, only known for V4 ION frames
+    pub epoch: Option<epoch::Epoch>,
+    /// Model parameters
+    pub model: ionmessage::Message,
+}
+
+impl IonosphericCorrection {
+    /// Formats self as V2 "ION ALPHA" and "ION BETA" header contents.
+    /// Only GPS Klobuchar models are described in V2 headers
+    pub fn to_v2_ion (&self) -> Option<(String, String)> {
+        if self.constellation != Constellation::GPS {
+            return None
+        }
+        let model = self.model.as_klobuchar()?;
+        Some((
+            format!("  {}", format_coefficients(model.alpha)),
+            format!("  {}", format_coefficients(model.beta)),
+        ))
+    }
+}
+
+/// Time system correction: relationship between
+/// a GNSS time system and UTC or another GNSS time system
+#[derive(Debug, Clone)]
+#[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct TimeSystemCorrection {
+    /// Correction type, like "GPUT" (GPS to UTC)
+    /// or "GAGP" (Galileo to GPS)
+    pub system: String,
+    /// UTC identifier, only known for V4 STO frames
+    pub utc: String,
+    /// Epoch of reference, only known for V4 STO frames
+    pub epoch: Option<epoch::Epoch>,
+    /// Reference time, as (week, seconds of week),
+    /// only known for V2/V3 headers
+    pub reference: Option<(u32, u32)>,
+    /// ([sec], [sec.sec⁻¹], [sec.sec⁻²])
+    pub a: (f64,f64,f64),
+}
+
+/// Parses a float value, header fields may use the 'D' exponent notation
+fn parse_float (content: &str) -> Result<f64, Error> {
+    Ok(f64::from_str(&content.trim().replace("D", "E"))?)
+}
+
+/// Parses 4 D12.4 coefficients, starting at given offset
+fn parse_coefficients (content: &str, offset: usize) -> Result<(f64,f64,f64,f64), Error> {
+    let mut values = [0.0_f64; 4];
+    for i in 0..4 {
+        let start = offset + i * 12;
+        let field = content.get(start..start+12)
+            .ok_or(Error::MissingData)?;
+        values[i] = parse_float(field)?
+    }
+    Ok((values[0], values[1], values[2], values[3]))
+}
+
+/// Formats given value as a D12.4 field (normalized mantissa)
+fn format_float (value: f64) -> String {
+    let (mut mantissa, mut exponent) = match value {
+        v if v == 0.0 => (0.0_f64, 0_i32),
+        v => {
+            let exponent = v.abs().log10().floor() as i32 + 1;
+            (v / 10.0_f64.powi(exponent), exponent)
+        },
+    };
+    // rounding may denormalize the mantissa
+    if (mantissa * 1.0E4).round().abs() >= 1.0E4 {
+        mantissa /= 10.0;
+        exponent += 1;
+    }
+    let mantissa = format!("{:.4}", mantissa);
+    format!("{:>12}", format!("{}D{}{:02}",
+        mantissa,
+        if exponent < 0 { '-' } else { '+' },
+        exponent.abs()))
+}
+
+/// Formats 4 coefficients as D12.4 fields
+fn format_coefficients (coefficients: (f64,f64,f64,f64)) -> String {
+    let (c0, c1, c2, c3) = coefficients;
+    [c0, c1, c2, c3]
+        .iter()
+        .map(|c| format_float(*c))
+        .collect()
+}
+
+/// Inserts Klobuchar coefficients into given list of corrections:
+/// alpha and beta coefficients are described on two separate lines
+fn insert_klobuchar (corrections: &mut Vec<IonosphericCorrection>, constellation: Constellation, coefficients: (f64,f64,f64,f64), alpha: bool) {
+    let existing = corrections
+        .iter_mut()
+        .find(|c| c.constellation == constellation && c.epoch.is_none() && c.model.as_klobuchar().is_some());
+    let correction = match existing {
+        Some(c) => c,
+        None => {
+            corrections.push(IonosphericCorrection {
+                constellation,
+                epoch: None,
+                model: ionmessage::Message::KlobucharModel(ionmessage::KbModel::default()),
+            });
+            corrections.last_mut().unwrap()
+        },
+    };
+    if let ionmessage::Message::KlobucharModel(model) = &mut correction.model {
+        if alpha {
+            model.alpha = coefficients
+        } else {
+            model.beta = coefficients
+        }
+    }
+}
+
+/// Parses V2 "ION ALPHA" or "ION BETA" header content (GPS Klobuchar coefficients)
+/// into given list of corrections
+pub fn parse_v2_ion (corrections: &mut Vec<IonosphericCorrection>, content: &str, alpha: bool) -> Result<(), Error> {
+    let coefficients = parse_coefficients(content, 2)?;
+    insert_klobuchar(corrections, Constellation::GPS, coefficients, alpha);
+    Ok(())
+}
+
+/// Parses V3 "IONOSPHERIC CORR" header content into given list of corrections
+pub fn parse_v3_ionospheric_corr (corrections: &mut Vec<IonosphericCorrection>, content: &str) -> Result<(), Error> {
+    let (kind, _) = content.split_at(4);
+    let coefficients = parse_coefficients(content, 5)?;
+    let (constellation, alpha) = match kind.trim() {
+        "GAL" => {
+            corrections.push(IonosphericCorrection {
+                constellation: Constellation::Galileo,
+                epoch: None,
+                model: ionmessage::Message::NequickGModel(ionmessage::NgModel {
+                    a: (coefficients.0, coefficients.1, coefficients.2),
+                    region: ionmessage::NgRegionFlags::empty(),
+                }),
+            });
+            return Ok(())
+        },
+        "GPSA" => (Constellation::GPS, true),
+        "GPSB" => (Constellation::GPS, false),
+        "QZSA" => (Constellation::QZSS, true),
+        "QZSB" => (Constellation::QZSS, false),
+        "BDSA" => (Constellation::BeiDou, true),
+        "BDSB" => (Constellation::BeiDou, false),
+        "IRNA" => (Constellation::IRNSS, true),
+        "IRNB" => (Constellation::IRNSS, false),
+        _ => return Err(Error::UnknownCorrectionType(kind.trim().to_string())),
+    };
+    insert_klobuchar(corrections, constellation, coefficients, alpha);
+    Ok(())
+}
+
+/// Parses V2 "DELTA-UTC: A0,A1,T,W" header content (GPS to UTC)
+pub fn parse_v2_delta_utc (content: &str) -> Result<TimeSystemCorrection, Error> {
+    if content.len() < 59 {
+        return Err(Error::MissingData)
+    }
+    let a0 = parse_float(&content[3..22])?;
+    let a1 = parse_float(&content[22..41])?;
+    let t = u32::from_str_radix(content[41..50].trim(), 10)?;
+    let w = u32::from_str_radix(content[50..59].trim(), 10)?;
+    Ok(TimeSystemCorrection {
+        system: String::from("GPUT"),
+        utc: String::new(),
+        epoch: None,
+        reference: Some((w, t)),
+        a: (a0, a1, 0.0),
+    })
+}
+
+/// Parses V3 "TIME SYSTEM CORR" header content
+pub fn parse_v3_time_system_corr (content: &str) -> Result<TimeSystemCorrection, Error> {
+    if content.len() < 50 {
+        return Err(Error::MissingData)
+    }
+    let a0 = parse_float(&content[5..22])?;
+    let a1 = parse_float(&content[22..38])?;
+    let t = u32::from_str_radix(content[38..45].trim(), 10)?;
+    let w = u32::from_str_radix(content[45..50].trim(), 10)?;
+    Ok(TimeSystemCorrection {
+        system: content[0..4].trim().to_string(),
+        utc: String::new(),
+        epoch: None,
+        reference: Some((w, t)),
+        a: (a0, a1, 0.0),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_ionospheric_corr() {
+        let mut corrections: Vec<IonosphericCorrection> = Vec::new();
+        parse_v3_ionospheric_corr(&mut corrections,
+            "GPSA   7.4506e-09 -1.4901e-08 -5.9605e-08  1.1921e-07       ").unwrap();
+        parse_v3_ionospheric_corr(&mut corrections,
+            "GPSB   9.0112e+04 -6.5536e+04 -1.3107e+05  4.5875e+05       ").unwrap();
+        parse_v3_ionospheric_corr(&mut corrections,
+            "GAL    6.6250e+01 -1.6406e-01 -2.4719e-03  0.0000e+00       ").unwrap();
+        assert_eq!(corrections.len(), 2);
+        let kb = corrections[0].model.as_klobuchar().unwrap();
+        assert_eq!(corrections[0].constellation, Constellation::GPS);
+        assert_eq!(kb.alpha, (7.4506e-09, -1.4901e-08, -5.9605e-08, 1.1921e-07));
+        assert_eq!(kb.beta, (9.0112e+04, -6.5536e+04, -1.3107e+05, 4.5875e+05));
+        let ng = corrections[1].model.as_nequick_g().unwrap();
+        assert_eq!(corrections[1].constellation, Constellation::Galileo);
+        assert_eq!(ng.a, (6.6250e+01, -1.6406e-01, -2.4719e-03));
+        assert!(parse_v3_ionospheric_corr(&mut corrections,
+            "XXXX   6.6250e+01 -1.6406e-01 -2.4719e-03  0.0000e+00       ").is_err());
+        let mut corrections: Vec<IonosphericCorrection> = Vec::new();
+        parse_v2_ion(&mut corrections,
+            "    0.7451D-08 -0.1490D-07 -0.5960D-07  0.1192D-06          ", true).unwrap();
+        assert_eq!(corrections.len(), 1);
+        let kb = corrections[0].model.as_klobuchar().unwrap();
+        assert_eq!(kb.alpha, (0.7451E-08, -0.1490E-07, -0.5960E-07, 0.1192E-06));
+        parse_v2_ion(&mut corrections,
+            "    0.9011D+05 -0.6554D+05 -0.1311D+06  0.4588D+06          ", false).unwrap();
+        assert_eq!(corrections.len(), 1);
+        let (alpha, beta) = corrections[0].to_v2_ion().unwrap();
+        assert_eq!(alpha, "    0.7451D-08 -0.1490D-07 -0.5960D-07  0.1192D-06");
+        assert_eq!(beta, "    0.9011D+05 -0.6554D+05 -0.1311D+06  0.4588D+06");
+        assert_eq!(format_float(0.0), "  0.0000D+00");
+        assert_eq!(format_float(0.99999), "  0.1000D+01");
+    }
+    #[test]
+    fn test_time_system_corr() {
+        let corr = parse_v3_time_system_corr(
+            "GPUT -3.7252902985E-09-1.065814104E-14  61440 2139          ").unwrap();
+        assert_eq!(corr.system, "GPUT");
+        assert_eq!(corr.reference, Some((2139, 61440)));
+        assert_eq!(corr.a, (-3.7252902985E-09, -1.065814104E-14, 0.0));
+        let corr = parse_v3_time_system_corr(
+            "GAUT   .1862645149E-08 -.888178420E-15 432000 2138          ").unwrap();
+        assert_eq!(corr.system, "GAUT");
+        assert_eq!(corr.reference, Some((2138, 432000)));
+        let corr = parse_v2_delta_utc(
+            "   0.931322574615D-09 0.355271367880D-14   233472     1930 ").unwrap();
+        assert_eq!(corr.system, "GPUT");
+        assert_eq!(corr.reference, Some((1930, 233472)));
+        assert_eq!(corr.a, (0.931322574615E-09, 0.355271367880E-14, 0.0));
+    }
+}
@@ -20,16 +20,16 @@ pub enum Error {
 #[derive(Debug, Clone)]
 #[derive(Default)]
 #[derive(PartialEq, PartialOrd)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Message {
     /// ([arc-sec], [arc-sec.day⁻¹], [arc-sec.day⁻²])
-    x: (f64,f64,f64),
+    pub x: (f64,f64,f64),
     /// ([arc-sec], [arc-sec.day⁻¹], [arc-sec.day⁻²])
-    y: (f64,f64,f64),
+    pub y: (f64,f64,f64),
     /// Message transmmission time [s] of GNSS week
-    t_tm: u32,
+    pub t_tm: u32,
     /// Delta UT1 ([sec], [sec.day⁻¹], [-sec.day⁻²])
-    dut1: (f64,f64,f64),
+    pub dut1: (f64,f64,f64),
 }
 
 impl Message {
@@ -88,3 +88,26 @@ impl Message {
         }))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_eop_message() {
+        let content =
+"    2022 06 08 10 00 00 1.234567890000E-01 2.500000000000E-04 0.000000000000E+00
+                        3.456789000000E-01-1.200000000000E-04 0.000000000000E+00
+     2.952840000000E+05-1.234000000000E-01 3.000000000000E-04 0.000000000000E+00";
+        let (epoch, msg) = Message::parse(content.lines())
+            .unwrap();
+        assert_eq!(epoch.date, epoch::str2date("2022 06 08 10 00 00").unwrap());
+        assert_eq!(msg.x, (1.234567890000E-01, 2.500000000000E-04, 0.0));
+        assert_eq!(msg.y, (3.456789000000E-01, -1.200000000000E-04, 0.0));
+        assert_eq!(msg.t_tm, 295284);
+        assert_eq!(msg.dut1, (-1.234000000000E-01, 3.000000000000E-04, 0.0));
+        // missing lines
+        let mut lines = content.lines();
+        let first = lines.next().unwrap();
+        assert_eq!(Message::parse(first.lines()).is_err(), true);
+    }
+}
@@ -0,0 +1,774 @@
+//! Typed Ephemeris content, per constellation.
+//! Ephemeris frames are interpreted once, when parsed: they store
+//! the typed content defined here, see [super::Frame::as_eph], along with
+//! the fields that are not interpreted, see [super::Frame::as_eph_raw].
+//! Field names refer to the RINEX layouts (db/NAV/navigation.json).
+use crate::sv::Sv;
+use crate::epoch::Epoch;
+use crate::constellation::Constellation;
+use crate::channel::{Channel, SPEED_OF_LIGHT_M_S};
+use super::orbits::{self, Kepler};
+use super::record::ComplexEnum;
+use std::collections::HashMap;
+
+/// Keplerian elements fields
+const KEPLER_FIELDS: [&str; 16] = [
+    "sqrta", "e", "i0", "omega0", "omega", "m0", "deltaN", "idot",
+    "omegaDot", "cuc", "cus", "crc", "crs", "cic", "cis", "toe",
+];
+
+/// Returns given field, as a floating point value
+fn get (map: &HashMap<String, ComplexEnum>, key: &str) -> Option<f64> {
+    match map.get(key)? {
+        ComplexEnum::F64(f) => Some(*f),
+        ComplexEnum::F32(f) => Some(*f as f64),
+        ComplexEnum::U8(u) => Some(*u as f64),
+        ComplexEnum::Str(_) => None,
+    }
+}
+
+/// Moves given field out of `map`, if it can be interpreted
+fn take (map: &mut HashMap<String, ComplexEnum>, key: &str) -> Option<f64> {
+    let value = get(map, key)?;
+    map.remove(key);
+    Some(value)
+}
+
+/// Moves first of given (alternate) fields out of `map`
+fn take_any (map: &mut HashMap<String, ComplexEnum>, keys: &[&str]) -> Option<f64> {
+    keys.iter()
+        .find_map(|key| take(map, key))
+}
+
+/// Moves Keplerian elements out of `map`, if they are all described
+fn take_kepler (map: &mut HashMap<String, ComplexEnum>) -> Option<Kepler> {
+    let kepler = Kepler::from_ephemeris(map)?;
+    for key in KEPLER_FIELDS.iter() {
+        map.remove(*key);
+    }
+    Some(kepler)
+}
+
+/// Moves a state vector out of `map`, if its 3 fields are described
+fn take_state_vector (map: &mut HashMap<String, ComplexEnum>, keys: [&str; 3]) -> Option<(f64, f64, f64)> {
+    let vector = (get(map, keys[0])?, get(map, keys[1])?, get(map, keys[2])?);
+    for key in keys.iter() {
+        map.remove(*key);
+    }
+    Some(vector)
+}
+
+/// Returns given Keplerian elements field
+fn kepler_field (kepler: &Kepler, key: &str) -> Option<f64> {
+    match key {
+        "sqrta" => Some(kepler.sqrta),
+        "e" => Some(kepler.e),
+        "i0" => Some(kepler.i0),
+        "omega0" => Some(kepler.omega0),
+        "omega" => Some(kepler.omega),
+        "m0" => Some(kepler.m0),
+        "deltaN" => Some(kepler.delta_n),
+        "idot" => Some(kepler.idot),
+        "omegaDot" => Some(kepler.omega_dot),
+        "cuc" => Some(kepler.cuc),
+        "cus" => Some(kepler.cus),
+        "crc" => Some(kepler.crc),
+        "crs" => Some(kepler.crs),
+        "cic" => Some(kepler.cic),
+        "cis" => Some(kepler.cis),
+        "toe" => Some(kepler.toe),
+        _ => None,
+    }
+}
+
+/// Returns given state vector field
+fn state_vector_field (vector: Option<(f64, f64, f64)>, keys: [&str; 3], key: &str) -> Option<f64> {
+    let (x, y, z) = vector?;
+    match keys.iter().position(|k| *k == key)? {
+        0 => Some(x),
+        1 => Some(y),
+        _ => Some(z),
+    }
+}
+
+/// Galileo data source bit, set when clock correction
+/// and group delay refer to the E5a/E1 (F/NAV) signal pair
+const GAL_DATA_SOURCE_E5A_E1: u32 = 1 << 8;
+
+/// Returns group delay scaling factor, for given channel,
+/// relative to given reference channel: (f_ref / f)²
+fn gamma (reference: Channel, channel: &Channel) -> f64 {
+    (reference.carrier_frequency_mhz() / channel.carrier_frequency_mhz()).powi(2)
+}
+
+/// Position fields of state vector Ephemeris
+const POSITION_FIELDS: [&str; 3] = ["satPosX", "satPosY", "satPosZ"];
+/// Velocity fields of state vector Ephemeris
+const VELOCITY_FIELDS: [&str; 3] = ["velX", "velY", "velZ"];
+/// Acceleration fields of state vector Ephemeris
+const ACCELERATION_FIELDS: [&str; 3] = ["accelX", "accelY", "accelZ"];
+
+/// GPS and QZSS Ephemeris content
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct GpsEphemeris {
+    /// Keplerian elements. Not described by CNAV (V4) frames
+    pub kepler: Option<Kepler>,
+    /// Issue of Data, Ephemeris. Not described by CNAV (V4) frames
+    pub iode: Option<f64>,
+    /// Issue of Data, Clock. Not described by CNAV (V4) frames
+    pub iodc: Option<f64>,
+    /// GPS week number (continuous, not modulo 1024)
+    pub week: Option<f64>,
+    /// Vehicule health, 0 means healthy
+    pub health: Option<f64>,
+    /// Vehicule accuracy [m]
+    pub accuracy: Option<f64>,
+    /// Total group delay [s]
+    pub tgd: Option<f64>,
+    /// Curve fit interval [h]
+    pub fit_interval: Option<f64>,
+    /// Transmission time of message, in seconds of week
+    pub t_tm: Option<f64>,
+}
+
+/// Galileo Ephemeris content
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct GalileoEphemeris {
+    /// Keplerian elements
+    pub kepler: Option<Kepler>,
+    /// Issue of Data of the navigation batch
+    pub iodnav: Option<f64>,
+    /// Galileo week number, aligned on GPS weeks
+    pub week: Option<f64>,
+    /// Vehicule health (signal health and data validity bits), 0 means healthy
+    pub health: Option<f64>,
+    /// Signal In Space Accuracy [m]
+    pub sisa: Option<f64>,
+    /// E5a/E1 broadcast group delay [s]
+    pub bgd_e5a_e1: Option<f64>,
+    /// E5b/E1 broadcast group delay [s]
+    pub bgd_e5b_e1: Option<f64>,
+    /// Data source (I/NAV, F/NAV) bits
+    pub data_source: Option<f64>,
+    /// Transmission time of message, in seconds of week
+    pub t_tm: Option<f64>,
+}
+
+/// BeiDou Ephemeris content
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct BeidouEphemeris {
+    /// Keplerian elements
+    pub kepler: Option<Kepler>,
+    /// Age of Data, Ephemeris
+    pub aode: Option<f64>,
+    /// Age of Data, Clock
+    pub aodc: Option<f64>,
+    /// BDT week number
+    pub week: Option<f64>,
+    /// Autonomous satellite health flag (SatH1), 0 means healthy
+    pub health: Option<f64>,
+    /// Vehicule accuracy [m]
+    pub accuracy: Option<f64>,
+    /// B1/B3 group delay [s]
+    pub tgd1: Option<f64>,
+    /// B2/B3 group delay [s]
+    pub tgd2: Option<f64>,
+    /// Transmission time of message, in seconds of BDT week
+    pub t_tm: Option<f64>,
+}
+
+/// Glonass Ephemeris content: state vector at frame epoch
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct GlonassEphemeris {
+    /// Position (PZ-90) [km]
+    pub position: Option<(f64, f64, f64)>,
+    /// Velocity [km.s⁻¹]
+    pub velocity: Option<(f64, f64, f64)>,
+    /// Luni-solar acceleration [km.s⁻²]
+    pub acceleration: Option<(f64, f64, f64)>,
+    /// Vehicule health, 0 means healthy
+    pub health: Option<f64>,
+    /// Frequency channel number
+    pub channel: Option<i8>,
+    /// Age of operation information [days]
+    pub age: Option<f64>,
+}
+
+/// Converts a state vector expressed in [km], to [m]
+fn km_to_m (vector: (f64, f64, f64)) -> (f64, f64, f64) {
+    (vector.0 * 1.0E3, vector.1 * 1.0E3, vector.2 * 1.0E3)
+}
+
+impl GlonassEphemeris {
+    /// Propagates this state vector by `dt` seconds from the frame epoch,
+    /// see [orbits::glonass_propagate]. Returns ECEF PZ-90.11
+    /// (position [m], velocity [m.s⁻¹]), None if the state vector
+    /// is not fully described. Luni-solar acceleration is assumed null
+    /// when not described
+    pub fn state_at (&self, dt: f64) -> Option<((f64, f64, f64), (f64, f64, f64))> {
+        let position = km_to_m(self.position?);
+        let velocity = km_to_m(self.velocity?);
+        let acceleration = km_to_m(self.acceleration.unwrap_or((0.0, 0.0, 0.0)));
+        Some(orbits::glonass_propagate(position, velocity, acceleration, dt))
+    }
+    /// Returns ECEF PZ-90.11 position [m], `dt` seconds after the frame epoch
+    pub fn position (&self, dt: f64) -> Option<(f64, f64, f64)> {
+        self.state_at(dt)
+            .map(|(position, _)| position)
+    }
+    /// Returns ECEF PZ-90.11 velocity [m.s⁻¹], `dt` seconds after the frame epoch
+    pub fn velocity (&self, dt: f64) -> Option<(f64, f64, f64)> {
+        self.state_at(dt)
+            .map(|(_, velocity)| velocity)
+    }
+}
+
+/// SBAS Ephemeris content: state vector at frame epoch
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct SbasEphemeris {
+    /// Position [km]
+    pub position: Option<(f64, f64, f64)>,
+    /// Velocity [km.s⁻¹]
+    pub velocity: Option<(f64, f64, f64)>,
+    /// Acceleration [km.s⁻²]
+    pub acceleration: Option<(f64, f64, f64)>,
+    /// Vehicule health, 0 means healthy
+    pub health: Option<f64>,
+    /// Accuracy code (URA)
+    pub accuracy: Option<f64>,
+    /// Issue of Data, Navigation
+    pub iodn: Option<f64>,
+}
+
+impl SbasEphemeris {
+    /// Extrapolates this state vector by `dt` seconds from the frame epoch,
+    /// with constant acceleration. Returns ECEF WGS84
+    /// (position [m], velocity [m.s⁻¹]), None if the state vector
+    /// is not fully described. Acceleration is assumed null
+    /// when not described
+    pub fn state_at (&self, dt: f64) -> Option<((f64, f64, f64), (f64, f64, f64))> {
+        let (x, y, z) = km_to_m(self.position?);
+        let (vx, vy, vz) = km_to_m(self.velocity?);
+        let (ax, ay, az) = km_to_m(self.acceleration.unwrap_or((0.0, 0.0, 0.0)));
+        let position = (
+            x + vx * dt + ax * dt * dt / 2.0,
+            y + vy * dt + ay * dt * dt / 2.0,
+            z + vz * dt + az * dt * dt / 2.0,
+        );
+        let velocity = (vx + ax * dt, vy + ay * dt, vz + az * dt);
+        Some((position, velocity))
+    }
+    /// Returns ECEF WGS84 position [m], `dt` seconds after the frame epoch
+    pub fn position (&self, dt: f64) -> Option<(f64, f64, f64)> {
+        self.state_at(dt)
+            .map(|(position, _)| position)
+    }
+    /// Returns ECEF WGS84 velocity [m.s⁻¹], `dt` seconds after the frame epoch
+    pub fn velocity (&self, dt: f64) -> Option<(f64, f64, f64)> {
+        self.state_at(dt)
+            .map(|(_, velocity)| velocity)
+    }
+}
+
+/// Typed Ephemeris content.
+/// Fields are optional: a field that is missing, or cannot be interpreted,
+/// remains in the raw content of the frame
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum Ephemeris {
+    /// GPS or QZSS Ephemeris
+    Gps(GpsEphemeris),
+    /// Galileo Ephemeris
+    Galileo(GalileoEphemeris),
+    /// BeiDou Ephemeris
+    Beidou(BeidouEphemeris),
+    /// Glonass Ephemeris
+    Glonass(GlonassEphemeris),
+    /// SBAS Ephemeris
+    Sbas(SbasEphemeris),
+}
+
+impl Default for Ephemeris {
+    fn default() -> Self {
+        Self::Gps(GpsEphemeris::default())
+    }
+}
+
+impl Ephemeris {
+    /// Interprets Ephemeris frame content (`map`), broadcast by given vehicule.
+    /// Interpreted fields are moved out of `map`, which then only
+    /// contains the fields that are not interpreted.
+    /// Returns None if this constellation is not supported
+    pub fn from_orbits (sv: &Sv, map: &mut HashMap<String, ComplexEnum>) -> Option<Self> {
+        match sv.constellation {
+            Constellation::GPS | Constellation::QZSS => {
+                Some(Self::Gps(GpsEphemeris {
+                    kepler: take_kepler(map),
+                    iode: take(map, "iode"),
+                    iodc: take(map, "iodc"),
+                    week: take_any(map, &["gpsWeek", "wn_op"]),
+                    health: take(map, "svHealth"),
+                    accuracy: take(map, "svAccuracy"),
+                    tgd: take(map, "tgd"),
+                    fit_interval: take(map, "fitInt"),
+                    t_tm: take(map, "t_tm"),
+                }))
+            },
+            Constellation::Galileo => {
+                Some(Self::Galileo(GalileoEphemeris {
+                    kepler: take_kepler(map),
+                    iodnav: take(map, "iodnav"),
+                    week: take(map, "galWeek"),
+                    health: take(map, "svHealth"),
+                    sisa: take(map, "sisa"),
+                    bgd_e5a_e1: take(map, "bgdE5aE1"),
+                    bgd_e5b_e1: take(map, "bgdE5bE1"),
+                    data_source: take(map, "dataSrc"),
+                    t_tm: take(map, "t_tm"),
+                }))
+            },
+            Constellation::BeiDou => {
+                Some(Self::Beidou(BeidouEphemeris {
+                    kepler: take_kepler(map),
+                    aode: take(map, "aode"),
+                    aodc: take(map, "oadc"),
+                    week: take(map, "bdtWeek"),
+                    health: take(map, "satH1"),
+                    accuracy: take(map, "svAccuracy"),
+                    tgd1: take_any(map, &["tgd1b1b3", "tgdb1b3"]),
+                    tgd2: take_any(map, &["tgd2b2b3", "tgdb2b3"]),
+                    t_tm: take(map, "t_tm"),
+                }))
+            },
+            Constellation::Glonass => {
+                Some(Self::Glonass(GlonassEphemeris {
+                    position: take_state_vector(map, POSITION_FIELDS),
+                    velocity: take_state_vector(map, VELOCITY_FIELDS),
+                    acceleration: take_state_vector(map, ACCELERATION_FIELDS),
+                    health: take(map, "health"),
+                    channel: take(map, "freqNum").map(|f| f as i8),
+                    age: take(map, "ageOp"),
+                }))
+            },
+            Constellation::SBAS(_) => {
+                Some(Self::Sbas(SbasEphemeris {
+                    position: take_state_vector(map, POSITION_FIELDS),
+                    velocity: take_state_vector(map, VELOCITY_FIELDS),
+                    acceleration: take_state_vector(map, ACCELERATION_FIELDS),
+                    health: take(map, "health"),
+                    accuracy: take(map, "accuracyCode"),
+                    iodn: take(map, "iodn"),
+                }))
+            },
+            _ => None,
+        }
+    }
+    /// Returns interpreted field, by its RINEX layout name
+    /// (db/NAV/navigation.json). Returns None if this field
+    /// is not described, or not interpreted: see [super::Frame::as_eph_raw]
+    pub fn field (&self, key: &str) -> Option<f64> {
+        match self {
+            Self::Gps(eph) => match key {
+                "iode" => eph.iode,
+                "iodc" => eph.iodc,
+                "gpsWeek" | "wn_op" => eph.week,
+                "svHealth" => eph.health,
+                "svAccuracy" => eph.accuracy,
+                "tgd" => eph.tgd,
+                "fitInt" => eph.fit_interval,
+                "t_tm" => eph.t_tm,
+                _ => kepler_field(eph.kepler.as_ref()?, key),
+            },
+            Self::Galileo(eph) => match key {
+                "iodnav" => eph.iodnav,
+                "galWeek" => eph.week,
+                "svHealth" => eph.health,
+                "sisa" => eph.sisa,
+                "bgdE5aE1" => eph.bgd_e5a_e1,
+                "bgdE5bE1" => eph.bgd_e5b_e1,
+                "dataSrc" => eph.data_source,
+                "t_tm" => eph.t_tm,
+                _ => kepler_field(eph.kepler.as_ref()?, key),
+            },
+            Self::Beidou(eph) => match key {
+                "aode" => eph.aode,
+                "oadc" => eph.aodc,
+                "bdtWeek" => eph.week,
+                "satH1" => eph.health,
+                "svAccuracy" => eph.accuracy,
+                "tgd1b1b3" | "tgdb1b3" => eph.tgd1,
+                "tgd2b2b3" | "tgdb2b3" => eph.tgd2,
+                "t_tm" => eph.t_tm,
+                _ => kepler_field(eph.kepler.as_ref()?, key),
+            },
+            Self::Glonass(eph) => match key {
+                "health" => eph.health,
+                "freqNum" => eph.channel.map(|c| c as f64),
+                "ageOp" => eph.age,
+                _ => state_vector_field(eph.position, POSITION_FIELDS, key)
+                    .or_else(|| state_vector_field(eph.velocity, VELOCITY_FIELDS, key))
+                    .or_else(|| state_vector_field(eph.acceleration, ACCELERATION_FIELDS, key)),
+            },
+            Self::Sbas(eph) => match key {
+                "health" => eph.health,
+                "accuracyCode" => eph.accuracy,
+                "iodn" => eph.iodn,
+                _ => state_vector_field(eph.position, POSITION_FIELDS, key)
+                    .or_else(|| state_vector_field(eph.velocity, VELOCITY_FIELDS, key))
+                    .or_else(|| state_vector_field(eph.acceleration, ACCELERATION_FIELDS, key)),
+            },
+        }
+    }
+    /// Returns Keplerian elements, for GPS, QZSS, Galileo and BeiDou Ephemeris
+    pub fn kepler (&self) -> Option<&Kepler> {
+        match self {
+            Self::Gps(eph) => eph.kepler.as_ref(),
+            Self::Galileo(eph) => eph.kepler.as_ref(),
+            Self::Beidou(eph) => eph.kepler.as_ref(),
+            _ => None,
+        }
+    }
+    /// Returns vehicule health flag, 0 means healthy
+    pub fn health (&self) -> Option<f64> {
+        match self {
+            Self::Gps(eph) => eph.health,
+            Self::Galileo(eph) => eph.health,
+            Self::Beidou(eph) => eph.health,
+            Self::Glonass(eph) => eph.health,
+            Self::Sbas(eph) => eph.health,
+        }
+    }
+    /// Returns true if vehicule declares itself healthy,
+    /// None if health is not described. Any non zero health flag
+    /// marks the vehicule as unhealthy
+    pub fn is_healthy (&self) -> Option<bool> {
+        self.health()
+            .map(|health| health == 0.0)
+    }
+    /// Returns Issue of Data (IODE, IODnav, AODE, IODN) of this Ephemeris,
+    /// if described
+    pub fn iod (&self) -> Option<f64> {
+        match self {
+            Self::Gps(eph) => eph.iode,
+            Self::Galileo(eph) => eph.iodnav,
+            Self::Beidou(eph) => eph.aode,
+            Self::Glonass(_) => None,
+            Self::Sbas(eph) => eph.iodn,
+        }
+    }
+    /// Returns week number, if described
+    pub fn week (&self) -> Option<f64> {
+        match self {
+            Self::Gps(eph) => eph.week,
+            Self::Galileo(eph) => eph.week,
+            Self::Beidou(eph) => eph.week,
+            _ => None,
+        }
+    }
+    /// Resolves ECEF position [m] of given vehicule at given epoch,
+    /// `toc` being the epoch of this Ephemeris frame. GPS, QZSS, Galileo
+    /// and BeiDou positions are resolved from Keplerian elements, GLONASS positions
+    /// by propagation of the state vector, SBAS positions by extrapolation
+    /// of the state vector. `epoch` is expressed in the time scale
+    /// of this Ephemeris (UTC for GLONASS).
+    /// Returns None if this constellation is not supported
+    pub fn position (&self, sv: &Sv, toc: &Epoch, epoch: &Epoch) -> Option<(f64, f64, f64)> {
+        match self {
+            Self::Glonass(glo) => glo.position(seconds_between(toc, epoch)),
+            Self::Sbas(sbas) => sbas.position(seconds_between(toc, epoch)),
+            _ => self.kepler()?.position(sv, epoch),
+        }
+    }
+    /// Resolves ECEF velocity [m.s⁻¹] of given vehicule at given epoch,
+    /// see [Ephemeris::position]
+    pub fn velocity (&self, sv: &Sv, toc: &Epoch, epoch: &Epoch) -> Option<(f64, f64, f64)> {
+        match self {
+            Self::Glonass(glo) => glo.velocity(seconds_between(toc, epoch)),
+            Self::Sbas(sbas) => sbas.velocity(seconds_between(toc, epoch)),
+            _ => self.kepler()?.velocity(sv, epoch),
+        }
+    }
+    /// Returns the broadcast group delay [s] that applies to single frequency
+    /// observations on given channel, scaled to that channel:
+    ///   - GPS & QZSS: TGD on L1, (f1/f2)².TGD on L2
+    ///   - Galileo: BGD(E5a,E1) on E1 for F/NAV Ephemeris, BGD(E5b,E1) otherwise,
+    ///   (fE1/fE5a)².BGD(E5a,E1) on E5a, (fE1/fE5b)².BGD(E5b,E1) on E5b
+    ///   - BeiDou: TGD1 on B1I, TGD2 on B2I, B3I is the reference (0)
+    ///
+    /// Returns None if this group delay is not described,
+    /// or if this channel is not supported
+    pub fn group_delay (&self, channel: &Channel) -> Option<f64> {
+        match self {
+            Self::Gps(eph) => {
+                let tgd = eph.tgd?;
+                match channel {
+                    Channel::L1 => Some(tgd),
+                    Channel::L2 => Some(gamma(Channel::L1, channel) * tgd),
+                    _ => None,
+                }
+            },
+            Self::Galileo(eph) => {
+                match channel {
+                    Channel::E1 => {
+                        let fnav = eph.data_source
+                            .map(|src| src as u32 & GAL_DATA_SOURCE_E5A_E1 > 0)
+                            .unwrap_or(false);
+                        if fnav {
+                            eph.bgd_e5a_e1
+                        } else {
+                            eph.bgd_e5b_e1
+                        }
+                    },
+                    Channel::E5 => Some(gamma(Channel::E1, channel) * eph.bgd_e5a_e1?),
+                    Channel::E7 => Some(gamma(Channel::E1, channel) * eph.bgd_e5b_e1?),
+                    _ => None,
+                }
+            },
+            Self::Beidou(eph) => {
+                match channel {
+                    Channel::B1I => eph.tgd1,
+                    Channel::B2I => eph.tgd2,
+                    Channel::B3I => Some(0.0),
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+    /// Applies the broadcast group delay (see [Ephemeris::group_delay])
+    /// to given single frequency pseudo range [m], observed on given channel.
+    /// Returns None if the group delay is not known for this channel
+    pub fn group_delay_corrected_pseudo_range (&self, pseudo_range: f64, channel: &Channel) -> Option<f64> {
+        Some(pseudo_range - SPEED_OF_LIGHT_M_S * self.group_delay(channel)?)
+    }
+}
+
+/// Returns time elapsed between given epochs [s]
+fn seconds_between (from: &Epoch, to: &Epoch) -> f64 {
+    (to.date - from.date).num_milliseconds() as f64 / 1.0E3
+}
+
+/// Builds an Ephemeris frame broadcast by given vehicule,
+/// from given fields, for unit tests
+#[cfg(test)]
+pub(crate) fn test_frame (sv: &str, fields: &[(&str, f64)]) -> super::Frame {
+    use std::str::FromStr;
+    let sv = Sv::from_str(sv).unwrap();
+    let mut map: HashMap<String, ComplexEnum> = fields.iter()
+        .map(|(k, v)| (k.to_string(), ComplexEnum::F64(*v)))
+        .collect();
+    let eph = Ephemeris::from_orbits(&sv, &mut map).unwrap();
+    super::Frame::Eph(super::MsgType::LNAV, sv, 0.0, 0.0, 0.0, eph, map)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    const KEPLER: [(&str, f64); 16] = [
+        ("sqrta", 5153.6), ("e", 0.01), ("i0", 0.96), ("omega0", 1.0),
+        ("omega", 0.5), ("m0", 0.2), ("deltaN", 4.0E-9), ("idot", 1.0E-10),
+        ("omegaDot", -8.0E-9), ("cuc", 1.0E-6), ("cus", 1.0E-6), ("crc", 200.0),
+        ("crs", 20.0), ("cic", 1.0E-8), ("cis", 1.0E-8), ("toe", 432000.0),
+    ];
+    /// Interprets given fields, checks that every interpreted field
+    /// is restored by [Ephemeris::field]
+    fn interpret (sv: &str, fields: &[(&str, f64)]) -> (Ephemeris, HashMap<String, ComplexEnum>) {
+        let frame = test_frame(sv, fields);
+        let (_, _, _, _, _, eph) = frame.as_eph().unwrap();
+        let raw = frame.as_eph_raw().unwrap();
+        for (k, v) in fields.iter() {
+            if !raw.contains_key(*k) {
+                assert_eq!(eph.field(k), Some(*v), "field \"{}\" not restored", k);
+            }
+        }
+        (*eph, raw.clone())
+    }
+    #[test]
+    fn test_gps() {
+        let mut fields = KEPLER.to_vec();
+        fields.extend_from_slice(&[("iode", 12.0), ("gpsWeek", 2138.0), ("svHealth", 0.0), ("tgd", -1.0E-8), ("l2Codes", 1.0)]);
+        let (eph, raw) = interpret("G01", &fields);
+        assert_eq!(eph.kepler().unwrap().toe, 432000.0);
+        assert_eq!(eph.is_healthy(), Some(true));
+        assert_eq!(eph.iod(), Some(12.0));
+        assert_eq!(eph.week(), Some(2138.0));
+        match eph {
+            Ephemeris::Gps(gps) => {
+                assert_eq!(gps.tgd, Some(-1.0E-8));
+                assert_eq!(gps.iodc, None);
+            },
+            _ => panic!("wrong ephemeris type"),
+        }
+        // not interpreted
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw["l2Codes"], ComplexEnum::F64(1.0));
+        assert_eq!(eph.field("l2Codes"), None);
+        // missing keplerian element: remaining elements are not interpreted
+        let fields: Vec<(&str, f64)> = fields.into_iter()
+            .filter(|(k, _)| *k != "sqrta")
+            .collect();
+        let (eph, raw) = interpret("G01", &fields);
+        assert_eq!(eph.kepler(), None);
+        assert_eq!(eph.field("toe"), None);
+        assert_eq!(eph.iod(), Some(12.0));
+        assert_eq!(raw.len(), 16);
+        assert_eq!(raw["toe"], ComplexEnum::F64(432000.0));
+    }
+    #[test]
+    fn test_gps_cnav() {
+        let fields = [("wn_op", 2200.0), ("svHealth", 1.0), ("t_op", 0.0)];
+        let (eph, raw) = interpret("G01", &fields);
+        assert_eq!(eph.week(), Some(2200.0));
+        assert_eq!(eph.field("wn_op"), Some(2200.0));
+        assert_eq!(eph.is_healthy(), Some(false));
+        assert_eq!(eph.iod(), None);
+        assert_eq!(raw.len(), 1);
+    }
+    #[test]
+    fn test_galileo() {
+        let mut fields = KEPLER.to_vec();
+        fields.extend_from_slice(&[("iodnav", 42.0), ("galWeek", 2138.0), ("svHealth", 0.0), ("bgdE5aE1", 1.0E-9)]);
+        let (eph, raw) = interpret("E01", &fields);
+        assert_eq!(eph.is_healthy(), Some(true));
+        assert_eq!(eph.iod(), Some(42.0));
+        assert_eq!(raw.len(), 0);
+    }
+    #[test]
+    fn test_group_delay() {
+        let mut fields = KEPLER.to_vec();
+        fields.extend_from_slice(&[("svHealth", 0.0), ("tgd", 1.0E-8)]);
+        let (eph, _) = interpret("G01", &fields);
+        assert_eq!(eph.group_delay(&Channel::L1), Some(1.0E-8));
+        let l2 = eph.group_delay(&Channel::L2).unwrap();
+        assert!((l2 - 1.6469444 * 1.0E-8).abs() < 1.0E-14);
+        assert_eq!(eph.group_delay(&Channel::L5), None);
+        let pr = eph.group_delay_corrected_pseudo_range(2.0E7, &Channel::L1).unwrap();
+        assert!((pr - (2.0E7 - 2.99792458)).abs() < 1.0E-6);
+        // Galileo: I/NAV (E5b,E1), F/NAV (E5a,E1)
+        let mut fields = KEPLER.to_vec();
+        fields.extend_from_slice(&[("bgdE5aE1", 1.0E-9), ("bgdE5bE1", 2.0E-9), ("dataSrc", 517.0)]);
+        let (eph, _) = interpret("E01", &fields);
+        assert_eq!(eph.group_delay(&Channel::E1), Some(2.0E-9));
+        let e5a = eph.group_delay(&Channel::E5).unwrap();
+        assert!((e5a - 1.7933274 * 1.0E-9).abs() < 1.0E-15);
+        fields.push(("dataSrc", 258.0));
+        let (eph, _) = interpret("E01", &fields);
+        assert_eq!(eph.group_delay(&Channel::E1), Some(1.0E-9));
+        // BeiDou: B3I is the reference
+        let fields = [("tgd1b1b3", 3.0E-9), ("tgd2b2b3", -1.0E-9)];
+        let (eph, _) = interpret("C05", &fields);
+        assert_eq!(eph.group_delay(&Channel::B1I), Some(3.0E-9));
+        assert_eq!(eph.group_delay(&Channel::B2I), Some(-1.0E-9));
+        assert_eq!(eph.group_delay(&Channel::B3I), Some(0.0));
+        // state vector Ephemeris
+        let (eph, _) = interpret("R01", &[("health", 0.0)]);
+        assert_eq!(eph.group_delay(&Channel::G1(None)), None);
+    }
+    #[test]
+    fn test_beidou() {
+        let mut fields = KEPLER.to_vec();
+        fields.extend_from_slice(&[("aode", 1.0), ("bdtWeek", 782.0), ("satH1", 1.0), ("tgdb1b3", 2.0E-9)]);
+        let (eph, raw) = interpret("C05", &fields);
+        assert_eq!(eph.is_healthy(), Some(false));
+        assert_eq!(eph.iod(), Some(1.0));
+        assert_eq!(eph.field("tgd1b1b3"), Some(2.0E-9));
+        assert_eq!(raw.len(), 0);
+        match eph {
+            Ephemeris::Beidou(bds) => assert_eq!(bds.tgd1, Some(2.0E-9)),
+            _ => panic!("wrong ephemeris type"),
+        }
+    }
+    #[test]
+    fn test_glonass() {
+        let fields = [
+            ("satPosX", -1488.79980469), ("velX", -2.19618225098), ("accelX", 3.72529029846E-09),
+            ("health", 0.0), ("satPosY", 12928.8071289), ("velY", -0.55), ("accelY", 0.0),
+            ("freqNum", -7.0), ("satPosZ", 21500.0), ("velZ", 0.2), ("accelZ", 0.0), ("ageOp", 0.0),
+        ];
+        let (eph, raw) = interpret("R01", &fields);
+        assert_eq!(eph.kepler(), None);
+        assert_eq!(eph.iod(), None);
+        assert_eq!(raw.len(), 0);
+        match eph {
+            Ephemeris::Glonass(glo) => {
+                assert_eq!(glo.position, Some((-1488.79980469, 12928.8071289, 21500.0)));
+                assert_eq!(glo.channel, Some(-7));
+            },
+            _ => panic!("wrong ephemeris type"),
+        }
+    }
+    #[test]
+    fn test_glonass_position() {
+        let fields = [
+            ("satPosX", 7003.008789), ("velX", 0.7835417), ("accelX", 0.0), ("health", 0.0),
+            ("satPosY", -12206.626953), ("velY", 2.8042530), ("accelY", 1.7E-9),
+            ("satPosZ", 21280.765625), ("velZ", 1.3525150), ("accelZ", -5.41E-9),
+        ];
+        let (eph, _) = interpret("R01", &fields);
+        let sv = Sv::from_str("R01").unwrap();
+        let toc = Epoch::new(
+            crate::epoch::str2date("2021 01 01 00 00 00").unwrap(),
+            crate::epoch::EpochFlag::Ok);
+        let (x, y, z) = eph.position(&sv, &toc, &toc).unwrap();
+        assert!((x - 7003008.789).abs() < 1.0E-3);
+        assert!((y - -12206626.953).abs() < 1.0E-3);
+        assert!((z - 21280765.625).abs() < 1.0E-3);
+        let later = Epoch::new(
+            toc.date + chrono::Duration::seconds(600),
+            crate::epoch::EpochFlag::Ok);
+        let (x, y, z) = eph.position(&sv, &toc, &later).unwrap();
+        assert!((x - 7523174.851).abs() < 1.0);
+        assert!((y - -10506961.864).abs() < 1.0);
+        assert!((z - 21999238.892).abs() < 1.0);
+        let (vx, _, _) = eph.velocity(&sv, &toc, &later).unwrap();
+        assert!((vx - 950.126).abs() < 1.0E-3);
+        // incomplete state vector
+        let (eph, _) = interpret("R01", &fields[..4]);
+        assert_eq!(eph.position(&sv, &toc, &later), None);
+    }
+    #[test]
+    fn test_sbas() {
+        let fields = [
+            ("satPosX", 1.0), ("velX", 2.0), ("accelX", 3.0), ("health", 0.0),
+            ("satPosY", 4.0), ("velY", 5.0), ("accelY", 6.0), ("accuracyCode", 2.0),
+            ("satPosZ", 7.0), ("velZ", 8.0), ("accelZ", 9.0), ("iodn", 10.0),
+        ];
+        let (eph, raw) = interpret("S23", &fields);
+        assert_eq!(eph.iod(), Some(10.0));
+        assert_eq!(eph.week(), None);
+        assert_eq!(raw.len(), 0);
+    }
+    #[test]
+    fn test_sbas_position() {
+        let fields = [
+            ("satPosX", 40000.0), ("velX", 1.0E-3), ("accelX", 2.0E-7), ("health", 0.0),
+            ("satPosY", 10000.0), ("velY", -2.0E-3), ("accelY", 0.0), ("accuracyCode", 2.0),
+            ("satPosZ", 0.0), ("velZ", 0.0), ("accelZ", 0.0), ("iodn", 10.0),
+        ];
+        let (eph, _) = interpret("S23", &fields);
+        let sv = Sv::from_str("S23").unwrap();
+        let toc = Epoch::new(
+            crate::epoch::str2date("2021 01 01 00 00 00").unwrap(),
+            crate::epoch::EpochFlag::Ok);
+        assert_eq!(eph.position(&sv, &toc, &toc), Some((40000.0E3, 10000.0E3, 0.0)));
+        let later = Epoch::new(
+            toc.date + chrono::Duration::seconds(100),
+            crate::epoch::EpochFlag::Ok);
+        let (x, y, z) = eph.position(&sv, &toc, &later).unwrap();
+        assert!((x - 40000101.0).abs() < 1.0E-6);
+        assert!((y - 9999800.0).abs() < 1.0E-6);
+        assert_eq!(z, 0.0);
+        let (vx, vy, _) = eph.velocity(&sv, &toc, &later).unwrap();
+        assert!((vx - 1.02).abs() < 1.0E-9);
+        assert!((vy - -2.0).abs() < 1.0E-9);
+        // incomplete state vector
+        let (eph, _) = interpret("S23", &fields[..4]);
+        assert_eq!(eph.position(&sv, &toc, &later), None);
+    }
+}
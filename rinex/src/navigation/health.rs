@@ -0,0 +1,96 @@
+//! Vehicule health status, from Ephemeris frames
+use crate::epoch;
+use crate::sv::Sv;
+use super::record::{Record, FrameClass};
+use std::collections::{BTreeMap, HashMap};
+
+/// Returns health status history of every vehicule described in given record,
+/// indexed by Ephemeris epoch
+pub fn health_history (record: &Record) -> HashMap<Sv, BTreeMap<epoch::Epoch, bool>> {
+    let mut results: HashMap<Sv, BTreeMap<epoch::Epoch, bool>> = HashMap::new();
+    for (e, classes) in record.iter() {
+        if let Some(frames) = classes.get(&FrameClass::Ephemeris) {
+            for frame in frames.iter() {
+                if let Some((_, sv, _, _, _, eph)) = frame.as_eph() {
+                    if let Some(healthy) = eph.is_healthy() {
+                        results
+                            .entry(sv)
+                            .or_insert_with(BTreeMap::new)
+                            .insert(*e, healthy);
+                    }
+                }
+            }
+        }
+    }
+    results
+}
+
+/// Returns health status at given epoch, from given history:
+/// latest Ephemeris published at or before given epoch,
+/// earliest Ephemeris otherwise
+pub fn health_at (history: &BTreeMap<epoch::Epoch, bool>, epoch: epoch::Epoch) -> Option<bool> {
+    history.range(..=epoch)
+        .next_back()
+        .or_else(|| history.range(epoch..).next())
+        .map(|(_, healthy)| *healthy)
+}
+
+/// Returns vehicules declared healthy at given epoch, in given record,
+/// from their latest Ephemeris (earliest Ephemeris if none published yet).
+/// Vehicules that do not describe their health are not reported
+pub fn healthy_sv (record: &Record, epoch: epoch::Epoch) -> Vec<Sv> {
+    let mut results: Vec<Sv> = health_history(record)
+        .iter()
+        .filter_map(|(sv, history)| {
+            if health_at(history, epoch)? {
+                Some(*sv)
+            } else {
+                None
+            }
+        })
+        .collect();
+    results.sort();
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::navigation::record::Frame;
+    use crate::navigation::ephemeris::test_frame;
+    use std::str::FromStr;
+    fn ephemeris (sv: &str, field: &str, health: f64) -> Frame {
+        test_frame(sv, &[(field, health)])
+    }
+    #[test]
+    fn test_healthy_sv() {
+        let at = |date: &str| epoch::Epoch::new(
+            epoch::str2date(date).unwrap(),
+            epoch::EpochFlag::Ok);
+        let mut record = Record::new();
+        let mut classes: BTreeMap<FrameClass, Vec<Frame>> = BTreeMap::new();
+        classes.insert(FrameClass::Ephemeris, vec![
+            ephemeris("G01", "svHealth", 0.0),
+            ephemeris("G02", "svHealth", 1.0),
+            ephemeris("R01", "health", 0.0),
+            ephemeris("C01", "satH1", 0.0),
+            ephemeris("E01", "iodnav", 0.0),
+        ]);
+        record.insert(at("2021 01 01 00 00 00"), classes);
+        let mut classes: BTreeMap<FrameClass, Vec<Frame>> = BTreeMap::new();
+        classes.insert(FrameClass::Ephemeris, vec![
+            ephemeris("G01", "svHealth", 63.0),
+            ephemeris("G02", "svHealth", 0.0),
+        ]);
+        record.insert(at("2021 01 01 02 00 00"), classes);
+        let sv = |s: &str| Sv::from_str(s).unwrap();
+        // earliest ephemeris applies
+        assert_eq!(healthy_sv(&record, at("2020 12 31 23 00 00")), vec![sv("C01"), sv("G01"), sv("R01")]);
+        assert_eq!(healthy_sv(&record, at("2021 01 01 01 00 00")), vec![sv("C01"), sv("G01"), sv("R01")]);
+        // health updates
+        assert_eq!(healthy_sv(&record, at("2021 01 01 02 00 00")), vec![sv("C01"), sv("G02"), sv("R01")]);
+        let history = health_history(&record);
+        assert_eq!(history.get(&sv("E01")), None);
+        assert_eq!(health_at(&history[&sv("G01")], at("2021 01 01 03 00 00")), Some(false));
+    }
+}
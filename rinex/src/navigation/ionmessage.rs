@@ -3,6 +3,7 @@ use bitflags::bitflags;
 use crate::epoch;
 use thiserror::Error;
 use std::str::FromStr;
+use chrono::Timelike;
 
 /// Model parsing error
 #[derive(Debug, Error)]
@@ -34,7 +35,7 @@ pub enum Error {
 /// Klobuchar Parameters region
 #[derive(Debug, Copy, Clone)]
 #[derive(PartialEq, PartialOrd)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum KbRegionCode {
     /// Coefficients apply to wide area
     WideArea = 0,
@@ -53,7 +54,7 @@ impl Default for KbRegionCode {
 #[derive(Default)]
 #[derive(Debug, Copy, Clone)]
 #[derive(PartialEq, PartialOrd)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct KbModel {
     /// Alpha coefficients 
     /// ([sec], [sec.semi-circle⁻¹], [sec.semi-circle⁻²], [sec.semi-circle⁻³])
@@ -129,11 +130,49 @@ impl KbModel {
             region,
         }))
     }
+    /// Evaluates the ionospheric slant delay [s] on L1,
+    /// at given epoch, for given user latitude [°], longitude [°],
+    /// and vehicule elevation [°] and azimuth [°],
+    /// following the ICD-GPS-200 algorithm.
+    /// Multiply by the speed of light to obtain a delay in [m],
+    /// and scale by (f_L1 / f)² for other carrier frequencies
+    pub fn slant_delay (&self, epoch: epoch::Epoch, user_lat: f64, user_lon: f64, elevation: f64, azimuth: f64) -> f64 {
+        // semi circles
+        let e = elevation / 180.0;
+        let a = azimuth.to_radians();
+        let (phi_u, lambda_u) = (user_lat / 180.0, user_lon / 180.0);
+        // earth centered angle
+        let psi = 0.0137 / (e + 0.11) - 0.022;
+        // ionospheric pierce point
+        let phi_i = (phi_u + psi * a.cos())
+            .clamp(-0.416, 0.416);
+        let lambda_i = lambda_u + psi * a.sin() / (phi_i * std::f64::consts::PI).cos();
+        // geomagnetic latitude
+        let phi_m = phi_i + 0.064 * ((lambda_i - 1.617) * std::f64::consts::PI).cos();
+        // local time [s]
+        let tod = epoch.date.num_seconds_from_midnight() as f64
+            + epoch.date.nanosecond() as f64 * 1.0E-9;
+        let t = (4.32E4 * lambda_i + tod).rem_euclid(86400.0);
+        // obliquity factor
+        let f = 1.0 + 16.0 * (0.53 - e).powi(3);
+        let (a0, a1, a2, a3) = self.alpha;
+        let (b0, b1, b2, b3) = self.beta;
+        let amp = (a0 + a1 * phi_m + a2 * phi_m.powi(2) + a3 * phi_m.powi(3))
+            .max(0.0);
+        let per = (b0 + b1 * phi_m + b2 * phi_m.powi(2) + b3 * phi_m.powi(3))
+            .max(72000.0);
+        let x = 2.0 * std::f64::consts::PI * (t - 50400.0) / per;
+        if x.abs() < 1.57 {
+            f * (5.0E-9 + amp * (1.0 - x.powi(2) / 2.0 + x.powi(4) / 24.0))
+        } else {
+            f * 5.0E-9
+        }
+    }
 }
 
 bitflags! {
     #[derive(Default)]
-    #[cfg_attr(feature = "with-serde", derive(Serialize))]
+    #[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
     pub struct NgRegionFlags: u16 {
         const REGION5 = 0x01;
         const REGION4 = 0x02;
@@ -147,7 +186,7 @@ bitflags! {
 #[derive(Debug, Clone)]
 #[derive(Default, Copy)]
 #[derive(PartialEq, PartialOrd)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct NgModel {
     /// a_i coefficients
     /// ([sfu], [sfu.semi-circle⁻¹], [sfu.semi-circle⁻²])
@@ -187,13 +226,34 @@ impl NgModel {
             region: NgRegionFlags::from_bits(f as u16).unwrap_or(NgRegionFlags::empty()),
         }))
     }
+    /// Returns the Effective Ionisation Level Az [sfu]
+    /// at given modified dip latitude (MODIP) [°], as defined in the
+    /// Galileo Ionospheric Model ICD. Az is only the solar activity input
+    /// of the NeQuick-G electron density model: this crate does not integrate
+    /// NeQuick-G (slant TEC and delays), which requires the CCIR and MODIP maps.
+    /// When all coefficients are null, the default value (63.7 sfu) applies
+    pub fn effective_ionisation_level (&self, modip: f64) -> f64 {
+        let (a0, a1, a2) = self.a;
+        if a0 == 0.0 && a1 == 0.0 && a2 == 0.0 {
+            return 63.7
+        }
+        (a0 + a1 * modip + a2 * modip.powi(2))
+            .clamp(0.0, 400.0)
+    }
+    /// Returns the effective sunspot number at given MODIP [°],
+    /// from the Effective Ionisation Level, as defined in the
+    /// Galileo Ionospheric Model ICD
+    pub fn effective_sunspot_number (&self, modip: f64) -> f64 {
+        let az = self.effective_ionisation_level(modip);
+        (167273.0 + (az - 63.7) * 1123.6).sqrt() - 408.99
+    }
 }
 
 /// BDGIM Model payload
 #[derive(Debug, Copy, Clone)]
 #[derive(Default)]
 #[derive(PartialEq, PartialOrd)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct BdModel {
     /// Alpha coefficients [TECu]
     pub alpha: (f64,f64,f64,f64,f64,f64,f64,f64,f64),
@@ -211,7 +271,7 @@ impl BdModel {
         
         let line = match lines.next() {
             Some(l) => l,
-            _ => return Err(Error::KbModelMissing2ndLine)
+            _ => return Err(Error::BdModelMissing2ndLine)
         };
         let (a3, rem) = line.split_at(23);
         let (a4, rem) = rem.split_at(19);
@@ -219,7 +279,7 @@ impl BdModel {
         
         let line = match lines.next() {
             Some(l) => l,
-            _ => return Err(Error::KbModelMissing3rdLine)
+            _ => return Err(Error::BdModelMissing3rdLine)
         };
         let (a7, a8) = line.split_at(23);
         
@@ -243,12 +303,209 @@ impl BdModel {
             alpha
         }))
     }
+    /// Evaluates the broadcast part of the BDGIM vertical TEC [TECu],
+    /// at given pierce point geomagnetic latitude [rad] and sun-fixed
+    /// geomagnetic longitude [rad]: the nine broadcast coefficients weight
+    /// normalized spherical harmonics up to degree and order 2,
+    /// as defined in the BDS-SIS-ICD.
+    /// This is not the total vertical TEC, see [BdModel::vtec]
+    pub fn broadcast_vtec (&self, lat: f64, lon: f64) -> f64 {
+        let alpha = [
+            self.alpha.0, self.alpha.1, self.alpha.2,
+            self.alpha.3, self.alpha.4, self.alpha.5,
+            self.alpha.6, self.alpha.7, self.alpha.8,
+        ];
+        alpha.iter()
+            .zip(BDGIM_BROADCAST_TERMS.iter())
+            .map(|(alpha, (n, m))| alpha * bdgim_harmonic(*n, *m, lat, lon))
+            .sum()
+    }
+    /// Evaluates the BDGIM vertical TEC [TECu] at given epoch (UTC),
+    /// above given pierce point latitude [°] and longitude [°]:
+    /// predicted background term (A0), described by `predicted`,
+    /// plus the broadcast term, see [BdModel::broadcast_vtec].
+    /// Negative values are clipped to 0, as defined in the BDS-SIS-ICD
+    pub fn vtec (&self, epoch: epoch::Epoch, lat: f64, lon: f64, predicted: &BdPredictedModel) -> f64 {
+        let (lat_m, lon_m) = bdgim_sun_fixed_geomagnetic(epoch, lat.to_radians(), lon.to_radians());
+        (predicted.background_vtec(epoch, lat_m, lon_m) + self.broadcast_vtec(lat_m, lon_m))
+            .max(0.0)
+    }
+    /// Evaluates the ionospheric slant delay [s] on B1C,
+    /// at given epoch (UTC), for given user latitude [°], longitude [°],
+    /// and vehicule elevation [°] and azimuth [°], following the BDS-SIS-ICD
+    /// algorithm: vertical TEC at the pierce point of a single layer
+    /// ionosphere (400 km), projected onto the line of sight.
+    /// Scale by (f_B1C / f)² for other carrier frequencies
+    pub fn slant_delay (&self, epoch: epoch::Epoch, user_lat: f64, user_lon: f64, elevation: f64, azimuth: f64, predicted: &BdPredictedModel) -> f64 {
+        let (lat, lon) = bdgim_pierce_point(user_lat, user_lon, elevation, azimuth);
+        let vtec = self.vtec(epoch, lat, lon, predicted);
+        let cos_e = BDGIM_EARTH_RADIUS_KM / (BDGIM_EARTH_RADIUS_KM + BDGIM_SHELL_HEIGHT_KM)
+            * elevation.to_radians().cos();
+        let mapping = 1.0 / (1.0 - cos_e.powi(2)).sqrt();
+        mapping * 40.28E16 * vtec / BDGIM_B1C_FREQUENCY.powi(2) / crate::channel::SPEED_OF_LIGHT_M_S
+    }
+}
+
+/// BDGIM mean Earth radius [km]
+const BDGIM_EARTH_RADIUS_KM: f64 = 6378.0;
+
+/// BDGIM single layer ionosphere height [km]
+const BDGIM_SHELL_HEIGHT_KM: f64 = 400.0;
+
+/// BDGIM geomagnetic north pole (latitude, longitude) [°]
+const BDGIM_GEOMAGNETIC_POLE: (f64, f64) = (80.27, -72.58);
+
+/// B1C carrier frequency [Hz]
+const BDGIM_B1C_FREQUENCY: f64 = 1575.42E6;
+
+/// (degree, order) of the broadcast terms,
+/// negative orders weight the sine of the longitude
+const BDGIM_BROADCAST_TERMS: [(u8, i8); 9] = [
+    (0, 0), (1, 0), (1, 1), (1, -1), (2, 0), (2, 1), (2, -1), (2, 2), (2, -2),
+];
+
+/// (degree, order) of the predicted (non broadcast) terms
+const BDGIM_PREDICTED_TERMS: [(u8, i8); 17] = [
+    (3, 0), (3, 1), (3, -1), (3, 2), (3, -2), (3, 3), (3, -3),
+    (4, 0), (4, 1), (4, -1), (4, 2), (4, -2),
+    (5, 0), (5, 1), (5, -1), (5, 2), (5, -2),
+];
+
+/// Periods [days] of the predicted coefficients expansion
+const BDGIM_PERIODS: [f64; 12] = [
+    1.0, 0.5, 0.33, 14.6, 27.0, 121.6, 182.62, 365.25, 4028.71, 2014.35, 1342.90, 1007.18,
+];
+
+/// Associated Legendre function P(n, m) at x = sin(latitude),
+/// without the Condon-Shortley phase
+fn legendre (n: u8, m: u8, x: f64) -> f64 {
+    // P(m, m) = (2m-1)!! (1-x²)^(m/2)
+    let mut pmm = 1.0;
+    for i in 0..m {
+        pmm *= (2 * i + 1) as f64 * (1.0 - x * x).sqrt();
+    }
+    if n == m {
+        return pmm
+    }
+    let mut pm1 = x * (2 * m + 1) as f64 * pmm;
+    for l in (m + 2)..=n {
+        let pl = ((2 * l - 1) as f64 * x * pm1 - (l + m - 1) as f64 * pmm) / (l - m) as f64;
+        pmm = pm1;
+        pm1 = pl;
+    }
+    pm1
+}
+
+/// Fully normalized spherical harmonic of given degree and order,
+/// at given latitude [rad] and longitude [rad]
+fn bdgim_harmonic (n: u8, m: i8, lat: f64, lon: f64) -> f64 {
+    let order = m.unsigned_abs();
+    let factorial = |k: u8| -> f64 { (1..=k).map(|i| i as f64).product() };
+    let delta = if order == 0 { 1.0 } else { 2.0 };
+    let norm = (delta * (2 * n + 1) as f64 * factorial(n - order) / factorial(n + order)).sqrt();
+    let p = norm * legendre(n, order, lat.sin());
+    if m < 0 {
+        p * (order as f64 * lon).sin()
+    } else {
+        p * (order as f64 * lon).cos()
+    }
+}
+
+/// Rotates given geographic coordinates [rad] into the BDGIM
+/// geomagnetic frame, returns (latitude, longitude) [rad]
+fn bdgim_geomagnetic (lat: f64, lon: f64) -> (f64, f64) {
+    let (pole_lat, pole_lon) = (BDGIM_GEOMAGNETIC_POLE.0.to_radians(), BDGIM_GEOMAGNETIC_POLE.1.to_radians());
+    let (x, y, z) = (lat.cos() * (lon - pole_lon).cos(), lat.cos() * (lon - pole_lon).sin(), lat.sin());
+    // bring the geomagnetic pole onto the Z axis
+    let colat = std::f64::consts::FRAC_PI_2 - pole_lat;
+    let (x, z) = (colat.cos() * x - colat.sin() * z, colat.sin() * x + colat.cos() * z);
+    (z.clamp(-1.0, 1.0).asin(), y.atan2(x))
+}
+
+/// Converts given geographic coordinates [rad] to geomagnetic latitude [rad]
+/// and sun-fixed geomagnetic longitude [rad] at given epoch (UTC):
+/// longitude is counted from the geomagnetic longitude of the mean sun
+fn bdgim_sun_fixed_geomagnetic (epoch: epoch::Epoch, lat: f64, lon: f64) -> (f64, f64) {
+    let tod = epoch.date.num_seconds_from_midnight() as f64
+        + epoch.date.nanosecond() as f64 * 1.0E-9;
+    // mean sun longitude: above Greenwich at noon
+    let sun_lon = std::f64::consts::PI * (1.0 - tod / 43200.0);
+    let (lat_m, lon_m) = bdgim_geomagnetic(lat, lon);
+    let (_, sun_lon_m) = bdgim_geomagnetic(0.0, sun_lon);
+    (lat_m, lon_m - sun_lon_m)
+}
+
+/// Returns the pierce point (latitude, longitude) [°] of the line of sight
+/// with the BDGIM single layer ionosphere, for given user latitude [°],
+/// longitude [°], and vehicule elevation [°] and azimuth [°]
+fn bdgim_pierce_point (user_lat: f64, user_lon: f64, elevation: f64, azimuth: f64) -> (f64, f64) {
+    let (lat_u, lon_u) = (user_lat.to_radians(), user_lon.to_radians());
+    let (e, a) = (elevation.to_radians(), azimuth.to_radians());
+    // earth centered angle
+    let psi = std::f64::consts::FRAC_PI_2 - e
+        - (BDGIM_EARTH_RADIUS_KM / (BDGIM_EARTH_RADIUS_KM + BDGIM_SHELL_HEIGHT_KM) * e.cos()).asin();
+    let lat = (lat_u.sin() * psi.cos() + lat_u.cos() * psi.sin() * a.cos()).asin();
+    let lon = lon_u + (psi.sin() * a.sin() / lat.cos()).asin();
+    (lat.to_degrees(), lon.to_degrees())
+}
+
+/// BDGIM predicted (non broadcast) model: the background vertical TEC (A0)
+/// is described by 17 spherical harmonics of degree 3 to 5, weighted by
+/// coefficients that vary over time, expanded on 12 periods (from 1/3 day
+/// to the 11 years solar cycle), as defined in the BDS-SIS-ICD.
+/// The coefficients table is published in the BDS-SIS-ICD
+/// and is not broadcast, it must be provided
+#[derive(Debug, Copy, Clone)]
+#[derive(Default)]
+#[derive(PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct BdPredictedModel {
+    /// Epoch (UTC) the time of prediction is counted from
+    pub reference: epoch::Epoch,
+    /// Per predicted term, in the BDS-SIS-ICD order:
+    /// constant coefficient [TECu], then (cosine, sine) coefficients [TECu]
+    /// of each period (1, 1/2, 1/3, 14.6, 27, 121.6, 182.62, 365.25, 4028.71,
+    /// 2014.35, 1342.90 and 1007.18 days)
+    pub coefficients: [(f64, [(f64, f64); 12]); 17],
+}
+
+impl BdPredictedModel {
+    /// Evaluates the weight of each predicted term at given epoch
+    pub fn weights (&self, epoch: epoch::Epoch) -> [f64; 17] {
+        let t = (epoch.date - self.reference.date).num_milliseconds() as f64 / 86400.0E3;
+        let mut weights = [0.0_f64; 17];
+        for (weight, (a0, periodic)) in weights.iter_mut().zip(self.coefficients.iter()) {
+            *weight = a0 + periodic.iter()
+                .zip(BDGIM_PERIODS.iter())
+                .map(|((a, b), period)| {
+                    let w = 2.0 * std::f64::consts::PI / period;
+                    a * (w * t).cos() + b * (w * t).sin()
+                })
+                .sum::<f64>();
+        }
+        weights
+    }
+    /// Evaluates the background vertical TEC (A0) [TECu] at given epoch,
+    /// at given geomagnetic latitude [rad] and sun-fixed geomagnetic longitude [rad]
+    pub fn background_vtec (&self, epoch: epoch::Epoch, lat: f64, lon: f64) -> f64 {
+        self.weights(epoch)
+            .iter()
+            .zip(BDGIM_PREDICTED_TERMS.iter())
+            .map(|(weight, (n, m))| weight * bdgim_harmonic(*n, *m, lat, lon))
+            .sum()
+    }
+}
+
+/// Converts given Total Electron Content [TECu]
+/// to an ionospheric group delay [m], at given carrier frequency [Hz]
+pub fn tec_to_delay (tec: f64, frequency: f64) -> f64 {
+    40.3E16 * tec / frequency.powi(2)
 }
 
 /// Existing ION Message declinations
 #[derive(Debug, Clone)]
 #[derive(PartialEq, PartialOrd)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum Message {
     /// Klobuchar Model
     KlobucharModel(KbModel),
@@ -287,3 +544,197 @@ impl Message {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_klobuchar_slant_delay() {
+        let model = KbModel {
+            alpha: (0.1118E-07, -0.7451E-08, -0.5961E-07, 0.1192E-06),
+            beta: (0.1167E+06, -0.2294E+06, -0.1311E+06, 0.1049E+07),
+            region: KbRegionCode::WideArea,
+        };
+        let at = |date: &str| epoch::Epoch {
+            date: epoch::str2date(date).unwrap(),
+            flag: epoch::EpochFlag::Ok,
+        };
+        // day time
+        let delay = model.slant_delay(at("2022 01 01 14 00 00"), 40.0, -100.0, 20.0, 210.0);
+        assert!((delay - 1.0880124E-8).abs() < 1.0E-15);
+        let delay = model.slant_delay(at("2022 01 01 20 40 00"), 40.0, -100.0, 90.0, 0.0);
+        assert!((delay - 1.2074361E-8).abs() < 1.0E-15);
+        // night time: constant offset, scaled by obliquity factor
+        let delay = model.slant_delay(at("2022 01 01 14 00 00"), 40.0, -100.0, 90.0, 0.0);
+        assert!((delay - 5.0E-9 * (1.0 + 16.0 * 0.03_f64.powi(3))).abs() < 1.0E-15);
+    }
+    #[test]
+    fn test_nequick_g() {
+        // Galileo NeQuick-G ICD validation set, high solar activity
+        let model = NgModel {
+            a: (236.831641, -0.39362878, 0.00402826613),
+            region: NgRegionFlags::empty(),
+        };
+        for (modip, az) in [
+            (0.0, 236.831641),
+            (20.0, 230.570372),
+            (-35.0, 255.543274),
+            (60.0, 227.715672),
+        ] {
+            assert!((model.effective_ionisation_level(modip) - az).abs() < 1.0E-6);
+        }
+        // ITU-R P.1239: Az = 63.7 + 0.728 R12 + 0.00089 R12²
+        for modip in [-60.0, 0.0, 20.0, 45.0] {
+            let az = model.effective_ionisation_level(modip);
+            let r12 = model.effective_sunspot_number(modip);
+            assert!((63.7 + 0.728 * r12 + 0.00089 * r12.powi(2) - az).abs() < 1.0E-2);
+        }
+        // default: R12 = 0
+        let model = NgModel::default();
+        assert_eq!(model.effective_ionisation_level(20.0), 63.7);
+        assert!(model.effective_sunspot_number(20.0).abs() < 0.01);
+        // clamped
+        let model = NgModel {
+            a: (500.0, 0.0, 0.0),
+            region: NgRegionFlags::empty(),
+        };
+        assert_eq!(model.effective_ionisation_level(0.0), 400.0);
+    }
+    #[test]
+    fn test_bdgim_harmonics() {
+        // all 26 harmonics are orthonormal over the sphere
+        let terms: Vec<(u8, i8)> = BDGIM_BROADCAST_TERMS.iter()
+            .chain(BDGIM_PREDICTED_TERMS.iter())
+            .copied()
+            .collect();
+        let (n_lat, n_lon) = (180, 360);
+        let d_lat = std::f64::consts::PI / n_lat as f64;
+        let d_lon = 2.0 * std::f64::consts::PI / n_lon as f64;
+        for (i, (n1, m1)) in terms.iter().enumerate() {
+            for (n2, m2) in terms.iter().skip(i) {
+                let mut sum = 0.0;
+                for j in 0..n_lat {
+                    let lat = -std::f64::consts::FRAC_PI_2 + (j as f64 + 0.5) * d_lat;
+                    for k in 0..n_lon {
+                        let lon = k as f64 * d_lon;
+                        sum += bdgim_harmonic(*n1, *m1, lat, lon)
+                            * bdgim_harmonic(*n2, *m2, lat, lon)
+                            * lat.cos() * d_lat * d_lon;
+                    }
+                }
+                let mean = sum / (4.0 * std::f64::consts::PI);
+                let expected = if (n1, m1) == (n2, m2) { 1.0 } else { 0.0 };
+                assert!((mean - expected).abs() < 1.0E-3, "({},{})x({},{}): {}", n1, m1, n2, m2, mean);
+            }
+        }
+        // P(2,0) = (3x²-1)/2, P(3,3) = 15(1-x²)^(3/2)
+        assert!((legendre(2, 0, 0.5) - (-0.125)).abs() < 1.0E-12);
+        assert!((legendre(3, 3, 0.5) - 15.0 * 0.75_f64.powf(1.5)).abs() < 1.0E-12);
+    }
+    #[test]
+    fn test_bdgim_pierce_point() {
+        // zenith: pierce point above the user
+        let (lat, lon) = bdgim_pierce_point(45.0, 10.0, 90.0, 0.0);
+        assert!((lat - 45.0).abs() < 1.0E-9);
+        assert!((lon - 10.0).abs() < 1.0E-9);
+        // intersection of the line of sight with the 400 km shell, in ECEF
+        let (r, h) = (BDGIM_EARTH_RADIUS_KM, BDGIM_SHELL_HEIGHT_KM);
+        let (user_lat, user_lon, elevation, azimuth) = (30.0_f64, 114.0_f64, 25.0_f64, 130.0_f64);
+        let (phi, lambda) = (user_lat.to_radians(), user_lon.to_radians());
+        let (e, a) = (elevation.to_radians(), azimuth.to_radians());
+        let up = [phi.cos() * lambda.cos(), phi.cos() * lambda.sin(), phi.sin()];
+        let east = [-lambda.sin(), lambda.cos(), 0.0];
+        let north = [-phi.sin() * lambda.cos(), -phi.sin() * lambda.sin(), phi.cos()];
+        let los: Vec<f64> = (0..3)
+            .map(|i| e.cos() * a.sin() * east[i] + e.cos() * a.cos() * north[i] + e.sin() * up[i])
+            .collect();
+        // |r.up + s.los| = r + h
+        let s = -r * e.sin() + ((r * e.sin()).powi(2) + (r + h).powi(2) - r * r).sqrt();
+        let p: Vec<f64> = (0..3)
+            .map(|i| r * up[i] + s * los[i])
+            .collect();
+        let expected_lat = (p[2] / (r + h)).asin().to_degrees();
+        let expected_lon = p[1].atan2(p[0]).to_degrees();
+        let (lat, lon) = bdgim_pierce_point(user_lat, user_lon, elevation, azimuth);
+        assert!((lat - expected_lat).abs() < 1.0E-6, "{} {}", lat, expected_lat);
+        assert!((lon - expected_lon).abs() < 1.0E-6, "{} {}", lon, expected_lon);
+    }
+    #[test]
+    fn test_bdgim_geomagnetic() {
+        // geomagnetic pole
+        let (lat, _) = bdgim_geomagnetic(
+            BDGIM_GEOMAGNETIC_POLE.0.to_radians(),
+            BDGIM_GEOMAGNETIC_POLE.1.to_radians());
+        assert!((lat - std::f64::consts::FRAC_PI_2).abs() < 1.0E-9);
+        // geographic pole lies on the antimeridian of the pole,
+        // at the pole colatitude
+        let (lat, lon) = bdgim_geomagnetic(std::f64::consts::FRAC_PI_2, 0.0);
+        assert!((lat.to_degrees() - 80.27).abs() < 1.0E-9);
+        assert!((lon.abs() - std::f64::consts::PI).abs() < 1.0E-9);
+        // sun-fixed frame rotates with the mean sun
+        let at = |date: &str| epoch::Epoch {
+            date: epoch::str2date(date).unwrap(),
+            flag: epoch::EpochFlag::Ok,
+        };
+        let (_, lon) = bdgim_sun_fixed_geomagnetic(at("2022 01 01 12 00 00"), 0.0, 0.0);
+        assert!(lon.abs() < 1.0E-9);
+        let (lat_12, lon_12) = bdgim_sun_fixed_geomagnetic(at("2022 01 01 12 00 00"), 0.3, 1.0);
+        let (lat_18, lon_18) = bdgim_sun_fixed_geomagnetic(at("2022 01 01 18 00 00"), 0.3, 1.0);
+        assert_eq!(lat_12, lat_18);
+        assert!(lon_12 != lon_18);
+    }
+    #[test]
+    fn test_bdgim() {
+        let at = |date: &str| epoch::Epoch {
+            date: epoch::str2date(date).unwrap(),
+            flag: epoch::EpochFlag::Ok,
+        };
+        let reference = at("2022 01 01 00 00 00");
+        // predicted weights: a0 + sum(a_k) at reference,
+        // sign flip of the daily term after half a day
+        let mut predicted = BdPredictedModel {
+            reference,
+            ..Default::default()
+        };
+        predicted.coefficients[0].0 = 1.0;
+        predicted.coefficients[0].1[0] = (2.0, 3.0);
+        predicted.coefficients[0].1[7] = (0.5, 0.0);
+        let weights = predicted.weights(reference);
+        assert!((weights[0] - 3.5).abs() < 1.0E-12);
+        assert!(weights[1..].iter().all(|w| *w == 0.0));
+        let weights = predicted.weights(at("2022 01 01 12 00 00"));
+        let yearly = 0.5 * (std::f64::consts::PI / 365.25).cos();
+        assert!((weights[0] - (1.0 - 2.0 + yearly)).abs() < 1.0E-9);
+        let weights = predicted.weights(at("2022 01 01 06 00 00"));
+        let yearly = 0.5 * (std::f64::consts::PI / 2.0 / 365.25).cos();
+        assert!((weights[0] - (1.0 + 3.0 + yearly)).abs() < 1.0E-9);
+        // A0: weight times normalized P(3,0) = sqrt(7)(5x³-3x)/2
+        let (lat, lon) = (0.4_f64, 1.1_f64);
+        let x = lat.sin();
+        let p30 = 7.0_f64.sqrt() * (5.0 * x.powi(3) - 3.0 * x) / 2.0;
+        assert!((predicted.background_vtec(reference, lat, lon) - 3.5 * p30).abs() < 1.0E-9);
+        // broadcast: constant plus sqrt(3) sin(lat) term
+        let mut model = BdModel::default();
+        model.alpha.0 = 10.0;
+        model.alpha.1 = 2.0;
+        let expected = 10.0 + 2.0 * 3.0_f64.sqrt() * lat.sin();
+        assert!((model.broadcast_vtec(lat, lon) - expected).abs() < 1.0E-12);
+        // vtec is clipped to 0
+        let mut model = BdModel::default();
+        model.alpha.0 = -5.0;
+        let empty = BdPredictedModel::default();
+        assert_eq!(model.vtec(reference, 30.0, 114.0, &empty), 0.0);
+        // zenith slant delay: 10 TECu on B1C
+        let mut model = BdModel::default();
+        model.alpha.0 = 10.0;
+        let delay = model.slant_delay(reference, 30.0, 114.0, 90.0, 0.0, &empty);
+        assert!((delay * crate::channel::SPEED_OF_LIGHT_M_S - 1.6229).abs() < 1.0E-3);
+        // obliquity: lower elevations, longer delays
+        let low = model.slant_delay(reference, 30.0, 114.0, 10.0, 0.0, &empty);
+        let r = BDGIM_EARTH_RADIUS_KM / (BDGIM_EARTH_RADIUS_KM + BDGIM_SHELL_HEIGHT_KM);
+        let mapping = 1.0 / (1.0 - (r * 10.0_f64.to_radians().cos()).powi(2)).sqrt();
+        assert!((low / delay - mapping).abs() < 1.0E-9);
+        // 1 TECu on L1
+        assert!((tec_to_delay(1.0, 1575.42E6) - 0.1624).abs() < 1.0E-4);
+    }
+}
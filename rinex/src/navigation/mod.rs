@@ -4,3 +4,15 @@ pub mod database;
 pub mod ionmessage;
 pub mod stomessage;
 pub mod eopmessage;
+pub mod almmessage;
+pub mod orbits;
+pub mod corrections;
+pub mod health;
+pub mod selection;
+pub mod ephemeris;
+
+pub use record::{FrameClass, MsgType, Frame};
+pub use corrections::{IonosphericCorrection, TimeSystemCorrection};
+pub use health::healthy_sv;
+pub use selection::ephemeris_at;
+pub use ephemeris::Ephemeris;
@@ -0,0 +1,416 @@
+//! Satellite orbits, resolved from broadcast Keplerian ephemeris,
+//! or GLONASS state vectors, and related sky view (elevation, azimuth) calculations
+use crate::sv;
+use crate::epoch;
+use crate::constellation::Constellation;
+use super::record::ComplexEnum;
+use std::collections::HashMap;
+
+/// GPS & QZSS earth gravitational constant [m³.s⁻²]
+pub const GPS_MU: f64 = 3.986005E14;
+/// Galileo & BeiDou earth gravitational constant [m³.s⁻²]
+pub const GAL_MU: f64 = 3.986004418E14;
+/// GPS, QZSS & Galileo earth rotation rate [rad.s⁻¹]
+pub const GPS_OMEGA_E: f64 = 7.2921151467E-5;
+/// BeiDou earth rotation rate [rad.s⁻¹]
+pub const BDS_OMEGA_E: f64 = 7.292115E-5;
+
+/// PZ-90.11 earth gravitational constant [m³.s⁻²]
+pub const GLO_MU: f64 = 3.986004418E14;
+/// PZ-90.11 earth semi major axis [m]
+pub const GLO_AE: f64 = 6378136.0;
+/// PZ-90.11 second zonal harmonic of the geopotential
+pub const GLO_J2: f64 = 1.0826257E-3;
+/// PZ-90.11 earth rotation rate [rad.s⁻¹]
+pub const GLO_OMEGA_E: f64 = 7.292115E-5;
+
+/// GLONASS state vector integration step [s]
+const GLO_INTEGRATION_STEP: f64 = 30.0;
+
+/// Maximal distance between an ephemeris and the epoch
+/// to be resolved, for this ephemeris to be used [s]
+pub const MAX_EPHEMERIS_AGE: i64 = 4 * 3600;
+
+/// Time step used to evaluate velocities [s]
+const VELOCITY_STEP: f64 = 0.5;
+
+/// Seconds in a week
+const WEEK_SECONDS: f64 = 604800.0;
+
+/// Keplerian elements and perturbations,
+/// as broadcast by GPS, QZSS, Galileo and BeiDou vehicules
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct Kepler {
+    /// Square root of semi major axis [m^1/2]
+    pub sqrta: f64,
+    /// Eccentricity
+    pub e: f64,
+    /// Inclination at reference time [rad]
+    pub i0: f64,
+    /// Longitude of ascending node at week epoch [rad]
+    pub omega0: f64,
+    /// Argument of perigee [rad]
+    pub omega: f64,
+    /// Mean anomaly at reference time [rad]
+    pub m0: f64,
+    /// Mean motion difference [rad.s⁻¹]
+    pub delta_n: f64,
+    /// Rate of inclination [rad.s⁻¹]
+    pub idot: f64,
+    /// Rate of right ascension [rad.s⁻¹]
+    pub omega_dot: f64,
+    /// Argument of latitude harmonic corrections [rad]
+    pub cuc: f64,
+    /// Argument of latitude harmonic corrections [rad]
+    pub cus: f64,
+    /// Orbit radius harmonic corrections [m]
+    pub crc: f64,
+    /// Orbit radius harmonic corrections [m]
+    pub crs: f64,
+    /// Inclination harmonic corrections [rad]
+    pub cic: f64,
+    /// Inclination harmonic corrections [rad]
+    pub cis: f64,
+    /// Reference time, in seconds of week
+    pub toe: f64,
+}
+
+impl Kepler {
+    /// Builds Keplerian elements from an Ephemeris frame content,
+    /// returns None if one element is missing
+    pub fn from_ephemeris (map: &HashMap<String, ComplexEnum>) -> Option<Self> {
+        let get = |key: &str| -> Option<f64> {
+            map.get(key)?.as_f64()
+        };
+        Some(Self {
+            sqrta: get("sqrta")?,
+            e: get("e")?,
+            i0: get("i0")?,
+            omega0: get("omega0")?,
+            omega: get("omega")?,
+            m0: get("m0")?,
+            delta_n: get("deltaN")?,
+            idot: get("idot")?,
+            omega_dot: get("omegaDot")?,
+            cuc: get("cuc")?,
+            cus: get("cus")?,
+            crc: get("crc")?,
+            crs: get("crs")?,
+            cic: get("cic")?,
+            cis: get("cis")?,
+            toe: get("toe")?,
+        })
+    }
+
+    /// Resolves ECEF position [m] of given vehicule at given epoch.
+    /// `epoch` is expressed in the vehicule constellation time scale.
+    /// Returns None if this constellation is not described by Keplerian elements
+    pub fn position (&self, sv: &sv::Sv, epoch: &epoch::Epoch) -> Option<(f64, f64, f64)> {
+        let tk = time_from_ephemeris(sv.constellation, epoch, self.toe)?;
+        self.position_at(sv, tk)
+    }
+
+    /// Resolves ECEF velocity [m.s⁻¹] of given vehicule at given epoch.
+    /// `epoch` is expressed in the vehicule constellation time scale.
+    pub fn velocity (&self, sv: &sv::Sv, epoch: &epoch::Epoch) -> Option<(f64, f64, f64)> {
+        let tk = time_from_ephemeris(sv.constellation, epoch, self.toe)?;
+        let (x0, y0, z0) = self.position_at(sv, tk - VELOCITY_STEP)?;
+        let (x1, y1, z1) = self.position_at(sv, tk + VELOCITY_STEP)?;
+        let dt = 2.0 * VELOCITY_STEP;
+        Some(((x1 - x0) / dt, (y1 - y0) / dt, (z1 - z0) / dt))
+    }
+
+    /// Resolves ECEF position [m], `tk` seconds after reference time
+    fn position_at (&self, sv: &sv::Sv, tk: f64) -> Option<(f64, f64, f64)> {
+        let (mu, omega_e) = match sv.constellation {
+            Constellation::GPS | Constellation::QZSS => (GPS_MU, GPS_OMEGA_E),
+            Constellation::Galileo => (GAL_MU, GPS_OMEGA_E),
+            Constellation::BeiDou => (GAL_MU, BDS_OMEGA_E),
+            _ => return None,
+        };
+        let a = self.sqrta.powi(2);
+        let n = (mu / a.powi(3)).sqrt() + self.delta_n;
+        // Kepler's equation
+        let mk = self.m0 + n * tk;
+        let mut ek = mk;
+        for _ in 0..30 {
+            let next = mk + self.e * ek.sin();
+            if (next - ek).abs() < 1.0E-14 {
+                ek = next;
+                break
+            }
+            ek = next
+        }
+        let vk = ((1.0 - self.e.powi(2)).sqrt() * ek.sin())
+            .atan2(ek.cos() - self.e);
+        // argument of latitude, radius & inclination
+        let phik = vk + self.omega;
+        let (sin2phi, cos2phi) = (2.0 * phik).sin_cos();
+        let uk = phik + self.cus * sin2phi + self.cuc * cos2phi;
+        let rk = a * (1.0 - self.e * ek.cos())
+            + self.crs * sin2phi + self.crc * cos2phi;
+        let ik = self.i0 + self.idot * tk
+            + self.cis * sin2phi + self.cic * cos2phi;
+        // position in orbital plane
+        let (xk, yk) = (rk * uk.cos(), rk * uk.sin());
+        if sv.constellation == Constellation::BeiDou && is_beidou_geo(sv) {
+            // GEO vehicules: resolved in a custom inertial frame first
+            let omegak = self.omega0 + self.omega_dot * tk - omega_e * self.toe;
+            let x = xk * omegak.cos() - yk * ik.cos() * omegak.sin();
+            let y = xk * omegak.sin() + yk * ik.cos() * omegak.cos();
+            let z = yk * ik.sin();
+            // Rz(ωe.tk) x Rx(-5°)
+            let (sin_f, cos_f) = (-5.0_f64).to_radians().sin_cos();
+            let (sin_r, cos_r) = (omega_e * tk).sin_cos();
+            let y_rx = y * cos_f + z * sin_f;
+            let z_rx = -y * sin_f + z * cos_f;
+            Some((
+                x * cos_r + y_rx * sin_r,
+                -x * sin_r + y_rx * cos_r,
+                z_rx))
+        } else {
+            let omegak = self.omega0 + (self.omega_dot - omega_e) * tk - omega_e * self.toe;
+            Some((
+                xk * omegak.cos() - yk * ik.cos() * omegak.sin(),
+                xk * omegak.sin() + yk * ik.cos() * omegak.cos(),
+                yk * ik.sin()))
+        }
+    }
+}
+
+/// GLONASS state vector: position [m], velocity [m.s⁻¹]
+type GloState = [f64; 6];
+
+/// GLONASS equations of motion, in the rotating PZ-90.11 frame:
+/// central term, J2 zonal harmonic, centrifugal and Coriolis terms,
+/// and broadcast (constant) luni-solar acceleration [m.s⁻²]
+fn glonass_derivatives (state: &GloState, acceleration: (f64, f64, f64)) -> GloState {
+    let [x, y, z, vx, vy, vz] = *state;
+    let r2 = x.powi(2) + y.powi(2) + z.powi(2);
+    let r = r2.sqrt();
+    let mu = GLO_MU / (r2 * r);
+    let j2 = 1.5 * GLO_J2 * GLO_MU * GLO_AE.powi(2) / (r2.powi(2) * r);
+    let z2 = 5.0 * z.powi(2) / r2;
+    let w2 = GLO_OMEGA_E.powi(2);
+    [
+        vx,
+        vy,
+        vz,
+        -mu * x - j2 * x * (1.0 - z2) + w2 * x + 2.0 * GLO_OMEGA_E * vy + acceleration.0,
+        -mu * y - j2 * y * (1.0 - z2) + w2 * y - 2.0 * GLO_OMEGA_E * vx + acceleration.1,
+        -mu * z - j2 * z * (3.0 - z2) + acceleration.2,
+    ]
+}
+
+/// 4th order Runge Kutta integration step, of `h` seconds
+fn glonass_rk4_step (state: &GloState, acceleration: (f64, f64, f64), h: f64) -> GloState {
+    let shift = |k: &GloState, factor: f64| -> GloState {
+        let mut shifted = *state;
+        for (s, k) in shifted.iter_mut().zip(k.iter()) {
+            *s += factor * k;
+        }
+        shifted
+    };
+    let k1 = glonass_derivatives(state, acceleration);
+    let k2 = glonass_derivatives(&shift(&k1, h / 2.0), acceleration);
+    let k3 = glonass_derivatives(&shift(&k2, h / 2.0), acceleration);
+    let k4 = glonass_derivatives(&shift(&k3, h), acceleration);
+    let mut next = *state;
+    for (i, s) in next.iter_mut().enumerate() {
+        *s += h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+    }
+    next
+}
+
+/// Propagates a GLONASS broadcast state vector by `dt` seconds (possibly negative),
+/// by numerical integration of the equations of motion in PZ-90.11
+/// (GLONASS ICD, A.3.1.2), 4th order Runge Kutta.
+/// `position` [m], `velocity` [m.s⁻¹] and luni-solar `acceleration` [m.s⁻²]
+/// are expressed in ECEF PZ-90.11 frame.
+/// Returns propagated (position [m], velocity [m.s⁻¹])
+pub fn glonass_propagate (position: (f64, f64, f64), velocity: (f64, f64, f64), acceleration: (f64, f64, f64), dt: f64) -> ((f64, f64, f64), (f64, f64, f64)) {
+    let mut state: GloState = [position.0, position.1, position.2, velocity.0, velocity.1, velocity.2];
+    let steps = (dt.abs() / GLO_INTEGRATION_STEP).ceil().max(1.0);
+    let h = dt / steps;
+    for _ in 0..steps as usize {
+        state = glonass_rk4_step(&state, acceleration, h);
+    }
+    ((state[0], state[1], state[2]), (state[3], state[4], state[5]))
+}
+
+/// WGS84 semi major axis [m]
+pub const WGS84_A: f64 = 6378137.0;
+/// WGS84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// Converts ECEF coordinates [m] to WGS84 geodetic
+/// (latitude [rad], longitude [rad], altitude [m]) coordinates
+pub fn ecef2geodetic (x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let p = (x.powi(2) + y.powi(2)).sqrt();
+    let lon = y.atan2(x);
+    let mut lat = z.atan2(p * (1.0 - e2));
+    let mut alt = 0.0_f64;
+    for _ in 0..10 {
+        let n = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        alt = p / lat.cos() - n;
+        lat = z.atan2(p * (1.0 - e2 * n / (n + alt)));
+    }
+    (lat, lon, alt)
+}
+
+/// Returns (elevation, azimuth) angles [°] of a vehicule located
+/// at ECEF `sv_position` [m], seen from ECEF `ref_position` [m].
+/// Azimuth is counted clockwise from north, within [0, 360[
+pub fn elevation_azimuth (sv_position: (f64, f64, f64), ref_position: (f64, f64, f64)) -> (f64, f64) {
+    let (lat, lon, _) = ecef2geodetic(ref_position.0, ref_position.1, ref_position.2);
+    let (dx, dy, dz) = (
+        sv_position.0 - ref_position.0,
+        sv_position.1 - ref_position.1,
+        sv_position.2 - ref_position.2);
+    let (sin_lat, cos_lat) = lat.sin_cos();
+    let (sin_lon, cos_lon) = lon.sin_cos();
+    // local ENU frame
+    let e = -sin_lon * dx + cos_lon * dy;
+    let n = -sin_lat * cos_lon * dx - sin_lat * sin_lon * dy + cos_lat * dz;
+    let u = cos_lat * cos_lon * dx + cos_lat * sin_lon * dy + sin_lat * dz;
+    let elevation = u.atan2((e.powi(2) + n.powi(2)).sqrt()).to_degrees();
+    let azimuth = e.atan2(n).to_degrees().rem_euclid(360.0);
+    (elevation, azimuth)
+}
+
+/// Returns true if given BeiDou vehicule is a geostationary vehicule
+fn is_beidou_geo (sv: &sv::Sv) -> bool {
+    sv.prn <= 5 || sv.prn >= 59
+}
+
+/// Returns time elapsed since reference time `toe`, in seconds,
+/// taking week crossovers into account
+pub(crate) fn time_from_ephemeris (constellation: Constellation, epoch: &epoch::Epoch, toe: f64) -> Option<f64> {
+    let origin = match constellation {
+        Constellation::GPS | Constellation::QZSS | Constellation::Galileo => {
+            chrono::NaiveDate::from_ymd(1980, 1, 6).and_hms(0, 0, 0)
+        },
+        Constellation::BeiDou => chrono::NaiveDate::from_ymd(2006, 1, 1).and_hms(0, 0, 0),
+        _ => return None,
+    };
+    let elapsed = epoch.date - origin;
+    let seconds = elapsed.num_seconds() as f64
+        + (elapsed - chrono::Duration::seconds(elapsed.num_seconds()))
+            .num_nanoseconds()
+            .unwrap_or(0) as f64 * 1.0E-9;
+    let mut tk = seconds.rem_euclid(WEEK_SECONDS) - toe;
+    if tk > WEEK_SECONDS / 2.0 {
+        tk -= WEEK_SECONDS
+    } else if tk < -WEEK_SECONDS / 2.0 {
+        tk += WEEK_SECONDS
+    }
+    Some(tk)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    fn norm (v: (f64, f64, f64)) -> f64 {
+        (v.0.powi(2) + v.1.powi(2) + v.2.powi(2)).sqrt()
+    }
+    #[test]
+    fn test_kepler_solver() {
+        // E01 broadcast on 2021/01/01
+        let kepler = Kepler {
+            sqrta: 5.440618339539e+03,
+            e: 2.254303544760e-04,
+            i0: 9.803093573396e-01,
+            omega0: -3.129895760424e+00,
+            omega: -2.802235833856e-01,
+            m0: 1.607129644018e+00,
+            delta_n: 2.666182485774e-09,
+            idot: -6.057395171966e-10,
+            omega_dot: -5.121999066106e-09,
+            cuc: 2.320855855942e-06,
+            cus: 1.223385334015e-05,
+            crc: 8.578125000000e+01,
+            crs: 4.915625000000e+01,
+            cic: -3.911554813385e-08,
+            cis: -1.676380634308e-08,
+            toe: 4.320000000000e+05,
+        };
+        let e01 = sv::Sv::from_str("E01").unwrap();
+        let toe = epoch::Epoch::new(
+            epoch::str2date("2021 01 01 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        // reference: IS-GPS-200 / Galileo OS SIS ICD algorithm, evaluated at toe
+        let (x, y, z) = kepler.position(&e01, &toe).unwrap();
+        assert!((x - -8302711.47).abs() < 1.0);
+        assert!((y - -15420750.60).abs() < 1.0);
+        assert!((z - 23863280.54).abs() < 1.0);
+        assert!((norm((x, y, z)) - 29600519.0).abs() < 1.0);
+        // velocity is consistent with motion
+        let v = kepler.velocity(&e01, &toe).unwrap();
+        let later = epoch::Epoch::new(
+            toe.date + chrono::Duration::seconds(1),
+            epoch::EpochFlag::Ok);
+        let (x1, y1, z1) = kepler.position(&e01, &later).unwrap();
+        assert!((x1 - x - v.0).abs() < 1.0);
+        assert!((y1 - y - v.1).abs() < 1.0);
+        assert!((z1 - z - v.2).abs() < 1.0);
+        // Glonass is not described by Keplerian elements
+        let r01 = sv::Sv::from_str("R01").unwrap();
+        assert_eq!(kepler.position(&r01, &toe), None);
+    }
+    #[test]
+    fn test_glonass_propagation() {
+        // GLONASS ICD example, A.3.1.2: 10 minute propagation
+        let position = (7003.008789E3, -12206.626953E3, 21280.765625E3);
+        let velocity = (0.7835417E3, 2.8042530E3, 1.3525150E3);
+        let acceleration = (0.0, 1.7E-6, -5.41E-6);
+        let ((x, y, z), (vx, vy, vz)) = glonass_propagate(position, velocity, acceleration, 600.0);
+        assert!((x - 7523174.851).abs() < 1.0);
+        assert!((y - -10506961.864).abs() < 1.0);
+        assert!((z - 21999238.892).abs() < 1.0);
+        assert!((vx - 950.126).abs() < 1.0E-3);
+        assert!((vy - 2855.688).abs() < 1.0E-3);
+        assert!((vz - 1040.678).abs() < 1.0E-3);
+        // backward propagation is reversible
+        let (p0, v0) = glonass_propagate((x, y, z), (vx, vy, vz), acceleration, -600.0);
+        assert!((p0.0 - position.0).abs() < 1.0E-3);
+        assert!((p0.1 - position.1).abs() < 1.0E-3);
+        assert!((p0.2 - position.2).abs() < 1.0E-3);
+        assert!((v0.0 - velocity.0).abs() < 1.0E-6);
+        // null propagation
+        let (p, v) = glonass_propagate(position, velocity, acceleration, 0.0);
+        assert_eq!(p, position);
+        assert_eq!(v, velocity);
+    }
+    #[test]
+    fn test_elevation_azimuth() {
+        let reference = (WGS84_A, 0.0, 0.0); // equator, greenwich
+        let (el, _) = elevation_azimuth((WGS84_A + 20.0E6, 0.0, 0.0), reference);
+        assert!((el - 90.0).abs() < 1.0E-6);
+        let (el, az) = elevation_azimuth((WGS84_A, 0.0, 1.0E6), reference);
+        assert!(el.abs() < 1.0E-6);
+        assert!(az.abs() < 1.0E-6); // north
+        let (el, az) = elevation_azimuth((WGS84_A, 1.0E6, 0.0), reference);
+        assert!(el.abs() < 1.0E-6);
+        assert!((az - 90.0).abs() < 1.0E-6); // east
+        let (el, az) = elevation_azimuth((WGS84_A - 1.0E6, -1.0E6, 0.0), reference);
+        assert!((el + 45.0).abs() < 1.0E-6); // below horizon
+        assert!((az - 270.0).abs() < 1.0E-6); // west
+        // geodetic coordinates
+        let (lat, lon, alt) = ecef2geodetic(4027881.0, 306998.0, 4919499.0);
+        assert!((lat.to_degrees() - 50.7983).abs() < 1.0E-3);
+        assert!((lon.to_degrees() - 4.3585).abs() < 1.0E-3);
+        assert!((alt - 158.0).abs() < 5.0);
+    }
+    #[test]
+    fn test_week_crossover() {
+        let toe = 604800.0 - 3600.0;
+        let epoch = epoch::Epoch::new(
+            epoch::str2date("2021 01 03 00 30 00").unwrap(), // new week
+            epoch::EpochFlag::Ok);
+        let tk = time_from_ephemeris(Constellation::GPS, &epoch, toe);
+        assert_eq!(tk, Some(5400.0));
+    }
+}
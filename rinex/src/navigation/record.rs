@@ -4,6 +4,7 @@ use thiserror::Error;
 use std::str::FromStr;
 use strum_macros::EnumString;
 use std::collections::{BTreeMap, HashMap};
+use chrono::{Datelike, Timelike};
 
 use crate::epoch;
 use crate::header;
@@ -17,11 +18,13 @@ use crate::navigation::database::NAV_MESSAGES;
 use crate::navigation::ionmessage;
 use crate::navigation::stomessage;
 use crate::navigation::eopmessage;
+use crate::navigation::almmessage;
+use crate::navigation::ephemeris;
 
 /// `ComplexEnum` is record payload 
 #[derive(Clone, Debug)]
 #[derive(PartialEq, PartialOrd)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum ComplexEnum {
     U8(u8),
     Str(String), 
@@ -101,7 +104,7 @@ impl ComplexEnum {
 #[derive(PartialEq, PartialOrd)]
 #[derive(Eq, Ord)]
 #[derive(EnumString)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum FrameClass {
     #[strum(serialize = "EPH", deserialize = "EPH")]
     Ephemeris,
@@ -111,6 +114,8 @@ pub enum FrameClass {
     EarthOrientation,
     #[strum(serialize = "ION", deserialize = "ION")]
     IonosphericModel,
+    #[strum(serialize = "ALM", deserialize = "ALM")]
+    Almanac,
 }
 
 impl Default for FrameClass {
@@ -126,6 +131,7 @@ impl std::fmt::Display for FrameClass {
             Self::SystemTimeOffset => f.write_str("STO"),
             Self::EarthOrientation => f.write_str("EOP"),
             Self::IonosphericModel => f.write_str("ION"),
+            Self::Almanac => f.write_str("ALM"),
         }
     }
 }
@@ -135,7 +141,7 @@ impl std::fmt::Display for FrameClass {
 #[derive(PartialEq, PartialOrd)]
 #[derive(Eq, Ord)]
 #[derive(EnumString)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum MsgType {
     /// Legacy NAV
     LNAV,
@@ -143,6 +149,10 @@ pub enum MsgType {
     FDMA,
     /// IFNV,
     IFNV,
+    /// Galileo I/NAV
+    INAV,
+    /// Galileo F/NAV
+    FNAV,
     /// D1
     D1,
     /// D2
@@ -167,6 +177,8 @@ impl std::fmt::Display for MsgType {
             Self::LNAV => f.write_str("LNAV"),
             Self::FDMA => f.write_str("FDMA"),
             Self::IFNV => f.write_str("IFNV"),
+            Self::INAV => f.write_str("INAV"),
+            Self::FNAV => f.write_str("FNAV"),
             Self::D1 => f.write_str("D1"),
             Self::D2 => f.write_str("D2"),
             Self::D1D2 => f.write_str("D1D2"),
@@ -180,75 +192,131 @@ impl std::fmt::Display for MsgType {
 #[derive(Debug, Clone)]
 #[derive(PartialEq)]
 #[derive(EnumString)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum Frame {
     /// Ephemeris for a given Vehicule `Sv`,
     /// with vehicule internal clock bias, clock drift and clock drift rate.
-    /// Rest of data is constellation dependent, see
+    /// Rest of data is constellation dependent: typed content,
+    /// and the fields it does not interpret, as described by
     /// RINEX specifications or db/NAV/navigation.json.
-    Eph(MsgType, Sv, f64, f64, f64, HashMap<String, ComplexEnum>),
-    /// Earth Orientation Parameters message 
-    Eop(eopmessage::Message),
-    /// Ionospheric Model Message
-    Ion(ionmessage::Message),
-    /// System Time Offset Message
-    Sto(stomessage::Message),
+    Eph(MsgType, Sv, f64, f64, f64, ephemeris::Ephemeris, HashMap<String, ComplexEnum>),
+    /// Earth Orientation Parameters message,
+    /// broadcasted by given vehicule
+    Eop(MsgType, Sv, eopmessage::Message),
+    /// Ionospheric Model Message,
+    /// broadcasted by given vehicule
+    Ion(MsgType, Sv, ionmessage::Message),
+    /// System Time Offset Message,
+    /// broadcasted by given vehicule
+    Sto(MsgType, Sv, stomessage::Message),
+    /// Almanac Message, describing given vehicule
+    Alm(MsgType, Sv, almmessage::Message),
 }
 
 impl Frame {
-    /// Unwraps self as Ephemeris frame
-    pub fn as_eph (&self) -> Option<(MsgType, Sv, f64, f64, f64, &HashMap<String, ComplexEnum>)> {
+    /// Returns class of this frame
+    pub fn class (&self) -> FrameClass {
         match self {
-            Self::Eph(msg, sv, clk, clk_dr, clk_drr, map) => Some((*msg, *sv, *clk, *clk_dr, *clk_drr, map)),
+            Self::Eph(_, _, _, _, _, _, _) => FrameClass::Ephemeris,
+            Self::Eop(_, _, _) => FrameClass::EarthOrientation,
+            Self::Ion(_, _, _) => FrameClass::IonosphericModel,
+            Self::Sto(_, _, _) => FrameClass::SystemTimeOffset,
+            Self::Alm(_, _, _) => FrameClass::Almanac,
+        }
+    }
+    /// Returns vehicule that broadcast this frame
+    pub fn sv (&self) -> Sv {
+        match self {
+            Self::Eph(_, sv, _, _, _, _, _)
+            | Self::Eop(_, sv, _)
+            | Self::Ion(_, sv, _)
+            | Self::Sto(_, sv, _)
+            | Self::Alm(_, sv, _) => *sv,
+        }
+    }
+    /// Unwraps self as Ephemeris frame, with its typed content
+    pub fn as_eph (&self) -> Option<(MsgType, Sv, f64, f64, f64, &ephemeris::Ephemeris)> {
+        match self {
+            Self::Eph(msg, sv, clk, clk_dr, clk_drr, eph, _) => Some((*msg, *sv, *clk, *clk_dr, *clk_drr, eph)),
+            _ => None,
+        }
+    }
+    /// Unwraps Ephemeris fields that are not interpreted by [ephemeris::Ephemeris],
+    /// as described by RINEX specifications or db/NAV/navigation.json
+    pub fn as_eph_raw (&self) -> Option<&HashMap<String, ComplexEnum>> {
+        match self {
+            Self::Eph(_, _, _, _, _, _, raw) => Some(raw),
+            _ => None,
+        }
+    }
+    /// Returns Ephemeris field, by its RINEX layout name (db/NAV/navigation.json),
+    /// whether it is interpreted or not
+    pub fn eph_field (&self, key: &str) -> Option<ComplexEnum> {
+        match self {
+            Self::Eph(_, _, _, _, _, eph, raw) => ephemeris_field(eph, raw, key),
             _ => None,
         }
     }
     /// Unwraps self as mutable Ephemeris frame reference
-    pub fn as_mut_eph (&mut self) -> Option<(MsgType, Sv, f64, f64, f64, &HashMap<String, ComplexEnum>)> {
+    pub fn as_mut_eph (&mut self) -> Option<(MsgType, Sv, f64, f64, f64, &ephemeris::Ephemeris)> {
         match self {
-            Self::Eph(msg, sv, clk, clk_dr, clk_drr, map) => Some((*msg, *sv, *clk, *clk_dr, *clk_drr, map)),
+            Self::Eph(msg, sv, clk, clk_dr, clk_drr, eph, _) => Some((*msg, *sv, *clk, *clk_dr, *clk_drr, eph)),
             _ => None,
         }
     }
     /// Unwraps self as Ionospheric Model frame
     pub fn as_ion (&self) -> Option<&ionmessage::Message> {
         match self {
-            Self::Ion(fr) => Some(fr),
+            Self::Ion(_, _, fr) => Some(fr),
             _ => None,
         }
     }
     /// Unwraps self as mutable Ionospheric Model frame reference
     pub fn as_mut_ion (&mut self) -> Option<&mut ionmessage::Message> {
         match self {
-            Self::Ion(fr) => Some(fr),
+            Self::Ion(_, _, fr) => Some(fr),
             _ => None,
         }
     }
     /// Unwraps self as Earth Orientation frame
     pub fn as_eop (&self) -> Option<&eopmessage::Message> {
         match self {
-            Self::Eop(fr) => Some(fr),
+            Self::Eop(_, _, fr) => Some(fr),
             _ => None,
         }
     }
     /// Unwraps self as Mutable Earth Orientation frame reference
     pub fn as_mut_eop (&mut self) -> Option<&mut eopmessage::Message> {
         match self {
-            Self::Eop(fr) => Some(fr),
+            Self::Eop(_, _, fr) => Some(fr),
             _ => None,
         }
     }
     /// Unwraps self as System Time Offset frame
     pub fn as_sto (&self) -> Option<&stomessage::Message> {
         match self {
-            Self::Sto(fr) => Some(fr),
+            Self::Sto(_, _, fr) => Some(fr),
             _ => None,
         }
     }
     /// Unwraps self as mutable System Time Offset frame reference
     pub fn as_mut_sto (&mut self) -> Option<&mut stomessage::Message> {
         match self {
-            Self::Sto(fr) => Some(fr),
+            Self::Sto(_, _, fr) => Some(fr),
+            _ => None,
+        }
+    }
+    /// Unwraps self as Almanac frame
+    pub fn as_alm (&self) -> Option<&almmessage::Message> {
+        match self {
+            Self::Alm(_, _, fr) => Some(fr),
+            _ => None,
+        }
+    }
+    /// Unwraps self as mutable Almanac frame reference
+    pub fn as_mut_alm (&mut self) -> Option<&mut almmessage::Message> {
+        match self {
+            Self::Alm(_, _, fr) => Some(fr),
             _ => None,
         }
     }
@@ -321,6 +389,8 @@ pub enum Error {
     IonMessageError(#[from] ionmessage::Error),
     #[error("failed to parse EOP message")]
     EopMessageError(#[from] eopmessage::Error),
+    #[error("failed to parse ALM message")]
+    AlmMessageError(#[from] almmessage::Error),
 }
 
 /// Builds `Record` entry for `NavigationData`
@@ -361,7 +431,8 @@ fn build_modern_record_entry (content: &str) ->
             
             let (svnn, rem) = line.split_at(4);
             let sv = Sv::from_str(svnn.trim())?;
-            let (epoch, rem) = rem.split_at(20);
+            // "YYYY MM DD HH MM SS" (19), clock bias follows without blank
+            let (epoch, rem) = rem.split_at(19);
             let epoch = epoch::Epoch {
                 date: epoch::str2date(epoch.trim())?,
                 flag: epoch::EpochFlag::Ok,
@@ -372,11 +443,13 @@ fn build_modern_record_entry (content: &str) ->
             let clk = f64::from_str(clk_bias.replace("D","E").trim())?;
             let clk_dr = f64::from_str(clk_dr.replace("D","E").trim())?;
             let clk_drr = f64::from_str(clk_drr.replace("D","E").trim())?;
-            let map = parse_complex_map(
-                Version { major: 4, minor: 0 },
+            let mut map = parse_complex_map(
+                modern_revision(sv.constellation, msg_type),
                 sv.constellation,
                 lines)?;
-            (epoch, Frame::Eph(msg_type, sv, clk, clk_dr, clk_drr, map))
+            let eph = ephemeris::Ephemeris::from_orbits(&sv, &mut map)
+                .ok_or(Error::DataBaseRevisionError)?;
+            (epoch, Frame::Eph(msg_type, sv, clk, clk_dr, clk_drr, eph, map))
         },
         FrameClass::SystemTimeOffset => {
             let line = match lines.next() {
@@ -384,8 +457,9 @@ fn build_modern_record_entry (content: &str) ->
                 _ => return Err(Error::MissingData),
             };
             
+            // epoch, system time, then UTC(k) identifier
             let (epoch, rem) = line.split_at(23);
-            let (system, _) = rem.split_at(5);
+            let (system, utc) = rem.split_at(5);
             let epoch = Epoch {
                 date: epoch::str2date(epoch.trim())?,
                 flag: epoch::EpochFlag::Ok,
@@ -394,10 +468,10 @@ fn build_modern_record_entry (content: &str) ->
                 Some(l) => l,
                 _ => return Err(Error::MissingData),
             };
+            // t_tm and polynomial terms: a2 ends the line
             let (time, rem) = line.split_at(23);
             let (a0, rem) = rem.split_at(19);
-            let (a1, rem) = rem.split_at(19);
-            let (a2, rem) = rem.split_at(19);
+            let (a1, a2) = rem.split_at(19);
 
             let t_tm = f64::from_str(time.trim())?;
             let msg = stomessage::Message {
@@ -408,13 +482,17 @@ fn build_modern_record_entry (content: &str) ->
                     f64::from_str(a1.trim()).unwrap_or(0.0_f64),
                     f64::from_str(a2.trim()).unwrap_or(0.0_f64),
                 ),
-                utc: rem.trim().to_string(),
+                utc: utc.trim().to_string(),
             };
-            (epoch, Frame::Sto(msg))
+            (epoch, Frame::Sto(msg_type, sv, msg))
         },
         FrameClass::EarthOrientation => {
             let (epoch, msg) = eopmessage::Message::parse(lines)?;
-            (epoch, Frame::Eop(msg))
+            (epoch, Frame::Eop(msg_type, sv, msg))
+        },
+        FrameClass::Almanac => {
+            let (epoch, msg) = almmessage::Message::parse(lines)?;
+            (epoch, Frame::Alm(msg_type, sv, msg))
         },
         FrameClass::IonosphericModel => {
             let (epoch, msg): (epoch::Epoch, ionmessage::Message) = match msg_type {
@@ -439,7 +517,7 @@ fn build_modern_record_entry (content: &str) ->
                     (epoch, ionmessage::Message::KlobucharModel(model))
                 }
             };
-            (epoch, Frame::Ion(msg))
+            (epoch, Frame::Ion(msg_type, sv, msg))
         },
     };
     Ok((epoch, frame_class, fr))
@@ -455,14 +533,14 @@ fn build_v2_v3_record_entry (version: Version, constell: Constellation, content:
         _ => return Err(Error::MissingData), 
     };
     
-    let svnn_offset: usize = match version.major {
-        1|2 => 2, // Y
-        3 => 4, // XYY
+    let (svnn_offset, date_offset): (usize, usize) = match version.major {
+        1|2 => (2, 20), // Y + " YY MM DD HH MM SS.S"
+        3 => (4, 19), // XYY + "YYYY MM DD HH MM SS"
         _ => unreachable!(),
     };
 
     let (svnn, rem) = line.split_at(svnn_offset);
-    let (date, rem) = rem.split_at(20);
+    let (date, rem) = rem.split_at(date_offset);
     let (clk_bias, rem) = rem.split_at(19);
     let (clk_dr, clk_drr) = rem.split_at(19);
 
@@ -473,10 +551,7 @@ fn build_v2_v3_record_entry (version: Version, constell: Constellation, content:
                     Sv::from_str(svnn.trim())?
                 },
                 _ => {
-                    Sv {
-                        constellation: constell.clone(),
-                        prn: u8::from_str_radix(svnn.trim(), 10)?,
-                    }
+                    Sv::new(constell, u8::from_str_radix(svnn.trim(), 10)?)
                 },
             }
         },
@@ -487,8 +562,10 @@ fn build_v2_v3_record_entry (version: Version, constell: Constellation, content:
     let clk = f64::from_str(clk_bias.replace("D","E").trim())?;
     let clk_dr = f64::from_str(clk_dr.replace("D","E").trim())?;
     let clk_drr = f64::from_str(clk_drr.replace("D","E").trim())?;
-    let map = parse_complex_map(version, sv.constellation, lines)?;
-    let fr = Frame::Eph(MsgType::LNAV, sv, clk, clk_dr, clk_drr, map); // indicate legacy frame
+    let mut map = parse_complex_map(version, sv.constellation, lines)?;
+    let eph = ephemeris::Ephemeris::from_orbits(&sv, &mut map)
+        .ok_or(Error::DataBaseRevisionError)?;
+    let fr = Frame::Eph(MsgType::LNAV, sv, clk, clk_dr, clk_drr, eph, map); // indicate legacy frame
     Ok((
         epoch::Epoch::new(
             epoch::str2date(date)?,
@@ -499,17 +576,28 @@ fn build_v2_v3_record_entry (version: Version, constell: Constellation, content:
     ))
 }
 
-/// Parses constellation + revision dependent complex map 
-fn parse_complex_map (version: Version, constell: Constellation, mut lines: std::str::Lines<'_>) 
-        -> Result<HashMap<String, ComplexEnum>, Error>
-{
+/// Returns the db revision describing a modern (RINEX4) Ephemeris frame:
+/// GPS and QZSS legacy (LNAV) frames and Galileo I/NAV, F/NAV frames
+/// follow the RINEX3 layout
+fn modern_revision (constell: Constellation, msg: MsgType) -> Version {
+    match (constell, msg) {
+        (Constellation::GPS, MsgType::LNAV)
+        | (Constellation::QZSS, MsgType::LNAV)
+        | (Constellation::Galileo, MsgType::INAV)
+        | (Constellation::Galileo, MsgType::FNAV) => Version { major: 3, minor: 0 },
+        _ => Version { major: 4, minor: 0 },
+    }
+}
+
+/// Returns constellation + revision dependent items, as described in db
+fn database_items (version: Version, constell: Constellation) -> Result<Vec<&'static (&'static str, &'static str)>, Error> {
     // locate closest revision in db
     let db_revision = match database::closest_revision(constell, version) {
         Some(v) => v,
         _ => return Err(Error::DataBaseRevisionError),
     };
 
-    // retrieve db items / fields to parse
+    // retrieve db items / fields
     let items :Vec<_> = NAV_MESSAGES
         .iter()
         .filter(|r| r.constellation == constell.to_3_letter_code())
@@ -525,6 +613,14 @@ fn parse_complex_map (version: Version, constell: Constellation, mut lines: std:
         })
         .flatten()
         .collect();
+    Ok(items)
+}
+
+/// Parses constellation + revision dependent complex map 
+fn parse_complex_map (version: Version, constell: Constellation, mut lines: std::str::Lines<'_>) 
+        -> Result<HashMap<String, ComplexEnum>, Error>
+{
+    let items = database_items(version, constell)?;
 
     // parse items
     let mut line = match lines.next() {
@@ -540,7 +636,7 @@ fn parse_complex_map (version: Version, constell: Constellation, mut lines: std:
             false => 19,
             true => {
                 new_line = false;
-                if version.major == 3 {
+                if version.major > 2 {
                     22+1
                 } else {
                     22
@@ -583,28 +679,286 @@ fn parse_complex_map (version: Version, constell: Constellation, mut lines: std:
 }
 
 
-/// Pushes observation record into given file writer
-pub fn to_file (header: &header::Header, record: &Record, mut writer: std::fs::File) -> std::io::Result<()> {
-    for (epoch, sv) in record.iter() {
-        let nb_sv = sv.keys().len();
-        match header.version.major {
-            1|2 => {
-                let _ = write!(writer, " {} {} ", nb_sv, epoch.date.format("%y %m %d %H %M %.6f").to_string());
-            },
-            _ => {
-                let _ = write!(writer, "> {} {} ", nb_sv, epoch.date.format("%Y %m %d %H %M %.6f").to_string());
-            }
+
+/// Identifies a frame within its class.
+/// Frames of a class are sorted by identity,
+/// and frames sharing the same identity at a given epoch are duplicates
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum FrameIdentity<'a> {
+    /// Ephemeris: identified by vehicule and message type
+    Eph(Sv, MsgType),
+    /// System time offset: identified by system time,
+    /// whatever the vehicule that broadcasted it
+    Sto(&'a str),
+    /// Earth orientation: identified by constellation and message type
+    Eop(Constellation, MsgType),
+    /// Ionospheric model: identified by constellation and message type
+    Ion(Constellation, MsgType),
+    /// Almanac: identified by vehicule and message type
+    Alm(Sv, MsgType),
+}
+
+impl Frame {
+    /// Returns identity of this frame
+    fn identity (&self) -> FrameIdentity {
+        match self {
+            Self::Eph(msg, sv, _, _, _, _, _) => FrameIdentity::Eph(*sv, *msg),
+            Self::Sto(_, _, sto) => FrameIdentity::Sto(&sto.system),
+            Self::Eop(msg, sv, _) => FrameIdentity::Eop(sv.constellation, *msg),
+            Self::Ion(msg, sv, _) => FrameIdentity::Ion(sv.constellation, *msg),
+            Self::Alm(msg, sv, _) => FrameIdentity::Alm(*sv, *msg),
         }
-        //let mut index = 1;
-        /*for (_sv, data) in sv.iter() {
-            for (_obs, data) in data.iter() {
-                let _ = write!(writer, "{}", data);
-            }
-            if (index+1)%4 == 0 {
-                let _ = write!(writer, "\n    ");
+    }
+}
+
+/// Sorts frames of a given class, in the order they are produced:
+///  - Ephemeris by vehicule, then message type
+///  - System time offsets by system time
+///  - Earth orientation and ionospheric models by constellation, then message type
+///  - Almanacs by vehicule, then message type
+///
+/// Frames sharing the same identity remain in order of appearance
+pub fn sort_frames (frames: &mut [Frame]) {
+    frames.sort_by(|a, b| a.identity().cmp(&b.identity()))
+}
+
+/// Sorts frames of a given class (see [sort_frames]) and
+/// removes duplicates: frames sharing the same identity at a given epoch
+/// (same ephemeris, same system time offset, same model..).
+/// Only first frame encountered is preserved
+pub fn dedup_frames (frames: &mut Vec<Frame>) {
+    sort_frames(frames);
+    frames.dedup_by(|a, b| a.identity() == b.identity())
+}
+
+/// Formats given value in RINEX scientific notation (19 digits),
+/// `d_exponent` selects the old fashion "D" exponent marker
+pub(crate) fn format_float (value: f64, d_exponent: bool) -> String {
+    let formatted = format!("{:.12E}", value);
+    let (mantissa, exponent) = formatted.split_once('E')
+        .unwrap();
+    let exponent = i32::from_str(exponent)
+        .unwrap();
+    format!("{:>15}{}{}{:02}",
+        mantissa,
+        if d_exponent { 'D' } else { 'E' },
+        if exponent < 0 { '-' } else { '+' },
+        exponent.abs())
+}
+
+/// Formats given payload (19 digits), blank if not provided
+fn format_complex (value: Option<&ComplexEnum>, d_exponent: bool) -> String {
+    match value {
+        Some(ComplexEnum::F64(f)) => format_float(*f, d_exponent),
+        Some(ComplexEnum::F32(f)) => format_float(*f as f64, d_exponent),
+        Some(ComplexEnum::U8(u)) => format!("{:>19X}", u),
+        Some(ComplexEnum::Str(s)) => format!("{:>19}", s),
+        None => format!("{:19}", ""),
+    }
+}
+
+/// Returns Ephemeris field described by given RINEX layout name:
+/// from the typed content when interpreted, from the raw fields otherwise
+fn ephemeris_field (eph: &ephemeris::Ephemeris, raw: &HashMap<String, ComplexEnum>, key: &str) -> Option<ComplexEnum> {
+    eph.field(key)
+        .map(ComplexEnum::F64)
+        .or_else(|| raw.get(key).cloned())
+}
+
+/// Writes an Ephemeris frame
+fn write_ephemeris (version: Version, epoch: &Epoch, frame: (MsgType, Sv, f64, f64, f64, &ephemeris::Ephemeris, &HashMap<String, ComplexEnum>), writer: &mut impl Write) -> std::io::Result<()> {
+    let (msg, sv, clk, clk_dr, clk_drr, eph, raw) = frame;
+    let d_exponent = version.major < 3;
+    let date = epoch.date;
+    match version.major {
+        1|2 => {
+            write!(writer, "{:2} {:02} {:>2} {:>2} {:>2} {:>2} {:>4.1}",
+                sv.prn,
+                date.year() % 100,
+                date.month(),
+                date.day(),
+                date.hour(),
+                date.minute(),
+                date.second() as f64 + date.nanosecond() as f64 / 1.0E9)?
+        },
+        3 => write!(writer, "{} {}", sv, date.format("%Y %m %d %H %M %S"))?,
+        _ => {
+            write!(writer, "> {} {} {}\n", FrameClass::Ephemeris, sv, msg)?;
+            write!(writer, "{} {}", sv, date.format("%Y %m %d %H %M %S"))?
+        },
+    }
+    write!(writer, "{}{}{}\n",
+        format_float(clk, d_exponent),
+        format_float(clk_dr, d_exponent),
+        format_float(clk_drr, d_exponent))?;
+    // same revision the parser would use
+    let revision = match version.major {
+        1|2|3 => version,
+        _ => modern_revision(sv.constellation, msg),
+    };
+    let items = database_items(revision, sv.constellation)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    // do not produce trailing blank fields
+    let nb_items = items
+        .iter()
+        .rposition(|(k, _)| ephemeris_field(eph, raw, k).is_some())
+        .map(|index| index + 1)
+        .unwrap_or(1)
+        .min(items.len());
+    let prefix = if d_exponent { "   " } else { "    " };
+    for (index, (k, _)) in items.iter().take(nb_items).enumerate() {
+        if index % 4 == 0 {
+            write!(writer, "{}", prefix)?
+        }
+        if k.contains("spare") {
+            write!(writer, "{}", format_float(0.0, d_exponent))?
+        } else {
+            write!(writer, "{}", format_complex(ephemeris_field(eph, raw, k).as_ref(), d_exponent))?
+        }
+        if index % 4 == 3 || index == nb_items - 1 {
+            write!(writer, "\n")?
+        }
+    }
+    Ok(())
+}
+
+/// Writes a System Time Offset frame (RINEX4)
+fn write_sto (epoch: &Epoch, frame: (MsgType, Sv, &stomessage::Message), writer: &mut impl Write) -> std::io::Result<()> {
+    let (msg, sv, sto) = frame;
+    write!(writer, "> {} {} {}\n", FrameClass::SystemTimeOffset, sv, msg)?;
+    write!(writer, "    {} {:<4}{:34}{}\n",
+        epoch.date.format("%Y %m %d %H %M %S"),
+        sto.system,
+        "",
+        sto.utc)?;
+    write!(writer, "    {}{}{}{}\n",
+        format_float(sto.t_tm as f64, false),
+        format_float(sto.a.0, false),
+        format_float(sto.a.1, false),
+        format_float(sto.a.2, false))
+}
+
+/// Writes an Earth Orientation frame (RINEX4)
+fn write_eop (epoch: &Epoch, frame: (MsgType, Sv, &eopmessage::Message), writer: &mut impl Write) -> std::io::Result<()> {
+    let (msg, sv, eop) = frame;
+    write!(writer, "> {} {} {}\n", FrameClass::EarthOrientation, sv, msg)?;
+    write!(writer, "    {}{}{}{}\n",
+        epoch.date.format("%Y %m %d %H %M %S"),
+        format_float(eop.x.0, false),
+        format_float(eop.x.1, false),
+        format_float(eop.x.2, false))?;
+    write!(writer, "{:23}{}{}{}\n",
+        "",
+        format_float(eop.y.0, false),
+        format_float(eop.y.1, false),
+        format_float(eop.y.2, false))?;
+    write!(writer, "    {}{}{}{}\n",
+        format_float(eop.t_tm as f64, false),
+        format_float(eop.dut1.0, false),
+        format_float(eop.dut1.1, false),
+        format_float(eop.dut1.2, false))
+}
+
+/// Writes an Ionospheric Model frame (RINEX4)
+fn write_ion (epoch: &Epoch, frame: (MsgType, Sv, &ionmessage::Message), writer: &mut impl Write) -> std::io::Result<()> {
+    let (msg, sv, ion) = frame;
+    write!(writer, "> {} {} {}\n", FrameClass::IonosphericModel, sv, msg)?;
+    let date = epoch.date.format("%Y %m %d %H %M %S");
+    match ion {
+        ionmessage::Message::KlobucharModel(model) => {
+            write!(writer, "    {}{}{}{}\n",
+                date,
+                format_float(model.alpha.0, false),
+                format_float(model.alpha.1, false),
+                format_float(model.alpha.2, false))?;
+            write!(writer, "    {}{}{}{}\n",
+                format_float(model.alpha.3, false),
+                format_float(model.beta.0, false),
+                format_float(model.beta.1, false),
+                format_float(model.beta.2, false))?;
+            write!(writer, "    {}{}\n",
+                format_float(model.beta.3, false),
+                format_float(model.region as u8 as f64, false))
+        },
+        ionmessage::Message::NequickGModel(model) => {
+            write!(writer, "    {}{}{}{}\n",
+                date,
+                format_float(model.a.0, false),
+                format_float(model.a.1, false),
+                format_float(model.a.2, false))?;
+            write!(writer, "    {}\n",
+                format_float(model.region.bits() as f64, false))
+        },
+        ionmessage::Message::BdgimModel(model) => {
+            write!(writer, "    {}{}{}{}\n",
+                date,
+                format_float(model.alpha.0, false),
+                format_float(model.alpha.1, false),
+                format_float(model.alpha.2, false))?;
+            write!(writer, "    {}{}{}{}\n",
+                format_float(model.alpha.3, false),
+                format_float(model.alpha.4, false),
+                format_float(model.alpha.5, false),
+                format_float(model.alpha.6, false))?;
+            write!(writer, "    {}{}\n",
+                format_float(model.alpha.7, false),
+                format_float(model.alpha.8, false))
+        },
+    }
+}
+
+/// Writes an Almanac frame (RINEX4)
+fn write_alm (epoch: &Epoch, frame: (MsgType, Sv, &almmessage::Message), writer: &mut impl Write) -> std::io::Result<()> {
+    let (msg, sv, alm) = frame;
+    write!(writer, "> {} {} {}\n", FrameClass::Almanac, sv, msg)?;
+    write!(writer, "    {}{}{}{}\n",
+        epoch.date.format("%Y %m %d %H %M %S"),
+        format_float(alm.e, false),
+        format_float(alm.toa as f64, false),
+        format_float(alm.delta_i, false))?;
+    write!(writer, "    {}{}{}{}\n",
+        format_float(alm.omega_dot, false),
+        format_float(alm.sqrt_a, false),
+        format_float(alm.omega_0, false),
+        format_float(alm.omega, false))?;
+    write!(writer, "    {}{}{}{}\n",
+        format_float(alm.m_0, false),
+        format_float(alm.af0, false),
+        format_float(alm.af1, false),
+        format_float(alm.health as f64, false))?;
+    write!(writer, "    {}\n",
+        format_float(alm.week as f64, false))
+}
+
+/// Streams navigation record into given writer, epoch by epoch.
+/// Frames are produced in a deterministic order: by epoch,
+/// by class (EPH, STO, EOP, ION, ALM), then as described in [sort_frames].
+/// Duplicated frames (see [dedup_frames]) are only produced once.
+/// Only Ephemeris frames can be described prior RINEX4,
+/// other classes are then dropped
+pub fn to_writer (header: &header::Header, record: &Record, writer: &mut impl Write) -> std::io::Result<()> {
+    let modern = header.version.major > 3;
+    let mut sorted : Vec<&Frame> = Vec::new();
+    for (epoch, classes) in record.iter() {
+        for (_, frames) in classes.iter() {
+            // sort & dedup borrowed frames, see [dedup_frames]
+            sorted.clear();
+            sorted.extend(frames.iter());
+            sorted.sort_by(|a, b| a.identity().cmp(&b.identity()));
+            sorted.dedup_by(|a, b| a.identity() == b.identity());
+            for frame in sorted.iter() {
+                match frame {
+                    Frame::Eph(msg, sv, clk, clk_dr, clk_drr, eph, raw) => {
+                        write_ephemeris(header.version, epoch, (*msg, *sv, *clk, *clk_dr, *clk_drr, eph, raw), writer)?
+                    },
+                    Frame::Sto(msg, sv, sto) if modern => write_sto(epoch, (*msg, *sv, sto), writer)?,
+                    Frame::Eop(msg, sv, eop) if modern => write_eop(epoch, (*msg, *sv, eop), writer)?,
+                    Frame::Ion(msg, sv, ion) if modern => write_ion(epoch, (*msg, *sv, ion), writer)?,
+                    Frame::Alm(msg, sv, alm) if modern => write_alm(epoch, (*msg, *sv, alm), writer)?,
+                    _ => {}, // not described prior RINEX4
+                }
             }
-            index += 1
-        }*/
+        }
     }
     Ok(())
 }
@@ -612,6 +966,17 @@ pub fn to_file (header: &header::Header, record: &Record, mut writer: std::fs::F
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::constellation::augmentation::Augmentation;
+    /// Returns Ephemeris frame content, as described by the RINEX layout
+    fn orbits (version: Version, frame: &Frame) -> HashMap<String, ComplexEnum> {
+        let (_, sv, _, _, _, eph) = frame.as_eph().unwrap();
+        let raw = frame.as_eph_raw().unwrap();
+        database_items(version, sv.constellation)
+            .unwrap()
+            .iter()
+            .filter_map(|(k, _)| Some((k.to_string(), ephemeris_field(eph, raw, k)?)))
+            .collect()
+    }
     #[test]
     fn test_complex_enum() {
         let e = ComplexEnum::U8(10);
@@ -697,9 +1062,20 @@ mod test {
             flag: epoch::EpochFlag::Ok,
         });
         assert_eq!(class, FrameClass::Ephemeris);
+        match frame.as_eph() {
+            Some((_, _, _, _, _, ephemeris::Ephemeris::Glonass(glo))) => {
+                assert_eq!(glo.position, Some((-1.488799804690E+03, 1.292880712890E+04, 2.193169775390E+04)));
+                assert_eq!(glo.channel, Some(1));
+                assert_eq!(glo.health, Some(0.0));
+            },
+            _ => panic!("failed to interpret glonass ephemeris"),
+        }
         let fr = frame.as_eph();
         assert_eq!(fr.is_some(), true);
-        let (msg_type, sv, clk, clk_dr, clk_drr, map) = fr.unwrap();
+        let (msg_type, sv, clk, clk_dr, clk_drr, _) = fr.unwrap();
+        // every field is interpreted
+        assert_eq!(frame.as_eph_raw().unwrap().len(), 0);
+        let map = orbits(version, &frame);
         assert_eq!(msg_type, MsgType::LNAV);
         assert_eq!(sv, Sv {
             constellation: Constellation::Glonass,
@@ -797,7 +1173,10 @@ mod test {
         assert_eq!(class, FrameClass::Ephemeris);
         let fr = frame.as_eph();
         assert_eq!(fr.is_some(), true);
-        let (msg_type, sv, clk, clk_dr, clk_drr, map) = fr.unwrap();
+        let (msg_type, sv, clk, clk_dr, clk_drr, _) = fr.unwrap();
+        // every field is interpreted
+        assert_eq!(frame.as_eph_raw().unwrap().len(), 0);
+        let map = orbits(version, &frame);
         assert_eq!(msg_type, MsgType::LNAV);
         assert_eq!(sv, Sv {
             constellation: Constellation::BeiDou,
@@ -942,6 +1321,58 @@ mod test {
         }
     }
     #[test]
+    fn test_v3_gps_entry() {
+        // clock bias follows the 19 columns epoch without blank
+        let content =
+"G04 2022 06 08 10 00 00-1.592547632754E-04 3.979039320257E-12 0.000000000000E+00
+     1.190000000000E+02-5.034375000000E+01 4.516259548909E-09-5.595092534177E-01
+    -2.581626176834E-06 1.831969129853E-03 8.709728717804E-06 5.153607158661E+03
+     2.952000000000E+05 2.421438694000E-08-1.799187327008E+00 2.793967723846E-08
+     9.610624171130E-01 2.087812500000E+02-2.965015012342E+00-8.042120700893E-09
+    -1.071473202588E-12 1.000000000000E+00 2.213000000000E+03 0.000000000000E+00
+     2.000000000000E+00 0.000000000000E+00-4.656612873077E-09 1.190000000000E+02
+     2.880180000000E+05 4.000000000000E+00";
+        let version = Version::new(3, 0);
+        let (epoch, class, frame) = build_record_entry(version, Constellation::Mixed, content)
+            .unwrap();
+        assert_eq!(epoch.date, epoch::str2date("2022 06 08 10 00 00").unwrap());
+        assert_eq!(class, FrameClass::Ephemeris);
+        let (msg_type, sv, clk, clk_dr, clk_drr, _) = frame.as_eph().unwrap();
+        assert_eq!(msg_type, MsgType::LNAV);
+        assert_eq!(sv, Sv::new(Constellation::GPS, 4));
+        assert_eq!(clk, -1.592547632754E-04);
+        assert_eq!(clk_dr, 3.979039320257E-12);
+        assert_eq!(clk_drr, 0.0);
+        // every orbit field lands in its own slot:
+        // cus (2nd orbit, 3rd field) shifts all following fields when missing
+        let map = orbits(version, &frame);
+        assert_eq!(map.len(), 26);
+        let field = |key: &str| map.get(key).and_then(|v| v.as_f64());
+        assert_eq!(field("iode"), Some(119.0));
+        assert_eq!(field("crs"), Some(-5.034375000000E+01));
+        assert_eq!(field("deltaN"), Some(4.516259548909E-09));
+        assert_eq!(field("m0"), Some(-5.595092534177E-01));
+        assert_eq!(field("cuc"), Some(-2.581626176834E-06));
+        assert_eq!(field("e"), Some(1.831969129853E-03));
+        assert_eq!(field("cus"), Some(8.709728717804E-06));
+        assert_eq!(field("sqrta"), Some(5.153607158661E+03));
+        assert_eq!(field("toe"), Some(2.952000000000E+05));
+        assert_eq!(field("cic"), Some(2.421438694000E-08));
+        assert_eq!(field("omega0"), Some(-1.799187327008E+00));
+        assert_eq!(field("cis"), Some(2.793967723846E-08));
+        assert_eq!(field("i0"), Some(9.610624171130E-01));
+        assert_eq!(field("crc"), Some(2.087812500000E+02));
+        assert_eq!(field("omega"), Some(-2.965015012342E+00));
+        assert_eq!(field("omegaDot"), Some(-8.042120700893E-09));
+        assert_eq!(field("idot"), Some(-1.071473202588E-12));
+        assert_eq!(field("gpsWeek"), Some(2213.0));
+        assert_eq!(field("svHealth"), Some(0.0));
+        assert_eq!(field("tgd"), Some(-4.656612873077E-09));
+        assert_eq!(field("iodc"), Some(119.0));
+        assert_eq!(field("t_tm"), Some(2.880180000000E+05));
+        assert_eq!(field("fitInt"), Some(4.0));
+    }
+    #[test]
     fn test_v3_galileo_entry() {
         let content =
 "E01 2021 01 01 10 10 00 -.101553811692e-02 -.804334376880e-11  .000000000000e+00
@@ -963,7 +1394,10 @@ mod test {
         assert_eq!(class, FrameClass::Ephemeris);
         let fr = frame.as_eph();
         assert_eq!(fr.is_some(), true);
-        let (msg_type, sv, clk, clk_dr, clk_drr, map) = fr.unwrap();
+        let (msg_type, sv, clk, clk_dr, clk_drr, _) = fr.unwrap();
+        // every field is interpreted
+        assert_eq!(frame.as_eph_raw().unwrap().len(), 0);
+        let map = orbits(version, &frame);
         assert_eq!(msg_type, MsgType::LNAV);
         assert_eq!(sv, Sv {
             constellation: Constellation::Galileo,
@@ -1125,7 +1559,10 @@ mod test {
         assert_eq!(class, FrameClass::Ephemeris);
         let fr = frame.as_eph();
         assert_eq!(fr.is_some(), true);
-        let (msg_type, sv, clk, clk_dr, clk_drr, map) = fr.unwrap();
+        let (msg_type, sv, clk, clk_dr, clk_drr, _) = fr.unwrap();
+        // every field is interpreted
+        assert_eq!(frame.as_eph_raw().unwrap().len(), 0);
+        let map = orbits(version, &frame);
         assert_eq!(msg_type, MsgType::LNAV);
         assert_eq!(sv, Sv {
             constellation: Constellation::Glonass,
@@ -1201,5 +1638,218 @@ mod test {
             }
         }
     }
-/* GAL V4 from example please */
+    #[test]
+    fn test_v2_sbas_entry() {
+        let content =
+"23 21 01 01 00 01 36.0 0.000000000000D+00 0.000000000000D+00 8.664000000000D+04
+    4.064920000000D+04 0.000000000000D+00 0.000000000000D+00 0.000000000000D+00
+    1.023824000000D+04 0.000000000000D+00 0.000000000000D+00 4.000000000000D+00
+    0.000000000000D+00 0.000000000000D+00 0.000000000000D+00 1.270000000000D+02";
+        let version = Version::new(2, 11);
+        let entry = build_record_entry(version, Constellation::SBAS(Augmentation::default()), content);
+        assert_eq!(entry.is_ok(), true);
+        let (epoch, class, frame) = entry.unwrap();
+        assert_eq!(epoch, Epoch {
+            date: epoch::str2date("21 01 01 00 01 36.0").unwrap(),
+            flag: epoch::EpochFlag::Ok,
+        });
+        assert_eq!(class, FrameClass::Ephemeris);
+        let (msg_type, sv, _, _, clk_drr, eph) = frame.as_eph().unwrap();
+        assert_eq!(msg_type, MsgType::LNAV);
+        assert_eq!(sv.prn, 23);
+        assert_eq!(sv.sbas_prn(), Some(123));
+        assert_eq!(sv.augmentation(), Some(Augmentation::EGNOS));
+        assert_eq!(clk_drr, 8.664E4);
+        // every field is interpreted
+        assert_eq!(frame.as_eph_raw().unwrap().len(), 0);
+        match eph {
+            ephemeris::Ephemeris::Sbas(sbas) => {
+                assert_eq!(sbas.position, Some((4.06492E4, 1.023824E4, 0.0)));
+                assert_eq!(sbas.velocity, Some((0.0, 0.0, 0.0)));
+                assert_eq!(sbas.accuracy, Some(4.0));
+                assert_eq!(sbas.iodn, Some(127.0));
+            },
+            _ => panic!("failed to interpret sbas ephemeris"),
+        }
+        assert_eq!(eph.is_healthy(), Some(true));
+    }
+    #[test]
+    fn test_v3_sbas_entry() {
+        let content =
+"S36 2021 01 01 00 01 04 -.558793544769e-08 -.909494701773e-12  .866400000000e+05
+      .421648950000e+05  .000000000000e+00  .000000000000e+00  .630000000000e+02
+      .327840000000e+04  .000000000000e+00  .000000000000e+00  .327670000000e+05
+     -.265800000000e+03  .000000000000e+00  .000000000000e+00  .900000000000e+01";
+        let version = Version::new(3, 4);
+        let entry = build_record_entry(version, Constellation::Mixed, content);
+        assert_eq!(entry.is_ok(), true);
+        let (epoch, class, frame) = entry.unwrap();
+        assert_eq!(epoch, Epoch {
+            date: epoch::str2date("2021 01 01 00 01 04").unwrap(),
+            flag: epoch::EpochFlag::Ok,
+        });
+        assert_eq!(class, FrameClass::Ephemeris);
+        let (_, sv, clk, clk_dr, _, eph) = frame.as_eph().unwrap();
+        assert_eq!(sv.to_string(), "S36");
+        assert_eq!(sv.augmentation(), Some(Augmentation::EGNOS));
+        assert_eq!(clk, -0.558793544769e-08);
+        assert_eq!(clk_dr, -0.909494701773e-12);
+        assert_eq!(frame.as_eph_raw().unwrap().len(), 0);
+        assert_eq!(eph.health(), Some(63.0));
+        assert_eq!(eph.is_healthy(), Some(false));
+        assert_eq!(eph.iod(), Some(9.0));
+        assert_eq!(eph.field("satPosZ"), Some(-0.2658E3));
+    }
+    #[test]
+    fn test_v4_almanac_entry() {
+        let content =
+"> ALM G01 LNAV
+    2022 06 08 00 00 00 9.853839874268E-03 5.898240000000E+05 8.544921875000E-03
+    -7.863184958883E-09 5.153650390625E+03-1.286897115738E+00 5.386352539063E-01
+    -2.914714813232E+00 1.144409179688E-04 3.637978807092E-12 0.000000000000E+00
+     2.213000000000E+03";
+        let entry = build_record_entry(Version::new(4, 0), Constellation::Mixed, content);
+        assert_eq!(entry.is_ok(), true);
+        let (epoch, class, frame) = entry.unwrap();
+        assert_eq!(epoch.date, epoch::str2date("2022 06 08 00 00 00").unwrap());
+        assert_eq!(class, FrameClass::Almanac);
+        assert_eq!(frame.class(), FrameClass::Almanac);
+        assert_eq!(frame.sv(), Sv::from_str("G01").unwrap());
+        assert_eq!(frame.as_eph().is_none(), true);
+        let alm = frame.as_alm().unwrap();
+        assert_eq!(alm.sqrt_a, 5.153650390625E+03);
+        assert_eq!(alm.week, 2213);
+        // reciprocal
+        let mut produced: Vec<u8> = Vec::new();
+        write_alm(&epoch, (MsgType::LNAV, frame.sv(), alm), &mut produced).unwrap();
+        let produced = String::from_utf8(produced).unwrap();
+        assert_eq!(produced.trim_end(), content);
+    }
+    #[test]
+    fn test_v4_ephemeris_epoch() {
+        let content =
+"> EPH G04 LNAV
+G04 2022 06 08 10 00 00-1.592547632754E-04 3.979039320257E-12 0.000000000000E+00
+     1.190000000000E+02-5.034375000000E+01 4.516259548909E-09-5.595092534177E-01
+    -2.581626176834E-06 1.831969129853E-03 8.709728717804E-06 5.153607158661E+03
+     2.952000000000E+05 2.421438694000E-08-1.799187327008E+00 2.793967723846E-08
+     9.610624171130E-01 2.087812500000E+02-2.965015012342E+00-8.042120700893E-09
+    -1.071473202588E-12 1.000000000000E+00 2.213000000000E+03 0.000000000000E+00
+     2.000000000000E+00 0.000000000000E+00-4.656612873077E-09 1.190000000000E+02
+     2.880180000000E+05 4.000000000000E+00";
+        let entry = build_record_entry(Version::new(4, 0), Constellation::Mixed, content);
+        assert_eq!(entry.is_ok(), true);
+        let (epoch, class, frame) = entry.unwrap();
+        assert_eq!(epoch.date, epoch::str2date("2022 06 08 10 00 00").unwrap());
+        assert_eq!(class, FrameClass::Ephemeris);
+        let (msg_type, sv, clk, clk_dr, clk_drr, _) = frame.as_eph().unwrap();
+        assert_eq!(msg_type, MsgType::LNAV);
+        assert_eq!(sv, Sv::new(Constellation::GPS, 4));
+        // clock bias sign is the 20th character after the vehicule
+        assert_eq!(clk, -1.592547632754E-04);
+        assert_eq!(clk_dr, 3.979039320257E-12);
+        assert_eq!(clk_drr, 0.0);
+    }
+    #[test]
+    fn test_v4_sto_entry() {
+        let content =
+"> STO G02 LNAV
+    2022 06 08 10 00 00 GPUT                                  UTC(USNO)         
+     2.952840000000E+05 9.313225746155E-10 2.664535259100E-15 0.000000000000E+00";
+        let entry = build_record_entry(Version::new(4, 0), Constellation::Mixed, content);
+        assert_eq!(entry.is_ok(), true);
+        let (epoch, class, frame) = entry.unwrap();
+        assert_eq!(epoch.date, epoch::str2date("2022 06 08 10 00 00").unwrap());
+        assert_eq!(class, FrameClass::SystemTimeOffset);
+        assert_eq!(frame.sv(), Sv::new(Constellation::GPS, 2));
+        let sto = frame.as_sto().unwrap();
+        assert_eq!(sto.system, "GPUT");
+        // UTC identifier comes from the epoch line
+        assert_eq!(sto.utc, "UTC(USNO)");
+        assert_eq!(sto.t_tm, 295284);
+        assert_eq!(sto.a, (9.313225746155E-10, 2.664535259100E-15, 0.0));
+    }
+    #[test]
+    fn test_v4_gps_lnav_layout() {
+        // legacy frames keep the RINEX3 layout, modern frames use the RINEX4 one
+        assert_eq!(modern_revision(Constellation::GPS, MsgType::LNAV), Version::new(3, 0));
+        assert_eq!(modern_revision(Constellation::QZSS, MsgType::LNAV), Version::new(3, 0));
+        assert_eq!(modern_revision(Constellation::GPS, MsgType::CNVX), Version::new(4, 0));
+        let content =
+"> EPH G04 LNAV
+G04 2022 06 08 10 00 00-1.592547632754E-04 3.979039320257E-12 0.000000000000E+00
+     1.190000000000E+02-5.034375000000E+01 4.516259548909E-09-5.595092534177E-01
+    -2.581626176834E-06 1.831969129853E-03 8.709728717804E-06 5.153607158661E+03
+     2.952000000000E+05 2.421438694000E-08-1.799187327008E+00 2.793967723846E-08
+     9.610624171130E-01 2.087812500000E+02-2.965015012342E+00-8.042120700893E-09
+    -1.071473202588E-12 1.000000000000E+00 2.213000000000E+03 0.000000000000E+00
+     2.000000000000E+00 0.000000000000E+00-4.656612873077E-09 1.190000000000E+02
+     2.880180000000E+05 4.000000000000E+00";
+        let (_, _, frame) = build_record_entry(Version::new(4, 0), Constellation::Mixed, content)
+            .unwrap();
+        let field = |key: &str| frame.eph_field(key).and_then(|v| v.as_f64());
+        assert_eq!(field("iode"), Some(119.0));
+        assert_eq!(field("crs"), Some(-5.034375000000E+01));
+        assert_eq!(field("cus"), Some(8.709728717804E-06));
+        assert_eq!(field("sqrta"), Some(5.153607158661E+03));
+        assert_eq!(field("toe"), Some(2.952000000000E+05));
+        assert_eq!(field("gpsWeek"), Some(2213.0));
+        assert_eq!(field("tgd"), Some(-4.656612873077E-09));
+        assert_eq!(field("iodc"), Some(119.0));
+        assert_eq!(field("t_tm"), Some(2.880180000000E+05));
+        assert_eq!(field("fitInt"), Some(4.0));
+        // not described by the RINEX3 layout
+        assert_eq!(frame.eph_field("iscL1Ca").is_none(), true);
+    }
+    #[test]
+    fn test_v4_frames_production() {
+        let ephemeris =
+"> EPH G04 LNAV
+G04 2022 06 08 10 00 00-1.592547632754E-04 3.979039320257E-12 0.000000000000E+00
+     1.190000000000E+02-5.034375000000E+01 4.516259548909E-09-5.595092534177E-01
+    -2.581626176834E-06 1.831969129853E-03 8.709728717804E-06 5.153607158661E+03
+     2.952000000000E+05 2.421438694000E-08-1.799187327008E+00 2.793967723846E-08
+     9.610624171130E-01 2.087812500000E+02-2.965015012342E+00-8.042120700893E-09
+    -1.071473202588E-12 1.000000000000E+00 2.213000000000E+03 0.000000000000E+00
+     2.000000000000E+00 0.000000000000E+00-4.656612873077E-09 1.190000000000E+02
+     2.880180000000E+05 4.000000000000E+00";
+        let sto =
+"> STO G02 LNAV
+    2022 06 08 10 00 00 GPUT                                  UTC(USNO)
+     2.952840000000E+05 9.313225746155E-10 2.664535259100E-15 0.000000000000E+00";
+        let eop =
+"> EOP G02 CNVX
+    2022 06 08 10 00 00 1.234567890000E-01 2.500000000000E-04 0.000000000000E+00
+                        3.456789000000E-01-1.200000000000E-04 0.000000000000E+00
+     2.952840000000E+05-1.234000000000E-01 3.000000000000E-04 0.000000000000E+00";
+        let ion =
+"> ION G02 LNAV
+    2022 06 08 10 00 00 1.024454832077E-08 2.235174179077E-08-5.960464477539E-08
+    -1.192092895508E-07 9.625600000000E+04 1.310720000000E+05-6.553600000000E+04
+    -5.898240000000E+05 0.000000000000E+00";
+        for content in [ephemeris, sto, eop, ion] {
+            let (epoch, _, frame) = build_record_entry(Version::new(4, 0), Constellation::Mixed, content)
+                .unwrap();
+            let mut produced: Vec<u8> = Vec::new();
+            match &frame {
+                Frame::Eph(msg, sv, clk, clk_dr, clk_drr, eph, raw) => {
+                    write_ephemeris(Version::new(4, 0), &epoch, (*msg, *sv, *clk, *clk_dr, *clk_drr, eph, raw), &mut produced)
+                },
+                Frame::Sto(msg, sv, sto) => write_sto(&epoch, (*msg, *sv, sto), &mut produced),
+                Frame::Eop(msg, sv, eop) => write_eop(&epoch, (*msg, *sv, eop), &mut produced),
+                Frame::Ion(msg, sv, ion) => write_ion(&epoch, (*msg, *sv, ion), &mut produced),
+                Frame::Alm(msg, sv, alm) => write_alm(&epoch, (*msg, *sv, alm), &mut produced),
+            }.unwrap();
+            let produced = String::from_utf8(produced).unwrap();
+            assert_eq!(produced.trim_end(), content);
+        }
+    }
+    #[test]
+    fn test_format_float() {
+        assert_eq!(format_float(-1.592547632754E-04, false), "-1.592547632754E-04");
+        assert_eq!(format_float(3.979039320257E-12, false), " 3.979039320257E-12");
+        assert_eq!(format_float(2.952E+05, true), " 2.952000000000D+05");
+        assert_eq!(format_float(0.0, false), " 0.000000000000E+00");
+        assert_eq!(format_complex(None, false), format!("{:19}", ""));
+    }
 }
@@ -0,0 +1,155 @@
+//! Ephemeris selection: most appropriate Ephemeris frame,
+//! for a given vehicule and epoch
+use crate::epoch;
+use crate::sv::Sv;
+use crate::constellation::Constellation;
+use super::orbits;
+use super::record::{Record, Frame, FrameClass};
+use super::ephemeris::Ephemeris;
+
+/// Returns the time elapsed [s] between the reference time of given Ephemeris
+/// and given epoch. Keplerian Ephemeris refer to Toe, GLONASS and SBAS
+/// Ephemeris refer to their own epoch (`frame_epoch`)
+fn time_from_reference (sv: &Sv, frame_epoch: &epoch::Epoch, eph: &Ephemeris, epoch: &epoch::Epoch) -> Option<f64> {
+    match sv.constellation {
+        Constellation::GPS | Constellation::QZSS
+        | Constellation::Galileo | Constellation::BeiDou => {
+            let toe = eph.kepler()?.toe;
+            orbits::time_from_ephemeris(sv.constellation, epoch, toe)
+        },
+        _ => {
+            let dt = epoch.date - frame_epoch.date;
+            Some(dt.num_milliseconds() as f64 / 1.0E3)
+        },
+    }
+}
+
+/// Returns the validity window of given Ephemeris [s], on either side
+/// of its reference time. GPS and QZSS describe their curve fit interval,
+/// 4 hours are assumed when not described. Galileo Ephemeris are valid for
+/// 4 hours, BeiDou Ephemeris for 2 hours, GLONASS Ephemeris for 30 minutes.
+/// Other constellations are limited to [orbits::MAX_EPHEMERIS_AGE]
+pub fn validity (sv: &Sv, eph: &Ephemeris) -> f64 {
+    match sv.constellation {
+        Constellation::GPS | Constellation::QZSS => {
+            let fit = match eph {
+                Ephemeris::Gps(gps) => gps.fit_interval.unwrap_or(0.0),
+                _ => 0.0,
+            };
+            if fit > 0.0 {
+                fit * 3600.0 / 2.0
+            } else {
+                2.0 * 3600.0
+            }
+        },
+        Constellation::Galileo => 2.0 * 3600.0,
+        Constellation::BeiDou => 3600.0,
+        Constellation::Glonass => 15.0 * 60.0,
+        _ => orbits::MAX_EPHEMERIS_AGE as f64,
+    }
+}
+
+/// Returns the most appropriate Ephemeris frame for given vehicule at given epoch,
+/// and the epoch of that frame: among the Ephemeris whose validity window
+/// contains given epoch (see [validity]), the closest to its reference time.
+/// When several frames describe the same reference time, the latest issue
+/// (most recent frame) is preferred.
+/// Returns None if no Ephemeris is valid at given epoch.
+pub fn ephemeris_at<'a> (record: &'a Record, sv: &Sv, epoch: &epoch::Epoch) -> Option<(epoch::Epoch, &'a Frame)> {
+    let mut best: Option<(f64, epoch::Epoch, &Frame)> = None;
+    for (e, classes) in record.iter() {
+        let frames = match classes.get(&FrameClass::Ephemeris) {
+            Some(frames) => frames,
+            None => continue,
+        };
+        for frame in frames.iter() {
+            let (_, frame_sv, _, _, _, eph) = match frame.as_eph() {
+                Some(eph) => eph,
+                None => continue,
+            };
+            if frame_sv != *sv {
+                continue
+            }
+            let dt = match time_from_reference(sv, e, eph, epoch) {
+                Some(dt) => dt.abs(),
+                None => continue,
+            };
+            if dt > validity(sv, eph) {
+                continue
+            }
+            let better = match best {
+                // frames are browsed chronologically: latest issue wins ties
+                Some((best_dt, _, _)) => dt <= best_dt,
+                None => true,
+            };
+            if better {
+                best = Some((dt, *e, frame))
+            }
+        }
+    }
+    best.map(|(_, e, frame)| (e, frame))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::navigation::ephemeris::test_frame;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+    fn ephemeris (sv: &str, toe: Option<f64>, iode: f64) -> Frame {
+        let mut fields = vec![("iode", iode)];
+        if let Some(toe) = toe {
+            fields.extend_from_slice(&[
+                ("sqrta", 5153.6), ("e", 0.01), ("i0", 0.96), ("omega0", 1.0),
+                ("omega", 0.5), ("m0", 0.2), ("deltaN", 4.0E-9), ("idot", 1.0E-10),
+                ("omegaDot", -8.0E-9), ("cuc", 1.0E-6), ("cus", 1.0E-6), ("crc", 200.0),
+                ("crs", 20.0), ("cic", 1.0E-8), ("cis", 1.0E-8), ("toe", toe),
+            ]);
+        }
+        test_frame(sv, &fields)
+    }
+    fn iode (frame: &Frame) -> f64 {
+        frame.eph_field("iode")
+            .unwrap()
+            .as_f64()
+            .unwrap()
+    }
+    #[test]
+    fn test_ephemeris_selection() {
+        let at = |date: &str| epoch::Epoch::new(
+            epoch::str2date(date).unwrap(),
+            epoch::EpochFlag::Ok);
+        // 2021/01/01 is a friday: 432000s of GPS week
+        let mut record = Record::new();
+        for (date, frames) in [
+            ("2021 01 01 00 00 00", vec![ephemeris("G01", Some(432000.0), 1.0), ephemeris("R01", None, 1.0)]),
+            ("2021 01 01 01 59 44", vec![ephemeris("G01", Some(439200.0), 2.0)]),
+            ("2021 01 01 02 00 00", vec![ephemeris("G01", Some(439200.0), 3.0)]),
+            ("2021 01 01 00 30 00", vec![ephemeris("R01", None, 2.0)]),
+        ] {
+            let mut classes: BTreeMap<FrameClass, Vec<Frame>> = BTreeMap::new();
+            classes.insert(FrameClass::Ephemeris, frames);
+            record.insert(at(date), classes);
+        }
+        let g01 = Sv::from_str("G01").unwrap();
+        let (e, frame) = ephemeris_at(&record, &g01, &at("2021 01 01 00 50 00")).unwrap();
+        assert_eq!(e, at("2021 01 01 00 00 00"));
+        assert_eq!(iode(frame), 1.0);
+        // same Toe: latest issue
+        let (e, frame) = ephemeris_at(&record, &g01, &at("2021 01 01 01 10 00")).unwrap();
+        assert_eq!(e, at("2021 01 01 02 00 00"));
+        assert_eq!(iode(frame), 3.0);
+        // outside of fit interval
+        assert_eq!(ephemeris_at(&record, &g01, &at("2021 01 01 04 00 01")).is_none(), true);
+        assert_eq!(ephemeris_at(&record, &g01, &at("2020 12 31 21 59 59")).is_none(), true);
+        // glonass: frame epoch is the reference
+        let r01 = Sv::from_str("R01").unwrap();
+        let (e, frame) = ephemeris_at(&record, &r01, &at("2021 01 01 00 20 00")).unwrap();
+        assert_eq!(e, at("2021 01 01 00 30 00"));
+        assert_eq!(iode(frame), 2.0);
+        assert_eq!(ephemeris_at(&record, &r01, &at("2021 01 01 00 46 00")).is_none(), true);
+        // unknown vehicule
+        let g02 = Sv::from_str("G02").unwrap();
+        assert_eq!(ephemeris_at(&record, &g02, &at("2021 01 01 00 50 00")).is_none(), true);
+    }
+}
@@ -0,0 +1,183 @@
+//! GNSS linear combinations of dual frequency observations
+use crate::sv;
+use crate::epoch;
+use crate::channel;
+use crate::observation::Observable;
+use std::collections::BTreeMap;
+
+/// Linear combinations of observations made on two carriers,
+/// noted 1 (highest frequency) and 2 (lowest frequency)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Combination {
+    /// Geometry free: x1 - x2,
+    /// cancels geometry and clocks, retains the ionospheric delay
+    GeometryFree,
+    /// Ionosphere free: (f1² x1 - f2² x2) / (f1² - f2²),
+    /// cancels the first order ionospheric delay
+    IonosphereFree,
+    /// Wide lane: (f1 x1 - f2 x2) / (f1 - f2),
+    /// long wavelength, eases ambiguity resolution
+    WideLane,
+    /// Narrow lane: (f1 x1 + f2 x2) / (f1 + f2),
+    /// low noise
+    NarrowLane,
+}
+
+impl Combination {
+    /// Combines observations `x1` and `x2` [m],
+    /// observed on carrier frequencies `f1` and `f2` [Hz]
+    pub fn combine (&self, (f1, x1): (f64, f64), (f2, x2): (f64, f64)) -> f64 {
+        match self {
+            Self::GeometryFree => x1 - x2,
+            Self::IonosphereFree => (f1.powi(2) * x1 - f2.powi(2) * x2) / (f1.powi(2) - f2.powi(2)),
+            Self::WideLane => (f1 * x1 - f2 * x2) / (f1 - f2),
+            Self::NarrowLane => (f1 * x1 + f2 * x2) / (f1 + f2),
+        }
+    }
+}
+
+/// Selects two observations, expressed in meters, made on separate carriers.
+/// Observables are browsed in alphabetical order, the first observable
+/// of each carrier is retained, and the two highest frequencies are selected.
+/// Returns ((f1, x1), (f2, x2)) with f1 > f2 [Hz],
+/// or None if observations were not made on two separate carriers
+pub fn dual_frequency (sv: &sv::Sv, observations: &[(Observable, f64)]) -> Option<((f64, f64), (f64, f64))> {
+    let mut observations : Vec<&(Observable, f64)> = observations.iter().collect();
+    observations.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut carriers : Vec<(f64, f64)> = Vec::with_capacity(observations.len());
+    for (code, value) in observations {
+        if let Ok(channel) = channel::Channel::from_observable(sv.constellation, code) {
+            let frequency = channel.carrier_frequency_mhz() * 1.0E6;
+            if carriers.iter().all(|(f, _)| *f != frequency) {
+                carriers.push((frequency, *value));
+            }
+        }
+    }
+    if carriers.len() < 2 {
+        return None
+    }
+    carriers.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    Some((carriers[0], carriers[1]))
+}
+
+/// Synthesizes a Galileo E5 AltBOC (E5a + E5b) equivalent observation,
+/// from observations made on E5a and E5b, all expressed in meters.
+/// Ionospheric delay of this narrow lane combination scales with
+/// 1/(f5a f5b), which matches the AltBOC central frequency (f5a + f5b)/2 to 0.02%
+pub fn e5_altboc (e5a: f64, e5b: f64) -> f64 {
+    let f5a = channel::Channel::E5.carrier_frequency_mhz() * 1.0E6;
+    let f5b = channel::Channel::E7.carrier_frequency_mhz() * 1.0E6;
+    Combination::NarrowLane.combine((f5b, e5b), (f5a, e5a))
+}
+
+/// Evaluates given combination on epoch indexed observations,
+/// expressed in meters (see [crate::Rinex::pseudo_ranges] and [crate::Rinex::carrier_phases_m]).
+/// Carriers are selected as described in [dual_frequency],
+/// vehicules that were not observed on two separate carriers are not exposed
+pub fn combine (observations: &BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(Observable, f64)>>>, combination: Combination) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+    let mut results : BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> = BTreeMap::new();
+    for (e, vehicules) in observations.iter() {
+        let mut map : BTreeMap<sv::Sv, f64> = BTreeMap::new();
+        for (sv, obs) in vehicules.iter() {
+            if let Some((x1, x2)) = dual_frequency(sv, obs) {
+                map.insert(*sv, combination.combine(x1, x2));
+            }
+        }
+        if map.len() > 0 {
+            results.insert(*e, map);
+        }
+    }
+    results
+}
+
+/// Evaluates the Melbourne-Wübbena combination: wide lane carrier phase
+/// minus narrow lane pseudo range, both expressed in meters.
+/// It cancels geometry, clocks and ionospheric delay, leaving
+/// the wide lane ambiguity: used in cycle slip detection.
+/// Vehicules for which phase and pseudo range were not
+/// observed on the same two carriers are not exposed
+pub fn melbourne_wubbena (pseudo_ranges: &BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(Observable, f64)>>>, phases: &BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(Observable, f64)>>>) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+    let mut results : BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> = BTreeMap::new();
+    for (e, vehicules) in phases.iter() {
+        let codes = match pseudo_ranges.get(e) {
+            Some(codes) => codes,
+            None => continue,
+        };
+        let mut map : BTreeMap<sv::Sv, f64> = BTreeMap::new();
+        for (sv, phase) in vehicules.iter() {
+            let code = match codes.get(sv) {
+                Some(code) => code,
+                None => continue,
+            };
+            if let Some((l1, l2)) = dual_frequency(sv, phase) {
+                if let Some((p1, p2)) = dual_frequency(sv, code) {
+                    if l1.0 == p1.0 && l2.0 == p2.0 {
+                        let wl = Combination::WideLane.combine(l1, l2);
+                        let nl = Combination::NarrowLane.combine(p1, p2);
+                        map.insert(*sv, wl - nl);
+                    }
+                }
+            }
+        }
+        if map.len() > 0 {
+            results.insert(*e, map);
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_combinations() {
+        let (f1, f2): (f64, f64) = (1575.42E6, 1227.60E6);
+        let range = 22.0E6;
+        let iono = 5.0; // on L1
+        let iono_2 = iono * (f1 / f2).powi(2);
+        // code is delayed, phase is advanced
+        let (p1, p2) = (range + iono, range + iono_2);
+        let (l1, l2) = (range - iono, range - iono_2);
+        let c = Combination::IonosphereFree;
+        assert!((c.combine((f1, p1), (f2, p2)) - range).abs() < 1.0E-6);
+        assert!((c.combine((f1, l1), (f2, l2)) - range).abs() < 1.0E-6);
+        let c = Combination::GeometryFree;
+        assert!((c.combine((f1, l1), (f2, l2)) - (iono_2 - iono)).abs() < 1.0E-6);
+        let c = Combination::WideLane;
+        assert_eq!(c.combine((f1, range), (f2, range)), range);
+        let c = Combination::NarrowLane;
+        assert_eq!(c.combine((f1, range), (f2, range)), range);
+        // carrier selection
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let obs = vec![
+            (Observable::from_str("L5Q").unwrap(), 3.0),
+            (Observable::from_str("L2W").unwrap(), 2.0),
+            (Observable::from_str("L1W").unwrap(), 11.0),
+            (Observable::from_str("L1C").unwrap(), 1.0),
+        ];
+        assert_eq!(dual_frequency(&g01, &obs), Some(((f1, 1.0), (f2, 2.0))));
+        assert_eq!(dual_frequency(&g01, &obs[2..]), None);
+        // Melbourne-Wübbena: geometry and ionosphere free
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2022 01 01 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let mut pseudo_ranges : BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(Observable, f64)>>> = BTreeMap::new();
+        let mut phases : BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Vec<(Observable, f64)>>> = BTreeMap::new();
+        let mut map = BTreeMap::new();
+        map.insert(g01, vec![(Observable::from_str("C1C").unwrap(), p1), (Observable::from_str("C2W").unwrap(), p2)]);
+        pseudo_ranges.insert(t0, map);
+        let mut map = BTreeMap::new();
+        map.insert(g01, vec![(Observable::from_str("L1C").unwrap(), l1), (Observable::from_str("L2W").unwrap(), l2)]);
+        phases.insert(t0, map);
+        let mw = melbourne_wubbena(&pseudo_ranges, &phases);
+        assert!(mw[&t0][&g01].abs() < 1.0E-6);
+        let gf = combine(&phases, Combination::GeometryFree);
+        assert!((gf[&t0][&g01] - (iono_2 - iono)).abs() < 1.0E-6);
+        // E5 AltBOC synthesis
+        let (f5a, f5b, f8) = (1176.45E6, 1207.14E6, 1191.795E6);
+        let iono_8 = iono * (f1 / f8).powi(2);
+        let e8 = e5_altboc(range + iono * (f1 / f5a).powi(2), range + iono * (f1 / f5b).powi(2));
+        assert!((e8 - (range + iono_8)).abs() < 5.0E-3);
+    }
+}
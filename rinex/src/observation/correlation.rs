@@ -0,0 +1,167 @@
+//! Temporal cross-correlation between signals,
+//! to detect events affecting the whole band (interferences..)
+use crate::sv;
+use crate::epoch;
+use crate::observation::Observable;
+use std::collections::BTreeMap;
+
+/// A signal is identified by a vehicule and an observable
+pub type Signal = (sv::Sv, Observable);
+
+/// Minimal number of shared samples required
+/// to evaluate a correlation coefficient
+pub const MIN_SAMPLES: usize = 3;
+
+/// Cross-correlation matrix, evaluated over a time window
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix {
+    /// Signals, indexing both dimensions of the matrix
+    pub signals: Vec<Signal>,
+    /// Pearson coefficients, symmetric matrix.
+    /// None when the two signals do not share enough samples,
+    /// or when one of them is constant over this window
+    pub coefficients: Vec<Vec<Option<f64>>>,
+}
+
+impl Matrix {
+    /// Returns correlation coefficient between two signals
+    pub fn get (&self, a: &Signal, b: &Signal) -> Option<f64> {
+        let i = self.signals.iter().position(|s| s == a)?;
+        let j = self.signals.iter().position(|s| s == b)?;
+        self.coefficients[i][j]
+    }
+    /// Returns the average of all off-diagonal coefficients.
+    /// A value close to 1.0 indicates an event affecting all signals at once,
+    /// like an interference affecting the whole band
+    pub fn mean_correlation (&self) -> Option<f64> {
+        let mut sum = 0.0_f64;
+        let mut count = 0;
+        for i in 0..self.signals.len() {
+            for j in i+1..self.signals.len() {
+                if let Some(c) = self.coefficients[i][j] {
+                    sum += c;
+                    count += 1
+                }
+            }
+        }
+        if count > 0 {
+            Some(sum / count as f64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Pearson correlation coefficient of paired samples
+fn pearson (samples: &[(f64, f64)]) -> Option<f64> {
+    if samples.len() < MIN_SAMPLES {
+        return None
+    }
+    let n = samples.len() as f64;
+    let (mean_x, mean_y) = samples
+        .iter()
+        .fold((0.0_f64, 0.0_f64), |(sx, sy), (x, y)| (sx + x, sy + y));
+    let (mean_x, mean_y) = (mean_x / n, mean_y / n);
+    let (mut cov, mut var_x, mut var_y) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for (x, y) in samples {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        None
+    } else {
+        Some(cov / (var_x * var_y).sqrt())
+    }
+}
+
+/// Evaluates the cross-correlation matrix of given time series
+/// (signal strengths, residuals..) over consecutive time windows of given duration.
+/// Windows start at the first epoch. Result is indexed by the first epoch
+/// of each window, windows with less than two signals are not produced.
+pub fn cross_correlation (series: &BTreeMap<epoch::Epoch, BTreeMap<Signal, f64>>, window: chrono::Duration) -> BTreeMap<epoch::Epoch, Matrix> {
+    let mut results : BTreeMap<epoch::Epoch, Matrix> = BTreeMap::new();
+    let mut iter = series.iter().peekable();
+    while let Some((start, _)) = iter.peek() {
+        let start = **start;
+        let end = start.date + window;
+        // gather this window
+        let mut content : Vec<&BTreeMap<Signal, f64>> = Vec::new();
+        while let Some((e, signals)) = iter.peek() {
+            if e.date >= end && content.len() > 0 {
+                break
+            }
+            content.push(signals);
+            iter.next();
+        }
+        let mut signals : Vec<Signal> = Vec::new();
+        for epoch in content.iter() {
+            for signal in epoch.keys() {
+                if !signals.contains(signal) {
+                    signals.push(*signal)
+                }
+            }
+        }
+        if signals.len() < 2 {
+            continue
+        }
+        signals.sort();
+        let n = signals.len();
+        let mut coefficients : Vec<Vec<Option<f64>>> = vec![vec![None; n]; n];
+        for i in 0..n {
+            coefficients[i][i] = Some(1.0);
+            for j in i+1..n {
+                let samples : Vec<(f64, f64)> = content
+                    .iter()
+                    .filter_map(|epoch| {
+                        let x = epoch.get(&signals[i])?;
+                        let y = epoch.get(&signals[j])?;
+                        Some((*x, *y))
+                    })
+                    .collect();
+                let c = pearson(&samples);
+                coefficients[i][j] = c;
+                coefficients[j][i] = c;
+            }
+        }
+        results.insert(start, Matrix {
+            signals,
+            coefficients,
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_cross_correlation() {
+        let g01 = (sv::Sv::from_str("G01").unwrap(), Observable::from_str("S1C").unwrap());
+        let g02 = (sv::Sv::from_str("G02").unwrap(), Observable::from_str("S1C").unwrap());
+        let e01 = (sv::Sv::from_str("E01").unwrap(), Observable::from_str("S1C").unwrap());
+        let t0 = epoch::str2date("2022 01 01 00 00 00").unwrap();
+        let mut series : BTreeMap<epoch::Epoch, BTreeMap<Signal, f64>> = BTreeMap::new();
+        for i in 0..20 {
+            let e = epoch::Epoch::new(
+                t0 + chrono::Duration::seconds(30 * i),
+                epoch::EpochFlag::Ok);
+            let mut signals : BTreeMap<Signal, f64> = BTreeMap::new();
+            let x = i as f64;
+            signals.insert(g01, 40.0 + x);
+            signals.insert(g02, 45.0 + 2.0 * x);
+            signals.insert(e01, 50.0 - x);
+            series.insert(e, signals);
+        }
+        // 5' windows: 10 epochs each
+        let matrices = cross_correlation(&series, chrono::Duration::minutes(5));
+        assert_eq!(matrices.len(), 2);
+        for (_, matrix) in matrices.iter() {
+            assert_eq!(matrix.signals.len(), 3);
+            assert!((matrix.get(&g01, &g02).unwrap() - 1.0).abs() < 1.0E-9);
+            assert!((matrix.get(&g01, &e01).unwrap() + 1.0).abs() < 1.0E-9);
+            assert_eq!(matrix.get(&g01, &g01), Some(1.0));
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Site events: header records following special epochs
+use std::str::FromStr;
+use std::collections::BTreeMap;
+use crate::epoch;
+use crate::hardware;
+use crate::header::{MarkerType, parse_xyz};
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+#[cfg(feature = "with-serde")]
+use crate::formatter::point3d;
+
+/// Site events, sorted by epoch.
+/// The epoch flag describes the nature of the event
+pub type SiteEvents = BTreeMap<epoch::Epoch, SiteEvent>;
+
+/// Returns true if given flag announces header records
+/// (kinematic event, new occupation, header information, external event)
+pub fn is_event_flag (flag: epoch::EpochFlag) -> bool {
+    match flag {
+        epoch::EpochFlag::AntennaBeingMoved
+        | epoch::EpochFlag::NewSiteOccupation
+        | epoch::EpochFlag::HeaderInformationFollows
+        | epoch::EpochFlag::ExternalEvent => true,
+        _ => false,
+    }
+}
+
+/// `SiteEvent` describes header records that follow
+/// an epoch flagged 2 to 5, typically a new site occupation
+/// during a kinematic session. Only fields that were
+/// described by the special records are specified
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct SiteEvent {
+    /// new station label
+    pub station: Option<String>,
+    /// new station identifier
+    pub station_id: Option<String>,
+    /// new receiver placement
+    pub marker_type: Option<MarkerType>,
+    /// new station approximate coordinates, or new position
+    /// of a moving platform
+    #[cfg_attr(feature = "with-serde", serde(with = "point3d"))]
+    pub coords: Option<rust_3d::Point3D>,
+    /// new vehicle center of mass, in vehicle body frame
+    #[cfg_attr(feature = "with-serde", serde(with = "point3d"))]
+    pub center_of_mass: Option<rust_3d::Point3D>,
+    /// new antenna
+    pub ant: Option<hardware::Antenna>,
+    /// new receiver
+    pub rcvr: Option<hardware::Rcvr>,
+    /// other header records, that we do not interpret,
+    /// preserved as is
+    pub records: Vec<String>,
+}
+
+impl SiteEvent {
+    /// Builds a `SiteEvent` from given special records.
+    /// Records we do not interpret are preserved as is
+    pub fn from_records (records: &[&str]) -> Self {
+        let mut event = Self::default();
+        for record in records {
+            if record.trim().is_empty() {
+                continue
+            }
+            if record.len() < 60 {
+                event.records.push(record.trim_end().to_string());
+                continue
+            }
+            let (content, marker) = record.split_at(60);
+            if marker.contains("MARKER NAME") {
+                event.station = Some(content.split_at(20).0.trim().to_string())
+
+            } else if marker.contains("MARKER NUMBER") {
+                event.station_id = Some(content.split_at(20).0.trim().to_string())
+
+            } else if marker.contains("MARKER TYPE") {
+                if let Ok(marker) = MarkerType::from_str(content.split_at(20).0.trim()) {
+                    event.marker_type = Some(marker)
+                }
+
+            } else if marker.contains("APPROX POSITION XYZ") {
+                if let Some((x, y, z)) = parse_xyz(content) {
+                    event.coords = Some(rust_3d::Point3D::new(x, y, z))
+                }
+
+            } else if marker.contains("REC # / TYPE / VERS") {
+                if let Ok(rcvr) = hardware::Rcvr::from_str(content) {
+                    event.rcvr = Some(rcvr)
+                }
+
+            } else if marker.contains("ANT # / TYPE") {
+                let (model, rem) = content.split_at(20);
+                let (sn, _) = rem.split_at(20);
+                let ant = event.ant.get_or_insert_with(hardware::Antenna::default);
+                ant.model = model.trim().to_string();
+                ant.sn = sn.trim().to_string();
+
+            } else if marker.contains("ANTENNA: DELTA H/E/N") {
+                if let Some((h, e, n)) = parse_xyz(content) {
+                    let ant = event.ant.get_or_insert_with(hardware::Antenna::default);
+                    ant.height = Some(h as f32);
+                    ant.eastern_ecc = Some(e as f32);
+                    ant.northern_ecc = Some(n as f32);
+                }
+
+            } else if marker.contains("ANTENNA: DELTA X/Y/Z") {
+                if let Some((x, y, z)) = parse_xyz(content) {
+                    let ant = event.ant.get_or_insert_with(hardware::Antenna::default);
+                    ant.coords = Some(rust_3d::Point3D::new(x, y, z))
+                }
+
+            } else if marker.contains("ANTENNA: B.SIGHT XYZ") {
+                if let Some((x, y, z)) = parse_xyz(content) {
+                    let ant = event.ant.get_or_insert_with(hardware::Antenna::default);
+                    ant.bsight = Some(rust_3d::Point3D::new(x, y, z))
+                }
+
+            } else if marker.contains("ANTENNA: ZERODIR XYZ") {
+                if let Some((x, y, z)) = parse_xyz(content) {
+                    let ant = event.ant.get_or_insert_with(hardware::Antenna::default);
+                    ant.zero_dir = Some(rust_3d::Point3D::new(x, y, z))
+                }
+
+            } else if marker.contains("CENTER OF MASS: XYZ") {
+                if let Some((x, y, z)) = parse_xyz(content) {
+                    event.center_of_mass = Some(rust_3d::Point3D::new(x, y, z))
+                }
+
+            } else {
+                event.records.push(record.trim_end().to_string())
+            }
+        }
+        event
+    }
+
+    /// Returns special records describing self, as found in RINEX files
+    pub fn to_records (&self) -> Vec<String> {
+        let mut records: Vec<String> = Vec::new();
+        if let Some(station) = &self.station {
+            records.push(format!("{:<60}MARKER NAME", station))
+        }
+        if let Some(station_id) = &self.station_id {
+            records.push(format!("{:<60}MARKER NUMBER", station_id))
+        }
+        if let Some(marker_type) = &self.marker_type {
+            records.push(format!("{:<60}MARKER TYPE", marker_type.to_string()))
+        }
+        if let Some(coords) = &self.coords {
+            records.push(format!("{:14.4}{:14.4}{:14.4}{:<18}APPROX POSITION XYZ", coords.x, coords.y, coords.z, ""))
+        }
+        if let Some(rcvr) = &self.rcvr {
+            records.push(format!("{:<20}{:<20}{:<20}REC # / TYPE / VERS", rcvr.sn, rcvr.model, rcvr.firmware))
+        }
+        if let Some(ant) = &self.ant {
+            records.push(format!("{:<20}{:<40}ANT # / TYPE", ant.model, ant.sn));
+            if let Some(h) = ant.height {
+                records.push(format!("{:14.4}{:14.4}{:14.4}{:<18}ANTENNA: DELTA H/E/N",
+                    h, ant.eastern_ecc.unwrap_or(0.0), ant.northern_ecc.unwrap_or(0.0), ""))
+            }
+            if let Some(coords) = &ant.coords {
+                records.push(format!("{:14.4}{:14.4}{:14.4}{:<18}ANTENNA: DELTA X/Y/Z", coords.x, coords.y, coords.z, ""))
+            }
+            if let Some(bsight) = &ant.bsight {
+                records.push(format!("{:14.4}{:14.4}{:14.4}{:<18}ANTENNA: B.SIGHT XYZ", bsight.x, bsight.y, bsight.z, ""))
+            }
+            if let Some(zero_dir) = &ant.zero_dir {
+                records.push(format!("{:14.4}{:14.4}{:14.4}{:<18}ANTENNA: ZERODIR XYZ", zero_dir.x, zero_dir.y, zero_dir.z, ""))
+            }
+        }
+        if let Some(com) = &self.center_of_mass {
+            records.push(format!("{:14.4}{:14.4}{:14.4}{:<18}CENTER OF MASS: XYZ", com.x, com.y, com.z, ""))
+        }
+        records.extend(self.records.iter().cloned());
+        records
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_site_event() {
+        let records = vec![
+            format!("{:<60}MARKER NAME", "ROVER"),
+            format!("{:<60}APPROX POSITION XYZ", "  4696989.6880   723994.1970  4239678.3040"),
+            format!("{:<60}ANTENNA: DELTA H/E/N", "        1.2340        0.0000        0.0000"),
+            format!("{:<20}{:<40}ANT # / TYPE", "TRM55971.00     NONE", "1234567"),
+            format!("{:<60}WAVELENGTH FACT L1/2", "unknown record"),
+        ];
+        let records: Vec<&str> = records.iter()
+            .map(|r| r.as_str())
+            .collect();
+        let event = SiteEvent::from_records(&records);
+        assert_eq!(event.station, Some(String::from("ROVER")));
+        assert_eq!(event.station_id, None);
+        assert_eq!(event.rcvr.is_none(), true);
+        assert_eq!(event.records.len(), 1);
+        assert_eq!(event.records[0].ends_with("WAVELENGTH FACT L1/2"), true);
+        let coords = event.coords.as_ref().unwrap();
+        assert_eq!((coords.x, coords.y, coords.z), (4696989.6880, 723994.1970, 4239678.3040));
+        let ant = event.ant.as_ref().unwrap();
+        assert_eq!(ant.model, "TRM55971.00     NONE");
+        assert_eq!(ant.sn, "1234567");
+        assert_eq!(ant.height, Some(1.234));
+        // reciprocal
+        let records = event.to_records();
+        let records: Vec<&str> = records.iter()
+            .map(|r| r.as_str())
+            .collect();
+        let parsed = SiteEvent::from_records(&records);
+        assert_eq!(parsed.station, event.station);
+        assert_eq!(parsed.ant.as_ref().unwrap().sn, "1234567");
+        assert_eq!(parsed.ant.as_ref().unwrap().height, Some(1.234));
+        assert_eq!(parsed.records, event.records);
+        assert!(is_event_flag(epoch::EpochFlag::NewSiteOccupation));
+        assert!(!is_event_flag(epoch::EpochFlag::PowerFailure));
+    }
+}
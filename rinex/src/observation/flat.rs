@@ -0,0 +1,108 @@
+//! Flattened observations, one row per measurement,
+//! for FFI (pyo3..) and columnar (Arrow, dataframes..) exports
+use crate::observation::Observable;
+use crate::observation::record::Record;
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+/// A single observation, flattened: made of plain fields only,
+/// so it converts to other languages and columnar formats
+/// without walking nested maps
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct FlatObservation {
+    /// Epoch, in seconds elapsed since 1970-01-01 00:00:00,
+    /// expressed in the time system of the record
+    pub epoch: f64,
+    /// Epoch flag, as found in the record
+    pub flag: u8,
+    /// Vehicule identification code, like "G01"
+    pub sv: String,
+    /// Observable code, like "C1C"
+    pub code: String,
+    /// Physical measurement, see [crate::observation::record::ObservationData]
+    pub value: f64,
+    /// Lock loss indicator bits, if provided
+    pub lli: Option<u8>,
+    /// Signal strength indicator, if provided
+    pub ssi: Option<u8>,
+}
+
+/// Flattens given record, rows are sorted by epoch,
+/// then by vehicule, then by observable
+pub fn flatten (record: &Record) -> Vec<FlatObservation> {
+    let mut rows: Vec<FlatObservation> = Vec::new();
+    for (epoch, (_, vehicules)) in record.iter() {
+        let seconds = epoch.date.timestamp() as f64
+            + epoch.date.timestamp_subsec_nanos() as f64 * 1.0E-9;
+        let flag = epoch.flag.to_string()
+            .parse::<u8>()
+            .unwrap_or(0);
+        for (sv, observations) in vehicules.iter() {
+            let sv_str = sv.to_string();
+            let mut codes: Vec<&Observable> = observations.keys().collect();
+            codes.sort();
+            for code in codes {
+                let data = observations[code];
+                rows.push(FlatObservation {
+                    epoch: seconds,
+                    flag,
+                    sv: sv_str.clone(),
+                    code: code.to_string(),
+                    value: data.obs,
+                    lli: data.lli.map(|lli| lli.bits()),
+                    ssi: data.ssi.map(|ssi| ssi as u8),
+                });
+            }
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sv;
+    use crate::epoch;
+    use crate::observation::record::{ObservationData, LliFlags, Ssi};
+    use std::str::FromStr;
+    use std::collections::{BTreeMap, HashMap};
+    #[test]
+    fn test_flatten() {
+        let e0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e1 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 30").unwrap(),
+            epoch::EpochFlag::PowerFailure);
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let c1c = Observable::from_str("C1C").unwrap();
+        let l1c = Observable::from_str("L1C").unwrap();
+        let mut record = Record::new();
+        for e in [e0, e1] {
+            let mut observations: HashMap<Observable, ObservationData> = HashMap::new();
+            observations.insert(l1c, ObservationData::new(1.0, Some(LliFlags::LOCK_LOSS), Some(Ssi::DbHz36_41)));
+            observations.insert(c1c, ObservationData::new(2.0, None, None));
+            let mut vehicules = BTreeMap::new();
+            vehicules.insert(g01, observations);
+            record.insert(e, (None, vehicules));
+        }
+        let rows = flatten(&record);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], FlatObservation {
+            epoch: 1654682400.0,
+            flag: 0,
+            sv: String::from("G01"),
+            code: String::from("C1C"),
+            value: 2.0,
+            lli: None,
+            ssi: None,
+        });
+        assert_eq!(rows[1].code, "L1C");
+        assert_eq!(rows[1].lli, Some(1));
+        assert_eq!(rows[1].ssi, Some(6));
+        assert_eq!(rows[3].epoch, 1654682430.0);
+        assert_eq!(rows[3].flag, 1);
+    }
+}
@@ -1,19 +1,35 @@
 //! `ObservationData` parser and related methods
-use std::collections::HashMap;
+use thiserror::Error;
+use std::collections::{BTreeMap, HashMap};
+use crate::sv;
+use crate::epoch;
 use crate::version;
 use crate::constellation::Constellation;
 
+pub mod event;
 pub mod record;
+pub mod observable;
+pub mod correlation;
+pub mod presence;
+pub mod flat;
+pub mod sparse;
+pub mod combination;
+pub mod phase_shift;
+
+pub use record::{LliFlags, Ssi, ObservationData};
+pub use observable::{Observable, Code};
+pub use event::{SiteEvent, SiteEvents};
+pub use phase_shift::{PhaseShift, apply_phase_shifts};
 
 #[cfg(feature = "with-serde")]
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
 #[cfg(feature = "with-serde")]
 use crate::formatter::datetime;
 
 /// Describes `Compact RINEX` specific information
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct Crinex {
     /// Compression program version
     pub version: version::Version,
@@ -27,13 +43,175 @@ pub struct Crinex {
 /// Describes known marker types
 /// Observation Record specific header fields
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct HeaderFields {
     /// Optional CRINEX information,
     /// only present on compressed OBS
     pub crinex: Option<Crinex>, 
     /// Observation codes present in this file, by Constellation
-    pub codes: HashMap<Constellation, Vec<String>>,
+    pub codes: HashMap<Constellation, Vec<Observable>>,
     /// True if epochs & data compensate for local clock drift
     pub clock_offset_applied: bool,
+    /// Time of first observation, if specified
+    pub time_of_first_obs: Option<epoch::Epoch>,
+    /// Time of last observation, if specified
+    pub time_of_last_obs: Option<epoch::Epoch>,
+    /// Time system in which epochs are expressed.
+    /// It is deduced from the constellation when not specified,
+    /// and is None in a mixed file that does not specify it (mandatory):
+    /// reported in `warnings` or rejected by strict parsing,
+    /// see [crate::Rinex::from_file_with_options]
+    pub time_system: Option<TimeSystem>,
+    /// Glonass FDMA channel numbers, per vehicule
+    pub glo_channels: HashMap<sv::Sv, i8>,
+    /// Carrier phase shifts, see [apply_phase_shifts]
+    pub phase_shifts: Vec<PhaseShift>,
+    /// Number of vehicules (# OF SATELLITES), if specified
+    pub nb_satellites: Option<usize>,
+    /// Number of observations, per vehicule and observable
+    /// (PRN / # OF OBS), if specified
+    pub nb_observations: BTreeMap<sv::Sv, HashMap<Observable, u32>>,
+}
+
+impl HeaderFields {
+    /// Reorders observables of given constellation, as they will be
+    /// described in the header and data lines of produced files.
+    /// Observables listed in `order` come first, in this order,
+    /// other observables follow in their current order.
+    /// Observables of `order` this constellation does not describe are ignored
+    pub fn sort_codes (&mut self, constellation: Constellation, order: &[Observable]) {
+        if let Some(codes) = self.codes.get_mut(&constellation) {
+            let mut sorted : Vec<Observable> = Vec::with_capacity(codes.len());
+            for code in order.iter().chain(codes.iter()) {
+                if codes.contains(code) && !sorted.contains(code) {
+                    sorted.push(*code)
+                }
+            }
+            *codes = sorted
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+/// Time system identification errors
+pub enum TimeSystemError {
+    #[error("unknown time system \"{0}\"")]
+    UnknownTimeSystem(String),
+}
+
+/// Time system, as described in TIME OF FIRST / LAST OBS
+#[derive(Copy, Clone, Debug)]
+#[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum TimeSystem {
+    /// GPS time
+    GPS,
+    /// Glonass time (UTC based)
+    GLO,
+    /// Galileo system time
+    GAL,
+    /// QZSS time
+    QZS,
+    /// BeiDou time
+    BDT,
+    /// IRNSS time
+    IRN,
+    /// Universal Coordinated Time
+    UTC,
+}
+
+impl TimeSystem {
+    /// Returns the time system a pure `constellation` file
+    /// is expressed in, when not specified.
+    /// Returns None for `Mixed` and `SBAS` constellations,
+    /// where the time system must be explicitly specified
+    pub fn from_constellation (constellation: Constellation) -> Option<Self> {
+        match constellation {
+            Constellation::GPS => Some(Self::GPS),
+            Constellation::Glonass => Some(Self::GLO),
+            Constellation::Galileo => Some(Self::GAL),
+            Constellation::QZSS => Some(Self::QZS),
+            Constellation::BeiDou => Some(Self::BDT),
+            Constellation::IRNSS => Some(Self::IRN),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for TimeSystem {
+    type Err = TimeSystemError;
+    fn from_str (s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "GPS" => Ok(Self::GPS),
+            "GLO" => Ok(Self::GLO),
+            "GAL" => Ok(Self::GAL),
+            "QZS" => Ok(Self::QZS),
+            "BDT" => Ok(Self::BDT),
+            "IRN" => Ok(Self::IRN),
+            "UTC" => Ok(Self::UTC),
+            _ => Err(TimeSystemError::UnknownTimeSystem(s.trim().to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for TimeSystem {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::GPS => f.write_str("GPS"),
+            Self::GLO => f.write_str("GLO"),
+            Self::GAL => f.write_str("GAL"),
+            Self::QZS => f.write_str("QZS"),
+            Self::BDT => f.write_str("BDT"),
+            Self::IRN => f.write_str("IRN"),
+            Self::UTC => f.write_str("UTC"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_time_system() {
+        for token in vec!["GPS", "GLO", "GAL", "QZS", "BDT", "IRN", "UTC"] {
+            let ts = TimeSystem::from_str(token);
+            assert_eq!(ts.is_ok(), true);
+            assert_eq!(ts.unwrap().to_string(), token);
+        }
+        assert_eq!(TimeSystem::from_str("XYZ").is_err(), true);
+        assert_eq!(TimeSystem::from_constellation(Constellation::BeiDou), Some(TimeSystem::BDT));
+        assert_eq!(TimeSystem::from_constellation(Constellation::Mixed), None);
+    }
+    #[test]
+    fn test_sort_codes() {
+        let codes : Vec<Observable> = vec!["C1C", "L1C", "S1C", "C5Q", "L5Q"]
+            .iter()
+            .map(|c| Observable::from_str(c).unwrap())
+            .collect();
+        let mut fields = HeaderFields {
+            crinex: None,
+            codes: HashMap::new(),
+            clock_offset_applied: false,
+            time_of_first_obs: None,
+            time_of_last_obs: None,
+            time_system: None,
+            glo_channels: HashMap::new(),
+            phase_shifts: Vec::new(),
+            nb_satellites: None,
+            nb_observations: BTreeMap::new(),
+        };
+        fields.codes.insert(Constellation::GPS, codes.clone());
+        let order : Vec<Observable> = vec!["L5Q", "C5Q", "D1C", "L5Q"]
+            .iter()
+            .map(|c| Observable::from_str(c).unwrap())
+            .collect();
+        fields.sort_codes(Constellation::GPS, &order);
+        assert_eq!(fields.codes[&Constellation::GPS], vec![
+            codes[4], codes[3], codes[0], codes[1], codes[2],
+        ]);
+        // not described: no effect
+        fields.sort_codes(Constellation::Galileo, &order);
+        assert_eq!(fields.codes.len(), 1);
+    }
 }
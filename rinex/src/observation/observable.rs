@@ -0,0 +1,236 @@
+//! Observation codes
+use thiserror::Error;
+use crate::channel::Channel;
+use crate::constellation::Constellation;
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+/// Observable parsing errors
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("invalid observable \"{0}\"")]
+    InvalidObservable(String),
+    #[error("unknown physics in observable \"{0}\"")]
+    UnknownPhysics(String),
+}
+
+/// Signal an observation was made on:
+/// carrier (frequency band) and tracking code,
+/// for example "1C" in "C1C": band 1, C/A code
+#[derive(Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct Code {
+    /// Frequency band identifier: '1', '2', '5'..,
+    /// or RINEX 2.12 legacy L1C tracking code: 'A', 'B', 'C', 'D'
+    pub band: char,
+    /// Tracking code or channel attribute: 'C', 'W', 'Q'..,
+    /// only specified in V3 and later revisions
+    pub attribute: Option<char>,
+}
+
+impl Code {
+    /// Builds a new `Code` from given band and attribute
+    pub fn new (band: char, attribute: Option<char>) -> Self {
+        Self {
+            band,
+            attribute,
+        }
+    }
+}
+
+/// Observation code, describes the physics of
+/// an observation and the signal it was made on.
+/// Observables are sorted like their RINEX code
+#[derive(Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Observable {
+    /// Pseudo range [m]: "C1C", "C1"
+    PseudoRange(Code),
+    /// Doppler shift [Hz]: "D1C", "D1"
+    Doppler(Code),
+    /// Ionospheric phase delay [cycles], V3.02 and later: "I1"
+    Ionosphere(Code),
+    /// Carrier phase [cycles]: "L1C", "L1"
+    Phase(Code),
+    /// V2 P code pseudo range [m]: "P1", "P2"
+    PCodePseudoRange(Code),
+    /// Signal strength, receiver dependent, usually [dB-Hz]: "S1C", "S1"
+    SSI(Code),
+    /// Receiver channel number, V3.02 and later: "X1"
+    ChannelNumber(Code),
+}
+
+impl Observable {
+    /// Returns signal this observation was made on
+    pub fn code (&self) -> Code {
+        match self {
+            Self::PseudoRange(c)
+            | Self::PCodePseudoRange(c)
+            | Self::Phase(c)
+            | Self::Doppler(c)
+            | Self::SSI(c)
+            | Self::Ionosphere(c)
+            | Self::ChannelNumber(c) => *c,
+        }
+    }
+    /// Returns frequency band identifier, see [Code::band]
+    pub fn band (&self) -> char {
+        self.code().band
+    }
+    /// Returns true if self is a pseudo range observation
+    pub fn is_pseudo_range (&self) -> bool {
+        matches!(self, Self::PseudoRange(_) | Self::PCodePseudoRange(_))
+    }
+    /// Returns true if self is a carrier phase observation
+    pub fn is_phase (&self) -> bool {
+        matches!(self, Self::Phase(_))
+    }
+    /// Returns true if self is a Doppler observation
+    pub fn is_doppler (&self) -> bool {
+        matches!(self, Self::Doppler(_))
+    }
+    /// Returns true if self is a signal strength observation
+    pub fn is_ssi (&self) -> bool {
+        matches!(self, Self::SSI(_))
+    }
+    /// Returns carrier frequency [MHz] of this observation,
+    /// made by a vehicule of given constellation, see [Channel::from_observable].
+    /// Returns None if the carrier could not be identified
+    pub fn carrier_frequency_mhz (&self, constellation: Constellation) -> Option<f64> {
+        Channel::from_observable(constellation, self)
+            .ok()
+            .map(|channel| channel.carrier_frequency_mhz())
+    }
+    /// Returns carrier wavelength [m] of this observation,
+    /// made by a vehicule of given constellation, see [Channel::from_observable].
+    /// Glonass wavelengths are evaluated at the center of the band,
+    /// use [Channel::from_sv_observable] to take the FDMA channel number into account.
+    /// Returns None if the carrier could not be identified
+    pub fn wavelength_m (&self, constellation: Constellation) -> Option<f64> {
+        Channel::from_observable(constellation, self)
+            .ok()
+            .map(|channel| channel.wavelength_m())
+    }
+    /// Returns physics identifier, as found in RINEX files
+    fn physics (&self) -> char {
+        match self {
+            Self::PseudoRange(_) => 'C',
+            Self::PCodePseudoRange(_) => 'P',
+            Self::Phase(_) => 'L',
+            Self::Doppler(_) => 'D',
+            Self::SSI(_) => 'S',
+            Self::Ionosphere(_) => 'I',
+            Self::ChannelNumber(_) => 'X',
+        }
+    }
+}
+
+#[cfg(feature = "with-serde")]
+impl Serialize for Observable {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "with-serde")]
+impl<'de> Deserialize<'de> for Observable {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        <Self as std::str::FromStr>::from_str(&s)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::str::FromStr for Observable {
+    type Err = Error;
+    fn from_str (content: &str) -> Result<Self, Self::Err> {
+        let content = content.trim();
+        let mut chars = content.chars();
+        let (physics, band, attribute) = match (chars.next(), chars.next(), chars.next(), chars.next()) {
+            (Some(p), Some(b), a, None) if b.is_ascii_alphanumeric()
+                && a.map(|a| a.is_ascii_alphanumeric()).unwrap_or(true) => (p, b, a),
+            _ => return Err(Error::InvalidObservable(content.to_string())),
+        };
+        let code = Code::new(band, attribute);
+        match physics {
+            'C' => Ok(Self::PseudoRange(code)),
+            'P' => Ok(Self::PCodePseudoRange(code)),
+            'L' => Ok(Self::Phase(code)),
+            'D' => Ok(Self::Doppler(code)),
+            'S' => Ok(Self::SSI(code)),
+            'I' => Ok(Self::Ionosphere(code)),
+            'X' => Ok(Self::ChannelNumber(code)),
+            _ => Err(Error::UnknownPhysics(content.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Observable {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let code = self.code();
+        let mut buf = [0_u8; 12];
+        let mut len = self.physics().encode_utf8(&mut buf).len();
+        len += code.band.encode_utf8(&mut buf[len..]).len();
+        if let Some(attribute) = code.attribute {
+            len += attribute.encode_utf8(&mut buf[len..]).len();
+        }
+        // padding is supported, for header formatting
+        f.pad(std::str::from_utf8(&buf[..len]).unwrap_or(""))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_observable() {
+        let obs = Observable::from_str("C1C").unwrap();
+        assert_eq!(obs, Observable::PseudoRange(Code::new('1', Some('C'))));
+        assert!(obs.is_pseudo_range());
+        assert_eq!(obs.band(), '1');
+        assert_eq!(obs.to_string(), "C1C");
+        let obs = Observable::from_str("P2").unwrap();
+        assert!(obs.is_pseudo_range());
+        assert_eq!(obs.code().attribute, None);
+        assert_eq!(obs.to_string(), "P2");
+        let obs = Observable::from_str("L5Q").unwrap();
+        assert!(obs.is_phase());
+        assert!(!obs.is_doppler());
+        assert!(Observable::from_str("D1").unwrap().is_doppler());
+        assert!(Observable::from_str("S7I").unwrap().is_ssi());
+        assert_eq!(Observable::from_str("LA").unwrap().band(), 'A');
+        assert_eq!(format!("{:>5}", Observable::from_str("C1").unwrap()), "   C1");
+        assert!(Observable::from_str("C").is_err());
+        assert!(Observable::from_str("C1CX").is_err());
+        assert!(Observable::from_str("Z1C").is_err());
+        let mut codes = vec![
+            Observable::from_str("L1C").unwrap(),
+            Observable::from_str("C2W").unwrap(),
+            Observable::from_str("C1C").unwrap(),
+        ];
+        codes.sort();
+        let codes: Vec<String> = codes
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(codes, vec!["C1C", "C2W", "L1C"]);
+    }
+    #[test]
+    fn test_wavelength() {
+        let l1c = Observable::from_str("L1C").unwrap();
+        assert_eq!(l1c.carrier_frequency_mhz(Constellation::GPS), Some(1575.42));
+        assert!((l1c.wavelength_m(Constellation::GPS).unwrap() - 0.190293672798).abs() < 1.0E-9);
+        let l9a = Observable::from_str("L9A").unwrap();
+        assert_eq!(l9a.carrier_frequency_mhz(Constellation::IRNSS), Some(2492.028));
+        assert_eq!(l9a.wavelength_m(Constellation::GPS), None);
+    }
+}
@@ -0,0 +1,200 @@
+//! Carrier phase shifts (SYS / PHASE SHIFT header records):
+//! quarter cycle corrections that align phase observations
+//! of a given signal to the reference signal of its frequency band
+use thiserror::Error;
+use std::str::FromStr;
+use crate::sv;
+use crate::constellation::{self, Constellation};
+use crate::observation::Observable;
+use crate::observation::observable;
+use crate::observation::record::Record;
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("failed to parse constellation")]
+    ConstellationError(#[from] constellation::Error),
+    #[error("failed to parse observable")]
+    ObservableError(#[from] observable::Error),
+    #[error("failed to parse correction")]
+    ParseFloatError(#[from] std::num::ParseFloatError),
+    #[error("failed to parse vehicule")]
+    SvError(#[from] sv::Error),
+}
+
+/// Phase shift correction of a carrier phase observable
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct PhaseShift {
+    /// Constellation this correction applies to
+    pub constellation: Constellation,
+    /// Carrier phase observable
+    pub observable: Observable,
+    /// Correction [cycles], None when unknown
+    pub correction: Option<f64>,
+    /// Vehicules this correction applies to,
+    /// empty when it applies to the whole constellation
+    pub vehicules: Vec<sv::Sv>,
+}
+
+impl PhaseShift {
+    /// Returns true if this correction applies to given vehicule
+    pub fn applies_to (&self, sv: &sv::Sv) -> bool {
+        if self.vehicules.is_empty() {
+            sv.constellation == self.constellation
+        } else {
+            self.vehicules.contains(sv)
+        }
+    }
+    /// Parses vehicules described on a continuation line
+    pub(crate) fn parse_vehicules (&mut self, content: &str) -> Result<(), Error> {
+        for item in content.split_ascii_whitespace() {
+            self.vehicules.push(sv::Sv::from_str(item)?)
+        }
+        Ok(())
+    }
+    /// Formats self as one or several header lines,
+    /// without the header marker
+    pub(crate) fn to_lines (&self) -> Vec<String> {
+        let mut first = format!("{} {}", self.constellation.to_1_letter_code(), self.observable);
+        if let Some(correction) = self.correction {
+            first.push_str(&format!(" {:8.5}", correction))
+        }
+        if self.vehicules.is_empty() {
+            return vec![first]
+        }
+        if self.correction.is_none() {
+            first.push_str(&format!("{:9}", ""))
+        }
+        first.push_str(&format!("  {:2}", self.vehicules.len()));
+        let mut lines: Vec<String> = Vec::new();
+        for (i, chunk) in self.vehicules.chunks(10).enumerate() {
+            let mut line = match i {
+                0 => first.clone(),
+                _ => format!("{:18}", ""),
+            };
+            for sv in chunk {
+                line.push_str(&format!(" {}", sv))
+            }
+            lines.push(line)
+        }
+        lines
+    }
+}
+
+impl std::str::FromStr for PhaseShift {
+    type Err = Error;
+    /// Parses the first line of a phase shift record,
+    /// vehicules described on following lines are not taken into account
+    fn from_str (content: &str) -> Result<Self, Self::Err> {
+        let (system, rem) = content.split_at(std::cmp::min(1, content.len()));
+        let constellation = Constellation::from_1_letter_code(system)?;
+        let code = rem.get(1..4).unwrap_or("").trim();
+        let observable = Observable::from_str(code)?;
+        let correction = match rem.get(4..13).unwrap_or("").trim() {
+            "" => None,
+            c => Some(f64::from_str(c)?),
+        };
+        let mut shift = Self {
+            constellation,
+            observable,
+            correction,
+            vehicules: Vec::new(),
+        };
+        // number of vehicules is implied by the list itself
+        shift.parse_vehicules(rem.get(17..).unwrap_or(""))?;
+        Ok(shift)
+    }
+}
+
+/// Applies given phase shifts to carrier phase observations of given record:
+/// corrections [cycles] are added to observations of matching observable and vehicule.
+/// Shifts with unknown correction are ignored.
+/// Example:
+/// ```
+/// use rinex::*;
+/// use rinex::observation::apply_phase_shifts;
+/// let mut rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+///     .unwrap();
+/// let shifts = rinex.header.obs
+///     .as_ref()
+///     .unwrap()
+///     .phase_shifts
+///     .clone();
+/// if let Some(record) = rinex.record.as_mut_obs() {
+///     apply_phase_shifts(record, &shifts);
+/// }
+/// ```
+pub fn apply_phase_shifts (record: &mut Record, shifts: &[PhaseShift]) {
+    for (_, (_, vehicules)) in record.iter_mut() {
+        for (sv, observations) in vehicules.iter_mut() {
+            for (observable, data) in observations.iter_mut() {
+                if !observable.is_phase() {
+                    continue
+                }
+                for shift in shifts {
+                    if shift.observable == *observable && shift.applies_to(sv) {
+                        if let Some(correction) = shift.correction {
+                            data.obs += correction
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::epoch;
+    use crate::observation::ObservationData;
+    use std::collections::{BTreeMap, HashMap};
+    #[test]
+    fn test_parsing() {
+        let shift = PhaseShift::from_str("G L2X -0.25000").unwrap();
+        assert_eq!(shift.constellation, Constellation::GPS);
+        assert_eq!(shift.observable, Observable::from_str("L2X").unwrap());
+        assert_eq!(shift.correction, Some(-0.25));
+        assert_eq!(shift.vehicules.len(), 0);
+        assert_eq!(shift.to_lines(), vec!["G L2X -0.25000"]);
+        let shift = PhaseShift::from_str("R L1C").unwrap();
+        assert_eq!(shift.correction, None);
+        assert_eq!(shift.to_lines(), vec!["R L1C"]);
+        let content = "G L2S -0.25000  12 G01 G02 G03 G04 G05 G06 G07 G08 G09 G10";
+        let mut shift = PhaseShift::from_str(content).unwrap();
+        shift.parse_vehicules(" G11 G12").unwrap();
+        assert_eq!(shift.vehicules.len(), 12);
+        assert_eq!(shift.to_lines(), vec![
+            content,
+            "                   G11 G12",
+        ]);
+    }
+    #[test]
+    fn test_apply() {
+        let e0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00.00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let g02 = sv::Sv::from_str("G02").unwrap();
+        let l2x = Observable::from_str("L2X").unwrap();
+        let c2x = Observable::from_str("C2X").unwrap();
+        let mut observations = HashMap::new();
+        observations.insert(l2x, ObservationData::new(100.0, None, None));
+        observations.insert(c2x, ObservationData::new(100.0, None, None));
+        let mut vehicules = BTreeMap::new();
+        vehicules.insert(g01, observations.clone());
+        vehicules.insert(g02, observations);
+        let mut record = Record::new();
+        record.insert(e0, (None, vehicules));
+        let mut shift = PhaseShift::from_str("G L2X -0.25000").unwrap();
+        shift.vehicules.push(g01);
+        apply_phase_shifts(&mut record, &[shift]);
+        let (_, vehicules) = &record[&e0];
+        assert_eq!(vehicules[&g01][&l2x].obs, 99.75);
+        assert_eq!(vehicules[&g01][&c2x].obs, 100.0);
+        assert_eq!(vehicules[&g02][&l2x].obs, 100.0);
+    }
+}
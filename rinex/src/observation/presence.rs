@@ -0,0 +1,101 @@
+//! Observable presence matrix, describes which observables
+//! were actually reported, for each vehicule
+use crate::sv;
+use crate::observation::Observable;
+use std::collections::BTreeMap;
+
+#[cfg(feature = "with-serde")]
+use serde::{Serialize, Deserialize};
+
+/// Presence matrix: number of epochs each observable
+/// was reported for, per vehicule
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct Matrix {
+    /// Vehicules, indexing the rows
+    pub vehicules: Vec<sv::Sv>,
+    /// Observables, indexing the columns
+    pub observables: Vec<Observable>,
+    /// Number of epochs an observable was reported for, per vehicule.
+    /// 0 means this vehicule never reported this observable
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl Matrix {
+    /// Builds a presence matrix from counts, sorted per vehicule
+    pub(crate) fn from_counts (counts: &BTreeMap<sv::Sv, BTreeMap<Observable, usize>>) -> Self {
+        let vehicules: Vec<sv::Sv> = counts.keys()
+            .copied()
+            .collect();
+        let mut observables: Vec<Observable> = counts.values()
+            .flat_map(|c| c.keys().copied())
+            .collect();
+        observables.sort();
+        observables.dedup();
+        let counts: Vec<Vec<usize>> = counts.values()
+            .map(|c| {
+                observables.iter()
+                    .map(|obs| c.get(obs).copied().unwrap_or(0))
+                    .collect()
+            })
+            .collect();
+        Self {
+            vehicules,
+            observables,
+            counts,
+        }
+    }
+    /// Returns number of epochs given observable was reported for, by given vehicule
+    pub fn count (&self, sv: &sv::Sv, observable: &Observable) -> usize {
+        let i = self.vehicules.iter().position(|s| s == sv);
+        let j = self.observables.iter().position(|o| o == observable);
+        match (i, j) {
+            (Some(i), Some(j)) => self.counts[i][j],
+            _ => 0,
+        }
+    }
+    /// Returns true if given vehicule reported given observable at least once
+    pub fn is_present (&self, sv: &sv::Sv, observable: &Observable) -> bool {
+        self.count(sv, observable) > 0
+    }
+    /// Returns vehicules that reported given observable at least once
+    pub fn vehicules_with (&self, observable: &Observable) -> Vec<sv::Sv> {
+        self.vehicules.iter()
+            .filter(|sv| self.is_present(sv, observable))
+            .copied()
+            .collect()
+    }
+    /// Returns observables reported by given vehicule at least once
+    pub fn observables_of (&self, sv: &sv::Sv) -> Vec<Observable> {
+        self.observables.iter()
+            .filter(|obs| self.is_present(sv, obs))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_presence_matrix() {
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let e05 = sv::Sv::from_str("E05").unwrap();
+        let c1c = Observable::from_str("C1C").unwrap();
+        let l5q = Observable::from_str("L5Q").unwrap();
+        let mut counts: BTreeMap<sv::Sv, BTreeMap<Observable, usize>> = BTreeMap::new();
+        counts.entry(g01).or_insert_with(BTreeMap::new).insert(c1c, 10);
+        counts.entry(e05).or_insert_with(BTreeMap::new).insert(c1c, 8);
+        counts.entry(e05).or_insert_with(BTreeMap::new).insert(l5q, 4);
+        let matrix = Matrix::from_counts(&counts);
+        assert_eq!(matrix.vehicules.len(), 2);
+        assert_eq!(matrix.observables, vec![c1c, l5q]);
+        assert_eq!(matrix.count(&g01, &c1c), 10);
+        assert_eq!(matrix.count(&g01, &l5q), 0);
+        assert_eq!(matrix.count(&e05, &l5q), 4);
+        assert_eq!(matrix.vehicules_with(&l5q), vec![e05]);
+        assert_eq!(matrix.observables_of(&g01), vec![c1c]);
+        assert_eq!(matrix.is_present(&sv::Sv::from_str("R01").unwrap(), &c1c), false);
+    }
+}
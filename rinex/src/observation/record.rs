@@ -2,7 +2,6 @@
 use std::io::Write;
 use thiserror::Error;
 use std::str::FromStr;
-use chrono::Timelike;
 use bitflags::bitflags;
 use std::collections::{BTreeMap, HashMap};
 
@@ -10,17 +9,22 @@ use crate::sv;
 use crate::epoch;
 use crate::header;
 use crate::version;
-use crate::constellation;
+use crate::channel;
+use crate::observation::Observable;
+use crate::observation::event::{SiteEvent, SiteEvents, is_event_flag};
+use crate::hatanaka;
+use crate::parser;
+use crate::record::Comments;
 use crate::constellation::Constellation;
 use crate::constellation::augmentation::Augmentation;
 
 #[cfg(feature = "with-serde")]
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
 /// `Ssi` describes signals strength
 #[repr(u8)]
 #[derive(PartialOrd, Ord, PartialEq, Eq, Copy, Clone, Debug)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum Ssi {
     /// Ssi ~= 0 dB/Hz
     DbHz0 = 0,
@@ -90,7 +94,7 @@ impl Ssi {
 }
 
 bitflags! {
-    #[cfg_attr(feature = "with-serde", derive(Serialize))]
+    #[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
     pub struct LliFlags: u8 {
         /// Current epoch is marked Ok or Unknown status 
         const OK_OR_UNKNOWN = 0x00;
@@ -108,9 +112,14 @@ bitflags! {
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
-#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub struct ObservationData {
-	/// physical measurement
+	/// physical measurement, as found in the file:
+	///  + pseudo ranges (C, P codes) are expressed in meters
+	///  + carrier phases (L codes) are expressed in carrier cycles,
+	///  see [channel::Channel::cycles_to_m] for conversion
+	///  + dopplers (D codes) are expressed in Hz
+	///  + signal strengths (S codes) are receiver dependent, usually dB-Hz
 	pub obs: f64,
 	/// Lock loss indicator 
 	pub lli: Option<LliFlags>,
@@ -148,7 +157,7 @@ impl ObservationData {
     /// - sv_offset: sv clock offset
     /// - bias: other (optionnal..) additive biases
     pub fn pr_real_distance (&self, rcvr_offset: f64, sv_offset: f64, biases: f64) -> f64 {
-        self.obs + 299_792_458.0_f64 * (rcvr_offset - sv_offset) + biases
+        self.obs + channel::SPEED_OF_LIGHT_M_S * (rcvr_offset - sv_offset) + biases
     }
 }
 
@@ -157,10 +166,13 @@ impl ObservationData {
 /// Measurements are of two kinds:
 ///  + Option<f64>: receiver clock offsets for OBS data files where   
 ///    receiver clock offsets are 'applied'    
-///  + map of ObservationData (physical measurements) sorted by `Sv` and by observation codes 
+///  + map of ObservationData (physical measurements) sorted by `Sv` and by observation codes.
+///
+/// Output is deterministic: vehicules are written in `Sv` order
+/// (constellation, then PRN #) and observables in header order
 pub type Record = BTreeMap<epoch::Epoch, 
     (Option<f64>, 
-    BTreeMap<sv::Sv, HashMap<String, ObservationData>>)>;
+    BTreeMap<sv::Sv, HashMap<Observable, ObservationData>>)>;
 
 #[derive(Error, Debug)]
 /// OBS Data `Record` parsing specific errors
@@ -177,6 +189,8 @@ pub enum Error {
     ParseFloatError(#[from] std::num::ParseFloatError),
     #[error("failed to parse vehicules properly (n_sat mismatch)")]
     EpochParsingError,
+    #[error("unknown constellation for vehicule \"{0}\"")]
+    UnknownConstellation(String),
 }
 
 /// Returns true if given content matches a new OBSERVATION data epoch
@@ -219,14 +233,33 @@ pub fn is_new_epoch (line: &str, v: version::Version) -> bool {
 }
 
 /// Builds `Record` entry for `ObservationData`
-/// from given epoch content
+/// from given epoch content.
+/// Vehicules of a constellation we can't interpret are skipped
 pub fn build_record_entry (header: &header::Header, content: &str)
-        -> Result<(epoch::Epoch, Option<f64>, BTreeMap<sv::Sv, HashMap<String, ObservationData>>), Error> 
+        -> Result<(epoch::Epoch, Option<f64>, BTreeMap<sv::Sv, HashMap<Observable, ObservationData>>), Error> 
 {
-    let mut lines = content.lines();
-    let mut line = lines.next()
-        .unwrap();
+    let (epoch, clock_offset, map, _) = build_record_entry_with_options(header, content, &parser::ParserOptions::default())?;
+    Ok((epoch, clock_offset, map))
+}
+
+/// Identifies constellation from given vehicule identifier,
+/// returns None if we can't interpret this constellation
+fn identify_constellation (identifier: char) -> Option<Constellation> {
+    match identifier {
+        'G' => Some(Constellation::GPS),
+        'R' => Some(Constellation::Glonass),
+        'J' => Some(Constellation::QZSS),
+        'E' => Some(Constellation::Galileo),
+        'C' => Some(Constellation::BeiDou),
+        //'H' => Constellation::SBAS(Augmentation::default()),
+        'S' => Some(Constellation::SBAS(Augmentation::default())),
+        _ => None,
+    }
+}
 
+/// Parses epoch descriptor (without V3 marker): returns epoch,
+/// number of vehicules (or special records) announced, and remaining content
+fn parse_epoch_descriptor<'a> (header: &header::Header, line: &'a str) -> Result<(epoch::Epoch, u16, &'a str), Error> {
     // epoch::
     let mut offset : usize = 
         2+1 // Y
@@ -240,24 +273,61 @@ pub fn build_record_entry (header: &header::Header, content: &str)
     if header.version.major > 2 {
         offset += 2
     }
-
-    // V > 2 might start with a ">" marker
-    if line.starts_with(">") {
-        line = line.split_at(1).1.clone();
+    if line.len() < offset + 6 {
+        return Err(Error::EpochParsingError)
     }
 
     let (date, rem) = line.split_at(offset);
     let (flag, rem) = rem.split_at(3);
-    let (n_sat, mut rem) = rem.split_at(3);
+    let (n_sat, rem) = rem.split_at(3);
     let n_sat = u16::from_str_radix(n_sat.trim(), 10)?;
-    let n_sv_line : usize = num_integer::div_ceil(n_sat, 12).into();
 
     let flag = epoch::EpochFlag::from_str(flag.trim())?;
     let date = epoch::str2date(date)?; 
-    let epoch = epoch::Epoch::new(date, flag);
+    Ok((epoch::Epoch::new(date, flag), n_sat, rem))
+}
+
+/// Builds a [SiteEvent] from given epoch content, if this epoch
+/// is flagged 2 to 5: the special records that follow
+/// are header records. Returns None on regular epochs
+pub fn build_event_entry (header: &header::Header, content: &str) -> Result<Option<(epoch::Epoch, SiteEvent)>, Error> {
+    let mut lines = content.lines();
+    let mut line = lines.next()
+        .unwrap_or("");
+    if line.starts_with(">") {
+        line = line.split_at(1).1;
+    }
+    let (epoch, n_records, _) = parse_epoch_descriptor(header, line)?;
+    if !is_event_flag(epoch.flag) {
+        return Ok(None)
+    }
+    let records: Vec<&str> = lines
+        .take(n_records.into())
+        .collect();
+    Ok(Some((epoch, SiteEvent::from_records(&records))))
+}
+
+/// Builds `Record` entry for `ObservationData`
+/// from given epoch content, with given parsing options.
+/// Also returns entries we could not interpret, when not considered as an error
+pub fn build_record_entry_with_options (header: &header::Header, content: &str, opts: &parser::ParserOptions)
+        -> Result<(epoch::Epoch, Option<f64>, BTreeMap<sv::Sv, HashMap<Observable, ObservationData>>, Vec<parser::RawEntry>), Error> 
+{
+    let mut lines = content.lines();
+    let mut line = lines.next()
+        .ok_or(Error::EpochParsingError)?;
 
-    let mut sv_list : Vec<sv::Sv> = Vec::with_capacity(24);
-	let mut map : BTreeMap<sv::Sv, HashMap<String, ObservationData>> = BTreeMap::new();
+    // V > 2 might start with a ">" marker
+    if line.starts_with(">") {
+        line = line.split_at(1).1.clone();
+    }
+
+    let (epoch, n_sat, mut rem) = parse_epoch_descriptor(header, line)?;
+    let n_sv_line : usize = num_integer::div_ceil(n_sat, 12).into();
+
+    // vehicules we could not interpret
+    let mut unknown : Vec<parser::RawEntry> = Vec::new();
+	let mut map : BTreeMap<sv::Sv, HashMap<Observable, ObservationData>> = BTreeMap::new();
 	
     // all encountered obs codes
     let obs = header.obs
@@ -308,38 +378,37 @@ pub fn build_record_entry (header: &header::Header, content: &str)
     if header.version.major < 3 {
         // old fashion:
         //   Sv list is passed on 1st and possible several lines
+        //   vehicules we can't interpret are stored as their identification code
+        let mut sv_list : Vec<Result<sv::Sv, String>> = Vec::with_capacity(24);
         let mut offset : usize = 0;
         for _ in 0..n_sv_line {
             loop {
-                let sv_str = &rem[offset..offset+3];
+                let sv_str = rem.get(offset..offset+3)
+                    .ok_or(Error::EpochParsingError)?; // truncated epoch
                 let identifier = sv_str.chars().nth(0)
                     .unwrap(); 
-                let prn = u8::from_str(&sv_str[1..].trim())?;
                 // build `sv` 
-                let sv : sv::Sv = match identifier.is_ascii_whitespace() {
-                    true => sv::Sv::new(header.constellation.unwrap(), prn),
-                    false => {
-                        let constell : Constellation = match identifier {
-                            'G' => Constellation::GPS,
-                            'R' => Constellation::Glonass,
-                            'J' => Constellation::QZSS,
-                            'E' => Constellation::Galileo,
-                            'C' => Constellation::BeiDou,
-                            'S' => Constellation::SBAS(Augmentation::default()),
-                            _ => return Err(
-                                Error::SvError(
-                                    sv::Error::ConstellationError(
-                                        constellation::Error::UnknownCode(identifier.to_string())))),
-                        };
-                        sv::Sv::new(constell, prn)
+                let constell = match identifier.is_ascii_whitespace() {
+                    true => header.constellation,
+                    false => identify_constellation(identifier),
+                };
+                let sv = match constell {
+                    Some(constell) if obs_codes.contains_key(&constell) => {
+                        let prn = u8::from_str(&sv_str[1..].trim())?;
+                        Ok(sv::Sv::new(constell, prn))
+                    },
+                    _ => {
+                        if opts.unknown_constellation == parser::UnknownConstellation::Error {
+                            return Err(Error::UnknownConstellation(sv_str.to_string()))
+                        }
+                        Err(sv_str.to_string())
                     },
                 };
-                
                 sv_list.push(sv);
                 offset += 3;
                 if offset == rem.len() {
                     line = lines.next()
-                        .unwrap();
+                        .ok_or(Error::EpochParsingError)?; // truncated epoch
                     rem = line.trim();
                     offset = 0;
                     break
@@ -353,92 +422,51 @@ pub fn build_record_entry (header: &header::Header, content: &str)
         }
 
 		for i in 0..sv_list.len() { // per vehicule
-			let mut offset : usize = 0;
-			let mut obs_map : HashMap<String, ObservationData> = HashMap::new();
+			let mut obs_map : HashMap<Observable, ObservationData> = HashMap::new();
 
 			// old RINEX revision : using previously identified Sv 
-			let sv : sv::Sv = sv_list[i]; 
-			let codes =  obs_codes
-                .get(&sv.constellation)
-                .unwrap();
-			let mut code_index : usize = 0;
-			loop { // per obs code
-				let code = &codes[code_index];
-				let obs : Option<f64> = match line.len() < offset+14 { 
-					true => {
-						// cant' grab a new measurement
-						//  * line is empty: contains only empty measurements
-						//  * end of line is reached
-						None
-					},
-					false => {
-						let obs = &line[offset..offset+14];
-						if let Ok(f) = f64::from_str(&obs.trim()) {
-							Some(f)
-						} else {
-							None // empty field
-						}
-					},
-				};
-
-				let lli : Option<LliFlags> = match line.len() < offset+14+1 {
-					true => {
-						// can't parse lli here
-						// 	* line is over and this measurement
-						//    does not have lli nor ssi 
-						None
-					},
-					false => {
-						let lli = &line[offset+14..offset+14+1];
-						if let Ok(lli) = u8::from_str_radix(&lli, 10) {
-                            LliFlags::from_bits(lli)
-                        } else {
-                            None
-                        }
-					},
-				};
-
-				let ssi : Option<Ssi> = match line.len() < offset+14+2 {
-					true => {
-						// can't parse ssi here
-						// 	* line is over and this measurement
-						//    does not have ssi 
-						None
-					},
-					false => {
-						let ssi = &line[offset+14+1..offset+14+2];
-						let ssi = match Ssi::from_str(ssi) {
-							Ok(ssi) => Some(ssi),
-							Err(_) => None, // ssi field is empty
-						};
-						ssi
-					},
-				};
-				
-				if let Some(obs) = obs { // parsed something
-					let obs = ObservationData::new(obs, lli, ssi);
-					obs_map.insert(code.to_string(), obs); 
-				}
-				
-				code_index += 1;
-				if code_index == obs_codes.len() {
-					break // last code that system sv
-				}
-				
-				offset += 14 // F14.3
-					+1  // +lli
-					+1; // +ssi
-
-				if offset >= line.len() {
-					// we just parsed the last
-					// code for this line
-					offset = 0;
+			let sv = &sv_list[i]; 
+			let codes = match sv {
+                Ok(sv) => &obs_codes[&sv.constellation],
+                // observables are shared by all systems in old RINEX
+                Err(_) => obs_codes.values().next().unwrap(),
+            };
+            // content of this vehicule, as is
+            let mut raw : Vec<String> = vec![line.to_string()];
+			for (index, code) in codes.iter().enumerate() { // per obs code
+				if index > 0 && index % 5 == 0 {
+					// 5 observations per line
 					if let Some(l) = lines.next() {
 						line = l;
+						raw.push(line.to_string());
 					}
 				}
+				// F14.3 +lli +ssi: one field per code, blank fields included
+				let offset = (index % 5) * (14+1+1);
+				let field = line.get(offset..).unwrap_or("");
+				let obs : Option<f64> = field.get(..14)
+					.unwrap_or(field)
+					.trim()
+					.parse::<f64>()
+					.ok(); // empty field or end of line
+				let lli : Option<LliFlags> = field.get(14..15)
+					.and_then(|lli| u8::from_str_radix(lli, 10).ok())
+					.and_then(LliFlags::from_bits);
+				let ssi : Option<Ssi> = field.get(15..16)
+					.and_then(|ssi| Ssi::from_str(ssi).ok()); // ssi field is empty
+				if let Some(obs) = obs { // parsed something
+					obs_map.insert(*code, ObservationData::new(obs, lli, ssi));
+				}
 			} // for all obs code
-            map.insert(sv, obs_map);
+            match sv {
+                Ok(sv) => {
+                    map.insert(*sv, obs_map);
+                },
+                Err(code) => unknown.push(parser::RawEntry {
+                    sv: code.to_string(),
+                    content: raw,
+                }),
+            }
 			if let Some(l) = lines.next() {
 				line = l;
 			} else {
@@ -455,174 +483,223 @@ pub fn build_record_entry (header: &header::Header, content: &str)
 			}
 			
 			// parse Sv and identify
+			if line.len() < 3 || !line.is_char_boundary(3) {
+				return Err(Error::EpochParsingError) // truncated epoch
+			}
 			let (sv, rem) = line.split_at(3);
 			let identifier = sv.chars().nth(0)
 				.unwrap();
+            let constell = match identify_constellation(identifier) {
+                Some(constell) if obs_codes.contains_key(&constell) => constell,
+                _ => {
+                    if opts.unknown_constellation == parser::UnknownConstellation::Error {
+                        return Err(Error::UnknownConstellation(sv.to_string()))
+                    }
+                    unknown.push(parser::RawEntry {
+                        sv: sv.to_string(),
+                        content: vec![line.to_string()],
+                    });
+                    continue
+                },
+            };
 			let prn = u8::from_str_radix(&sv[1..].trim(),10)?;
-			let constell : Constellation = match identifier {
-				'G' => Constellation::GPS,
-				'R' => Constellation::Glonass,
-				'J' => Constellation::QZSS,
-				'E' => Constellation::Galileo,
-				'C' => Constellation::BeiDou,
-				//'H' => Constellation::SBAS(Augmentation::default()),
-                'S' => Constellation::SBAS(Augmentation::default()),
-				_ => return Err(
-                        Error::SvError(
-                            sv::Error::ConstellationError(
-                                constellation::Error::UnknownCode(identifier.to_string())))),
-			};
 			let sv = sv::Sv::new(constell, prn);
 			// retrieve obs code for that system
 			let codes =  &obs_codes[&constell];
-			let mut offset : usize = 0;
-			let mut code_index : usize = 0;
-			let mut obs_map : HashMap<String, ObservationData> = HashMap::new();
-			loop { // per obs code
-				let code = &codes[code_index];
-				let obs = &rem[offset..offset+14];
-				let obs : Option<f64> = match f64::from_str(&obs.trim()) {
-					Ok(f) => Some(f),
-					Err(_) => None, // empty field
-				};
-				let lli : Option<LliFlags> = match rem.len() < offset+14+1 {
-					true => {
-						// can't parse lli here,
-						// line is terminated by an OBS without lli nor ssi
-						None
-					},
-					false => {
-						let lli = &rem[offset+14..offset+14+1];
-						if let Ok(lli) = u8::from_str_radix(&lli, 10) {
-                            LliFlags::from_bits(lli)
-                        } else {
-                            None
-                        }
-					},
+			let mut obs_map : HashMap<Observable, ObservationData> = HashMap::new();
+			for (index, code) in codes.iter().enumerate() { // per obs code
+				// F14.3 +lli +ssi: one field per code, blank fields included
+				let offset = index * (14+1+1);
+				let field = match rem.get(offset..) {
+					Some(field) if !field.is_empty() => field,
+					_ => break, // line is over: remaining codes were not observed
 				};
-				let ssi : Option<Ssi> = match rem.len() < offset+14+2 {
-					true => {
-						// can't parse ssi here,
-						// line is terminated by an OBS without ssi
-						None
-					},
-					false => {
-						let ssi = &rem[offset+14+1..offset+14+2];
-						let ssi = match Ssi::from_str(ssi) {
-							Ok(ssi) => Some(ssi),
-							Err(_) => None, // ssi field is empty
-						};
-						ssi
-					},
-				};
-
+				let obs : Option<f64> = field.get(..14)
+					.unwrap_or(field)
+					.trim()
+					.parse::<f64>()
+					.ok(); // empty field
+				let lli : Option<LliFlags> = field.get(14..15)
+					.and_then(|lli| u8::from_str_radix(lli, 10).ok())
+					.and_then(LliFlags::from_bits);
+				let ssi : Option<Ssi> = field.get(15..16)
+					.and_then(|ssi| Ssi::from_str(ssi).ok()); // ssi field is empty
 				if let Some(obs) = obs { // parsed something
-					let obs = ObservationData::new(obs, lli, ssi);
-					obs_map.insert(code.to_string(), obs);
-					code_index += 1;
-				}
-				
-				offset += 14 // F14.3
-					+1  // +lli
-					+1; // +ssi
-				
-				if offset >= rem.len() { // done parsing this line
-					map.insert(sv, obs_map);
-					break
+					obs_map.insert(*code, ObservationData::new(obs, lli, ssi));
 				}
 			} // per obs code
+			map.insert(sv, obs_map);
 		} // per sat
 	} // V>2
-    Ok((epoch, clock_offset, map))
+    Ok((epoch, clock_offset, map, unknown))
 }
 
-/// Pushes observation record into given file writer
-pub fn to_file (header: &header::Header, record: &Record, mut writer: std::fs::File) -> std::io::Result<()> {
-    for (epoch, (clock_offset, sv)) in record.iter() {
-        let date = epoch.date;
-        let flag = epoch.flag;
-        let vehicules = sv.keys();
-        let nb_sv = vehicules.len(); 
-        let obscodes = &header.obs.as_ref().unwrap().codes;
-        // first line(s)
-        //   Epoch + flag + svnn + possible clock offset
-        match header.version.major {
-            1|2 => {
-                write!(writer, " {} ",  date.format("%y %m %d %H %M").to_string())?;
-                write!(writer, " {}         ", date.time().second())?;
-                write!(writer, " {}", flag)?; 
-                write!(writer, " {}", nb_sv)?; 
-                let nb_extra = nb_sv / 12;
-                let mut index = 0;
-                for vehicule in vehicules.into_iter() {
-                    write!(writer, "{}", vehicule)?; 
-                    if (index+1) % 12 == 0 {
-                        if let Some(clock_offset) = clock_offset {
-                            write!(writer, "{:3.9}", clock_offset)?
-                        }
-                        write!(writer, "\n                                ")?
-                    }
-                    index += 1
-                }
-                if nb_extra == 0 {
-                    if let Some(clock_offset) = clock_offset {
-                        let _ = write!(writer, "{:3.9}\n", clock_offset);
-                    } else {
-                        let _ = write!(writer, "\n");
-                    }
-                }
-            },
-            _ => { // Modern revisions 
-                write!(writer, "> {} ",  date.format("%Y %m %d %H %M").to_string())?;
-                write!(writer, " {}         ", date.time().second())?;
-                write!(writer, " {} ", flag)?; 
-                write!(writer, " {}", nb_sv)?; 
-                if let Some(clock_offset) = clock_offset {
-                    write!(writer, "{:.12}", clock_offset)?
-                }
-                write!(writer, "\n")?
+/// Streams observation record into given writer, epoch by epoch.
+/// Record is compressed on the fly, if `header` describes a CRINEX:
+/// only the epoch being compressed is buffered.
+/// Site `events` are inserted chronologically, with their special records
+/// and the `comments` attached to them.
+/// `raw` entries are passed through as is. They can't be compressed:
+/// the observables of vehicules we could not interpret are unknown,
+/// see [check_production]
+pub fn to_writer (header: &header::Header, record: &Record, events: &SiteEvents, comments: &Comments, raw: &parser::RawRecord, writer: &mut impl Write) -> std::io::Result<()> {
+    to_writer_with_statistics(header, record, events, comments, raw, writer)?;
+    Ok(())
+}
+
+/// Verifies given record content can be produced with given `header`:
+/// CRINEX production fails if `raw` entries are to be passed through
+pub fn check_production (header: &header::Header, raw: &parser::RawRecord) -> std::io::Result<()> {
+    if header.is_crinex() && !raw.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+            "raw entries (unknown vehicules) can't be Hatanaka compressed"))
+    }
+    Ok(())
+}
+
+/// Same as [to_writer], also returns the compression statistics
+/// when `header` describes a CRINEX
+pub fn to_writer_with_statistics (header: &header::Header, record: &Record, events: &SiteEvents, comments: &Comments, raw: &parser::RawRecord, writer: &mut impl Write) -> std::io::Result<Option<hatanaka::Statistics>> {
+    check_production(header, raw)?;
+    let mut events = events.iter().peekable();
+    if header.is_crinex() {
+        let mut compressor = hatanaka::Compressor::new(8);
+        let mut buffer : Vec<u8> = Vec::with_capacity(4096);
+        let mut compress = |buffer: &[u8], writer: &mut dyn Write| -> std::io::Result<()> {
+            let content = String::from_utf8_lossy(buffer);
+            let compressed = compressor.compress(header, &content)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            writer.write_all(compressed.as_bytes())
+        };
+        for (epoch, (clock_offset, sv)) in record.iter() {
+            while let Some((e, event)) = events.next_if(|(e, _)| *e < epoch) {
+                buffer.clear();
+                write_event(header, e, event, comments, &mut buffer)?;
+                compress(&buffer, writer)?
             }
+            buffer.clear();
+            write_epoch(header, epoch, clock_offset, sv, &[], &mut buffer)?;
+            compress(&buffer, writer)?
         }
-        // epoch body
-        let mut index = 0;
-        for (sv, obs) in sv.iter() {
-            let mut modulo = 5;
-            if header.version.major > 2 {
-                // modern RINEX
-                modulo = 100000; // 'infinite': no wrapping
-                    // we behave like CRX2RNX which does not respect the standards,
-                    // we should wrap @ 80 once again
-                let _ = write!(writer, "{} ", sv);
-            } else {
-                let _ = write!(writer, " ");
+        for (e, event) in events {
+            buffer.clear();
+            write_event(header, e, event, comments, &mut buffer)?;
+            compress(&buffer, writer)?
+        }
+        Ok(Some(compressor.statistics().clone()))
+    } else {
+        for (epoch, (clock_offset, sv)) in record.iter() {
+            while let Some((e, event)) = events.next_if(|(e, _)| *e < epoch) {
+                write_event(header, e, event, comments, writer)?
             }
-            // observables for this constellation
-            // --> respect header order and data might be missing
-            let codes = &obscodes[&sv.constellation];
-            for code in codes.iter() {
-                if let Some(data) = obs.get(code) {
-                    let _ = write!(writer, "{:13.3}", data.obs);
-                    if let Some(lli) = data.lli {
-                        let _ = write!(writer, "{}", lli.bits());
-                    } else {
-                        let _ = write!(writer, " ");
-                    }
-                    if let Some(ssi) = data.ssi {
-                        let _ = write!(writer, "{}", ssi as u8);
-                    } else {
-                        let _ = write!(writer, " ");
-                    }
-                    if (index+1) % modulo == 0 {
-                        let _ = write!(writer, "\n");
-                    }
-                    let _ = write!(writer, " ");
+            let raw = match raw.get(epoch) {
+                Some(entries) => entries.as_slice(),
+                None => &[],
+            };
+            write_epoch(header, epoch, clock_offset, sv, raw, writer)?
+        }
+        for (e, event) in events {
+            write_event(header, e, event, comments, writer)?
+        }
+        Ok(None)
+    }
+}
+
+/// Writes given site event into given writer: epoch descriptor,
+/// followed by its special records and the comments attached to it
+fn write_event (header: &header::Header, epoch: &epoch::Epoch, event: &SiteEvent, comments: &Comments, writer: &mut impl Write) -> std::io::Result<()> {
+    let mut records = event.to_records();
+    if let Some(comments) = comments.get(epoch) {
+        for comment in comments {
+            records.push(format!("{:<60}COMMENT", comment))
+        }
+    }
+    match header.version.major {
+        1|2 => writeln!(writer, " {:#}{:3}", epoch, records.len())?,
+        _ => writeln!(writer, "> {}{:3}", epoch, records.len())?,
+    }
+    for record in records {
+        writeln!(writer, "{}", record)?
+    }
+    Ok(())
+}
+
+/// Writes given epoch into given writer,
+/// `raw` entries are appended as is
+fn write_epoch (header: &header::Header, epoch: &epoch::Epoch, clock_offset: &Option<f64>, sv: &BTreeMap<sv::Sv, HashMap<Observable, ObservationData>>, raw: &[parser::RawEntry], writer: &mut impl Write) -> std::io::Result<()> {
+    let vehicules = sv.keys()
+        .map(|sv| sv as &dyn std::fmt::Display)
+        .chain(raw.iter().map(|entry| &entry.sv as &dyn std::fmt::Display));
+    let nb_sv = sv.len() + raw.len(); 
+    let obscodes = &header.obs.as_ref().unwrap().codes;
+    // first line(s)
+    //   Epoch + flag + svnn + possible clock offset
+    match header.version.major {
+        1|2 => {
+            // 12 vehicules per line, F12.9 clock offset on first line
+            let vehicules : Vec<String> = vehicules
+                .map(|vehicule| vehicule.to_string())
+                .collect();
+            let mut lines = vehicules.chunks(12);
+            let first : String = lines.next()
+                .map(|chunk| chunk.concat())
+                .unwrap_or_default();
+            write!(writer, " {:#}{:3}{}", epoch, nb_sv, first)?;
+            if let Some(clock_offset) = clock_offset {
+                write!(writer, "{}{:12.9}", " ".repeat(36usize.saturating_sub(first.len())), clock_offset)?;
+            }
+            write!(writer, "\n")?;
+            for chunk in lines {
+                write!(writer, "                                {}\n", chunk.concat())?;
+            }
+        },
+        _ => { // Modern revisions 
+            write!(writer, "> {}", epoch)?;
+            write!(writer, "{:3}", nb_sv)?; 
+            if let Some(clock_offset) = clock_offset {
+                write!(writer, "      {:15.12}", clock_offset)?
+            }
+            write!(writer, "\n")?
+        }
+    }
+    // epoch body: F14.3 +lli +ssi per observable,
+    // observables for this constellation respect header order,
+    // missing data is filled with whitespaces
+    for (sv, obs) in sv.iter() {
+        let codes = match obscodes.get(&sv.constellation) {
+            Some(codes) => codes,
+            None => continue,
+        };
+        if header.version.major > 2 {
+            // modern RINEX: no wrapping
+            write!(writer, "{}", sv)?;
+        }
+        for (index, code) in codes.iter().enumerate() {
+            if header.version.major < 3 && index > 0 && index % 5 == 0 {
+                // old RINEX: 5 observations per line
+                write!(writer, "\n")?;
+            }
+            if let Some(data) = obs.get(code) {
+                write!(writer, "{:14.3}", data.obs)?;
+                if let Some(lli) = data.lli {
+                    write!(writer, "{}", lli.bits())?;
+                } else {
+                    write!(writer, " ")?;
+                }
+                if let Some(ssi) = data.ssi {
+                    write!(writer, "{}", ssi as u8)?;
                 } else {
-                    // obs is missing, simply fill with whitespace
-                    let _ = write!(writer, "                ");
+                    write!(writer, " ")?;
                 }
-                index += 1
+            } else {
+                write!(writer, "                ")?;
             }
-            write!(writer, "\n")?
+        }
+        write!(writer, "\n")?
+    }
+    for entry in raw.iter() {
+        for line in entry.content.iter() {
+            write!(writer, "{}\n", line)?
         }
     }
     Ok(())
@@ -707,4 +784,54 @@ mod test {
             false
         );
     }
+    #[test]
+    fn v3_blank_fields() {
+        let gps = ["C1C", "L1C", "S1C"]
+            .iter()
+            .map(|c| Observable::from_str(c).unwrap())
+            .collect::<Vec<_>>();
+        let mut codes = HashMap::new();
+        codes.insert(Constellation::GPS, gps.clone());
+        let mut header = header::Header::basic_obs()
+            .with_version(version::Version::new(3, 5))
+            .with_constellation(Constellation::GPS);
+        header.obs = Some(crate::observation::HeaderFields {
+            crinex: None,
+            codes,
+            clock_offset_applied: false,
+            time_of_first_obs: None,
+            time_of_last_obs: None,
+            time_system: None,
+            glo_channels: HashMap::new(),
+            phase_shifts: Vec::new(),
+            nb_satellites: None,
+            nb_observations: BTreeMap::new(),
+        });
+        // G01: blank C1C, S1C without lli nor ssi
+        // G02: line is over after C1C
+        // G03: truncated L1C field
+        let content = "> 2022 01 09 00 00 30.0000000  0  3
+G01                117352685.28218        48.950
+G02  22331467.880  
+G03  22331469.280     1173526";
+        let (_, _, map) = build_record_entry(&header, content)
+            .unwrap();
+        let g01 = &map[&sv::Sv::new(Constellation::GPS, 1)];
+        assert_eq!(g01.len(), 2);
+        assert_eq!(g01.get(&gps[0]), None);
+        let l1c = g01[&gps[1]];
+        assert_eq!(l1c.obs, 117352685.282);
+        assert_eq!(l1c.lli, LliFlags::from_bits(1));
+        assert_eq!(l1c.ssi, Some(Ssi::DbHz48_53));
+        let s1c = g01[&gps[2]];
+        assert_eq!(s1c.obs, 48.950);
+        assert_eq!(s1c.lli, None);
+        assert_eq!(s1c.ssi, None);
+        let g02 = &map[&sv::Sv::new(Constellation::GPS, 2)];
+        assert_eq!(g02.len(), 1);
+        assert_eq!(g02[&gps[0]].obs, 22331467.880);
+        let g03 = &map[&sv::Sv::new(Constellation::GPS, 3)];
+        assert_eq!(g03[&gps[0]].obs, 22331469.280);
+        assert_eq!(g03.get(&gps[2]), None);
+    }
 }
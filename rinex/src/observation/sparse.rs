@@ -0,0 +1,229 @@
+//! Sparse (columnar) observation storage, one time series
+//! per vehicule and observable. Suited to files where few observables
+//! are sampled at very high rate (scintillation, 50 Hz power data..):
+//! memory and iteration costs are proportional to the data volume,
+//! instead of allocating maps at every epoch
+use crate::sv;
+use crate::epoch;
+use crate::record::{Error, Streamer, StreamEntry};
+use crate::observation::Observable;
+use crate::observation::record::{Record, ObservationData};
+use std::collections::{BTreeMap, HashMap};
+
+/// Time series of a single observable, for a single vehicule.
+/// Epochs are sorted and unique, `data[i]` was sampled at `epochs[i]`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Series {
+    /// Sampling epochs
+    pub epochs: Vec<epoch::Epoch>,
+    /// Observations
+    pub data: Vec<ObservationData>,
+}
+
+impl Series {
+    /// Inserts a new observation. Appending is cheap,
+    /// out of order insertion requires moving the following samples.
+    /// Existing observation at this epoch is replaced
+    pub fn insert (&mut self, epoch: epoch::Epoch, data: ObservationData) {
+        match self.epochs.last() {
+            Some(last) if *last >= epoch => {
+                match self.epochs.binary_search(&epoch) {
+                    Ok(index) => self.data[index] = data,
+                    Err(index) => {
+                        self.epochs.insert(index, epoch);
+                        self.data.insert(index, data);
+                    },
+                }
+            },
+            _ => {
+                self.epochs.push(epoch);
+                self.data.push(data);
+            },
+        }
+    }
+    /// Returns observation at given epoch
+    pub fn get (&self, epoch: &epoch::Epoch) -> Option<&ObservationData> {
+        self.epochs.binary_search(epoch)
+            .ok()
+            .map(|index| &self.data[index])
+    }
+    /// Returns number of samples
+    pub fn len (&self) -> usize {
+        self.epochs.len()
+    }
+    /// Returns true if this series is empty
+    pub fn is_empty (&self) -> bool {
+        self.epochs.is_empty()
+    }
+    /// Iterates samples, in chronological order
+    pub fn iter (&self) -> impl Iterator<Item = (&epoch::Epoch, &ObservationData)> {
+        self.epochs.iter().zip(self.data.iter())
+    }
+}
+
+/// Sparse observation record: one [Series] per vehicule and observable
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SparseRecord {
+    /// Receiver clock offsets, when applied
+    pub clock_offsets: BTreeMap<epoch::Epoch, f64>,
+    /// Time series, per vehicule and observable
+    columns: BTreeMap<(sv::Sv, Observable), Series>,
+}
+
+impl SparseRecord {
+    /// Builds an empty record
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Inserts a single observation
+    pub fn insert (&mut self, epoch: epoch::Epoch, sv: sv::Sv, observable: Observable, data: ObservationData) {
+        self.columns
+            .entry((sv, observable))
+            .or_insert_with(Series::default)
+            .insert(epoch, data)
+    }
+    /// Inserts all observations of a given epoch
+    pub fn insert_epoch (&mut self, epoch: epoch::Epoch, clock_offset: Option<f64>, vehicules: &BTreeMap<sv::Sv, HashMap<Observable, ObservationData>>) {
+        if let Some(offset) = clock_offset {
+            self.clock_offsets.insert(epoch, offset);
+        }
+        for (sv, observations) in vehicules.iter() {
+            for (observable, data) in observations.iter() {
+                self.insert(epoch, *sv, *observable, *data)
+            }
+        }
+    }
+    /// Builds a sparse record from given record
+    pub fn from_record (record: &Record) -> Self {
+        let mut sparse = Self::new();
+        for (epoch, (clock_offset, vehicules)) in record.iter() {
+            sparse.insert_epoch(*epoch, *clock_offset, vehicules)
+        }
+        sparse
+    }
+    /// Builds a sparse record from given stream, one epoch at a time:
+    /// the standard record is never built. Non observation entries are dropped.
+    /// Stream errors are returned as is.
+    /// Example:
+    /// ```
+    /// use rinex::*;
+    /// use rinex::observation::sparse::SparseRecord;
+    /// let stream = Rinex::epochs_iter_from_file("../test_resources/OBS/V3/DUTH0630.22O")
+    ///     .unwrap();
+    /// let record = SparseRecord::from_stream(stream)
+    ///     .unwrap();
+    /// for (sv, observable, series) in record.iter() {
+    ///     for (epoch, data) in series.iter() {
+    ///         // process this time series
+    ///     }
+    /// }
+    /// ```
+    pub fn from_stream (stream: Streamer) -> Result<Self, Error> {
+        let mut sparse = Self::new();
+        for entry in stream {
+            if let StreamEntry::Obs(epoch, clock_offset, vehicules) = entry? {
+                sparse.insert_epoch(epoch, clock_offset, &vehicules)
+            }
+        }
+        Ok(sparse)
+    }
+    /// Converts self to a standard record
+    pub fn to_record (&self) -> Record {
+        let mut record = Record::new();
+        for ((sv, observable), series) in self.columns.iter() {
+            for (epoch, data) in series.iter() {
+                let (_, vehicules) = record
+                    .entry(*epoch)
+                    .or_insert_with(|| (self.clock_offsets.get(epoch).copied(), BTreeMap::new()));
+                vehicules
+                    .entry(*sv)
+                    .or_insert_with(HashMap::new)
+                    .insert(*observable, *data);
+            }
+        }
+        record
+    }
+    /// Returns time series of given vehicule and observable
+    pub fn series (&self, sv: &sv::Sv, observable: &Observable) -> Option<&Series> {
+        self.columns.get(&(*sv, *observable))
+    }
+    /// Iterates time series, sorted by vehicule then observable
+    pub fn iter (&self) -> impl Iterator<Item = (&sv::Sv, &Observable, &Series)> {
+        self.columns
+            .iter()
+            .map(|((sv, observable), series)| (sv, observable, series))
+    }
+    /// Returns total number of observations
+    pub fn len (&self) -> usize {
+        self.columns.values().map(|s| s.len()).sum()
+    }
+    /// Returns true if this record does not contain any observation
+    pub fn is_empty (&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns all epochs, in chronological order
+    pub fn epochs (&self) -> Vec<epoch::Epoch> {
+        let mut epochs: Vec<epoch::Epoch> = self.columns
+            .values()
+            .flat_map(|s| s.epochs.iter().copied())
+            .collect();
+        epochs.sort();
+        epochs.dedup();
+        epochs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    #[test]
+    fn test_series() {
+        let e0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00.00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e1 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00.02").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e2 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00.04").unwrap(),
+            epoch::EpochFlag::Ok);
+        let mut series = Series::default();
+        series.insert(e0, ObservationData::new(1.0, None, None));
+        series.insert(e2, ObservationData::new(3.0, None, None));
+        series.insert(e1, ObservationData::new(2.0, None, None));
+        series.insert(e2, ObservationData::new(4.0, None, None));
+        assert_eq!(series.len(), 3);
+        assert_eq!(series.epochs, vec![e0, e1, e2]);
+        assert_eq!(series.get(&e1).map(|d| d.obs), Some(2.0));
+        assert_eq!(series.get(&e2).map(|d| d.obs), Some(4.0));
+    }
+    #[test]
+    fn test_sparse_record() {
+        let e0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00.00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e1 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00.02").unwrap(),
+            epoch::EpochFlag::Ok);
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let g02 = sv::Sv::from_str("G02").unwrap();
+        let s1c = Observable::from_str("S1C").unwrap();
+        let mut record = Record::new();
+        let mut vehicules = BTreeMap::new();
+        let mut observations = HashMap::new();
+        observations.insert(s1c, ObservationData::new(40.0, None, None));
+        vehicules.insert(g01, observations.clone());
+        vehicules.insert(g02, observations);
+        record.insert(e0, (Some(1.0E-3), vehicules.clone()));
+        vehicules.remove(&g02);
+        record.insert(e1, (None, vehicules));
+        let sparse = SparseRecord::from_record(&record);
+        assert_eq!(sparse.len(), 3);
+        assert_eq!(sparse.epochs(), vec![e0, e1]);
+        assert_eq!(sparse.series(&g01, &s1c).map(|s| s.len()), Some(2));
+        assert_eq!(sparse.series(&g02, &s1c).map(|s| s.len()), Some(1));
+        assert_eq!(sparse.clock_offsets.get(&e0), Some(&1.0E-3));
+        assert_eq!(sparse.to_record(), record);
+    }
+}
@@ -0,0 +1,75 @@
+//! Record parsing options
+use crate::epoch;
+use std::collections::BTreeMap;
+
+/// Non fatal issue encountered while parsing a record
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct Diagnostic {
+    /// Line number (1 based), in the file being parsed
+    pub line: Option<usize>,
+    /// Epoch of the record entry concerned, when identified
+    pub epoch: Option<epoch::Epoch>,
+    /// Description of the issue
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.line, self.epoch) {
+            (Some(line), Some(epoch)) => write!(f, "line {} ({}): {}", line, epoch.date, self.message),
+            (Some(line), None) => write!(f, "line {}: {}", line, self.message),
+            (None, Some(epoch)) => write!(f, "{}: {}", epoch.date, self.message),
+            (None, None) => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Non fatal issues encountered while parsing, in order of appearance
+pub type Diagnostics = Vec<Diagnostic>;
+
+/// Policy regarding vehicules of a constellation
+/// this library can't interpret (future systems, bad codes..)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum UnknownConstellation {
+    /// Entries are dropped and reported in `warnings`,
+    /// rest of the epoch is preserved. This is the default policy
+    Skip,
+    /// Parsing fails
+    Error,
+    /// Entries are reported in `warnings` and stored as is
+    /// in the raw record, so they are passed through when producing a file
+    KeepRaw,
+}
+
+impl Default for UnknownConstellation {
+    fn default() -> Self {
+        Self::Skip
+    }
+}
+
+/// Record parsing options
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct ParserOptions {
+    /// Policy regarding vehicules this library can't interpret
+    pub unknown_constellation: UnknownConstellation,
+    /// Strict parsing: a malformed record entry (truncated epoch,
+    /// faulty descriptor..) aborts the parsing process.
+    /// Otherwise, and by default, it is skipped and reported in `warnings`
+    pub strict: bool,
+}
+
+/// Record entry that could not be interpreted
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct RawEntry {
+    /// Vehicule identification code, as found in the record
+    pub sv: String,
+    /// Lines describing this entry, as found in the record
+    pub content: Vec<String>,
+}
+
+/// Raw record: entries that could not be interpreted, sorted by epoch
+pub type RawRecord = BTreeMap<epoch::Epoch, Vec<RawEntry>>;
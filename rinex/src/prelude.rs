@@ -0,0 +1,26 @@
+//! Commonly used types, to be imported at once:
+//! ```
+//! use rinex::prelude::*;
+//! let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+//!     .unwrap();
+//! let record = rinex.record.as_obs()
+//!     .unwrap();
+//! for (epoch, (_, vehicules)) in record.iter() {
+//!     assert_eq!(epoch.flag, EpochFlag::Ok);
+//!     for (sv, _) in vehicules.iter() {
+//!         let _ : Constellation = sv.constellation;
+//!     }
+//! }
+//! ```
+pub use crate::Rinex;
+pub use crate::header::{Header, MarkerType};
+pub use crate::types::Type as RinexType;
+pub use crate::epoch::{Epoch, EpochFlag};
+pub use crate::sv::Sv;
+pub use crate::constellation::Constellation;
+pub use crate::meteo::Observable;
+pub use crate::observation::{LliFlags, Ssi, ObservationData};
+pub use crate::observation::Observable as ObsObservable;
+pub use crate::navigation::{FrameClass, MsgType, Frame};
+pub use crate::parser::ParserOptions;
+pub use crate::builder::RinexBuilder;
@@ -0,0 +1,676 @@
+//! Quality Check (QC) heuristics,
+//! to help station operators identify faulty periods
+use crate::sv;
+use crate::epoch;
+use crate::observation::combination;
+use crate::observation::Observable;
+use crate::observation::record::{Record, LliFlags};
+use crate::record;
+use crate::Rinex;
+use chrono::Timelike;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Weight of simultaneous SNR drops in the interference score
+const SNR_WEIGHT: f64 = 0.5;
+/// Weight of simultaneous cycle slips in the interference score
+const SLIP_WEIGHT: f64 = 0.3;
+/// Weight of simultaneous anti spoofing changes in the interference score
+const AS_WEIGHT: f64 = 0.2;
+
+/// Interference detector parameters
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InterferenceOpts {
+    /// Minimal SNR decrease [dB], with respect to
+    /// the last clean epoch, for a signal to be considered affected
+    pub snr_drop: f64,
+    /// Minimal number of affected vehicules
+    /// for an epoch to be considered
+    pub min_vehicules: usize,
+    /// Minimal score, in [0, 1], for an epoch to be considered
+    pub threshold: f64,
+}
+
+impl Default for InterferenceOpts {
+    fn default() -> Self {
+        Self {
+            snr_drop: 6.0,
+            min_vehicules: 3,
+            threshold: 0.3,
+        }
+    }
+}
+
+/// Interference (jamming) event
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct InterferenceEvent {
+    /// First epoch affected
+    pub start: epoch::Epoch,
+    /// Last epoch affected
+    pub end: epoch::Epoch,
+    /// Highest score encountered during this event, in [0, 1].
+    /// 1.0 means all vehicules suffered from a SNR drop,
+    /// a cycle slip and an anti spoofing change at once
+    pub score: f64,
+    /// Vehicules affected during this event
+    pub vehicules: Vec<sv::Sv>,
+}
+
+impl InterferenceEvent {
+    /// Returns duration of this event
+    pub fn duration (&self) -> chrono::Duration {
+        self.end.date - self.start.date
+    }
+}
+
+/// Scans given observation record for possible interferences (jamming).
+/// An epoch is scored by combining the ratio of vehicules suffering from
+/// a sudden SNR drop (with respect to the last clean epoch),
+/// a cycle slip and an anti spoofing flag change, at the same time.
+/// Consecutive suspicious epochs are gathered into a single event.
+/// SNR drops are only identified on S observables.
+pub fn interference (record: &Record, opts: &InterferenceOpts) -> Vec<InterferenceEvent> {
+    let mut events: Vec<InterferenceEvent> = Vec::new();
+    let mut pending: Option<InterferenceEvent> = None;
+    // signal strengths of last clean epoch
+    let mut reference: BTreeMap<(sv::Sv, Observable), f64> = BTreeMap::new();
+    // anti spoofing status of previous epoch
+    let mut anti_spoofing: BTreeMap<sv::Sv, bool> = BTreeMap::new();
+    for (e, (_, vehicules)) in record.iter() {
+        let (mut snr, mut slips, mut as_changes) = (0_usize, 0_usize, 0_usize);
+        let mut affected: BTreeSet<sv::Sv> = BTreeSet::new();
+        for (sv, observations) in vehicules.iter() {
+            let mut dropped = false;
+            let mut slipped = false;
+            let mut under_as = false;
+            for (code, data) in observations.iter() {
+                if code.is_ssi() {
+                    if let Some(previous) = reference.get(&(*sv, *code)) {
+                        if previous - data.obs >= opts.snr_drop {
+                            dropped = true
+                        }
+                    }
+                }
+                if let Some(lli) = data.lli {
+                    if lli.intersects(LliFlags::LOCK_LOSS) {
+                        slipped = true
+                    }
+                    if lli.intersects(LliFlags::UNDER_ANTI_SPOOFING) {
+                        under_as = true
+                    }
+                }
+            }
+            let as_changed = match anti_spoofing.insert(*sv, under_as) {
+                Some(previous) => previous != under_as,
+                None => false,
+            };
+            if dropped {
+                snr += 1
+            }
+            if slipped {
+                slips += 1
+            }
+            if as_changed {
+                as_changes += 1
+            }
+            if dropped || slipped || as_changed {
+                affected.insert(*sv);
+            }
+        }
+        let score = match vehicules.len() {
+            0 => 0.0,
+            n => {
+                let n = n as f64;
+                SNR_WEIGHT * snr as f64 / n
+                    + SLIP_WEIGHT * slips as f64 / n
+                    + AS_WEIGHT * as_changes as f64 / n
+            },
+        };
+        if score >= opts.threshold && affected.len() >= opts.min_vehicules {
+            match pending.as_mut() {
+                Some(event) => {
+                    event.end = *e;
+                    event.score = event.score.max(score);
+                    for sv in affected {
+                        if !event.vehicules.contains(&sv) {
+                            event.vehicules.push(sv)
+                        }
+                    }
+                    event.vehicules.sort();
+                },
+                None => {
+                    pending = Some(InterferenceEvent {
+                        start: *e,
+                        end: *e,
+                        score,
+                        vehicules: affected.into_iter().collect(),
+                    })
+                },
+            }
+        } else {
+            // clean epoch: closes pending event
+            if let Some(event) = pending.take() {
+                events.push(event)
+            }
+            // and becomes the new reference
+            for (sv, observations) in vehicules.iter() {
+                for (code, data) in observations.iter() {
+                    if code.is_ssi() {
+                        reference.insert((*sv, *code), data.obs);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(event) = pending {
+        events.push(event)
+    }
+    events
+}
+
+/// Station score weights, applied to each sub score.
+/// They do not need to sum up to 1: the composite score
+/// is normalized by the weights of the sub scores that could be evaluated
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScoreWeights {
+    /// Data completeness weight
+    pub completeness: f64,
+    /// Slips per phase observation weight
+    pub slips: f64,
+    /// Code multipath RMS weight
+    pub multipath: f64,
+    /// Average SNR weight
+    pub snr: f64,
+    /// Code residuals RMS weight
+    pub residuals: f64,
+    /// Outages weight
+    pub outages: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            completeness: 0.25,
+            slips: 0.15,
+            multipath: 0.15,
+            snr: 0.15,
+            residuals: 0.15,
+            outages: 0.15,
+        }
+    }
+}
+
+/// Station score parameters
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScoreOpts {
+    /// Sub scores weights
+    pub weights: ScoreWeights,
+    /// Slips per phase observation yielding a null slip score
+    pub max_slip_rate: f64,
+    /// Code multipath RMS [m] yielding a null multipath score
+    pub max_multipath_rms: f64,
+    /// Average SNR [dB-Hz] range, mapped to a null and perfect SNR score
+    pub snr_range: (f64, f64),
+    /// Code residuals RMS [m] yielding a null residuals score
+    pub max_residuals_rms: f64,
+    /// Vehicules below this elevation [°] are not used in residuals
+    pub elevation_mask: f64,
+    /// Minimal duration between two epochs, to be considered an outage
+    pub min_outage: chrono::Duration,
+}
+
+impl Default for ScoreOpts {
+    fn default() -> Self {
+        Self {
+            weights: ScoreWeights::default(),
+            max_slip_rate: 0.01,
+            max_multipath_rms: 1.0,
+            snr_range: (30.0, 50.0),
+            max_residuals_rms: 20.0,
+            elevation_mask: 10.0,
+            min_outage: chrono::Duration::minutes(5),
+        }
+    }
+}
+
+/// Station quality metrics, evaluated from parsed files only
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct StationMetrics {
+    /// Ratio of epochs and signals actually received,
+    /// with respect to header INTERVAL and observables, in [0, 1]
+    pub completeness: f64,
+    /// Loss of lock events per phase observation
+    pub slip_rate: f64,
+    /// Code multipath RMS [m] on the highest frequency,
+    /// None if data is not dual frequency
+    pub multipath_rms: Option<f64>,
+    /// Average SNR [dB-Hz], None if no S observables were found
+    pub mean_snr: Option<f64>,
+    /// Ionosphere free code residuals RMS [m],
+    /// after geometry, vehicule clocks, troposphere
+    /// and receiver clock removal.
+    /// None without Navigation context or station position
+    pub residuals_rms: Option<f64>,
+    /// Ratio of the time span lost to outages, in [0, 1]
+    pub outage_ratio: f64,
+}
+
+impl StationMetrics {
+    /// Returns the composite station score, in [0, 100].
+    /// Each metric is linearly mapped to [0, 1] using `opts`,
+    /// metrics that could not be evaluated do not contribute
+    pub fn score (&self, opts: &ScoreOpts) -> f64 {
+        let w = &opts.weights;
+        let mut scores: Vec<(f64, f64)> = vec![
+            (w.completeness, self.completeness),
+            (w.slips, 1.0 - self.slip_rate / opts.max_slip_rate),
+            (w.outages, 1.0 - self.outage_ratio),
+        ];
+        if let Some(rms) = self.multipath_rms {
+            scores.push((w.multipath, 1.0 - rms / opts.max_multipath_rms))
+        }
+        if let Some(snr) = self.mean_snr {
+            let (min, max) = opts.snr_range;
+            scores.push((w.snr, (snr - min) / (max - min)))
+        }
+        if let Some(rms) = self.residuals_rms {
+            scores.push((w.residuals, 1.0 - rms / opts.max_residuals_rms))
+        }
+        let (mut sum, mut weights) = (0.0_f64, 0.0_f64);
+        for (weight, score) in scores {
+            sum += weight * score.max(0.0).min(1.0);
+            weights += weight;
+        }
+        if weights > 0.0 {
+            100.0 * sum / weights
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Evaluates station quality metrics from an Observation RINEX,
+/// with optional Navigation context (code residuals)
+/// and Meteo context (troposphere modeling, standard atmosphere otherwise).
+/// Station position is taken from the Observation then Navigation headers.
+pub fn station_metrics (obs: &Rinex, nav: Option<&Rinex>, meteo: Option<&Rinex>, opts: &ScoreOpts) -> StationMetrics {
+    let mut metrics = StationMetrics {
+        completeness: 0.0,
+        slip_rate: 0.0,
+        multipath_rms: None,
+        mean_snr: None,
+        residuals_rms: None,
+        outage_ratio: 0.0,
+    };
+    let record = match obs.record.as_obs() {
+        Some(record) => record,
+        None => return metrics,
+    };
+    let epochs: Vec<epoch::Epoch> = record.keys().copied().collect();
+    if epochs.len() == 0 {
+        return metrics
+    }
+    // sampling interval: header or smallest epoch interval
+    let interval = match obs.header.sampling_interval {
+        Some(interval) => chrono::Duration::milliseconds((interval * 1000.0) as i64),
+        None => {
+            epochs
+                .windows(2)
+                .map(|w| w[1].date - w[0].date)
+                .min()
+                .unwrap_or(chrono::Duration::zero())
+        },
+    };
+    let span = epochs[epochs.len()-1].date - epochs[0].date;
+    // outages
+    if span > chrono::Duration::zero() {
+        let mut lost = chrono::Duration::zero();
+        for w in epochs.windows(2) {
+            let dt = w[1].date - w[0].date;
+            if dt >= opts.min_outage {
+                lost = lost + dt - interval
+            }
+        }
+        metrics.outage_ratio = lost.num_milliseconds() as f64 / span.num_milliseconds() as f64;
+    }
+    // completeness, slips, SNR
+    let codes = obs.header.obs.as_ref().map(|obs| &obs.codes);
+    let (mut received, mut expected) = (0_usize, 0_usize);
+    let (mut phases, mut slips) = (0_usize, 0_usize);
+    let (mut snr, mut snr_count) = (0.0_f64, 0_usize);
+    let mut lock_losses: BTreeSet<(epoch::Epoch, sv::Sv)> = BTreeSet::new();
+    for (e, (_, vehicules)) in record.iter() {
+        for (sv, observations) in vehicules.iter() {
+            if let Some(codes) = codes.and_then(|codes| codes.get(&sv.constellation)) {
+                received += observations.len();
+                expected += codes.len().max(observations.len());
+            }
+            for (code, data) in observations.iter() {
+                if code.is_phase() {
+                    phases += 1;
+                    if let Some(lli) = data.lli {
+                        if lli.intersects(LliFlags::LOCK_LOSS) {
+                            slips += 1;
+                            lock_losses.insert((*e, *sv));
+                        }
+                    }
+                } else if code.is_ssi() {
+                    snr += data.obs;
+                    snr_count += 1;
+                }
+            }
+        }
+    }
+    let epoch_ratio = match interval.num_milliseconds() {
+        0 => 1.0,
+        dt => epochs.len() as f64 / (span.num_milliseconds() / dt + 1) as f64,
+    };
+    let signal_ratio = match expected {
+        0 => 1.0,
+        expected => received as f64 / expected as f64,
+    };
+    metrics.completeness = (epoch_ratio * signal_ratio).min(1.0);
+    if phases > 0 {
+        metrics.slip_rate = slips as f64 / phases as f64;
+    }
+    if snr_count > 0 {
+        metrics.mean_snr = Some(snr / snr_count as f64);
+    }
+    metrics.multipath_rms = multipath_rms(obs, &lock_losses);
+    if let Some(nav) = nav {
+        metrics.residuals_rms = residuals_rms(obs, nav, meteo, opts);
+    }
+    metrics
+}
+
+/// Evaluates the composite station score, in [0, 100],
+/// see [station_metrics] and [StationMetrics::score]
+pub fn station_score (obs: &Rinex, nav: Option<&Rinex>, meteo: Option<&Rinex>, opts: &ScoreOpts) -> f64 {
+    station_metrics(obs, nav, meteo, opts).score(opts)
+}
+
+/// Aggregation period
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum Period {
+    /// Hourly buckets
+    Hourly,
+    /// Daily buckets
+    Daily,
+}
+
+impl Period {
+    /// Returns duration of a bucket
+    pub fn duration (&self) -> chrono::Duration {
+        match self {
+            Self::Hourly => chrono::Duration::hours(1),
+            Self::Daily => chrono::Duration::days(1),
+        }
+    }
+    /// Returns start of the bucket given date belongs to
+    fn bucket (&self, date: chrono::NaiveDateTime) -> chrono::NaiveDateTime {
+        match self {
+            Self::Hourly => date.date().and_hms(date.hour(), 0, 0),
+            Self::Daily => date.date().and_hms(0, 0, 0),
+        }
+    }
+}
+
+/// QC metrics summary, over a single aggregation bucket
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub struct Summary {
+    /// Start of this bucket (included)
+    pub start: chrono::NaiveDateTime,
+    /// End of this bucket (excluded)
+    pub end: chrono::NaiveDateTime,
+    /// Number of epochs within this bucket
+    pub epochs: usize,
+    /// Station metrics, evaluated within this bucket only
+    pub metrics: StationMetrics,
+}
+
+/// Rolls QC metrics (completeness, slips, code multipath RMS, SNR..)
+/// of given Observation RINEX into hourly or daily buckets,
+/// as expected by station monitoring tools.
+/// Buckets that do not contain any epoch are not reported.
+/// Returns an empty list if `obs` is not an Observation RINEX.
+/// Example:
+/// ```
+/// use rinex::*;
+/// use rinex::qc::{self, Period};
+/// let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+///     .unwrap();
+/// let hourly = qc::aggregate(&rinex, Period::Hourly);
+/// for summary in hourly.iter() {
+///     assert_eq!(summary.end - summary.start, chrono::Duration::hours(1));
+/// }
+/// ```
+pub fn aggregate (obs: &Rinex, period: Period) -> Vec<Summary> {
+    let mut summaries: Vec<Summary> = Vec::new();
+    let record = match obs.record.as_obs() {
+        Some(record) => record,
+        None => return summaries,
+    };
+    let opts = ScoreOpts::default();
+    let mut buckets: Vec<chrono::NaiveDateTime> = record
+        .keys()
+        .map(|e| period.bucket(e.date))
+        .collect();
+    buckets.dedup();
+    for start in buckets {
+        let end = start + period.duration();
+        let content: Record = record
+            .range(epoch::Epoch::new(start, epoch::EpochFlag::Ok)..epoch::Epoch::new(end, epoch::EpochFlag::Ok))
+            .map(|(e, data)| (*e, data.clone()))
+            .collect();
+        let epochs = content.len();
+        let bucket = Rinex::new(obs.header.clone(), record::Record::ObsRecord(content));
+        summaries.push(Summary {
+            start,
+            end,
+            epochs,
+            metrics: station_metrics(&bucket, None, None, &opts),
+        })
+    }
+    summaries
+}
+
+/// Code multipath RMS [m] on the highest frequency:
+/// MP1 = P1 - L1 - 2/(α-1) (L1 - L2), α = (f1/f2)²,
+/// with the mean value (ambiguities, hardware biases)
+/// removed on each continuous phase arc
+fn multipath_rms (obs: &Rinex, lock_losses: &BTreeSet<(epoch::Epoch, sv::Sv)>) -> Option<f64> {
+    let pseudo_ranges = obs.pseudo_ranges();
+    let phases = obs.carrier_phases_m();
+    // continuous arcs, per vehicule
+    let mut arcs: BTreeMap<sv::Sv, Vec<f64>> = BTreeMap::new();
+    let (mut sum, mut count) = (0.0_f64, 0_usize);
+    // accumulates an arc, once its mean value is removed
+    let close = |arc: Vec<f64>, sum: &mut f64, count: &mut usize| {
+        if arc.len() > 1 {
+            let mean = arc.iter().sum::<f64>() / arc.len() as f64;
+            for mp in arc {
+                *sum += (mp - mean).powi(2);
+                *count += 1;
+            }
+        }
+    };
+    for (e, vehicules) in phases.iter() {
+        let codes = pseudo_ranges.get(e);
+        let mut observed: BTreeSet<sv::Sv> = BTreeSet::new();
+        for (sv, phase) in vehicules.iter() {
+            let code = match codes.and_then(|codes| codes.get(sv)) {
+                Some(code) => code,
+                None => continue,
+            };
+            let ((f1, l1), (f2, l2)) = match combination::dual_frequency(sv, phase) {
+                Some(phases) => phases,
+                None => continue,
+            };
+            let p1 = match combination::dual_frequency(sv, code) {
+                Some(((f, p1), _)) if f == f1 => p1,
+                _ => continue,
+            };
+            let alpha = (f1 / f2).powi(2);
+            let mp = p1 - l1 - 2.0 / (alpha - 1.0) * (l1 - l2);
+            if lock_losses.contains(&(*e, *sv)) {
+                if let Some(arc) = arcs.remove(sv) {
+                    close(arc, &mut sum, &mut count)
+                }
+            }
+            arcs.entry(*sv)
+                .or_insert_with(Vec::new)
+                .push(mp);
+            observed.insert(*sv);
+        }
+        // vehicules that were not observed: arc is interrupted
+        let interrupted: Vec<sv::Sv> = arcs
+            .keys()
+            .filter(|sv| !observed.contains(sv))
+            .copied()
+            .collect();
+        for sv in interrupted {
+            if let Some(arc) = arcs.remove(&sv) {
+                close(arc, &mut sum, &mut count)
+            }
+        }
+    }
+    for (_, arc) in arcs {
+        close(arc, &mut sum, &mut count)
+    }
+    match count {
+        0 => None,
+        count => Some((sum / count as f64).sqrt()),
+    }
+}
+
+/// Ionosphere free code residuals RMS [m]
+fn residuals_rms (obs: &Rinex, nav: &Rinex, meteo: Option<&Rinex>, opts: &ScoreOpts) -> Option<f64> {
+    let position = obs.header.coords
+        .as_ref()
+        .or(nav.header.coords.as_ref())?;
+    let residuals = obs.pseudo_range_residuals(nav, meteo, (position.x, position.y, position.z), opts.elevation_mask);
+    let (mut sum, mut count) = (0.0_f64, 0_usize);
+    for (_, vehicules) in residuals.iter() {
+        if vehicules.len() < 2 {
+            continue
+        }
+        // receiver clock is common to all vehicules
+        let mean = vehicules.values().sum::<f64>() / vehicules.len() as f64;
+        for residual in vehicules.values() {
+            sum += (residual - mean).powi(2);
+            count += 1;
+        }
+    }
+    match count {
+        0 => None,
+        count => Some((sum / count as f64).sqrt()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    use std::collections::HashMap;
+    use crate::observation::record::ObservationData;
+    #[test]
+    fn test_interference() {
+        let t0 = epoch::str2date("2022 01 01 00 00 00").unwrap();
+        let vehicules: Vec<sv::Sv> = vec!["G01", "G02", "G03", "G04", "E01"]
+            .iter()
+            .map(|s| sv::Sv::from_str(s).unwrap())
+            .collect();
+        let mut record = Record::new();
+        for i in 0..20 {
+            let e = epoch::Epoch::new(
+                t0 + chrono::Duration::seconds(30 * i),
+                epoch::EpochFlag::Ok);
+            // jamming between epochs 8 and 11
+            let jammed = (8..=11).contains(&i);
+            let mut map: BTreeMap<sv::Sv, HashMap<Observable, ObservationData>> = BTreeMap::new();
+            for sv in vehicules.iter() {
+                let mut obs: HashMap<Observable, ObservationData> = HashMap::new();
+                let snr = if jammed { 30.0 } else { 45.0 + (i % 2) as f64 };
+                let lli = if i == 8 {
+                    Some(LliFlags::LOCK_LOSS)
+                } else {
+                    Some(LliFlags::OK_OR_UNKNOWN)
+                };
+                obs.insert(Observable::from_str("S1C").unwrap(), ObservationData::new(snr, None, None));
+                obs.insert(Observable::from_str("L1C").unwrap(), ObservationData::new(1.0E8, lli, None));
+                map.insert(*sv, obs);
+            }
+            record.insert(e, (None, map));
+        }
+        let events = interference(&record, &InterferenceOpts::default());
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.start.date, t0 + chrono::Duration::seconds(30 * 8));
+        assert_eq!(event.end.date, t0 + chrono::Duration::seconds(30 * 11));
+        assert_eq!(event.duration(), chrono::Duration::seconds(90));
+        assert!((event.score - 0.8).abs() < 1.0E-9);
+        assert_eq!(event.vehicules.len(), vehicules.len());
+        // a single vehicule dropping out is not an interference
+        let mut record = Record::new();
+        for i in 0..10 {
+            let e = epoch::Epoch::new(
+                t0 + chrono::Duration::seconds(30 * i),
+                epoch::EpochFlag::Ok);
+            let mut map: BTreeMap<sv::Sv, HashMap<Observable, ObservationData>> = BTreeMap::new();
+            for (j, sv) in vehicules.iter().enumerate() {
+                let mut obs: HashMap<Observable, ObservationData> = HashMap::new();
+                let snr = if j == 0 && i > 5 { 20.0 } else { 45.0 };
+                obs.insert(Observable::from_str("S1C").unwrap(), ObservationData::new(snr, None, None));
+                map.insert(*sv, obs);
+            }
+            record.insert(e, (None, map));
+        }
+        assert_eq!(interference(&record, &InterferenceOpts::default()).len(), 0);
+    }
+    #[test]
+    fn test_station_score() {
+        let opts = ScoreOpts::default();
+        let perfect = StationMetrics {
+            completeness: 1.0,
+            slip_rate: 0.0,
+            multipath_rms: Some(0.0),
+            mean_snr: Some(55.0),
+            residuals_rms: Some(0.0),
+            outage_ratio: 0.0,
+        };
+        assert!((perfect.score(&opts) - 100.0).abs() < 1.0E-9);
+        let worst = StationMetrics {
+            completeness: 0.0,
+            slip_rate: 1.0,
+            multipath_rms: Some(10.0),
+            mean_snr: Some(10.0),
+            residuals_rms: Some(100.0),
+            outage_ratio: 1.0,
+        };
+        assert!(worst.score(&opts).abs() < 1.0E-9);
+        // missing metrics do not contribute
+        let partial = StationMetrics {
+            completeness: 0.5,
+            slip_rate: 0.0,
+            multipath_rms: None,
+            mean_snr: None,
+            residuals_rms: None,
+            outage_ratio: 0.0,
+        };
+        let expected = 100.0 * (0.25 * 0.5 + 0.15 + 0.15) / 0.55;
+        assert!((partial.score(&opts) - expected).abs() < 1.0E-9);
+        let mut opts = ScoreOpts::default();
+        opts.weights = ScoreWeights {
+            completeness: 1.0,
+            slips: 0.0,
+            multipath: 0.0,
+            snr: 0.0,
+            residuals: 0.0,
+            outages: 0.0,
+        };
+        assert!((partial.score(&opts) - 50.0).abs() < 1.0E-9);
+    }
+}
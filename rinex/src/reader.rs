@@ -1,6 +1,7 @@
 //! Generic Buffered Reader, for efficient record iteration,
 //! with powerful internal Hatanaka / Gz decompression.
-use std::io::{BufReader}; // Seek, SeekFrom};
+use std::io::{BufReader, Cursor, Read}; // Seek, SeekFrom};
+use crate::lzw;
 use crate::hatanaka::Decompressor;
 #[cfg(feature = "with-gzip")]
 use flate2::read::GzDecoder;
@@ -13,6 +14,9 @@ pub enum ReaderWrapper {
     /// gzip compressed RINEX
     #[cfg(feature = "with-gzip")]
     GzFile(BufReader<GzDecoder<std::fs::File>>),
+    /// RINEX content held in memory,
+    /// like decompressed .Z files or carved segments
+    Memory(Cursor<Vec<u8>>),
 }
 
 pub struct BufferedReader {
@@ -25,9 +29,9 @@ pub struct BufferedReader {
 
 impl BufferedReader {
     /// Builds a new BufferedReader for efficient file interation,
-    /// with possible .gz and .gz + hatanaka decompression
+    /// with possible .gz, .Z and .gz + hatanaka decompression
     pub fn new (path: &str) -> std::io::Result<Self> {
-        let f = std::fs::File::open(path)?;
+        let mut f = std::fs::File::open(path)?;
         if path.ends_with(".gz") {
             // --> gzip encoded
             #[cfg(feature = "with-gzip")] {
@@ -39,11 +43,21 @@ impl BufferedReader {
                 })
             }
             #[cfg(not(feature = "with-gzip"))] {
-                panic!("gzip compressed data require the --with-gzip build feature")
+                Err(std::io::Error::new(std::io::ErrorKind::Unsupported,
+                    "gzip compressed data require the --with-gzip build feature"))
             }
         
         } else if path.ends_with(".Z") {
-            panic!(".z compressed files not supported yet, uncompress manually")
+            // --> LZW encoded
+            // example : .n.Z, .crx.Z 
+            let mut content : Vec<u8> = Vec::new();
+            f.read_to_end(&mut content)?;
+            let content = lzw::decompress(&content)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            Ok(Self {
+                reader: ReaderWrapper::Memory(Cursor::new(content)),
+                decompressor: None,
+            })
         
         } else { // Assumes no extra compression
             Ok(Self {
@@ -52,6 +66,39 @@ impl BufferedReader {
             })
         }
     }
+    /// Builds a new BufferedReader over given content, held in memory
+    pub fn from_memory (content: Vec<u8>) -> Self {
+        Self {
+            reader: ReaderWrapper::Memory(Cursor::new(content)),
+            decompressor: None,
+        }
+    }
+    /// Builds a new BufferedReader over any readable source
+    /// (in-memory buffer, network socket, archive entry..).
+    /// Content is read to completion, compression is identified
+    /// by its magic bytes: .Z is always supported,
+    /// gzip requires the `with-gzip` build feature
+    pub fn from_reader<R: Read> (mut reader: R) -> std::io::Result<Self> {
+        let mut content : Vec<u8> = Vec::new();
+        reader.read_to_end(&mut content)?;
+        if content.starts_with(&[0x1f, 0x8b]) {
+            // --> gzip encoded
+            #[cfg(feature = "with-gzip")] {
+                let mut inflated : Vec<u8> = Vec::new();
+                GzDecoder::new(&content[..]).read_to_end(&mut inflated)?;
+                content = inflated;
+            }
+            #[cfg(not(feature = "with-gzip"))] {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                    "gzip compressed data require the --with-gzip build feature"))
+            }
+        } else if content.starts_with(&[0x1f, 0x9d]) {
+            // --> LZW encoded
+            content = lzw::decompress(&content)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        }
+        Ok(Self::from_memory(content))
+    }
     /// Enhances self for hatanaka internal decompression,
     /// preserves inner pointer state
     pub fn with_hatanaka (&self, m: usize) -> std::io::Result<Self> {
@@ -73,6 +120,12 @@ impl BufferedReader {
                     decompressor: Some(Decompressor::new(m)),
                 })
             },
+            ReaderWrapper::Memory(cursor) => {
+                Ok(BufferedReader {
+                    reader: ReaderWrapper::Memory(cursor.clone()), // preserves pointer
+                    decompressor: Some(Decompressor::new(m)),
+                })
+            },
         }
     }
 /*
@@ -101,6 +154,7 @@ impl std::io::Read for BufferedReader {
             ReaderWrapper::PlainFile(ref mut h) => h.read(buf),
             #[cfg(feature = "with-gzip")]
             ReaderWrapper::GzFile(ref mut h) => h.read(buf),
+            ReaderWrapper::Memory(ref mut h) => h.read(buf),
         }
     }
 }
@@ -111,6 +165,7 @@ impl std::io::BufRead for BufferedReader {
             ReaderWrapper::PlainFile(ref mut bufreader) => bufreader.fill_buf(),
             #[cfg(feature = "with-gzip")]
             ReaderWrapper::GzFile(ref mut bufreader) => bufreader.fill_buf(),
+            ReaderWrapper::Memory(ref mut cursor) => cursor.fill_buf(),
         }
     }
     
@@ -119,6 +174,7 @@ impl std::io::BufRead for BufferedReader {
             ReaderWrapper::PlainFile(ref mut bufreader) => bufreader.consume(s),
             #[cfg(feature = "with-gzip")]
             ReaderWrapper::GzFile(ref mut bufreader) => bufreader.consume(s),
+            ReaderWrapper::Memory(ref mut cursor) => cursor.consume(s),
         }
     }
 }
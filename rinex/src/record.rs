@@ -1,13 +1,16 @@
 //! `RINEX` file content description and parsing
 use thiserror::Error;
 use std::io::{prelude::*};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::btree_map::{Iter, Range};
 
+use crate::sv;
 use crate::antex;
 use crate::epoch;
 use crate::meteo;
 use crate::clocks;
 use crate::header;
+use crate::parser;
 use crate::hatanaka;
 use crate::navigation;
 use crate::observation;
@@ -18,6 +21,7 @@ use crate::reader::BufferedReader;
 
 /// `Record`
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
 pub enum Record {
     /// ATX record, list of Antenna caracteristics,
     /// sorted by antenna model. ATX record is not
@@ -130,29 +134,326 @@ impl Record {
             _ => None,
         }
     }
-    /// Streams into given file writer
-    pub fn to_file (&self, header: &header::Header, writer: std::fs::File) -> std::io::Result<()> {
+    /// Returns a borrowed view over given time window,
+    /// exploiting the `BTreeMap` indexing: nothing is copied.
+    /// Epochs are compared by date, then by flag:
+    /// `range.start` should use [epoch::EpochFlag::Ok] to include all events.
+    /// ATX records are not epoch iterable and are entirely borrowed.
+    /// Panics if `range.start` > `range.end`
+    pub fn window (&self, range: std::ops::Range<epoch::Epoch>) -> RecordView {
+        match self {
+            Record::AntexRecord(r) => RecordView::AntexRecord(r),
+            Record::ClockRecord(r) => RecordView::ClockRecord(r.range(range)),
+            Record::IonexRecord(r) => RecordView::IonexRecord(r.range(range)),
+            Record::MeteoRecord(r) => RecordView::MeteoRecord(r.range(range)),
+            Record::NavRecord(r) => RecordView::NavRecord(r.range(range)),
+            Record::ObsRecord(r) => RecordView::ObsRecord(r.range(range)),
+        }
+    }
+    /// Returns a borrowed iterator over all epochs and their content,
+    /// in chronological order, without allocation.
+    /// Empty on ATX records, which are not epoch iterable
+    pub fn epochs_iter (&self) -> EpochIter {
+        match self {
+            Record::AntexRecord(_) => EpochIter::AntexRecord,
+            Record::ClockRecord(r) => EpochIter::ClockRecord(r.iter()),
+            Record::IonexRecord(r) => EpochIter::IonexRecord(r.iter()),
+            Record::MeteoRecord(r) => EpochIter::MeteoRecord(r.iter()),
+            Record::NavRecord(r) => EpochIter::NavRecord(r.iter()),
+            Record::ObsRecord(r) => EpochIter::ObsRecord(r.iter()),
+        }
+    }
+    /// Retains only epochs for which given predicate returns true.
+    /// ATX records are not epoch iterable and are left untouched
+    pub fn retain_epochs<F: FnMut(&epoch::Epoch) -> bool> (&mut self, mut f: F) {
+        self.retain(|e, _| f(e))
+    }
+    /// Retains only epochs for which given predicate, evaluated
+    /// on each epoch and its content, returns true.
+    /// ATX records are not epoch iterable and are left untouched
+    pub fn retain<F: FnMut(&epoch::Epoch, RecordEntry) -> bool> (&mut self, mut f: F) {
+        match self {
+            Record::AntexRecord(_) => {},
+            Record::ClockRecord(r) => r.retain(|e, v| f(e, RecordEntry::ClockRecord(v))),
+            Record::IonexRecord(r) => r.retain(|e, v| f(e, RecordEntry::IonexRecord(v))),
+            Record::MeteoRecord(r) => r.retain(|e, v| f(e, RecordEntry::MeteoRecord(v))),
+            Record::NavRecord(r) => r.retain(|e, v| f(e, RecordEntry::NavRecord(v))),
+            Record::ObsRecord(r) => r.retain(|e, v| f(e, RecordEntry::ObsRecord(v))),
+        }
+    }
+    /// Streams into given writer, epoch by epoch,
+    /// `raw` entries are passed through as is.
+    /// Observation site `events` are written with their special records,
+    /// followed by the `comments` attached to them
+    pub fn to_writer (&self, header: &header::Header, comments: &Comments, events: &observation::SiteEvents, raw: &parser::RawRecord, writer: &mut impl Write) -> std::io::Result<()> {
+        self.to_writer_with_statistics(header, comments, events, raw, writer)?;
+        Ok(())
+    }
+
+    /// Same as [Record::to_writer], also returns the compression
+    /// statistics when `header` describes a CRINEX
+    pub fn to_writer_with_statistics (&self, header: &header::Header, comments: &Comments, events: &observation::SiteEvents, raw: &parser::RawRecord, writer: &mut impl Write) -> std::io::Result<Option<hatanaka::Statistics>> {
         match &header.rinex_type {
             Type::MeteoData => {
                 let record = self.as_meteo()
                     .unwrap();
-                Ok(meteo::record::to_file(header, &record, writer)?)
+                meteo::record::to_writer(header, &record, writer)?;
+                Ok(None)
             },
             Type::ObservationData => {
                 let record = self.as_obs()
                     .unwrap();
-                Ok(observation::record::to_file(header, &record, writer)?)
+                observation::record::to_writer_with_statistics(header, &record, events, comments, raw, writer)
             },
             Type::NavigationData => {
                 let record = self.as_nav()
                     .unwrap();
-                Ok(navigation::record::to_file(header, &record, writer)?)
+                navigation::record::to_writer(header, &record, writer)?;
+                Ok(None)
+            },
+            Type::ClockData => {
+                let record = self.as_clock()
+                    .unwrap();
+                clocks::record::to_writer(header, &record, writer)?;
+                Ok(None)
             },
             _ => panic!("record type not supported yet"),
         }
     }
 }
 
+/// `RecordView` is a zero-copy, borrowed view
+/// over a time window of a `Record`, see [Record::window]
+#[derive(Clone, Debug)]
+pub enum RecordView<'a> {
+    /// ATX record is not epoch iterable:
+    /// entire record is borrowed
+    AntexRecord(&'a antex::record::Record),
+    /// Borrowed view over a CLOCKS record
+    ClockRecord(Range<'a, epoch::Epoch, clocks::record::ClockEntries>),
+    /// Borrowed view over an IONEX record
+    IonexRecord(Range<'a, epoch::Epoch, (ionosphere::record::Map, Option<ionosphere::record::Map>, Option<ionosphere::record::Map>)>),
+    /// Borrowed view over a Meteo record
+    MeteoRecord(Range<'a, epoch::Epoch, HashMap<meteo::observable::Observable, f32>>),
+    /// Borrowed view over a Navigation record
+    NavRecord(Range<'a, epoch::Epoch, BTreeMap<navigation::record::FrameClass, Vec<navigation::record::Frame>>>),
+    /// Borrowed view over an Observation record
+    ObsRecord(Range<'a, epoch::Epoch, (Option<f64>, BTreeMap<sv::Sv, HashMap<observation::Observable, observation::record::ObservationData>>)>),
+}
+
+impl<'a> RecordView<'a> {
+    /// Unwraps self as a borrowed ANTEX `record`
+    pub fn as_antex (&self) -> Option<&'a antex::record::Record> {
+        match self {
+            RecordView::AntexRecord(r) => Some(*r),
+            _ => None,
+        }
+    }
+    /// Returns an iterator over the CLOCKS `record` window
+    pub fn as_clock (&self) -> Option<Range<'a, epoch::Epoch, clocks::record::ClockEntries>> {
+        match self {
+            RecordView::ClockRecord(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+    /// Returns an iterator over the IONEX `record` window
+    pub fn as_ionex (&self) -> Option<Range<'a, epoch::Epoch, (ionosphere::record::Map, Option<ionosphere::record::Map>, Option<ionosphere::record::Map>)>> {
+        match self {
+            RecordView::IonexRecord(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+    /// Returns an iterator over the Meteo `record` window
+    pub fn as_meteo (&self) -> Option<Range<'a, epoch::Epoch, HashMap<meteo::observable::Observable, f32>>> {
+        match self {
+            RecordView::MeteoRecord(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+    /// Returns an iterator over the NAV `record` window
+    pub fn as_nav (&self) -> Option<Range<'a, epoch::Epoch, BTreeMap<navigation::record::FrameClass, Vec<navigation::record::Frame>>>> {
+        match self {
+            RecordView::NavRecord(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+    /// Returns an iterator over the OBS `record` window
+    pub fn as_obs (&self) -> Option<Range<'a, epoch::Epoch, (Option<f64>, BTreeMap<sv::Sv, HashMap<observation::Observable, observation::record::ObservationData>>)>> {
+        match self {
+            RecordView::ObsRecord(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+    /// Returns list of epochs contained in this window.
+    /// Empty on ATX records, which are not epoch iterable
+    pub fn epochs (&self) -> Vec<epoch::Epoch> {
+        match self {
+            RecordView::AntexRecord(_) => Vec::new(),
+            RecordView::ClockRecord(r) => r.clone().map(|(e, _)| *e).collect(),
+            RecordView::IonexRecord(r) => r.clone().map(|(e, _)| *e).collect(),
+            RecordView::MeteoRecord(r) => r.clone().map(|(e, _)| *e).collect(),
+            RecordView::NavRecord(r) => r.clone().map(|(e, _)| *e).collect(),
+            RecordView::ObsRecord(r) => r.clone().map(|(e, _)| *e).collect(),
+        }
+    }
+    /// Returns number of epochs contained in this window
+    pub fn len (&self) -> usize {
+        match self {
+            RecordView::AntexRecord(_) => 0,
+            RecordView::ClockRecord(r) => r.clone().count(),
+            RecordView::IonexRecord(r) => r.clone().count(),
+            RecordView::MeteoRecord(r) => r.clone().count(),
+            RecordView::NavRecord(r) => r.clone().count(),
+            RecordView::ObsRecord(r) => r.clone().count(),
+        }
+    }
+    /// Returns true if this window does not contain any epoch
+    pub fn is_empty (&self) -> bool {
+        self.first_epoch().is_none()
+    }
+    /// Returns first epoch contained in this window
+    pub fn first_epoch (&self) -> Option<epoch::Epoch> {
+        match self {
+            RecordView::AntexRecord(_) => None,
+            RecordView::ClockRecord(r) => r.clone().next().map(|(e, _)| *e),
+            RecordView::IonexRecord(r) => r.clone().next().map(|(e, _)| *e),
+            RecordView::MeteoRecord(r) => r.clone().next().map(|(e, _)| *e),
+            RecordView::NavRecord(r) => r.clone().next().map(|(e, _)| *e),
+            RecordView::ObsRecord(r) => r.clone().next().map(|(e, _)| *e),
+        }
+    }
+    /// Returns last epoch contained in this window
+    pub fn last_epoch (&self) -> Option<epoch::Epoch> {
+        match self {
+            RecordView::AntexRecord(_) => None,
+            RecordView::ClockRecord(r) => r.clone().next_back().map(|(e, _)| *e),
+            RecordView::IonexRecord(r) => r.clone().next_back().map(|(e, _)| *e),
+            RecordView::MeteoRecord(r) => r.clone().next_back().map(|(e, _)| *e),
+            RecordView::NavRecord(r) => r.clone().next_back().map(|(e, _)| *e),
+            RecordView::ObsRecord(r) => r.clone().next_back().map(|(e, _)| *e),
+        }
+    }
+}
+
+/// `RecordEntry` is the borrowed content of a `Record`
+/// at a given epoch, see [Record::epochs_iter]
+#[derive(Copy, Clone, Debug)]
+pub enum RecordEntry<'a> {
+    /// CLOCKS data, station and satellite clocks
+    ClockRecord(&'a clocks::record::ClockEntries),
+    /// IONEX maps (TEC, RMS, height)
+    IonexRecord(&'a (ionosphere::record::Map, Option<ionosphere::record::Map>, Option<ionosphere::record::Map>)),
+    /// Meteo observations
+    MeteoRecord(&'a HashMap<meteo::observable::Observable, f32>),
+    /// Navigation frames
+    NavRecord(&'a BTreeMap<navigation::record::FrameClass, Vec<navigation::record::Frame>>),
+    /// Receiver clock offset and observations
+    ObsRecord(&'a (Option<f64>, BTreeMap<sv::Sv, HashMap<observation::Observable, observation::record::ObservationData>>)),
+}
+
+impl<'a> RecordEntry<'a> {
+    /// Unwraps self as CLOCKS data
+    pub fn as_clock (&self) -> Option<&'a clocks::record::ClockEntries> {
+        match self {
+            RecordEntry::ClockRecord(e) => Some(*e),
+            _ => None,
+        }
+    }
+    /// Unwraps self as IONEX maps
+    pub fn as_ionex (&self) -> Option<&'a (ionosphere::record::Map, Option<ionosphere::record::Map>, Option<ionosphere::record::Map>)> {
+        match self {
+            RecordEntry::IonexRecord(e) => Some(*e),
+            _ => None,
+        }
+    }
+    /// Unwraps self as Meteo observations
+    pub fn as_meteo (&self) -> Option<&'a HashMap<meteo::observable::Observable, f32>> {
+        match self {
+            RecordEntry::MeteoRecord(e) => Some(*e),
+            _ => None,
+        }
+    }
+    /// Unwraps self as Navigation frames
+    pub fn as_nav (&self) -> Option<&'a BTreeMap<navigation::record::FrameClass, Vec<navigation::record::Frame>>> {
+        match self {
+            RecordEntry::NavRecord(e) => Some(*e),
+            _ => None,
+        }
+    }
+    /// Unwraps self as clock offset and observations
+    pub fn as_obs (&self) -> Option<&'a (Option<f64>, BTreeMap<sv::Sv, HashMap<observation::Observable, observation::record::ObservationData>>)> {
+        match self {
+            RecordEntry::ObsRecord(e) => Some(*e),
+            _ => None,
+        }
+    }
+}
+
+/// `EpochIter` is a borrowed, chronological iterator
+/// over a `Record`, see [Record::epochs_iter]
+#[derive(Clone, Debug)]
+pub enum EpochIter<'a> {
+    /// ATX record is not epoch iterable
+    AntexRecord,
+    /// Iterator over a CLOCKS record
+    ClockRecord(Iter<'a, epoch::Epoch, clocks::record::ClockEntries>),
+    /// Iterator over an IONEX record
+    IonexRecord(Iter<'a, epoch::Epoch, (ionosphere::record::Map, Option<ionosphere::record::Map>, Option<ionosphere::record::Map>)>),
+    /// Iterator over a Meteo record
+    MeteoRecord(Iter<'a, epoch::Epoch, HashMap<meteo::observable::Observable, f32>>),
+    /// Iterator over a Navigation record
+    NavRecord(Iter<'a, epoch::Epoch, BTreeMap<navigation::record::FrameClass, Vec<navigation::record::Frame>>>),
+    /// Iterator over an Observation record
+    ObsRecord(Iter<'a, epoch::Epoch, (Option<f64>, BTreeMap<sv::Sv, HashMap<observation::Observable, observation::record::ObservationData>>)>),
+}
+
+impl<'a> Iterator for EpochIter<'a> {
+    type Item = (&'a epoch::Epoch, RecordEntry<'a>);
+    fn next (&mut self) -> Option<Self::Item> {
+        match self {
+            EpochIter::AntexRecord => None,
+            EpochIter::ClockRecord(it) => it.next().map(|(e, v)| (e, RecordEntry::ClockRecord(v))),
+            EpochIter::IonexRecord(it) => it.next().map(|(e, v)| (e, RecordEntry::IonexRecord(v))),
+            EpochIter::MeteoRecord(it) => it.next().map(|(e, v)| (e, RecordEntry::MeteoRecord(v))),
+            EpochIter::NavRecord(it) => it.next().map(|(e, v)| (e, RecordEntry::NavRecord(v))),
+            EpochIter::ObsRecord(it) => it.next().map(|(e, v)| (e, RecordEntry::ObsRecord(v))),
+        }
+    }
+    fn size_hint (&self) -> (usize, Option<usize>) {
+        match self {
+            EpochIter::AntexRecord => (0, Some(0)),
+            EpochIter::ClockRecord(it) => it.size_hint(),
+            EpochIter::IonexRecord(it) => it.size_hint(),
+            EpochIter::MeteoRecord(it) => it.size_hint(),
+            EpochIter::NavRecord(it) => it.size_hint(),
+            EpochIter::ObsRecord(it) => it.size_hint(),
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for EpochIter<'a> {
+    fn next_back (&mut self) -> Option<Self::Item> {
+        match self {
+            EpochIter::AntexRecord => None,
+            EpochIter::ClockRecord(it) => it.next_back().map(|(e, v)| (e, RecordEntry::ClockRecord(v))),
+            EpochIter::IonexRecord(it) => it.next_back().map(|(e, v)| (e, RecordEntry::IonexRecord(v))),
+            EpochIter::MeteoRecord(it) => it.next_back().map(|(e, v)| (e, RecordEntry::MeteoRecord(v))),
+            EpochIter::NavRecord(it) => it.next_back().map(|(e, v)| (e, RecordEntry::NavRecord(v))),
+            EpochIter::ObsRecord(it) => it.next_back().map(|(e, v)| (e, RecordEntry::ObsRecord(v))),
+        }
+    }
+}
+
+impl<'a> ExactSizeIterator for EpochIter<'a> {}
+
+impl<'a> IntoIterator for &'a Record {
+    type Item = (&'a epoch::Epoch, RecordEntry<'a>);
+    type IntoIter = EpochIter<'a>;
+    fn into_iter (self) -> Self::IntoIter {
+        self.epochs_iter()
+    }
+}
+
 impl Default for Record {
     fn default() -> Record {
         Record::NavRecord(navigation::record::Record::new())
@@ -165,6 +466,12 @@ pub enum Error {
     TypeError(String),
     #[error("file i/o error")]
     IoError(#[from] std::io::Error),
+    #[error("line {0}: unknown constellation for vehicule \"{1}\"")]
+    UnknownConstellation(usize, String),
+    #[error("line {0}: malformed record entry \"{1}\"")]
+    MalformedEntry(usize, String),
+    #[error("CRINEX decompression failed")]
+    HatanakaError(#[from] hatanaka::Error),
 }
 
 /// Returns true if given line matches the start   
@@ -186,7 +493,68 @@ pub fn is_new_epoch (line: &str, header: &header::Header) -> bool {
 /// Builds a `Record`, `RINEX` file body content,
 /// which is constellation and `RINEX` file type dependent
 pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Result<(Record, Comments), Error> {
+    let (record, comments, _, _, _) = build_record_with_options(reader, header, &parser::ParserOptions::default())?;
+    Ok((record, comments))
+}
+
+/// Reports vehicules we could not interpret, and stores them
+/// in the raw record if requested
+fn report_unknown_entries (epoch: &epoch::Epoch, line: usize, entries: Vec<parser::RawEntry>, opts: &parser::ParserOptions, warnings: &mut parser::Diagnostics, raw: &mut parser::RawRecord) {
+    for entry in entries {
+        let message = match opts.unknown_constellation {
+            parser::UnknownConstellation::KeepRaw => format!("unknown vehicule \"{}\" kept as is", entry.sv),
+            _ => format!("unknown vehicule \"{}\" skipped", entry.sv),
+        };
+        warnings.push(parser::Diagnostic {
+            line: Some(line),
+            epoch: Some(*epoch),
+            message,
+        });
+        if opts.unknown_constellation == parser::UnknownConstellation::KeepRaw {
+            raw.entry(*epoch)
+                .or_insert_with(Vec::new)
+                .push(entry);
+        }
+    }
+}
+
+/// Reports a record entry we could not parse:
+/// an error in strict mode, a warning otherwise
+fn report_malformed_entry (content: &str, line: usize, opts: &parser::ParserOptions, warnings: &mut parser::Diagnostics) -> Result<(), Error> {
+    let descriptor = match content.lines().next() {
+        Some(line) if !line.trim().is_empty() => line.trim(),
+        _ => return Ok(()), // no content: nothing to report
+    };
+    if opts.strict {
+        return Err(Error::MalformedEntry(line, descriptor.to_string()))
+    }
+    warnings.push(parser::Diagnostic {
+        line: Some(line),
+        epoch: None,
+        message: format!("malformed entry \"{}\" skipped", descriptor),
+    });
+    Ok(())
+}
+
+/// Builds a `Record`, `RINEX` file body content, with given parsing options.
+/// Also returns warnings emitted during parsing,
+/// and record entries that were not interpreted (see [parser::UnknownConstellation]).
+/// Errors and warnings are located by line numbers,
+/// counted from current reader position
+pub fn build_record_with_options (reader: &mut BufferedReader, header: &header::Header, opts: &parser::ParserOptions) -> Result<(Record, Comments, observation::SiteEvents, parser::Diagnostics, parser::RawRecord), Error> {
+    build_record_at(reader, header, opts, &mut hatanaka::Decompressor::new(8), 0)
+}
+
+/// Builds a `Record` with given parsing options, `first_line` being
+/// the number of lines consumed prior to the record (header section).
+/// CRINEX records are recovered with given `decompressor`,
+/// which then holds the compression statistics
+pub(crate) fn build_record_at (reader: &mut BufferedReader, header: &header::Header, opts: &parser::ParserOptions, decompressor: &mut hatanaka::Decompressor, first_line: usize) -> Result<(Record, Comments, observation::SiteEvents, parser::Diagnostics, parser::RawRecord), Error> {
     let mut first_epoch = true;
+    let mut warnings = parser::Diagnostics::new();
+    let mut line_number : usize = first_line;
+    let mut epoch_line : usize = first_line; // line where current epoch starts
+    let mut raw = parser::RawRecord::new();
     let mut content : Option<String>; // epoch content to build
     let mut epoch_content = String::with_capacity(6*64);
     let mut exponent: i8 = -1; //IONEX record scaling: this is the default value
@@ -195,6 +563,8 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
     let mut comments : Comments = Comments::new();
     let mut comment_ts = epoch::Epoch::default();
     let mut comment_content : Vec<String> = Vec::with_capacity(4);
+    // OBS: special records following epoch events
+    let mut events = observation::SiteEvents::new();
 
     // CRINEX record special process is special
     // we need the decompression algorithm to run in rolling fashion
@@ -204,7 +574,6 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
     } else {
         false
     };
-    let mut decompressor = hatanaka::Decompressor::new(8);
     // record 
     let mut atx_rec = antex::record::Record::new(); // ATX
     let mut nav_rec = navigation::record::Record::new(); // NAV
@@ -214,7 +583,8 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
     let mut ionx_rec = ionosphere::record::Record::new(); //IONEX
 
     for l in reader.lines() { // iterates one line at a time 
-        let line = l.unwrap();
+        line_number += 1;
+        let line = l?;
         // COMMENTS special case
         // --> store
         // ---> append later with epoch.timestamp attached to it
@@ -286,44 +656,48 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
                                     nav_rec.insert(e, map);
                                 }
                                 comment_ts = e.clone(); // for comments classification & management
+                            } else {
+                                report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?;
                             }
                         },
                         Type::ObservationData => {
-                            if let Ok((e, ck_offset, map)) = observation::record::build_record_entry(&header, &epoch_content) {
-                                obs_rec.insert(e, (ck_offset, map));
+                            if let Ok(Some((e, event))) = observation::record::build_event_entry(&header, &epoch_content) {
+                                events.insert(e, event);
                                 comment_ts = e.clone(); // for comments classification & management
+                            } else {
+                                match observation::record::build_record_entry_with_options(&header, &epoch_content, opts) {
+                                    Ok((e, ck_offset, map, unknown)) => {
+                                        obs_rec.insert(e, (ck_offset, map));
+                                        report_unknown_entries(&e, epoch_line, unknown, opts, &mut warnings, &mut raw);
+                                        comment_ts = e.clone(); // for comments classification & management
+                                    },
+                                    Err(observation::record::Error::UnknownConstellation(sv)) => {
+                                        return Err(Error::UnknownConstellation(epoch_line, sv))
+                                    },
+                                    Err(_) => report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?,
+                                }
                             }
                         },
                         Type::MeteoData => {
                             if let Ok((e, map)) = meteo::record::build_record_entry(&header, &epoch_content) {
                                 met_rec.insert(e, map);
                                 comment_ts = e.clone(); // for comments classification & management
+                            } else {
+                                report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?;
                             }
                         },
                         Type::ClockData => {
-                            if let Ok((epoch, system, dtype, data)) = clocks::record::build_record_entry(&epoch_content) {
+                            if let Ok((epoch, system, dtype, data)) = clocks::record::build_record_entry(header.version, &epoch_content) {
                                 // Clocks `RINEX` files are handled a little different,
                                 // because we parse one line at a time, while we parsed one (unique) epoch at a time other RINEXes.
                                 // One line may contribute to a previously existing epoch in the record 
                                 // (different type of measurements etc..etc..)
-                                if let Some(e) = clk_rec.get_mut(&epoch) {
-                                    if let Some(s) = e.get_mut(&system) {
-                                        s.insert(dtype, data);
-                                    } else {
-                                        // --> new system entry for this `epoch`
-                                        let mut inner: HashMap<clocks::record::DataType, clocks::record::Data> = HashMap::new();
-                                        inner.insert(dtype, data);
-                                        e.insert(system, inner);
-                                    }
-                                } else {
-                                    // --> new epoch entry
-                                    let mut inner:HashMap<clocks::record::DataType, clocks::record::Data> = HashMap::new();
-                                    inner.insert(dtype, data);
-                                    let mut map : HashMap<clocks::record::System, HashMap<clocks::record::DataType, clocks::record::Data>> = HashMap::new();
-                                    map.insert(system, inner);
-                                    clk_rec.insert(epoch, map);
-                                }
+                                clk_rec.entry(epoch)
+                                    .or_insert_with(clocks::record::ClockEntries::default)
+                                    .insert(system, dtype, data);
                                 comment_ts = epoch.clone(); // for comments classification & management
+                            } else {
+                                report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?;
                             }
                         },
                         Type::AntennaData => {
@@ -345,7 +719,7 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
                         },
                         Type::IonosphereMaps => {
                             if let Ok((epoch, map)) = ionosphere::record::build_record_entry(&epoch_content, exponent) {
-                                ionx_rec.insert(epoch, (map, None, None));
+                                ionosphere::record::insert_map(&mut ionx_rec, &epoch_content, epoch, map);
                             }
                         }
                     }
@@ -362,6 +736,7 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
                         epoch_content.clear()
                     }
                     first_epoch = false;
+                    epoch_line = line_number;
                 }
                 // epoch content builder
                 epoch_content.push_str(&line);
@@ -395,49 +770,53 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
                     nav_rec.insert(e, map);
                 }
                 comment_ts = e.clone(); // for comments classification & management
+            } else {
+                report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?;
             }
         },
         Type::ObservationData => {
-            if let Ok((e, ck_offset, map)) = observation::record::build_record_entry(&header, &epoch_content) {
-                obs_rec.insert(e, (ck_offset, map));
+            if let Ok(Some((e, event))) = observation::record::build_event_entry(&header, &epoch_content) {
+                events.insert(e, event);
                 comment_ts = e.clone(); // for comments classification + management
+            } else {
+                match observation::record::build_record_entry_with_options(&header, &epoch_content, opts) {
+                    Ok((e, ck_offset, map, unknown)) => {
+                        obs_rec.insert(e, (ck_offset, map));
+                        report_unknown_entries(&e, epoch_line, unknown, opts, &mut warnings, &mut raw);
+                        comment_ts = e.clone(); // for comments classification + management
+                    },
+                    Err(observation::record::Error::UnknownConstellation(sv)) => {
+                        return Err(Error::UnknownConstellation(epoch_line, sv))
+                    },
+                    Err(_) => report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?,
+                }
             }
         },
         Type::MeteoData => {
             if let Ok((e, map)) = meteo::record::build_record_entry(&header, &epoch_content) {
                 met_rec.insert(e, map);
                 comment_ts = e.clone(); // for comments classification + management
+            } else {
+                report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?;
             }
         },
         Type::ClockData => {
-            if let Ok((e, system, dtype, data)) = clocks::record::build_record_entry(&epoch_content) {
+            if let Ok((e, system, dtype, data)) = clocks::record::build_record_entry(header.version, &epoch_content) {
                 // Clocks `RINEX` files are handled a little different,
                 // because we parse one line at a time, while we parsed one epoch at a time for other RINEXes.
                 // One line may contribute to a previously existing epoch in the record 
                 // (different type of measurements etc..etc..)
-                if let Some(e) = clk_rec.get_mut(&e) {
-                    if let Some(s) = e.get_mut(&system) {
-                        s.insert(dtype, data);
-                    } else {
-                        // --> new system entry for this `epoch`
-                        let mut inner: HashMap<clocks::record::DataType, clocks::record::Data> = HashMap::new();
-                        let mut map: HashMap<clocks::record::System, HashMap<clocks::record::DataType, clocks::record::Data>> = HashMap::new();
-                        inner.insert(dtype, data);
-                        map.insert(system, inner);
-                    }
-                } else {
-                    // --> new epoch entry
-                    let mut inner:HashMap<clocks::record::DataType, clocks::record::Data> = HashMap::new();
-                    inner.insert(dtype, data);
-                    let mut map : HashMap<clocks::record::System, HashMap<clocks::record::DataType, clocks::record::Data>> = HashMap::new();
-                    map.insert(system, inner);
-                    clk_rec.insert(e, map);
-                }
+                clk_rec.entry(e)
+                    .or_insert_with(clocks::record::ClockEntries::default)
+                    .insert(system, dtype, data);
                 comment_ts = e.clone(); // for comments classification & management
+            } else {
+                report_malformed_entry(&epoch_content, epoch_line, opts, &mut warnings)?;
             }
         },
         Type::IonosphereMaps => {
-            if let Ok((_epoch, _maps)) = ionosphere::record::build_record_entry(&epoch_content, exponent) {
+            if let Ok((epoch, map)) = ionosphere::record::build_record_entry(&epoch_content, exponent) {
+                ionosphere::record::insert_map(&mut ionx_rec, &epoch_content, epoch, map);
             }
         }
         Type::AntennaData => {
@@ -462,6 +841,21 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
     if !comment_content.is_empty() {
         comments.insert(comment_ts, comment_content.clone());
     }
+    // debug builds: verify epochs are expressed in the declared time system,
+    // an offset by a timezone from TIME OF FIRST OBS betrays a local time conversion
+    #[cfg(debug_assertions)] {
+        let declared = header.obs.as_ref()
+            .and_then(|obs| obs.time_of_first_obs);
+        if let (Some(declared), Some((first, _))) = (declared, obs_rec.iter().next()) {
+            if let Some(offset) = epoch::local_time_offset(&first.date, &declared.date) {
+                warnings.push(parser::Diagnostic {
+                    line: None,
+                    epoch: Some(*first),
+                    message: format!("first epoch is offset by {}' from TIME OF FIRST OBS: epochs are not expressed in the declared time system", offset),
+                });
+            }
+        }
+    }
     // wrap record
     let record = match &header.rinex_type {
         Type::AntennaData => Record::AntexRecord(atx_rec),
@@ -471,5 +865,206 @@ pub fn build_record (reader: &mut BufferedReader, header: &header::Header) -> Re
         Type::NavigationData => Record::NavRecord(nav_rec),
         Type::ObservationData => Record::ObsRecord(obs_rec), 
     };
-    Ok((record, comments))
+    Ok((record, comments, events, warnings, raw))
+}
+
+/// `StreamEntry` is a single `record` entry,
+/// as produced by a [Streamer], one epoch at a time
+#[derive(Clone, Debug)]
+pub enum StreamEntry {
+    /// ATX antenna block, ATX records are not epoch iterable
+    Antex(antex::antenna::Antenna, Vec<antex::frequency::Frequency>),
+    /// CLK single data line
+    Clock(epoch::Epoch, clocks::record::System, clocks::record::DataType, clocks::record::Data),
+    /// IONEX single map
+    Ionex(epoch::Epoch, ionosphere::record::Map),
+    /// MET epoch
+    Meteo(epoch::Epoch, HashMap<meteo::observable::Observable, f32>),
+    /// NAV single frame
+    Nav(epoch::Epoch, navigation::record::FrameClass, navigation::record::Frame),
+    /// OBS epoch, with possible receiver clock offset
+    Obs(epoch::Epoch, Option<f64>, BTreeMap<sv::Sv, HashMap<observation::Observable, observation::record::ObservationData>>),
+}
+
+/// Parses a single `record` entry from given epoch content
+fn build_stream_entry (header: &header::Header, content: &str, exponent: i8) -> Option<StreamEntry> {
+    match &header.rinex_type {
+        Type::AntennaData => {
+            if let Ok((antenna, frequencies)) = antex::record::build_record_entry(content) {
+                Some(StreamEntry::Antex(antenna, frequencies))
+            } else {
+                None
+            }
+        },
+        Type::ClockData => {
+            if let Ok((e, system, dtype, data)) = clocks::record::build_record_entry(header.version, content) {
+                Some(StreamEntry::Clock(e, system, dtype, data))
+            } else {
+                None
+            }
+        },
+        Type::IonosphereMaps => {
+            if let Ok((e, map)) = ionosphere::record::build_record_entry(content, exponent) {
+                Some(StreamEntry::Ionex(e, map))
+            } else {
+                None
+            }
+        },
+        Type::MeteoData => {
+            if let Ok((e, map)) = meteo::record::build_record_entry(header, content) {
+                Some(StreamEntry::Meteo(e, map))
+            } else {
+                None
+            }
+        },
+        Type::NavigationData => {
+            let constellation = header.constellation?;
+            if let Ok((e, class, fr)) = navigation::record::build_record_entry(header.version, constellation, content) {
+                Some(StreamEntry::Nav(e, class, fr))
+            } else {
+                None
+            }
+        },
+        Type::ObservationData => {
+            if let Ok((e, ck_offset, map)) = observation::record::build_record_entry(header, content) {
+                Some(StreamEntry::Obs(e, ck_offset, map))
+            } else {
+                None
+            }
+        },
+    }
+}
+
+/// `Streamer` parses a `RINEX` record lazily, one epoch at a time,
+/// instead of materializing the entire record in memory like [build_record] does.
+/// This is the prefered method to browse very large files (high rate daily OBS..).   
+/// `record` comments are not retained.
+/// I/O errors (invalid encoding, truncated gzip stream..) and CRINEX
+/// decompression failures are returned to the caller and terminate the iteration,
+/// malformed entries are skipped.
+pub struct Streamer {
+    /// previously parsed header section
+    header: header::Header,
+    /// line iterator, past header section
+    lines: std::io::Lines<BufferedReader>,
+    /// CRINEX decompressor, in case of compressed OBS
+    decompressor: Option<hatanaka::Decompressor>,
+    /// decompressed lines, not consumed yet
+    pending: VecDeque<String>,
+    /// epoch being built
+    epoch_content: String,
+    /// IONEX record scaling
+    exponent: i8,
+    /// true until first epoch is identified
+    first_epoch: bool,
+    /// true once end of file is reached, or an error was returned
+    eof: bool,
+}
+
+impl Streamer {
+    /// Builds a new `Streamer` from a reader
+    /// that was previously used to parse the `header` section
+    pub fn new (reader: BufferedReader, header: &header::Header) -> Self {
+        let crinex = if let Some(obs) = &header.obs {
+            obs.crinex.is_some()
+        } else {
+            false
+        };
+        Self {
+            header: header.clone(),
+            lines: reader.lines(),
+            decompressor: {
+                if crinex {
+                    Some(hatanaka::Decompressor::new(8))
+                } else {
+                    None
+                }
+            },
+            pending: VecDeque::new(),
+            epoch_content: String::with_capacity(6*64),
+            exponent: -1, // IONEX default scaling
+            first_epoch: true,
+            eof: false,
+        }
+    }
+
+    /// Returns `header` section this record is tied to
+    pub fn header (&self) -> &header::Header {
+        &self.header
+    }
+
+    /// Returns next (possibly decompressed) record line, comments are dropped
+    fn next_line (&mut self) -> Option<Result<String, Error>> {
+        loop {
+            if let Some(line) = self.pending.pop_front() {
+                return Some(Ok(line))
+            }
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(Error::IoError(e))),
+            };
+            if is_comment!(line) {
+                continue
+            }
+            // IONEX exponent-->data scaling
+            if line.contains("EXPONENT") {
+                let content = line.split_at(60).0;
+                if let Ok(e) = i8::from_str_radix(content.trim(), 10) {
+                    self.exponent = e
+                }
+            }
+            if let Some(decompressor) = &mut self.decompressor {
+                let mut l = line.to_owned();
+                l.push_str("\n");
+                let recovered = match decompressor.decompress(&self.header, &l) {
+                    Ok(recovered) => recovered,
+                    Err(e) => return Some(Err(Error::HatanakaError(e))),
+                };
+                for line in recovered.lines() {
+                    self.pending.push_back(line.to_string())
+                }
+            } else {
+                return Some(Ok(line))
+            }
+        }
+    }
+}
+
+impl Iterator for Streamer {
+    type Item = Result<StreamEntry, Error>;
+    fn next (&mut self) -> Option<Self::Item> {
+        if self.eof {
+            return None
+        }
+        while let Some(line) = self.next_line() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    self.eof = true; // content past an error can't be trusted
+                    return Some(Err(e))
+                },
+            };
+            let new_epoch = is_new_epoch(&line, &self.header);
+            let mut entry: Option<StreamEntry> = None;
+            if new_epoch {
+                if !self.first_epoch {
+                    entry = build_stream_entry(&self.header, &self.epoch_content, self.exponent);
+                    self.epoch_content.clear()
+                }
+                self.first_epoch = false
+            }
+            self.epoch_content.push_str(&line);
+            self.epoch_content.push_str("\n");
+            if let Some(entry) = entry {
+                return Some(Ok(entry))
+            }
+        }
+        // --> try to build an epoch out of current residues
+        self.eof = true;
+        if !self.first_epoch {
+            return build_stream_entry(&self.header, &self.epoch_content, self.exponent)
+                .map(Ok)
+        }
+        None
+    }
 }
@@ -0,0 +1,256 @@
+//! File carving: recovers intact `RINEX` segments
+//! from corrupted, truncated or concatenated streams
+use thiserror::Error;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Read;
+use crate::Rinex;
+use crate::header;
+use crate::record;
+use crate::parser;
+use crate::reader::BufferedReader;
+
+/// Header markers that initiate a new segment
+const RINEX_MARKER: &str = "RINEX VERSION / TYPE";
+const CRINEX_MARKER: &str = "CRINEX VERS   / TYPE";
+
+#[derive(Error, Debug)]
+/// File carving related errors
+pub enum Error {
+    #[error("file i/o error")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Describes what was recovered from a given segment
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostic {
+    /// Line number (starting at 1) where this segment begins,
+    /// in the input stream
+    pub start_line: usize,
+    /// Number of lines contained in this segment
+    pub lines: usize,
+    /// True if a complete header section was identified
+    pub header_complete: bool,
+    /// True if the record was interpreted with the header
+    /// of a previous segment, because its own header is missing or corrupt
+    pub header_borrowed: bool,
+    /// True if this segment was interrupted by a truncated line,
+    /// or by the beginning of another segment in the middle of a line
+    pub truncated: bool,
+    /// Number of epochs (number of antennas, for ATX)
+    /// that were recovered
+    pub epochs: usize,
+    /// Header or record parsing error description, if any
+    pub error: Option<String>,
+}
+
+/// A carved segment
+#[derive(Clone, Debug)]
+pub struct Segment {
+    /// Partially (or fully) recovered `RINEX`,
+    /// None if nothing could be interpreted
+    pub rinex: Option<Rinex>,
+    /// What was recovered
+    pub diagnostic: Diagnostic,
+}
+
+/// Scans given file for header markers and epoch patterns,
+/// and extracts every intact `RINEX` segment.
+/// This is intended to process truncated downloads
+/// or accidentally concatenated files.
+/// Supports .gz (with the `with-gzip` feature) and .Z compressed files.
+/// Segments are returned in order of appearance.
+///
+/// Example:
+/// ```
+/// use rinex::recover;
+/// let segments = recover::carve("../test_resources/NAV/V2/amel0010.21g")
+///     .unwrap();
+/// assert_eq!(segments.len(), 1);
+/// let segment = &segments[0];
+/// assert_eq!(segment.diagnostic.header_complete, true);
+/// assert_eq!(segment.diagnostic.truncated, false);
+/// assert_eq!(segment.rinex.is_some(), true);
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub fn carve (path: &str) -> Result<Vec<Segment>, Error> {
+    let mut reader = BufferedReader::new(path)?;
+    let mut content : Vec<u8> = Vec::new();
+    reader.read_to_end(&mut content)?;
+    Ok(carve_bytes(&content))
+}
+
+/// Identical to [carve], but works on given byte stream
+pub fn carve_bytes (content: &[u8]) -> Vec<Segment> {
+    // corrupt content is not necessarily valid Utf8
+    let content = String::from_utf8_lossy(content);
+    let ends_with_newline = content.ends_with('\n');
+    // [1] split into segments
+    let mut segments : Vec<(Diagnostic, Vec<String>)> = Vec::new();
+    let mut diagnostic = Diagnostic::default();
+    let mut lines : Vec<String> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let marker = match line.find(CRINEX_MARKER) {
+            Some(pos) => Some(pos),
+            None => {
+                // RINEX marker following CRINEX markers does not start a new segment
+                let crinex_prefix = lines.len() > 0 && lines.len() < 3
+                    && lines[0].contains(CRINEX_MARKER);
+                if crinex_prefix {
+                    None
+                } else {
+                    line.find(RINEX_MARKER)
+                }
+            },
+        };
+        if let Some(pos) = marker {
+            // new segment: might start in the middle of a line
+            // if previous segment was truncated
+            let mut offset = pos.saturating_sub(60);
+            while !line.is_char_boundary(offset) {
+                offset -= 1
+            }
+            if offset > 0 {
+                lines.push(line[..offset].to_string());
+                diagnostic.truncated = true;
+            }
+            if lines.len() > 0 {
+                diagnostic.lines = lines.len();
+                segments.push((diagnostic, lines));
+            }
+            diagnostic = Diagnostic {
+                start_line: index +1,
+                ..Default::default()
+            };
+            lines = vec![line[offset..].to_string()];
+        } else {
+            if lines.len() == 0 {
+                diagnostic.start_line = index +1;
+            }
+            lines.push(line.to_string())
+        }
+    }
+    if lines.len() > 0 {
+        diagnostic.lines = lines.len();
+        diagnostic.truncated |= !ends_with_newline;
+        segments.push((diagnostic, lines));
+    }
+    // [2] interprete each segment
+    let mut result : Vec<Segment> = Vec::with_capacity(segments.len());
+    let mut previous_header : Option<header::Header> = None;
+    for (mut diagnostic, mut lines) in segments {
+        let mut rinex : Option<Rinex> = None;
+        if diagnostic.truncated {
+            // partial line cannot be interpreted
+            lines.pop();
+        }
+        diagnostic.header_complete = lines
+            .iter()
+            .any(|l| l.contains("END OF HEADER"));
+        if diagnostic.header_complete {
+            let mut reader = BufferedReader::from_memory(to_bytes(&lines));
+            match header::Header::new(&mut reader) {
+                Ok(header) => {
+                    match record::build_record_with_options(&mut reader, &header, &parser::ParserOptions::default()) {
+                        Ok((record, comments, events, warnings, raw)) => {
+                            diagnostic.epochs = record_len(&record);
+                            rinex = Some(Rinex {
+                                header: header.clone(),
+                                record,
+                                comments,
+                                events,
+                                warnings,
+                                raw,
+                                history: false,
+                            })
+                        },
+                        Err(e) => diagnostic.error = Some(e.to_string()),
+                    }
+                    previous_header = Some(header)
+                },
+                Err(e) => diagnostic.error = Some(e.to_string()),
+            }
+        } else if let Some(header) = &previous_header {
+            // orphan record content:
+            // interprete from first epoch pattern, with previous header
+            let start = lines
+                .iter()
+                .position(|l| record::is_new_epoch(l, header));
+            if let Some(start) = start {
+                let mut reader = BufferedReader::from_memory(to_bytes(&lines[start..]));
+                match record::build_record_with_options(&mut reader, header, &parser::ParserOptions::default()) {
+                    Ok((record, comments, events, warnings, raw)) => {
+                        diagnostic.header_borrowed = true;
+                        diagnostic.epochs = record_len(&record);
+                        rinex = Some(Rinex {
+                            header: header.clone(),
+                            record,
+                            comments,
+                            events,
+                            warnings,
+                            raw,
+                            history: false,
+                        })
+                    },
+                    Err(e) => diagnostic.error = Some(e.to_string()),
+                }
+            } else {
+                diagnostic.error = Some(String::from("no header and no epoch identified"))
+            }
+        } else {
+            diagnostic.error = Some(String::from("no header identified"))
+        }
+        result.push(Segment {
+            rinex,
+            diagnostic,
+        })
+    }
+    result
+}
+
+/// Rebuilds a byte stream from given lines
+fn to_bytes (lines: &[String]) -> Vec<u8> {
+    let mut bytes : Vec<u8> = Vec::with_capacity(lines.len() * 81);
+    for line in lines {
+        bytes.extend_from_slice(line.as_bytes());
+        bytes.push(b'\n')
+    }
+    bytes
+}
+
+/// Returns number of entries in given record
+fn record_len (record: &record::Record) -> usize {
+    match record {
+        record::Record::AntexRecord(r) => r.len(),
+        record::Record::ClockRecord(r) => r.len(),
+        record::Record::IonexRecord(r) => r.len(),
+        record::Record::MeteoRecord(r) => r.len(),
+        record::Record::NavRecord(r) => r.len(),
+        record::Record::ObsRecord(r) => r.len(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_concatenated_carving() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/NAV/V2/amel0010.21g";
+        let content = std::fs::read(&path)
+            .unwrap();
+        let single = carve_bytes(&content);
+        assert_eq!(single.len(), 1);
+        let epochs = single[0].diagnostic.epochs;
+        assert!(epochs > 0);
+        // truncated copy, immediately followed by a complete copy
+        let mut stream = content[..content.len()-20].to_vec();
+        stream.extend_from_slice(&content);
+        let segments = carve_bytes(&stream);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].diagnostic.truncated, true);
+        assert_eq!(segments[0].diagnostic.header_complete, true);
+        assert_eq!(segments[1].diagnostic.truncated, false);
+        assert_eq!(segments[1].diagnostic.epochs, epochs);
+        assert_eq!(segments[1].rinex.is_some(), true);
+    }
+}
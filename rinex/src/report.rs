@@ -0,0 +1,279 @@
+//! Self describing reports, meant to be consumed programmatically
+//! (web frontends, CI gates..). With the `with-serde` feature,
+//! each report serializes to a JSON object with a stable schema:
+//!
+//! * `version`: schema version, see [VERSION]. Fields are only added
+//!   within a given version, removals and renamings increment it
+//! * `kind`: report kind, one of "Qc", "Validation", "Gaps", "Diff"
+//! * report specific fields, described on each report.
+//!
+//! Epochs serialize as "YYYY-MM-DD HH:MM:SS.f FLAG" strings,
+//! vehicules as "G01" and observables as "L1C".
+//! Durations are expressed in seconds.
+use crate::sv;
+use crate::qc;
+use crate::epoch;
+use crate::validation;
+use crate::observation::Observable;
+use crate::Rinex;
+
+/// Report schema version
+pub const VERSION: u32 = 1;
+
+/// Report kind
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub enum Kind {
+    /// [QcReport]
+    Qc,
+    /// [ValidationReport]
+    Validation,
+    /// [GapReport]
+    Gaps,
+    /// [DiffReport]
+    Diff,
+}
+
+/// Quality Check report
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct QcReport {
+    /// Schema version
+    pub version: u32,
+    /// Always [Kind::Qc]
+    pub kind: Kind,
+    /// Station metrics over the whole file
+    pub metrics: qc::StationMetrics,
+    /// Composite station score, in [0, 100]
+    pub score: f64,
+    /// Station metrics per aggregation period
+    pub summaries: Vec<qc::Summary>,
+    /// Interference events
+    pub interferences: Vec<qc::InterferenceEvent>,
+}
+
+impl QcReport {
+    /// Builds a QC report of given Observation RINEX, with default
+    /// QC parameters. Metrics are aggregated over given `period`
+    pub fn new (obs: &Rinex, period: qc::Period) -> Self {
+        let opts = qc::ScoreOpts::default();
+        let metrics = qc::station_metrics(obs, None, None, &opts);
+        let interferences = match obs.record.as_obs() {
+            Some(record) => qc::interference(record, &qc::InterferenceOpts::default()),
+            None => Vec::new(),
+        };
+        Self {
+            version: VERSION,
+            kind: Kind::Qc,
+            metrics,
+            score: metrics.score(&opts),
+            summaries: qc::aggregate(obs, period),
+            interferences,
+        }
+    }
+}
+
+/// Validation report
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct ValidationReport {
+    /// Schema version
+    pub version: u32,
+    /// Always [Kind::Validation]
+    pub kind: Kind,
+    /// Profile the file was validated against
+    pub profile: validation::Profile,
+    /// True if no rule was violated
+    pub compliant: bool,
+    /// Rule violations
+    pub violations: Vec<validation::Violation>,
+}
+
+impl ValidationReport {
+    /// Validates given RINEX against given profile, see [validation::validate]
+    pub fn new (rinex: &Rinex, profile: validation::Profile) -> Self {
+        let violations = validation::validate(rinex, profile);
+        Self {
+            version: VERSION,
+            kind: Kind::Validation,
+            profile,
+            compliant: violations.is_empty(),
+            violations,
+        }
+    }
+}
+
+/// Data gap
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct Gap {
+    /// Last epoch prior this gap
+    pub start: epoch::Epoch,
+    /// First epoch after this gap
+    pub end: epoch::Epoch,
+    /// Duration of this gap [s]
+    pub duration: f64,
+}
+
+/// Data gaps report
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct GapReport {
+    /// Schema version
+    pub version: u32,
+    /// Always [Kind::Gaps]
+    pub kind: Kind,
+    /// Sampling interval [s]: header INTERVAL,
+    /// smallest epoch interval otherwise. None for less than 2 epochs
+    pub interval: Option<f64>,
+    /// Data gaps, in chronological order
+    pub gaps: Vec<Gap>,
+}
+
+impl GapReport {
+    /// Identifies data gaps in given RINEX: successive epochs
+    /// further apart than the sampling interval
+    pub fn new (rinex: &Rinex) -> Self {
+        let epochs = rinex.epochs();
+        let interval = match rinex.header.sampling_interval {
+            Some(interval) => Some(chrono::Duration::nanoseconds((interval as f64 * 1.0E9).round() as i64)),
+            None => {
+                epochs
+                    .windows(2)
+                    .map(|w| w[1] - w[0])
+                    .filter(|dt| *dt > chrono::Duration::zero())
+                    .min()
+            },
+        };
+        let mut gaps: Vec<Gap> = Vec::new();
+        if let Some(interval) = interval {
+            for w in epochs.windows(2) {
+                let dt = w[1] - w[0];
+                if dt > interval {
+                    gaps.push(Gap {
+                        start: w[0],
+                        end: w[1],
+                        duration: seconds(dt),
+                    })
+                }
+            }
+        }
+        Self {
+            version: VERSION,
+            kind: Kind::Gaps,
+            interval: interval.map(seconds),
+            gaps,
+        }
+    }
+}
+
+/// Observation that differs between two files
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct Difference {
+    /// Epoch
+    pub epoch: epoch::Epoch,
+    /// Vehicule
+    pub sv: sv::Sv,
+    /// Observable
+    pub observable: Observable,
+    /// Observation in the first file, None if missing
+    pub a: Option<f64>,
+    /// Observation in the second file, None if missing
+    pub b: Option<f64>,
+}
+
+/// Observation files comparison report
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct DiffReport {
+    /// Schema version
+    pub version: u32,
+    /// Always [Kind::Diff]
+    pub kind: Kind,
+    /// Tolerance applied to observations
+    pub tolerance: f64,
+    /// Epochs only found in the first file
+    pub only_in_a: Vec<epoch::Epoch>,
+    /// Epochs only found in the second file
+    pub only_in_b: Vec<epoch::Epoch>,
+    /// Observations that differ by more than `tolerance`,
+    /// or are missing in either file, on common epochs
+    pub differences: Vec<Difference>,
+}
+
+impl DiffReport {
+    /// Compares two Observation RINEX. Produces an empty report
+    /// if either file is not an Observation RINEX
+    pub fn new (a: &Rinex, b: &Rinex, tolerance: f64) -> Self {
+        let mut report = Self {
+            version: VERSION,
+            kind: Kind::Diff,
+            tolerance,
+            only_in_a: Vec::new(),
+            only_in_b: Vec::new(),
+            differences: Vec::new(),
+        };
+        let (rec_a, rec_b) = match (a.record.as_obs(), b.record.as_obs()) {
+            (Some(rec_a), Some(rec_b)) => (rec_a, rec_b),
+            _ => return report,
+        };
+        report.only_in_b = rec_b.keys()
+            .filter(|e| !rec_a.contains_key(e))
+            .copied()
+            .collect();
+        for (e, (_, vehicules_a)) in rec_a.iter() {
+            let vehicules_b = match rec_b.get(e) {
+                Some((_, vehicules_b)) => vehicules_b,
+                None => {
+                    report.only_in_a.push(*e);
+                    continue
+                },
+            };
+            let mut svs: Vec<&sv::Sv> = vehicules_a.keys()
+                .chain(vehicules_b.keys())
+                .collect();
+            svs.sort();
+            svs.dedup();
+            for sv in svs {
+                let obs_a = vehicules_a.get(sv);
+                let obs_b = vehicules_b.get(sv);
+                let mut observables: Vec<&Observable> = obs_a.iter()
+                    .flat_map(|obs| obs.keys())
+                    .chain(obs_b.iter().flat_map(|obs| obs.keys()))
+                    .collect();
+                observables.sort();
+                observables.dedup();
+                for observable in observables {
+                    let value_a = obs_a.and_then(|obs| obs.get(observable)).map(|d| d.obs);
+                    let value_b = obs_b.and_then(|obs| obs.get(observable)).map(|d| d.obs);
+                    let differs = match (value_a, value_b) {
+                        (Some(va), Some(vb)) => (va - vb).abs() > tolerance,
+                        _ => true,
+                    };
+                    if differs {
+                        report.differences.push(Difference {
+                            epoch: *e,
+                            sv: *sv,
+                            observable: *observable,
+                            a: value_a,
+                            b: value_b,
+                        })
+                    }
+                }
+            }
+        }
+        report
+    }
+    /// Returns true if both files are identical, within tolerance
+    pub fn is_identical (&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.differences.is_empty()
+    }
+}
+
+/// Converts duration to seconds
+fn seconds (dt: chrono::Duration) -> f64 {
+    dt.num_nanoseconds()
+        .map(|ns| ns as f64 / 1.0E9)
+        .unwrap_or(dt.num_seconds() as f64)
+}
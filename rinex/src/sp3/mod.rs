@@ -0,0 +1,440 @@
+//! SP3 precise orbit & clock files parser & production.
+//! SP3 is not a `RINEX` format but is the natural companion
+//! of NAV RINEX for orbits & clocks comparison.
+//! SP3-c and SP3-d revisions are supported.
+use thiserror::Error;
+use std::str::FromStr;
+use std::io::BufRead;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+use std::collections::BTreeMap;
+use chrono::{Datelike, Timelike};
+
+use crate::sv;
+use crate::epoch;
+use crate::constellation;
+use crate::constellation::Constellation;
+use crate::reader::BufferedReader;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::writer::BufferedWriter;
+
+pub mod record;
+
+#[cfg(feature = "with-serde")]
+use serde::Serialize;
+
+/// Bad or absent clock value
+pub const BAD_CLOCK_VALUE: f64 = 999999.999999;
+
+/// Supported SP3 revisions
+#[derive(Copy, Clone, Debug)]
+#[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub enum Version {
+    /// SP3-c
+    C,
+    /// SP3-d
+    D,
+}
+
+impl Default for Version {
+    fn default() -> Self { Self::D }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::C => f.write_str("c"),
+            Self::D => f.write_str("d"),
+        }
+    }
+}
+
+/// Describes the content of an SP3 file
+#[derive(Copy, Clone, Debug)]
+#[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub enum DataType {
+    /// Positions and clocks only
+    Position,
+    /// Positions, velocities and clocks (and clock rates)
+    Velocity,
+}
+
+impl Default for DataType {
+    fn default() -> Self { Self::Position }
+}
+
+impl std::fmt::Display for DataType {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Position => f.write_str("P"),
+            Self::Velocity => f.write_str("V"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+/// SP3 parsing related errors
+pub enum Error {
+    #[error("file i/o error")]
+    IoError(#[from] std::io::Error),
+    #[error("this is not an SP3 file")]
+    NotSp3File,
+    #[error("non supported SP3 revision \"{0}\"")]
+    NonSupportedRevision(String),
+    #[error("unknown data type \"{0}\"")]
+    UnknownDataType(String),
+    #[error("failed to parse epoch")]
+    ParseEpochError(#[from] epoch::ParseDateError),
+    #[error("failed to parse integer number")]
+    ParseIntError(#[from] std::num::ParseIntError),
+    #[error("failed to parse float number")]
+    ParseFloatError(#[from] std::num::ParseFloatError),
+    #[error("failed to identify sat. vehicule")]
+    SvError(#[from] sv::Error),
+    #[error("failed to identify constellation")]
+    ConstellationError(#[from] constellation::Error),
+    #[error("missing header line \"{0}\"")]
+    MissingHeaderLine(String),
+    #[error("line is too short: \"{0}\"")]
+    TruncatedLine(String),
+}
+
+/// Returns given columns of this line, fails if the line is too short
+pub(crate) fn field<R> (line: &str, columns: R) -> Result<&str, Error>
+where
+    R: std::slice::SliceIndex<str, Output = str>,
+{
+    line.get(columns)
+        .ok_or_else(|| Error::TruncatedLine(line.to_string()))
+}
+
+/// SP3 header section
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct Header {
+    /// SP3 revision
+    pub version: Version,
+    /// Type of data contained in this file
+    pub data_type: DataType,
+    /// First epoch in this file
+    pub first_epoch: epoch::Epoch,
+    /// Number of epochs in this file
+    pub nb_epochs: u32,
+    /// Descriptor of the data used, "ORBIT", "u+U"..
+    pub data_used: String,
+    /// Coordinates system, "IGS14", "ITR20"..
+    pub coord_system: String,
+    /// Orbit type, "FIT", "HLM", "BCT"..
+    pub orbit_type: String,
+    /// Agency that produced this file
+    pub agency: String,
+    /// GPS week of first epoch
+    pub week: u32,
+    /// Seconds of week of first epoch
+    pub week_seconds: f64,
+    /// Epoch interval, in seconds
+    pub interval: f64,
+    /// Modified Julian Day of first epoch
+    pub mjd: u32,
+    /// Fractional part of the MJD of first epoch
+    pub mjd_fraction: f64,
+    /// Constellation described in this file,
+    /// `Mixed` for multi GNSS files
+    pub constellation: Constellation,
+    /// Time system, "GPS", "UTC", "GAL"..
+    pub time_system: String,
+    /// Floating point base for position / velocity accuracy
+    pub pos_vel_base: f64,
+    /// Floating point base for clock / clock rate accuracy
+    pub clk_base: f64,
+    /// Satellites described in this file
+    pub satellites: Vec<sv::Sv>,
+    /// Accuracy exponent, for each satellite.
+    /// 0 means unknown accuracy
+    pub accuracy: Vec<u8>,
+    /// Comments found in header section
+    pub comments: Vec<String>,
+}
+
+/// Parses an `Sv` identifier,
+/// SP3-a files may omit the GPS identifier
+fn parse_sv (content: &str) -> Result<sv::Sv, sv::Error> {
+    if content.starts_with(' ') {
+        sv::Sv::from_str(&format!("G{}", &content[1..]))
+    } else {
+        sv::Sv::from_str(content)
+    }
+}
+
+impl Header {
+    /// Parses header section from given reader.
+    /// Returns the first epoch line that terminated the header section
+    fn parse (reader: &mut BufferedReader) -> Result<(Self, Option<String>), Error> {
+        let mut header = Self::default();
+        let mut lines = reader.lines();
+        // [1] first line
+        let line = match lines.next() {
+            Some(l) => l?,
+            None => return Err(Error::NotSp3File),
+        };
+        if !line.starts_with('#') || line.len() < 60 {
+            return Err(Error::NotSp3File)
+        }
+        header.version = match field(&line, 1..2)? {
+            "c" => Version::C,
+            "d" => Version::D,
+            v => return Err(Error::NonSupportedRevision(v.to_string())),
+        };
+        header.data_type = match field(&line, 2..3)? {
+            "P" => DataType::Position,
+            "V" => DataType::Velocity,
+            t => return Err(Error::UnknownDataType(t.to_string())),
+        };
+        header.first_epoch = epoch::Epoch::new(
+            epoch::str2date(field(&line, 3..31)?)?,
+            epoch::EpochFlag::Ok);
+        header.nb_epochs = u32::from_str_radix(field(&line, 32..39)?.trim(), 10)?;
+        header.data_used = field(&line, 40..45)?.trim().to_string();
+        header.coord_system = field(&line, 46..51)?.trim().to_string();
+        header.orbit_type = field(&line, 52..55)?.trim().to_string();
+        header.agency = field(&line, 56..)?.trim().to_string();
+        // [2] second line
+        let line = match lines.next() {
+            Some(l) => l?,
+            None => return Err(Error::MissingHeaderLine(String::from("##"))),
+        };
+        if !line.starts_with("##") || line.len() < 60 {
+            return Err(Error::MissingHeaderLine(String::from("##")))
+        }
+        header.week = u32::from_str_radix(field(&line, 3..7)?.trim(), 10)?;
+        header.week_seconds = f64::from_str(field(&line, 8..23)?.trim())?;
+        header.interval = f64::from_str(field(&line, 24..38)?.trim())?;
+        header.mjd = u32::from_str_radix(field(&line, 39..44)?.trim(), 10)?;
+        header.mjd_fraction = f64::from_str(field(&line, 45..)?.trim())?;
+        // [3] remaining lines
+        let mut nb_sat : usize = 0;
+        let mut first_c = true;
+        let mut first_f = true;
+        for line in lines {
+            let line = line?;
+            if line.starts_with("*") {
+                // end of header
+                return Ok((header, Some(line)))
+            } else if line.starts_with("++") {
+                for i in 0..17 {
+                    if header.accuracy.len() == nb_sat {
+                        break
+                    }
+                    let offset = 9 + i*3;
+                    if let Some(acc) = line.get(offset..offset+3) {
+                        header.accuracy.push(u8::from_str_radix(acc.trim(), 10)?)
+                    }
+                }
+            } else if line.starts_with("+ ") {
+                if nb_sat == 0 {
+                    nb_sat = usize::from_str_radix(field(&line, 2..6)?.trim(), 10)?;
+                }
+                for i in 0..17 {
+                    if header.satellites.len() == nb_sat {
+                        break
+                    }
+                    let offset = 9 + i*3;
+                    if let Some(sv) = line.get(offset..offset+3) {
+                        header.satellites.push(parse_sv(sv)?)
+                    }
+                }
+            } else if line.starts_with("%c") {
+                if first_c {
+                    header.constellation = Constellation::from_1_letter_code(field(&line, 3..4)?.trim())?;
+                    header.time_system = field(&line, 9..12)?.trim().to_string();
+                    first_c = false
+                }
+            } else if line.starts_with("%f") {
+                if first_f {
+                    header.pos_vel_base = f64::from_str(field(&line, 3..13)?.trim())?;
+                    header.clk_base = f64::from_str(field(&line, 14..26)?.trim())?;
+                    first_f = false
+                }
+            } else if line.starts_with("/*") {
+                let comment = line.get(2..).unwrap_or("").trim();
+                if comment.len() > 0 {
+                    header.comments.push(comment.to_string())
+                }
+            }
+        }
+        Ok((header, None))
+    }
+}
+
+impl std::fmt::Display for Header {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let date = self.first_epoch.date;
+        write!(f, "#{}{}", self.version, self.data_type)?;
+        let seconds = date.time().second() as f64
+            + date.time().nanosecond() as f64 * 1.0E-9;
+        write!(f, "{:4} {:2} {:2} {:2} {:2} {:11.8} ",
+            date.year(), date.month(), date.day(),
+            date.time().hour(), date.time().minute(), seconds)?;
+        write!(f, "{:7} ", self.nb_epochs)?;
+        write!(f, "{:<5} {:<5} {:<3} {:<4}\n", self.data_used, self.coord_system, self.orbit_type, self.agency)?;
+        write!(f, "## {:4} {:15.8} {:14.8} {:5} {:15.13}\n", self.week, self.week_seconds, self.interval, self.mjd, self.mjd_fraction)?;
+        // satellites & accuracy: at least 5 lines
+        let nb_lines = std::cmp::max(5, (self.satellites.len() + 16) / 17);
+        for i in 0..nb_lines {
+            if i == 0 {
+                write!(f, "+  {:3}   ", self.satellites.len())?;
+            } else {
+                write!(f, "+        ")?;
+            }
+            for j in 0..17 {
+                if let Some(sv) = self.satellites.get(i*17+j) {
+                    write!(f, "{}", sv)?
+                } else {
+                    write!(f, "  0")?
+                }
+            }
+            write!(f, "\n")?
+        }
+        for i in 0..nb_lines {
+            write!(f, "++       ")?;
+            for j in 0..17 {
+                write!(f, "{:3}", self.accuracy.get(i*17+j).unwrap_or(&0))?
+            }
+            write!(f, "\n")?
+        }
+        write!(f, "%c {}  cc {:<3} ccc cccc cccc cccc cccc ccccc ccccc ccccc ccccc\n",
+            self.constellation.to_1_letter_code(), self.time_system)?;
+        write!(f, "%c cc cc ccc ccc cccc cccc cccc cccc ccccc ccccc ccccc ccccc\n")?;
+        write!(f, "%f {:10.7} {:12.9}  0.00000000000  0.000000000000000\n", self.pos_vel_base, self.clk_base)?;
+        write!(f, "%f  0.0000000  0.000000000  0.00000000000  0.000000000000000\n")?;
+        write!(f, "%i    0    0    0    0      0      0      0      0         0\n")?;
+        write!(f, "%i    0    0    0    0      0      0      0      0         0\n")?;
+        // comments: at least 4 lines
+        for comment in self.comments.iter() {
+            write!(f, "/* {}\n", comment)?
+        }
+        for _ in self.comments.len()..4 {
+            write!(f, "/*\n")?
+        }
+        Ok(())
+    }
+}
+
+/// `Sp3` describes an SP3 precise orbit file
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct Sp3 {
+    /// Header section
+    pub header: Header,
+    /// Orbits & clocks record
+    pub record: record::Record,
+}
+
+impl Sp3 {
+    /// Parses given SP3 file.
+    /// Supports .gz (with the `with-gzip` feature) and .Z compressed files
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file (path: &str) -> Result<Self, Error> {
+        let mut reader = BufferedReader::new(path)?;
+        let (header, first_epoch) = Header::parse(&mut reader)?;
+        let record = record::parse(&mut reader, &header, first_epoch)?;
+        Ok(Self {
+            header,
+            record,
+        })
+    }
+
+    /// Writes self into given file.
+    /// File is gzip compressed if `path` terminates with .gz,
+    /// this requires the `with-gzip` feature
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_file (&self, path: &str) -> std::io::Result<()> {
+        let mut writer = BufferedWriter::new(path)?;
+        write!(writer, "{}", self.header)?;
+        record::to_file(&self.header, &self.record, &mut writer)?;
+        write!(writer, "EOF\n")?;
+        writer.finish()
+    }
+
+    /// Parses SP3 content from given byte slice,
+    /// like the content of a file uploaded in a browser
+    pub fn from_bytes (content: &[u8]) -> Result<Self, Error> {
+        let mut reader = BufferedReader::from_memory(content.to_vec());
+        let (header, first_epoch) = Header::parse(&mut reader)?;
+        let record = record::parse(&mut reader, &header, first_epoch)?;
+        Ok(Self {
+            header,
+            record,
+        })
+    }
+
+    /// Returns list of epochs contained in self
+    pub fn epochs (&self) -> Vec<epoch::Epoch> {
+        self.record
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Returns (x, y, z) positions in km,
+    /// on an epoch basis and per space vehicule
+    pub fn sv_positions (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, (f64,f64,f64)>> {
+        self.record
+            .iter()
+            .map(|(e, vehicules)| {
+                let vehicules = vehicules
+                    .iter()
+                    .map(|(sv, entry)| (*sv, entry.position))
+                    .collect();
+                (*e, vehicules)
+            })
+            .collect()
+    }
+
+    /// Returns clock offsets in microseconds,
+    /// on an epoch basis and per space vehicule.
+    /// Bad or missing clock values are not exposed.
+    pub fn sv_clocks (&self) -> BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, f64>> {
+        self.record
+            .iter()
+            .map(|(e, vehicules)| {
+                let vehicules = vehicules
+                    .iter()
+                    .filter_map(|(sv, entry)| {
+                        entry.clock.map(|clk| (*sv, clk))
+                    })
+                    .collect();
+                (*e, vehicules)
+            })
+            .collect()
+    }
+
+    /// Returns (x, y, z) positions in km,
+    /// of given vehicule, on an epoch basis
+    pub fn sv_position (&self, sv: sv::Sv) -> BTreeMap<epoch::Epoch, (f64,f64,f64)> {
+        self.record
+            .iter()
+            .filter_map(|(e, vehicules)| {
+                vehicules
+                    .get(&sv)
+                    .map(|entry| (*e, entry.position))
+            })
+            .collect()
+    }
+
+    /// Returns clock offsets in microseconds,
+    /// of given vehicule, on an epoch basis
+    pub fn sv_clock (&self, sv: sv::Sv) -> BTreeMap<epoch::Epoch, f64> {
+        self.record
+            .iter()
+            .filter_map(|(e, vehicules)| {
+                vehicules
+                    .get(&sv)
+                    .and_then(|entry| entry.clock)
+                    .map(|clk| (*e, clk))
+            })
+            .collect()
+    }
+}
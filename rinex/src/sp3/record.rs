@@ -0,0 +1,126 @@
+//! SP3 record: orbits & clocks
+use std::str::FromStr;
+use std::io::{BufRead, Write};
+use std::collections::BTreeMap;
+use chrono::{Datelike, Timelike};
+
+use crate::sv;
+use crate::epoch;
+use crate::reader::BufferedReader;
+use crate::writer::BufferedWriter;
+use super::{field, parse_sv, Header, Error, BAD_CLOCK_VALUE};
+
+#[cfg(feature = "with-serde")]
+use serde::Serialize;
+
+/// Orbit & clock state of a vehicule, at a given epoch
+#[derive(Copy, Clone, Debug, Default)]
+#[derive(PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct Entry {
+    /// (x, y, z) position, in km
+    pub position: (f64, f64, f64),
+    /// Clock offset, in microseconds
+    pub clock: Option<f64>,
+    /// (x, y, z) velocity, in dm/s
+    pub velocity: Option<(f64, f64, f64)>,
+    /// Clock rate of change, in 1E-4 microseconds/s
+    pub clock_rate: Option<f64>,
+}
+
+/// SP3 record content, sorted by epoch and by vehicule
+pub type Record = BTreeMap<epoch::Epoch, BTreeMap<sv::Sv, Entry>>;
+
+/// Parses (x, y, z, clock) fields of a "P" or "V" line
+fn parse_state (line: &str) -> Result<((f64,f64,f64), Option<f64>), Error> {
+    let mut values : [f64; 3] = [0.0; 3];
+    for i in 0..3 {
+        let offset = 4 + i*14;
+        values[i] = f64::from_str(field(line, offset..offset+14)?.trim())?
+    }
+    let clock = match line.get(46..60) {
+        Some(content) => {
+            let content = content.trim();
+            if content.len() > 0 {
+                let value = f64::from_str(content)?;
+                if value >= BAD_CLOCK_VALUE {
+                    None
+                } else {
+                    Some(value)
+                }
+            } else {
+                None
+            }
+        },
+        None => None,
+    };
+    Ok(((values[0], values[1], values[2]), clock))
+}
+
+/// Builds SP3 record from given reader,
+/// `first_line` is the epoch line that terminated the header section
+pub fn parse (reader: &mut BufferedReader, _header: &Header, first_line: Option<String>) -> Result<Record, Error> {
+    let mut record = Record::new();
+    let mut epoch = match first_line {
+        Some(line) => epoch::Epoch::new(
+            epoch::str2date(field(&line, 1..)?)?,
+            epoch::EpochFlag::Ok),
+        None => return Ok(record),
+    };
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with("EOF") {
+            break
+        } else if line.starts_with("*") {
+            epoch = epoch::Epoch::new(
+                epoch::str2date(field(&line, 1..)?)?,
+                epoch::EpochFlag::Ok);
+        } else if line.starts_with("P") {
+            let sv = parse_sv(field(&line, 1..4)?)?;
+            let (position, clock) = parse_state(&line)?;
+            let vehicules = record
+                .entry(epoch)
+                .or_insert_with(BTreeMap::new);
+            let entry = vehicules
+                .entry(sv)
+                .or_insert_with(Entry::default);
+            entry.position = position;
+            entry.clock = clock;
+        } else if line.starts_with("V") {
+            let sv = parse_sv(field(&line, 1..4)?)?;
+            let (velocity, clock_rate) = parse_state(&line)?;
+            let vehicules = record
+                .entry(epoch)
+                .or_insert_with(BTreeMap::new);
+            let entry = vehicules
+                .entry(sv)
+                .or_insert_with(Entry::default);
+            entry.velocity = Some(velocity);
+            entry.clock_rate = clock_rate;
+        }
+        // EP / EV correlation lines are not interpreted
+    }
+    Ok(record)
+}
+
+/// Writes given SP3 record into given writer
+pub fn to_file (_header: &Header, record: &Record, writer: &mut BufferedWriter) -> std::io::Result<()> {
+    for (epoch, vehicules) in record.iter() {
+        let date = epoch.date;
+        let seconds = date.time().second() as f64
+            + date.time().nanosecond() as f64 * 1.0E-9;
+        write!(writer, "*  {:4} {:2} {:2} {:2} {:2} {:11.8}\n",
+            date.year(), date.month(), date.day(),
+            date.time().hour(), date.time().minute(), seconds)?;
+        for (sv, entry) in vehicules.iter() {
+            let (x, y, z) = entry.position;
+            write!(writer, "P{}{:14.6}{:14.6}{:14.6}{:14.6}\n",
+                sv, x, y, z, entry.clock.unwrap_or(BAD_CLOCK_VALUE))?;
+            if let Some((x, y, z)) = entry.velocity {
+                write!(writer, "V{}{:14.6}{:14.6}{:14.6}{:14.6}\n",
+                    sv, x, y, z, entry.clock_rate.unwrap_or(BAD_CLOCK_VALUE))?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,9 +1,10 @@
 //! Satellite vehicule representation 
 use thiserror::Error;
 use crate::constellation;
+use crate::constellation::augmentation::Augmentation;
 
 #[cfg(feature = "with-serde")]
-use serde::{Serialize, Serializer};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
 /// ̀`Sv` describes a Satellite Vehiculee
 #[derive(Copy, Clone, Debug)]
@@ -17,23 +18,19 @@ pub struct Sv {
 
 impl std::cmp::PartialOrd for Sv {
     fn partial_cmp (&self, rhs: &Self) -> Option<std::cmp::Ordering> {
-        let (c1, c2) = (self.constellation, rhs.constellation); 
-        if c1 == c2 { // same constellation: PRN # differientiates
-            self.prn.partial_cmp(&rhs.prn)
-        } else { // By alphabetical order
-            c1.to_1_letter_code().partial_cmp(c2.to_1_letter_code())
-        }
+        Some(self.cmp(rhs))
     }
 }
 
 impl std::cmp::Ord for Sv {
+    /// Vehicules are sorted by constellation (alphabetical order
+    /// of the 1 letter code), then by PRN #.
+    /// This ordering is used when producing files, it is total and
+    /// consistent with `Eq`, so output is deterministic
     fn cmp (&self, rhs: &Self) -> std::cmp::Ordering {
         let (c1, c2) = (self.constellation, rhs.constellation); 
-        if c1 == c2 { // same constellation: PRN # differentiates
-            self.prn.cmp(&rhs.prn)
-        } else { // By alphabetical order
-            c1.to_1_letter_code().cmp(c2.to_1_letter_code())
-        }
+        c1.to_1_letter_code().cmp(c2.to_1_letter_code())
+            .then(self.prn.cmp(&rhs.prn))
     }
 }
 
@@ -50,6 +47,21 @@ impl Serialize for Sv {
     }
 }
 
+#[cfg(feature = "with-serde")]
+impl<'de> Deserialize<'de> for Sv {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        if s.len() < 2 || !s.is_char_boundary(1) {
+            return Err(serde::de::Error::custom(format!("invalid vehicule \"{}\"", s)))
+        }
+        <Self as std::str::FromStr>::from_str(&s)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 /// ̀`Sv` parsing & identification related errors
 #[derive(Error, Debug)]
 pub enum Error {
@@ -69,21 +81,56 @@ impl Default for Sv {
     }
 }
 
+/// SBAS vehicules are identified by their PRN # minus 100 in RINEX
+const SBAS_PRN_OFFSET: u8 = 100;
+
 impl Sv {
-    /// Creates a new `Sv` descriptor
-    pub fn new (constellation: constellation::Constellation, prn: u8) -> Sv { Sv {constellation, prn }}
+    /// Creates a new `Sv` descriptor.
+    /// SBAS vehicules are described by their RINEX identification
+    /// number (PRN - 100), their augmentation system is then
+    /// identified from the PRN #, see [Augmentation::from_prn]
+    pub fn new (constellation: constellation::Constellation, prn: u8) -> Sv {
+        let constellation = match constellation {
+            constellation::Constellation::SBAS(sbas) => {
+                let sbas = prn.checked_add(SBAS_PRN_OFFSET)
+                    .and_then(Augmentation::from_prn)
+                    .unwrap_or(sbas);
+                constellation::Constellation::SBAS(sbas)
+            },
+            c => c,
+        };
+        Sv { constellation, prn }
+    }
+    /// Returns full PRN # of this SBAS vehicule (120 to 158),
+    /// None if this is not an SBAS vehicule
+    pub fn sbas_prn (&self) -> Option<u8> {
+        match self.constellation {
+            constellation::Constellation::SBAS(_) => self.prn.checked_add(SBAS_PRN_OFFSET),
+            _ => None,
+        }
+    }
+    /// Returns augmentation system operating this SBAS vehicule,
+    /// None if this is not an SBAS vehicule
+    pub fn augmentation (&self) -> Option<Augmentation> {
+        self.constellation.augmentation()
+    }
 }
 
 impl std::str::FromStr for Sv {
     type Err = Error;
     /// Builds an `Sv` from XYY identification code.   
     /// code should strictly follow rinex conventions.   
-    /// This method tolerates trailing whitespaces 
+    /// This method tolerates trailing whitespaces.
+    /// SBAS vehicules may also be described by their full PRN # ("S123")
     fn from_str (s: &str) -> Result<Self, Self::Err> {
-        Ok(Sv {
-            constellation: constellation::Constellation::from_1_letter_code(&s[0..1])?,
-            prn: u8::from_str_radix(&s[1..].trim(), 10)?
-        })
+        let constellation = constellation::Constellation::from_1_letter_code(&s[0..1])?;
+        let mut prn = u8::from_str_radix(&s[1..].trim(), 10)?;
+        if let constellation::Constellation::SBAS(_) = constellation {
+            if prn >= SBAS_PRN_OFFSET {
+                prn -= SBAS_PRN_OFFSET
+            }
+        }
+        Ok(Sv::new(constellation, prn))
     }
 }
 
@@ -107,4 +154,37 @@ mod test {
             let _ = Sv::from_str(t).unwrap();
         }
     }
+    #[test]
+    fn test_sv_ordering() {
+        let mut vehicules : Vec<Sv> = vec!["R09", "G12", "E01", "G01", "C05", "G10"]
+            .iter()
+            .map(|s| Sv::from_str(s).unwrap())
+            .collect();
+        vehicules.sort();
+        let vehicules : Vec<String> = vehicules
+            .iter()
+            .map(|sv| sv.to_string())
+            .collect();
+        assert_eq!(vehicules, vec!["C05", "E01", "G01", "G10", "G12", "R09"]);
+    }
+    #[test]
+    fn test_sbas() {
+        let sv = Sv::from_str("S23").unwrap();
+        assert_eq!(sv.prn, 23);
+        assert_eq!(sv.sbas_prn(), Some(123));
+        assert_eq!(sv.augmentation(), Some(Augmentation::EGNOS));
+        assert_eq!(sv.to_string(), "S23");
+        // full PRN #
+        assert_eq!(Sv::from_str("S123").unwrap(), sv);
+        let sv = Sv::from_str("S38").unwrap();
+        assert_eq!(sv.augmentation(), Some(Augmentation::WAAS));
+        let sv = Sv::new(constellation::Constellation::SBAS(Augmentation::default()), 29);
+        assert_eq!(sv.augmentation(), Some(Augmentation::MSAS));
+        // unassigned PRN: augmentation is preserved
+        let sv = Sv::new(constellation::Constellation::SBAS(Augmentation::KASS), 57);
+        assert_eq!(sv.augmentation(), Some(Augmentation::KASS));
+        let sv = Sv::from_str("G23").unwrap();
+        assert_eq!(sv.sbas_prn(), None);
+        assert_eq!(sv.augmentation(), None);
+    }
 }
@@ -48,6 +48,7 @@ impl Type {
             Type::NavigationData => {
                 match constell {
                     Some(constellation::Constellation::Glonass) => String::from("Glonass NAV"),
+                    Some(constellation::Constellation::SBAS(_)) => String::from("GEO NAV MSG"),
                     _ => String::from("NAV DATA"),
                 }
             },
@@ -64,7 +65,7 @@ impl std::str::FromStr for Type {
     fn from_str (s: &str) -> Result<Self, Self::Err> {
         if s.eq("NAVIGATION DATA") {
             Ok(Type::NavigationData)
-        } else if s.contains("NAV DATA") {
+        } else if s.contains("NAV DATA") || s.contains("NAV MSG DATA") {
             Ok(Type::NavigationData)
         } else if s.eq("OBSERVATION DATA") {
             Ok(Type::ObservationData)
@@ -0,0 +1,411 @@
+//! Validation of `RINEX` content against a target profile:
+//! a revision of the standard, or data center submission rules.
+//! Only rules applicable to the selected profile are checked
+use crate::Rinex;
+use crate::epoch;
+use crate::header::Header;
+use crate::types::Type;
+use crate::version::Version;
+use crate::observation::Observable;
+use crate::constellation::Constellation;
+use std::collections::HashSet;
+
+/// Validation profile: standard (or set of rules)
+/// a file is certified against
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+pub enum Profile {
+    /// RINEX 2.11
+    V2_11,
+    /// RINEX 3.04
+    V3_04,
+    /// RINEX 4.00
+    V4_00,
+    /// IGS data submission rules: RINEX 3.04 and later,
+    /// with complete station metadata
+    Igs,
+}
+
+impl Profile {
+    /// Returns the profile of given revision: latest
+    /// minor revision of given major revision.
+    /// Returns None for revisions that do not have a profile
+    pub fn from_version (version: Version) -> Option<Self> {
+        match version.major {
+            2 => Some(Self::V2_11),
+            3 => Some(Self::V3_04),
+            4 => Some(Self::V4_00),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::V2_11 => write!(f, "RINEX 2.11"),
+            Self::V3_04 => write!(f, "RINEX 3.04"),
+            Self::V4_00 => write!(f, "RINEX 4.00"),
+            Self::Igs => write!(f, "IGS"),
+        }
+    }
+}
+
+/// Validation rule
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    /// Rule identifier, like "HDR-001"
+    pub id: &'static str,
+    /// Rule description
+    pub description: &'static str,
+    /// Profiles this rule applies to
+    profiles: &'static [Profile],
+}
+
+impl Rule {
+    /// Returns true if this rule applies to given profile
+    pub fn applies_to (&self, profile: Profile) -> bool {
+        self.profiles.contains(&profile)
+    }
+}
+
+const ALL: &[Profile] = &[Profile::V2_11, Profile::V3_04, Profile::V4_00, Profile::Igs];
+const MODERN: &[Profile] = &[Profile::V3_04, Profile::V4_00, Profile::Igs];
+
+/// Revision must match the profile
+pub const VERSION: Rule = Rule {
+    id: "VER-001",
+    description: "revision number must match the profile",
+    profiles: ALL,
+};
+/// PGM / RUN BY / DATE
+pub const PROGRAM: Rule = Rule {
+    id: "HDR-001",
+    description: "PGM / RUN BY / DATE program must be specified",
+    profiles: ALL,
+};
+/// MARKER NAME
+pub const MARKER_NAME: Rule = Rule {
+    id: "HDR-002",
+    description: "OBS: MARKER NAME must be specified",
+    profiles: ALL,
+};
+/// APPROX POSITION XYZ
+pub const APPROX_POSITION: Rule = Rule {
+    id: "HDR-003",
+    description: "OBS: APPROX POSITION XYZ must be specified",
+    profiles: ALL,
+};
+/// REC # / TYPE / VERS and ANT # / TYPE
+pub const HARDWARE: Rule = Rule {
+    id: "HDR-004",
+    description: "OBS: receiver and antenna must be described",
+    profiles: ALL,
+};
+/// MARKER TYPE
+pub const MARKER_TYPE: Rule = Rule {
+    id: "HDR-005",
+    description: "OBS: MARKER TYPE must be specified",
+    profiles: MODERN,
+};
+/// TIME OF FIRST OBS
+pub const TIME_OF_FIRST_OBS: Rule = Rule {
+    id: "HDR-006",
+    description: "OBS: TIME OF FIRST OBS must be specified",
+    profiles: MODERN,
+};
+/// V2 observables
+pub const V2_OBSERVABLES: Rule = Rule {
+    id: "OBS-001",
+    description: "OBS: observables must be 2 character codes, no I or X observables",
+    profiles: &[Profile::V2_11],
+};
+/// V3+ observables
+pub const V3_OBSERVABLES: Rule = Rule {
+    id: "OBS-002",
+    description: "OBS: observables must be 3 character codes, no P observables",
+    profiles: MODERN,
+};
+/// Observables
+pub const DECLARED_OBSERVABLES: Rule = Rule {
+    id: "HDR-007",
+    description: "OBS, MET: observables must be declared",
+    profiles: ALL,
+};
+/// Empty record
+pub const EMPTY_RECORD: Rule = Rule {
+    id: "REC-001",
+    description: "record must not be empty",
+    profiles: ALL,
+};
+/// Sampling interval
+pub const SAMPLING: Rule = Rule {
+    id: "REC-002",
+    description: "OBS: epochs must not be closer than declared INTERVAL",
+    profiles: ALL,
+};
+/// TIME OF FIRST OBS consistency
+pub const FIRST_OBS_CONSISTENCY: Rule = Rule {
+    id: "REC-003",
+    description: "OBS: TIME OF FIRST OBS must match the first epoch",
+    profiles: ALL,
+};
+/// Declared observables
+pub const UNUSED_OBSERVABLES: Rule = Rule {
+    id: "REC-004",
+    description: "OBS: declared observables must be observed at least once",
+    profiles: ALL,
+};
+/// IGS: INTERVAL
+pub const IGS_INTERVAL: Rule = Rule {
+    id: "IGS-001",
+    description: "OBS: INTERVAL must be specified",
+    profiles: &[Profile::Igs],
+};
+/// IGS: hardware serial numbers
+pub const IGS_SERIAL_NUMBERS: Rule = Rule {
+    id: "IGS-002",
+    description: "OBS: receiver and antenna serial numbers must be specified",
+    profiles: &[Profile::Igs],
+};
+/// IGS: OBSERVER / AGENCY
+pub const IGS_AGENCY: Rule = Rule {
+    id: "IGS-003",
+    description: "OBSERVER / AGENCY must be specified",
+    profiles: &[Profile::Igs],
+};
+/// IGS: marker name
+pub const IGS_MARKER_NAME: Rule = Rule {
+    id: "IGS-004",
+    description: "OBS: MARKER NAME must start with the 4 character site identifier",
+    profiles: &[Profile::Igs],
+};
+
+/// All rules, in order of evaluation
+pub const RULES: &[Rule] = &[
+    VERSION, PROGRAM, MARKER_NAME, APPROX_POSITION, HARDWARE,
+    MARKER_TYPE, TIME_OF_FIRST_OBS, DECLARED_OBSERVABLES,
+    V2_OBSERVABLES, V3_OBSERVABLES,
+    EMPTY_RECORD, SAMPLING, FIRST_OBS_CONSISTENCY, UNUSED_OBSERVABLES,
+    IGS_INTERVAL, IGS_SERIAL_NUMBERS, IGS_AGENCY, IGS_MARKER_NAME,
+];
+
+/// Rule violation
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct Violation {
+    /// Identifier of the violated rule, see [RULES]
+    pub rule: &'static str,
+    /// Epoch at which the violation was identified, if any
+    pub epoch: Option<epoch::Epoch>,
+    /// Description of the violation
+    pub message: String,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[{}] ", self.rule)?;
+        if let Some(epoch) = self.epoch {
+            write!(f, "{}: ", epoch.date)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Validates given `RINEX` against given profile.
+/// Only rules applicable to this profile are checked, see [Rule::applies_to].
+/// Header is validated first, see [validate_header], then the record,
+/// and its consistency with the header.
+/// Returns an empty list when given `RINEX` complies.
+pub fn validate (rinex: &Rinex, profile: Profile) -> Vec<Violation> {
+    let mut violations = validate_header(&rinex.header, profile);
+    let mut report = |rule: &Rule, epoch: Option<epoch::Epoch>, message: String| {
+        if rule.applies_to(profile) {
+            violations.push(Violation {
+                rule: rule.id,
+                epoch,
+                message,
+            })
+        }
+    };
+    let header = &rinex.header;
+    let epochs = rinex.epochs();
+    if epochs.is_empty() {
+        report(&EMPTY_RECORD, None, String::from("no epochs"))
+    }
+    if header.rinex_type != Type::ObservationData {
+        return violations
+    }
+    if let Some(interval) = header.sampling_interval {
+        let interval = chrono::Duration::milliseconds((interval as f64 * 1.0E3) as i64);
+        for pair in epochs.windows(2) {
+            let dt = pair[1].date - pair[0].date;
+            if dt > chrono::Duration::zero() && dt < interval {
+                report(&SAMPLING, Some(pair[1]), format!("epoch is {}s after previous epoch", dt.num_milliseconds() as f64 / 1.0E3))
+            }
+        }
+    }
+    let obs = match &header.obs {
+        Some(obs) => obs,
+        None => return violations,
+    };
+    if let (Some(first), Some(time_of_first_obs)) = (epochs.first(), obs.time_of_first_obs) {
+        if first.date != time_of_first_obs.date {
+            report(&FIRST_OBS_CONSISTENCY, Some(*first), format!("TIME OF FIRST OBS is {}", time_of_first_obs.date))
+        }
+    }
+    if let Some(record) = rinex.record.as_obs() {
+        let mut observed: HashSet<(Constellation, Observable)> = HashSet::new();
+        for (_, (_, vehicules)) in record.iter() {
+            for (sv, observations) in vehicules.iter() {
+                for code in observations.keys() {
+                    observed.insert((sv.constellation, *code));
+                }
+            }
+        }
+        // V2 headers declare shared observables for all constellations:
+        // only constellations actually present in the record are verified
+        let mut codes: Vec<_> = obs.codes.iter()
+            .filter(|(constellation, _)| observed.iter().any(|(c, _)| c == *constellation))
+            .collect();
+        codes.sort_by_key(|(constellation, _)| **constellation);
+        for (constellation, codes) in codes {
+            for code in codes {
+                if !observed.contains(&(*constellation, *code)) {
+                    report(&UNUSED_OBSERVABLES, None, format!("{:?}: \"{}\" is declared but never observed", constellation, code))
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// Validates given header against given profile.
+/// Only rules applicable to this profile are checked, see [Rule::applies_to].
+/// Returns an empty list when given header complies.
+pub fn validate_header (header: &Header, profile: Profile) -> Vec<Violation> {
+    let mut violations: Vec<Violation> = Vec::new();
+    let mut report = |rule: &Rule, epoch: Option<epoch::Epoch>, message: String| {
+        if rule.applies_to(profile) {
+            violations.push(Violation {
+                rule: rule.id,
+                epoch,
+                message,
+            })
+        }
+    };
+    let version = header.version;
+    let expected = match profile {
+        Profile::V2_11 => version.major == 2 && version.minor == 11,
+        Profile::V3_04 => version.major == 3 && version.minor == 4,
+        Profile::V4_00 => version.major == 4 && version.minor == 0,
+        Profile::Igs => version.major > 3 || (version.major == 3 && version.minor >= 4),
+    };
+    if !expected {
+        report(&VERSION, None, format!("revision {}.{:02} does not match {}", version.major, version.minor, profile))
+    }
+    if header.program.trim().is_empty() {
+        report(&PROGRAM, None, String::from("missing program"))
+    }
+    if header.observer.trim().is_empty() || header.agency.trim().is_empty() {
+        report(&IGS_AGENCY, None, String::from("missing observer or agency"))
+    }
+    if header.rinex_type == Type::MeteoData {
+        let declared = header.meteo.as_ref()
+            .map(|meteo| meteo.codes.len() > 0)
+            .unwrap_or(false);
+        if !declared {
+            report(&DECLARED_OBSERVABLES, None, String::from("no observables declared"))
+        }
+    }
+
+    if header.rinex_type != Type::ObservationData {
+        return violations
+    }
+
+    let station = header.station.trim();
+    if station.is_empty() {
+        report(&MARKER_NAME, None, String::from("missing marker name"))
+    }
+    let site = station.get(0..4).unwrap_or("");
+    if site.len() < 4 || !site.chars().all(|c| c.is_ascii_alphanumeric()) {
+        report(&IGS_MARKER_NAME, None, format!("\"{}\" is not a valid site identifier", station))
+    }
+    if header.coords.is_none() {
+        report(&APPROX_POSITION, None, String::from("missing approximate position"))
+    }
+    if header.marker_type.is_none() {
+        report(&MARKER_TYPE, None, String::from("missing marker type"))
+    }
+    match (&header.rcvr, &header.ant) {
+        (Some(rcvr), Some(ant)) => {
+            if rcvr.sn.trim().is_empty() || ant.sn.trim().is_empty() {
+                report(&IGS_SERIAL_NUMBERS, None, String::from("missing serial number"))
+            }
+        },
+        _ => {
+            report(&HARDWARE, None, String::from("missing receiver or antenna"));
+            report(&IGS_SERIAL_NUMBERS, None, String::from("missing serial number"))
+        },
+    }
+    match &header.obs {
+        Some(obs) => {
+            if obs.time_of_first_obs.is_none() {
+                report(&TIME_OF_FIRST_OBS, None, String::from("missing time of first observation"))
+            }
+            if obs.codes.values().all(|codes| codes.is_empty()) {
+                report(&DECLARED_OBSERVABLES, None, String::from("no observables declared"))
+            }
+            let mut codes: Vec<_> = obs.codes.iter().collect();
+            codes.sort_by_key(|(constellation, _)| **constellation);
+            for (constellation, codes) in codes {
+                for code in codes {
+                    let v3_only = matches!(code, Observable::Ionosphere(_) | Observable::ChannelNumber(_));
+                    if v3_only || code.code().attribute.is_some() {
+                        report(&V2_OBSERVABLES, None, format!("{:?}: \"{}\" is not a V2 observable", constellation, code))
+                    }
+                    if matches!(code, Observable::PCodePseudoRange(_))
+                        || (!v3_only && code.code().attribute.is_none()) {
+                        report(&V3_OBSERVABLES, None, format!("{:?}: \"{}\" is not a V3 observable", constellation, code))
+                    }
+                }
+            }
+        },
+        None => {
+            report(&TIME_OF_FIRST_OBS, None, String::from("missing time of first observation"));
+            report(&DECLARED_OBSERVABLES, None, String::from("no observables declared"))
+        },
+    }
+    if header.sampling_interval.is_none() {
+        report(&IGS_INTERVAL, None, String::from("missing sampling interval"))
+    }
+    violations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_rules() {
+        let mut ids: Vec<&str> = RULES.iter().map(|r| r.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), RULES.len(), "rule identifiers must be unique");
+        assert_eq!(VERSION.applies_to(Profile::V2_11), true);
+        assert_eq!(V2_OBSERVABLES.applies_to(Profile::V3_04), false);
+        assert_eq!(V3_OBSERVABLES.applies_to(Profile::Igs), true);
+        assert_eq!(IGS_INTERVAL.applies_to(Profile::V4_00), false);
+        assert_eq!(Profile::from_version(Version { major: 3, minor: 2 }), Some(Profile::V3_04));
+        assert_eq!(Profile::from_version(Version { major: 1, minor: 0 }), None);
+    }
+    #[test]
+    fn test_display() {
+        let v = Violation {
+            rule: "HDR-001",
+            epoch: None,
+            message: String::from("missing program"),
+        };
+        assert_eq!(v.to_string(), "[HDR-001] missing program");
+        assert_eq!(Profile::V3_04.to_string(), "RINEX 3.04");
+    }
+}
@@ -0,0 +1,82 @@
+//! Generic Buffered Writer, for efficient record production,
+//! with possible internal Gz compression.
+use std::io::{BufWriter, Write}; 
+#[cfg(feature = "with-gzip")]
+use flate2::{Compression, write::GzEncoder};
+
+#[derive(Debug)]
+pub enum WriterWrapper {
+    /// Plain `RINEX` (or `CRINEX`)
+    PlainFile(BufWriter<std::fs::File>),
+    /// gzip compressed RINEX
+    #[cfg(feature = "with-gzip")]
+    GzFile(BufWriter<GzEncoder<std::fs::File>>),
+}
+
+pub struct BufferedWriter {
+    /// Internal writer,
+    /// supports Plain RINEX, CRINEX, .gz
+    writer: WriterWrapper,
+}
+
+impl BufferedWriter {
+    /// Builds a new BufferedWriter for efficient file production,
+    /// with possible .gz compression.
+    /// Producing .gz files requires the `with-gzip` feature,
+    /// otherwise this returns an `Unsupported` error and no file is created
+    pub fn new (path: &str) -> std::io::Result<Self> {
+        if path.ends_with(".gz") {
+            // --> gzip encoded
+            #[cfg(feature = "with-gzip")] {
+                // .gz
+                // example : i.gz, .n.gz, .crx.gz 
+                let f = std::fs::File::create(path)?;
+                Ok(Self {
+                    writer: WriterWrapper::GzFile(BufWriter::new(GzEncoder::new(f, Compression::new(5)))),
+                })
+            }
+            #[cfg(not(feature = "with-gzip"))] {
+                Err(std::io::Error::new(std::io::ErrorKind::Unsupported,
+                    "gzip compressed data require the --with-gzip build feature"))
+            }
+        
+        } else { // Assumes no extra compression
+            let f = std::fs::File::create(path)?;
+            Ok(Self {
+                writer: WriterWrapper::PlainFile(BufWriter::new(f)),
+            })
+        }
+    }
+    /// Flushes all pending content and terminates the file,
+    /// writing the gzip trailer when compressing.
+    /// Unlike dropping `self`, this reports I/O errors
+    pub fn finish (self) -> std::io::Result<()> {
+        match self.writer {
+            WriterWrapper::PlainFile(mut h) => h.flush(),
+            #[cfg(feature = "with-gzip")]
+            WriterWrapper::GzFile(h) => {
+                let encoder = h.into_inner()
+                    .map_err(std::io::Error::from)?;
+                encoder.finish()?;
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::io::Write for BufferedWriter {
+    fn write (&mut self, buf: &[u8]) -> Result<usize, std::io::Error> { 
+        match self.writer {
+            WriterWrapper::PlainFile(ref mut h) => h.write(buf),
+            #[cfg(feature = "with-gzip")]
+            WriterWrapper::GzFile(ref mut h) => h.write(buf),
+        }
+    }
+    fn flush (&mut self) -> Result<(), std::io::Error> { 
+        match self.writer {
+            WriterWrapper::PlainFile(ref mut h) => h.flush(),
+            #[cfg(feature = "with-gzip")]
+            WriterWrapper::GzFile(ref mut h) => h.flush(),
+        }
+    }
+}
@@ -12,7 +12,7 @@ mod test {
         assert_eq!(rinex.is_ok(), true);
         let rinex = rinex.unwrap();
         assert_eq!(rinex.is_antex_rinex(), true);
-        let header = rinex.header;
+        let header = &rinex.header;
         assert_eq!(header.version.major, 1);
         assert_eq!(header.version.minor, 4);
         assert_eq!(header.antex.is_some(), true);
@@ -32,12 +32,19 @@ mod test {
         let cal = &antenna.calibration;
         assert_eq!(cal.method, Method::Chamber);
         assert_eq!(cal.agency, "IGG, Univ. Bonn");
-        assert_eq!(cal.date, "23-SEP-20");
+        assert_eq!(cal.number, 1);
+        assert_eq!(cal.date, Some(chrono::NaiveDate::from_ymd(2020, 9, 23)));
         assert_eq!(antenna.dazi, 5.0);
         assert_eq!(antenna.zen, (0.0, 90.0));
         assert_eq!(antenna.dzen, 5.0);
         assert_eq!(antenna.valid_from.is_none(), true);
         assert_eq!(antenna.valid_until.is_none(), true);
+        assert_eq!(antenna.is_individual(), true);
+        // calibration lookup
+        let date = epoch::str2date("2021 01 01 00 00 00").unwrap();
+        let cal = rinex.antenna_calibration("TROSAR25.R4", "LEIT727259", date);
+        assert_eq!(cal.is_some(), true);
+        assert_eq!(rinex.antenna_calibration("TROSAR25.R4", "LEIT000000", date).is_none(), true);
         for freq in frequencies.iter() {
             let first = freq.patterns.first();
             assert_eq!(first.is_some(), true);
@@ -31,5 +31,128 @@ mod test {
         //assert_eq!(rinex.is_ok(), true);
         //let rinex = rinex.unwrap();
         //assert_eq!(rinex.is_clocks_rinex(), true);
+        assert_eq!(clocks.analysis_center().map(|a| a.code.as_str()), Some("USN"));
+        assert_eq!(clocks.reference_clocks.len(), 2);
+        let refs = &clocks.reference_clocks[0];
+        assert_eq!(refs.start.map(|e| e.date), Some(epoch::str2date("1994 07 14 00 00 00").unwrap()));
+        assert_eq!(refs.end.map(|e| e.date), Some(epoch::str2date("1994 07 14 20 59 59").unwrap()));
+        assert_eq!(refs.clocks, vec![
+            clocks::ReferenceClock {
+                name: String::from("USNO"),
+                id: String::from("40451S003"),
+                constraint: Some(-0.123456789012E+00),
+            },
+        ]);
+        let e = epoch::Epoch::new(
+            epoch::str2date("1994 07 14 22 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let names: Vec<&str> = clocks.reference_clocks_at(e)
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["TIBD"]);
+    }
+    #[test]
+    fn v3_04_reference_clocks() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/CLK/V3/example1.txt";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let clocks = rinex.header.clocks
+            .as_ref()
+            .unwrap();
+        assert_eq!(clocks.reference_clocks.len(), 2);
+        let clock = &clocks.reference_clocks[1].clocks[0];
+        assert_eq!(clock.name, "TIDB");
+        assert_eq!(clock.id, "50103M108");
+        assert_eq!(clock.constraint, Some(-0.123456789012E+00));
+    }
+    #[test]
+    fn v3_production() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/CLK/V3/USNO1.txt";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let copy = std::env::temp_dir()
+            .join("USNO1.txt")
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(rinex.to_file(&copy).is_ok(), true);
+        let parsed = Rinex::from_file(&copy);
+        assert_eq!(parsed.is_ok(), true);
+        let parsed = parsed.unwrap();
+        let _ = std::fs::remove_file(&copy);
+        assert_eq!(parsed.header.clocks.as_ref().unwrap().reference_clocks,
+            rinex.header.clocks.as_ref().unwrap().reference_clocks);
+        assert_eq!(parsed.header.clocks.as_ref().unwrap().agency,
+            rinex.header.clocks.as_ref().unwrap().agency);
+        assert_eq!(parsed.record.as_clock(), rinex.record.as_clock());
+        // reference clocks are required for analysis results
+        let mut rinex = rinex;
+        rinex.header.clocks.as_mut().unwrap().reference_clocks.clear();
+        let mut buffer: Vec<u8> = Vec::new();
+        assert_eq!(rinex.to_writer(&mut buffer).is_err(), true);
+    }
+    #[test]
+    fn v3_constellation_filter() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/CLK/V3/USNO1.txt";
+        let rinex = Rinex::from_file(&test_resource);
+        assert_eq!(rinex.is_ok(), true);
+        let mut rinex = rinex.unwrap();
+        rinex.constellation_filter_mut(vec![constellation::Constellation::Galileo]);
+        let record = rinex.record
+            .as_clock()
+            .unwrap();
+        for (_e, entries) in record.iter() {
+            // AS entries were all GPS: only stations remain
+            assert_eq!(entries.satellites().is_empty(), true);
+            assert_eq!(entries.stations().is_empty(), false);
+        }
+    }
+    #[test]
+    fn v3_sv_station_filters() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/CLK/V3/USNO1.txt";
+        let rinex = Rinex::from_file(&test_resource);
+        assert_eq!(rinex.is_ok(), true);
+        let mut rinex = rinex.unwrap();
+        let g16 = sv::Sv {
+            constellation: constellation::Constellation::GPS,
+            prn: 16,
+        };
+        rinex.space_vehicule_filter_mut(vec![g16]);
+        rinex.clock_station_filter_mut(vec![]);
+        let record = rinex.record
+            .as_clock()
+            .unwrap();
+        for (_e, entries) in record.iter() {
+            assert_eq!(entries.stations().is_empty(), true);
+            for (sv, _) in entries.satellites().iter() {
+                assert_eq!(*sv, g16);
+            }
+        }
+    }
+    #[test]
+    fn v3_usno1_cache() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/CLK/V3/USNO1.txt";
+        let cache = std::env::temp_dir().join("usno1.rnxcache");
+        let cache = cache.to_str().unwrap();
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        rinex.to_cache(&test_resource, cache)
+            .unwrap();
+        let cached = Rinex::from_cache(&test_resource, cache)
+            .unwrap();
+        assert_eq!(cached.record.as_clock(), rinex.record.as_clock());
+        assert_eq!(cached.comments, rinex.comments);
+        let _ = std::fs::remove_file(cache);
     }
 }
@@ -31,4 +31,74 @@ mod merge {
         //println!("is merged          : {}", rinex.is_merged_rinex());
         //println!("boundaries: \n{:#?}", rinex.merge_boundaries());
     }
+    #[test]
+    fn test_merge_teqc_markers() {
+        use rinex::merge::MarkerCompat;
+        let test_resources = env!("CARGO_MANIFEST_DIR").to_owned() + "/../test_resources/";
+        let path1 = test_resources.to_owned() + "NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx";
+        let mut r1 = Rinex::from_file(&path1).unwrap();
+        let path2 = test_resources.to_owned() + "NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx";
+        let r2 = Rinex::from_file(&path2).unwrap();
+        let boundaries = r1.merge_boundaries().len() + r2.merge_boundaries().len();
+        assert_eq!(r1.merge_with_markers_mut(&r2, &[MarkerCompat::Teqc]).is_ok(), true);
+        // produce, parse back
+        let copy_path = std::env::temp_dir().join("amel00nld-teqc-merged.rnx");
+        let copy_path = copy_path.to_str().unwrap();
+        r1.to_file(copy_path).unwrap();
+        let parsed = Rinex::from_file(copy_path).unwrap();
+        let _ = std::fs::remove_file(copy_path);
+        assert_eq!(parsed.is_merged(), true);
+        // native + teqc markers describe a single boundary
+        assert_eq!(parsed.merge_boundaries().len(), boundaries + 1);
+        let teqc = parsed.header.comments.iter()
+            .find(|c| c.starts_with("teqc") && c.contains("rustrnx-"))
+            .unwrap();
+        assert_eq!(rinex::merge::marker_date(teqc).is_some(), true);
+        // teqc compatible marker on its own is recognized
+        let mut teqc_only = parsed.clone();
+        teqc_only.header.comments.retain(|c| !c.contains("FILE MERGE"));
+        assert_eq!(teqc_only.is_merged(), true);
+    }
+    #[test]
+    fn test_merge_clocks() {
+        let test_resources = env!("CARGO_MANIFEST_DIR").to_owned() + "/../test_resources/";
+        let path = test_resources.to_owned() + "CLK/V3/USNO1.txt";
+        let mut r1 = Rinex::from_file(&path).unwrap();
+        let r2 = Rinex::from_file(&path).unwrap();
+        assert_eq!(r1.merge_mut(&r2).is_ok(), true);
+        assert_eq!(r1.epochs(), r2.epochs());
+        assert_eq!(r1.record.as_clock(), r2.record.as_clock());
+    }
+    #[test]
+    fn test_merge_antex() {
+        let test_resources = env!("CARGO_MANIFEST_DIR").to_owned() + "/../test_resources/";
+        let path1 = test_resources.to_owned() + "ATX/V1/ROULAR25.24__LEIT_2020_09_24.atx";
+        let mut r1 = Rinex::from_file(&path1).unwrap();
+        let path2 = test_resources.to_owned() + "ATX/V1/TROSAR25.R4__LEIT_2020_09_23.atx";
+        let r2 = Rinex::from_file(&path2).unwrap();
+        let (n1, n2) = (r1.record.as_antex().unwrap().len(), r2.record.as_antex().unwrap().len());
+        assert_eq!(r1.merge_mut(&r2).is_ok(), true);
+        assert_eq!(r1.record.as_antex().unwrap().len(), n1 + n2);
+        assert_eq!(r1.is_merged(), true);
+        assert_eq!(r1.merge_boundaries().len(), 1);
+        // antennas are not duplicated
+        assert_eq!(r1.merge_mut(&r2).is_ok(), true);
+        assert_eq!(r1.record.as_antex().unwrap().len(), n1 + n2);
+    }
+    #[test]
+    fn test_merge_antex_markers() {
+        use rinex::merge::MarkerCompat;
+        let test_resources = env!("CARGO_MANIFEST_DIR").to_owned() + "/../test_resources/";
+        let path1 = test_resources.to_owned() + "ATX/V1/ROULAR25.24__LEIT_2020_09_24.atx";
+        let mut r1 = Rinex::from_file(&path1).unwrap();
+        let path2 = test_resources.to_owned() + "ATX/V1/TROSAR25.R4__LEIT_2020_09_23.atx";
+        let r2 = Rinex::from_file(&path2).unwrap();
+        assert_eq!(r1.merge_with_markers_mut(&r2, &[MarkerCompat::Gfzrnx, MarkerCompat::Teqc]).is_ok(), true);
+        // our marker, followed by the third party markers
+        let n = r1.header.comments.len();
+        let markers = &r1.header.comments[n-3..];
+        assert!(markers[0].starts_with("rustrnx-"));
+        assert!(markers[1].starts_with("gfzrnx"));
+        assert!(markers[2].starts_with("teqc"));
+    }
 }
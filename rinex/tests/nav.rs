@@ -3,8 +3,8 @@ mod test {
     use rinex::*;
     use rinex::sv::Sv;
     use rinex::constellation::Constellation;
-    use rinex::navigation::record::MsgType;
-    use rinex::navigation::record::FrameClass;
+    use rinex::navigation::{MsgType, FrameClass};
+    use rinex::gnss_time::TimeScale;
     #[test]
     fn v2_amel0010_21g() {
         let test_resource = 
@@ -49,102 +49,102 @@ mod test {
                 for frame in frames.iter() { // only EPH
                     let ephemeris = frame.as_eph(); // ONLY EPH in V2
                     assert_eq!(ephemeris.is_some(), true);
-                    let (msgtype, sv, clk, clk_dr, clk_drr, data) = ephemeris.unwrap();
+                    let (msgtype, sv, clk, clk_dr, clk_drr, _) = ephemeris.unwrap();
                     assert_eq!(msgtype, MsgType::LNAV); // legacy NAV
                     assert_eq!(expected_vehicules.contains(&sv), true);            
                     if sv.prn == 1 {
                         assert_eq!(clk, 7.282570004460E-5);
                         assert_eq!(clk_dr, 0.0);
                         assert_eq!(clk_drr, 7.380000000000E+04);
-                        let posx = data.get("satPosX").unwrap();
+                        let posx = frame.eph_field("satPosX").unwrap();
                         assert_eq!(posx.as_f64(), Some(-1.488799804690E+03));
-                        let posy = data.get("satPosY").unwrap();
+                        let posy = frame.eph_field("satPosY").unwrap();
                         assert_eq!(posy.as_f64(), Some( 1.292880712890E+04));
-                        let posz = data.get("satPosZ").unwrap();
+                        let posz = frame.eph_field("satPosZ").unwrap();
                         assert_eq!(posz.as_f64(), Some( 2.193169775390E+04));
-                        let health = data.get("health").unwrap();
+                        let health = frame.eph_field("health").unwrap();
                         assert_eq!(health.as_f64(), Some(0.0));
-                        let freq = data.get("freqNum").unwrap();
+                        let freq = frame.eph_field("freqNum").unwrap();
                         assert_eq!(freq.as_f64(), Some(1.0));
-                        let ageop = data.get("ageOp").unwrap();
+                        let ageop = frame.eph_field("ageOp").unwrap();
                         assert_eq!(ageop.as_f64(), Some(0.0));
                     } else if sv.prn == 2 {
                         assert_eq!(clk, 4.610531032090E-04);
                         assert_eq!(clk_dr, 1.818989403550E-12);
                         assert_eq!(clk_drr,  4.245000000000E+04);
-                        let posx = data.get("satPosX").unwrap();
+                        let posx = frame.eph_field("satPosX").unwrap();
                         assert_eq!(posx.as_f64(), Some(-8.955041992190E+03));
-                        let posy = data.get("satPosY").unwrap();
+                        let posy = frame.eph_field("satPosY").unwrap();
                         assert_eq!(posy.as_f64(), Some(-1.834875292970E+04));
-                        let posz = data.get("satPosZ").unwrap();
+                        let posz = frame.eph_field("satPosZ").unwrap();
                         assert_eq!(posz.as_f64(), Some( 1.536620703130E+04));
-                        let freq = data.get("freqNum").unwrap();
+                        let freq = frame.eph_field("freqNum").unwrap();
                         assert_eq!(freq.as_f64(), Some(-4.0));
-                        let ageop = data.get("ageOp").unwrap();
+                        let ageop = frame.eph_field("ageOp").unwrap();
                         assert_eq!(ageop.as_f64(), Some(0.0));
                     } else if sv.prn == 3 {
                         assert_eq!(clk, 2.838205546140E-05); 
                         assert_eq!(clk_dr, 0.0); 
                         assert_eq!(clk_drr, 4.680000000000E+04);
-                        let posx = data.get("satPosX").unwrap();
+                        let posx = frame.eph_field("satPosX").unwrap();
                         assert_eq!(posx.as_f64(), Some(1.502522949220E+04));
-                        let posy = data.get("satPosY").unwrap();
+                        let posy = frame.eph_field("satPosY").unwrap();
                         assert_eq!(posy.as_f64(), Some(-1.458877050780E+04));
-                        let posz = data.get("satPosZ").unwrap();
+                        let posz = frame.eph_field("satPosZ").unwrap();
                         assert_eq!(posz.as_f64(), Some( 1.455863281250E+04));
-                        let health = data.get("health").unwrap();
+                        let health = frame.eph_field("health").unwrap();
                         assert_eq!(health.as_f64(), Some(0.0));
-                        let freq = data.get("freqNum").unwrap();
+                        let freq = frame.eph_field("freqNum").unwrap();
                         assert_eq!(freq.as_f64(), Some(5.0));
-                        let ageop = data.get("ageOp").unwrap();
+                        let ageop = frame.eph_field("ageOp").unwrap();
                         assert_eq!(ageop.as_f64(), Some(0.0));
                     } else if sv.prn == 4 {
                         assert_eq!(clk,  6.817653775220E-05);
                         assert_eq!(clk_dr, 1.818989403550E-12);
                         assert_eq!(clk_drr, 4.680000000000E+04);
-                        let posx = data.get("satPosX").unwrap();
+                        let posx = frame.eph_field("satPosX").unwrap();
                         assert_eq!(posx.as_f64(), Some(-1.688173828130E+03));
-                        let posy = data.get("satPosY").unwrap();
+                        let posy = frame.eph_field("satPosY").unwrap();
                         assert_eq!(posy.as_f64(), Some(-1.107156738280E+04));
-                        let posz = data.get("satPosZ").unwrap();
+                        let posz = frame.eph_field("satPosZ").unwrap();
                         assert_eq!(posz.as_f64(), Some( 2.293745361330E+04));
-                        let health = data.get("health").unwrap();
+                        let health = frame.eph_field("health").unwrap();
                         assert_eq!(health.as_f64(), Some(0.0));
-                        let freq = data.get("freqNum").unwrap();
+                        let freq = frame.eph_field("freqNum").unwrap();
                         assert_eq!(freq.as_f64(), Some(6.0));
-                        let ageop = data.get("ageOp").unwrap();
+                        let ageop = frame.eph_field("ageOp").unwrap();
                         assert_eq!(ageop.as_f64(), Some(0.0));
                     } else if sv.prn == 5 {
                         assert_eq!(clk, 6.396882236000E-05);
                         assert_eq!(clk_dr, 9.094947017730E-13);
                         assert_eq!(clk_drr, 8.007000000000E+04); 
-                        let posx = data.get("satPosX").unwrap();
+                        let posx = frame.eph_field("satPosX").unwrap();
                         assert_eq!(posx.as_f64(), Some( -1.754308935550E+04));
-                        let posy = data.get("satPosY").unwrap();
+                        let posy = frame.eph_field("satPosY").unwrap();
                         assert_eq!(posy.as_f64(), Some(-1.481773437500E+03));
-                        let posz = data.get("satPosZ").unwrap();
+                        let posz = frame.eph_field("satPosZ").unwrap();
                         assert_eq!(posz.as_f64(), Some(  1.847386083980E+04));
-                        let health = data.get("health").unwrap();
+                        let health = frame.eph_field("health").unwrap();
                         assert_eq!(health.as_f64(), Some(0.0));
-                        let freq = data.get("freqNum").unwrap();
+                        let freq = frame.eph_field("freqNum").unwrap();
                         assert_eq!(freq.as_f64(), Some(1.0));
-                        let ageop = data.get("ageOp").unwrap();
+                        let ageop = frame.eph_field("ageOp").unwrap();
                         assert_eq!(ageop.as_f64(), Some(0.0));
                     } else if sv.prn == 7 {
                         assert_eq!(clk, -4.201009869580E-05);
                         assert_eq!(clk_dr, 0.0);
                         assert_eq!(clk_drr, 2.88E4);
-                        let posx = data.get("satPosX").unwrap();
+                        let posx = frame.eph_field("satPosX").unwrap();
                         assert_eq!(posx.as_f64(), Some( 1.817068505860E+04)); 
-                        let posy = data.get("satPosY").unwrap();
+                        let posy = frame.eph_field("satPosY").unwrap();
                         assert_eq!(posy.as_f64(), Some(1.594814404300E+04));
-                        let posz = data.get("satPosZ").unwrap();
+                        let posz = frame.eph_field("satPosZ").unwrap();
                         assert_eq!(posz.as_f64(), Some(8.090271484380E+03));
-                        let health = data.get("health").unwrap();
+                        let health = frame.eph_field("health").unwrap();
                         assert_eq!(health.as_f64(), Some(0.0));
-                        let freq = data.get("freqNum").unwrap();
+                        let freq = frame.eph_field("freqNum").unwrap();
                         assert_eq!(freq.as_f64(), Some(5.0));
-                        let ageop = data.get("ageOp").unwrap();
+                        let ageop = frame.eph_field("ageOp").unwrap();
                         assert_eq!(ageop.as_f64(), Some(0.0));
                     }
                 }
@@ -188,7 +188,7 @@ mod test {
                 for frame in frames.iter() { // Only EPH in V3
                     let ephemeris = frame.as_eph(); // Only EPH in V3
                     assert_eq!(ephemeris.is_some(), true);
-                    let (msgtype, sv, clk, clk_dr, clk_drr, data) = ephemeris.unwrap();
+                    let (msgtype, sv, clk, clk_dr, clk_drr, _) = ephemeris.unwrap();
                     assert_eq!(msgtype, MsgType::LNAV); // legacy NAV
                     match sv.constellation {
                         Constellation::BeiDou => {
@@ -197,38 +197,38 @@ mod test {
                                     assert_eq!(clk, -0.426337239332e-03);
                                     assert_eq!(clk_dr,  -0.752518047875e-10);
                                     assert_eq!(clk_drr, 0.0);
-                                    let aode = data.get("aode").unwrap();
+                                    let aode = frame.eph_field("aode").unwrap();
                                     assert_eq!(aode.as_f64(), Some(0.100000000000e+01));
-                                    let crs = data.get("crs").unwrap();
+                                    let crs = frame.eph_field("crs").unwrap();
                                     assert_eq!(crs.as_f64(), Some(0.118906250000e+02));
-                                    let m0 = data.get("m0").unwrap();
+                                    let m0 = frame.eph_field("m0").unwrap();
                                     assert_eq!(m0.as_f64(), Some(-0.255139531119e+01));
-                                    let i0 = data.get("i0").unwrap();
+                                    let i0 = frame.eph_field("i0").unwrap();
                                     assert_eq!(i0.as_f64(), Some(0.607169709798e-01));
-                                    let acc = data.get("svAccuracy").unwrap();
+                                    let acc = frame.eph_field("svAccuracy").unwrap();
                                     assert_eq!(acc.as_f64(), Some(0.200000000000e+01));
-                                    let sath1 = data.get("satH1").unwrap();
+                                    let sath1 = frame.eph_field("satH1").unwrap();
                                     assert_eq!(sath1.as_f64(), Some(0.0));
-                                    let tgd1 = data.get("tgd1b1b3").unwrap();
+                                    let tgd1 = frame.eph_field("tgd1b1b3").unwrap();
                                     assert_eq!(tgd1.as_f64(), Some( -0.599999994133e-09));
                                 },
                                 21 => {
                                     assert_eq!(clk,  -0.775156309828e-03);
                                     assert_eq!(clk_dr, -0.144968481663e-10);
                                     assert_eq!(clk_drr,  0.000000000000e+0);
-                                    let aode = data.get("aode").unwrap();
+                                    let aode = frame.eph_field("aode").unwrap();
                                     assert_eq!(aode.as_f64(), Some(0.100000000000e+01));
-                                    let crs = data.get("crs").unwrap();
+                                    let crs = frame.eph_field("crs").unwrap();
                                     assert_eq!(crs.as_f64(), Some(-0.793437500000e+02));
-                                    let m0 = data.get("m0").unwrap();
+                                    let m0 = frame.eph_field("m0").unwrap();
                                     assert_eq!(m0.as_f64(), Some(0.206213212749e+01));
-                                    let i0 = data.get("i0").unwrap();
+                                    let i0 = frame.eph_field("i0").unwrap();
                                     assert_eq!(i0.as_f64(), Some(0.964491154768e+00)); 
-                                    let acc = data.get("svAccuracy").unwrap();
+                                    let acc = frame.eph_field("svAccuracy").unwrap();
                                     assert_eq!(acc.as_f64(), Some(0.200000000000e+01));
-                                    let sath1 = data.get("satH1").unwrap();
+                                    let sath1 = frame.eph_field("satH1").unwrap();
                                     assert_eq!(sath1.as_f64(), Some(0.0));
-                                    let tgd1 = data.get("tgd1b1b3").unwrap();
+                                    let tgd1 = frame.eph_field("tgd1b1b3").unwrap();
                                     assert_eq!(tgd1.as_f64(), Some(0.143000002950e-07));
                                 },
                                 _ => panic!("identified unexpected BDS vehicule \"{}\"", sv.prn)
@@ -240,22 +240,22 @@ mod test {
                                     assert_eq!(clk,  -0.126023776829e-03);
                                     assert_eq!(clk_dr,  -0.909494701773e-12); 
                                     assert_eq!(clk_drr, 0.0);
-                                    let pos = data.get("satPosX").unwrap();
+                                    let pos = frame.eph_field("satPosX").unwrap();
                                     assert_eq!(pos.as_f64(), Some(0.783916601562e+04));
-                                    let pos = data.get("satPosY").unwrap();
+                                    let pos = frame.eph_field("satPosY").unwrap();
                                     assert_eq!(pos.as_f64(), Some(-0.216949155273e+05));
-                                    let pos = data.get("satPosZ").unwrap();
+                                    let pos = frame.eph_field("satPosZ").unwrap();
                                     assert_eq!(pos.as_f64(), Some(0.109021518555e+05)); 
                                 },
                                 7 => {
                                     assert_eq!(clk, -0.420100986958E-04); 
                                     assert_eq!(clk_dr, 0.0); 
                                     assert_eq!(clk_drr, 0.342000000000e+05); 
-                                    let pos = data.get("satPosX").unwrap();
+                                    let pos = frame.eph_field("satPosX").unwrap();
                                     assert_eq!(pos.as_f64(), Some(0.124900639648e+05));
-                                    let pos = data.get("satPosY").unwrap();
+                                    let pos = frame.eph_field("satPosY").unwrap();
                                     assert_eq!(pos.as_f64(), Some(0.595546582031e+04));
-                                    let pos = data.get("satPosZ").unwrap();
+                                    let pos = frame.eph_field("satPosZ").unwrap();
                                     assert_eq!(pos.as_f64(), Some(0.214479208984e+05));
                                 },
                                 _ => panic!("identified unexpected GLO vehicule \"{}\"", sv.prn)
@@ -267,38 +267,38 @@ mod test {
                                     assert_eq!(clk, -0.101553811692e-02);
                                     assert_eq!(clk_dr,  -0.804334376880e-11);
                                     assert_eq!(clk_drr, 0.0);
-                                    let iodnav = data.get("iodnav").unwrap();
+                                    let iodnav = frame.eph_field("iodnav").unwrap();
                                     assert_eq!(iodnav.as_f64(), Some(0.130000000000e+02));
-                                    let crs = data.get("crs").unwrap();
+                                    let crs = frame.eph_field("crs").unwrap();
                                     assert_eq!(crs.as_f64(), Some( 0.435937500000e+02));
-                                    let cis = data.get("cis").unwrap();
+                                    let cis = frame.eph_field("cis").unwrap();
                                     assert_eq!(cis.as_f64(), Some(0.409781932831e-07));
-                                    let omega_dot = data.get("omegaDot").unwrap();
+                                    let omega_dot = frame.eph_field("omegaDot").unwrap();
                                     assert_eq!(omega_dot.as_f64(), Some( -0.518200156545e-08));
-                                    let idot = data.get("idot").unwrap();
+                                    let idot = frame.eph_field("idot").unwrap();
                                     assert_eq!(idot.as_f64(), Some( -0.595381942905e-09));
-                                    let sisa = data.get("sisa").unwrap();
+                                    let sisa = frame.eph_field("sisa").unwrap();
                                     assert_eq!(sisa.as_f64(), Some(0.312000000000e+01));
-                                    let bgd =  data.get("bgdE5aE1").unwrap();
+                                    let bgd =  frame.eph_field("bgdE5aE1").unwrap();
                                     assert_eq!(bgd.as_f64(), Some( 0.232830643654e-09));
                                 },
                                 3 => {
                                     assert_eq!(clk, -0.382520200219e-03);
                                     assert_eq!(clk_dr,  -0.422062385041e-11);
                                     assert_eq!(clk_drr, 0.0);
-                                    let iodnav = data.get("iodnav").unwrap();
+                                    let iodnav = frame.eph_field("iodnav").unwrap();
                                     assert_eq!(iodnav.as_f64(), Some(0.460000000000e+02));
-                                    let crs = data.get("crs").unwrap();
+                                    let crs = frame.eph_field("crs").unwrap();
                                     assert_eq!(crs.as_f64(), Some( -0.103750000000e+02));
-                                    let cis = data.get("cis").unwrap();
+                                    let cis = frame.eph_field("cis").unwrap();
                                     assert_eq!(cis.as_f64(), Some(0.745058059692e-08));
-                                    let omega_dot = data.get("omegaDot").unwrap();
+                                    let omega_dot = frame.eph_field("omegaDot").unwrap();
                                     assert_eq!(omega_dot.as_f64(), Some( -0.539986778331e-08));
-                                    let idot = data.get("idot").unwrap();
+                                    let idot = frame.eph_field("idot").unwrap();
                                     assert_eq!(idot.as_f64(), Some(0.701814947695e-09));
-                                    let sisa = data.get("sisa").unwrap();
+                                    let sisa = frame.eph_field("sisa").unwrap();
                                     assert_eq!(sisa.as_f64(), Some(0.312000000000e+01));
-                                    let bgd =  data.get("bgdE5aE1").unwrap();
+                                    let bgd =  frame.eph_field("bgdE5aE1").unwrap();
                                     assert_eq!(bgd.as_f64(), Some( 0.302679836750e-08));
                                 },
                                 _ => panic!("identified unexpected GAL vehicule \"{}\"", sv.prn)
@@ -850,4 +850,356 @@ mod test {
             }
         }
     }
+    #[test]
+    fn v3_cbw100nld_r_2021_orbits() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let norm = |(x, y, z): (f64, f64, f64)| -> f64 {
+            (x.powi(2) + y.powi(2) + z.powi(2)).sqrt()
+        };
+        let e01 = Sv::new(Constellation::Galileo, 1);
+        let c05 = Sv::new(Constellation::BeiDou, 5);
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2021 01 01 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let positions = rinex.space_vehicule_positions();
+        let vehicules = positions.get(&t0).unwrap();
+        let e01_pos = vehicules.get(&e01).unwrap();
+        assert!((norm(*e01_pos) - 29600519.0).abs() < 1.0);
+        // C05 is geostationary, above the indian ocean
+        let (x, y, z) = vehicules.get(&c05).unwrap();
+        assert!((norm((*x, *y, *z)) - 42179166.69).abs() < 1.0);
+        let longitude = y.atan2(*x).to_degrees();
+        assert!((longitude - 58.69).abs() < 0.01);
+        // resolve in between ephemeris
+        let t1 = epoch::Epoch::new(
+            t0.date + chrono::Duration::minutes(15),
+            epoch::EpochFlag::Ok);
+        let positions = rinex.space_vehicule_positions_at(&[t1]);
+        let e01_pos = positions.get(&t1).unwrap().get(&e01).unwrap();
+        assert!((norm(*e01_pos) - 29600.0E3).abs() < 10.0E3);
+        let velocities = rinex.space_vehicule_velocities_at(&[t1]);
+        let c05_vel = velocities.get(&t1).unwrap().get(&c05).unwrap();
+        assert!(norm(*c05_vel) < 10.0); // geostationary
+        // too far away from any ephemeris
+        let t2 = epoch::Epoch::new(
+            t0.date + chrono::Duration::days(2),
+            epoch::EpochFlag::Ok);
+        assert_eq!(rinex.space_vehicule_positions_at(&[t2]).len(), 0);
+    }
+    #[test]
+    fn v3_amel00nld_r_2021_glonass_positions() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let norm = |(x, y, z): (f64, f64, f64)| -> f64 {
+            (x.powi(2) + y.powi(2) + z.powi(2)).sqrt()
+        };
+        let r19 = Sv::new(Constellation::Glonass, 19);
+        let toc = epoch::Epoch::new(
+            epoch::str2date("2021 01 01 00 15 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        // broadcast state vector, at frame epoch
+        let positions = rinex.space_vehicule_positions();
+        let (x, y, z) = positions.get(&toc).unwrap().get(&r19).unwrap();
+        assert!((x - 7839166.01562).abs() < 1.0E-3);
+        assert!((y - -21694915.5273).abs() < 1.0E-3);
+        assert!((z - 10902151.8555).abs() < 1.0E-3);
+        // propagated state vector
+        let t1 = epoch::Epoch::new(
+            toc.date + chrono::Duration::minutes(15),
+            epoch::EpochFlag::Ok);
+        let positions = rinex.space_vehicule_positions_at(&[t1]);
+        let r19_pos = positions.get(&t1).unwrap().get(&r19).unwrap();
+        assert!((norm(*r19_pos) - 25514.3E3).abs() < 10.0E3);
+        let velocities = rinex.space_vehicule_velocities_at(&[t1]);
+        let r19_vel = velocities.get(&t1).unwrap().get(&r19).unwrap();
+        assert!((norm(*r19_vel) - 3.48E3).abs() < 0.1E3);
+    }
+    #[test]
+    fn v3_cbw100nld_r_2021_elevation_azimuth() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        // no reference position in a NAV header
+        assert_eq!(rinex.space_vehicule_elevation_azimuth(&rinex, None).len(), 0);
+        // seen from Brussels (BE)
+        let brussels = (4027881.0, 306998.0, 4919499.0);
+        let angles = rinex.space_vehicule_elevation_azimuth(&rinex, Some(brussels));
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2021 01 01 00 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let vehicules = angles.get(&t0).unwrap();
+        let c05 = Sv::new(Constellation::BeiDou, 5);
+        let (el, az) = vehicules.get(&c05).unwrap();
+        assert!((el - 14.832).abs() < 0.01);
+        assert!((az - 117.902).abs() < 0.01);
+        let e01 = Sv::new(Constellation::Galileo, 1);
+        let (el, az) = vehicules.get(&e01).unwrap();
+        assert!((el - 12.912).abs() < 0.01);
+        assert!((az - 326.677).abs() < 0.01);
+        for (_, vehicules) in angles.iter() {
+            for (_, (el, az)) in vehicules.iter() {
+                assert!(*el >= -90.0 && *el <= 90.0);
+                assert!(*az >= 0.0 && *az < 360.0);
+            }
+        }
+    }
+    #[test]
+    fn v4_almanac_frames() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V4/FRMS00XXX_R_20221591000_01H_MN.rnx";
+        let almanac = vec![
+            "> ALM G05 LNAV",
+            "    2022 06 08 10 00 00 9.853839874268E-03 5.898240000000E+05 8.544921875000E-03",
+            "    -7.863184958883E-09 5.153650390625E+03-1.286897115738E+00 5.386352539063E-01",
+            "    -2.914714813232E+00 1.144409179688E-04 3.637978807092E-12 0.000000000000E+00",
+            "     2.213000000000E+03",
+        ];
+        let content = std::fs::read_to_string(&test_resource)
+            .unwrap();
+        let path = std::env::temp_dir().join("FRMS00XXX-almanac.rnx");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, content.trim_end().to_owned() + "\n" + &almanac.join("\n") + "\n")
+            .unwrap();
+        let rinex = Rinex::from_file(path)
+            .unwrap();
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let g05 = Sv::new(Constellation::GPS, 5);
+        // distinct frame class, not mixed with ephemeris
+        let classes = rinex.record.as_nav()
+            .unwrap()
+            .get(&t0)
+            .unwrap();
+        assert_eq!(classes.get(&FrameClass::Ephemeris).unwrap().len(), 3);
+        assert_eq!(classes.get(&FrameClass::Almanac).unwrap().len(), 1);
+        assert_eq!(rinex.ephemeris().get(&t0).unwrap().contains_key(&g05), false);
+        let almanacs = rinex.almanacs();
+        assert_eq!(almanacs.len(), 1);
+        let alm = almanacs.get(&t0).unwrap().get(&g05).unwrap();
+        assert_eq!(alm.toa, 589824);
+        assert_eq!(alm.sqrt_a, 5.153650390625E+03);
+        assert_eq!(alm.week, 2213);
+        // production
+        let copy_path = path.to_owned() + "-copy";
+        assert_eq!(rinex.to_file(&copy_path).is_ok(), true);
+        let copy = Rinex::from_file(&copy_path)
+            .unwrap();
+        let _ = std::fs::remove_file(&copy_path);
+        assert_eq!(copy.almanacs(), almanacs);
+        // filters apply to almanacs
+        let mut rinex = rinex;
+        rinex.constellation_filter_mut(vec![Constellation::Galileo]);
+        assert_eq!(rinex.almanacs().len(), 0);
+    }
+    #[test]
+    fn v4_frms00xxx_r_2022_frame_classes() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V4/FRMS00XXX_R_20221591000_01H_MN.rnx";
+        let mut rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let histogram = rinex.frame_class_histogram();
+        assert_eq!(histogram.len(), 4);
+        assert_eq!(histogram.get(&FrameClass::Ephemeris), Some(&3));
+        assert_eq!(histogram.get(&FrameClass::SystemTimeOffset), Some(&2));
+        assert_eq!(histogram.get(&FrameClass::EarthOrientation), Some(&1));
+        assert_eq!(histogram.get(&FrameClass::IonosphericModel), Some(&1));
+        let mut eph = rinex.clone();
+        eph.retain_frame_classes_mut(&[FrameClass::Ephemeris]);
+        assert_eq!(eph.frame_class_histogram().len(), 1);
+        assert_eq!(eph.ephemeris().len() > 0, true);
+        assert_eq!(eph.system_time_offsets().len(), 0);
+        rinex.retain_frame_classes_mut(&[FrameClass::IonosphericModel, FrameClass::EarthOrientation]);
+        let histogram = rinex.frame_class_histogram();
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(histogram.get(&FrameClass::IonosphericModel), Some(&1));
+        // no frame left: epochs are dropped
+        rinex.retain_frame_classes_mut(&[FrameClass::Almanac]);
+        assert_eq!(rinex.epochs().len(), 0);
+    }
+    #[test]
+    fn v4_frms00xxx_r_2022_frames_production() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V4/FRMS00XXX_R_20221591000_01H_MN.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let g02 = Sv::new(Constellation::GPS, 2);
+        let g04 = Sv::new(Constellation::GPS, 4);
+        // all classes at a single epoch, with duplicates
+        let record = rinex.record.as_nav()
+            .unwrap();
+        assert_eq!(record.len(), 1);
+        let classes = record.get(&t0)
+            .unwrap();
+        assert_eq!(classes.len(), 4);
+        assert_eq!(classes.get(&FrameClass::Ephemeris).unwrap().len(), 3);
+        assert_eq!(classes.get(&FrameClass::SystemTimeOffset).unwrap().len(), 2);
+        assert_eq!(classes.get(&FrameClass::EarthOrientation).unwrap().len(), 1);
+        assert_eq!(classes.get(&FrameClass::IonosphericModel).unwrap().len(), 1);
+        let eop = classes.get(&FrameClass::EarthOrientation).unwrap()[0]
+            .as_eop()
+            .unwrap();
+        assert_eq!(eop.x, (1.234567890000E-01, 2.500000000000E-04, 0.0));
+        assert_eq!(eop.y, (3.456789000000E-01, -1.200000000000E-04, 0.0));
+        assert_eq!(eop.t_tm, 295284);
+        assert_eq!(eop.dut1, (-1.234000000000E-01, 3.000000000000E-04, 0.0));
+        let sto = classes.get(&FrameClass::SystemTimeOffset).unwrap()[0]
+            .as_sto()
+            .unwrap();
+        assert_eq!(sto.system, "GPUT");
+        assert_eq!(sto.utc, "UTC(USNO)");
+        // produce a copy
+        let copy_path = test_resource.to_owned() + "-copy";
+        assert_eq!(rinex.to_file(&copy_path).is_ok(), true);
+        let content = std::fs::read_to_string(&copy_path)
+            .unwrap();
+        let frames : Vec<&str> = content
+            .lines()
+            .filter(|l| l.starts_with('>'))
+            .collect();
+        // deterministic order, no duplicates
+        assert_eq!(frames, vec![
+            "> EPH G02 LNAV",
+            "> EPH G04 LNAV",
+            "> STO G02 LNAV",
+            "> EOP G02 CNVX",
+            "> ION G02 LNAV",
+        ]);
+        // round trip
+        let copy = Rinex::from_file(&copy_path)
+            .unwrap();
+        let _ = std::fs::remove_file(&copy_path);
+        let copy_classes = copy.record.as_nav()
+            .unwrap()
+            .get(&t0)
+            .unwrap();
+        for (class, frames) in classes.iter() {
+            let mut expected = frames.clone();
+            navigation::record::dedup_frames(&mut expected);
+            assert_eq!(copy_classes.get(class), Some(&expected));
+        }
+        let ephemeris = copy_classes.get(&FrameClass::Ephemeris).unwrap();
+        assert_eq!(ephemeris[0].as_eph().unwrap().1, g02);
+        assert_eq!(ephemeris[1].as_eph().unwrap().1, g04);
+        let (_, _, clk, _, _, eph) = ephemeris[0].as_eph().unwrap();
+        assert_eq!(clk, -6.528543308377E-04);
+        assert_eq!(eph.iod(), Some(96.0));
+        assert_eq!(ephemeris[0].eph_field("iode").unwrap().as_f64(), Some(96.0));
+        // producing the copy is idempotent
+        assert_eq!(copy.to_file(&copy_path).is_ok(), true);
+        let content_bis = std::fs::read_to_string(&copy_path)
+            .unwrap();
+        let _ = std::fs::remove_file(&copy_path);
+        let skip_header = |content: &str| -> Vec<String> {
+            content.lines()
+                .skip_while(|l| !l.contains("END OF HEADER"))
+                .map(|l| l.to_string())
+                .collect()
+        };
+        assert_eq!(skip_header(&content), skip_header(&content_bis));
+        // merging does not introduce duplicates
+        let mut merged = copy.clone();
+        merged.merge_mut(&rinex)
+            .unwrap();
+        assert_eq!(merged.record.as_nav().unwrap(), copy.record.as_nav().unwrap());
+    }
+    #[test]
+    fn nav_corrections() {
+        // V3: header corrections
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let iono = rinex.ionosphere_corrections();
+        assert_eq!(iono.len(), 3);
+        assert_eq!(iono[0].constellation, Constellation::Galileo);
+        let ng = iono[0].model.as_nequick_g().unwrap();
+        assert_eq!(ng.a, (6.6250e+01, -1.6410e-01, -2.4720e-03));
+        assert_eq!(iono[1].constellation, Constellation::GPS);
+        let kb = iono[1].model.as_klobuchar().unwrap();
+        assert_eq!(kb.alpha, (7.4510e-09, -1.4900e-08, -5.9600e-08, 1.1920e-07));
+        assert_eq!(kb.beta, (9.0110e+04, -6.5540e+04, -1.3110e+05, 4.5880e+05));
+        assert_eq!(iono[2].constellation, Constellation::QZSS);
+        for corr in iono.iter() {
+            assert_eq!(corr.epoch, None);
+        }
+        let time = rinex.time_system_corrections();
+        assert_eq!(time.len(), 4);
+        let systems: Vec<&str> = time.iter().map(|c| c.system.as_str()).collect();
+        assert_eq!(systems, vec!["GAGP", "GAUT", "GPUT", "QZUT"]);
+        assert_eq!(time[2].reference, Some((2139, 61440)));
+        assert_eq!(time[2].a, (-0.3725290298E-08, -0.106581410E-13, 0.0));
+        // V4: record frames
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V4/FRMS00XXX_R_20221591000_01H_MN.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let t0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let iono = rinex.ionosphere_corrections();
+        assert_eq!(iono.len(), 1);
+        assert_eq!(iono[0].constellation, Constellation::GPS);
+        assert_eq!(iono[0].epoch, Some(t0));
+        assert_eq!(iono[0].model.as_klobuchar().is_some(), true);
+        let time = rinex.time_system_corrections();
+        assert_eq!(time.len(), 2);
+        for corr in time.iter() {
+            assert_eq!(corr.system, "GPUT");
+            assert_eq!(corr.utc, "UTC(USNO)");
+            assert_eq!(corr.epoch, Some(t0));
+            assert_eq!(corr.reference, None);
+        }
+    }
+    #[test]
+    fn v3_amel00nld_timescales() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let epochs = rinex.sv_epochs_to_timescale(TimeScale::GPST)
+            .unwrap();
+        let find = |sv: Sv| {
+            epochs.iter()
+                .find(|(_, vehicules)| vehicules.contains(&sv))
+                .map(|(e, _)| e.date)
+                .unwrap()
+        };
+        let date = |s: &str| epoch::str2date(s).unwrap();
+        let close = |a: chrono::NaiveDateTime, b: chrono::NaiveDateTime| {
+            (a - b).num_nanoseconds().unwrap().abs() < 1000
+        };
+        // BDT is 14s behind GPST
+        let c05 = Sv::new(Constellation::BeiDou, 5);
+        assert_eq!(find(c05), date("2021 01 01 00 00 14"));
+        // Glonass epochs are expressed in UTC, 18 leap seconds
+        let r19 = Sv::new(Constellation::Glonass, 19);
+        assert!(close(find(r19), date("2021 01 01 00 15 18")));
+        // GST is aligned to GPST, within a few ns (GAGP)
+        let e01 = Sv::new(Constellation::Galileo, 1);
+        assert!(close(find(e01), date("2021 01 01 10 10 00")));
+        assert_ne!(find(e01), date("2021 01 01 10 10 00"));
+        // UTC
+        let utc = rinex.timescale_convert(date("2021 01 01 00 00 00"), TimeScale::GPST, TimeScale::UTC)
+            .unwrap();
+        assert!(close(utc, date("2020 12 31 23 59 42")));
+    }
 }
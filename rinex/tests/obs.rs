@@ -2,6 +2,8 @@
 mod test {
     use rinex::*;
     use rinex::constellation::Constellation;
+    use rinex::observation::combination::Combination;
+    use std::str::FromStr;
     #[test]
     fn v2_aopr0010_17o() {
         let test_resource = 
@@ -33,7 +35,7 @@ mod test {
         let glo_observables = glo_observables.unwrap();
         let mut index = 0;
         for code in vec!["C1C","C1P","C2C","C2P","C3Q","L1C","L1P","L2C","L2P","L3Q"] {
-            assert_eq!(glo_observables[index], code);
+            assert_eq!(glo_observables[index].to_string(), code);
             index += 1
         }
         
@@ -76,4 +78,1176 @@ mod test {
         assert_eq!(clk_offset.is_none(), true);
         assert_eq!(epoch.len(), 47);
     }
+    #[test]
+    fn v3_acor00esp_streamed() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource);
+        assert_eq!(rinex.is_ok(), true);
+        let rinex = rinex.unwrap();
+        let record = rinex.record.as_obs()
+            .unwrap();
+        let stream = Rinex::epochs_iter_from_file(&test_resource);
+        assert_eq!(stream.is_ok(), true);
+        let stream = stream.unwrap();
+        assert_eq!(stream.header().rinex_type, types::Type::ObservationData);
+        let mut count = 0;
+        for entry in stream {
+            match entry.unwrap() {
+                record::StreamEntry::Obs(e, clk_offset, vehicules) => {
+                    let expected = record.get(&e);
+                    assert_eq!(expected.is_some(), true);
+                    let (expected_clk, expected_vehicules) = expected.unwrap();
+                    assert_eq!(clk_offset, *expected_clk);
+                    assert_eq!(vehicules, *expected_vehicules);
+                },
+                _ => panic!("streamed non OBS content"),
+            }
+            count += 1
+        }
+        assert_eq!(count, record.len());
+    }
+    #[test]
+    fn v3_acor00esp_stream_errors() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        // invalid encoding in the middle of the record
+        let mut content = std::fs::read(&test_resource).unwrap();
+        let middle = content.len() / 2;
+        content[middle] = 0xFF;
+        let path = std::env::temp_dir().join("ACOR00ESP-invalid-encoding.rnx");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &content).unwrap();
+        let stream = Rinex::epochs_iter_from_file(path)
+            .unwrap();
+        let entries: Vec<_> = stream.collect();
+        let _ = std::fs::remove_file(path);
+        // error is reported, not mistaken for the end of file
+        let last = entries.last().unwrap();
+        assert_eq!(last.is_err(), true);
+        assert_eq!(entries.iter().filter(|e| e.is_err()).count(), 1);
+        assert_eq!(entries.len() > 1, true);
+    }
+    #[test]
+    fn v3_acor00esp_phase_units() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let cycles = rinex.carrier_phases_cycles();
+        let meters = rinex.carrier_phases_m();
+        assert_eq!(meters.len() > 0, true);
+        let l1 = channel::Channel::L1;
+        for (e, vehicules) in meters.iter() {
+            for (sv, phases) in vehicules.iter() {
+                if sv.constellation != Constellation::GPS {
+                    continue
+                }
+                let raw = &cycles[e][sv];
+                for (code, m) in phases.iter() {
+                    if code.to_string() == "L1C" {
+                        let (_, c) = raw.iter().find(|(c, _)| c == code).unwrap();
+                        assert!((l1.cycles_to_m(*c) - m).abs() < 1.0E-6);
+                    }
+                }
+            }
+        }
+        // combinations are now feasible
+        assert_eq!(rinex.iono_free_carrier_phases().len() > 0, true);
+        assert_eq!(
+            rinex.iono_free_carrier_phases(),
+            rinex.carrier_phase_combinations(Combination::IonosphereFree));
+        let gf = rinex.carrier_phase_combinations(Combination::GeometryFree);
+        let mw = rinex.melbourne_wubbena();
+        assert_eq!(gf.len() > 0, true);
+        assert_eq!(mw.len() > 0, true);
+        for (e, vehicules) in mw.iter() {
+            for sv in vehicules.keys() {
+                assert!(gf[e].contains_key(sv));
+            }
+        }
+    }
+    #[test]
+    fn v3_acor00esp_snr_cross_correlation() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        // this file spans 00:00:00 - 00:12:00
+        let matrices = rinex.snr_cross_correlation(chrono::Duration::minutes(5));
+        assert_eq!(matrices.len(), 3);
+        for (_, matrix) in matrices.iter() {
+            let n = matrix.signals.len();
+            assert_eq!(n > 1, true);
+            assert_eq!(matrix.coefficients.len(), n);
+            for i in 0..n {
+                assert_eq!(matrix.coefficients[i][i], Some(1.0));
+                for j in 0..n {
+                    assert_eq!(matrix.coefficients[i][j], matrix.coefficients[j][i]);
+                    if let Some(c) = matrix.coefficients[i][j] {
+                        assert!(c >= -1.0 - 1.0E-9 && c <= 1.0 + 1.0E-9);
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn v3_duth0630_observables_order() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/DUTH0630.22O";
+        let mut rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let order : Vec<observation::Observable> = vec!["S2W", "S1C", "C1C"]
+            .iter()
+            .map(|c| observation::Observable::from_str(c).unwrap())
+            .collect();
+        rinex.sort_observables_mut(Constellation::GPS, &order);
+        let path = std::env::temp_dir().join("DUTH0630-sorted.22O");
+        let path = path.to_str().unwrap();
+        assert_eq!(rinex.to_file(path).is_ok(), true);
+        let content = std::fs::read_to_string(path)
+            .unwrap();
+        assert_eq!(content.lines().any(|l| l.starts_with("G    8 S2W S1C C1C L1C D1C C2W L2W D2W")), true);
+        // glonass is untouched
+        assert_eq!(content.lines().any(|l| l.starts_with("R    8 C1C L1C D1C S1C C2P L2P D2P S2P")), true);
+        // data lines follow the new order
+        let copy = Rinex::from_file(path)
+            .unwrap();
+        assert_eq!(copy.header.obs.as_ref().unwrap().codes[&Constellation::GPS][0..3], order[..]);
+        assert_eq!(copy.record.as_obs(), rinex.record.as_obs());
+    }
+    #[test]
+    fn v3_duth0630_flatten() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/DUTH0630.22O";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let rows = rinex.flatten();
+        let record = rinex.record.as_obs().unwrap();
+        let total: usize = record.values()
+            .map(|(_, vehicules)| vehicules.values().map(|obs| obs.len()).sum::<usize>())
+            .sum();
+        assert_eq!(rows.len(), total);
+        let first = rinex.first_epoch().unwrap();
+        assert_eq!(rows[0].epoch, first.date.timestamp() as f64);
+        for row in rows.iter() {
+            assert_eq!(row.sv.len(), 3);
+            assert_eq!(row.code.len(), 3);
+        }
+        // epochs are sorted
+        assert_eq!(rows.windows(2).all(|w| w[0].epoch <= w[1].epoch), true);
+        // not an observation record
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/MET/V2/abvi0010.15m";
+        let meteo = Rinex::from_file(&test_resource)
+            .unwrap();
+        assert_eq!(meteo.flatten().is_empty(), true);
+    }
+    #[test]
+    fn v3_acor00esp_observable_matrix() {
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let matrix = rinex.observable_matrix();
+        assert_eq!(matrix.vehicules.len() > 0, true);
+        assert_eq!(matrix.counts.len(), matrix.vehicules.len());
+        let epochs = rinex.epochs().len();
+        let mut total = 0;
+        for row in matrix.counts.iter() {
+            assert_eq!(row.len(), matrix.observables.len());
+            for count in row.iter() {
+                assert!(*count <= epochs);
+                total += count;
+            }
+        }
+        // every observation is accounted for
+        let record = rinex.record.as_obs().unwrap();
+        let observations: usize = record.iter()
+            .map(|(_, (_, vehicules))| vehicules.values().map(|obs| obs.len()).sum::<usize>())
+            .sum();
+        assert_eq!(total, observations);
+        for sv in matrix.vehicules.iter() {
+            for observable in matrix.observables_of(sv) {
+                assert!(matrix.vehicules_with(&observable).contains(sv));
+            }
+        }
+        // not an observation RINEX
+        let rinex = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+            .unwrap();
+        assert_eq!(rinex.observable_matrix(), observation::presence::Matrix::default());
+    }
+    #[test]
+    fn v3_acor00esp_lli_series() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let g18 = sv::Sv::from_str("G18").unwrap();
+        let series = rinex.lli_series(&g18, &observation::Observable::from_str("L5Q").unwrap());
+        assert_eq!(series.len() > 0, true);
+        let slips: Vec<_> = series
+            .iter()
+            .filter_map(|(e, lli)| {
+                if (*lli)?.intersects(observation::LliFlags::LOCK_LOSS) {
+                    Some(e.date)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(slips.len(), 7);
+        assert_eq!(slips[0], epoch::str2date("2021 12 21 00 00 00").unwrap());
+        assert_eq!(slips[1], epoch::str2date("2021 12 21 00 02 30").unwrap());
+        // each slip is also reported by lock_loss_events
+        let events: Vec<_> = rinex.lock_loss_events()
+            .iter()
+            .map(|e| e.date)
+            .collect();
+        for slip in slips.iter() {
+            assert!(events.contains(slip));
+        }
+        // non existing signal
+        assert_eq!(rinex.lli_series(&g18, &observation::Observable::from_str("L6X").unwrap()).len(), 0);
+    }
+    #[test]
+    fn v3_acor00esp_sv_time_series() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let g18 = sv::Sv::from_str("G18").unwrap();
+        let c1c = observation::Observable::from_str("C1C").unwrap();
+        let series = rinex.sv_time_series(&g18, &c1c);
+        assert_eq!(series.len(), 25);
+        assert_eq!(series[0].0.date, epoch::str2date("2021 12 21 00 00 00").unwrap());
+        assert_eq!(series[0].1, 25102873.240);
+        assert_eq!(series[1].0.date, epoch::str2date("2021 12 21 00 00 30").unwrap());
+        assert_eq!(series[1].1, 25122366.420);
+        assert_eq!(series[24].0.date, epoch::str2date("2021 12 21 00 12 00").unwrap());
+        assert_eq!(series[24].1, 25569410.400);
+        // chronological order
+        assert!(series.windows(2).all(|w| w[0].0 < w[1].0));
+        // non existing signal
+        let l6x = observation::Observable::from_str("L6X").unwrap();
+        assert_eq!(rinex.sv_time_series(&g18, &l6x).len(), 0);
+        // not an observation RINEX
+        let rinex = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+            .unwrap();
+        assert_eq!(rinex.sv_time_series(&g18, &c1c).len(), 0);
+    }
+    #[test]
+    fn v3_acor00esp_reports() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let gaps = report::GapReport::new(&rinex);
+        assert_eq!(gaps.interval, Some(30.0));
+        assert_eq!(gaps.gaps.len(), 0);
+        // introduce a gap
+        let e2 = rinex.epochs()[2];
+        let mut gapped = rinex.clone();
+        gapped.retain_epochs_mut(|e| *e != e2);
+        let gaps = report::GapReport::new(&gapped);
+        assert_eq!(gaps.gaps.len(), 1);
+        assert_eq!(gaps.gaps[0].end, rinex.epochs()[3]);
+        assert_eq!(gaps.gaps[0].duration, 60.0);
+        // comparison
+        assert_eq!(report::DiffReport::new(&rinex, &rinex, 0.0).is_identical(), true);
+        let diff = report::DiffReport::new(&rinex, &gapped, 0.0);
+        assert_eq!(diff.is_identical(), false);
+        assert_eq!(diff.only_in_a, vec![e2]);
+        assert_eq!(diff.only_in_b.len(), 0);
+        assert_eq!(diff.differences.len(), 0);
+        let qc = report::QcReport::new(&rinex, qc::Period::Daily);
+        assert_eq!(qc.version, report::VERSION);
+        assert_eq!(qc.summaries.len(), 1);
+        assert_eq!(qc.score, qc.metrics.score(&qc::ScoreOpts::default()));
+    }
+    #[test]
+    fn filenames() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V2/aopr0010.17o";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        assert_eq!(rinex.filename(), "aopr0010.17o");
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let mut rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        // test resource was truncated to 12'30"
+        assert_eq!(rinex.filename(), "ACOR00XXX_R_20213550000_13M_30S_MO.rnx");
+        let opts = naming::Options::default()
+            .with_country("ESP");
+        assert_eq!(rinex.filename_with_options(&opts), "ACOR00ESP_R_20213550000_13M_30S_MO.rnx");
+        rinex.rnx2crx();
+        assert_eq!(rinex.filename_with_options(&opts), "ACOR00ESP_R_20213550000_13M_30S_MO.crx");
+    }
+    #[test]
+    fn v3_duth0630_sparse_record() {
+        use rinex::observation::sparse::SparseRecord;
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/DUTH0630.22O";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let record = rinex.record.as_obs()
+            .unwrap();
+        let stream = Rinex::epochs_iter_from_file(&test_resource)
+            .unwrap();
+        let sparse = SparseRecord::from_stream(stream)
+            .unwrap();
+        assert_eq!(sparse, SparseRecord::from_record(record));
+        assert_eq!(sparse.epochs(), rinex.epochs());
+        let total: usize = record.values()
+            .map(|(_, vehicules)| vehicules.values().map(|obs| obs.len()).sum::<usize>())
+            .sum();
+        assert_eq!(sparse.len(), total);
+        assert_eq!(&sparse.to_record(), record);
+    }
+    #[test]
+    fn v3_acor00esp_decompress_file() {
+        let crinex = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/CRNX/V3/ACOR00ESP_R_20213550000_01D_30S_MO.crx";
+        let expected = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let output = std::env::temp_dir().join("acor00esp-decompressed.rnx");
+        let output = output.to_str().unwrap();
+        assert_eq!(convert::decompress_file(&crinex, output).is_ok(), true);
+        let rinex = Rinex::from_file(output)
+            .unwrap();
+        let _ = std::fs::remove_file(output);
+        let expected = Rinex::from_file(&expected)
+            .unwrap();
+        assert_eq!(rinex.header.is_crinex(), false);
+        assert_eq!(rinex.header.comments.iter().any(|c| c.contains("RNX2CRX")), true);
+        assert_eq!(rinex.epochs(), expected.epochs());
+        assert_eq!(rinex.record.as_obs(), expected.record.as_obs());
+    }
+    #[test]
+    fn v3_acor00esp_station_score() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let opts = qc::ScoreOpts::default();
+        let metrics = qc::station_metrics(&rinex, None, None, &opts);
+        assert!(metrics.completeness > 0.0 && metrics.completeness <= 1.0);
+        assert!(metrics.slip_rate > 0.0 && metrics.slip_rate < 1.0);
+        assert_eq!(metrics.outage_ratio, 0.0);
+        assert_eq!(metrics.multipath_rms.is_some(), true);
+        assert_eq!(metrics.mean_snr.is_some(), true);
+        // no navigation context
+        assert_eq!(metrics.residuals_rms, None);
+        let score = qc::station_score(&rinex, None, None, &opts);
+        assert_eq!(score, metrics.score(&opts));
+        assert!(score >= 0.0 && score <= 100.0);
+        // daily summary covers the whole file
+        let daily = qc::aggregate(&rinex, qc::Period::Daily);
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].epochs, rinex.epochs().len());
+        assert_eq!(daily[0].metrics, metrics);
+        // file spans 12', within a single hour
+        let hourly = qc::aggregate(&rinex, qc::Period::Hourly);
+        assert_eq!(hourly.len(), 1);
+        assert_eq!(hourly[0].start, chrono::NaiveDate::from_ymd(2021, 12, 21).and_hms(0, 0, 0));
+        assert_eq!(hourly[0].end, chrono::NaiveDate::from_ymd(2021, 12, 21).and_hms(1, 0, 0));
+        assert_eq!(hourly[0].epochs, 25);
+    }
+    #[test]
+    #[cfg(feature = "with-gzip")]
+    fn v3_kms300dnk_receiver_clock_offsets() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/CRNX/V3/KMS300DNK_R_20221591000_01H_30S_MO.crx";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/NAV/V4/KMS300DNK_R_20221591000_01H_MN.rnx.gz";
+        let nav = Rinex::from_file(&test_resource)
+            .unwrap();
+        let offsets = rinex.receiver_clock_offsets(&nav, None);
+        assert_eq!(offsets.len() > 0, true);
+        for (_, offset) in offsets.iter() {
+            assert!(offset.is_finite());
+            assert!(offset.abs() < 1.0E-2);
+        }
+        // header position was used
+        let position = (3516213.4380, 781859.8595, 5246037.9660);
+        assert_eq!(rinex.receiver_clock_offsets(&nav, Some(position)), offsets);
+        // oscillator drift
+        let drifts = rinex.receiver_clock_drifts(&nav, None);
+        assert_eq!(drifts.len() > 0, true);
+        for (e, drift) in drifts.iter() {
+            assert!(drift.is_finite());
+            assert!(offsets.contains_key(e));
+        }
+        assert_eq!(drifts.keys().next() > offsets.keys().next(), true);
+        // navigation context enables residuals
+        let metrics = qc::station_metrics(&rinex, Some(&nav), None, &qc::ScoreOpts::default());
+        assert_eq!(metrics.residuals_rms.is_some(), true);
+        // inter system biases
+        let biases = rinex.inter_system_biases(&nav, None);
+        assert_eq!(biases.contains_key(&(Constellation::GPS, Constellation::Galileo)), true);
+        for ((a, b), biases) in biases.iter() {
+            assert!(a < b);
+            assert_eq!(biases.len() > 0, true);
+            for (e, bias) in biases.iter() {
+                assert!(bias.is_finite());
+                assert!(offsets.contains_key(e));
+            }
+        }
+        assert_eq!(rinex.inter_system_biases(&nav, Some(position)), biases);
+    }
+    #[test]
+    fn v3_larm0630_range_rates() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/LARM0630.22O";
+        let rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let shifts = rinex.doppler_shifts();
+        let rates = rinex.range_rates();
+        assert_eq!(shifts.len() > 0, true);
+        assert_eq!(rates.len(), shifts.len());
+        let l1 = channel::Channel::L1;
+        for (e, vehicules) in rates.iter() {
+            for (sv, rates) in vehicules.iter() {
+                for (code, rate) in rates.iter() {
+                    assert_eq!(code.is_doppler(), true);
+                    // vehicules move at a few km/s at most
+                    assert!(rate.abs() < 1.0E4);
+                    if sv.constellation == Constellation::GPS && code.to_string() == "D1C" {
+                        let (_, doppler) = shifts[e][sv]
+                            .iter()
+                            .find(|(c, _)| c == code)
+                            .unwrap();
+                        assert!((l1.doppler_to_range_rate(*doppler) - rate).abs() < 1.0E-6);
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn v3_duth0630_unknown_constellation() {
+        use rinex::parser::{ParserOptions, UnknownConstellation};
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/DUTH0630.22O";
+        // first epoch, with an extra vehicule we can't interpret
+        let content = std::fs::read_to_string(&test_resource).unwrap();
+        let mut lines : Vec<String> = Vec::new();
+        let mut epochs = 0;
+        for line in content.lines() {
+            if line.starts_with('>') {
+                epochs += 1;
+                if epochs > 1 {
+                    break
+                }
+                lines.push(line.replace(" 0 18", " 0 19"));
+            } else {
+                lines.push(line.to_string());
+            }
+        }
+        let unknown = lines.last().unwrap().replacen("R24", "X01", 1);
+        lines.push(unknown.clone());
+        let path = std::env::temp_dir().join("DUTH0630-unknown.22O");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+        // default policy: skipped
+        let rinex = Rinex::from_file_with_options(path, &ParserOptions::default());
+        assert_eq!(rinex.is_ok(), true);
+        let rinex = rinex.unwrap();
+        let record = rinex.record.as_obs().unwrap();
+        assert_eq!(record.len(), 1);
+        for (_, (_, vehicules)) in record.iter() {
+            assert_eq!(vehicules.len(), 18);
+        }
+        assert_eq!(rinex.warnings.len(), 1);
+        assert_eq!(rinex.warnings[0].message.contains("X01"), true);
+        // located at the epoch line
+        let epoch_line = lines.iter()
+            .position(|l| l.starts_with('>'))
+            .unwrap() + 1;
+        assert_eq!(rinex.warnings[0].line, Some(epoch_line));
+        assert_eq!(rinex.warnings[0].to_string().starts_with(&format!("line {}", epoch_line)), true);
+        assert_eq!(rinex.raw.len(), 0);
+        // error policy
+        let opts = ParserOptions {
+            unknown_constellation: UnknownConstellation::Error,
+            ..ParserOptions::default()
+        };
+        assert_eq!(Rinex::from_file_with_options(path, &opts).is_err(), true);
+        // raw policy: passed through
+        let opts = ParserOptions {
+            unknown_constellation: UnknownConstellation::KeepRaw,
+            ..ParserOptions::default()
+        };
+        let rinex = Rinex::from_file_with_options(path, &opts);
+        assert_eq!(rinex.is_ok(), true);
+        let rinex = rinex.unwrap();
+        assert_eq!(rinex.warnings.len(), 1);
+        assert_eq!(rinex.raw.len(), 1);
+        for (_, entries) in rinex.raw.iter() {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].sv, "X01");
+            assert_eq!(entries[0].content, vec![unknown.clone()]);
+        }
+        let copy_path = path.to_owned() + "-copy";
+        assert_eq!(rinex.to_file(&copy_path).is_ok(), true);
+        let copy = std::fs::read_to_string(&copy_path).unwrap();
+        assert_eq!(copy.contains(&unknown), true);
+        let copy = Rinex::from_file_with_options(&copy_path, &opts)
+            .unwrap();
+        assert_eq!(copy.raw, rinex.raw);
+        let _ = std::fs::remove_file(copy_path);
+        // raw entries can't be compressed
+        let mut crinex = rinex.clone();
+        crinex.rnx2crx();
+        let mut content: Vec<u8> = Vec::new();
+        let err = crinex.to_writer(&mut content)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(content.is_empty(), true);
+        let _ = std::fs::remove_file(path);
+    }
+    #[test]
+    fn v3_acor00esp_cache() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let source = std::env::temp_dir().join("acor00esp-cache.rnx");
+        let source = source.to_str().unwrap();
+        let cache = std::env::temp_dir().join("acor00esp.rnxcache");
+        let cache = cache.to_str().unwrap();
+        std::fs::copy(&test_resource, source)
+            .unwrap();
+        let rinex = Rinex::from_file(source)
+            .unwrap();
+        rinex.to_cache(source, cache)
+            .unwrap();
+        let cached = Rinex::from_cache(source, cache)
+            .unwrap();
+        assert_eq!(cached.record.as_obs(), rinex.record.as_obs());
+        assert_eq!(cached.comments, rinex.comments);
+        assert_eq!(cached.header.station, rinex.header.station);
+        // cache is tied to its source file
+        let other = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/DUTH0630.22O";
+        assert!(Rinex::from_cache(&other, cache).is_err());
+        // source modification invalidates the cache
+        let mut content = std::fs::read(source)
+            .unwrap();
+        content.extend_from_slice(b"\n");
+        std::fs::write(source, content)
+            .unwrap();
+        match Rinex::from_cache(source, cache) {
+            Err(rinex::cache::Error::Outdated) => {},
+            _ => panic!("outdated cache should have been rejected"),
+        }
+        // falls back to parsing, then refreshes the cache
+        let rinex = Rinex::from_file_cached(source, cache)
+            .unwrap();
+        let cached = Rinex::from_cache(source, cache)
+            .unwrap();
+        assert_eq!(cached.record.as_obs(), rinex.record.as_obs());
+        let _ = std::fs::remove_file(source);
+        let _ = std::fs::remove_file(cache);
+    }
+    #[test]
+    fn v3_acor00esp_e5_altboc() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let mut rinex = Rinex::from_file(&test_resource)
+            .unwrap();
+        let e02 = sv::Sv::from_str("E02").unwrap();
+        let t0 = rinex.first_epoch().unwrap();
+        let c8q = observation::Observable::from_str("C8Q").unwrap();
+        let l8q = observation::Observable::from_str("L8Q").unwrap();
+        let c8x = observation::Observable::from_str("C8X").unwrap();
+        let l8x = observation::Observable::from_str("L8X").unwrap();
+        let d8x = observation::Observable::from_str("D8X").unwrap();
+        // E5 AltBOC is identified
+        let pseudo_ranges = rinex.pseudo_ranges();
+        let codes = &pseudo_ranges[&t0][&e02];
+        assert!(codes.iter().any(|(code, _)| code.to_string() == "C8Q"));
+        // AltBOC was tracked: nothing to synthesize
+        let mut tracked = rinex.clone();
+        tracked.e5_altboc_synthesis_mut();
+        let record = tracked.record.as_obs().unwrap();
+        assert!(record[&t0].1[&e02].get(&c8x).is_none());
+        // synthesize from E5a + E5b
+        let (c8q_obs, l8q_obs) = {
+            let observations = &rinex.record.as_obs().unwrap()[&t0].1[&e02];
+            (observations[&c8q].obs, observations[&l8q].obs)
+        };
+        let record = rinex.record.as_mut_obs().unwrap();
+        for (_, (_, vehicules)) in record.iter_mut() {
+            for (_, observations) in vehicules.iter_mut() {
+                observations.retain(|code, _| code.band() != '8');
+            }
+        }
+        rinex.e5_altboc_synthesis_mut();
+        let record = rinex.record.as_obs().unwrap();
+        let observations = &record[&t0].1[&e02];
+        assert!((observations[&c8x].obs - c8q_obs).abs() < 5.0);
+        assert!((observations[&l8x].obs - l8q_obs).abs() < 10.0);
+        assert!(observations.get(&d8x).is_none());
+        let codes = &rinex.header.obs.as_ref().unwrap().codes[&Constellation::Galileo];
+        assert!(codes.contains(&c8x));
+        assert!(codes.contains(&l8x));
+    }
+    #[test]
+    fn v2_kinematic_truncated_epoch() {
+        use rinex::parser::ParserOptions;
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V2/kinematic.obs";
+        // second epoch has a truncated vehicule list
+        let content = std::fs::read_to_string(&test_resource).unwrap();
+        let content = content.replacen(
+            " 17  1  1  0  1  0.0000000  0  2G31G27",
+            " 17  1  1  0  1  0.0000000  0  2G31G2", 1);
+        let path = std::env::temp_dir().join("kinematic-truncated.obs");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &content).unwrap();
+        // default: skipped and reported
+        let rinex = Rinex::from_file_with_options(path, &ParserOptions::default());
+        assert_eq!(rinex.is_ok(), true);
+        let rinex = rinex.unwrap();
+        assert_eq!(rinex.epochs().len(), 2);
+        assert_eq!(rinex.warnings.len(), 1);
+        assert_eq!(rinex.warnings[0].message.contains("2G31G2"), true);
+        let epoch_line = content.lines()
+            .position(|l| l.starts_with(" 17  1  1  0  1  0.0000000  0  2G31G2"))
+            .unwrap() + 1;
+        assert_eq!(rinex.warnings[0].line, Some(epoch_line));
+        // strict: aborted
+        let opts = ParserOptions {
+            strict: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(Rinex::from_file_with_options(path, &opts).is_err(), true);
+    }
+    #[test]
+    fn v3_acor00esp_unspecified_time_system() {
+        use rinex::parser::ParserOptions;
+        let test_resource = 
+            env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        // mixed file: time system is mandatory
+        let content = std::fs::read_to_string(&test_resource).unwrap()
+            .replacen("     GPS         TIME OF FIRST OBS", "                 TIME OF FIRST OBS", 1)
+            .replacen("     GPS         TIME OF LAST OBS", "                 TIME OF LAST OBS", 1);
+        let path = std::env::temp_dir().join("ACOR00ESP-no-time-system.rnx");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &content).unwrap();
+        // left undefined
+        let rinex = Rinex::from_file(path);
+        assert_eq!(rinex.is_ok(), true);
+        let rinex = rinex.unwrap();
+        let obs = rinex.header.obs.as_ref().unwrap();
+        assert_eq!(obs.time_system, None);
+        assert_eq!(obs.time_of_first_obs.is_some(), true);
+        // default: reported
+        let reference = Rinex::from_file_with_options(&test_resource, &ParserOptions::default())
+            .unwrap();
+        let rinex = Rinex::from_file_with_options(path, &ParserOptions::default())
+            .unwrap();
+        assert_eq!(rinex.warnings.len(), reference.warnings.len() + 1);
+        assert_eq!(rinex.warnings[0].message.contains("time system"), true);
+        // strict: rejected
+        let opts = ParserOptions {
+            strict: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(Rinex::from_file_with_options(path, &opts).is_err(), true);
+        // produced as is: no time system is implied
+        let copy_path = std::env::temp_dir().join("ACOR00ESP-no-time-system-copy.rnx");
+        let copy_path = copy_path.to_str().unwrap();
+        rinex.to_file(copy_path)
+            .unwrap();
+        let copy = std::fs::read_to_string(copy_path).unwrap();
+        for marker in ["TIME OF FIRST OBS", "TIME OF LAST OBS"] {
+            let line = copy.lines()
+                .find(|l| l.contains(marker))
+                .unwrap();
+            assert_eq!(line.get(43..60).unwrap().trim().is_empty(), true);
+        }
+        let copy = Rinex::from_file(copy_path)
+            .unwrap();
+        let obs = copy.header.obs.as_ref().unwrap();
+        assert_eq!(obs.time_system, None);
+        assert_eq!(obs.time_of_first_obs, rinex.header.obs.as_ref().unwrap().time_of_first_obs);
+        let _ = std::fs::remove_file(copy_path);
+        let _ = std::fs::remove_file(path);
+    }
+    #[test]
+    fn v3_duth0630_processing_history() {
+        use rinex::observation::record::LliFlags;
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+            .unwrap()
+            .with_history(true);
+        let trail = |rinex: &Rinex| -> Vec<String> {
+            rinex.processing_history()
+                .into_iter()
+                .map(|(_, description)| description)
+                .collect()
+        };
+        // non mutable forms log the same trail as mutable ones
+        let mut mutated = rinex.clone();
+        mutated.epoch_ok_filter_mut();
+        assert_eq!(trail(&rinex.epoch_ok_filter()), trail(&mutated));
+        let mut mutated = rinex.clone();
+        mutated.epoch_nok_filter_mut();
+        assert_eq!(trail(&rinex.epoch_nok_filter()), trail(&mutated));
+        let mut mutated = rinex.clone();
+        mutated.lli_filter_mut(LliFlags::LOCK_LOSS);
+        assert_eq!(trail(&rinex.lli_filter(LliFlags::LOCK_LOSS)), trail(&mutated));
+        let interval = std::time::Duration::from_secs(60);
+        let mut mutated = rinex.clone();
+        mutated.decimate_by_interval_mut(interval);
+        assert_eq!(trail(&rinex.decimate_by_interval(interval)), trail(&mutated));
+        let mut mutated = rinex.clone();
+        mutated.decimate_by_ratio_mut(2);
+        let decimated = rinex.decimate_by_ratio(2);
+        assert_eq!(trail(&decimated), trail(&mutated));
+        assert_eq!(trail(&decimated), vec![String::from("decimated by ratio 2")]);
+        // header edits are logged
+        let edited = rinex.with_header(rinex.header.with_station("DUTH"));
+        assert_eq!(trail(&edited), vec![String::from("header edit")]);
+        // nothing is logged when history is disabled
+        let rinex = rinex.with_history(false);
+        assert_eq!(trail(&rinex.decimate_by_ratio(2)).is_empty(), true);
+    }
+    #[test]
+    fn v2_kinematic_event_log() {
+        use rinex::epoch::{Epoch, EpochFlag};
+        let rinex = Rinex::from_file("../test_resources/OBS/V2/kinematic.obs")
+            .unwrap();
+        let moving = Epoch::new(
+            epoch::str2date("2017 01 01 00 00 30.0000000").unwrap(),
+            EpochFlag::AntennaBeingMoved);
+        let occupation = Epoch::new(
+            epoch::str2date("2017 01 01 00 02 00.0000000").unwrap(),
+            EpochFlag::NewSiteOccupation);
+        let log = rinex.event_log();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.get(&moving), Some(&Vec::<&str>::new()));
+        assert_eq!(log.get(&occupation), Some(&vec!["NEW SITE OCCUPATION"]));
+        assert_eq!(rinex.event_description(moving), None);
+        assert_eq!(rinex.event_description(occupation), Some(String::from("NEW SITE OCCUPATION")));
+    }
+    #[test]
+    fn splice_policies() {
+        use rinex::merge::{DuplicatePolicy, MergeError};
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+            .unwrap();
+        let epochs = rinex.epochs();
+        let mut first = rinex.clone();
+        first.retain_epochs_mut(|e| *e <= epochs[9]);
+        // disjoint files: gap at the seam
+        let mut second = rinex.clone();
+        second.retain_epochs_mut(|e| *e >= epochs[20]);
+        let mut spliced = first.clone();
+        let report = spliced.splice_mut(&second, DuplicatePolicy::Error)
+            .unwrap();
+        assert_eq!(report.duplicates.len(), 0);
+        assert_eq!(report.gap, Some((epochs[9], epochs[20])));
+        assert_eq!(spliced.epochs().len(), 10 + epochs.len() - 20);
+        assert_eq!(spliced.is_merged(), true);
+        // overlapping files, with different observations
+        let mut second = rinex.clone();
+        second.retain_epochs_mut(|e| *e >= epochs[5] && *e <= epochs[14]);
+        for (_, (_, vehicules)) in second.record.as_mut_obs().unwrap().iter_mut() {
+            for (_, observations) in vehicules.iter_mut() {
+                for (_, data) in observations.iter_mut() {
+                    data.obs += 2.0
+                }
+            }
+        }
+        let mut failed = first.clone();
+        match failed.splice_mut(&second, DuplicatePolicy::Error) {
+            Err(MergeError::DuplicateEpoch(e)) => assert_eq!(e, epochs[5]),
+            _ => panic!("duplicate epochs should have been reported"),
+        }
+        // failed splice operation leaves self untouched
+        assert_eq!(failed.header.comments, first.header.comments);
+        assert_eq!(failed.epochs(), first.epochs());
+        assert_eq!(failed.is_merged(), false);
+        let (_, vehicules) = &first.record.as_obs().unwrap()[&epochs[5]];
+        let (sv, observations) = vehicules.iter().next().unwrap();
+        let (observable, data) = observations.iter().next().unwrap();
+        for (policy, offset) in [
+            (DuplicatePolicy::KeepFirst, 0.0),
+            (DuplicatePolicy::KeepSecond, 2.0),
+            (DuplicatePolicy::Average, 1.0),
+        ] {
+            let mut spliced = first.clone();
+            let report = spliced.splice_mut(&second, policy)
+                .unwrap();
+            assert_eq!(report.duplicates, epochs[5..10].to_vec());
+            assert_eq!(report.gap, None);
+            assert_eq!(spliced.epochs(), epochs[..15].to_vec());
+            let (_, vehicules) = &spliced.record.as_obs().unwrap()[&epochs[5]];
+            let spliced = vehicules[sv][observable].obs;
+            assert!((spliced - (data.obs + offset)).abs() < 1.0E-6);
+        }
+    }
+    #[test]
+    fn merge_policies() {
+        use rinex::merge::{MergePolicy, FieldPolicy, MergeError};
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+            .unwrap();
+        let gps_codes = rinex.header.obs.as_ref().unwrap().codes[&Constellation::GPS].clone();
+        // another session, with another receiver and fewer GPS observables
+        let mut other = rinex.clone();
+        let mut rcvr = other.header.rcvr.clone().unwrap();
+        rcvr.sn = String::from("1234");
+        other.header.rcvr = Some(rcvr.clone());
+        other.header.obs.as_mut().unwrap().codes
+            .insert(Constellation::GPS, gps_codes[..2].to_vec());
+        // default: self is preferred
+        let mut merged = rinex.clone();
+        merged.merge_mut(&other).unwrap();
+        assert_eq!(merged.header.rcvr, rinex.header.rcvr);
+        assert_eq!(merged.header.obs.as_ref().unwrap().codes[&Constellation::GPS], gps_codes);
+        // other is preferred
+        let mut merged = rinex.clone();
+        merged.merge_with_policy_mut(&other, &MergePolicy::all(FieldPolicy::KeepOther))
+            .unwrap();
+        assert_eq!(merged.header.rcvr, Some(rcvr));
+        assert_eq!(merged.header.obs.as_ref().unwrap().codes[&Constellation::GPS], gps_codes);
+        // intersection
+        let mut merged = rinex.clone();
+        merged.merge_with_policy_mut(&other, &MergePolicy::all(FieldPolicy::Intersect))
+            .unwrap();
+        assert_eq!(merged.header.rcvr, None);
+        assert_eq!(merged.header.ant.is_some(), true);
+        assert_eq!(merged.header.coords.is_some(), true);
+        assert_eq!(merged.header.obs.as_ref().unwrap().codes[&Constellation::GPS], gps_codes[..2].to_vec());
+        // mismatches
+        let policy = MergePolicy {
+            receiver: FieldPolicy::Fail,
+            ..MergePolicy::default()
+        };
+        match rinex.clone().merge_with_policy_mut(&other, &policy) {
+            Err(MergeError::FieldMismatch(field)) => assert_eq!(field, "receiver"),
+            _ => panic!("receiver mismatch should have been reported"),
+        }
+        let policy = MergePolicy {
+            observables: FieldPolicy::Fail,
+            ..MergePolicy::default()
+        };
+        match rinex.clone().merge_with_policy_mut(&other, &policy) {
+            Err(MergeError::FieldMismatch(field)) => assert_eq!(field, "observables"),
+            _ => panic!("observables mismatch should have been reported"),
+        }
+        assert_eq!(rinex.clone().merge_with_policy_mut(&rinex, &MergePolicy::all(FieldPolicy::Fail)).is_ok(), true);
+    }
+    #[test]
+    fn resampling() {
+        let mut rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+            .unwrap();
+        rinex.retain_epochs_mut(|e| e.flag.is_ok());
+        let epochs = rinex.epochs();
+        let interval = std::time::Duration::from_secs(15);
+        // grid is already synchronized
+        let resampled = rinex.resample(interval, false);
+        assert_eq!(resampled.epochs(), epochs);
+        assert_eq!(resampled.header.sampling_interval, Some(15.0));
+        // upsampling
+        let resampled = rinex.resample(interval, true);
+        let e = epoch::Epoch::new(
+            epochs[0].date + chrono::Duration::seconds(15),
+            epoch::EpochFlag::Ok);
+        let record = resampled.record.as_obs().unwrap();
+        let (_, vehicules) = &record[&e];
+        let (_, vehicules0) = &record[&epochs[0]];
+        let (_, vehicules1) = &record[&epochs[1]];
+        let c1c = observation::Observable::from_str("C1C").unwrap();
+        let mut tested = false;
+        for (sv, observations) in vehicules.iter() {
+            for (observable, data) in observations.iter() {
+                assert!(observable.is_phase() || observable.is_pseudo_range());
+                if *observable == c1c {
+                    let expected = (vehicules0[sv][observable].obs + vehicules1[sv][observable].obs) / 2.0;
+                    assert!((data.obs - expected).abs() < 1.0E-6);
+                    tested = true;
+                }
+            }
+        }
+        assert!(tested);
+    }
+    #[test]
+    fn validation_profiles() {
+        use rinex::validation::Profile;
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+            .unwrap();
+        // 3.02 file, without MARKER TYPE
+        let rules: Vec<&str> = rinex.validate(Profile::V3_04)
+            .iter()
+            .map(|v| v.rule)
+            .collect();
+        assert_eq!(rules, vec!["VER-001", "HDR-005"]);
+        let rules: Vec<&str> = rinex.validate(Profile::Igs)
+            .iter()
+            .map(|v| v.rule)
+            .collect();
+        assert_eq!(rules, vec!["VER-001", "HDR-005"]);
+        // V3 observables are not V2 observables
+        let violations = rinex.validate(Profile::V2_11);
+        assert_eq!(violations.iter().any(|v| v.rule == "OBS-001"), true);
+        assert_eq!(violations.iter().any(|v| v.rule == "HDR-005"), false);
+        // 2.10 file
+        let rinex = Rinex::from_file("../test_resources/OBS/V2/aopr0010.17o")
+            .unwrap();
+        let rules: Vec<&str> = rinex.validate(Profile::V2_11)
+            .iter()
+            .map(|v| v.rule)
+            .collect();
+        assert_eq!(rules, vec!["VER-001"]);
+        let violations = rinex.validate(Profile::Igs);
+        assert_eq!(violations.iter().any(|v| v.rule == "OBS-002"), true);
+        assert_eq!(violations.iter().any(|v| v.rule == "IGS-001"), true);
+        assert_eq!(rinex.header.validate().is_empty(), true);
+    }
+    #[test]
+    fn v3_duth0630_glo_channels() {
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+            .unwrap();
+        let channels = rinex.glo_channels();
+        assert_eq!(channels.len(), 22);
+        let r01 = sv::Sv::from_str("R01").unwrap();
+        let r10 = sv::Sv::from_str("R10").unwrap();
+        assert_eq!(channels.get(&r01), Some(&1));
+        assert_eq!(channels.get(&r10), Some(&-7));
+        assert_eq!(channels.get(&sv::Sv::from_str("R11").unwrap()), None);
+        // true FDMA frequencies
+        let c1c = observation::Observable::from_str("C1C").unwrap();
+        let channel = channel::Channel::from_sv_observable(&r10, &c1c, &channels)
+            .unwrap();
+        assert_eq!(channel.carrier_frequency_mhz(), 1598.0625);
+        // header formatting
+        let header = rinex.header.to_string();
+        let lines: Vec<&str> = header.lines()
+            .filter(|l| l.contains("GLONASS SLOT / FRQ #"))
+            .collect();
+        assert_eq!(lines, vec![
+            " 22 R01  1 R02 -4 R03  5 R04  6 R05  1 R06 -4 R07  5 R08  6 GLONASS SLOT / FRQ #",
+            "    R09 -2 R10 -7 R12 -1 R13 -2 R14 -7 R15  0 R17  4 R18 -3 GLONASS SLOT / FRQ #",
+            "    R19  3 R20  2 R21  4 R22 -3 R23  3 R24  2               GLONASS SLOT / FRQ #",
+        ]);
+    }
+    #[test]
+    fn v3_duth0630_phase_shifts() {
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+            .unwrap();
+        let shifts = &rinex.header.obs.as_ref().unwrap().phase_shifts;
+        assert_eq!(shifts.len(), 4);
+        assert_eq!(shifts[2].constellation, Constellation::Glonass);
+        assert_eq!(shifts[2].observable, observation::Observable::from_str("L2P").unwrap());
+        assert_eq!(shifts[2].correction, Some(0.25));
+        assert_eq!(shifts[2].vehicules.len(), 0);
+        // header formatting
+        let header = rinex.header.to_string();
+        let lines: Vec<&str> = header.lines()
+            .filter(|l| l.contains("SYS / PHASE SHIFT"))
+            .collect();
+        assert_eq!(lines, vec![
+            "G L2S -0.25000                                              SYS / PHASE SHIFT",
+            "G L2X -0.25000                                              SYS / PHASE SHIFT",
+            "R L2P  0.25000                                              SYS / PHASE SHIFT",
+            "E L8Q -0.25000                                              SYS / PHASE SHIFT",
+        ]);
+        // application
+        let l2p = observation::Observable::from_str("L2P").unwrap();
+        let mut corrected = rinex.clone();
+        if let Some(record) = corrected.record.as_mut_obs() {
+            observation::apply_phase_shifts(record, shifts);
+        }
+        let record = rinex.record.as_obs().unwrap();
+        let corrected = corrected.record.as_obs().unwrap();
+        let mut tested = 0;
+        for (e, (_, vehicules)) in record.iter() {
+            for (sv, observations) in vehicules.iter() {
+                if let Some(data) = observations.get(&l2p) {
+                    let shifted = corrected[e].1[sv][&l2p].obs;
+                    assert!((shifted - data.obs - 0.25).abs() < 1.0E-9);
+                    tested += 1;
+                }
+            }
+        }
+        assert!(tested > 0);
+    }
+    #[test]
+    fn v2_zegv0010_sat_counts() {
+        let mut rinex = Rinex::from_file("../test_resources/OBS/V2/zegv0010.21o")
+            .unwrap();
+        let obs = rinex.header.obs.as_ref().unwrap();
+        assert_eq!(obs.nb_satellites, Some(54));
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let g02 = sv::Sv::from_str("G02").unwrap();
+        let c1 = observation::Observable::from_str("C1").unwrap();
+        let c5 = observation::Observable::from_str("C5").unwrap();
+        let s5 = observation::Observable::from_str("S5").unwrap();
+        assert_eq!(obs.nb_observations[&g01].get(&c1), Some(&1020));
+        assert_eq!(obs.nb_observations[&g01].get(&s5), Some(&1036));
+        assert_eq!(obs.nb_observations[&g02].get(&c5), None);
+        // header formatting
+        let header = rinex.header.to_string();
+        let lines: Vec<&str> = header.lines()
+            .filter(|l| l.contains("PRN / # OF OBS"))
+            .take(4)
+            .collect();
+        assert_eq!(lines, vec![
+            "   G01  1020  1033  1036   990   984  1036   984   984  1020PRN / # OF OBS",
+            "         984  1036                                          PRN / # OF OBS",
+            "   G02  1183              1167  1161        1161  1161  1183PRN / # OF OBS",
+            "        1161                                                PRN / # OF OBS",
+        ]);
+        // record content
+        let record = rinex.record.as_obs().unwrap();
+        let mut svs: Vec<sv::Sv> = Vec::new();
+        let mut g01_c1 = 0;
+        for (_, (_, vehicules)) in record.iter() {
+            for (sv, observations) in vehicules.iter() {
+                if !svs.contains(sv) {
+                    svs.push(*sv);
+                }
+                if *sv == g01 && observations.contains_key(&c1) {
+                    g01_c1 += 1;
+                }
+            }
+        }
+        // this excerpt does not contain G01
+        assert_eq!(g01_c1, 0);
+        let g07 = sv::Sv::from_str("G07").unwrap();
+        let g07_c1 = record.iter()
+            .filter(|(_, (_, vehicules))| {
+                vehicules.get(&g07)
+                    .map(|observations| observations.contains_key(&c1))
+                    .unwrap_or(false)
+            })
+            .count() as u32;
+        // counts are regenerated on production
+        let copy = std::env::temp_dir().join("zegv0010-sat-counts.21o");
+        let copy = copy.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy).is_ok(), true);
+        let parsed = Rinex::from_file(copy);
+        let _ = std::fs::remove_file(copy);
+        let parsed = parsed.unwrap();
+        let obs = parsed.header.obs.as_ref().unwrap();
+        assert_eq!(obs.nb_satellites, Some(svs.len()));
+        assert_eq!(obs.nb_observations.get(&g01), None);
+        assert_eq!(obs.nb_observations[&g07].get(&c1), Some(&g07_c1));
+        // regeneration
+        rinex.header.rework_sat_counts(record);
+        let obs = rinex.header.obs.as_ref().unwrap();
+        assert_eq!(obs.nb_satellites, Some(svs.len()));
+        assert_eq!(obs.nb_observations.len(), svs.len());
+        assert_eq!(obs.nb_observations[&g07].get(&c1), Some(&g07_c1));
+    }
+    #[test]
+    fn v3_duth0630_spaceborne() {
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+            .unwrap();
+        assert_eq!(rinex.header.is_spaceborne(), false);
+        let mut header = rinex.header.with_marker_type(header::MarkerType::Spaceborne);
+        header.center_of_mass = Some(rust_3d::Point3D::new(1.0, -0.5, 0.25));
+        assert_eq!(header.is_spaceborne(), true);
+        let content = header.to_string();
+        let lines: Vec<&str> = content.lines()
+            .filter(|l| l.contains("MARKER TYPE") || l.contains("CENTER OF MASS: XYZ"))
+            .collect();
+        assert_eq!(lines, vec![
+            "SPACEBORNE                                                  MARKER TYPE",
+            "        1.0000       -0.5000        0.2500                  CENTER OF MASS: XYZ",
+        ]);
+        assert_eq!(header::MarkerType::from_str("SPACEBORNE").unwrap(), header::MarkerType::Spaceborne);
+        assert_eq!(header.is_static(), false);
+        // receiver is not static: header position is not used
+        let rinex = rinex.with_header(header);
+        let nav = Rinex::from_file("../test_resources/NAV/V3/CBW100NLD_R_20210010000_01D_MN.rnx")
+            .unwrap();
+        assert_eq!(rinex.space_vehicule_elevation_azimuth(&nav, None).is_empty(), true);
+        assert_eq!(rinex.receiver_clock_offsets(&nav, None).is_empty(), true);
+    }
+    #[test]
+    fn marker_types() {
+        use rinex::header::MarkerType;
+        let markers = vec![
+            MarkerType::Geodetic, MarkerType::NonGeodetic, MarkerType::NonPhysical,
+            MarkerType::Spaceborne, MarkerType::Airborne, MarkerType::Watercraft,
+            MarkerType::Groundcraft, MarkerType::FixedBuoy, MarkerType::FloatingBuoy,
+            MarkerType::FloatingIce, MarkerType::Glacier, MarkerType::Ballistic,
+            MarkerType::Animal, MarkerType::Human,
+        ];
+        for marker in markers {
+            assert_eq!(MarkerType::from_str(&marker.to_string()).unwrap(), marker);
+        }
+        assert_eq!(MarkerType::from_str("NON GEODETIC").unwrap(), MarkerType::NonGeodetic);
+        assert_eq!(MarkerType::FixedBuoy.is_static(), true);
+        assert_eq!(MarkerType::FloatingBuoy.is_static(), false);
+        // MARKER TYPE is parsed and produced
+        let rinex = Rinex::from_file("../test_resources/CRNX/V3/DOUR00BEL_R_20200130000_01D_30S_MO.crx")
+            .unwrap();
+        assert_eq!(rinex.header.marker_type, Some(MarkerType::Geodetic));
+        assert_eq!(rinex.header.is_static(), true);
+        // not described: static
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx")
+            .unwrap();
+        assert_eq!(rinex.header.marker_type, None);
+        assert_eq!(rinex.header.is_static(), true);
+        let content = rinex.header.with_marker_type(MarkerType::Groundcraft).to_string();
+        assert_eq!(content.lines().any(|l| l.starts_with("GROUND_CRAFT ") && l.ends_with("MARKER TYPE")), true);
+    }
+    #[test]
+    fn validation_record_consistency() {
+        use rinex::validation::Profile;
+        let rinex = Rinex::from_file("../test_resources/OBS/V3/DUTH0630.22O")
+            .unwrap();
+        // TIME OF FIRST OBS no longer matches the record
+        let obs = rinex.header.obs.as_ref().unwrap();
+        let mut shifted_obs = obs.clone();
+        if let Some(first) = shifted_obs.time_of_first_obs.as_mut() {
+            *first += chrono::Duration::hours(1);
+        }
+        let mut header = rinex.header.clone();
+        header.obs = Some(shifted_obs);
+        let shifted = rinex.with_header(header);
+        let violations = shifted.validate(Profile::V3_04);
+        assert_eq!(violations.iter().any(|v| v.rule == "REC-003"), true);
+        // observables that are declared but never observed
+        let mut header = rinex.header.clone();
+        let mut obs = obs.clone();
+        obs.codes.get_mut(&Constellation::GPS)
+            .unwrap()
+            .push(observation::Observable::from_str("S5Q").unwrap());
+        header.obs = Some(obs);
+        let rinex = rinex.with_header(header);
+        let violations: Vec<_> = rinex.validate(Profile::V3_04)
+            .into_iter()
+            .filter(|v| v.rule == "REC-004")
+            .collect();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "GPS: \"S5Q\" is declared but never observed");
+    }
 }
@@ -61,16 +61,11 @@ mod test {
                         continue // not a test resource
                     }
                     
-                    let mut is_gzip_encoded = entry
+                    let is_gzip_encoded = entry
                         .file_name()
                         .to_str()
                         .unwrap()
                         .ends_with(".gz");
-                    is_gzip_encoded |= entry
-                        .file_name()
-                        .to_str()
-                        .unwrap()
-                        .ends_with(".Z");
                     if is_gzip_encoded && !cfg!(feature="with-gzip") {
                         continue // do not run in this build configuration
                     }
@@ -124,4 +119,155 @@ mod test {
             }
         }
     }
+    #[test]
+    fn test_from_reader() {
+        for path in vec![
+            "../test_resources/OBS/V2/kinematic.obs",
+            "../test_resources/CRNX/V3/KMS300DNK_R_20221591000_01H_30S_MO.crx",
+            "../test_resources/NAV/V2/amel0010.21g.Z",
+        ] {
+            let content = std::fs::read(path)
+                .unwrap();
+            let rinex = Rinex::from_reader(&content[..]);
+            assert_eq!(rinex.is_ok(), true, "failed to parse \"{}\" from memory", path);
+            let rinex = rinex.unwrap();
+            let expected = Rinex::from_file(path)
+                .unwrap();
+            assert_eq!(rinex.header.rinex_type, expected.header.rinex_type);
+            assert_eq!(rinex.epochs(), expected.epochs());
+            let header = header::Header::from_reader(&content[..]);
+            assert_eq!(header.is_ok(), true);
+            assert_eq!(header.unwrap().station, expected.header.station);
+        }
+        // truncated content
+        let rinex = Rinex::from_reader(&b"     2.10           OBSERVATION DATA"[..]);
+        assert!(matches!(rinex, Err(Error::TruncatedHeader)));
+    }
+    #[test]
+    fn test_from_bytes() {
+        for path in vec![
+            "../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx",
+            "../test_resources/CRNX/V3/KMS300DNK_R_20221591000_01H_30S_MO.crx",
+        ] {
+            let content = std::fs::read(path)
+                .unwrap();
+            let rinex = Rinex::from_bytes(&content);
+            assert_eq!(rinex.is_ok(), true, "failed to parse \"{}\" from bytes", path);
+            let expected = Rinex::from_file(path)
+                .unwrap();
+            assert_eq!(rinex.unwrap().epochs(), expected.epochs());
+        }
+        assert!(matches!(Rinex::from_bytes(&[]), Err(Error::TruncatedHeader)));
+    }
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_from_async_reader() {
+        let path = "../test_resources/CRNX/V3/KMS300DNK_R_20221591000_01H_30S_MO.crx";
+        let content = std::fs::read(path)
+            .unwrap();
+        let rinex = Rinex::from_async_reader(&content[..])
+            .await;
+        assert_eq!(rinex.is_ok(), true);
+        let rinex = rinex.unwrap();
+        let expected = Rinex::from_file(path)
+            .unwrap();
+        assert_eq!(rinex.epochs(), expected.epochs());
+        let rinex = Rinex::from_async_reader(&b"     2.10           OBSERVATION DATA"[..])
+            .await;
+        assert!(matches!(rinex, Err(Error::TruncatedHeader)));
+    }
+    #[test]
+    fn test_from_files() {
+        let paths = [
+            "../test_resources/OBS/V2/kinematic.obs",
+            "../test_resources/OBS/V2/aopr0010.17o",
+        ];
+        let results = Rinex::from_files(&paths);
+        assert_eq!(results.len(), 2);
+        let mut epochs: Vec<epoch::Epoch> = Vec::new();
+        for (path, result) in paths.iter().zip(results.iter()) {
+            let rinex = result.as_ref().unwrap();
+            assert_eq!(rinex.epochs(), Rinex::from_file(path).unwrap().epochs());
+            epochs.extend(rinex.epochs());
+        }
+        epochs.sort();
+        epochs.dedup();
+        let merged = Rinex::from_files_merged(&paths);
+        assert_eq!(merged.is_ok(), true);
+        assert_eq!(merged.unwrap().epochs(), epochs);
+        // failures
+        let merged = Rinex::from_files_merged(&[paths[0], "../test_resources/OBS/V2/does-not-exist.obs"]);
+        assert!(matches!(merged, Err(Error::IoError(_))));
+        let merged = Rinex::from_files_merged(&[paths[0], "../test_resources/NAV/V2/amel0010.21g"]);
+        assert!(matches!(merged, Err(Error::MergeError(_))));
+        assert!(matches!(Rinex::from_files_merged(&[]), Err(Error::NoFiles)));
+    }
+    #[test]
+    fn test_corrupt_files() {
+        let tmp = std::env::temp_dir();
+        // short first line
+        let path = tmp.join("truncated.rnx");
+        std::fs::write(&path, "     2.10           OBSERVATION DATA")
+            .unwrap();
+        let rinex = Rinex::from_file(path.to_str().unwrap());
+        assert!(matches!(rinex, Err(Error::TruncatedHeader)));
+        // non utf-8 header
+        let path = tmp.join("encoding.rnx");
+        let mut content = vec![0xff_u8; 80];
+        content.extend_from_slice(b"\n");
+        std::fs::write(&path, content)
+            .unwrap();
+        let rinex = Rinex::from_file(path.to_str().unwrap());
+        assert!(matches!(rinex, Err(Error::InvalidEncoding)));
+        // non existing file
+        let rinex = Rinex::from_file("../test_resources/OBS/V2/does-not-exist.obs");
+        assert!(matches!(rinex, Err(Error::IoError(_))));
+        // faulty header field: located
+        let content = std::fs::read_to_string("../test_resources/OBS/V2/kinematic.obs")
+            .unwrap();
+        let content = content.replacen("     2.10           OBSERVATION DATA", "     9.10           OBSERVATION DATA", 1);
+        let path = tmp.join("unsupported.obs");
+        std::fs::write(&path, content)
+            .unwrap();
+        let rinex = Rinex::from_file(path.to_str().unwrap());
+        match rinex {
+            Err(Error::HeaderError(header::Error::Line(line, e))) => {
+                assert_eq!(line, 1);
+                assert!(matches!(*e, header::Error::VersionNotSupported(_)));
+            },
+            _ => panic!("expecting located header error"),
+        }
+        // malformed meteo epochs: invalid date, truncated epoch
+        let original = Rinex::from_file("../test_resources/MET/V2/abvi0010.15m")
+            .unwrap();
+        let content = std::fs::read_to_string("../test_resources/MET/V2/abvi0010.15m")
+            .unwrap();
+        let content = content
+            .replacen(" 15  1  1  0  3  0 1018.7", " 15 13  1  0  3  0 1018.7", 1)
+            .replacen(" 15  1  1  0  4  0 1018.7   25.4   80.4    3.9   11.0    0.0    0.0", " 15  1  1  0  4", 1);
+        let path = tmp.join("malformed.15m");
+        std::fs::write(&path, content)
+            .unwrap();
+        let rinex = Rinex::from_file(path.to_str().unwrap())
+            .unwrap();
+        assert_eq!(rinex.epochs().len(), original.epochs().len() - 2);
+        // truncated clock data line
+        let content = std::fs::read_to_string("../test_resources/CLK/V3/USNO1.txt")
+            .unwrap();
+        let content = content.replacen("AS G16  1994 07 14 20 59  0.000000  2   -0.123456789012E+00 -0.123456789012E+01", "AS G16  1994 07 14", 1);
+        let path = tmp.join("malformed.clk");
+        std::fs::write(&path, content)
+            .unwrap();
+        let rinex = Rinex::from_file(path.to_str().unwrap())
+            .unwrap();
+        let g16: sv::Sv = "G16".parse().unwrap();
+        let record = rinex.record.as_clock().unwrap();
+        assert_eq!(record.values().any(|entries| entries.satellites().contains_key(&g16)), false);
+        assert_eq!(record.values().any(|entries| entries.stations().contains_key("USNO")), true);
+        // gzip compressed file, without gzip support
+        #[cfg(not(feature = "with-gzip"))] {
+            let rinex = Rinex::from_file("../test_resources/NAV/V3/BRDC00GOP_R_20210010000_01D_MN.rnx.gz");
+            assert!(matches!(rinex, Err(Error::IoError(_))));
+        }
+    }
 }
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod test {
     use rinex::*;
-    //use std::str::FromStr;
+    use std::str::FromStr;
     use std::process::Command;
     /// Runs `diff` to determines whether f1 & f2 
     /// are strictly identical or not
@@ -30,8 +30,12 @@ mod test {
             // produce a copy
             let copy_path = fp.to_owned() + "-copy";
             assert_eq!(rinex.to_file(&copy_path).is_ok(), true);
+            // every observation is preserved
+            let parsed = Rinex::from_file(&copy_path);
             // remove copy to not disturb other tests browser
-            let _ = std::fs::remove_file(copy_path);
+            let _ = std::fs::remove_file(&copy_path);
+            let parsed = parsed.unwrap();
+            assert_eq!(parsed.record.as_obs(), rinex.record.as_obs(), "\"{}\" round trip", fp);
         }
     }
     #[test]
@@ -53,6 +57,169 @@ mod test {
         }
     }
     #[test]
+    fn test_obs_v3_deterministic_production() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&fp)
+            .unwrap();
+        // produce two copies: must be strictly identical
+        let copy1 = std::env::temp_dir().join("ACOR-copy1.rnx");
+        let copy1 = copy1.to_str().unwrap();
+        let copy2 = std::env::temp_dir().join("ACOR-copy2.rnx");
+        let copy2 = copy2.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy1).is_ok(), true);
+        assert_eq!(rinex.clone().to_file(copy2).is_ok(), true);
+        let content = std::fs::read_to_string(copy1).unwrap();
+        assert_eq!(content, std::fs::read_to_string(copy2).unwrap());
+        // header: constellations are sorted
+        let systems : Vec<&str> = content
+            .lines()
+            .filter(|l| l.contains("SYS / # / OBS TYPES"))
+            .filter(|l| !l.starts_with(' '))
+            .map(|l| &l[0..1])
+            .collect();
+        let mut sorted = systems.clone();
+        sorted.sort();
+        assert_eq!(systems, sorted);
+        // record: vehicules are sorted within each epoch
+        let mut vehicules : Vec<String> = Vec::new();
+        for line in content.lines().skip_while(|l| !l.contains("END OF HEADER")).skip(1) {
+            if line.starts_with('>') {
+                let mut sorted = vehicules.clone();
+                sorted.sort_by(|a, b| a[0..1].cmp(&b[0..1])
+                    .then(a[1..].cmp(&b[1..])));
+                assert_eq!(vehicules, sorted);
+                vehicules.clear();
+            } else {
+                vehicules.push(line[0..3].to_string())
+            }
+        }
+        let _ = std::fs::remove_file(copy1);
+        let _ = std::fs::remove_file(copy2);
+    }
+    #[test]
+    fn test_obs_time_of_first_obs_production() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let mut rinex = Rinex::from_file(&fp)
+            .unwrap();
+        let obs = rinex.header.obs.as_ref().unwrap();
+        assert_eq!(obs.time_system, Some(observation::TimeSystem::GPS));
+        assert_eq!(obs.time_of_first_obs.is_some(), true);
+        assert_eq!(obs.time_of_last_obs.is_some(), true);
+        // mixed file expressed in another time system
+        rinex.header.obs.as_mut().unwrap().time_system = Some(observation::TimeSystem::GAL);
+        let copy = std::env::temp_dir().join("ACOR-gal.rnx");
+        let copy = copy.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy).is_ok(), true);
+        let content = std::fs::read_to_string(copy).unwrap();
+        let line = content
+            .lines()
+            .find(|l| l.contains("TIME OF FIRST OBS"))
+            .unwrap();
+        assert_eq!(&line[48..51], "GAL");
+        let parsed = Rinex::from_file(copy)
+            .unwrap();
+        let p_obs = parsed.header.obs.as_ref().unwrap();
+        assert_eq!(p_obs.time_system, Some(observation::TimeSystem::GAL));
+        assert_eq!(p_obs.time_of_first_obs, rinex.header.obs.as_ref().unwrap().time_of_first_obs);
+        let _ = std::fs::remove_file(copy);
+    }
+    #[test]
+    fn test_obs_header_correction_production() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&fp)
+            .unwrap();
+        let antenna = hardware::Antenna {
+            model: String::from("TRM59800.00     SCIS"),
+            sn: String::from("5000118"),
+            ..rinex.header.ant.clone().unwrap_or_default()
+        };
+        let header = rinex.header
+            .with_station("ACOR")
+            .with_station_id("13434M001")
+            .with_antenna(antenna)
+            .with_observer_agency("NOBODY", "IGN")
+            .with_position((4594489.7, -678367.7, 4357066.2));
+        let rinex = rinex.with_header(header);
+        let copy = std::env::temp_dir().join("ACOR-corrected.rnx");
+        let copy = copy.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy).is_ok(), true);
+        let parsed = Rinex::from_file(copy)
+            .unwrap();
+        let _ = std::fs::remove_file(copy);
+        assert_eq!(parsed.header.station, "ACOR");
+        assert_eq!(parsed.header.station_id, "13434M001");
+        assert_eq!(parsed.header.observer, "NOBODY");
+        assert_eq!(parsed.header.agency, "IGN");
+        let ant = parsed.header.ant.as_ref().unwrap();
+        assert_eq!(ant.model, "TRM59800.00     SCIS");
+        assert_eq!(ant.sn, "5000118");
+        let coords = parsed.header.coords.as_ref().unwrap();
+        assert!((coords.x - 4594489.7).abs() < 1.0E-3);
+        assert_eq!(parsed.epochs(), rinex.epochs());
+    }
+    #[test]
+    fn test_obs_events_production() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V2/kinematic.obs";
+        let rinex = Rinex::from_file(&fp)
+            .unwrap();
+        assert_eq!(rinex.events.len(), 2);
+        let copy = std::env::temp_dir().join("kinematic-events.obs");
+        let copy = copy.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy).is_ok(), true);
+        let parsed = Rinex::from_file(copy)
+            .unwrap();
+        let _ = std::fs::remove_file(copy);
+        assert_eq!(parsed.epochs(), rinex.epochs());
+        assert_eq!(parsed.record.as_obs(), rinex.record.as_obs());
+        assert_eq!(parsed.events.len(), 2);
+        for ((e0, ev0), (e1, ev1)) in rinex.events.iter().zip(parsed.events.iter()) {
+            assert_eq!(e0, e1);
+            assert_eq!(ev0.station, ev1.station);
+            assert_eq!(ev0.to_records(), ev1.to_records());
+        }
+        assert_eq!(parsed.event_log(), rinex.event_log());
+    }
+    #[test]
+    fn test_obs_antenna_production() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&fp)
+            .unwrap();
+        let ant = rinex.header.ant.clone().unwrap();
+        assert_eq!(ant.height, Some(3.046));
+        assert_eq!(ant.eastern_ecc, Some(0.0));
+        assert_eq!(ant.northern_ecc, Some(0.0));
+        let antenna = hardware::Antenna {
+            zero_dir_azimuth: Some(90.0),
+            phase_centers: vec![
+                hardware::PhaseCenter {
+                    constellation: constellation::Constellation::GPS,
+                    observable: observation::Observable::from_str("L1C").unwrap(),
+                    offset: (0.0912, 0.0012, -0.0009),
+                },
+            ],
+            ..ant
+        };
+        let header = rinex.header.with_antenna(antenna);
+        let rinex = rinex.with_header(header);
+        let copy = std::env::temp_dir().join("ACOR-antenna.rnx");
+        let copy = copy.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy).is_ok(), true);
+        let parsed = Rinex::from_file(copy)
+            .unwrap();
+        let _ = std::fs::remove_file(copy);
+        let ant = parsed.header.ant.unwrap();
+        assert_eq!(ant.sn, rinex.header.ant.as_ref().unwrap().sn);
+        assert_eq!(ant.height, Some(3.046));
+        assert_eq!(ant.zero_dir_azimuth, Some(90.0));
+        assert_eq!(ant.phase_centers, rinex.header.ant.as_ref().unwrap().phase_centers);
+        assert_eq!(parsed.header.coords.is_some(), true);
+    }
+    #[test]
     fn test_meteo_v2_production() {
         let folder = env!("CARGO_MANIFEST_DIR").to_owned() + "/../test_resources/MET/V2/";
         for file in std::fs::read_dir(folder).unwrap() {
@@ -88,4 +255,271 @@ mod test {
             let _ = std::fs::remove_file(copy_path);
         }
     }
+    #[test]
+    fn test_streaming_production() {
+        let resources = vec![
+            "OBS/V2/aopr0010.17o",
+            "OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx",
+            "CRNX/V3/KMS300DNK_R_20221591000_01H_30S_MO.crx",
+            "NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx",
+            "NAV/V4/FRMS00XXX_R_20221591000_01H_MN.rnx",
+            "MET/V2/abvi0010.15m",
+        ];
+        for resource in resources {
+            let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+                + "/../test_resources/" + resource;
+            let rinex = Rinex::from_file(&fp)
+                .unwrap();
+            // stream into memory
+            let mut content: Vec<u8> = Vec::new();
+            assert_eq!(rinex.to_writer(&mut content).is_ok(), true);
+            // produce a file: must be strictly identical
+            let copy_path = std::env::temp_dir()
+                .join(resource.replace("/", "_") + "-stream");
+            let copy_path = copy_path.to_str().unwrap();
+            assert_eq!(rinex.to_file(copy_path).is_ok(), true);
+            let produced = std::fs::read(copy_path).unwrap();
+            assert_eq!(produced, content, "\"{}\": streamed content differs", resource);
+            let _ = std::fs::remove_file(copy_path);
+        }
+    }
+    #[test]
+    fn test_crx_v3_production() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&fp);
+        assert_eq!(rinex.is_ok(), true);
+        let rinex = rinex.unwrap();
+        // produce a compressed copy
+        let mut crinex = rinex.clone();
+        crinex.rnx2crx();
+        assert_eq!(crinex.header.is_crinex(), true);
+        let copy_path = std::env::temp_dir()
+            .join("ACOR00ESP_R_20213550000_01D_30S_MO.crx");
+        let copy_path = copy_path.to_str().unwrap();
+        assert_eq!(crinex.to_file(copy_path).is_ok(), true);
+        // parse it back: must match original record
+        let parsed = Rinex::from_file(copy_path);
+        assert_eq!(parsed.is_ok(), true);
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.header.is_crinex(), true);
+        assert_eq!(parsed.epochs(), rinex.epochs());
+        let record = rinex.record.as_obs().unwrap();
+        let parsed = parsed.record.as_obs().unwrap();
+        for (epoch, (_, vehicules)) in record.iter() {
+            let (_, p_vehicules) = parsed.get(epoch).unwrap();
+            for (sv, observations) in vehicules.iter() {
+                let p_observations = p_vehicules.get(sv).unwrap();
+                for (code, data) in observations.iter() {
+                    let p_data = p_observations.get(code).unwrap();
+                    assert_eq!(data.obs, p_data.obs);
+                }
+            }
+        }
+        let _ = std::fs::remove_file(copy_path);
+    }
+    #[test]
+    fn test_obs_builder_production() {
+        let e0 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 00").unwrap(),
+            epoch::EpochFlag::Ok);
+        let e1 = epoch::Epoch::new(
+            epoch::str2date("2022 06 08 10 00 30").unwrap(),
+            epoch::EpochFlag::Ok);
+        let g01 = sv::Sv::from_str("G01").unwrap();
+        let e05 = sv::Sv::from_str("E05").unwrap();
+        let c1c = observation::Observable::from_str("C1C").unwrap();
+        let l1c = observation::Observable::from_str("L1C").unwrap();
+        let rinex = builder::RinexBuilder::new(types::Type::ObservationData)
+            .with_version(version::Version::new(3, 4))
+            .with_general_infos("rust-rinex", "tests", "tests")
+            .with_station("TEST", "00000M000")
+            .with_position((3516213.4380, 781859.8595, 5246037.9660))
+            .with_sampling_interval(30.0)
+            .with_observation(e0, g01, c1c, observation::ObservationData::new(20243517.560, None, None))
+            .with_observation(e0, g01, l1c, observation::ObservationData::new(106380411.276, None, Some(observation::Ssi::DbHz36_41)))
+            .with_observation(e1, e05, c1c, observation::ObservationData::new(22957710.250, None, None))
+            .build()
+            .unwrap();
+        let copy_path = std::env::temp_dir()
+            .join("builder.rnx");
+        let copy_path = copy_path.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy_path).is_ok(), true);
+        // parse it back
+        let parsed = Rinex::from_file(copy_path)
+            .unwrap();
+        assert_eq!(parsed.header.station, "TEST");
+        assert_eq!(parsed.header.constellation, Some(constellation::Constellation::Mixed));
+        assert_eq!(parsed.header.coords.is_some(), true);
+        assert_eq!(parsed.epochs(), vec![e0, e1]);
+        let record = parsed.record.as_obs().unwrap();
+        assert_eq!(record[&e0].1[&g01][&l1c].obs, 106380411.276);
+        assert_eq!(record[&e1].1[&e05][&c1c].obs, 22957710.250);
+        let _ = std::fs::remove_file(copy_path);
+    }
+    #[test]
+    fn test_crx_v3_compression_statistics() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let mut rinex = Rinex::from_file(&fp)
+            .unwrap();
+        rinex.rnx2crx();
+        let content = std::fs::read_to_string(&fp)
+            .unwrap();
+        let (_, record) = content.split_at(
+            content.find("END OF HEADER").unwrap());
+        let (_, record) = record.split_at(record.find("\n").unwrap()+1);
+        // compress
+        let mut compressor = hatanaka::Compressor::new(8);
+        let compressed = compressor.compress(&rinex.header, record)
+            .unwrap();
+        let stats = compressor.statistics();
+        assert_eq!(stats.max_order, 8);
+        assert_eq!(stats.order, hatanaka::DEFAULT_ORDER);
+        assert_eq!(stats.epochs.len(), rinex.epochs().len());
+        assert_eq!(stats.crinex_bytes(), compressed.len());
+        assert!(stats.ratio() < 1.0);
+        // first epoch initializes all kernels
+        let (_, first) = stats.epochs.iter().next().unwrap();
+        assert!(first.resets > 0);
+        assert!(first.ratio() > stats.ratio());
+        // decompress: same resets must be identified
+        let mut decompressor = hatanaka::Decompressor::new(8);
+        decompressor.decompress(&rinex.header, &compressed)
+            .unwrap();
+        let d_stats = decompressor.statistics();
+        assert_eq!(d_stats.order, stats.order);
+        assert_eq!(d_stats.epochs.len(), stats.epochs.len());
+        assert_eq!(d_stats.crinex_bytes(), compressed.len());
+        for (epoch, e_stats) in stats.epochs.iter() {
+            let d = d_stats.epochs.get(epoch).unwrap();
+            assert_eq!(d.resets, e_stats.resets);
+        }
+    }
+    #[test]
+    fn test_crx_v3_file_statistics() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let opts = rinex::parser::ParserOptions::default();
+        let (mut rinex, stats) = Rinex::from_file_with_statistics(&fp, &opts)
+            .unwrap();
+        assert_eq!(stats, None); // not compressed
+        rinex.rnx2crx();
+        let copy_path = std::env::temp_dir().join("acor00esp_statistics.crx");
+        let copy_path = copy_path.to_str().unwrap();
+        // statistics gathered while producing the CRINEX
+        let produced = rinex.to_file_with_statistics(copy_path)
+            .unwrap()
+            .unwrap();
+        assert_eq!(produced.epochs.len(), rinex.epochs().len());
+        assert!(produced.ratio() < 1.0);
+        // statistics gathered while parsing it back
+        let (parsed, recovered) = Rinex::from_file_with_statistics(copy_path, &opts)
+            .unwrap();
+        let recovered = recovered.unwrap();
+        assert_eq!(parsed.header.is_crinex(), true);
+        assert_eq!(recovered.order, produced.order);
+        assert_eq!(recovered.resets(), produced.resets());
+        assert_eq!(recovered.epochs.len(), produced.epochs.len());
+        let _ = std::fs::remove_file(copy_path);
+    }
+    #[test]
+    fn test_crx_v3_decompression_fidelity() {
+        // official CRX2RNX output is reproduced byte per byte
+        let crx = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/CRNX/V3/ACOR00ESP_R_20213550000_01D_30S_MO.crx";
+        let rnx = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&crx)
+            .unwrap();
+        let crx_content = std::fs::read_to_string(&crx)
+            .unwrap();
+        let (_, crx_record) = crx_content.split_at(
+            crx_content.find("END OF HEADER").unwrap());
+        let (_, crx_record) = crx_record.split_at(crx_record.find("\n").unwrap()+1);
+        let rnx_content = std::fs::read_to_string(&rnx)
+            .unwrap();
+        let (_, rnx_record) = rnx_content.split_at(
+            rnx_content.find("END OF HEADER").unwrap());
+        let (_, rnx_record) = rnx_record.split_at(rnx_record.find("\n").unwrap()+1);
+        let mut decompressor = hatanaka::Decompressor::new(8);
+        let recovered = decompressor.decompress(&rinex.header, crx_record)
+            .unwrap();
+        assert_eq!(recovered, rnx_record);
+    }
+    #[test]
+    fn test_crx_v3_events_and_clock_offsets() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let mut rinex = Rinex::from_file(&fp)
+            .unwrap();
+        rinex.rnx2crx();
+        let content = std::fs::read_to_string(&fp)
+            .unwrap();
+        let (_, record) = content.split_at(
+            content.find("END OF HEADER").unwrap());
+        let (_, record) = record.split_at(record.find("\n").unwrap()+1);
+        // first two epochs, with receiver clock offsets,
+        // and a new site occupation in between
+        let mut lines : Vec<String> = Vec::new();
+        let mut epochs = 0;
+        for line in record.lines() {
+            if line.starts_with('>') {
+                epochs += 1;
+                if epochs > 2 {
+                    break
+                }
+                if epochs == 2 {
+                    lines.push(String::from("> 2021 12 21 00 00 15.0000000  3  2"));
+                    lines.push(format!("{:<60}MARKER NAME", "ROVER"));
+                    lines.push(format!("{:<60}COMMENT", "NEW SITE OCCUPATION"));
+                }
+                lines.push(format!("{}      {:15.12}", line, -1.234567890123E-4 * epochs as f64));
+            } else {
+                lines.push(line.to_string());
+            }
+        }
+        let record = lines.join("\n") + "\n";
+        let mut compressor = hatanaka::Compressor::new(8);
+        let compressed = compressor.compress(&rinex.header, &record)
+            .unwrap();
+        assert_eq!(compressed.contains("> 2021 12 21 00 00 15.0000000  3  2\n"), true);
+        assert_eq!(compressed.contains("NEW SITE OCCUPATION"), true);
+        assert_eq!(compressor.statistics().epochs.len(), 2);
+        let mut decompressor = hatanaka::Decompressor::new(8);
+        let recovered = decompressor.decompress(&rinex.header, &compressed)
+            .unwrap();
+        assert_eq!(recovered, record);
+        assert_eq!(decompressor.statistics().epochs.len(), 2);
+    }
+    #[test]
+    #[cfg(feature = "with-gzip")]
+    fn test_gzip_production() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/MET/V2/abvi0010.15m";
+        let rinex = Rinex::from_file(&fp)
+            .unwrap();
+        let copy = std::env::temp_dir().join("abvi-copy.15m.gz");
+        let copy = copy.to_str().unwrap();
+        assert_eq!(rinex.to_file(copy).is_ok(), true);
+        let parsed = Rinex::from_file(copy);
+        let _ = std::fs::remove_file(copy);
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.header.version, rinex.header.version);
+        assert_eq!(parsed.record.as_meteo(), rinex.record.as_meteo());
+    }
+    #[test]
+    #[cfg(not(feature = "with-gzip"))]
+    fn test_gzip_production_not_supported() {
+        let fp = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/MET/V2/abvi0010.15m";
+        let rinex = Rinex::from_file(&fp)
+            .unwrap();
+        let copy = std::env::temp_dir().join("abvi-copy.15m.gz");
+        let copy = copy.to_str().unwrap();
+        let result = rinex.to_file(copy);
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+        // no file was created
+        assert_eq!(std::path::Path::new(copy).exists(), false);
+    }
 }
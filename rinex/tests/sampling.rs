@@ -48,6 +48,104 @@ mod sampling {
         assert_eq!(epochs.len(), 3); 
     }
     #[test]
+    fn test_retain_epochs() {
+        use chrono::Timelike;
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx";
+        let mut rinex = Rinex::from_file(&path).unwrap();
+        // 00:00:00, 00:15:00, 05:00:00, 09:45:00, 10:10:00, 15:40:00
+        rinex.retain_epochs_mut(|e| e.date.hour() >= 9);
+        assert_eq!(rinex.epochs().len(), 3);
+        rinex.retain_mut(|_, entry| {
+            let classes = entry.as_nav().unwrap();
+            classes.values()
+                .flatten()
+                .any(|frame| {
+                    let (_, sv, _, _, _, _) = frame.as_eph().unwrap();
+                    sv.constellation == constellation::Constellation::Galileo
+                })
+        });
+        let epochs = rinex.epochs();
+        assert_eq!(epochs.len(), 2);
+        assert_eq!(epochs[0].date.hour(), 10);
+        
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/MET/V2/abvi0010.15m";
+        let mut rinex = Rinex::from_file(&path).unwrap();
+        let len = rinex.epochs().len();
+        rinex.retain_mut(|_, entry| entry.as_meteo().is_some());
+        assert_eq!(rinex.epochs().len(), len);
+        rinex.retain_epochs_mut(|_| false);
+        assert_eq!(rinex.epochs().len(), 0);
+    }
+    #[test]
+    fn test_decimate_sub_second() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/DUTH0630.22O";
+        let mut rinex = Rinex::from_file(&path).unwrap();
+        // 4 Hz record, built from first epoch content
+        let record = rinex.record.as_obs().unwrap();
+        let (e0, data) = record.iter().next().unwrap();
+        let t0 = e0.date;
+        let resampled = (0..8)
+            .map(|k| {
+                let date = t0 + chrono::Duration::milliseconds(250 * k as i64);
+                (epoch::Epoch::new(date, e0.flag), data.clone())
+            })
+            .collect();
+        rinex.record = record::Record::ObsRecord(resampled);
+        rinex.decimate_by_interval_mut(std::time::Duration::from_millis(500));
+        let epochs = rinex.epochs();
+        assert_eq!(epochs.len(), 4);
+        assert_eq!(epochs[1].date, t0 + chrono::Duration::milliseconds(500));
+    }
+    #[test]
+    fn test_split_nav_by_duration() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx";
+        let rinex = Rinex::from_file(&path).unwrap();
+        ////////////////////////
+        // Epochs in this file:
+        // 00:00:00
+        // 00:15:00
+        // 05:00:00
+        // 09:45:00
+        // 10:10:00
+        // 15:40:00
+        ////////////////////////
+        let batches = rinex.split_by_duration(chrono::Duration::hours(1));
+        // [00:00, 01:00[, [05:00, 06:00[, [09:00, 10:00[, [10:00, 11:00[, [15:00, 16:00[
+        assert_eq!(batches.len(), 5);
+        assert_eq!(batches[0].epochs().len(), 2);
+        let total : usize = batches
+            .iter()
+            .map(|b| b.epochs().len())
+            .sum();
+        assert_eq!(total, rinex.epochs().len());
+        let batches = rinex.split_by_duration(chrono::Duration::days(1));
+        assert_eq!(batches.len(), 1);
+    }
+    #[test]
+    fn test_split_obs_by_duration() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() 
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&path).unwrap();
+        // this file spans 00:00:00 - 00:12:00
+        let batches = rinex.split_by_duration(chrono::Duration::minutes(5));
+        assert_eq!(batches.len(), 3);
+        for (index, batch) in batches.iter().enumerate() {
+            let epochs = batch.epochs();
+            if index < 2 {
+                assert_eq!(epochs.len(), 10);
+            } else {
+                assert_eq!(epochs.len(), 5);
+            }
+            let obs = batch.header.obs.as_ref().unwrap();
+            assert_eq!(obs.time_of_first_obs, Some(epochs[0]));
+            assert_eq!(obs.time_of_last_obs, Some(epochs[epochs.len()-1]));
+        }
+    }
+    #[test]
     fn test_decimate_nav_by_interval() {
         let path = env!("CARGO_MANIFEST_DIR").to_owned() 
             + "/../test_resources/NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx";
@@ -0,0 +1,110 @@
+#[cfg(test)]
+#[cfg(feature = "with-serde")]
+mod test {
+    use rinex::*;
+    use rinex::constellation::Constellation;
+    use std::str::FromStr;
+    /// Parses given resource, serializes it, deserializes it
+    /// and serializes it again: both representations must match
+    fn roundtrip (resource: &str) {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/"
+            + resource;
+        let rinex = Rinex::from_file(&path);
+        assert_eq!(rinex.is_ok(), true, "failed to parse \"{}\"", resource);
+        let rinex = rinex.unwrap();
+        let value = serde_json::to_value(&rinex);
+        assert_eq!(value.is_ok(), true, "failed to serialize \"{}\": {:?}", resource, value.err());
+        let value = value.unwrap();
+        let parsed = serde_json::from_value::<Rinex>(value.clone());
+        assert_eq!(parsed.is_ok(), true, "failed to deserialize \"{}\": {:?}", resource, parsed.err());
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.header.rinex_type, rinex.header.rinex_type);
+        assert_eq!(parsed.epochs(), rinex.epochs());
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+    }
+    #[test]
+    fn obs_roundtrip() {
+        roundtrip("OBS/V2/aopr0010.17o");
+        roundtrip("OBS/V3/DUTH0630.22O");
+        roundtrip("CRNX/V3/KMS300DNK_R_20221591000_01H_30S_MO.crx");
+    }
+    #[test]
+    fn nav_roundtrip() {
+        roundtrip("NAV/V2/amel0010.21g");
+        roundtrip("NAV/V3/AMEL00NLD_R_20210010000_01D_MN.rnx");
+        roundtrip("NAV/V4/FRMS00XXX_R_20221591000_01H_MN.rnx");
+    }
+    #[test]
+    fn meteo_roundtrip() {
+        roundtrip("MET/V2/abvi0010.15m");
+        roundtrip("MET/V4/example1.txt");
+    }
+    #[test]
+    fn clocks_roundtrip() {
+        roundtrip("CLK/V3/example1.txt");
+    }
+    #[test]
+    fn antex_roundtrip() {
+        roundtrip("ATX/V1/TROSAR25.R4__LEIT_2020_09_23.atx");
+    }
+    #[test]
+    fn qc_summaries() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/DUTH0630.22O";
+        let rinex = Rinex::from_file(&path).unwrap();
+        let summaries = qc::aggregate(&rinex, qc::Period::Hourly);
+        assert_eq!(summaries.len() > 0, true);
+        let value = serde_json::to_value(&summaries).unwrap();
+        assert_eq!(value[0]["epochs"], summaries[0].epochs);
+        let parsed = serde_json::from_value::<Vec<qc::Summary>>(value).unwrap();
+        assert_eq!(parsed.len(), summaries.len());
+        assert_eq!(parsed[0].start, summaries[0].start);
+    }
+    #[test]
+    fn reports() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/OBS/V3/ACOR00ESP_R_20213550000_01D_30S_MO.rnx";
+        let rinex = Rinex::from_file(&path).unwrap();
+        let qc = report::QcReport::new(&rinex, qc::Period::Hourly);
+        let value = serde_json::to_value(&qc).unwrap();
+        assert_eq!(value["version"], report::VERSION);
+        assert_eq!(value["kind"], "Qc");
+        assert_eq!(value["summaries"].as_array().unwrap().len(), 1);
+        assert_eq!(value["metrics"]["completeness"].is_number(), true);
+        let validation = report::ValidationReport::new(&rinex, validation::Profile::V2_11);
+        let value = serde_json::to_value(&validation).unwrap();
+        assert_eq!(value["kind"], "Validation");
+        assert_eq!(value["compliant"], false);
+        assert_eq!(value["violations"][0]["rule"], "VER-001");
+        let gaps = report::GapReport::new(&rinex);
+        let value = serde_json::to_value(&gaps).unwrap();
+        assert_eq!(value["kind"], "Gaps");
+        assert_eq!(value["interval"], 30.0);
+        assert_eq!(value["gaps"].as_array().unwrap().len(), 0);
+        let diff = report::DiffReport::new(&rinex, &rinex, 0.0);
+        let value = serde_json::to_value(&diff).unwrap();
+        assert_eq!(value["kind"], "Diff");
+        assert_eq!(value["differences"].as_array().unwrap().len(), 0);
+    }
+    #[test]
+    fn epoch_sv_observable() {
+        let epoch = epoch::Epoch {
+            date: chrono::NaiveDate::from_ymd(2022, 6, 8)
+                .and_hms_milli(10, 0, 30, 500),
+            flag: epoch::EpochFlag::PowerFailure,
+        };
+        let s = serde_json::to_string(&epoch).unwrap();
+        assert_eq!(s, "\"2022-06-08 10:00:30.500 1\"");
+        assert_eq!(serde_json::from_str::<epoch::Epoch>(&s).unwrap(), epoch);
+        let sv = sv::Sv::new(Constellation::Galileo, 5);
+        let s = serde_json::to_string(&sv).unwrap();
+        assert_eq!(s, "\"E05\"");
+        assert_eq!(serde_json::from_str::<sv::Sv>(&s).unwrap(), sv);
+        assert_eq!(serde_json::from_str::<sv::Sv>("\"\"").is_err(), true);
+        let obs = observation::Observable::from_str("L1C").unwrap();
+        let s = serde_json::to_string(&obs).unwrap();
+        assert_eq!(s, "\"L1C\"");
+        assert_eq!(serde_json::from_str::<observation::Observable>(&s).unwrap(), obs);
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod test {
+    use rinex::sv::Sv;
+    use rinex::sp3::{Sp3, Version, DataType};
+    use rinex::constellation::Constellation;
+    #[test]
+    fn sp3d_parsing() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/SP3/example.sp3";
+        let sp3 = Sp3::from_file(&test_resource);
+        assert_eq!(sp3.is_ok(), true);
+        let sp3 = sp3.unwrap();
+        assert_eq!(sp3.header.version, Version::D);
+        assert_eq!(sp3.header.data_type, DataType::Position);
+        assert_eq!(sp3.header.nb_epochs, 3);
+        assert_eq!(sp3.header.coord_system, "IGS14");
+        assert_eq!(sp3.header.orbit_type, "FIT");
+        assert_eq!(sp3.header.agency, "IGS");
+        assert_eq!(sp3.header.week, 2190);
+        assert_eq!(sp3.header.interval, 900.0);
+        assert_eq!(sp3.header.constellation, Constellation::Mixed);
+        assert_eq!(sp3.header.time_system, "GPS");
+        assert_eq!(sp3.header.satellites, vec![
+            Sv::new(Constellation::GPS, 1),
+            Sv::new(Constellation::GPS, 2),
+            Sv::new(Constellation::Galileo, 1),
+            Sv::new(Constellation::Glonass, 5),
+        ]);
+        assert_eq!(sp3.header.accuracy, vec![2, 2, 3, 4]);
+        assert_eq!(sp3.header.comments.len(), 2);
+        assert_eq!(sp3.epochs().len(), 3);
+
+        let g01 = Sv::new(Constellation::GPS, 1);
+        let positions = sp3.sv_position(g01);
+        assert_eq!(positions.len(), 3);
+        let (_, first) = positions.iter().next().unwrap();
+        assert_eq!(*first, (-22335.782004, -14656.280389, -1218.238499));
+        let clocks = sp3.sv_clock(g01);
+        assert_eq!(clocks.len(), 3);
+        // bad clock values are not exposed
+        let r05 = Sv::new(Constellation::Glonass, 5);
+        assert_eq!(sp3.sv_position(r05).len(), 3);
+        assert_eq!(sp3.sv_clock(r05).len(), 0);
+    }
+    #[test]
+    fn sp3d_production() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/SP3/example.sp3";
+        let sp3 = Sp3::from_file(&test_resource)
+            .unwrap();
+        let copy = std::env::temp_dir()
+            .join("example.sp3")
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(sp3.to_file(&copy).is_ok(), true);
+        let parsed = Sp3::from_file(&copy);
+        assert_eq!(parsed.is_ok(), true);
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.header.satellites, sp3.header.satellites);
+        assert_eq!(parsed.header.accuracy, sp3.header.accuracy);
+        assert_eq!(parsed.record, sp3.record);
+        let _ = std::fs::remove_file(&copy);
+    }
+    #[test]
+    fn sp3d_from_bytes() {
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/SP3/example.sp3";
+        let content = std::fs::read(&test_resource)
+            .unwrap();
+        let sp3 = Sp3::from_bytes(&content);
+        assert_eq!(sp3.is_ok(), true);
+        let sp3 = sp3.unwrap();
+        let expected = Sp3::from_file(&test_resource)
+            .unwrap();
+        assert_eq!(sp3.header.nb_epochs, expected.header.nb_epochs);
+        assert_eq!(sp3.record, expected.record);
+    }
+    #[test]
+    fn sp3d_truncated_lines() {
+        use rinex::sp3::Error;
+        let test_resource =
+            env!("CARGO_MANIFEST_DIR").to_owned()
+            + "/../test_resources/SP3/example.sp3";
+        let content = std::fs::read_to_string(&test_resource)
+            .unwrap();
+        // (line, truncated version)
+        for (line, truncated) in [
+            ("+    4   G01G02E01R05", "+ "),
+            ("%c M  cc GPS ccc", "%c M"),
+            ("%f  1.2500000  1.025000000", "%f  1.25"),
+            ("PG02  15036.483241", "PG0"),
+            ("PG02  15036.483241", "PG02  15036.483241 -2133"),
+        ] {
+            let mut replaced = false;
+            let content: Vec<&str> = content.lines()
+                .map(|l| {
+                    if !replaced && l.starts_with(line) {
+                        replaced = true;
+                        truncated
+                    } else {
+                        l
+                    }
+                })
+                .collect();
+            let content = content.join("\n");
+            let sp3 = Sp3::from_bytes(content.as_bytes());
+            assert!(matches!(sp3, Err(Error::TruncatedLine(_))), "\"{}\"", truncated);
+        }
+    }
+}